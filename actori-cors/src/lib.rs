@@ -39,6 +39,8 @@
 //! endpoint.
 //!
 //! Cors middleware automatically handle *OPTIONS* preflight request.
+//! If allowed methods are not configured explicitly, preflight response
+//! lists methods of the matched resource.
 use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::iter::FromIterator;
@@ -189,6 +191,7 @@ impl Cors {
                 expose_hdrs: None,
                 max_age: None,
                 preflight: true,
+                route_methods: false,
                 send_wildcard: false,
                 supports_credentials: false,
                 vary_header: true,
@@ -220,6 +223,7 @@ impl Cors {
             expose_hdrs: None,
             max_age: None,
             preflight: true,
+            route_methods: true,
             send_wildcard: false,
             supports_credentials: false,
             vary_header: true,
@@ -271,7 +275,11 @@ impl Cors {
     /// This is the `list of methods` in the
     /// [Resource Processing Model](https://www.w3.org/TR/cors/#resource-processing-model).
     ///
-    /// Defaults to `[GET, HEAD, POST, OPTIONS, PUT, PATCH, DELETE]`
+    /// If methods are not set, preflight requests are checked against
+    /// methods of the resource that matches request path. Resource methods
+    /// are known if all resource's routes have method guards, e.g. routes
+    /// created with `web::get()` or `web::post()`, otherwise
+    /// `[GET, HEAD, POST, OPTIONS, PUT, PATCH, DELETE]` is used.
     pub fn allowed_methods<U, M>(mut self, methods: U) -> Cors
     where
         U: IntoIterator<Item = M>,
@@ -469,6 +477,7 @@ impl Cors {
 
     /// Construct cors middleware
     pub fn finish(self) -> CorsFactory {
+        let route_methods = !self.methods;
         let mut slf = if !self.methods {
             self.allowed_methods(vec![
                 Method::GET,
@@ -488,6 +497,7 @@ impl Cors {
         }
 
         let mut cors = slf.cors.take().expect("cannot reuse CorsBuilder");
+        cors.route_methods = route_methods;
 
         if cors.supports_credentials && cors.send_wildcard && cors.origins.is_all() {
             panic!("Credentials are allowed, but the Origin is set to \"*\"");
@@ -572,6 +582,7 @@ struct Inner {
     expose_hdrs: Option<String>,
     max_age: Option<usize>,
     preflight: bool,
+    route_methods: bool,
     send_wildcard: bool,
     supports_credentials: bool,
     vary_header: bool,
@@ -627,11 +638,17 @@ impl Inner {
     }
 
     fn validate_allowed_method(&self, req: &RequestHead) -> Result<(), CorsError> {
+        Inner::validate_method(req, &self.methods)
+    }
+
+    fn validate_method(
+        req: &RequestHead,
+        methods: &HashSet<Method>,
+    ) -> Result<(), CorsError> {
         if let Some(hdr) = req.headers().get(&header::ACCESS_CONTROL_REQUEST_METHOD) {
             if let Ok(meth) = hdr.to_str() {
                 if let Ok(method) = Method::try_from(meth) {
-                    return self
-                        .methods
+                    return methods
                         .get(&method)
                         .and_then(|_| Some(()))
                         .ok_or_else(|| CorsError::MethodNotAllowed);
@@ -643,6 +660,17 @@ impl Inner {
         }
     }
 
+    /// Methods of the resource that matches request path
+    fn resource_methods(&self, req: &ServiceRequest) -> Option<HashSet<Method>> {
+        if self.route_methods {
+            req.resource_map()
+                .allowed_methods(req.path())
+                .map(|methods| methods.iter().cloned().collect())
+        } else {
+            None
+        }
+    }
+
     fn validate_allowed_headers(&self, req: &RequestHead) -> Result<(), CorsError> {
         match self.headers {
             AllOrSome::All => Ok(()),
@@ -696,10 +724,13 @@ where
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
         if self.inner.preflight && Method::OPTIONS == *req.method() {
+            let resource_methods = self.inner.resource_methods(&req);
+            let methods = resource_methods.as_ref().unwrap_or(&self.inner.methods);
+
             if let Err(e) = self
                 .inner
                 .validate_origin(req.head())
-                .and_then(|_| self.inner.validate_allowed_method(req.head()))
+                .and_then(|_| Inner::validate_method(req.head(), methods))
                 .and_then(|_| self.inner.validate_allowed_headers(req.head()))
             {
                 return Either::Left(ok(req.error_response(e)));
@@ -726,32 +757,37 @@ where
 
             let res = HttpResponse::Ok()
                 .if_some(self.inner.max_age.as_ref(), |max_age, resp| {
-                    let _ = resp.header(
+                    let _ = resp.insert_header((
                         header::ACCESS_CONTROL_MAX_AGE,
                         format!("{}", max_age).as_str(),
-                    );
+                    ));
                 })
                 .if_some(headers, |headers, resp| {
-                    let _ = resp.header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers);
+                    let _ = resp
+                        .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, headers));
                 })
                 .if_some(
                     self.inner.access_control_allow_origin(req.head()),
                     |origin, resp| {
-                        let _ = resp.header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+                        let _ = resp.insert_header((
+                            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+                            origin,
+                        ));
                     },
                 )
                 .if_true(self.inner.supports_credentials, |resp| {
-                    resp.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+                    resp.insert_header((
+                        header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                        "true",
+                    ));
                 })
-                .header(
+                .insert_header((
                     header::ACCESS_CONTROL_ALLOW_METHODS,
-                    &self
-                        .inner
-                        .methods
+                    &methods
                         .iter()
                         .fold(String::new(), |s, v| s + "," + v.as_str())
                         .as_str()[1..],
-                )
+                ))
                 .finish()
                 .into_body();
 
@@ -950,6 +986,55 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actori_rt::test]
+    async fn test_preflight_resource_methods() {
+        use actori_web::{web, App, HttpResponse};
+
+        let mut srv = test::init_service(
+            App::new().wrap(Cors::new().finish()).service(
+                web::resource("/test")
+                    .route(web::get().to(|| HttpResponse::Ok()))
+                    .route(web::post().to(|| HttpResponse::Ok())),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .header("Origin", "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/test")
+            .header("Origin", "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let methods = resp
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("GET"));
+        assert!(methods.contains("POST"));
+        assert!(!methods.contains("DELETE"));
+
+        // unknown resource, default methods
+        let req = TestRequest::with_uri("/unknown")
+            .header("Origin", "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = test::call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     // #[actori_rt::test]
     // #[should_panic(expected = "MissingOrigin")]
     // async fn test_validate_missing_origin() {
@@ -1085,7 +1170,9 @@ mod tests {
             .finish()
             .new_transform(fn_service(|req: ServiceRequest| {
                 ok(req.into_response(
-                    HttpResponse::Ok().header(header::VARY, "Accept").finish(),
+                    HttpResponse::Ok()
+                        .insert_header((header::VARY, "Accept"))
+                        .finish(),
                 ))
             }))
             .await
@@ -0,0 +1,279 @@
+use std::fmt;
+
+use actori_http::body::{Body, BodyStream};
+use actori_web::http::header::{
+    self, Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+};
+use actori_web::http::{ContentEncoding, StatusCode};
+use actori_web::{dev::BodyEncoding, Error, HttpRequest, HttpResponse, Responder};
+use bytes::Bytes;
+use futures::future::{ready, Ready};
+use futures::Stream;
+use mime_guess::from_path;
+
+use crate::range::HttpRange;
+
+enum Data {
+    Bytes(Bytes),
+    Body(Body),
+}
+
+/// In-memory or streamed content with an associated file name.
+///
+/// Responder sets `Content-Disposition` header with the file name, non-ascii
+/// names are sent with `filename*` parameter and ascii fallback.
+/// `Content-Type` is guessed from the file name extension. Content is sent
+/// as an attachment by default, use `inline()` to display it in browser.
+///
+/// In-memory content supports single byte range requests.
+///
+/// ```rust
+/// use actori_files::NamedBytes;
+/// use actori_web::{web, App};
+///
+/// async fn report() -> NamedBytes {
+///     NamedBytes::new("report.csv", "id,name\n1,test\n")
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/report", web::get().to(report));
+/// }
+/// ```
+pub struct NamedBytes {
+    data: Data,
+    status_code: StatusCode,
+    content_type: mime::Mime,
+    content_disposition: ContentDisposition,
+}
+
+impl NamedBytes {
+    /// Create responder for in-memory content
+    pub fn new<T, B>(filename: T, data: B) -> NamedBytes
+    where
+        T: AsRef<str>,
+        B: Into<Bytes>,
+    {
+        NamedBytes::with_data(filename.as_ref(), Data::Bytes(data.into()))
+    }
+
+    /// Create responder for streamed content
+    pub fn from_stream<T, S, E>(filename: T, stream: S) -> NamedBytes
+    where
+        T: AsRef<str>,
+        S: Stream<Item = Result<Bytes, E>> + 'static,
+        E: Into<Error> + 'static,
+    {
+        let body = Body::from_message(BodyStream::new(stream));
+        NamedBytes::with_data(filename.as_ref(), Data::Body(body))
+    }
+
+    fn with_data(filename: &str, data: Data) -> NamedBytes {
+        let content_type = from_path(filename).first_or_octet_stream();
+
+        let mut parameters = vec![DispositionParam::Filename(ascii_filename(filename))];
+        if !filename.is_ascii() {
+            parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext(String::from("UTF-8")),
+                language_tag: None,
+                value: filename.as_bytes().to_vec(),
+            }))
+        }
+
+        NamedBytes {
+            data,
+            content_type,
+            status_code: StatusCode::OK,
+            content_disposition: ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters,
+            },
+        }
+    }
+
+    /// Display content in browser, `Content-Disposition` type is `inline`
+    pub fn inline(mut self) -> Self {
+        self.content_disposition.disposition = DispositionType::Inline;
+        self
+    }
+
+    /// Save content as a file, `Content-Disposition` type is `attachment`.
+    ///
+    /// This is default.
+    pub fn attachment(mut self) -> Self {
+        self.content_disposition.disposition = DispositionType::Attachment;
+        self
+    }
+
+    /// Set response **Status Code**
+    pub fn set_status_code(mut self, status: StatusCode) -> Self {
+        self.status_code = status;
+        self
+    }
+
+    /// Set the MIME Content-Type for serving this content. By default
+    /// the Content-Type is inferred from the file name extension.
+    pub fn set_content_type(mut self, mime_type: mime::Mime) -> Self {
+        self.content_type = mime_type;
+        self
+    }
+
+    pub fn into_response(self, req: &HttpRequest) -> HttpResponse {
+        let mut resp = HttpResponse::build(self.status_code);
+        resp.set(header::ContentType(self.content_type))
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                self.content_disposition.to_string(),
+            ));
+
+        let bytes = match self.data {
+            Data::Body(body) => return resp.body(body),
+            Data::Bytes(bytes) => bytes,
+        };
+        if self.status_code != StatusCode::OK {
+            return resp.body(bytes);
+        }
+
+        resp.insert_header((header::ACCEPT_RANGES, "bytes"));
+
+        // check for range header
+        let size = bytes.len() as u64;
+        if let Some(ranges) = req.headers().get(&header::RANGE) {
+            let ranges = match ranges.to_str() {
+                Ok(ranges) => HttpRange::parse(ranges, size),
+                Err(_) => return resp.status(StatusCode::BAD_REQUEST).finish(),
+            };
+            match ranges {
+                Ok(ref ranges) if !ranges.is_empty() => {
+                    let start = ranges[0].start;
+                    let end = start + ranges[0].length;
+                    resp.encoding(ContentEncoding::Identity);
+                    resp.insert_header((
+                        header::CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", start, end - 1, size),
+                    ));
+                    return resp
+                        .status(StatusCode::PARTIAL_CONTENT)
+                        .body(bytes.slice(start as usize..end as usize));
+                }
+                Ok(_) => (),
+                Err(_) => {
+                    resp.insert_header((
+                        header::CONTENT_RANGE,
+                        format!("bytes */{}", size),
+                    ));
+                    return resp.status(StatusCode::RANGE_NOT_SATISFIABLE).finish();
+                }
+            }
+        }
+
+        resp.body(bytes)
+    }
+}
+
+impl fmt::Debug for NamedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = match self.data {
+            Data::Bytes(ref bytes) => Some(bytes.len()),
+            Data::Body(_) => None,
+        };
+        f.debug_struct("NamedBytes")
+            .field("size", &size)
+            .field("status_code", &self.status_code)
+            .field("content_type", &self.content_type)
+            .field("content_disposition", &self.content_disposition)
+            .finish()
+    }
+}
+
+impl Responder for NamedBytes {
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ready(Ok(self.into_response(req)))
+    }
+}
+
+/// File name for `filename` parameter, non-ascii characters are replaced
+fn ascii_filename(filename: &str) -> String {
+    filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actori_web::test::{self, TestRequest};
+
+    #[actori_rt::test]
+    async fn test_named_bytes() {
+        let req = TestRequest::default().to_http_request();
+        let resp = NamedBytes::new("report.csv", "id,name\n").into_response(&req);
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/csv"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"report.csv\""
+        );
+
+        let resp = NamedBytes::new("отчёт.txt", "text")
+            .inline()
+            .into_response(&req);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"_____.txt\"; \
+             filename*=UTF-8''%D0%BE%D1%82%D1%87%D1%91%D1%82.txt"
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_named_bytes_ranges() {
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=2-5")
+            .to_http_request();
+        let resp = NamedBytes::new("data.bin", "0123456789").into_response(&req);
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 2-5/10"
+        );
+        let body =
+            test::read_body(actori_web::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(body, Bytes::from_static(b"2345"));
+
+        let req = TestRequest::default()
+            .header(header::RANGE, "bytes=20-")
+            .to_http_request();
+        let resp = NamedBytes::new("data.bin", "0123456789").into_response(&req);
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes */10"
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_named_bytes_stream() {
+        let req = TestRequest::default().to_http_request();
+        let stream = futures::stream::once(futures::future::ok::<_, Error>(
+            Bytes::from_static(b"data"),
+        ));
+        let resp = NamedBytes::from_stream("data.json", stream).into_response(&req);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert!(resp.headers().get(header::ACCEPT_RANGES).is_none());
+    }
+}
@@ -31,11 +31,13 @@ use mime_guess::from_ext;
 use percent_encoding::{utf8_percent_encode, CONTROLS};
 use v_htmlescape::escape as escape_html_entity;
 
+mod download;
 mod error;
 mod named;
 mod range;
 
 use self::error::{FilesError, UriSegmentError};
+pub use crate::download::NamedBytes;
 pub use crate::named::NamedFile;
 pub use crate::range::HttpRange;
 
@@ -511,7 +513,7 @@ impl Service for FilesService {
         if !is_method_valid {
             return Either::Left(ok(req.into_response(
                 actori_web::HttpResponse::MethodNotAllowed()
-                    .header(header::CONTENT_TYPE, "text/plain")
+                    .insert_header((header::CONTENT_TYPE, "text/plain"))
                     .body("Request did not meet this resource's requirements."),
             )));
         }
@@ -533,7 +535,7 @@ impl Service for FilesService {
                     let redirect_to = format!("{}/", req.path());
                     return Either::Left(ok(req.into_response(
                         HttpResponse::Found()
-                            .header(header::LOCATION, redirect_to)
+                            .insert_header((header::LOCATION, redirect_to))
                             .body("")
                             .into_body(),
                     )));
@@ -262,10 +262,10 @@ impl NamedFile {
             let mut resp = HttpResponse::build(self.status_code);
             resp.set(header::ContentType(self.content_type.clone()))
                 .if_true(self.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
-                    res.header(
+                    res.insert_header((
                         header::CONTENT_DISPOSITION,
                         self.content_disposition.to_string(),
-                    );
+                    ));
                 });
             if let Some(current_encoding) = self.encoding {
                 resp.encoding(current_encoding);
@@ -328,10 +328,10 @@ impl NamedFile {
         let mut resp = HttpResponse::build(self.status_code);
         resp.set(header::ContentType(self.content_type.clone()))
             .if_true(self.flags.contains(Flags::CONTENT_DISPOSITION), |res| {
-                res.header(
+                res.insert_header((
                     header::CONTENT_DISPOSITION,
                     self.content_disposition.to_string(),
-                );
+                ));
             });
         // default compressing
         if let Some(current_encoding) = self.encoding {
@@ -345,7 +345,7 @@ impl NamedFile {
             resp.set(header::ETag(etag));
         });
 
-        resp.header(header::ACCEPT_RANGES, "bytes");
+        resp.insert_header((header::ACCEPT_RANGES, "bytes"));
 
         let mut length = self.md.len();
         let mut offset = 0;
@@ -357,7 +357,7 @@ impl NamedFile {
                     length = rangesvec[0].length;
                     offset = rangesvec[0].start;
                     resp.encoding(ContentEncoding::Identity);
-                    resp.header(
+                    resp.insert_header((
                         header::CONTENT_RANGE,
                         format!(
                             "bytes {}-{}/{}",
@@ -365,9 +365,12 @@ impl NamedFile {
                             offset + length - 1,
                             self.md.len()
                         ),
-                    );
+                    ));
                 } else {
-                    resp.header(header::CONTENT_RANGE, format!("bytes */{}", length));
+                    resp.insert_header((
+                        header::CONTENT_RANGE,
+                        format!("bytes */{}", length),
+                    ));
                     return Ok(resp.status(StatusCode::RANGE_NOT_SATISFIABLE).finish());
                 };
             } else {
@@ -0,0 +1,78 @@
+//! HTTP/1 codec benchmarks
+//!
+//! Run with `cargo bench -p actori-http --features bench`
+use actori_http::body::BodySize;
+use actori_http::h1::bench;
+use actori_http::http::header::{HeaderValue, CONTENT_TYPE, SERVER};
+use actori_http::{Response, ServiceConfig};
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const REQ_SHORT: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+const REQ_BROWSER: &[u8] = b"GET /plaintext HTTP/1.1\r\n\
+    Host: localhost:8080\r\n\
+    User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:72.0) Gecko/20100101 Firefox/72.0\r\n\
+    Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+    Accept-Language: en-US,en;q=0.5\r\n\
+    Accept-Encoding: gzip, deflate, br\r\n\
+    Cookie: session=0123456789abcdef; theme=dark\r\n\
+    Connection: keep-alive\r\n\
+    Upgrade-Insecure-Requests: 1\r\n\r\n";
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("h1_decode");
+
+    for (name, req) in &[("short", REQ_SHORT), ("browser", REQ_BROWSER)] {
+        group.throughput(Throughput::Bytes(req.len() as u64));
+        group.bench_function(*name, |b| {
+            let mut buf = BytesMut::with_capacity(req.len());
+            b.iter(|| {
+                buf.extend_from_slice(req);
+                bench::decode_request(&mut buf).unwrap().unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let config = ServiceConfig::default();
+    let mut group = c.benchmark_group("h1_encode");
+
+    group.bench_function("plaintext", |b| {
+        let mut buf = BytesMut::with_capacity(1024);
+        b.iter(|| {
+            let mut res = Response::Ok().finish().drop_body();
+            res.headers_mut()
+                .insert(SERVER, HeaderValue::from_static("actori"));
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+            bench::encode_response(&mut buf, &mut res, BodySize::Sized(13), &config)
+                .unwrap();
+            buf.clear();
+        })
+    });
+
+    group.throughput(Throughput::Bytes(4096));
+    group.bench_function("chunk", |b| {
+        let chunk = [b'a'; 4096];
+        let mut buf = BytesMut::with_capacity(8192);
+        b.iter(|| {
+            bench::encode_chunk(&chunk, &mut buf).unwrap();
+            buf.clear();
+        })
+    });
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    // date header service requires running system
+    actori_rt::System::new("h1").block_on(async move {
+        bench_decode(c);
+        bench_encode(c);
+    })
+}
+
+criterion_group!(h1, benches);
+criterion_main!(h1);
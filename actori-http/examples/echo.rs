@@ -27,10 +27,10 @@ async fn main() -> io::Result<()> {
                         info!("request body: {:?}", body);
                         Ok::<_, Error>(
                             Response::Ok()
-                                .header(
+                                .insert_header((
                                     "x-head",
                                     HeaderValue::from_static("dummy value!"),
-                                )
+                                ))
                                 .body(body),
                         )
                     }
@@ -4,10 +4,15 @@ use std::task::{Context, Poll};
 use std::{fmt, mem};
 
 use bytes::{Bytes, BytesMut};
+use derive_more::Display;
+use futures_channel::mpsc;
 use futures_core::Stream;
+use futures_util::future::poll_fn;
 use pin_project::{pin_project, project};
 
 use crate::error::Error;
+use crate::header::HeaderMap;
+use crate::payload::Payload;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 /// Body size hint
@@ -36,6 +41,13 @@ pub trait MessageBody {
     fn size(&self) -> BodySize;
 
     fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>>;
+
+    /// Trailer fields of the body, called after the end of the body.
+    ///
+    /// Trailers are sent with chunked transfer encoding only.
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        None
+    }
 }
 
 impl MessageBody for () {
@@ -56,6 +68,10 @@ impl<T: MessageBody> MessageBody for Box<T> {
     fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
         self.as_mut().poll_next(cx)
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.as_mut().take_trailers()
+    }
 }
 
 #[pin_project]
@@ -103,6 +119,13 @@ impl<B: MessageBody> MessageBody for ResponseBody<B> {
             ResponseBody::Other(ref mut body) => body.poll_next(cx),
         }
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            ResponseBody::Body(ref mut body) => body.take_trailers(),
+            ResponseBody::Other(ref mut body) => body.take_trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody> Stream for ResponseBody<B> {
@@ -170,6 +193,13 @@ impl MessageBody for Body {
             Body::Message(ref mut body) => body.poll_next(cx),
         }
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Body::Message(ref mut body) => body.take_trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Body {
@@ -260,6 +290,12 @@ where
     }
 }
 
+impl From<ChannelBody> for Body {
+    fn from(s: ChannelBody) -> Body {
+        Body::from_message(s)
+    }
+}
+
 impl<S, E> From<BodyStream<S, E>> for Body
 where
     S: Stream<Item = Result<Bytes, E>> + 'static,
@@ -432,10 +468,288 @@ where
     }
 }
 
+/// Incoming payload forwarded as streaming body.
+///
+/// Chunks are forwarded as they are received without buffering, chunk
+/// boundaries of h1 chunked payload are kept as long as the chunk is
+/// received at once. Trailers of the payload are forwarded as trailers of
+/// the body. It is useful for proxies.
+pub struct PayloadBody {
+    payload: Payload,
+}
+
+impl PayloadBody {
+    pub fn new(payload: Payload) -> Self {
+        PayloadBody { payload }
+    }
+}
+
+impl MessageBody for PayloadBody {
+    fn size(&self) -> BodySize {
+        match self.payload {
+            Payload::None => BodySize::Empty,
+            _ => BodySize::Stream,
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        Pin::new(&mut self.payload)
+            .poll_next(cx)
+            .map(|res| res.map(|res| res.map_err(Error::from)))
+    }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.payload.take_trailers()
+    }
+}
+
+/// Create bounded body channel.
+///
+/// `BodySender` could buffer up to `buffer` chunks, `BodySender::send()`
+/// waits until response body is consumed by the connection.
+pub fn channel(buffer: usize) -> (BodySender, ChannelBody) {
+    let (tx, rx) = mpsc::channel(buffer);
+    (BodySender { tx }, ChannelBody { rx })
+}
+
+/// Sending side of the body channel.
+///
+/// Body is finished once sender is dropped. Use `BodySender::error()` to
+/// abort response body, connection is closed in that case.
+#[derive(Debug)]
+pub struct BodySender {
+    tx: mpsc::Sender<Result<Bytes, Error>>,
+}
+
+/// Error returned by `BodySender` if response body is dropped,
+/// i.e. peer is disconnected.
+#[derive(Debug, Display, Clone, Copy, PartialEq)]
+#[display(fmt = "Response body is dropped")]
+pub struct BodyClosed;
+
+impl std::error::Error for BodyClosed {}
+
+impl BodySender {
+    /// Send chunk, waits until body channel has capacity.
+    pub async fn send<T: Into<Bytes>>(&mut self, chunk: T) -> Result<(), BodyClosed> {
+        self.send_item(Ok(chunk.into())).await
+    }
+
+    /// Abort response body with error.
+    pub async fn error<E: Into<Error>>(mut self, err: E) -> Result<(), BodyClosed> {
+        self.send_item(Err(err.into())).await
+    }
+
+    /// Check if response body is dropped
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Poll body channel capacity
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BodyClosed>> {
+        self.tx.poll_ready(cx).map_err(|_| BodyClosed)
+    }
+
+    async fn send_item(&mut self, item: Result<Bytes, Error>) -> Result<(), BodyClosed> {
+        poll_fn(|cx| self.poll_ready(cx)).await?;
+        self.tx.start_send(item).map_err(|_| BodyClosed)
+    }
+}
+
+/// Receiving side of the body channel, streaming response body.
+#[derive(Debug)]
+pub struct ChannelBody {
+    rx: mpsc::Receiver<Result<Bytes, Error>>,
+}
+
+impl MessageBody for ChannelBody {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+/// `MessageBody` adapter for `http_body::Body` implementations, i.e. bodies
+/// of hyper ecosystem libraries.
+///
+/// Body is streamed, data chunks are converted to `Bytes` and trailers are
+/// ignored. Body errors are converted to *500 Internal Server Error*
+/// errors. Exact size hint of the body is used as response body size.
+#[cfg(feature = "http-body")]
+pub struct FromHttpBody<B> {
+    body: Pin<Box<B>>,
+}
+
+#[cfg(feature = "http-body")]
+impl<B> FromHttpBody<B>
+where
+    B: http_body_rs::Body,
+    B::Error: fmt::Debug + fmt::Display + 'static,
+{
+    /// Wrap `http_body::Body`
+    pub fn new(body: B) -> Self {
+        FromHttpBody {
+            body: Box::pin(body),
+        }
+    }
+}
+
+#[cfg(feature = "http-body")]
+impl<B> MessageBody for FromHttpBody<B>
+where
+    B: http_body_rs::Body,
+    B::Error: fmt::Debug + fmt::Display + 'static,
+{
+    fn size(&self) -> BodySize {
+        if self.body.is_end_stream() {
+            BodySize::Empty
+        } else if let Some(size) = self.body.size_hint().exact() {
+            BodySize::Sized64(size)
+        } else {
+            BodySize::Stream
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        use bytes::Buf;
+
+        match self.body.as_mut().poll_data(cx) {
+            Poll::Ready(Some(Ok(mut data))) => Poll::Ready(Some(Ok(data.to_bytes()))),
+            Poll::Ready(Some(Err(e))) => {
+                Poll::Ready(Some(Err(crate::error::ErrorInternalServerError(e))))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "http-body")]
+impl<B> From<FromHttpBody<B>> for Body
+where
+    B: http_body_rs::Body + 'static,
+    B::Error: fmt::Debug + fmt::Display + 'static,
+{
+    fn from(body: FromHttpBody<B>) -> Body {
+        Body::from_message(body)
+    }
+}
+
+/// `http_body::Body` adapter for `MessageBody`, i.e. to pass response
+/// bodies to hyper ecosystem libraries.
+#[cfg(feature = "http-body")]
+pub struct IntoHttpBody<B> {
+    body: B,
+}
+
+#[cfg(feature = "http-body")]
+impl<B: MessageBody + Unpin> IntoHttpBody<B> {
+    /// Wrap `MessageBody`
+    pub fn new(body: B) -> Self {
+        IntoHttpBody { body }
+    }
+
+    /// Unwrap `MessageBody`
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+#[cfg(feature = "http-body")]
+impl<B: MessageBody + Unpin> http_body_rs::Body for IntoHttpBody<B> {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        self.get_mut().body.poll_next(cx)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Error>> {
+        Poll::Ready(Ok(None))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.size().is_eof()
+    }
+
+    fn size_hint(&self) -> http_body_rs::SizeHint {
+        match self.body.size() {
+            BodySize::None | BodySize::Empty => http_body_rs::SizeHint::with_exact(0),
+            BodySize::Sized(size) => http_body_rs::SizeHint::with_exact(size as u64),
+            BodySize::Sized64(size) => http_body_rs::SizeHint::with_exact(size),
+            BodySize::Stream => http_body_rs::SizeHint::default(),
+        }
+    }
+}
+
+/// Error of `to_bytes_limited()`
+#[derive(Debug, Display)]
+pub enum BodyLimitError {
+    /// Body is bigger than the limit
+    #[display(fmt = "Body is bigger than {} bytes", _0)]
+    Overflow(usize),
+    /// Body stream error
+    #[display(fmt = "{}", _0)]
+    Body(Error),
+}
+
+impl std::error::Error for BodyLimitError {}
+
+/// Collect body into `Bytes`, fails with `BodyLimitError::Overflow` once
+/// body is bigger than `limit` bytes.
+///
+/// Sized bodies bigger than the limit are rejected without reading them.
+/// Single chunk bodies are returned without copying.
+pub async fn to_bytes_limited<B: MessageBody>(
+    mut body: B,
+    limit: usize,
+) -> Result<Bytes, BodyLimitError> {
+    match body.size() {
+        BodySize::None | BodySize::Empty => return Ok(Bytes::new()),
+        BodySize::Sized(len) if len > limit => {
+            return Err(BodyLimitError::Overflow(limit))
+        }
+        BodySize::Sized64(len) if len > limit as u64 => {
+            return Err(BodyLimitError::Overflow(limit))
+        }
+        _ => (),
+    }
+
+    let mut size = 0;
+    let mut first: Option<Bytes> = None;
+    let mut buf = BytesMut::new();
+    while let Some(item) = poll_fn(|cx| body.poll_next(cx)).await {
+        let chunk = item.map_err(BodyLimitError::Body)?;
+        size += chunk.len();
+        if size > limit {
+            return Err(BodyLimitError::Overflow(limit));
+        }
+
+        // copy chunks only if there is more than one
+        match first.take() {
+            None if buf.is_empty() => first = Some(chunk),
+            Some(prev) => {
+                buf.extend_from_slice(&prev);
+                buf.extend_from_slice(&chunk);
+            }
+            None => buf.extend_from_slice(&chunk),
+        }
+    }
+    Ok(first.unwrap_or_else(|| buf.freeze()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use futures_util::future::poll_fn;
 
     impl Body {
         pub(crate) fn get_ref(&self) -> &[u8] {
@@ -589,4 +903,91 @@ mod tests {
             BodySize::Sized(25)
         );
     }
+
+    #[actori_rt::test]
+    async fn test_channel() {
+        let (mut tx, mut body) = channel(1);
+        assert_eq!(body.size(), BodySize::Stream);
+
+        tx.send("test").await.unwrap();
+        assert_eq!(
+            poll_fn(|cx| body.poll_next(cx)).await.unwrap().ok(),
+            Some(Bytes::from("test"))
+        );
+        tx.error(crate::error::ErrorBadRequest("err"))
+            .await
+            .unwrap();
+        assert!(poll_fn(|cx| body.poll_next(cx)).await.unwrap().is_err());
+        assert!(poll_fn(|cx| body.poll_next(cx)).await.is_none());
+
+        let (mut tx, body) = channel(1);
+        assert!(!tx.is_closed());
+        drop(body);
+        assert!(tx.is_closed());
+        assert_eq!(tx.send("test").await, Err(BodyClosed));
+    }
+
+    #[actori_rt::test]
+    async fn test_to_bytes_limited() {
+        let body = to_bytes_limited(Body::from("test"), 4).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"test"));
+        assert!(to_bytes_limited(Body::Empty, 0).await.unwrap().is_empty());
+        match to_bytes_limited(Body::from("test"), 3).await {
+            Err(BodyLimitError::Overflow(3)) => (),
+            _ => panic!(),
+        }
+
+        let (mut tx, body) = channel(4);
+        tx.send("te").await.unwrap();
+        tx.send("st").await.unwrap();
+        drop(tx);
+        let body = to_bytes_limited(body, 4).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"test"));
+
+        let (mut tx, body) = channel(4);
+        tx.send("test").await.unwrap();
+        tx.send("!").await.unwrap();
+        drop(tx);
+        match to_bytes_limited(body, 4).await {
+            Err(BodyLimitError::Overflow(4)) => (),
+            _ => panic!(),
+        }
+
+        let (mut tx, body) = channel(4);
+        tx.send("te").await.unwrap();
+        tx.error(crate::error::ErrorBadRequest("err"))
+            .await
+            .unwrap();
+        match to_bytes_limited(body, 4).await {
+            Err(BodyLimitError::Body(_)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[cfg(feature = "http-body")]
+    #[actori_rt::test]
+    async fn test_http_body() {
+        use http_body_rs::Body as _;
+
+        let body = IntoHttpBody::new(Body::from("test"));
+        assert!(!body.is_end_stream());
+        assert_eq!(body.size_hint().exact(), Some(4));
+        assert!(IntoHttpBody::new(Body::Empty).is_end_stream());
+
+        let body = FromHttpBody::new(body);
+        assert_eq!(body.size(), BodySize::Sized64(4));
+        let body = to_bytes_limited(Body::from(body), 4).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"test"));
+
+        let (mut tx, body) = channel(1);
+        let mut body = FromHttpBody::new(IntoHttpBody::new(body));
+        assert_eq!(body.size(), BodySize::Stream);
+        tx.send("data").await.unwrap();
+        assert_eq!(
+            poll_fn(|cx| body.poll_next(cx)).await.unwrap().ok(),
+            Some(Bytes::from_static(b"data"))
+        );
+        drop(tx);
+        assert!(poll_fn(|cx| body.poll_next(cx)).await.is_none());
+    }
 }
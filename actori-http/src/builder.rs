@@ -1,16 +1,21 @@
 use std::marker::PhantomData;
 use std::rc::Rc;
+use std::time::Duration;
 use std::{fmt, net};
 
 use actori_codec::Framed;
 use actori_service::{IntoServiceFactory, Service, ServiceFactory};
+use http::header::HeaderValue;
 
 use crate::body::MessageBody;
-use crate::config::{KeepAlive, ServiceConfig};
+use crate::config::{
+    H2Limits, KeepAlive, PayloadLimits, ServiceConfig, TlsLimits, DEFAULT_OPTIONS_ALLOW,
+};
 use crate::error::Error;
 use crate::h1::{Codec, ExpectHandler, H1Service, UpgradeHandler};
 use crate::h2::H2Service;
 use crate::helpers::{Data, DataFactory};
+use crate::metrics::Metrics;
 use crate::request::Request;
 use crate::response::Response;
 use crate::service::HttpService;
@@ -25,6 +30,14 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler<T>> {
     client_disconnect: u64,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    listener_name: Option<String>,
+    options_allow: Option<HeaderValue>,
+    h2_limits: H2Limits,
+    payload_limits: PayloadLimits,
+    tls_limits: TlsLimits,
+    metrics: Option<Rc<dyn Metrics>>,
+    close_on_error: bool,
+    write_batch: u64,
     expect: X,
     upgrade: Option<U>,
     on_connect: Option<Rc<dyn Fn(&T) -> Box<dyn DataFactory>>>,
@@ -46,6 +59,14 @@ where
             client_disconnect: 0,
             secure: false,
             local_addr: None,
+            listener_name: None,
+            options_allow: Some(HeaderValue::from_static(DEFAULT_OPTIONS_ALLOW)),
+            h2_limits: H2Limits::default(),
+            payload_limits: PayloadLimits::default(),
+            tls_limits: TlsLimits::default(),
+            metrics: None,
+            close_on_error: false,
+            write_batch: 0,
             expect: ExpectHandler,
             upgrade: None,
             on_connect: None,
@@ -89,6 +110,14 @@ where
         self
     }
 
+    /// Set name of the listener that this service is bound to.
+    ///
+    /// Name is available to the service via `ListenerName` connection data.
+    pub fn listener_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.listener_name = Some(name.into());
+        self
+    }
+
     /// Set server client timeout in milliseconds for first request.
     ///
     /// Defines a timeout for reading client request header. If a client does not transmit
@@ -116,6 +145,168 @@ where
         self
     }
 
+    /// Set `Allow` header value of the response for server-wide `OPTIONS *` requests.
+    ///
+    /// `OPTIONS` requests with asterisk-form request target are answered by
+    /// dispatcher directly. To forward such requests to the service set value
+    /// to `None`.
+    ///
+    /// By default `Allow` header is set to
+    /// `OPTIONS, GET, HEAD, POST, PUT, PATCH, DELETE`.
+    pub fn options_allow(mut self, allow: Option<HeaderValue>) -> Self {
+        self.options_allow = allow;
+        self
+    }
+
+    /// Set max size of request payload in bytes.
+    ///
+    /// Payload of a request that declares bigger `Content-Length` or sends
+    /// more data fails with `PayloadError::Overflow` error, so the service
+    /// could respond with 413 (Payload Too Large) error without waiting for
+    /// the rest of the payload. Excess data is discarded.
+    ///
+    /// To disable limit set value to 0.
+    ///
+    /// By default payload size is not limited.
+    pub fn max_payload_size(mut self, size: u64) -> Self {
+        self.payload_limits.max_size = size;
+        self
+    }
+
+    /// Set max size of unread request payload in bytes that http/1 connection
+    /// discards after early response.
+    ///
+    /// If service responds before reading whole request payload, for example
+    /// because of a payload error, dispatcher reads and discards the rest of
+    /// the payload to keep connection alive. If the rest of the payload is
+    /// bigger than this value or its size is unknown, response is sent with
+    /// `Connection: close` header and connection is closed instead.
+    ///
+    /// By default drain size is set to 64Kb.
+    pub fn payload_drain_size(mut self, size: u64) -> Self {
+        self.payload_limits.drain_size = size;
+        self
+    }
+
+    /// Set max duration of http/2 stream.
+    ///
+    /// Stream that is not completed within this time, including sending
+    /// of the response payload, is reset with `CANCEL` error code.
+    ///
+    /// By default stream duration is not limited.
+    pub fn h2_max_stream_duration(mut self, dur: Duration) -> Self {
+        self.h2_limits.max_stream_duration = Some(dur);
+        self
+    }
+
+    /// Set idle timeout of http/2 stream.
+    ///
+    /// Stream is reset with `CANCEL` error code if nothing is sent to the peer
+    /// within this time, i.e. service does not respond or response payload
+    /// does not produce data or peer does not release flow control capacity.
+    ///
+    /// By default idle streams are not reset.
+    pub fn h2_stream_idle_timeout(mut self, dur: Duration) -> Self {
+        self.h2_limits.stream_idle_timeout = Some(dur);
+        self
+    }
+
+    /// Set max number of reset streams that http/2 connection keeps track of.
+    ///
+    /// Frames received for recently reset streams are ignored, the state
+    /// is kept for a limited number of streams. Lower value reduces memory
+    /// used by `RST_STREAM` floods.
+    ///
+    /// By default h2 crate default is used, which is 10 streams.
+    pub fn h2_max_concurrent_reset_streams(mut self, max: usize) -> Self {
+        self.h2_limits.max_concurrent_reset_streams = Some(max);
+        self
+    }
+
+    /// Set max number of streams a client may open per http/2 connection
+    /// within a period.
+    ///
+    /// Opening and immediately resetting streams ("rapid reset") allows a
+    /// client to load the server without being limited by concurrent streams
+    /// setting. If the client opens more than `max` streams within `period`,
+    /// new streams are refused and connection is closed with
+    /// `ENHANCE_YOUR_CALM` error code.
+    ///
+    /// By default stream opening rate is not limited.
+    pub fn h2_stream_rate_limit(mut self, max: u32, period: Duration) -> Self {
+        self.h2_limits.stream_rate = Some((max, period));
+        self
+    }
+
+    /// Set max time in milliseconds to complete tls handshake.
+    ///
+    /// Connection is closed if client does not complete tls handshake within
+    /// this time, so slow clients can not hold handshake slots. Applies to
+    /// `HttpService::openssl()` and `HttpService::rustls()` services.
+    ///
+    /// To disable timeout set value to 0.
+    ///
+    /// By default handshake timeout is set to 3000 milliseconds.
+    pub fn tls_handshake_timeout(mut self, val: u64) -> Self {
+        self.tls_limits.handshake_timeout = val;
+        self
+    }
+
+    /// Set max number of concurrent tls handshakes per service instance,
+    /// i.e. per listener and worker.
+    ///
+    /// Service stops accepting connections of the listener while the limit is
+    /// reached. Applies to `HttpService::openssl()` and `HttpService::rustls()`
+    /// services, in addition to the process wide limit of `actori-tls`.
+    ///
+    /// To disable limit set value to 0.
+    ///
+    /// By default number of handshakes is not limited.
+    pub fn max_tls_handshakes(mut self, num: usize) -> Self {
+        self.tls_limits.max_handshakes = num;
+        self
+    }
+
+    /// Set connection metrics hooks.
+    ///
+    /// Hooks are called by http/1 and http/2 dispatchers on connection open
+    /// and close, on request start and end and on connection reads and writes.
+    /// Service instance is created per worker, use `Arc` to share metrics
+    /// between workers.
+    pub fn metrics<M: Metrics + 'static>(mut self, metrics: M) -> Self {
+        self.metrics = Some(Rc::new(metrics));
+        self
+    }
+
+    /// Close http/1 connections after server error (5xx) responses.
+    ///
+    /// Connection state could be corrupted after a failure, closing connection
+    /// sheds it. Response could override this setting with
+    /// `ResponseBuilder::keep_alive()`. Http/2 connections are not affected.
+    ///
+    /// By default connections are kept alive.
+    pub fn close_on_error(mut self, close: bool) -> Self {
+        self.close_on_error = close;
+        self
+    }
+
+    /// Set max delay in milliseconds of batched http/1 response payload writes.
+    ///
+    /// Small chunks of streaming response payload are buffered and written
+    /// to the socket together, once buffer reaches 4Kb, payload is complete
+    /// or delay is elapsed. It improves throughput for services that emit
+    /// many tiny chunks. Tcp connections of `HttpService::tcp()` and
+    /// `H1Service::tcp()` are switched to `TCP_NODELAY` mode if batching
+    /// is enabled.
+    ///
+    /// To disable batching set value to 0.
+    ///
+    /// By default batching is disabled.
+    pub fn h1_write_batch(mut self, val: u64) -> Self {
+        self.write_batch = val;
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -135,6 +326,14 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            listener_name: self.listener_name,
+            options_allow: self.options_allow,
+            h2_limits: self.h2_limits,
+            payload_limits: self.payload_limits,
+            tls_limits: self.tls_limits,
+            metrics: self.metrics,
+            close_on_error: self.close_on_error,
+            write_batch: self.write_batch,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect: self.on_connect,
@@ -164,6 +363,14 @@ where
             client_disconnect: self.client_disconnect,
             secure: self.secure,
             local_addr: self.local_addr,
+            listener_name: self.listener_name,
+            options_allow: self.options_allow,
+            h2_limits: self.h2_limits,
+            payload_limits: self.payload_limits,
+            tls_limits: self.tls_limits,
+            metrics: self.metrics,
+            close_on_error: self.close_on_error,
+            write_batch: self.write_batch,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect: self.on_connect,
@@ -199,7 +406,13 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_listener_name(self.listener_name)
+        .with_options_allow(self.options_allow)
+        .with_payload_limits(self.payload_limits)
+        .with_metrics(self.metrics)
+        .with_close_on_error(self.close_on_error)
+        .with_write_batch(write_batch(self.write_batch));
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
@@ -222,7 +435,13 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_listener_name(self.listener_name)
+        .with_options_allow(self.options_allow)
+        .with_h2_limits(self.h2_limits)
+        .with_payload_limits(self.payload_limits)
+        .with_metrics(self.metrics)
+        .with_close_on_error(self.close_on_error);
         H2Service::with_config(cfg, service.into_factory()).on_connect(self.on_connect)
     }
 
@@ -242,10 +461,26 @@ where
             self.client_disconnect,
             self.secure,
             self.local_addr,
-        );
+        )
+        .with_listener_name(self.listener_name)
+        .with_options_allow(self.options_allow)
+        .with_h2_limits(self.h2_limits)
+        .with_payload_limits(self.payload_limits)
+        .with_tls_limits(self.tls_limits)
+        .with_metrics(self.metrics)
+        .with_close_on_error(self.close_on_error)
+        .with_write_batch(write_batch(self.write_batch));
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
             .upgrade(self.upgrade)
             .on_connect(self.on_connect)
     }
 }
+
+fn write_batch(delay: u64) -> Option<Duration> {
+    if delay == 0 {
+        None
+    } else {
+        Some(Duration::from_millis(delay))
+    }
+}
@@ -15,6 +15,7 @@ use crate::payload::Payload;
 
 use super::error::SendRequestError;
 use super::pool::{Acquired, Protocol};
+use super::proxy::Proxy;
 use super::{h1proto, h2proto};
 
 pub(crate) enum ConnectionType<Io> {
@@ -57,6 +58,7 @@ pub struct IoConnection<T> {
     io: Option<ConnectionType<T>>,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    proxy: Option<Proxy>,
 }
 
 impl<T> fmt::Debug for IoConnection<T>
@@ -82,9 +84,16 @@ impl<T: AsyncRead + AsyncWrite + Unpin> IoConnection<T> {
             pool,
             created,
             io: Some(io),
+            proxy: None,
         }
     }
 
+    /// Mark connection as connection to http proxy, requests are sent
+    /// in absolute-form
+    pub(crate) fn set_proxy(&mut self, proxy: Proxy) {
+        self.proxy = Some(proxy);
+    }
+
     pub(crate) fn into_inner(self) -> (ConnectionType<T>, time::Instant) {
         (self.io.unwrap(), self.created)
     }
@@ -112,10 +121,15 @@ where
         body: B,
     ) -> Self::Future {
         match self.io.take().unwrap() {
-            ConnectionType::H1(io) => {
-                h1proto::send_request(io, head.into(), body, self.created, self.pool)
-                    .boxed_local()
-            }
+            ConnectionType::H1(io) => h1proto::send_request(
+                io,
+                head.into(),
+                body,
+                self.created,
+                self.pool,
+                self.proxy,
+            )
+            .boxed_local(),
             ConnectionType::H2(io) => {
                 h2proto::send_request(io, head.into(), body, self.created, self.pool)
                     .boxed_local()
@@ -134,9 +148,9 @@ where
     /// Send request, returns Response and Framed
     fn open_tunnel<H: Into<RequestHeadType>>(mut self, head: H) -> Self::TunnelFuture {
         match self.io.take().unwrap() {
-            ConnectionType::H1(io) => {
-                Either::Left(h1proto::open_tunnel(io, head.into()).boxed_local())
-            }
+            ConnectionType::H1(io) => Either::Left(
+                h1proto::open_tunnel(io, head.into(), self.proxy).boxed_local(),
+            ),
             ConnectionType::H2(io) => {
                 if let Some(mut pool) = self.pool.take() {
                     pool.release(IoConnection::new(
@@ -42,6 +42,14 @@ pub enum ConnectError {
     #[display(fmt = "Timeout out while establishing connection")]
     Timeout,
 
+    /// Connection was not acquired from the pool in time
+    #[display(fmt = "Timeout out while waiting for a connection from the pool")]
+    PoolTimeout,
+
+    /// Too many requests are waiting for a connection from the pool
+    #[display(fmt = "Connection pool waiters queue is full")]
+    PoolOverloaded,
+
     /// Connector has been disconnected
     #[display(fmt = "Internal error: connector has been disconnected")]
     Disconnected,
@@ -115,13 +123,45 @@ pub enum SendRequestError {
     Body(Error),
 }
 
+impl SendRequestError {
+    /// Request could be sent again.
+    ///
+    /// True if connection could not be established or acquired from the pool,
+    /// or if connection is closed or stream is refused before response is
+    /// received. Failed connections are not returned to the pool, request that
+    /// is sent again acquires another connection.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SendRequestError::Connect(e) => match e {
+                ConnectError::Timeout
+                | ConnectError::PoolTimeout
+                | ConnectError::PoolOverloaded
+                | ConnectError::Disconnected
+                | ConnectError::H2(_)
+                | ConnectError::Io(_) => true,
+                _ => false,
+            },
+            SendRequestError::Send(_)
+            | SendRequestError::Response(ParseError::Io(_)) => true,
+            SendRequestError::H2(e) => {
+                e.is_io() || e.reason() == Some(h2::Reason::REFUSED_STREAM)
+            }
+            _ => false,
+        }
+    }
+}
+
 /// Convert `SendRequestError` to a server `Response`
 impl ResponseError for SendRequestError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            SendRequestError::Connect(ConnectError::Timeout) => {
+            SendRequestError::Connect(ConnectError::Timeout)
+            | SendRequestError::Connect(ConnectError::PoolTimeout) => {
                 StatusCode::GATEWAY_TIMEOUT
             }
+            SendRequestError::Connect(ConnectError::PoolOverloaded) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             SendRequestError::Connect(_) => StatusCode::BAD_REQUEST,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
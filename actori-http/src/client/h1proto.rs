@@ -1,33 +1,67 @@
 use std::io::Write;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
-use std::{io, mem, time};
+use std::time::Duration;
+use std::{fmt, io, mem, time};
 
 use actori_codec::{AsyncRead, AsyncWrite, Framed};
+use actori_rt::time::delay_for;
 use bytes::buf::BufMutExt;
 use bytes::{Bytes, BytesMut};
 use futures_core::Stream;
-use futures_util::future::poll_fn;
+use futures_util::future::{poll_fn, select, Either};
 use futures_util::{SinkExt, StreamExt};
 
 use crate::error::PayloadError;
 use crate::h1;
 use crate::header::HeaderMap;
-use crate::http::header::{IntoHeaderValue, HOST};
+use crate::http::header::{IntoHeaderValue, EXPECT, HOST, PROXY_AUTHORIZATION};
+use crate::http::StatusCode;
 use crate::message::{RequestHeadType, ResponseHead};
 use crate::payload::{Payload, PayloadStream};
 
 use super::connection::{ConnectionLifetime, ConnectionType, IoConnection};
 use super::error::{ConnectError, SendRequestError};
 use super::pool::Acquired;
+use super::proxy::Proxy;
 use crate::body::{BodySize, MessageBody};
 
+/// Time to wait for `100 Continue` response before request body is sent
+/// anyway, server may not support `Expect` header.
+const EXPECT_CONTINUE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Handler for informational (1xx) responses received before the final
+/// response.
+///
+/// Handler is stored in request head extensions, informational responses
+/// are skipped if handler is not set.
+#[derive(Clone)]
+pub struct InformationalHandler(Rc<dyn Fn(&ResponseHead)>);
+
+impl InformationalHandler {
+    /// Create handler from a function
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&ResponseHead) + 'static,
+    {
+        InformationalHandler(Rc::new(f))
+    }
+}
+
+impl fmt::Debug for InformationalHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InformationalHandler")
+    }
+}
+
 pub(crate) async fn send_request<T, B>(
     io: T,
     mut head: RequestHeadType,
     body: B,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    proxy: Option<Proxy>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
@@ -66,47 +100,149 @@ where
         io: Some(io),
     };
 
+    let informational = head
+        .as_ref()
+        .extensions()
+        .get::<InformationalHandler>()
+        .cloned();
+    let has_body = match body.size() {
+        BodySize::None | BodySize::Empty | BodySize::Sized(0) => false,
+        _ => true,
+    };
+    let expect = has_body && expect_continue(&head);
+
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut framed = Framed::new(io, proxy_codec(&mut head, proxy));
     framed.send((head, body.size()).into()).await?;
 
+    // wait for `100 Continue`, server could reject request with final
+    // response, in that case body is not sent and connection is closed
+    if expect {
+        let fut = Box::pin(read_continue(&mut framed, informational.as_ref()));
+        let res = match select(fut, delay_for(EXPECT_CONTINUE_TIMEOUT)).await {
+            Either::Left((res, _)) => res?,
+            Either::Right(_) => None,
+        };
+        if let Some(head) = res {
+            return Ok(response(head, framed, true));
+        }
+    }
+
     // send request body
-    match body.size() {
-        BodySize::None | BodySize::Empty | BodySize::Sized(0) => (),
-        _ => send_body(body, &mut framed).await?,
-    };
+    if has_body {
+        send_body(body, &mut framed).await?;
+    }
 
     // read response and init read body
-    let res = framed.into_future().await;
-    let (head, framed) = if let (Some(result), framed) = res {
-        let item = result.map_err(SendRequestError::from)?;
-        (item, framed)
-    } else {
-        return Err(SendRequestError::from(ConnectError::Disconnected));
+    let head = read_response(&mut framed, informational.as_ref()).await?;
+    Ok(response(head, framed, false))
+}
+
+/// Check if request contains `Expect: 100-continue` header
+fn expect_continue(head: &RequestHeadType) -> bool {
+    let is_continue = |headers: &HeaderMap| {
+        headers
+            .get(EXPECT)
+            .map(|val| val.as_bytes().eq_ignore_ascii_case(b"100-continue"))
+            .unwrap_or(false)
     };
 
+    is_continue(&head.as_ref().headers)
+        || head.extra_headers().iter().any(|h| is_continue(h))
+}
+
+/// Read responses until `100 Continue` or final response
+async fn read_continue<T>(
+    framed: &mut Framed<T, h1::ClientCodec>,
+    informational: Option<&InformationalHandler>,
+) -> Result<Option<ResponseHead>, SendRequestError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let head = read_head(framed).await?;
+        if head.status == StatusCode::CONTINUE {
+            return Ok(None);
+        } else if is_informational(&head) {
+            if let Some(handler) = informational {
+                (handler.0)(&head);
+            }
+        } else {
+            return Ok(Some(head));
+        }
+    }
+}
+
+/// Read final response, informational responses are passed to the handler
+async fn read_response<T>(
+    framed: &mut Framed<T, h1::ClientCodec>,
+    informational: Option<&InformationalHandler>,
+) -> Result<ResponseHead, SendRequestError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let head = read_head(framed).await?;
+        if is_informational(&head) {
+            log::trace!("Informational response: {}", head.status);
+            if let Some(handler) = informational {
+                (handler.0)(&head);
+            }
+        } else {
+            return Ok(head);
+        }
+    }
+}
+
+async fn read_head<T>(
+    framed: &mut Framed<T, h1::ClientCodec>,
+) -> Result<ResponseHead, SendRequestError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    match framed.next().await {
+        Some(result) => result.map_err(SendRequestError::from),
+        None => Err(SendRequestError::from(ConnectError::Disconnected)),
+    }
+}
+
+/// `101 Switching Protocols` is a final response for the h1 connection
+fn is_informational(head: &ResponseHead) -> bool {
+    head.status.is_informational() && head.status != StatusCode::SWITCHING_PROTOCOLS
+}
+
+/// Init response payload
+fn response<T>(
+    head: ResponseHead,
+    framed: Framed<T, h1::ClientCodec>,
+    force_close: bool,
+) -> (ResponseHead, Payload)
+where
+    T: ConnectionLifetime,
+{
     match framed.get_codec().message_type() {
         h1::MessageType::None => {
-            let force_close = !framed.get_codec().keepalive();
+            let force_close = force_close || !framed.get_codec().keepalive();
             release_connection(framed, force_close);
-            Ok((head, Payload::None))
+            (head, Payload::None)
         }
         _ => {
-            let pl: PayloadStream = PlStream::new(framed).boxed_local();
-            Ok((head, pl.into()))
+            let pl: PayloadStream = PlStream::new(framed, force_close).boxed_local();
+            (head, pl.into())
         }
     }
 }
 
 pub(crate) async fn open_tunnel<T>(
     io: T,
-    head: RequestHeadType,
+    mut head: RequestHeadType,
+    proxy: Option<Proxy>,
 ) -> Result<(ResponseHead, Framed<T, h1::ClientCodec>), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     // create Framed and send request
-    let mut framed = Framed::new(io, h1::ClientCodec::default());
+    let mut framed = Framed::new(io, proxy_codec(&mut head, proxy));
     framed.send((head, BodySize::None).into()).await?;
 
     // read response
@@ -130,12 +266,29 @@ where
     let mut eof = false;
     while !eof {
         while !eof && !framed.is_write_buf_full() {
-            match poll_fn(|cx| body.poll_next(cx)).await {
+            let item = poll_fn(|cx| match body.poll_next(cx) {
+                Poll::Ready(item) => Poll::Ready(Ok(item)),
+                Poll::Pending => {
+                    // send buffered chunks while waiting for the body
+                    if !framed.is_write_buf_empty() {
+                        if let Poll::Ready(Err(err)) = framed.flush(cx) {
+                            return Poll::Ready(Err(err));
+                        }
+                    }
+                    Poll::Pending
+                }
+            })
+            .await?;
+
+            match item {
                 Some(result) => {
                     framed.write(h1::Message::Chunk(Some(result?)))?;
                 }
                 None => {
                     eof = true;
+                    if let Some(trailers) = body.take_trailers() {
+                        framed.get_codec_mut().set_trailers(trailers);
+                    }
                     framed.write(h1::Message::Chunk(None))?;
                 }
             }
@@ -161,6 +314,34 @@ where
     Ok(())
 }
 
+/// Codec for the request, requests to http proxy are sent in absolute-form
+/// with proxy credentials
+fn proxy_codec(head: &mut RequestHeadType, proxy: Option<Proxy>) -> h1::ClientCodec {
+    let mut codec = h1::ClientCodec::default();
+    if let Some(proxy) = proxy {
+        codec.set_absolute_form(true);
+
+        if let Some(auth) = proxy.authorization() {
+            match head {
+                RequestHeadType::Owned(ref mut head) => {
+                    if !head.headers.contains_key(PROXY_AUTHORIZATION) {
+                        head.headers.insert(PROXY_AUTHORIZATION, auth);
+                    }
+                }
+                RequestHeadType::Rc(ref head, ref mut extra_headers) => {
+                    let headers = extra_headers.get_or_insert(HeaderMap::new());
+                    if !head.headers.contains_key(PROXY_AUTHORIZATION)
+                        && !headers.contains_key(PROXY_AUTHORIZATION)
+                    {
+                        headers.insert(PROXY_AUTHORIZATION, auth);
+                    }
+                }
+            }
+        }
+    }
+    codec
+}
+
 #[doc(hidden)]
 /// HTTP client connection
 pub struct H1Connection<T> {
@@ -243,12 +424,14 @@ impl<T: AsyncRead + AsyncWrite + Unpin + 'static> AsyncWrite for H1Connection<T>
 
 pub(crate) struct PlStream<Io> {
     framed: Option<Framed<Io, h1::ClientPayloadCodec>>,
+    force_close: bool,
 }
 
 impl<Io: ConnectionLifetime> PlStream<Io> {
-    fn new(framed: Framed<Io, h1::ClientCodec>) -> Self {
+    fn new(framed: Framed<Io, h1::ClientCodec>, force_close: bool) -> Self {
         PlStream {
             framed: Some(framed.map_codec(|codec| codec.into_payload_codec())),
+            force_close,
         }
     }
 }
@@ -269,7 +452,8 @@ impl<Io: ConnectionLifetime> Stream for PlStream<Io> {
                     Poll::Ready(Some(Ok(chunk)))
                 } else {
                     let framed = this.framed.take().unwrap();
-                    let force_close = !framed.get_codec().keepalive();
+                    let force_close =
+                        this.force_close || !framed.get_codec().keepalive();
                     release_connection(framed, force_close);
                     Poll::Ready(None)
                 }
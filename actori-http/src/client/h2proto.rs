@@ -1,34 +1,42 @@
 use std::convert::TryFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use std::time;
 
 use actori_codec::{AsyncRead, AsyncWrite};
 use bytes::Bytes;
+use futures_core::{ready, Stream};
 use futures_util::future::poll_fn;
 use h2::{client::SendRequest, SendStream};
 use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, TRANSFER_ENCODING};
 use http::{request::Request, Method, Version};
 
 use crate::body::{BodySize, MessageBody};
+use crate::error::PayloadError;
 use crate::header::HeaderMap;
 use crate::message::{RequestHeadType, ResponseHead};
 use crate::payload::Payload;
 
 use super::connection::{ConnectionType, IoConnection};
 use super::error::SendRequestError;
-use super::pool::Acquired;
+use super::pool::{Acquired, H2Stream};
 
 pub(crate) async fn send_request<T, B>(
     mut io: SendRequest<Bytes>,
     head: RequestHeadType,
     body: B,
     created: time::Instant,
-    pool: Option<Acquired<T>>,
+    mut pool: Option<Acquired<T>>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
     B: MessageBody,
 {
     trace!("Sending client request: {:?} {:?}", head, body.size());
+    // stream is counted by connection pool until response payload is
+    // read to the end or dropped
+    let stream = pool.as_mut().and_then(|pool| pool.take_stream());
+
     let head_req = head.as_ref().method == Method::HEAD;
     let length = body.size();
     let eof = match length {
@@ -100,6 +108,12 @@ where
 
     let resp = match io.send_request(req, eof) {
         Ok((fut, send)) => {
+            // stream is pending, if server's concurrent streams limit is reached
+            if let Some(ref stream) = stream {
+                if poll_fn(|cx| Poll::Ready(io.poll_ready(cx).is_pending())).await {
+                    stream.saturated();
+                }
+            }
             release(io, pool, created, false);
 
             if !eof {
@@ -114,7 +128,14 @@ where
     };
 
     let (parts, body) = resp.into_parts();
-    let payload = if head_req { Payload::None } else { body.into() };
+    let payload = match stream {
+        _ if head_req => Payload::None,
+        Some(stream) => Payload::Stream(Box::pin(StreamPayload {
+            pl: crate::h2::Payload::new(body),
+            stream: Some(stream),
+        })),
+        None => body.into(),
+    };
 
     let mut head = ResponseHead::new(parts.status);
     head.version = parts.version;
@@ -122,6 +143,30 @@ where
     Ok((head, payload))
 }
 
+/// Response payload of pooled h2 connection, stream is released
+/// once payload is read to the end, failed or dropped
+struct StreamPayload {
+    pl: crate::h2::Payload,
+    stream: Option<H2Stream>,
+}
+
+impl Stream for StreamPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let item = ready!(Pin::new(&mut this.pl).poll_next(cx));
+        match item {
+            Some(Ok(_)) => (),
+            _ => this.stream = None,
+        }
+        Poll::Ready(item)
+    }
+}
+
 async fn send_body<B: MessageBody>(
     mut body: B,
     mut send: SendStream<Bytes>,
@@ -7,14 +7,44 @@ mod error;
 mod h1proto;
 mod h2proto;
 mod pool;
+mod proxy;
+#[cfg(unix)]
+mod unix;
 
 pub use self::connection::Connection;
 pub use self::connector::Connector;
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
-pub use self::pool::Protocol;
+pub use self::h1proto::InformationalHandler;
+pub use self::pool::{PoolMetrics, Protocol};
+pub use self::proxy::Proxy;
+#[cfg(unix)]
+pub use self::unix::UnixConnector;
 
 #[derive(Clone)]
 pub struct Connect {
     pub uri: Uri,
     pub addr: Option<std::net::SocketAddr>,
+    proxy: Option<Proxy>,
+}
+
+impl Connect {
+    /// Create connect message for the uri
+    pub fn new(uri: Uri, addr: Option<std::net::SocketAddr>) -> Self {
+        Connect {
+            uri,
+            addr,
+            proxy: None,
+        }
+    }
+
+    /// Set proxy for the request, overrides connector's proxy
+    pub fn with_proxy(mut self, proxy: Option<Proxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Proxy for the request
+    pub fn proxy(&self) -> Option<&Proxy> {
+        self.proxy.as_ref()
+    }
 }
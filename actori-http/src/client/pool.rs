@@ -1,5 +1,7 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp;
 use std::collections::VecDeque;
+use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
@@ -11,7 +13,7 @@ use actori_rt::time::{delay_for, Delay};
 use actori_service::Service;
 use actori_utils::{oneshot, task::LocalWaker};
 use bytes::Bytes;
-use futures_util::future::{poll_fn, FutureExt, LocalBoxFuture};
+use futures_util::future::{poll_fn, select, Either, FutureExt, LocalBoxFuture};
 use fxhash::FxHashMap;
 use h2::client::{handshake, Connection, SendRequest};
 use http::uri::Authority;
@@ -20,6 +22,7 @@ use slab::Slab;
 
 use super::connection::{ConnectionType, IoConnection};
 use super::error::ConnectError;
+use super::proxy::Proxy;
 use super::Connect;
 
 #[derive(Clone, Copy, PartialEq)]
@@ -32,11 +35,110 @@ pub enum Protocol {
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub(crate) struct Key {
     authority: Authority,
+    proxy: Option<Proxy>,
 }
 
-impl From<Authority> for Key {
-    fn from(authority: Authority) -> Key {
-        Key { authority }
+impl Key {
+    /// Pool key of the request, connections through different proxies
+    /// are not shared
+    fn new(connect: &Connect) -> Option<Key> {
+        connect.uri.authority().map(|authority| Key {
+            authority: authority.clone(),
+            proxy: connect.proxy.clone(),
+        })
+    }
+}
+
+/// Waiters queue settings of connections pool
+#[derive(Clone)]
+pub(crate) struct WaitConfig {
+    /// Max number of queued waiters, 0 means no limit
+    pub(crate) max_waiters: usize,
+    /// Max time to wait for a connection
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) metrics: PoolMetrics,
+}
+
+/// Wait statistics of connection pool.
+///
+/// Requests wait for a connection if the pool's connections limit is reached.
+/// Metrics handle is shared between all pools of a `Connector` and could be
+/// obtained with `Connector::pool_metrics()` before the connector is finished.
+#[derive(Clone, Default)]
+pub struct PoolMetrics(Rc<MetricsInner>);
+
+#[derive(Default)]
+struct MetricsInner {
+    waiting: Cell<usize>,
+    waits: Cell<u64>,
+    wait_time: Cell<Duration>,
+    max_wait_time: Cell<Duration>,
+    timeouts: Cell<u64>,
+    rejected: Cell<u64>,
+}
+
+impl PoolMetrics {
+    /// Number of requests waiting for a connection right now.
+    pub fn waiting(&self) -> usize {
+        self.0.waiting.get()
+    }
+
+    /// Total number of completed waits, including timed out ones.
+    pub fn waits(&self) -> u64 {
+        self.0.waits.get()
+    }
+
+    /// Total time spent waiting for connections.
+    pub fn wait_time(&self) -> Duration {
+        self.0.wait_time.get()
+    }
+
+    /// Longest completed wait.
+    pub fn max_wait_time(&self) -> Duration {
+        self.0.max_wait_time.get()
+    }
+
+    /// Number of waits that ended with `ConnectError::PoolTimeout`.
+    pub fn timeouts(&self) -> u64 {
+        self.0.timeouts.get()
+    }
+
+    /// Number of requests rejected with `ConnectError::PoolOverloaded`.
+    pub fn rejected(&self) -> u64 {
+        self.0.rejected.get()
+    }
+
+    fn wait_started(&self) {
+        self.0.waiting.set(self.0.waiting.get() + 1);
+    }
+
+    fn wait_finished(&self, elapsed: Duration, timeout: bool) {
+        let m = &self.0;
+        m.waiting.set(m.waiting.get() - 1);
+        m.waits.set(m.waits.get() + 1);
+        m.wait_time.set(m.wait_time.get() + elapsed);
+        m.max_wait_time
+            .set(cmp::max(m.max_wait_time.get(), elapsed));
+        if timeout {
+            m.timeouts.set(m.timeouts.get() + 1);
+        }
+    }
+
+    fn wait_rejected(&self) {
+        self.0.rejected.set(self.0.rejected.get() + 1);
+    }
+}
+
+impl fmt::Debug for PoolMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolMetrics")
+            .field("waiting", &self.waiting())
+            .field("waits", &self.waits())
+            .field("wait_time", &self.wait_time())
+            .field("max_wait_time", &self.max_wait_time())
+            .field("timeouts", &self.timeouts())
+            .field("rejected", &self.rejected())
+            .finish()
     }
 }
 
@@ -55,6 +157,8 @@ where
         conn_keep_alive: Duration,
         disconnect_timeout: Option<Duration>,
         limit: usize,
+        h2_max_streams: usize,
+        wait: WaitConfig,
     ) -> Self {
         ConnectionPool(
             Rc::new(RefCell::new(connector)),
@@ -63,10 +167,13 @@ where
                 conn_keep_alive,
                 disconnect_timeout,
                 limit,
+                h2_max_streams,
+                wait,
                 acquired: 0,
                 waiters: Slab::new(),
                 waiters_queue: IndexSet::new(),
                 available: FxHashMap::default(),
+                h2: FxHashMap::default(),
                 waker: LocalWaker::new(),
             })),
         )
@@ -108,20 +215,20 @@ where
         let inner = self.1.clone();
 
         let fut = async move {
-            let key = if let Some(authority) = req.uri.authority() {
-                authority.clone().into()
+            let key = if let Some(key) = Key::new(&req) {
+                key
             } else {
                 return Err(ConnectError::Unresolverd);
             };
 
             // acquire connection
             match poll_fn(|cx| Poll::Ready(inner.borrow_mut().acquire(&key, cx))).await {
-                Acquire::Acquired(io, created) => {
+                Acquire::Acquired(io, created, stream) => {
                     // use existing connection
                     return Ok(IoConnection::new(
                         io,
                         created,
-                        Some(Acquired(key, Some(inner))),
+                        Some(Acquired(key, Some(inner), stream)),
                     ));
                 }
                 Acquire::Available => {
@@ -139,24 +246,55 @@ where
                     } else {
                         let (snd, connection) = handshake(io).await?;
                         actori_rt::spawn(connection.map(|_| ()));
+                        let mut acquired = guard.consume();
+                        acquired.register_h2(&snd);
                         Ok(IoConnection::new(
                             ConnectionType::H2(snd),
                             Instant::now(),
-                            Some(guard.consume()),
+                            Some(acquired),
                         ))
                     }
                 }
                 _ => {
                     // connection is not available, wait
-                    let (rx, token) = inner.borrow_mut().wait_for(req);
+                    let (rx, token, wait) = {
+                        let mut inner = inner.borrow_mut();
+                        let wait = inner.wait.clone();
+                        if wait.max_waiters > 0
+                            && inner.waiters_queue.len() >= wait.max_waiters
+                        {
+                            wait.metrics.wait_rejected();
+                            return Err(ConnectError::PoolOverloaded);
+                        }
+                        let (rx, token) = inner.wait_for(req);
+                        (rx, token, wait)
+                    };
 
                     let guard = WaiterGuard::new(key, token, inner);
-                    let res = match rx.await {
-                        Err(_) => Err(ConnectError::Disconnected),
-                        Ok(res) => res,
+                    let start = Instant::now();
+                    wait.metrics.wait_started();
+
+                    let res = if let Some(timeout) = wait.timeout {
+                        match select(rx, delay_for(timeout)).await {
+                            Either::Left((res, _)) => Some(res),
+                            Either::Right(_) => None,
+                        }
+                    } else {
+                        Some(rx.await)
                     };
-                    guard.consume();
-                    res
+                    wait.metrics.wait_finished(start.elapsed(), res.is_none());
+
+                    match res {
+                        Some(res) => {
+                            guard.consume();
+                            match res {
+                                Err(_) => Err(ConnectError::Disconnected),
+                                Ok(res) => res,
+                            }
+                        }
+                        // guard releases waiter
+                        None => Err(ConnectError::PoolTimeout),
+                    }
                 }
             }
         };
@@ -224,7 +362,7 @@ where
     }
 
     fn consume(mut self) -> Acquired<Io> {
-        Acquired(self.key.clone(), self.inner.take())
+        Acquired(self.key.clone(), self.inner.take(), None)
     }
 }
 
@@ -242,7 +380,7 @@ where
 }
 
 enum Acquire<T> {
-    Acquired(ConnectionType<T>, Instant),
+    Acquired(ConnectionType<T>, Instant, Option<H2Stream>),
     Available,
     NotAvailable,
 }
@@ -253,13 +391,67 @@ struct AvailableConnection<Io> {
     created: Instant,
 }
 
+/// Open h2 connection, it is shared by concurrent requests
+struct H2Connection {
+    snd: SendRequest<Bytes>,
+    streams: Rc<H2Streams>,
+    used: Instant,
+    created: Instant,
+}
+
+/// Streams of h2 connection
+struct H2Streams {
+    active: Cell<usize>,
+    /// server's max concurrent streams, it is known once limit is reached
+    limit: Cell<usize>,
+}
+
+impl H2Streams {
+    fn new() -> Rc<Self> {
+        Rc::new(H2Streams {
+            active: Cell::new(0),
+            limit: Cell::new(usize::max_value()),
+        })
+    }
+
+    fn is_available(&self, max_streams: usize) -> bool {
+        self.active.get() < cmp::min(self.limit.get(), max_streams)
+    }
+}
+
+/// Request stream of h2 connection, stream is closed on drop
+pub(crate) struct H2Stream(Rc<H2Streams>);
+
+impl H2Stream {
+    fn open(streams: &Rc<H2Streams>) -> Self {
+        streams.active.set(streams.active.get() + 1);
+        H2Stream(streams.clone())
+    }
+
+    /// Stream could not be opened immediately, server's concurrent
+    /// streams limit is reached
+    pub(crate) fn saturated(&self) {
+        let limit = cmp::max(self.0.active.get() - 1, 1);
+        self.0.limit.set(cmp::min(self.0.limit.get(), limit));
+    }
+}
+
+impl Drop for H2Stream {
+    fn drop(&mut self) {
+        self.0.active.set(self.0.active.get() - 1);
+    }
+}
+
 pub(crate) struct Inner<Io> {
     conn_lifetime: Duration,
     conn_keep_alive: Duration,
     disconnect_timeout: Option<Duration>,
     limit: usize,
+    h2_max_streams: usize,
+    wait: WaitConfig,
     acquired: usize,
     available: FxHashMap<Key, VecDeque<AvailableConnection<Io>>>,
+    h2: FxHashMap<Key, VecDeque<H2Connection>>,
     waiters: Slab<
         Option<(
             Connect,
@@ -299,7 +491,7 @@ where
     ) {
         let (tx, rx) = oneshot::channel();
 
-        let key: Key = connect.uri.authority().unwrap().clone().into();
+        let key = Key::new(&connect).unwrap();
         let entry = self.waiters.vacant_entry();
         let token = entry.key();
         entry.insert(Some((connect, tx)));
@@ -316,6 +508,36 @@ where
 
         self.reserve();
 
+        // multiplex request over open h2 connection, connections
+        // are used in round-robin order
+        if let Some(ref mut connections) = self.h2.get_mut(key) {
+            let now = Instant::now();
+            for _ in 0..connections.len() {
+                let mut conn = connections.pop_front().unwrap();
+
+                // drop expired and closed connections
+                let idle = conn.streams.active.get() == 0;
+                if (idle && (now - conn.used) > self.conn_keep_alive)
+                    || (now - conn.created) > self.conn_lifetime
+                {
+                    continue;
+                }
+                if let Poll::Ready(Err(_)) = conn.snd.poll_ready(cx) {
+                    continue;
+                }
+
+                if conn.streams.is_available(self.h2_max_streams) {
+                    let io = ConnectionType::H2(conn.snd.clone());
+                    let stream = H2Stream::open(&conn.streams);
+                    let created = conn.created;
+                    conn.used = now;
+                    connections.push_back(conn);
+                    return Acquire::Acquired(io, created, Some(stream));
+                }
+                connections.push_back(conn);
+            }
+        }
+
         // check if open connection is available
         // cleanup stale connections at the same time
         if let Some(ref mut connections) = self.available.get_mut(key) {
@@ -349,15 +571,38 @@ where
                             _ => continue,
                         }
                     }
-                    return Acquire::Acquired(io, conn.created);
+                    return Acquire::Acquired(io, conn.created, None);
                 }
             }
         }
         Acquire::Available
     }
 
+    fn register_h2(&mut self, key: &Key, snd: SendRequest<Bytes>) -> H2Stream {
+        let streams = H2Streams::new();
+        let stream = H2Stream::open(&streams);
+        let now = Instant::now();
+        self.h2
+            .entry(key.clone())
+            .or_insert_with(VecDeque::new)
+            .push_back(H2Connection {
+                snd,
+                streams,
+                used: now,
+                created: now,
+            });
+        stream
+    }
+
     fn release_conn(&mut self, key: &Key, io: ConnectionType<Io>, created: Instant) {
         self.acquired -= 1;
+
+        // h2 connections stay in the pool while they are open
+        if let ConnectionType::H2(_) = io {
+            self.check_availibility();
+            return;
+        }
+
         self.available
             .entry(key.clone())
             .or_insert_with(VecDeque::new)
@@ -459,12 +704,12 @@ where
 
             match inner.acquire(&key, cx) {
                 Acquire::NotAvailable => break,
-                Acquire::Acquired(io, created) => {
+                Acquire::Acquired(io, created, stream) => {
                     let tx = inner.waiters.get_mut(token).unwrap().take().unwrap().1;
                     if let Err(conn) = tx.send(Ok(IoConnection::new(
                         io,
                         created,
-                        Some(Acquired(key.clone(), Some(this.inner.clone()))),
+                        Some(Acquired(key.clone(), Some(this.inner.clone()), stream)),
                     ))) {
                         let (io, created) = conn.unwrap().into_inner();
                         inner.release_conn(&key, io, created);
@@ -552,11 +797,14 @@ where
             return match Pin::new(h2).poll(cx) {
                 Poll::Ready(Ok((snd, connection))) => {
                     actori_rt::spawn(connection.map(|_| ()));
+                    let mut acquired =
+                        Acquired(this.key.clone(), this.inner.take(), None);
+                    acquired.register_h2(&snd);
                     let rx = this.rx.take().unwrap();
                     let _ = rx.send(Ok(IoConnection::new(
                         ConnectionType::H2(snd),
                         Instant::now(),
-                        Some(Acquired(this.key.clone(), this.inner.take())),
+                        Some(acquired),
                     )));
                     Poll::Ready(())
                 }
@@ -585,7 +833,7 @@ where
                     let _ = rx.send(Ok(IoConnection::new(
                         ConnectionType::H1(io),
                         Instant::now(),
-                        Some(Acquired(this.key.clone(), this.inner.take())),
+                        Some(Acquired(this.key.clone(), this.inner.take(), None)),
                     )));
                     Poll::Ready(())
                 } else {
@@ -598,12 +846,26 @@ where
     }
 }
 
-pub(crate) struct Acquired<T>(Key, Option<Rc<RefCell<Inner<T>>>>);
+pub(crate) struct Acquired<T>(Key, Option<Rc<RefCell<Inner<T>>>>, Option<H2Stream>);
 
 impl<T> Acquired<T>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
+    /// Add new h2 connection to the pool, so it could be shared
+    /// with concurrent requests
+    fn register_h2(&mut self, snd: &SendRequest<Bytes>) {
+        if let Some(ref inner) = self.1 {
+            let stream = inner.borrow_mut().register_h2(&self.0, snd.clone());
+            self.2 = Some(stream);
+        }
+    }
+
+    /// Take h2 stream, stream is open until it is dropped
+    pub(crate) fn take_stream(&mut self) -> Option<H2Stream> {
+        self.2.take()
+    }
+
     pub(crate) fn close(&mut self, conn: IoConnection<T>) {
         if let Some(inner) = self.1.take() {
             let (io, _) = conn.into_inner();
@@ -628,3 +890,29 @@ impl<T> Drop for Acquired<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_h2_streams() {
+        let streams = H2Streams::new();
+        assert!(streams.is_available(2));
+
+        let s1 = H2Stream::open(&streams);
+        let s2 = H2Stream::open(&streams);
+        assert!(!streams.is_available(2));
+        assert!(streams.is_available(3));
+
+        // server limit is reached on the second stream
+        s2.saturated();
+        assert!(!streams.is_available(3));
+
+        drop(s2);
+        assert!(!streams.is_available(3));
+        drop(s1);
+        assert_eq!(streams.active.get(), 0);
+        assert!(streams.is_available(3));
+    }
+}
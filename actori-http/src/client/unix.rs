@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actori_connect::{
+    Connect as TcpConnect, ConnectError as TcpConnectError, Connection as TcpConnection,
+};
+use actori_rt::net::UnixStream;
+use actori_service::Service;
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use http::Uri;
+
+/// Unix domain socket connector.
+///
+/// Every connection is opened to the same socket, request uri is used
+/// for `Host` header and for connection pooling only.
+///
+/// ```rust,ignore
+/// use actori_http::client::Connector;
+///
+/// let connector = Connector::new()
+///      .unix_socket("/run/app.sock")
+///      .finish();
+/// ```
+#[derive(Clone, Debug)]
+pub struct UnixConnector {
+    path: Rc<PathBuf>,
+}
+
+impl UnixConnector {
+    /// Create connector to the socket at the path
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        UnixConnector {
+            path: Rc::new(path.as_ref().to_path_buf()),
+        }
+    }
+}
+
+impl Service for UnixConnector {
+    type Request = TcpConnect<Uri>;
+    type Response = TcpConnection<Uri, UnixStream>;
+    type Error = TcpConnectError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: TcpConnect<Uri>) -> Self::Future {
+        let path = self.path.clone();
+        let uri = Uri::builder()
+            .scheme("http")
+            .authority(format!("{}:{}", req.host(), req.port()).as_str())
+            .path_and_query("/")
+            .build()
+            .unwrap_or_default();
+
+        async move {
+            log::trace!("Unix socket connect to {:?} for {}", path, uri);
+            let io = UnixStream::connect(path.as_path()).await?;
+            Ok(TcpConnection::new(io, Box::new(uri)))
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actori_rt::test]
+    async fn test_unix_connect() {
+        use actori_rt::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!(
+            "actori-http-unix-connect-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let mut lst = UnixListener::bind(&path).unwrap();
+
+        let mut connector = UnixConnector::new(&path);
+        let (conn, _) = futures_util::future::join(
+            connector.call(TcpConnect::new(Uri::from_static("http://localhost/"))),
+            lst.accept(),
+        )
+        .await;
+        let (_, uri) = conn.unwrap().into_parts();
+        assert_eq!(uri.host(), Some("localhost"));
+
+        let _ = std::fs::remove_file(&path);
+        let mut connector = UnixConnector::new(&path);
+        assert!(connector
+            .call(TcpConnect::new(Uri::from_static("http://localhost/")))
+            .await
+            .is_err());
+    }
+}
@@ -7,11 +7,20 @@ use std::{fmt, net};
 use actori_rt::time::{delay_for, delay_until, Delay, Instant};
 use bytes::BytesMut;
 use futures_util::{future, FutureExt};
+use http::header::HeaderValue;
 use time;
 
+use crate::conn::{ListenerName, LocalAddr, PeerAddr};
+use crate::extensions::Extensions;
+use crate::metrics::Metrics;
+
 // "Sun, 06 Nov 1994 08:49:37 GMT".len()
 const DATE_VALUE_LENGTH: usize = 29;
 
+/// Default `Allow` header value of the response for `OPTIONS *` requests
+pub const DEFAULT_OPTIONS_ALLOW: &str =
+    "OPTIONS, GET, HEAD, POST, PUT, PATCH, DELETE";
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 /// Server keep-alive setting
 pub enum KeepAlive {
@@ -39,6 +48,63 @@ impl From<Option<usize>> for KeepAlive {
     }
 }
 
+/// Http/2 streams protection settings
+#[derive(Debug, Clone, Default)]
+pub(crate) struct H2Limits {
+    /// Max time to process a stream, including sending of the response
+    pub(crate) max_stream_duration: Option<Duration>,
+    /// Max time a stream could stay without sending anything
+    pub(crate) stream_idle_timeout: Option<Duration>,
+    /// Max number of locally reset streams remembered by connection
+    pub(crate) max_concurrent_reset_streams: Option<usize>,
+    /// Max number of streams opened per connection within a period
+    pub(crate) stream_rate: Option<(u32, Duration)>,
+}
+
+/// Tls handshake protection settings
+#[derive(Debug, Clone)]
+#[cfg_attr(not(any(feature = "openssl", feature = "rustls")), allow(dead_code))]
+pub(crate) struct TlsLimits {
+    /// Max time to complete handshake in milliseconds, zero means no limit
+    pub(crate) handshake_timeout: u64,
+    /// Max number of concurrent handshakes, zero means no limit
+    pub(crate) max_handshakes: usize,
+}
+
+impl Default for TlsLimits {
+    fn default() -> Self {
+        TlsLimits {
+            handshake_timeout: 3000,
+            max_handshakes: 0,
+        }
+    }
+}
+
+/// Request payload limits
+#[derive(Debug, Clone)]
+pub(crate) struct PayloadLimits {
+    /// Max size of request payload, zero means no limit
+    pub(crate) max_size: u64,
+    /// Max size of unread payload that is discarded after early response
+    pub(crate) drain_size: u64,
+}
+
+impl PayloadLimits {
+    /// Returns true if payload of this size is not allowed
+    pub(crate) fn overflows(&self, size: u64) -> bool {
+        self.max_size != 0 && size > self.max_size
+    }
+}
+
+impl Default for PayloadLimits {
+    fn default() -> Self {
+        PayloadLimits {
+            max_size: 0,
+            drain_size: 65_536,
+        }
+    }
+}
+
 /// Http service configuration
 pub struct ServiceConfig(Rc<Inner>);
 
@@ -49,6 +115,14 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    listener_name: Option<String>,
+    options_allow: Option<HeaderValue>,
+    h2_limits: H2Limits,
+    payload_limits: PayloadLimits,
+    tls_limits: TlsLimits,
+    metrics: Option<Rc<dyn Metrics>>,
+    close_on_error: bool,
+    write_batch: Option<Duration>,
     timer: DateService,
 }
 
@@ -91,10 +165,105 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
+            listener_name: None,
+            options_allow: Some(HeaderValue::from_static(DEFAULT_OPTIONS_ALLOW)),
+            h2_limits: H2Limits::default(),
+            payload_limits: PayloadLimits::default(),
+            tls_limits: TlsLimits::default(),
+            metrics: None,
+            close_on_error: false,
+            write_batch: None,
             timer: DateService::new(),
         }))
     }
 
+    /// Set `Allow` header value of the response for server-wide `OPTIONS *` requests.
+    ///
+    /// `OPTIONS` requests with asterisk-form request target are answered by
+    /// dispatcher directly, such requests never reach the service. If value
+    /// is `None` dispatcher forwards these requests to the service.
+    ///
+    /// By default `Allow` header is set to
+    /// `OPTIONS, GET, HEAD, POST, PUT, PATCH, DELETE`.
+    ///
+    /// This method panics if configuration is already shared.
+    pub fn with_options_allow(mut self, allow: Option<HeaderValue>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .options_allow = allow;
+        self
+    }
+
+    /// Set name of the listener this service is bound to.
+    ///
+    /// This method panics if configuration is already shared.
+    pub fn with_listener_name(mut self, name: Option<String>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .listener_name = name;
+        self
+    }
+
+    /// Set http/2 streams protection settings.
+    ///
+    /// This method panics if configuration is already shared.
+    pub(crate) fn with_h2_limits(mut self, limits: H2Limits) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .h2_limits = limits;
+        self
+    }
+
+    /// Set request payload limits.
+    ///
+    /// This method panics if configuration is already shared.
+    pub(crate) fn with_payload_limits(mut self, limits: PayloadLimits) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .payload_limits = limits;
+        self
+    }
+
+    /// Set tls handshake protection settings.
+    ///
+    /// This method panics if configuration is already shared.
+    pub(crate) fn with_tls_limits(mut self, limits: TlsLimits) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .tls_limits = limits;
+        self
+    }
+
+    /// Set connection metrics hooks.
+    ///
+    /// This method panics if configuration is already shared.
+    pub fn with_metrics(mut self, metrics: Option<Rc<dyn Metrics>>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .metrics = metrics;
+        self
+    }
+
+    /// Close http/1 connections after server error responses.
+    ///
+    /// This method panics if configuration is already shared.
+    pub fn with_close_on_error(mut self, close: bool) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .close_on_error = close;
+        self
+    }
+
+    /// Set max delay of batched http/1 response payload writes.
+    ///
+    /// This method panics if configuration is already shared.
+    pub(crate) fn with_write_batch(mut self, delay: Option<Duration>) -> Self {
+        Rc::get_mut(&mut self.0)
+            .expect("ServiceConfig is already in use")
+            .write_batch = delay;
+        self
+    }
+
     #[inline]
     /// Returns true if connection is secure(https)
     pub fn secure(&self) -> bool {
@@ -107,6 +276,72 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    #[inline]
+    /// Returns name of the listener that this server is bound to.
+    pub fn listener_name(&self) -> Option<&str> {
+        self.0.listener_name.as_ref().map(|name| name.as_str())
+    }
+
+    /// Create connection data for a new connection
+    pub(crate) fn conn_data(
+        &self,
+        peer_addr: Option<net::SocketAddr>,
+    ) -> Rc<Extensions> {
+        let mut data = Extensions::new();
+        if let Some(addr) = peer_addr {
+            data.insert(PeerAddr(addr));
+        }
+        if let Some(addr) = self.0.local_addr {
+            data.insert(LocalAddr(addr));
+        }
+        if let Some(ref name) = self.0.listener_name {
+            data.insert(ListenerName(name.clone()));
+        }
+        Rc::new(data)
+    }
+
+    #[inline]
+    /// `Allow` header value for server-wide `OPTIONS *` requests if configured.
+    pub fn options_allow(&self) -> Option<&HeaderValue> {
+        self.0.options_allow.as_ref()
+    }
+
+    #[inline]
+    /// Returns true if http/1 connections are closed after server error responses.
+    pub fn close_on_error(&self) -> bool {
+        self.0.close_on_error
+    }
+
+    #[inline]
+    /// Max delay of batched http/1 response payload writes, if enabled.
+    pub(crate) fn write_batch(&self) -> Option<Duration> {
+        self.0.write_batch
+    }
+
+    #[inline]
+    /// Http/2 streams protection settings.
+    pub(crate) fn h2_limits(&self) -> &H2Limits {
+        &self.0.h2_limits
+    }
+
+    #[inline]
+    pub(crate) fn payload_limits(&self) -> &PayloadLimits {
+        &self.0.payload_limits
+    }
+
+    #[inline]
+    #[cfg(any(feature = "openssl", feature = "rustls"))]
+    /// Tls handshake protection settings.
+    pub(crate) fn tls_limits(&self) -> &TlsLimits {
+        &self.0.tls_limits
+    }
+
+    #[inline]
+    /// Connection metrics hooks if configured.
+    pub fn metrics(&self) -> Option<&Rc<dyn Metrics>> {
+        self.0.metrics.as_ref()
+    }
+
     #[inline]
     /// Keep alive duration if configured.
     pub fn keep_alive(&self) -> Option<Duration> {
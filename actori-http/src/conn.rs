@@ -0,0 +1,55 @@
+//! Connection data
+use std::net;
+use std::rc::Rc;
+
+/// Peer address of the connection.
+///
+/// Stored in connection data, check `Request::conn_data()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerAddr(pub net::SocketAddr);
+
+/// Local address the connection is accepted on.
+///
+/// Stored in connection data if local address is configured with
+/// `HttpServiceBuilder::local_addr()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalAddr(pub net::SocketAddr);
+
+/// Name of the listener that accepted the connection.
+///
+/// Stored in connection data if listener name is configured with
+/// `HttpServiceBuilder::listener_name()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerName(pub String);
+
+impl ListenerName {
+    /// Listener name as string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Certificate chain presented by tls client, DER encoded.
+///
+/// Stored in request extensions by `HttpService::openssl()` and
+/// `HttpService::rustls()` if client certificate is verified, acceptor
+/// has to be configured to request client certificates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificates(Rc<Vec<Vec<u8>>>);
+
+impl PeerCertificates {
+    /// Create certificate chain, leaf certificate goes first
+    pub fn new(chain: Vec<Vec<u8>>) -> Self {
+        PeerCertificates(Rc::new(chain))
+    }
+
+    /// Client's own certificate
+    pub fn leaf(&self) -> &[u8] {
+        self.0.first().map(|cert| cert.as_slice()).unwrap_or(&[])
+    }
+
+    /// Full certificate chain, leaf certificate goes first
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.0
+    }
+}
@@ -9,9 +9,11 @@ use brotli2::write::BrotliEncoder;
 use bytes::Bytes;
 use flate2::write::{GzEncoder, ZlibEncoder};
 use futures_core::ready;
+#[cfg(feature = "compress-zstd")]
+use zstd::stream::write::Encoder as ZstdEncoder;
 
 use crate::body::{Body, BodySize, MessageBody, ResponseBody};
-use crate::http::header::{ContentEncoding, CONTENT_ENCODING};
+use crate::http::header::{ContentEncoding, CONTENT_ENCODING, CONTENT_LENGTH};
 use crate::http::{HeaderValue, StatusCode};
 use crate::{Error, ResponseHead};
 
@@ -165,6 +167,8 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
 }
 
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
+    // encoded payload size is not known
+    head.headers_mut().remove(CONTENT_LENGTH);
     head.headers_mut().insert(
         CONTENT_ENCODING,
         HeaderValue::from_static(encoding.as_str()),
@@ -175,6 +179,8 @@ enum ContentEncoder {
     Deflate(ZlibEncoder<Writer>),
     Gzip(GzEncoder<Writer>),
     Br(BrotliEncoder<Writer>),
+    #[cfg(feature = "compress-zstd")]
+    Zstd(ZstdEncoder<Writer>),
 }
 
 impl ContentEncoder {
@@ -191,6 +197,10 @@ impl ContentEncoder {
             ContentEncoding::Br => {
                 Some(ContentEncoder::Br(BrotliEncoder::new(Writer::new(), 3)))
             }
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoding::Zstd => ZstdEncoder::new(Writer::new(), 3)
+                .ok()
+                .map(ContentEncoder::Zstd),
             _ => None,
         }
     }
@@ -201,6 +211,8 @@ impl ContentEncoder {
             ContentEncoder::Br(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Deflate(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Gzip(ref mut encoder) => encoder.get_mut().take(),
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => encoder.get_mut().take(),
         }
     }
 
@@ -218,6 +230,11 @@ impl ContentEncoder {
                 Ok(writer) => Ok(writer.buf.freeze()),
                 Err(err) => Err(err),
             },
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoder::Zstd(encoder) => match encoder.finish() {
+                Ok(writer) => Ok(writer.buf.freeze()),
+                Err(err) => Err(err),
+            },
         }
     }
 
@@ -244,6 +261,14 @@ impl ContentEncoder {
                     Err(err)
                 }
             },
+            #[cfg(feature = "compress-zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => match encoder.write_all(data) {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    trace!("Error decoding zstd encoding: {}", err);
+                    Err(err)
+                }
+            },
         }
     }
 }
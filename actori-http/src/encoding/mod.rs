@@ -5,9 +5,11 @@ use bytes::{Bytes, BytesMut};
 
 mod decoder;
 mod encoder;
+mod negotiate;
 
 pub use self::decoder::Decoder;
 pub use self::encoder::Encoder;
+pub use self::negotiate::negotiate;
 
 pub(self) struct Writer {
     buf: BytesMut,
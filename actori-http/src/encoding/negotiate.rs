@@ -0,0 +1,166 @@
+//! `Accept-Encoding` negotiation
+use crate::http::header::{ContentEncoding, ACCEPT_ENCODING};
+use crate::http::HeaderMap;
+
+/// Server preference of encodings for `ContentEncoding::Auto`
+#[cfg(not(feature = "compress-zstd"))]
+const AUTO: &[ContentEncoding] = &[
+    ContentEncoding::Br,
+    ContentEncoding::Gzip,
+    ContentEncoding::Deflate,
+];
+#[cfg(feature = "compress-zstd")]
+const AUTO: &[ContentEncoding] = &[
+    ContentEncoding::Zstd,
+    ContentEncoding::Br,
+    ContentEncoding::Gzip,
+    ContentEncoding::Deflate,
+];
+
+/// Select content encoding of the response to the request with `headers`.
+///
+/// Quality values of `Accept-Encoding` headers are honored. Encodings with
+/// `q=0` are not acceptable, as well as encodings that are not listed,
+/// unless `*` allows them. For `ContentEncoding::Auto` encoding with the
+/// highest quality is selected, ties are resolved by server preference:
+/// `zstd` (with `compress-zstd` feature), `br`, `gzip`, `deflate`. Specific
+/// encoding is selected only if the client accepts it.
+/// `ContentEncoding::Identity` is returned if no encoding is acceptable.
+pub fn negotiate(headers: &HeaderMap, encoding: ContentEncoding) -> ContentEncoding {
+    let mut accepted = Vec::new();
+    for val in headers.get_all(ACCEPT_ENCODING) {
+        if let Ok(val) = val.to_str() {
+            accepted.extend(val.split(',').filter_map(parse_item));
+        }
+    }
+
+    match encoding {
+        ContentEncoding::Auto => {
+            let mut selected = ContentEncoding::Identity;
+            let mut selected_q = 0;
+            for enc in AUTO.iter() {
+                let q = quality(&accepted, *enc);
+                if q > selected_q {
+                    selected = *enc;
+                    selected_q = q;
+                }
+            }
+            selected
+        }
+        ContentEncoding::Identity => ContentEncoding::Identity,
+        enc => {
+            if quality(&accepted, enc) > 0 {
+                enc
+            } else {
+                ContentEncoding::Identity
+            }
+        }
+    }
+}
+
+/// Quality of the encoding in thousandths
+fn quality(accepted: &[(&str, u16)], encoding: ContentEncoding) -> u16 {
+    let mut wildcard = 0;
+    for (coding, q) in accepted {
+        if coding.eq_ignore_ascii_case(encoding.as_str()) {
+            return *q;
+        } else if *coding == "*" {
+            wildcard = *q;
+        }
+    }
+    wildcard
+}
+
+/// Parse `coding;q=0.5` item, items with invalid quality are ignored
+fn parse_item(item: &str) -> Option<(&str, u16)> {
+    let mut parts = item.split(';');
+    let coding = parts.next()?.trim();
+    if coding.is_empty() {
+        return None;
+    }
+
+    let mut q = 1000;
+    for param in parts {
+        let param = param.trim();
+        if param.len() > 2 && param[..2].eq_ignore_ascii_case("q=") {
+            q = parse_qvalue(&param[2..])?;
+        }
+    }
+    Some((coding, q))
+}
+
+/// Parse quality value, `0.5` is 500
+fn parse_qvalue(s: &str) -> Option<u16> {
+    let mut parts = s.splitn(2, '.');
+    let int = match parts.next()? {
+        "0" => 0,
+        "1" => 1000,
+        _ => return None,
+    };
+    let frac = parts.next().unwrap_or("");
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut val = 0;
+    for (idx, b) in frac.bytes().enumerate() {
+        val += u16::from(b - b'0') * [100, 10, 1][idx];
+    }
+    if int + val > 1000 {
+        None
+    } else {
+        Some(int + val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HeaderValue;
+
+    fn headers(val: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(val));
+        headers
+    }
+
+    #[test]
+    fn test_negotiate() {
+        use ContentEncoding::*;
+
+        assert_eq!(negotiate(&HeaderMap::new(), Auto), Identity);
+        assert_eq!(negotiate(&headers("gzip, deflate, br"), Auto), Br);
+        assert_eq!(negotiate(&headers("gzip;q=1.0, br;q=0.8"), Auto), Gzip);
+        assert_eq!(negotiate(&headers("br;q=0, GZIP;q=0.5"), Auto), Gzip);
+        assert_eq!(negotiate(&headers("deflate, *;q=0.5"), Auto), Deflate);
+        assert_eq!(negotiate(&headers("br;q=0, zstd;q=0, *"), Auto), Gzip);
+        assert_eq!(negotiate(&headers("identity"), Auto), Identity);
+        assert_eq!(negotiate(&headers("gzip;q=0"), Auto), Identity);
+        assert_eq!(negotiate(&headers("gzip;q=2, deflate"), Auto), Deflate);
+
+        assert_eq!(negotiate(&headers("gzip, br"), Gzip), Gzip);
+        assert_eq!(negotiate(&headers("br"), Gzip), Identity);
+        assert_eq!(negotiate(&headers("*;q=0.1"), Deflate), Deflate);
+        assert_eq!(negotiate(&headers("gzip"), Identity), Identity);
+        assert_eq!(negotiate(&headers("zstd, gzip"), Zstd), Zstd);
+
+        #[cfg(not(feature = "compress-zstd"))]
+        assert_eq!(negotiate(&headers("*"), Auto), Br);
+        #[cfg(feature = "compress-zstd")]
+        {
+            assert_eq!(negotiate(&headers("*"), Auto), Zstd);
+            assert_eq!(negotiate(&headers("br, zstd;q=0.9"), Auto), Br);
+        }
+    }
+
+    #[test]
+    fn test_parse_qvalue() {
+        assert_eq!(parse_qvalue("1"), Some(1000));
+        assert_eq!(parse_qvalue("1.000"), Some(1000));
+        assert_eq!(parse_qvalue("0.5"), Some(500));
+        assert_eq!(parse_qvalue("0.05"), Some(50));
+        assert_eq!(parse_qvalue("0"), Some(0));
+        assert_eq!(parse_qvalue("1.1"), None);
+        assert_eq!(parse_qvalue("0.5555"), None);
+        assert_eq!(parse_qvalue("abc"), None);
+    }
+}
@@ -21,7 +21,7 @@ use serde_json::error::Error as JsonError;
 use serde_urlencoded::ser::Error as FormError;
 
 // re-export for convinience
-use crate::body::Body;
+use crate::body::{Body, ResponseBody};
 pub use crate::cookie::ParseError as CookieParseError;
 use crate::helpers::Writer;
 use crate::response::{Response, ResponseBuilder};
@@ -58,6 +58,17 @@ impl Error {
     pub fn as_error<T: ResponseError + 'static>(&self) -> Option<&T> {
         ResponseError::downcast_ref(self.cause.as_ref())
     }
+
+    /// Returns type name of the underlying `ResponseError`.
+    pub fn type_name(&self) -> &'static str {
+        self.cause.__private_get_type_name__()
+    }
+
+    /// Returns true if error is a complete response, i.e. it is
+    /// converted from `Response` or `ResponseBuilder`.
+    pub fn is_response(&self) -> bool {
+        self.as_error::<EarlyResponse>().is_some()
+    }
 }
 
 /// Error that can be converted to `Response`
@@ -90,6 +101,11 @@ pub trait ResponseError: fmt::Debug + fmt::Display {
     {
         TypeId::of::<Self>()
     }
+
+    #[doc(hidden)]
+    fn __private_get_type_name__(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl dyn ResponseError + 'static {
@@ -156,16 +172,74 @@ impl<T: ResponseError + 'static> From<T> for Error {
 }
 
 /// Convert Response to a Error
+///
+/// Response is used as is, it is not treated as error response.
+/// This allows to return complete response as error of handler or
+/// extractor, i.e. `Result<T, Response>`.
 impl From<Response> for Error {
     fn from(res: Response) -> Error {
-        InternalError::from_response("", res).into()
+        EarlyResponse::new(res).into()
     }
 }
 
 /// Convert ResponseBuilder to a Error
 impl From<ResponseBuilder> for Error {
     fn from(mut res: ResponseBuilder) -> Error {
-        InternalError::from_response("", res.finish()).into()
+        EarlyResponse::new(res.finish()).into()
+    }
+}
+
+/// Complete response returned in place of an error.
+///
+/// Head of the response is copied for each `error_response()` call. In-memory
+/// body is copied as well, streaming body is returned by the first call only.
+struct EarlyResponse {
+    head: Response<()>,
+    body: RefCell<Body>,
+}
+
+impl EarlyResponse {
+    fn new(res: Response) -> Self {
+        let (head, body) = res.into_parts();
+        let body = match body {
+            ResponseBody::Body(body) | ResponseBody::Other(body) => body,
+        };
+        EarlyResponse {
+            head,
+            body: RefCell::new(body),
+        }
+    }
+}
+
+impl fmt::Debug for EarlyResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EarlyResponse({})", self.head.status())
+    }
+}
+
+impl fmt::Display for EarlyResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl ResponseError for EarlyResponse {
+    fn status_code(&self) -> StatusCode {
+        self.head.status()
+    }
+
+    fn error_response(&self) -> Response {
+        let mut stored = self.body.borrow_mut();
+        let body = match *stored {
+            Body::None => Body::None,
+            Body::Empty => Body::Empty,
+            Body::Bytes(ref bytes) => Body::Bytes(bytes.clone()),
+            Body::Message(_) => std::mem::replace(&mut *stored, Body::Empty),
+        };
+
+        let mut res = Response::new(self.head.status());
+        self.head.head().copy_to(res.head_mut());
+        res.set_body(body)
     }
 }
 
@@ -1078,6 +1152,59 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[test]
+    fn test_early_response() {
+        let err: Error = Response::Accepted()
+            .insert_header(("x-test", "1"))
+            .finish()
+            .into();
+        assert!(err.is_response());
+        assert_eq!(err.as_response_error().status_code(), StatusCode::ACCEPTED);
+
+        let resp = Response::from_error(err);
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        assert_eq!(resp.headers().get("x-test").unwrap(), "1");
+        assert!(resp.error().is_none());
+
+        let err: Error = Response::BadRequest().into();
+        assert!(err.is_response());
+        let resp: Response = err.into();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(resp.error().is_none());
+
+        let err: Error = ParseError::Method.into();
+        assert!(!err.is_response());
+        assert_eq!(err.type_name(), "actori_http::error::ParseError");
+    }
+
+    #[test]
+    fn test_early_response_reuse() {
+        use crate::body::{BodySize, MessageBody};
+
+        let err: Error = Response::Created()
+            .insert_header(("x-test", "1"))
+            .body("created")
+            .into();
+        for _ in 0..2 {
+            let resp = err.as_response_error().error_response();
+            assert_eq!(resp.status(), StatusCode::CREATED);
+            assert_eq!(resp.headers().get("x-test").unwrap(), "1");
+            assert_eq!(resp.body().get_ref(), b"created");
+        }
+
+        // streaming body is returned once
+        let err: Error = Response::Ok()
+            .streaming(futures_util::stream::once(async {
+                Ok::<_, Error>(bytes::Bytes::from_static(b"stream"))
+            }))
+            .into();
+        let resp = err.as_response_error().error_response();
+        assert_eq!(resp.body().size(), BodySize::Stream);
+        let resp = err.as_response_error().error_response();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.body().size(), BodySize::Empty);
+    }
+
     #[test]
     fn test_error_casting() {
         let err = PayloadError::Overflow;
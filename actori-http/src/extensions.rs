@@ -1,4 +1,4 @@
-use std::any::{Any, TypeId};
+use std::any::{type_name, Any, TypeId};
 use std::fmt;
 
 use fxhash::FxHashMap;
@@ -6,7 +6,7 @@ use fxhash::FxHashMap;
 #[derive(Default)]
 /// A type map of request extensions.
 pub struct Extensions {
-    map: FxHashMap<TypeId, Box<dyn Any>>,
+    map: FxHashMap<TypeId, (&'static str, Box<dyn Any>)>,
 }
 
 impl Extensions {
@@ -23,7 +23,8 @@ impl Extensions {
     /// If a extension of this type already existed, it will
     /// be returned.
     pub fn insert<T: 'static>(&mut self, val: T) {
-        self.map.insert(TypeId::of::<T>(), Box::new(val));
+        self.map
+            .insert(TypeId::of::<T>(), (type_name::<T>(), Box::new(val)));
     }
 
     /// Check if container contains entry
@@ -35,21 +36,21 @@ impl Extensions {
     pub fn get<T: 'static>(&self) -> Option<&T> {
         self.map
             .get(&TypeId::of::<T>())
-            .and_then(|boxed| (&**boxed as &(dyn Any + 'static)).downcast_ref())
+            .and_then(|(_, boxed)| (&**boxed as &(dyn Any + 'static)).downcast_ref())
     }
 
     /// Get a mutable reference to a type previously inserted on this `Extensions`.
     pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
-        self.map
-            .get_mut(&TypeId::of::<T>())
-            .and_then(|boxed| (&mut **boxed as &mut (dyn Any + 'static)).downcast_mut())
+        self.map.get_mut(&TypeId::of::<T>()).and_then(|(_, boxed)| {
+            (&mut **boxed as &mut (dyn Any + 'static)).downcast_mut()
+        })
     }
 
     /// Remove a type from this `Extensions`.
     ///
     /// If a extension of this type existed, it will be returned.
     pub fn remove<T: 'static>(&mut self) -> Option<T> {
-        self.map.remove(&TypeId::of::<T>()).and_then(|boxed| {
+        self.map.remove(&TypeId::of::<T>()).and_then(|(_, boxed)| {
             (boxed as Box<dyn Any + 'static>)
                 .downcast()
                 .ok()
@@ -62,11 +63,200 @@ impl Extensions {
     pub fn clear(&mut self) {
         self.map.clear();
     }
+
+    /// Returns number of stored extensions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if container does not contain any extension.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate over type names of all stored extensions.
+    ///
+    /// Type names are produced by `std::any::type_name()` and are intended
+    /// for diagnostic purposes only.
+    pub fn type_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.map.values().map(|(name, _)| *name)
+    }
+
+    /// Iterate over type ids of all stored extensions.
+    pub fn type_ids(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.map.keys().cloned()
+    }
 }
 
 impl fmt::Debug for Extensions {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Extensions").finish()
+        f.debug_set().entries(self.type_names()).finish()
+    }
+}
+
+/// A string keyed map of extensions.
+///
+/// Complements type keyed `Extensions` for plugins that are developed
+/// separately and identify their data by name. Keys of a plugin could be
+/// namespaced with `namespace()` and `namespace_mut()` to avoid collisions.
+#[derive(Default)]
+pub struct DynExtensions {
+    map: FxHashMap<String, Box<dyn Any>>,
+}
+
+impl DynExtensions {
+    /// Create an empty `DynExtensions`.
+    #[inline]
+    pub fn new() -> DynExtensions {
+        DynExtensions {
+            map: FxHashMap::default(),
+        }
+    }
+
+    /// Insert a value under the key.
+    ///
+    /// Previous value of the key is replaced.
+    pub fn insert<K: Into<String>, T: 'static>(&mut self, key: K, val: T) {
+        self.map.insert(key.into(), Box::new(val));
+    }
+
+    /// Check if container contains entry with the key
+    pub fn contains(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Get a reference to a value of the key, if the value is of type `T`.
+    pub fn get<T: 'static>(&self, key: &str) -> Option<&T> {
+        self.map
+            .get(key)
+            .and_then(|boxed| (&**boxed as &(dyn Any + 'static)).downcast_ref())
+    }
+
+    /// Get a mutable reference to a value of the key, if the value is of type `T`.
+    pub fn get_mut<T: 'static>(&mut self, key: &str) -> Option<&mut T> {
+        self.map
+            .get_mut(key)
+            .and_then(|boxed| (&mut **boxed as &mut (dyn Any + 'static)).downcast_mut())
+    }
+
+    /// Remove a value of the key.
+    ///
+    /// Value is returned if it is of type `T`, otherwise it is dropped.
+    pub fn remove<T: 'static>(&mut self, key: &str) -> Option<T> {
+        self.map.remove(key).and_then(|boxed| {
+            (boxed as Box<dyn Any + 'static>)
+                .downcast()
+                .ok()
+                .map(|boxed| *boxed)
+        })
+    }
+
+    /// Clear the `DynExtensions` of all inserted values.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns number of stored values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if container does not contain any value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate over all keys.
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        self.map.keys().map(|key| key.as_str())
+    }
+
+    /// Read-only view of the namespace, keys are prefixed with `"{ns}::"`.
+    pub fn namespace<'a>(&'a self, ns: &'a str) -> DynNamespace<'a> {
+        DynNamespace { ext: self, ns }
+    }
+
+    /// Mutable view of the namespace, keys are prefixed with `"{ns}::"`.
+    pub fn namespace_mut<'a>(&'a mut self, ns: &'a str) -> DynNamespaceMut<'a> {
+        DynNamespaceMut { ext: self, ns }
+    }
+}
+
+impl fmt::Debug for DynExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.keys()).finish()
+    }
+}
+
+fn ns_key(ns: &str, key: &str) -> String {
+    format!("{}::{}", ns, key)
+}
+
+/// Read-only namespace of `DynExtensions`
+pub struct DynNamespace<'a> {
+    ext: &'a DynExtensions,
+    ns: &'a str,
+}
+
+impl<'a> DynNamespace<'a> {
+    /// Check if namespace contains entry with the key
+    pub fn contains(&self, key: &str) -> bool {
+        self.ext.contains(&ns_key(self.ns, key))
+    }
+
+    /// Get a reference to a value of the key, if the value is of type `T`.
+    pub fn get<T: 'static>(&self, key: &str) -> Option<&'a T> {
+        self.ext.get(&ns_key(self.ns, key))
+    }
+
+    /// Iterate over keys of the namespace, without namespace prefix.
+    pub fn keys(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let ns = self.ns;
+        self.ext.keys().filter_map(move |key| {
+            if key.starts_with(ns) && key[ns.len()..].starts_with("::") {
+                Some(&key[ns.len() + 2..])
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Mutable namespace of `DynExtensions`
+pub struct DynNamespaceMut<'a> {
+    ext: &'a mut DynExtensions,
+    ns: &'a str,
+}
+
+impl<'a> DynNamespaceMut<'a> {
+    /// Insert a value under the key of the namespace.
+    pub fn insert<T: 'static>(&mut self, key: &str, val: T) {
+        self.ext.insert(ns_key(self.ns, key), val);
+    }
+
+    /// Check if namespace contains entry with the key
+    pub fn contains(&self, key: &str) -> bool {
+        self.ext.contains(&ns_key(self.ns, key))
+    }
+
+    /// Get a reference to a value of the key, if the value is of type `T`.
+    pub fn get<T: 'static>(&self, key: &str) -> Option<&T> {
+        self.ext.get(&ns_key(self.ns, key))
+    }
+
+    /// Get a mutable reference to a value of the key, if the value is of type `T`.
+    pub fn get_mut<T: 'static>(&mut self, key: &str) -> Option<&mut T> {
+        self.ext.get_mut(&ns_key(self.ns, key))
+    }
+
+    /// Remove a value of the key of the namespace.
+    pub fn remove<T: 'static>(&mut self, key: &str) -> Option<T> {
+        self.ext.remove(&ns_key(self.ns, key))
     }
 }
 
@@ -89,3 +279,54 @@ fn test_extensions() {
     assert_eq!(extensions.get::<bool>(), None);
     assert_eq!(extensions.get(), Some(&MyType(10)));
 }
+
+#[test]
+fn test_extensions_type_names() {
+    let mut extensions = Extensions::new();
+    assert!(extensions.is_empty());
+
+    extensions.insert(5i32);
+    extensions.insert(String::from("test"));
+    assert_eq!(extensions.len(), 2);
+
+    let mut names: Vec<_> = extensions.type_names().collect();
+    names.sort();
+    assert_eq!(names, vec!["alloc::string::String", "i32"]);
+}
+
+#[test]
+fn test_extensions_type_ids() {
+    let mut extensions = Extensions::new();
+    extensions.insert(5i32);
+
+    let ids: Vec<_> = extensions.type_ids().collect();
+    assert_eq!(ids, vec![TypeId::of::<i32>()]);
+}
+
+#[test]
+fn test_dyn_extensions() {
+    let mut ext = DynExtensions::new();
+    ext.insert("count", 5i32);
+    ext.namespace_mut("plugin")
+        .insert("count", String::from("five"));
+    assert_eq!(ext.len(), 2);
+
+    assert_eq!(ext.get::<i32>("count"), Some(&5));
+    assert_eq!(ext.get::<String>("count"), None);
+    *ext.get_mut::<i32>("count").unwrap() += 1;
+    assert_eq!(ext.get::<i32>("count"), Some(&6));
+    assert_eq!(
+        ext.get::<String>("plugin::count").map(|s| s.as_str()),
+        Some("five")
+    );
+
+    let ns = ext.namespace("plugin");
+    assert!(ns.contains("count"));
+    assert!(!ext.namespace("plugin2").contains("count"));
+    assert_eq!(ns.keys().collect::<Vec<_>>(), vec!["count"]);
+
+    assert_eq!(ext.namespace_mut("plugin").remove::<i32>("count"), None);
+    assert!(!ext.contains("plugin::count"));
+    assert_eq!(ext.remove::<i32>("count"), Some(6));
+    assert!(ext.is_empty());
+}
@@ -0,0 +1,49 @@
+//! HTTP/1 codec hot paths, exposed for benchmarks.
+//!
+//! Available with `bench` feature only. This is not a part of public api,
+//! functions could be changed or removed in any release.
+use std::io;
+
+use actori_codec::Decoder;
+use bytes::BytesMut;
+use http::Version;
+
+use super::decoder::MessageDecoder;
+use super::encoder::{MessageEncoder, TransferEncoding};
+use crate::body::BodySize;
+use crate::config::ServiceConfig;
+use crate::error::ParseError;
+use crate::message::ConnectionType;
+use crate::request::Request;
+use crate::response::Response;
+
+/// Decode request head, same as h1 dispatcher does for each request.
+pub fn decode_request(src: &mut BytesMut) -> Result<Option<Request>, ParseError> {
+    Ok(MessageDecoder::<Request>::default()
+        .decode(src)?
+        .map(|(req, _)| req))
+}
+
+/// Encode response head for a keep-alive HTTP/1.1 connection.
+pub fn encode_response(
+    dst: &mut BytesMut,
+    res: &mut Response<()>,
+    length: BodySize,
+    config: &ServiceConfig,
+) -> io::Result<()> {
+    MessageEncoder::<Response<()>>::default().encode(
+        dst,
+        res,
+        false,
+        false,
+        Version::HTTP_11,
+        length,
+        ConnectionType::KeepAlive,
+        config,
+    )
+}
+
+/// Encode body chunk with chunked transfer encoding.
+pub fn encode_chunk(msg: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+    TransferEncoding::chunked().encode(msg, dst).map(|_| ())
+}
@@ -11,6 +11,8 @@ use super::{Message, MessageType};
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
 use crate::error::{ParseError, PayloadError};
+use crate::header::HeaderMap;
+use crate::helpers;
 use crate::message::{ConnectionType, RequestHeadType, ResponseHead};
 
 bitflags! {
@@ -41,6 +43,7 @@ struct ClientCodecInner {
     // encoder part
     flags: Flags,
     encoder: encoder::MessageEncoder<RequestHeadType>,
+    trailers: Option<HeaderMap>,
 }
 
 impl Default for ClientCodec {
@@ -69,6 +72,7 @@ impl ClientCodec {
 
                 flags,
                 encoder: encoder::MessageEncoder::default(),
+                trailers: None,
             },
         }
     }
@@ -78,6 +82,12 @@ impl ClientCodec {
         self.inner.ctype == ConnectionType::Upgrade
     }
 
+    /// Set trailers of the request payload, trailers are sent with the end
+    /// of chunked payload
+    pub(crate) fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.inner.trailers = Some(trailers);
+    }
+
     /// Check if last response is keep-alive
     pub fn keepalive(&self) -> bool {
         self.inner.ctype == ConnectionType::KeepAlive
@@ -94,6 +104,12 @@ impl ClientCodec {
         }
     }
 
+    /// Send request target in absolute-form, i.e. `GET http://host/path`,
+    /// as required for requests to http proxies
+    pub(crate) fn set_absolute_form(&mut self, enabled: bool) {
+        self.inner.encoder.absolute_form = enabled;
+    }
+
     /// Convert message codec to a payload codec
     pub fn into_payload_codec(self) -> ClientPayloadCodec {
         ClientPayloadCodec { inner: self.inner }
@@ -203,6 +219,21 @@ impl Encoder for ClientCodec {
                     ConnectionType::Close => ConnectionType::Close,
                 };
 
+                // http/1.0 does not support chunked transfer encoding
+                if inner.version < Version::HTTP_11
+                    && length == BodySize::Stream
+                    && helpers::content_length(
+                        encoder::MessageType::headers(&head),
+                        encoder::MessageType::extra_headers(&head),
+                    )
+                    .is_none()
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Streaming request body requires Content-Length in HTTP/1.0",
+                    ));
+                }
+
                 inner.encoder.encode(
                     dst,
                     &mut head,
@@ -217,9 +248,10 @@ impl Encoder for ClientCodec {
             Message::Chunk(Some(bytes)) => {
                 self.inner.encoder.encode_chunk(bytes.as_ref(), dst)?;
             }
-            Message::Chunk(None) => {
-                self.inner.encoder.encode_eof(dst)?;
-            }
+            Message::Chunk(None) => match self.inner.trailers.take() {
+                Some(trailers) => self.inner.encoder.encode_trailers(&trailers, dst)?,
+                None => self.inner.encoder.encode_eof(dst)?,
+            },
         }
         Ok(())
     }
@@ -11,6 +11,7 @@ use super::{Message, MessageType};
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
 use crate::error::ParseError;
+use crate::header::HeaderMap;
 use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
@@ -20,6 +21,7 @@ bitflags! {
         const HEAD              = 0b0000_0001;
         const KEEPALIVE_ENABLED = 0b0000_0010;
         const STREAM            = 0b0000_0100;
+        const KEEPALIVE_REQUEST = 0b0000_1000;
     }
 }
 
@@ -28,6 +30,7 @@ pub struct Codec {
     config: ServiceConfig,
     decoder: decoder::MessageDecoder<Request>,
     payload: Option<PayloadDecoder>,
+    trailers: Option<HeaderMap>,
     version: Version,
     ctype: ConnectionType,
 
@@ -63,6 +66,7 @@ impl Codec {
             flags,
             decoder: decoder::MessageDecoder::default(),
             payload: None,
+            trailers: None,
             version: Version::HTTP_11,
             ctype: ConnectionType::Close,
             encoder: encoder::MessageEncoder::default(),
@@ -81,6 +85,12 @@ impl Codec {
         self.ctype == ConnectionType::KeepAlive
     }
 
+    #[inline]
+    /// Check if last request is `HEAD` request
+    pub fn head(&self) -> bool {
+        self.flags.contains(Flags::HEAD)
+    }
+
     #[inline]
     /// Check if keep-alive enabled on server level
     pub fn keepalive_enabled(&self) -> bool {
@@ -103,6 +113,11 @@ impl Codec {
     pub fn config(&self) -> &ServiceConfig {
         &self.config
     }
+
+    /// Trailers of the last request payload
+    pub(crate) fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
 }
 
 impl Decoder for Codec {
@@ -114,7 +129,8 @@ impl Decoder for Codec {
             Ok(match self.payload.as_mut().unwrap().decode(src)? {
                 Some(PayloadItem::Chunk(chunk)) => Some(Message::Chunk(Some(chunk))),
                 Some(PayloadItem::Eof) => {
-                    self.payload.take();
+                    self.trailers =
+                        self.payload.take().and_then(|mut pl| pl.take_trailers());
                     Some(Message::Chunk(None))
                 }
                 None => None,
@@ -124,6 +140,10 @@ impl Decoder for Codec {
             self.flags.set(Flags::HEAD, head.method == Method::HEAD);
             self.version = head.version;
             self.ctype = head.connection_type();
+            self.flags.set(
+                Flags::KEEPALIVE_REQUEST,
+                self.ctype == ConnectionType::KeepAlive,
+            );
             if self.ctype == ConnectionType::KeepAlive
                 && !self.flags.contains(Flags::KEEPALIVE_ENABLED)
             {
@@ -158,15 +178,18 @@ impl Encoder for Codec {
                 // set response version
                 res.head_mut().version = self.version;
 
-                // connection status
-                self.ctype = if let Some(ct) = res.head().ctype() {
-                    if ct == ConnectionType::KeepAlive {
-                        self.ctype
-                    } else {
-                        ct
+                // connection status, response keep-alive overrides server
+                // settings but not client's request to close connection
+                self.ctype = match res.head().ctype() {
+                    Some(ConnectionType::KeepAlive) => {
+                        if self.flags.contains(Flags::KEEPALIVE_REQUEST) {
+                            ConnectionType::KeepAlive
+                        } else {
+                            self.ctype
+                        }
                     }
-                } else {
-                    self.ctype
+                    Some(ct) => ct,
+                    None => self.ctype,
                 };
 
                 // encode message
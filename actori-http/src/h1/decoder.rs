@@ -18,6 +18,7 @@ use crate::request::Request;
 
 const MAX_BUFFER_SIZE: usize = 131_072;
 const MAX_HEADERS: usize = 96;
+const MAX_TRAILERS_SIZE: usize = 8192;
 
 /// Incoming messagd decoder
 pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
@@ -357,23 +358,41 @@ pub enum PayloadItem {
 #[derive(Debug, Clone, PartialEq)]
 pub struct PayloadDecoder {
     kind: Kind,
+    trailers: Vec<(HeaderName, HeaderValue)>,
 }
 
 impl PayloadDecoder {
     pub fn length(x: u64) -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Length(x),
+            trailers: Vec::new(),
         }
     }
 
     pub fn chunked() -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Chunked(ChunkedState::Size, 0),
+            trailers: Vec::new(),
         }
     }
 
     pub fn eof() -> PayloadDecoder {
-        PayloadDecoder { kind: Kind::Eof }
+        PayloadDecoder {
+            kind: Kind::Eof,
+            trailers: Vec::new(),
+        }
+    }
+
+    /// Trailer fields of chunked payload, available after the end of payload
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        if self.trailers.is_empty() {
+            return None;
+        }
+        let mut map = HeaderMap::new();
+        for (name, value) in self.trailers.drain(..) {
+            map.append(name, value);
+        }
+        Some(map)
     }
 }
 
@@ -410,8 +429,7 @@ enum ChunkedState {
     Body,
     BodyCr,
     BodyLf,
-    EndCr,
-    EndLf,
+    Trailers,
     End,
 }
 
@@ -445,7 +463,7 @@ impl Decoder for PayloadDecoder {
                 loop {
                     let mut buf = None;
                     // advances the chunked state
-                    *state = match state.step(src, size, &mut buf) {
+                    *state = match state.step(src, size, &mut buf, &mut self.trailers) {
                         Poll::Pending => return Ok(None),
                         Poll::Ready(Ok(state)) => state,
                         Poll::Ready(Err(e)) => return Err(e),
@@ -491,6 +509,7 @@ impl ChunkedState {
         body: &mut BytesMut,
         size: &mut u64,
         buf: &mut Option<Bytes>,
+        trailers: &mut Vec<(HeaderName, HeaderValue)>,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         use self::ChunkedState::*;
         match *self {
@@ -501,8 +520,7 @@ impl ChunkedState {
             Body => ChunkedState::read_body(body, size, buf),
             BodyCr => ChunkedState::read_body_cr(body),
             BodyLf => ChunkedState::read_body_lf(body),
-            EndCr => ChunkedState::read_end_cr(body),
-            EndLf => ChunkedState::read_end_lf(body),
+            Trailers => ChunkedState::read_trailers(body, trailers),
             End => Poll::Ready(Ok(ChunkedState::End)),
         }
     }
@@ -563,7 +581,7 @@ impl ChunkedState {
     ) -> Poll<Result<ChunkedState, io::Error>> {
         match byte!(rdr) {
             b'\n' if *size > 0 => Poll::Ready(Ok(ChunkedState::Body)),
-            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::EndCr)),
+            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::Trailers)),
             _ => Poll::Ready(Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Invalid chunk size LF",
@@ -617,23 +635,57 @@ impl ChunkedState {
             ))),
         }
     }
-    fn read_end_cr(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
-        match byte!(rdr) {
-            b'\r' => Poll::Ready(Ok(ChunkedState::EndLf)),
-            _ => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid chunk end CR",
-            ))),
+
+    /// Read trailer section, it ends with an empty line
+    fn read_trailers(
+        rdr: &mut BytesMut,
+        trailers: &mut Vec<(HeaderName, HeaderValue)>,
+    ) -> Poll<Result<ChunkedState, io::Error>> {
+        if rdr.len() < 2 {
+            return Poll::Pending;
         }
-    }
-    fn read_end_lf(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
-        match byte!(rdr) {
-            b'\n' => Poll::Ready(Ok(ChunkedState::End)),
-            _ => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid chunk end LF",
-            ))),
+        if &rdr[..2] == b"\r\n" {
+            rdr.advance(2);
+            return Poll::Ready(Ok(ChunkedState::End));
         }
+
+        let len = match rdr.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(pos) => pos + 4,
+            None if rdr.len() > MAX_TRAILERS_SIZE => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Chunked trailers are too large",
+                )));
+            }
+            None => return Poll::Pending,
+        };
+        let section = rdr.split_to(len);
+
+        let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let headers = match httparse::parse_headers(&section, &mut parsed) {
+            Ok(httparse::Status::Complete((_, headers))) => headers,
+            _ => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Invalid chunked trailers",
+                )));
+            }
+        };
+        for header in headers {
+            match (
+                HeaderName::from_bytes(header.name.as_bytes()),
+                HeaderValue::from_bytes(header.value),
+            ) {
+                (Ok(name), Ok(value)) => trailers.push((name, value)),
+                _ => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Invalid chunked trailer field",
+                    )));
+                }
+            }
+        }
+        Poll::Ready(Ok(ChunkedState::End))
     }
 }
 
@@ -1176,10 +1228,6 @@ mod tests {
         let msg = pl.decode(&mut buf).unwrap().unwrap();
         assert_eq!(msg.chunk().as_ref(), b"li");
 
-        //trailers
-        //buf.feed_data("test: test\r\n");
-        //not_ready!(reader.parse(&mut buf, &mut readbuf));
-
         buf.extend(b"ne\r\n0\r\n");
         let msg = pl.decode(&mut buf).unwrap().unwrap();
         assert_eq!(msg.chunk().as_ref(), b"ne");
@@ -1189,6 +1237,38 @@ mod tests {
         assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
     }
 
+    #[test]
+    fn test_http_request_chunked_payload_trailers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+
+        let mut reader = MessageDecoder::<Request>::default();
+        let (_, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+
+        buf.extend(b"4\r\ndata\r\n0\r\nchecksum: abc\r\n");
+        let msg = pl.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(msg.chunk().as_ref(), b"data");
+        assert!(pl.decode(&mut buf).unwrap().is_none());
+
+        buf.extend(b"x-test: 1\r\n\r\n");
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+        let trailers = pl.take_trailers().unwrap();
+        assert_eq!(trailers.get("checksum").unwrap(), "abc");
+        assert_eq!(trailers.get("x-test").unwrap(), "1");
+        assert!(pl.take_trailers().is_none());
+
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             transfer-encoding: chunked\r\n\r\n\
+             0\r\ninvalid\r\n\r\n",
+        );
+        let (_, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        assert!(pl.unwrap().decode(&mut buf).is_err());
+    }
+
     #[test]
     fn test_parse_chunked_payload_chunk_extension() {
         let mut buf = BytesMut::from(
@@ -1,14 +1,17 @@
 use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
 use std::{fmt, io, net};
 
 use actori_codec::{AsyncRead, AsyncWrite, Decoder, Encoder, Framed, FramedParts};
-use actori_rt::time::{delay_until, Delay, Instant};
+use actori_rt::time::{delay_for, delay_until, Delay, Instant};
 use actori_service::Service;
 use bitflags::bitflags;
 use bytes::{Buf, BytesMut};
+use futures_util::future::LocalBoxFuture;
+use http::{header, StatusCode};
 use log::{error, trace};
 
 use crate::body::{Body, BodySize, MessageBody, ResponseBody};
@@ -16,13 +19,18 @@ use crate::cloneable::CloneableService;
 use crate::config::ServiceConfig;
 use crate::error::{DispatchError, Error};
 use crate::error::{ParseError, PayloadError};
+use crate::extensions::Extensions;
 use crate::helpers::DataFactory;
 use crate::httpmessage::HttpMessage;
+use crate::message::ConnectionType;
+use crate::metrics::{ConnectionMetrics, RequestMetrics};
 use crate::request::Request;
 use crate::response::Response;
+use crate::Protocol;
 
 use super::codec::Codec;
 use super::payload::{Payload, PayloadSender, PayloadStatus};
+use super::upgraded::{OnUpgrade, Upgraded};
 use super::{Message, MessageType};
 
 const LW_BUFFER_SIZE: usize = 4096;
@@ -30,14 +38,16 @@ const HW_BUFFER_SIZE: usize = 32_768;
 const MAX_PIPELINED_MESSAGES: usize = 16;
 
 bitflags! {
-    pub struct Flags: u8 {
-        const STARTED            = 0b0000_0001;
-        const KEEPALIVE          = 0b0000_0010;
-        const POLLED             = 0b0000_0100;
-        const SHUTDOWN           = 0b0000_1000;
-        const READ_DISCONNECT    = 0b0001_0000;
-        const WRITE_DISCONNECT   = 0b0010_0000;
-        const UPGRADE            = 0b0100_0000;
+    pub struct Flags: u16 {
+        const STARTED            = 0b0000_0000_0001;
+        const KEEPALIVE          = 0b0000_0000_0010;
+        const POLLED             = 0b0000_0000_0100;
+        const SHUTDOWN           = 0b0000_0000_1000;
+        const READ_DISCONNECT    = 0b0000_0001_0000;
+        const WRITE_DISCONNECT   = 0b0000_0010_0000;
+        const UPGRADE            = 0b0000_0100_0000;
+        const DISCARD            = 0b0000_1000_0000;
+        const DRAIN              = 0b0001_0000_0000;
     }
 }
 
@@ -67,6 +77,7 @@ where
 {
     Normal(InnerDispatcher<T, S, B, X, U>),
     Upgrade(U::Future),
+    OnUpgrade(LocalBoxFuture<'static, ()>),
     None,
 }
 
@@ -83,17 +94,24 @@ where
     service: CloneableService<S>,
     expect: CloneableService<X>,
     upgrade: Option<CloneableService<U>>,
+    on_upgrade: Option<OnUpgrade>,
     on_connect: Option<Box<dyn DataFactory>>,
     flags: Flags,
     peer_addr: Option<net::SocketAddr>,
+    conn_data: Rc<Extensions>,
+    metrics: Option<ConnectionMetrics>,
+    requests: VecDeque<RequestMetrics>,
     error: Option<DispatchError>,
 
     state: State<S, B, X>,
     payload: Option<PayloadSender>,
+    payload_size: Option<u64>,
+    payload_read: u64,
     messages: VecDeque<DispatcherMessage>,
 
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    batch_timer: Option<Delay>,
 
     io: T,
     read_buf: BytesMut,
@@ -140,6 +158,14 @@ where
             false
         }
     }
+
+    fn is_send_payload(&self) -> bool {
+        if let State::SendPayload(_) = self {
+            true
+        } else {
+            false
+        }
+    }
 }
 
 enum PollResponse {
@@ -228,11 +254,20 @@ where
         } else {
             (config.now(), None)
         };
+        let conn_data = config.conn_data(peer_addr);
+
+        // bytes of the read buffer are read during protocol detection
+        let metrics = ConnectionMetrics::new(&config, Protocol::Http1, peer_addr);
+        if let Some(ref metrics) = metrics {
+            metrics.handle().bytes_read(read_buf.len());
+        }
 
         Dispatcher {
             inner: DispatcherState::Normal(InnerDispatcher {
                 write_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
                 payload: None,
+                payload_size: None,
+                payload_read: 0,
                 state: State::None,
                 error: None,
                 messages: VecDeque::new(),
@@ -242,11 +277,16 @@ where
                 service,
                 expect,
                 upgrade,
+                on_upgrade: None,
                 on_connect,
                 flags,
                 peer_addr,
+                conn_data,
+                metrics,
+                requests: VecDeque::new(),
                 ka_expire,
                 ka_timer,
+                batch_timer: None,
             }),
         }
     }
@@ -271,7 +311,12 @@ where
         {
             false
         } else if let Some(ref info) = self.payload {
-            info.need_read(cx) == PayloadStatus::Read
+            if self.flags.contains(Flags::DISCARD) {
+                // payload is not needed, read it only if it could be drained
+                self.flags.contains(Flags::DRAIN)
+            } else {
+                info.need_read(cx) == PayloadStatus::Read
+            }
         } else {
             true
         }
@@ -311,6 +356,7 @@ where
                     written += n;
                 }
                 Poll::Pending => {
+                    self.bytes_written(written);
                     if written > 0 {
                         self.write_buf.advance(written);
                     }
@@ -319,6 +365,7 @@ where
                 Poll::Ready(Err(err)) => return Err(DispatchError::Io(err)),
             }
         }
+        self.bytes_written(written);
         if written == self.write_buf.len() {
             unsafe { self.write_buf.set_len(0) }
         } else {
@@ -327,11 +374,103 @@ where
         Ok(false)
     }
 
+    /// Hold small chunks of streaming payload in write buffer, until
+    /// buffer is big enough or batch delay is elapsed
+    fn hold_writes(&mut self, cx: &mut Context<'_>) -> bool {
+        let delay = match self.codec.config().write_batch() {
+            Some(delay) => delay,
+            None => return false,
+        };
+        if !self.state.is_send_payload()
+            || self.write_buf.is_empty()
+            || self.write_buf.len() >= LW_BUFFER_SIZE
+        {
+            self.batch_timer = None;
+            return false;
+        }
+
+        let timer = self.batch_timer.get_or_insert_with(|| delay_for(delay));
+        match Pin::new(timer).poll(cx) {
+            Poll::Ready(_) => {
+                self.batch_timer = None;
+                false
+            }
+            Poll::Pending => true,
+        }
+    }
+
+    fn bytes_written(&self, size: usize) {
+        if let Some(ref metrics) = self.metrics {
+            metrics.handle().bytes_written(size);
+        }
+    }
+
+    /// Response of the oldest request is sent
+    fn request_end(&mut self) {
+        if let Some(req) = self.requests.pop_front() {
+            req.end();
+        }
+    }
+
+    /// Check request payload that is not going to be read.
+    ///
+    /// Rest of the payload is discarded if it is small enough, otherwise
+    /// connection is closed after the response.
+    fn unread_payload(&mut self, message: &mut Response<()>) {
+        let unread = match self.payload {
+            Some(ref payload)
+                if self.messages.is_empty()
+                    && (self.flags.contains(Flags::DISCARD) || payload.is_dropped()) =>
+            {
+                self.payload_size
+                    .map(|size| size.saturating_sub(self.payload_read))
+            }
+            _ => return,
+        };
+
+        match unread {
+            Some(size) if size <= self.codec.config().payload_limits().drain_size => {
+                self.flags.insert(Flags::DISCARD | Flags::DRAIN);
+            }
+            _ => {
+                trace!("Request payload is not read, close connection");
+                message
+                    .head_mut()
+                    .set_connection_type(ConnectionType::Close);
+                self.flags.insert(Flags::READ_DISCONNECT);
+                self.payload.take();
+            }
+        }
+    }
+
     fn send_response(
         &mut self,
-        message: Response<()>,
+        mut message: Response<()>,
         body: ResponseBody<B>,
     ) -> Result<State<S, B, X>, DispatchError> {
+        // response takes over connection io, request payload is not read anymore
+        if message.status() == StatusCode::SWITCHING_PROTOCOLS {
+            self.on_upgrade = message.extensions_mut().remove::<OnUpgrade>();
+        }
+        if self.on_upgrade.is_some() {
+            self.flags.insert(Flags::UPGRADE);
+            self.payload.take();
+        } else {
+            self.unread_payload(&mut message);
+        }
+
+        // connection state could be corrupted after server error
+        if message.status().is_server_error()
+            && self.codec.config().close_on_error()
+            && message.head().ctype() != Some(ConnectionType::KeepAlive)
+        {
+            message
+                .head_mut()
+                .set_connection_type(ConnectionType::Close);
+        }
+
+        let status = message.status();
+        let drain = message.head().drain_head_body();
         self.codec
             .encode(Message::Item((message, body.size())), &mut self.write_buf)
             .map_err(|err| {
@@ -342,8 +481,20 @@ where
             })?;
 
         self.flags.set(Flags::KEEPALIVE, self.codec.keepalive());
+        // requests that are answered by dispatcher itself are not tracked
+        if let Some(req) = self.requests.front_mut() {
+            req.set_status(status);
+        }
         match body.size() {
-            BodySize::None | BodySize::Empty => Ok(State::None),
+            BodySize::None | BodySize::Empty => {
+                self.request_end();
+                Ok(State::None)
+            }
+            // body of `HEAD` request response is not sent
+            _ if self.codec.head() && !drain => {
+                self.request_end();
+                Ok(State::None)
+            }
             _ => Ok(State::SendPayload(body)),
         }
     }
@@ -417,6 +568,7 @@ where
                                         Message::Chunk(None),
                                         &mut self.write_buf,
                                     )?;
+                                    self.request_end();
                                     self.state = State::None;
                                 }
                                 Poll::Ready(Some(Err(_))) => {
@@ -518,26 +670,85 @@ where
                         Message::Item(mut req) => {
                             let pl = self.codec.message_type();
                             req.head_mut().peer_addr = self.peer_addr;
+                            req.head_mut().conn_data = Some(self.conn_data.clone());
+                            if let Some(ref metrics) = self.metrics {
+                                self.requests.push_back(
+                                    metrics.handle().request_start(req.head()),
+                                );
+                            }
 
                             // set on_connect data
                             if let Some(ref on_connect) = self.on_connect {
                                 on_connect.set(&mut req.extensions_mut());
                             }
 
+                            // server-wide `OPTIONS *` request
+                            if pl != MessageType::Stream
+                                && req.head().is_options_asterisk()
+                            {
+                                if let Some(allow) = self.codec.config().options_allow()
+                                {
+                                    if pl == MessageType::Payload {
+                                        // payload is discarded, it is drained or
+                                        // connection is closed after the response
+                                        let (ps, _) = Payload::create(false);
+                                        self.payload = Some(ps);
+                                        self.payload_read = 0;
+                                        // client that expects `100 Continue` might
+                                        // not send payload, connection is closed
+                                        self.payload_size = if req.head().expect() {
+                                            None
+                                        } else {
+                                            payload_size(&req)
+                                        };
+                                        self.flags.remove(Flags::DRAIN);
+                                        self.flags.insert(Flags::DISCARD);
+                                    }
+                                    self.messages.push_back(DispatcherMessage::Error(
+                                        Response::Ok()
+                                            .insert_header((
+                                                header::ALLOW,
+                                                allow.clone(),
+                                            ))
+                                            .finish()
+                                            .drop_body(),
+                                    ));
+                                    continue;
+                                }
+                            }
+
                             if pl == MessageType::Stream && self.upgrade.is_some() {
                                 self.messages.push_back(DispatcherMessage::Upgrade(req));
                                 break;
                             }
+                            self.flags.remove(Flags::DISCARD | Flags::DRAIN);
                             if pl == MessageType::Payload || pl == MessageType::Stream {
-                                let (ps, pl) = Payload::create(false);
+                                let (mut ps, pl) = Payload::create(false);
+                                self.payload_read = 0;
+                                self.payload_size = payload_size(&req);
+
+                                // declared size is bigger than allowed,
+                                // service can respond without reading payload
+                                if let Some(size) = self.payload_size {
+                                    if self
+                                        .codec
+                                        .config()
+                                        .payload_limits()
+                                        .overflows(size)
+                                    {
+                                        ps.set_error(PayloadError::Overflow);
+                                        self.flags.insert(Flags::DISCARD);
+                                    }
+                                }
+
                                 let (req1, _) =
                                     req.replace_payload(crate::Payload::H1(pl));
                                 req = req1;
                                 self.payload = Some(ps);
                             }
 
-                            // handle request early
-                            if self.state.is_empty() {
+                            // handle request early, responses are sent in order
+                            if self.state.is_empty() && self.messages.is_empty() {
                                 self.state = self.handle_request(req, cx)?;
                             } else {
                                 self.messages.push_back(DispatcherMessage::Item(req));
@@ -545,7 +756,20 @@ where
                         }
                         Message::Chunk(Some(chunk)) => {
                             if let Some(ref mut payload) = self.payload {
-                                payload.feed_data(chunk);
+                                self.payload_read += chunk.len() as u64;
+                                if self.flags.contains(Flags::DISCARD) {
+                                    // nobody reads the payload, drop chunk
+                                } else if self
+                                    .codec
+                                    .config()
+                                    .payload_limits()
+                                    .overflows(self.payload_read)
+                                {
+                                    payload.set_error(PayloadError::Overflow);
+                                    self.flags.insert(Flags::DISCARD);
+                                } else {
+                                    payload.feed_data(chunk);
+                                }
                             } else {
                                 error!(
                                     "Internal server error: unexpected payload chunk"
@@ -560,7 +784,13 @@ where
                         }
                         Message::Chunk(None) => {
                             if let Some(mut payload) = self.payload.take() {
-                                payload.feed_eof();
+                                if !self.flags.contains(Flags::DISCARD) {
+                                    if let Some(trailers) = self.codec.take_trailers() {
+                                        payload.set_trailers(trailers);
+                                    }
+                                    payload.feed_eof();
+                                }
+                                self.flags.remove(Flags::DISCARD | Flags::DRAIN);
                             } else {
                                 error!("Internal server error: unexpected eof");
                                 self.flags.insert(Flags::READ_DISCONNECT);
@@ -697,7 +927,7 @@ where
 
 impl<T, S, B, X, U> Future for Dispatcher<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: Service<Request = Request>,
     S::Error: Into<Error>,
     S::Response: Into<Response<B>>,
@@ -736,7 +966,13 @@ where
                     // read socket into a buf
                     let should_disconnect =
                         if !inner.flags.contains(Flags::READ_DISCONNECT) {
-                            read_available(cx, &mut inner.io, &mut inner.read_buf)?
+                            let len = inner.read_buf.len();
+                            let res =
+                                read_available(cx, &mut inner.io, &mut inner.read_buf)?;
+                            if let Some(ref metrics) = inner.metrics {
+                                metrics.handle().bytes_read(inner.read_buf.len() - len);
+                            }
+                            res
                         } else {
                             None
                         };
@@ -782,7 +1018,7 @@ where
                         // we didnt get WouldBlock from write operation,
                         // so data get written to kernel completely (OSX)
                         // and we have to write again otherwise response can get stuck
-                        if inner.poll_flush(cx)? || !drain {
+                        if inner.hold_writes(cx) || inner.poll_flush(cx)? || !drain {
                             break;
                         }
                     }
@@ -792,6 +1028,23 @@ where
                         return Poll::Ready(Ok(()));
                     }
 
+                    // switch to upgrade handler of the response
+                    if inner.on_upgrade.is_some()
+                        && inner.state.is_empty()
+                        && inner.write_buf.is_empty()
+                    {
+                        if let DispatcherState::Normal(inner) =
+                            std::mem::replace(&mut self.inner, DispatcherState::None)
+                        {
+                            let io = Upgraded::new(inner.io, inner.read_buf);
+                            let on_upgrade = inner.on_upgrade.unwrap();
+                            self.inner = DispatcherState::OnUpgrade(on_upgrade.call(io));
+                            return self.poll(cx);
+                        } else {
+                            panic!()
+                        }
+                    }
+
                     let is_empty = inner.state.is_empty();
 
                     // read half is closed and we do not processing any responses
@@ -828,11 +1081,23 @@ where
                     DispatchError::Upgrade
                 })
             }
+            DispatcherState::OnUpgrade(ref mut fut) => fut.as_mut().poll(cx).map(Ok),
             DispatcherState::None => panic!(),
         }
     }
 }
 
+/// Declared size of request payload, `None` for chunked payload
+fn payload_size(req: &Request) -> Option<u64> {
+    if req.chunked().unwrap_or(false) {
+        return None;
+    }
+    req.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse().ok())
+}
+
 fn read_available<T>(
     cx: &mut Context<'_>,
     io: &mut T,
@@ -889,12 +1154,18 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::time::Duration;
+
     use actori_service::IntoService;
     use futures_util::future::{lazy, ok};
 
     use super::*;
+    use crate::config::KeepAlive;
     use crate::error::Error;
     use crate::h1::{ExpectHandler, UpgradeHandler};
+    use crate::message::RequestHead;
+    use crate::metrics::Metrics;
     use crate::test::TestBuffer;
 
     #[actori_rt::test]
@@ -925,4 +1196,315 @@ mod tests {
         })
         .await;
     }
+
+    #[actori_rt::test]
+    async fn test_options_asterisk() {
+        lazy(|cx| {
+            let buf = TestBuffer::new("OPTIONS * HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default(),
+                CloneableService::new(
+                    (|_| ok::<_, Error>(Response::NotFound().finish())).into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            let _ = Pin::new(&mut h1).poll(cx);
+
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                let buf = String::from_utf8_lossy(&inner.io.write_buf).to_string();
+                assert!(buf.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(buf.contains("allow: OPTIONS, GET, HEAD"));
+            } else {
+                panic!();
+            }
+        })
+        .await;
+    }
+
+    #[actori_rt::test]
+    async fn test_options_asterisk_payload() {
+        lazy(|cx| {
+            let buf = TestBuffer::new(
+                "OPTIONS * HTTP/1.1\r\ncontent-length: 4\r\n\r\ntest\
+                 GET /test HTTP/1.1\r\n\r\n",
+            );
+            let calls = Rc::new(Cell::new(0));
+            let calls2 = calls.clone();
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default(),
+                CloneableService::new(
+                    (move |req: Request| {
+                        assert_eq!(req.path(), "/test");
+                        calls2.set(calls2.get() + 1);
+                        ok::<_, Error>(Response::NotFound().finish())
+                    })
+                    .into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            let _ = Pin::new(&mut h1).poll(cx);
+            let _ = Pin::new(&mut h1).poll(cx);
+
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                let buf = String::from_utf8_lossy(&inner.io.write_buf).to_string();
+                assert!(buf.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(buf.contains("allow: OPTIONS, GET, HEAD"));
+                assert!(!buf.contains("connection: close"));
+                let idx = buf.find("HTTP/1.1 404 Not Found\r\n").unwrap();
+                assert!(idx > 0);
+                assert_eq!(calls.get(), 1);
+            } else {
+                panic!();
+            }
+        })
+        .await;
+    }
+
+    #[actori_rt::test]
+    async fn test_head_body() {
+        lazy(|cx| {
+            let polled = Rc::new(Cell::new(false));
+            let polled2 = polled.clone();
+            let buf = TestBuffer::new("HEAD /test HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default(),
+                CloneableService::new(
+                    (move |_| {
+                        let polled = polled2.clone();
+                        let body = futures_util::stream::poll_fn(move |_| {
+                            polled.set(true);
+                            Poll::<Option<Result<bytes::Bytes, Error>>>::Pending
+                        });
+                        let mut res =
+                            Response::Ok().body(crate::body::SizedStream::new(4, body));
+                        res.head_mut().set_drain_head_body(false);
+                        ok::<_, Error>(res)
+                    })
+                    .into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            let _ = Pin::new(&mut h1).poll(cx);
+
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                let buf = String::from_utf8_lossy(&inner.io.write_buf).to_string();
+                assert!(buf.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(buf.contains("content-length: 4\r\n"));
+                assert!(buf.ends_with("\r\n\r\n"));
+                assert!(inner.state.is_empty());
+            } else {
+                panic!();
+            }
+            assert!(!polled.get());
+        })
+        .await;
+    }
+
+    #[actori_rt::test]
+    async fn test_close_on_error() {
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default().with_close_on_error(true),
+                CloneableService::new(
+                    (|_| ok::<_, Error>(Response::InternalServerError().finish()))
+                        .into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            let _ = Pin::new(&mut h1).poll(cx);
+
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                let buf = String::from_utf8_lossy(&inner.io.write_buf).to_string();
+                assert!(buf.contains("connection: close"));
+                assert!(!inner.flags.contains(Flags::KEEPALIVE));
+            } else {
+                panic!();
+            }
+        })
+        .await;
+    }
+
+    #[actori_rt::test]
+    async fn test_response_keep_alive() {
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::new(KeepAlive::Disabled, 0, 0, false, None)
+                    .with_close_on_error(true),
+                CloneableService::new(
+                    (|_| {
+                        ok::<_, Error>(
+                            Response::ServiceUnavailable().keep_alive(true).finish(),
+                        )
+                    })
+                    .into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            let _ = Pin::new(&mut h1).poll(cx);
+
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                let buf = String::from_utf8_lossy(&inner.io.write_buf).to_string();
+                assert!(!buf.contains("connection: close"));
+                assert!(inner.flags.contains(Flags::KEEPALIVE));
+            } else {
+                panic!();
+            }
+        })
+        .await;
+    }
+
+    #[actori_rt::test]
+    async fn test_write_batch() {
+        let done = Rc::new(Cell::new(false));
+        let done2 = done.clone();
+        let mut sent = false;
+        let mut body = Some(futures_util::stream::poll_fn(move |_| {
+            if !sent {
+                sent = true;
+                Poll::Ready(Some(Ok::<_, Error>(bytes::Bytes::from_static(b"chunk"))))
+            } else if done2.get() {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            }
+        }));
+
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default().with_write_batch(Some(Duration::from_secs(10))),
+                CloneableService::new(
+                    (move |_| {
+                        ok::<_, Error>(Response::Ok().streaming(body.take().unwrap()))
+                    })
+                    .into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+
+            // response head and first chunk are held
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                assert!(inner.io.write_buf.is_empty());
+                assert!(!inner.write_buf.is_empty());
+            } else {
+                panic!();
+            }
+
+            done.set(true);
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                let buf = String::from_utf8_lossy(&inner.io.write_buf).to_string();
+                assert!(buf.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(buf.ends_with("5\r\nchunk\r\n0\r\n\r\n"));
+                assert!(inner.write_buf.is_empty());
+            } else {
+                panic!();
+            }
+        })
+        .await;
+    }
+
+    #[derive(Default)]
+    struct TestMetrics {
+        events: RefCell<Vec<String>>,
+        read: Cell<usize>,
+        written: Cell<usize>,
+    }
+
+    impl Metrics for TestMetrics {
+        fn connection_open(&self, protocol: Protocol, _: Option<net::SocketAddr>) {
+            self.events
+                .borrow_mut()
+                .push(format!("open {:?}", protocol));
+        }
+
+        fn connection_close(&self, _: Protocol) {
+            self.events.borrow_mut().push("close".to_string());
+        }
+
+        fn request_start(&self, _: Protocol, head: &RequestHead) {
+            self.events.borrow_mut().push(format!("start {}", head.uri));
+        }
+
+        fn request_end(&self, _: Protocol, status: Option<StatusCode>, _: Duration) {
+            self.events.borrow_mut().push(format!("end {:?}", status));
+        }
+
+        fn bytes_read(&self, _: Protocol, size: usize) {
+            self.read.set(self.read.get() + size);
+        }
+
+        fn bytes_written(&self, _: Protocol, size: usize) {
+            self.written.set(self.written.get() + size);
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_metrics() {
+        let metrics = Rc::new(TestMetrics::default());
+        let m: Rc<dyn Metrics> = metrics.clone();
+
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+
+            let mut h1 = Dispatcher::<_, _, _, _, UpgradeHandler<TestBuffer>>::new(
+                buf,
+                ServiceConfig::default().with_metrics(Some(m)),
+                CloneableService::new(
+                    (|_| ok::<_, Error>(Response::Ok().body("hello"))).into_service(),
+                ),
+                CloneableService::new(ExpectHandler),
+                None,
+                None,
+                None,
+            );
+            assert!(Pin::new(&mut h1).poll(cx).is_pending());
+
+            if let DispatcherState::Normal(ref inner) = h1.inner {
+                assert_eq!(metrics.written.get(), inner.io.write_buf.len());
+            } else {
+                panic!();
+            }
+        })
+        .await;
+
+        assert_eq!(metrics.read.get(), 22);
+        assert_eq!(
+            *metrics.events.borrow(),
+            vec!["open Http1", "start /test", "end Some(200)", "close"]
+        );
+    }
 }
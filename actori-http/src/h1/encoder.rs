@@ -1,8 +1,7 @@
-use std::io::Write;
+use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::ptr::copy_nonoverlapping;
 use std::slice::from_raw_parts_mut;
-use std::{cmp, io};
 
 use bytes::{buf::BufMutExt, BufMut, BytesMut};
 
@@ -12,7 +11,7 @@ use crate::header::map;
 use crate::helpers;
 use crate::http::header::{CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
 use crate::http::{HeaderMap, StatusCode, Version};
-use crate::message::{ConnectionType, RequestHeadType};
+use crate::message::{ConnectionType, RequestHead, RequestHeadType};
 use crate::response::Response;
 
 const AVERAGE_HEADER_SIZE: usize = 30;
@@ -21,6 +20,7 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(crate) struct MessageEncoder<T: MessageType> {
     pub length: BodySize,
     pub te: TransferEncoding,
+    pub absolute_form: bool,
     _t: PhantomData<T>,
 }
 
@@ -29,6 +29,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: TransferEncoding::empty(),
+            absolute_form: false,
             _t: PhantomData,
         }
     }
@@ -47,8 +48,18 @@ pub(crate) trait MessageType: Sized {
 
     fn chunked(&self) -> bool;
 
+    /// Validate streaming payload against `Content-Length` header
+    fn length_checked(&self) -> bool {
+        true
+    }
+
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()>;
 
+    /// Encode request line with absolute-form request target
+    fn encode_absolute_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode_status(dst)
+    }
+
     fn encode_headers(
         &mut self,
         dst: &mut BytesMut,
@@ -246,6 +257,10 @@ impl MessageType for Response<()> {
         self.head().chunked()
     }
 
+    fn length_checked(&self) -> bool {
+        self.head().length_checked()
+    }
+
     fn headers(&self) -> &HeaderMap {
         &self.head().headers
     }
@@ -289,29 +304,44 @@ impl MessageType for RequestHeadType {
 
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
         let head = self.as_ref();
-        dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
-        write!(
-            Writer(dst),
-            "{} {} {}",
-            head.method,
-            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
-            match head.version {
-                Version::HTTP_09 => "HTTP/0.9",
-                Version::HTTP_10 => "HTTP/1.0",
-                Version::HTTP_11 => "HTTP/1.1",
-                Version::HTTP_2 => "HTTP/2.0",
-                Version::HTTP_3 => "HTTP/3.0",
-                _ =>
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "unsupported version"
-                    )),
-            }
-        )
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        let target = head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/");
+        encode_request_line(head, target, dst)
+    }
+
+    fn encode_absolute_status(&mut self, dst: &mut BytesMut) -> io::Result<()> {
+        let head = self.as_ref();
+        if head.uri.authority().is_some() {
+            encode_request_line(head, &head.uri.to_string(), dst)
+        } else {
+            self.encode_status(dst)
+        }
     }
 }
 
+fn encode_request_line(
+    head: &RequestHead,
+    target: &str,
+    dst: &mut BytesMut,
+) -> io::Result<()> {
+    dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE);
+    write!(
+        Writer(dst),
+        "{} {} {}",
+        head.method,
+        target,
+        match head.version {
+            Version::HTTP_09 => "HTTP/0.9",
+            Version::HTTP_10 => "HTTP/1.0",
+            Version::HTTP_11 => "HTTP/1.1",
+            Version::HTTP_2 => "HTTP/2.0",
+            Version::HTTP_3 => "HTTP/3.0",
+            _ =>
+                return Err(io::Error::new(io::ErrorKind::Other, "unsupported version")),
+        }
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
+
 impl<T: MessageType> MessageEncoder<T> {
     /// Encode message
     pub fn encode_chunk(&mut self, msg: &[u8], buf: &mut BytesMut) -> io::Result<bool> {
@@ -323,6 +353,15 @@ impl<T: MessageType> MessageEncoder<T> {
         self.te.encode_eof(buf)
     }
 
+    /// Encode end of the payload with trailer fields
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        self.te.encode_trailers(trailers, buf)
+    }
+
     pub fn encode(
         &mut self,
         dst: &mut BytesMut,
@@ -334,6 +373,20 @@ impl<T: MessageType> MessageEncoder<T> {
         ctype: ConnectionType,
         config: &ServiceConfig,
     ) -> io::Result<()> {
+        // streaming payload with explicit `Content-Length` header is sent
+        // as sized payload, encoder fails if payload size does not match
+        let length = match length {
+            BodySize::Stream
+                if message.length_checked()
+                    && message.status() != Some(StatusCode::SWITCHING_PROTOCOLS) =>
+            {
+                helpers::content_length(message.headers(), message.extra_headers())
+                    .map(BodySize::Sized64)
+                    .unwrap_or(length)
+            }
+            _ => length,
+        };
+
         // transfer encoding
         if !head {
             self.te = match length {
@@ -353,7 +406,11 @@ impl<T: MessageType> MessageEncoder<T> {
             self.te = TransferEncoding::empty();
         }
 
-        message.encode_status(dst)?;
+        if self.absolute_form {
+            message.encode_absolute_status(dst)?;
+        } else {
+            message.encode_status(dst)?;
+        }
         message.encode_headers(dst, version, length, ctype, config)
     }
 }
@@ -372,6 +429,10 @@ enum TransferEncodingKind {
     ///
     /// Enforces that the body is not longer than the Content-Length header.
     Length(u64),
+    /// An Encoder for messages without body, i.e. responses to HEAD requests.
+    ///
+    /// Body is discarded.
+    Empty,
     /// An Encoder for when Content-Length is not known.
     ///
     /// Application decides when to stop writing.
@@ -382,7 +443,7 @@ impl TransferEncoding {
     #[inline]
     pub fn empty() -> TransferEncoding {
         TransferEncoding {
-            kind: TransferEncodingKind::Length(0),
+            kind: TransferEncodingKind::Empty,
         }
     }
 
@@ -411,6 +472,7 @@ impl TransferEncoding {
     #[inline]
     pub fn encode(&mut self, msg: &[u8], buf: &mut BytesMut) -> io::Result<bool> {
         match self.kind {
+            TransferEncodingKind::Empty => Ok(true),
             TransferEncodingKind::Eof => {
                 let eof = msg.is_empty();
                 buf.extend_from_slice(msg);
@@ -435,15 +497,20 @@ impl TransferEncoding {
                 Ok(*eof)
             }
             TransferEncodingKind::Length(ref mut remaining) => {
+                if msg.len() as u64 > *remaining {
+                    error!("Payload exceeds declared Content-Length");
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "payload is longer than Content-Length",
+                    ));
+                }
                 if *remaining > 0 {
                     if msg.is_empty() {
                         return Ok(*remaining == 0);
                     }
-                    let len = cmp::min(*remaining, msg.len() as u64);
-
-                    buf.extend_from_slice(&msg[..len as usize]);
+                    buf.extend_from_slice(msg);
 
-                    *remaining -= len as u64;
+                    *remaining -= msg.len() as u64;
                     Ok(*remaining == 0)
                 } else {
                     Ok(true)
@@ -456,9 +523,10 @@ impl TransferEncoding {
     #[inline]
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
         match self.kind {
-            TransferEncodingKind::Eof => Ok(()),
+            TransferEncodingKind::Empty | TransferEncodingKind::Eof => Ok(()),
             TransferEncodingKind::Length(rem) => {
                 if rem != 0 {
+                    error!("Payload is shorter than declared Content-Length");
                     Err(io::Error::new(io::ErrorKind::UnexpectedEof, ""))
                 } else {
                     Ok(())
@@ -473,6 +541,32 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode eof with trailer fields, trailers are dropped unless
+    /// chunked encoding is used
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        match self.kind {
+            TransferEncodingKind::Chunked(ref mut eof) => {
+                if !*eof {
+                    *eof = true;
+                    buf.extend_from_slice(b"0\r\n");
+                    for (name, value) in trailers.iter() {
+                        buf.extend_from_slice(name.as_str().as_bytes());
+                        buf.extend_from_slice(b": ");
+                        buf.extend_from_slice(value.as_bytes());
+                        buf.extend_from_slice(b"\r\n");
+                    }
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Ok(())
+            }
+            _ => self.encode_eof(buf),
+        }
+    }
 }
 
 struct Writer<'a>(pub &'a mut BytesMut);
@@ -652,4 +746,111 @@ mod tests {
         assert!(data.contains("authorization: another authorization\r\n"));
         assert!(data.contains("date: date\r\n"));
     }
+
+    #[test]
+    fn test_absolute_form() {
+        let mut bytes = BytesMut::with_capacity(2048);
+        let mut enc = MessageEncoder::<RequestHeadType>::default();
+
+        let mut head = RequestHead::default();
+        head.uri = http::Uri::from_static("http://example.com:8080/path?q=1");
+        let mut head = RequestHeadType::Owned(head);
+
+        let _ = enc.encode(
+            &mut bytes,
+            &mut head,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::Close,
+            &ServiceConfig::default(),
+        );
+        assert!(bytes.starts_with(b"GET /path?q=1 HTTP/1.1\r\n"));
+
+        bytes.clear();
+        enc.absolute_form = true;
+        let _ = enc.encode(
+            &mut bytes,
+            &mut head,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::Close,
+            &ServiceConfig::default(),
+        );
+        assert!(bytes.starts_with(b"GET http://example.com:8080/path?q=1 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_declared_length() {
+        let mut bytes = BytesMut::with_capacity(2048);
+        let mut enc = MessageEncoder::<Response<()>>::default();
+        let cfg = ServiceConfig::default();
+
+        let mut res = Response::Ok()
+            .insert_header((CONTENT_LENGTH, "4"))
+            .finish()
+            .drop_body();
+        enc.encode(
+            &mut bytes,
+            &mut res,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Stream,
+            ConnectionType::KeepAlive,
+            &cfg,
+        )
+        .unwrap();
+        let data = String::from_utf8(bytes.split().to_vec()).unwrap();
+        assert!(data.contains("content-length: 4\r\n"));
+        assert!(!data.contains("transfer-encoding"));
+
+        assert!(enc.encode_chunk(b"test", &mut bytes).unwrap());
+        assert_eq!(&bytes.split()[..], b"test");
+        assert!(enc.encode_chunk(b"more", &mut bytes).is_err());
+
+        // payload is shorter than declared
+        enc.encode(
+            &mut bytes,
+            &mut res,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Stream,
+            ConnectionType::KeepAlive,
+            &cfg,
+        )
+        .unwrap();
+        bytes.clear();
+        assert!(!enc.encode_chunk(b"te", &mut bytes).unwrap());
+        assert!(enc.encode_eof(&mut bytes).is_err());
+
+        // header is sent as is
+        let mut res = Response::Ok()
+            .insert_header((CONTENT_LENGTH, "4"))
+            .unchecked_length()
+            .no_chunking()
+            .finish()
+            .drop_body();
+        bytes.clear();
+        enc.encode(
+            &mut bytes,
+            &mut res,
+            false,
+            false,
+            Version::HTTP_11,
+            BodySize::Stream,
+            ConnectionType::KeepAlive,
+            &cfg,
+        )
+        .unwrap();
+        let data = String::from_utf8(bytes.split().to_vec()).unwrap();
+        assert!(data.contains("content-length: 4\r\n"));
+        assert!(!enc.encode_chunk(b"testmore", &mut bytes).unwrap());
+        assert!(enc.encode_eof(&mut bytes).is_ok());
+        assert_eq!(&bytes[..], b"testmore");
+    }
 }
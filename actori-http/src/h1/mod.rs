@@ -1,6 +1,9 @@
 //! HTTP/1 implementation
 use bytes::{Bytes, BytesMut};
 
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 mod client;
 mod codec;
 mod decoder;
@@ -10,6 +13,7 @@ mod expect;
 mod payload;
 mod service;
 mod upgrade;
+mod upgraded;
 mod utils;
 
 pub use self::client::{ClientCodec, ClientPayloadCodec};
@@ -19,6 +23,8 @@ pub use self::expect::ExpectHandler;
 pub use self::payload::Payload;
 pub use self::service::{H1Service, H1ServiceHandler, OneRequest};
 pub use self::upgrade::UpgradeHandler;
+pub(crate) use self::upgraded::OnUpgrade;
+pub use self::upgraded::Upgraded;
 pub use self::utils::SendResponse;
 
 #[derive(Debug)]
@@ -10,6 +10,7 @@ use bytes::Bytes;
 use futures_core::Stream;
 
 use crate::error::PayloadError;
+use crate::header::HeaderMap;
 
 /// max buffer size 32k
 pub(crate) const MAX_BUFFER_SIZE: usize = 32_768;
@@ -79,6 +80,12 @@ impl Payload {
         self.inner.borrow_mut().unread_data(data);
     }
 
+    /// Trailer fields of chunked payload, available after the end of payload
+    #[inline]
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.inner.borrow_mut().trailers.take()
+    }
+
     #[inline]
     pub fn readany(
         &mut self,
@@ -126,6 +133,19 @@ impl PayloadSender {
         }
     }
 
+    #[inline]
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        if let Some(shared) = self.inner.upgrade() {
+            shared.borrow_mut().trailers = Some(trailers);
+        }
+    }
+
+    /// Returns true if receiver side of the payload is dropped
+    #[inline]
+    pub fn is_dropped(&self) -> bool {
+        self.inner.upgrade().is_none()
+    }
+
     #[inline]
     pub fn need_read(&self, cx: &mut Context<'_>) -> PayloadStatus {
         // we check need_read only if Payload (other side) is alive,
@@ -150,6 +170,7 @@ struct Inner {
     err: Option<PayloadError>,
     need_read: bool,
     items: VecDeque<Bytes>,
+    trailers: Option<HeaderMap>,
     task: LocalWaker,
     io_task: LocalWaker,
 }
@@ -161,6 +182,7 @@ impl Inner {
             len: 0,
             err: None,
             items: VecDeque::new(),
+            trailers: None,
             need_read: true,
             task: LocalWaker::new(),
             io_task: LocalWaker::new(),
@@ -170,6 +192,9 @@ impl Inner {
     #[inline]
     fn set_error(&mut self, err: PayloadError) {
         self.err = Some(err);
+        if let Some(task) = self.task.take() {
+            task.wake()
+        }
     }
 
     #[inline]
@@ -85,7 +85,12 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        pipeline_factory(|io: TcpStream| {
+        let nodelay = self.cfg.write_batch().is_some();
+        pipeline_factory(move |io: TcpStream| {
+            // writes are batched by dispatcher, no need to delay them again
+            if nodelay {
+                let _ = io.set_nodelay(true);
+            }
             let peer_addr = io.peer_addr().ok();
             ok((io, peer_addr))
         })
@@ -245,7 +250,7 @@ where
 
 impl<T, S, B, X, U> ServiceFactory for H1Service<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: ServiceFactory<Config = (), Request = Request>,
     S::Error: Into<Error>,
     S::Response: Into<Response<B>>,
@@ -309,7 +314,7 @@ where
 
 impl<T, S, B, X, U> Future for H1ServiceResponse<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: ServiceFactory<Request = Request>,
     S::Error: Into<Error>,
     S::Response: Into<Response<B>>,
@@ -404,7 +409,7 @@ where
 
 impl<T, S, B, X, U> Service for H1ServiceHandler<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: Service<Request = Request>,
     S::Error: Into<Error>,
     S::Response: Into<Response<B>>,
@@ -489,7 +494,7 @@ pub struct OneRequest<T> {
 
 impl<T> OneRequest<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     /// Create new `H1SimpleService` instance.
     pub fn new() -> Self {
@@ -502,7 +507,7 @@ where
 
 impl<T> ServiceFactory for OneRequest<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     type Config = ();
     type Request = T;
@@ -529,7 +534,7 @@ pub struct OneRequestService<T> {
 
 impl<T> Service for OneRequestService<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     type Request = T;
     type Response = (Request, Framed<T, Codec>);
@@ -550,14 +555,14 @@ where
 #[doc(hidden)]
 pub struct OneRequestServiceResponse<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     framed: Option<Framed<T, Codec>>,
 }
 
 impl<T> Future for OneRequestServiceResponse<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
 {
     type Output = Result<(Request, Framed<T, Codec>), ParseError>;
 
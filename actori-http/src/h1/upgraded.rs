@@ -0,0 +1,97 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{cmp, fmt, io};
+
+use actori_codec::{AsyncRead, AsyncWrite};
+use bytes::{Buf, BytesMut};
+use futures_util::future::{FutureExt, LocalBoxFuture};
+
+trait Io: AsyncRead + AsyncWrite + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> Io for T {}
+
+/// Connection io taken over after *101 Switching Protocols* response.
+///
+/// Data that was received after request head and not consumed by
+/// the request is read first.
+pub struct Upgraded {
+    io: Box<dyn Io>,
+    read_buf: BytesMut,
+}
+
+impl Upgraded {
+    pub(crate) fn new<T>(io: T, read_buf: BytesMut) -> Self
+    where
+        T: AsyncRead + AsyncWrite + Unpin + 'static,
+    {
+        Upgraded {
+            io: Box::new(io),
+            read_buf,
+        }
+    }
+}
+
+impl fmt::Debug for Upgraded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgraded")
+            .field("read_buf", &self.read_buf.len())
+            .finish()
+    }
+}
+
+impl AsyncRead for Upgraded {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.read_buf.is_empty() {
+            let len = cmp::min(buf.len(), self.read_buf.len());
+            buf[..len].copy_from_slice(&self.read_buf[..len]);
+            self.read_buf.advance(len);
+            return Poll::Ready(Ok(len));
+        }
+        Pin::new(&mut *self.io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Upgraded {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.io).poll_shutdown(cx)
+    }
+}
+
+/// Upgrade handler stored in response extensions
+pub(crate) struct OnUpgrade(Box<dyn FnOnce(Upgraded) -> LocalBoxFuture<'static, ()>>);
+
+impl OnUpgrade {
+    pub(crate) fn new<F, R>(f: F) -> Self
+    where
+        F: FnOnce(Upgraded) -> R + 'static,
+        R: std::future::Future<Output = ()> + 'static,
+    {
+        OnUpgrade(Box::new(move |io| f(io).boxed_local()))
+    }
+
+    pub(crate) fn call(self, io: Upgraded) -> LocalBoxFuture<'static, ()> {
+        (self.0)(io)
+    }
+}
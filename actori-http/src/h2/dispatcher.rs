@@ -3,27 +3,39 @@ use std::future::Future;
 use std::marker::PhantomData;
 use std::net;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use actori_codec::{AsyncRead, AsyncWrite};
-use actori_rt::time::{Delay, Instant};
+use actori_rt::time::{delay_until, Delay, Instant};
 use actori_service::Service;
 use bytes::{Bytes, BytesMut};
-use h2::server::{Connection, SendResponse};
-use h2::SendStream;
-use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use h2::server::{Connection, SendPushedResponse, SendResponse};
+use h2::{Reason, SendStream};
+use http::header::{
+    HeaderValue, ACCEPT_ENCODING, ACCEPT_LANGUAGE, ALLOW, CONNECTION, CONTENT_LENGTH,
+    COOKIE, DATE, TRANSFER_ENCODING, USER_AGENT,
+};
 use log::{error, trace};
 
 use crate::body::{BodySize, MessageBody, ResponseBody};
 use crate::cloneable::CloneableService;
 use crate::config::ServiceConfig;
 use crate::error::{DispatchError, Error};
-use crate::helpers::DataFactory;
+use crate::extensions::Extensions;
+use crate::h2::PushPromises;
+use crate::header::HeaderMap;
+use crate::helpers::{content_length, DataFactory};
 use crate::httpmessage::HttpMessage;
 use crate::message::ResponseHead;
+use crate::metrics::{ConnectionMetrics, RequestMetrics};
 use crate::payload::Payload;
 use crate::request::Request;
 use crate::response::Response;
+use crate::Protocol;
 
 const CHUNK_SIZE: usize = 16_384;
 
@@ -38,8 +50,15 @@ where
     on_connect: Option<Box<dyn DataFactory>>,
     config: ServiceConfig,
     peer_addr: Option<net::SocketAddr>,
+    conn_data: Rc<Extensions>,
+    metrics: Option<ConnectionMetrics>,
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    rate_start: Instant,
+    rate_count: u32,
+    goaway: bool,
+    push_tx: mpsc::UnboundedSender<(Request, SendPushedResponse<Bytes>)>,
+    push_rx: mpsc::UnboundedReceiver<(Request, SendPushedResponse<Bytes>)>,
     _t: PhantomData<B>,
 }
 
@@ -76,17 +95,61 @@ where
             (config.now(), None)
         };
 
+        let rate_start = config.now();
+        let conn_data = config.conn_data(peer_addr);
+        let metrics = ConnectionMetrics::new(&config, Protocol::Http2, peer_addr);
+        let (push_tx, push_rx) = mpsc::unbounded();
+
         Dispatcher {
             service,
             config,
             peer_addr,
+            conn_data,
+            metrics,
             connection,
             on_connect,
             ka_expire,
             ka_timer,
+            rate_start,
+            rate_count: 0,
+            goaway: false,
+            push_tx,
+            push_rx,
             _t: PhantomData,
         }
     }
+
+    /// Count opened stream, returns false if streams opening rate is exceeded.
+    fn check_stream_rate(&mut self) -> bool {
+        if let Some((max, period)) = self.config.h2_limits().stream_rate {
+            let now = self.config.now();
+            if now - self.rate_start >= period {
+                self.rate_start = now;
+                self.rate_count = 0;
+            }
+            self.rate_count += 1;
+
+            if self.rate_count > max {
+                if !self.goaway {
+                    warn!(
+                        "h2 streams opening rate is exceeded, closing connection: {:?}",
+                        self.peer_addr
+                    );
+                    self.connection.abrupt_shutdown(Reason::ENHANCE_YOUR_CALM);
+                    self.goaway = true;
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Report request start if metrics are configured
+    fn request_start(&self, req: &Request) -> Option<RequestMetrics> {
+        self.metrics
+            .as_ref()
+            .map(|metrics| metrics.handle().request_start(req.head()))
+    }
 }
 
 impl<T, S, B> Future for Dispatcher<T, S, B>
@@ -104,11 +167,34 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        // pushed requests are handled by the service as regular requests
+        while let Poll::Ready(Some((req, send))) =
+            Pin::new(&mut this.push_rx).poll_next(cx)
+        {
+            let metrics = this.request_start(&req);
+            actori_rt::spawn(
+                ServiceResponse::<S::Future, S::Response, S::Error, B>::new(
+                    this.service.call(req),
+                    Sender::Pushed(send),
+                    None,
+                    metrics,
+                    false,
+                    &this.config,
+                ),
+            );
+        }
+
         loop {
             match Pin::new(&mut this.connection).poll_accept(cx) {
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err.into())),
-                Poll::Ready(Some(Ok((req, res)))) => {
+                Poll::Ready(Some(Ok((req, mut res)))) => {
+                    // rapid reset mitigation
+                    if !this.check_stream_rate() {
+                        res.send_reset(Reason::REFUSED_STREAM);
+                        continue;
+                    }
+
                     // update keep-alive expire
                     if this.ka_timer.is_some() {
                         if let Some(expire) = this.config.keep_alive_expire() {
@@ -117,10 +203,17 @@ where
                     }
 
                     let (parts, body) = req.into_parts();
+                    let size = parts
+                        .headers
+                        .get(CONTENT_LENGTH)
+                        .and_then(|len| len.to_str().ok())
+                        .and_then(|len| len.parse().ok());
                     let mut req = Request::with_payload(Payload::<
                         crate::payload::PayloadStream,
                     >::H2(
                         crate::h2::Payload::new(body)
+                            .limit(this.config.payload_limits(), size)
+                            .metrics(this.metrics.as_ref().map(|m| m.handle().clone())),
                     ));
 
                     let head = &mut req.head_mut();
@@ -129,26 +222,43 @@ where
                     head.version = parts.version;
                     head.headers = parts.headers.into();
                     head.peer_addr = this.peer_addr;
+                    head.conn_data = Some(this.conn_data.clone());
 
                     // set on_connect data
                     if let Some(ref on_connect) = this.on_connect {
                         on_connect.set(&mut req.extensions_mut());
                     }
 
+                    let metrics = this.request_start(&req);
+
+                    // server-wide `OPTIONS *` request, payload is dropped
+                    // with the request
+                    if req.head().is_options_asterisk() {
+                        if let Some(allow) = this.config.options_allow() {
+                            send_options_response(res, allow, &this.config);
+                            if let Some(mut metrics) = metrics {
+                                metrics.set_status(http::StatusCode::OK);
+                                metrics.end();
+                            }
+                            continue;
+                        }
+                    }
+
+                    let head = req.head().method == http::Method::HEAD;
+                    let push = PushContext::new(&req, this.push_tx.clone());
                     actori_rt::spawn(ServiceResponse::<
                         S::Future,
                         S::Response,
                         S::Error,
                         B,
-                    > {
-                        state: ServiceResponseState::ServiceCall(
-                            this.service.call(req),
-                            Some(res),
-                        ),
-                        config: this.config.clone(),
-                        buffer: None,
-                        _t: PhantomData,
-                    });
+                    >::new(
+                        this.service.call(req),
+                        Sender::Stream(res),
+                        Some(push),
+                        metrics,
+                        head,
+                        &this.config,
+                    ));
                 }
                 Poll::Pending => return Poll::Pending,
             }
@@ -156,17 +266,198 @@ where
     }
 }
 
+/// Send response for server-wide `OPTIONS *` request
+fn send_options_response(
+    mut stream: SendResponse<Bytes>,
+    allow: &HeaderValue,
+    config: &ServiceConfig,
+) {
+    let mut res = http::Response::new(());
+    *res.version_mut() = http::Version::HTTP_2;
+    res.headers_mut().insert(ALLOW, allow.clone());
+    res.headers_mut()
+        .insert(CONTENT_LENGTH, HeaderValue::from_static("0"));
+
+    let mut bytes = BytesMut::with_capacity(29);
+    config.set_date_header(&mut bytes);
+    res.headers_mut().insert(DATE, unsafe {
+        HeaderValue::from_maybe_shared_unchecked(bytes.freeze())
+    });
+
+    if let Err(e) = stream.send_response(res, true) {
+        trace!("Error sending h2 response: {:?}", e);
+    }
+}
+
+/// Size of sized payload
+fn sized_payload(size: BodySize) -> Option<u64> {
+    match size {
+        BodySize::Sized(len) => Some(len as u64),
+        BodySize::Sized64(len) => Some(len),
+        _ => None,
+    }
+}
+
+/// Stream duration and idle timers
+struct StreamTimer {
+    deadline: Option<Delay>,
+    idle: Option<(Delay, Duration)>,
+}
+
+impl StreamTimer {
+    fn new(config: &ServiceConfig) -> Self {
+        let limits = config.h2_limits();
+        let now = config.now();
+        StreamTimer {
+            deadline: limits.max_stream_duration.map(|dur| delay_until(now + dur)),
+            idle: limits
+                .stream_idle_timeout
+                .map(|dur| (delay_until(now + dur), dur)),
+        }
+    }
+
+    /// Stream made progress, restart idle timer
+    fn touch(&mut self) {
+        if let Some((ref mut delay, dur)) = self.idle {
+            delay.reset(Instant::now() + dur);
+        }
+    }
+
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        if let Some(ref mut delay) = self.deadline {
+            if Pin::new(delay).poll(cx).is_ready() {
+                return true;
+            }
+        }
+        if let Some((ref mut delay, _)) = self.idle {
+            if Pin::new(delay).poll(cx).is_ready() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Response stream of a request or of a pushed request
+enum Sender {
+    Stream(SendResponse<Bytes>),
+    Pushed(SendPushedResponse<Bytes>),
+}
+
+impl Sender {
+    fn send_response(
+        &mut self,
+        res: http::Response<()>,
+        end_of_stream: bool,
+    ) -> Result<SendStream<Bytes>, h2::Error> {
+        match self {
+            Sender::Stream(send) => send.send_response(res, end_of_stream),
+            Sender::Pushed(send) => send.send_response(res, end_of_stream),
+        }
+    }
+
+    fn send_reset(&mut self, reason: Reason) {
+        match self {
+            Sender::Stream(send) => send.send_reset(reason),
+            Sender::Pushed(send) => send.send_reset(reason),
+        }
+    }
+}
+
+/// Data of the original request for pushed requests
+struct PushContext {
+    tx: mpsc::UnboundedSender<(Request, SendPushedResponse<Bytes>)>,
+    uri: http::Uri,
+    headers: HeaderMap,
+    peer_addr: Option<net::SocketAddr>,
+    conn_data: Option<Rc<Extensions>>,
+}
+
+impl PushContext {
+    fn new(
+        req: &Request,
+        tx: mpsc::UnboundedSender<(Request, SendPushedResponse<Bytes>)>,
+    ) -> Self {
+        let mut headers = HeaderMap::new();
+        for name in &[ACCEPT_ENCODING, ACCEPT_LANGUAGE, COOKIE, USER_AGENT] {
+            for value in req.headers().get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+
+        PushContext {
+            tx,
+            headers,
+            uri: req.head().uri.clone(),
+            peer_addr: req.head().peer_addr,
+            conn_data: req.head().conn_data.clone(),
+        }
+    }
+
+    /// Send push promise, pushed request is handled by the dispatcher
+    fn push(&self, send: &mut SendResponse<Bytes>, path: &str) {
+        let authority = match self.uri.authority() {
+            Some(authority) => authority.as_str(),
+            None => return,
+        };
+        let uri = match http::Uri::builder()
+            .scheme(self.uri.scheme_str().unwrap_or("https"))
+            .authority(authority)
+            .path_and_query(path)
+            .build()
+        {
+            Ok(uri) => uri,
+            Err(e) => {
+                trace!("Invalid push path {:?}: {:?}", path, e);
+                return;
+            }
+        };
+
+        let mut h2_req = http::Request::new(());
+        *h2_req.uri_mut() = uri.clone();
+        for (name, value) in self.headers.iter() {
+            h2_req.headers_mut().append(name, value.clone());
+        }
+
+        // client could disable server push
+        let pushed = match send.push_request(h2_req) {
+            Ok(pushed) => pushed,
+            Err(e) => {
+                trace!("Error sending h2 push promise: {:?}", e);
+                return;
+            }
+        };
+
+        let mut req = Request::new();
+        let head = req.head_mut();
+        head.uri = uri;
+        head.method = http::Method::GET;
+        head.version = http::Version::HTTP_2;
+        head.headers = self.headers.clone();
+        head.peer_addr = self.peer_addr;
+        head.conn_data = self.conn_data.clone();
+        let _ = self.tx.unbounded_send((req, pushed));
+    }
+}
+
 #[pin_project::pin_project]
 struct ServiceResponse<F, I, E, B> {
     state: ServiceResponseState<F, B>,
+    timer: StreamTimer,
     config: ServiceConfig,
     buffer: Option<Bytes>,
+    push: Option<PushContext>,
+    metrics: Option<RequestMetrics>,
+    head: bool,
     _t: PhantomData<(I, E)>,
 }
 
 enum ServiceResponseState<F, B> {
-    ServiceCall(F, Option<SendResponse<Bytes>>),
-    SendPayload(SendStream<Bytes>, ResponseBody<B>),
+    ServiceCall(F, Option<Sender>),
+    /// Response stream, payload and size of the rest of sized payload
+    SendPayload(SendStream<Bytes>, ResponseBody<B>, Option<u64>),
+    /// Payload of `HEAD` request response, read and discarded
+    DrainPayload(ResponseBody<B>),
 }
 
 impl<F, I, E, B> ServiceResponse<F, I, E, B>
@@ -176,12 +467,43 @@ where
     I: Into<Response<B>>,
     B: MessageBody,
 {
+    fn new(
+        call: F,
+        send: Sender,
+        push: Option<PushContext>,
+        metrics: Option<RequestMetrics>,
+        head: bool,
+        config: &ServiceConfig,
+    ) -> Self {
+        ServiceResponse {
+            state: ServiceResponseState::ServiceCall(call, Some(send)),
+            timer: StreamTimer::new(config),
+            config: config.clone(),
+            buffer: None,
+            push,
+            metrics,
+            head,
+            _t: PhantomData,
+        }
+    }
+
     fn prepare_response(
         &self,
         head: &ResponseHead,
         size: &mut BodySize,
     ) -> http::Response<()> {
         let mut has_date = false;
+
+        // streaming payload with explicit `Content-Length` header is sent
+        // as sized payload, stream is reset if payload size does not match
+        if *size == BodySize::Stream
+            && head.length_checked()
+            && head.status != http::StatusCode::SWITCHING_PROTOCOLS
+        {
+            if let Some(len) = content_length(&head.headers, None) {
+                *size = BodySize::Sized64(len);
+            }
+        }
         let mut skip_len = size != &BodySize::Stream;
 
         let mut res = http::Response::new(());
@@ -250,32 +572,73 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut this = self.as_mut().project();
 
+        if this.timer.poll_expired(cx) {
+            trace!("h2 stream timed out, resetting");
+            match this.state {
+                ServiceResponseState::ServiceCall(_, ref mut send) => {
+                    if let Some(ref mut send) = send {
+                        send.send_reset(Reason::CANCEL);
+                    }
+                }
+                ServiceResponseState::SendPayload(ref mut stream, _, _) => {
+                    stream.send_reset(Reason::CANCEL);
+                }
+                ServiceResponseState::DrainPayload(_) => (),
+            }
+            return Poll::Ready(());
+        }
+
         match this.state {
             ServiceResponseState::ServiceCall(ref mut call, ref mut send) => {
                 match unsafe { Pin::new_unchecked(call) }.poll(cx) {
                     Poll::Ready(Ok(res)) => {
-                        let (res, body) = res.into().replace_body(());
+                        let (mut res, body) = res.into().replace_body(());
 
                         let mut send = send.take().unwrap();
+                        if let Some(push) = this.push.take() {
+                            let paths = res.extensions_mut().remove::<PushPromises>();
+                            if let (Some(paths), Sender::Stream(send)) =
+                                (paths, &mut send)
+                            {
+                                for path in &paths.0 {
+                                    push.push(send, path);
+                                }
+                            }
+                        }
                         let mut size = body.size();
                         let h2_res =
                             self.as_mut().prepare_response(res.head(), &mut size);
                         this = self.as_mut().project();
 
-                        let stream = match send.send_response(h2_res, size.is_eof()) {
+                        // body of `HEAD` request response is not sent
+                        let eof = size.is_eof() || *this.head;
+                        let stream = match send.send_response(h2_res, eof) {
                             Err(e) => {
                                 trace!("Error sending h2 response: {:?}", e);
                                 return Poll::Ready(());
                             }
                             Ok(stream) => stream,
                         };
+                        this.timer.touch();
+                        if let Some(ref mut metrics) = this.metrics {
+                            metrics.set_status(res.status());
+                        }
 
-                        if size.is_eof() {
-                            Poll::Ready(())
-                        } else {
-                            *this.state =
-                                ServiceResponseState::SendPayload(stream, body);
+                        if !size.is_eof() && !*this.head {
+                            *this.state = ServiceResponseState::SendPayload(
+                                stream,
+                                body,
+                                sized_payload(size),
+                            );
                             self.poll(cx)
+                        } else if !size.is_eof() && res.head().drain_head_body() {
+                            *this.state = ServiceResponseState::DrainPayload(body);
+                            self.poll(cx)
+                        } else {
+                            if let Some(metrics) = this.metrics.take() {
+                                metrics.end();
+                            }
+                            Poll::Ready(())
                         }
                     }
                     Poll::Pending => Poll::Pending,
@@ -289,27 +652,45 @@ where
                             self.as_mut().prepare_response(res.head(), &mut size);
                         this = self.as_mut().project();
 
-                        let stream = match send.send_response(h2_res, size.is_eof()) {
+                        // body of `HEAD` request response is not sent
+                        let eof = size.is_eof() || *this.head;
+                        let stream = match send.send_response(h2_res, eof) {
                             Err(e) => {
                                 trace!("Error sending h2 response: {:?}", e);
                                 return Poll::Ready(());
                             }
                             Ok(stream) => stream,
                         };
+                        this.timer.touch();
+                        if let Some(ref mut metrics) = this.metrics {
+                            metrics.set_status(res.status());
+                        }
 
-                        if size.is_eof() {
-                            Poll::Ready(())
-                        } else {
+                        if !size.is_eof() && !*this.head {
                             *this.state = ServiceResponseState::SendPayload(
                                 stream,
                                 body.into_body(),
+                                sized_payload(size),
                             );
                             self.poll(cx)
+                        } else if !size.is_eof() && res.head().drain_head_body() {
+                            *this.state =
+                                ServiceResponseState::DrainPayload(body.into_body());
+                            self.poll(cx)
+                        } else {
+                            if let Some(metrics) = this.metrics.take() {
+                                metrics.end();
+                            }
+                            Poll::Ready(())
                         }
                     }
                 }
             }
-            ServiceResponseState::SendPayload(ref mut stream, ref mut body) => loop {
+            ServiceResponseState::SendPayload(
+                ref mut stream,
+                ref mut body,
+                ref mut remaining,
+            ) => loop {
                 loop {
                     if let Some(ref mut buffer) = this.buffer {
                         match stream.poll_capacity(cx) {
@@ -319,10 +700,17 @@ where
                                 let len = buffer.len();
                                 let bytes = buffer.split_to(std::cmp::min(cap, len));
 
+                                let size = bytes.len();
                                 if let Err(e) = stream.send_data(bytes, false) {
                                     warn!("{:?}", e);
                                     return Poll::Ready(());
-                                } else if !buffer.is_empty() {
+                                }
+                                this.timer.touch();
+                                if let Some(ref metrics) = this.metrics {
+                                    metrics.bytes_written(size);
+                                }
+
+                                if !buffer.is_empty() {
                                     let cap = std::cmp::min(buffer.len(), CHUNK_SIZE);
                                     stream.reserve_capacity(cap);
                                 } else {
@@ -338,12 +726,31 @@ where
                         match body.poll_next(cx) {
                             Poll::Pending => return Poll::Pending,
                             Poll::Ready(None) => {
+                                if remaining.map(|rem| rem != 0).unwrap_or(false) {
+                                    error!(
+                                        "Payload is shorter than declared Content-Length"
+                                    );
+                                    stream.send_reset(Reason::INTERNAL_ERROR);
+                                    return Poll::Ready(());
+                                }
                                 if let Err(e) = stream.send_data(Bytes::new(), true) {
                                     warn!("{:?}", e);
+                                } else if let Some(metrics) = this.metrics.take() {
+                                    metrics.end();
                                 }
                                 return Poll::Ready(());
                             }
                             Poll::Ready(Some(Ok(chunk))) => {
+                                if let Some(ref mut rem) = remaining {
+                                    if chunk.len() as u64 > *rem {
+                                        error!(
+                                            "Payload exceeds declared Content-Length"
+                                        );
+                                        stream.send_reset(Reason::INTERNAL_ERROR);
+                                        return Poll::Ready(());
+                                    }
+                                    *rem -= chunk.len() as u64;
+                                }
                                 stream.reserve_capacity(std::cmp::min(
                                     chunk.len(),
                                     CHUNK_SIZE,
@@ -358,6 +765,22 @@ where
                     }
                 }
             },
+            ServiceResponseState::DrainPayload(ref mut body) => loop {
+                match body.poll_next(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Some(Ok(_))) => this.timer.touch(),
+                    Poll::Ready(None) => {
+                        if let Some(metrics) = this.metrics.take() {
+                            metrics.end();
+                        }
+                        return Poll::Ready(());
+                    }
+                    Poll::Ready(Some(Err(e))) => {
+                        error!("Response payload stream error: {:?}", e);
+                        return Poll::Ready(());
+                    }
+                }
+            },
         }
     }
 }
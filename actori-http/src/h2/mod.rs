@@ -2,8 +2,10 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use actori_codec::{AsyncRead, AsyncWrite};
 use bytes::Bytes;
 use futures_core::Stream;
+use h2::server::{Builder, Handshake};
 use h2::RecvStream;
 
 mod dispatcher;
@@ -11,16 +13,57 @@ mod service;
 
 pub use self::dispatcher::Dispatcher;
 pub use self::service::H2Service;
+use crate::config::{PayloadLimits, ServiceConfig};
 use crate::error::PayloadError;
+use crate::metrics::MetricsHandle;
+
+/// Start server handshake, connection settings are taken from service config
+pub(crate) fn handshake<T>(io: T, config: &ServiceConfig) -> Handshake<T, Bytes>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut builder = Builder::new();
+    if let Some(max) = config.h2_limits().max_concurrent_reset_streams {
+        builder.max_concurrent_reset_streams(max);
+    }
+    builder.handshake(io)
+}
+
+/// Paths of resources pushed with the response, see `ResponseBuilder::push()`
+pub(crate) struct PushPromises(pub(crate) Vec<String>);
 
 /// H2 receive stream
 pub struct Payload {
     pl: RecvStream,
+    limit: u64,
+    size: u64,
+    overflow: bool,
+    metrics: Option<MetricsHandle>,
 }
 
 impl Payload {
     pub(crate) fn new(pl: RecvStream) -> Self {
-        Self { pl }
+        Self {
+            pl,
+            limit: 0,
+            size: 0,
+            overflow: false,
+            metrics: None,
+        }
+    }
+
+    /// Fail with `PayloadError::Overflow` if payload is bigger than limit,
+    /// `size` is payload size declared by the request.
+    pub(crate) fn limit(mut self, limits: &PayloadLimits, size: Option<u64>) -> Self {
+        self.limit = limits.max_size;
+        self.overflow = size.map(|size| limits.overflows(size)).unwrap_or(false);
+        self
+    }
+
+    /// Report received data to connection metrics
+    pub(crate) fn metrics(mut self, metrics: Option<MetricsHandle>) -> Self {
+        self.metrics = metrics;
+        self
     }
 }
 
@@ -32,12 +75,22 @@ impl Stream for Payload {
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
+        if this.overflow {
+            return Poll::Ready(Some(Err(PayloadError::Overflow)));
+        }
 
         match Pin::new(&mut this.pl).poll_data(cx) {
             Poll::Ready(Some(Ok(chunk))) => {
                 let len = chunk.len();
+                this.size += len as u64;
+                if let Some(ref metrics) = this.metrics {
+                    metrics.bytes_read(len);
+                }
                 if let Err(err) = this.pl.flow_control().release_capacity(len) {
                     Poll::Ready(Some(Err(err.into())))
+                } else if this.limit != 0 && this.size > this.limit {
+                    this.overflow = true;
+                    Poll::Ready(Some(Err(PayloadError::Overflow)))
                 } else {
                     Poll::Ready(Some(Ok(chunk)))
                 }
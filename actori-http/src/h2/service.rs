@@ -13,7 +13,7 @@ use actori_service::{
 use bytes::Bytes;
 use futures_core::ready;
 use futures_util::future::ok;
-use h2::server::{self, Handshake};
+use h2::server::Handshake;
 use log::error;
 
 use crate::body::MessageBody;
@@ -310,7 +310,7 @@ where
                 Some(self.cfg.clone()),
                 addr,
                 on_connect,
-                server::handshake(io),
+                super::handshake(io, &self.cfg),
             ),
         }
     }
@@ -129,6 +129,58 @@ impl IntoHeaderValue for Mime {
     }
 }
 
+/// A trait for any object that can be converted to a header name and value pair.
+///
+/// Implemented for typed headers and for `(name, value)` tuples.
+pub trait IntoHeaderPair: Sized {
+    /// Try to convert to a header name and value pair.
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError>;
+}
+
+impl<H: Header> IntoHeaderPair for H {
+    #[inline]
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError> {
+        let value = self.try_into().map_err(Into::into)?;
+        Ok((H::name(), value))
+    }
+}
+
+impl<V: IntoHeaderValue> IntoHeaderPair for (HeaderName, V) {
+    #[inline]
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError> {
+        let value = self.1.try_into().map_err(Into::into)?;
+        Ok((self.0, value))
+    }
+}
+
+impl<'a, V: IntoHeaderValue> IntoHeaderPair for (&'a HeaderName, V) {
+    #[inline]
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError> {
+        (self.0.clone(), self.1).try_into_header_pair()
+    }
+}
+
+impl<'a, V: IntoHeaderValue> IntoHeaderPair for (&'a str, V) {
+    #[inline]
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError> {
+        (HeaderName::try_from(self.0)?, self.1).try_into_header_pair()
+    }
+}
+
+impl<'a, V: IntoHeaderValue> IntoHeaderPair for (&'a [u8], V) {
+    #[inline]
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError> {
+        (HeaderName::try_from(self.0)?, self.1).try_into_header_pair()
+    }
+}
+
+impl<V: IntoHeaderValue> IntoHeaderPair for (String, V) {
+    #[inline]
+    fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), HttpError> {
+        (self.0.as_str(), self.1).try_into_header_pair()
+    }
+}
+
 /// Represents supported types of content encodings
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ContentEncoding {
@@ -142,6 +194,8 @@ pub enum ContentEncoding {
     Gzip,
     /// Indicates the identity function (i.e. no compression, nor modification)
     Identity,
+    /// Zstandard algorithm, supported with `compress-zstd` feature
+    Zstd,
 }
 
 impl ContentEncoding {
@@ -161,6 +215,7 @@ impl ContentEncoding {
             ContentEncoding::Br => "br",
             ContentEncoding::Gzip => "gzip",
             ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Zstd => "zstd",
             ContentEncoding::Identity | ContentEncoding::Auto => "identity",
         }
     }
@@ -169,6 +224,7 @@ impl ContentEncoding {
     /// default quality value
     pub fn quality(self) -> f64 {
         match self {
+            ContentEncoding::Zstd => 1.2,
             ContentEncoding::Br => 1.1,
             ContentEncoding::Gzip => 1.0,
             ContentEncoding::Deflate => 0.9,
@@ -187,6 +243,8 @@ impl<'a> From<&'a str> for ContentEncoding {
             ContentEncoding::Gzip
         } else if s.eq_ignore_ascii_case("deflate") {
             ContentEncoding::Deflate
+        } else if s.eq_ignore_ascii_case("zstd") {
+            ContentEncoding::Zstd
         } else {
             ContentEncoding::Identity
         }
@@ -392,6 +450,17 @@ impl From<http::HeaderMap> for HeaderMap {
     }
 }
 
+/// Convert HeaderMap to a http::HeaderMap
+impl From<HeaderMap> for http::HeaderMap {
+    fn from(map: HeaderMap) -> http::HeaderMap {
+        let mut new_map = http::HeaderMap::with_capacity(map.len());
+        for (h, v) in map.iter() {
+            new_map.append(h.clone(), v.clone());
+        }
+        new_map
+    }
+}
+
 // This encode set is used for HTTP header values and is defined at
 // https://tools.ietf.org/html/rfc5987#section-3.2
 pub(crate) const HTTP_VALUE: &AsciiSet = &CONTROLS
@@ -419,7 +488,9 @@ pub(crate) const HTTP_VALUE: &AsciiSet = &CONTROLS
 #[cfg(test)]
 mod tests {
     use super::shared::Charset;
-    use super::{parse_extended_value, ExtendedValue};
+    use super::{
+        parse_extended_value, ContentType, ExtendedValue, IntoHeaderPair, CONTENT_TYPE,
+    };
     use language_tags::LanguageTag;
 
     #[test]
@@ -502,4 +573,23 @@ mod tests {
             format!("{}", extended_value)
         );
     }
+
+    #[test]
+    fn test_into_header_pair() {
+        let (name, value) = (CONTENT_TYPE, "text/plain").try_into_header_pair().unwrap();
+        assert_eq!(name, CONTENT_TYPE);
+        assert_eq!(value, "text/plain");
+
+        let (name, _) = ("X-Test", 10usize).try_into_header_pair().unwrap();
+        assert_eq!(name, "x-test");
+
+        let (name, value) = ContentType(mime::APPLICATION_JSON)
+            .try_into_header_pair()
+            .unwrap();
+        assert_eq!(name, CONTENT_TYPE);
+        assert_eq!(value, "application/json");
+
+        assert!(("bad header", "value").try_into_header_pair().is_err());
+        assert!((CONTENT_TYPE, "bad\nvalue").try_into_header_pair().is_err());
+    }
 }
@@ -1,9 +1,11 @@
 use std::{io, mem, ptr, slice};
 
 use bytes::{BufMut, BytesMut};
+use http::header::CONTENT_LENGTH;
 use http::Version;
 
 use crate::extensions::Extensions;
+use crate::header::HeaderMap;
 
 const DEC_DIGITS_LUT: &[u8] = b"0001020304050607080910111213141516171819\
       2021222324252627282930313233343536373839\
@@ -113,6 +115,18 @@ pub fn write_content_length(mut n: usize, bytes: &mut BytesMut) {
     }
 }
 
+/// Parse `Content-Length` header, extra headers take precedence
+pub(crate) fn content_length(
+    headers: &HeaderMap,
+    extra_headers: Option<&HeaderMap>,
+) -> Option<u64> {
+    extra_headers
+        .and_then(|headers| headers.get(CONTENT_LENGTH))
+        .or_else(|| headers.get(CONTENT_LENGTH))
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.trim().parse().ok())
+}
+
 pub(crate) fn convert_usize(mut n: usize, bytes: &mut BytesMut) {
     let mut curr: isize = 39;
     let mut buf: [u8; 41] = unsafe { mem::MaybeUninit::uninit().assume_init() };
@@ -15,6 +15,7 @@ mod builder;
 pub mod client;
 mod cloneable;
 mod config;
+mod conn;
 #[cfg(feature = "compress")]
 pub mod encoding;
 mod extensions;
@@ -23,28 +24,36 @@ mod helpers;
 mod httpcodes;
 pub mod httpmessage;
 mod message;
+mod metrics;
 mod payload;
 mod request;
 mod response;
 mod service;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+mod tls;
 
 pub mod cookie;
 pub mod error;
 pub mod h1;
 pub mod h2;
 pub mod test;
+pub mod trace;
 pub mod ws;
 
 pub use self::builder::HttpServiceBuilder;
-pub use self::config::{KeepAlive, ServiceConfig};
+pub use self::config::{KeepAlive, ServiceConfig, DEFAULT_OPTIONS_ALLOW};
+pub use self::conn::{ListenerName, LocalAddr, PeerAddr, PeerCertificates};
 pub use self::error::{Error, ResponseError, Result};
-pub use self::extensions::Extensions;
+pub use self::extensions::{DynExtensions, DynNamespace, DynNamespaceMut, Extensions};
 pub use self::httpmessage::HttpMessage;
 pub use self::message::{Message, RequestHead, RequestHeadType, ResponseHead};
+pub use self::metrics::Metrics;
 pub use self::payload::{Payload, PayloadStream};
 pub use self::request::Request;
 pub use self::response::{Response, ResponseBuilder};
 pub use self::service::HttpService;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub use self::tls::TlsReload;
 
 pub mod http {
     //! Various HTTP related types
@@ -28,6 +28,8 @@ bitflags! {
         const EXPECT      = 0b0000_1000;
         const NO_CHUNKING = 0b0001_0000;
         const CAMEL_CASE  = 0b0010_0000;
+        const UNCHECKED_LENGTH = 0b0100_0000;
+        const NO_HEAD_DRAIN = 0b1000_0000;
     }
 }
 
@@ -46,6 +48,7 @@ pub struct RequestHead {
     pub headers: HeaderMap,
     pub extensions: RefCell<Extensions>,
     pub peer_addr: Option<net::SocketAddr>,
+    pub(crate) conn_data: Option<Rc<Extensions>>,
     flags: Flags,
 }
 
@@ -58,16 +61,56 @@ impl Default for RequestHead {
             headers: HeaderMap::with_capacity(16),
             flags: Flags::empty(),
             peer_addr: None,
+            conn_data: None,
             extensions: RefCell::new(Extensions::new()),
         }
     }
 }
 
+/// Convert `http::request::Parts` to a `RequestHead`.
+///
+/// `http::Extensions` of the request are stored as an item of head
+/// extensions, they are restored by the reverse conversion.
+impl From<http::request::Parts> for RequestHead {
+    fn from(parts: http::request::Parts) -> RequestHead {
+        let mut extensions = Extensions::new();
+        extensions.insert(parts.extensions);
+
+        RequestHead {
+            uri: parts.uri,
+            method: parts.method,
+            version: parts.version,
+            headers: parts.headers.into(),
+            extensions: RefCell::new(extensions),
+            ..Default::default()
+        }
+    }
+}
+
+/// Convert `RequestHead` to a `http::request::Parts`.
+///
+/// Only `http::Extensions` item of head extensions is converted, other
+/// extensions, peer address and connection data are dropped.
+impl From<RequestHead> for http::request::Parts {
+    fn from(mut head: RequestHead) -> http::request::Parts {
+        let (mut parts, _) = http::Request::new(()).into_parts();
+        parts.method = head.method;
+        parts.uri = head.uri;
+        parts.version = head.version;
+        parts.headers = head.headers.into();
+        if let Some(ext) = head.extensions.get_mut().remove::<http::Extensions>() {
+            parts.extensions = ext;
+        }
+        parts
+    }
+}
+
 impl Head for RequestHead {
     fn clear(&mut self) {
         self.flags = Flags::empty();
         self.headers.clear();
         self.extensions.borrow_mut().clear();
+        self.conn_data = None;
     }
 
     fn pool() -> &'static MessagePool<Self> {
@@ -88,6 +131,15 @@ impl RequestHead {
         self.extensions.borrow_mut()
     }
 
+    /// Connection data item.
+    ///
+    /// Connection data is shared by all requests of a connection, it
+    /// contains `PeerAddr`, `LocalAddr` and `ListenerName` items.
+    #[inline]
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.conn_data.as_ref().and_then(|data| data.get::<T>())
+    }
+
     /// Read the message headers.
     pub fn headers(&self) -> &HeaderMap {
         &self.headers
@@ -179,6 +231,12 @@ impl RequestHead {
     pub(crate) fn set_expect(&mut self) {
         self.flags.insert(Flags::EXPECT);
     }
+
+    #[inline]
+    /// Request is `OPTIONS` request with asterisk-form request target, i.e. `OPTIONS *`
+    pub fn is_options_asterisk(&self) -> bool {
+        self.method == Method::OPTIONS && self.uri == "*"
+    }
 }
 
 #[derive(Debug)]
@@ -260,8 +318,10 @@ impl ResponseHead {
     }
 
     #[inline]
-    /// Set connection type of the message
+    /// Set connection type of the message, replaces previously set type
     pub fn set_connection_type(&mut self, ctype: ConnectionType) {
+        self.flags
+            .remove(Flags::CLOSE | Flags::KEEP_ALIVE | Flags::UPGRADE);
         match ctype {
             ConnectionType::Close => self.flags.insert(Flags::CLOSE),
             ConnectionType::KeepAlive => self.flags.insert(Flags::KEEP_ALIVE),
@@ -308,6 +368,15 @@ impl ResponseHead {
         }
     }
 
+    /// Copy status, headers and flags to other head, extensions are not copied
+    pub(crate) fn copy_to(&self, other: &mut ResponseHead) {
+        other.status = self.status;
+        other.version = self.version;
+        other.headers = self.headers.clone();
+        other.reason = self.reason;
+        other.flags = self.flags;
+    }
+
     #[inline]
     pub(crate) fn ctype(&self) -> Option<ConnectionType> {
         if self.flags.contains(Flags::CLOSE) {
@@ -336,6 +405,44 @@ impl ResponseHead {
             self.flags.remove(Flags::NO_CHUNKING);
         }
     }
+
+    #[inline]
+    /// Check if streaming payload is validated against `Content-Length` header
+    pub fn length_checked(&self) -> bool {
+        !self.flags.contains(Flags::UNCHECKED_LENGTH)
+    }
+
+    #[inline]
+    /// Send `Content-Length` header as is, without payload validation
+    pub fn unchecked_length(&mut self, val: bool) {
+        if val {
+            self.flags.insert(Flags::UNCHECKED_LENGTH);
+        } else {
+            self.flags.remove(Flags::UNCHECKED_LENGTH);
+        }
+    }
+
+    #[inline]
+    /// Check if body of `HEAD` request response is read by dispatcher
+    pub fn drain_head_body(&self) -> bool {
+        !self.flags.contains(Flags::NO_HEAD_DRAIN)
+    }
+
+    #[inline]
+    /// Set body draining for `HEAD` request response.
+    ///
+    /// Response body of `HEAD` request is never sent to the peer, headers
+    /// are sent as for `GET` request, including `Content-Length`. By default
+    /// dispatcher reads body to the end and discards it, so body wrappers
+    /// see full body size. If draining is disabled, body is dropped right
+    /// after response head is sent.
+    pub fn set_drain_head_body(&mut self, val: bool) {
+        if val {
+            self.flags.remove(Flags::NO_HEAD_DRAIN);
+        } else {
+            self.flags.insert(Flags::NO_HEAD_DRAIN);
+        }
+    }
 }
 
 pub struct Message<T: Head> {
@@ -493,3 +600,33 @@ impl BoxedResponsePool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::CONTENT_TYPE;
+
+    #[test]
+    fn test_request_head_parts() {
+        let (mut parts, _) = http::Request::post("/test?q=1")
+            .header(CONTENT_TYPE, "text/plain")
+            .header("x-test", "1")
+            .header("x-test", "2")
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts.extensions.insert(10u32);
+
+        let head = RequestHead::from(parts);
+        assert_eq!(head.method, Method::POST);
+        assert_eq!(head.uri, "/test?q=1");
+        assert_eq!(head.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+        assert_eq!(head.headers().get_all("x-test").count(), 2);
+
+        let parts = http::request::Parts::from(head);
+        assert_eq!(parts.method, Method::POST);
+        assert_eq!(parts.uri, "/test?q=1");
+        assert_eq!(parts.headers.get_all("x-test").iter().count(), 2);
+        assert_eq!(parts.extensions.get::<u32>(), Some(&10));
+    }
+}
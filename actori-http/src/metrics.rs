@@ -0,0 +1,239 @@
+//! Connection level metrics
+use std::net;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use http::StatusCode;
+
+use crate::config::ServiceConfig;
+use crate::message::RequestHead;
+use crate::Protocol;
+
+/// Connection level instrumentation hooks.
+///
+/// Metrics are registered with `HttpServiceBuilder::metrics()`, callbacks
+/// are called by http/1 and http/2 dispatchers. All methods have empty
+/// default implementations.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use actori_http::{Metrics, Protocol};
+///
+/// #[derive(Default)]
+/// struct Connections(AtomicUsize);
+///
+/// impl Metrics for Connections {
+///     fn connection_open(&self, _: Protocol, _: Option<std::net::SocketAddr>) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn connection_close(&self, _: Protocol) {
+///         self.0.fetch_sub(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// // shared between workers
+/// let metrics = Arc::new(Connections::default());
+/// ```
+pub trait Metrics {
+    /// Connection is accepted, for tls connections after handshake.
+    fn connection_open(&self, _protocol: Protocol, _peer_addr: Option<net::SocketAddr>) {
+    }
+
+    /// Connection is closed.
+    ///
+    /// Upgraded connections, i.e. websockets, are reported closed once
+    /// the connection is passed to the upgrade handler.
+    fn connection_close(&self, _protocol: Protocol) {}
+
+    /// Request head is received.
+    fn request_start(&self, _protocol: Protocol, _head: &RequestHead) {}
+
+    /// Response is sent, `status` is `None` if connection or stream is
+    /// closed before the whole response is sent.
+    fn request_end(
+        &self,
+        _protocol: Protocol,
+        _status: Option<StatusCode>,
+        _duration: Duration,
+    ) {
+    }
+
+    /// Bytes are read from the connection.
+    ///
+    /// For http/2 connections only bytes of request payloads are reported.
+    fn bytes_read(&self, _protocol: Protocol, _size: usize) {}
+
+    /// Bytes are written to the connection.
+    ///
+    /// For http/2 connections only bytes of response payloads are reported.
+    fn bytes_written(&self, _protocol: Protocol, _size: usize) {}
+}
+
+impl<M: Metrics + ?Sized> Metrics for Rc<M> {
+    fn connection_open(&self, protocol: Protocol, peer_addr: Option<net::SocketAddr>) {
+        (**self).connection_open(protocol, peer_addr)
+    }
+
+    fn connection_close(&self, protocol: Protocol) {
+        (**self).connection_close(protocol)
+    }
+
+    fn request_start(&self, protocol: Protocol, head: &RequestHead) {
+        (**self).request_start(protocol, head)
+    }
+
+    fn request_end(
+        &self,
+        protocol: Protocol,
+        status: Option<StatusCode>,
+        duration: Duration,
+    ) {
+        (**self).request_end(protocol, status, duration)
+    }
+
+    fn bytes_read(&self, protocol: Protocol, size: usize) {
+        (**self).bytes_read(protocol, size)
+    }
+
+    fn bytes_written(&self, protocol: Protocol, size: usize) {
+        (**self).bytes_written(protocol, size)
+    }
+}
+
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+    fn connection_open(&self, protocol: Protocol, peer_addr: Option<net::SocketAddr>) {
+        (**self).connection_open(protocol, peer_addr)
+    }
+
+    fn connection_close(&self, protocol: Protocol) {
+        (**self).connection_close(protocol)
+    }
+
+    fn request_start(&self, protocol: Protocol, head: &RequestHead) {
+        (**self).request_start(protocol, head)
+    }
+
+    fn request_end(
+        &self,
+        protocol: Protocol,
+        status: Option<StatusCode>,
+        duration: Duration,
+    ) {
+        (**self).request_end(protocol, status, duration)
+    }
+
+    fn bytes_read(&self, protocol: Protocol, size: usize) {
+        (**self).bytes_read(protocol, size)
+    }
+
+    fn bytes_written(&self, protocol: Protocol, size: usize) {
+        (**self).bytes_written(protocol, size)
+    }
+}
+
+/// Metrics of a connection
+#[derive(Clone)]
+pub(crate) struct MetricsHandle {
+    metrics: Rc<dyn Metrics>,
+    protocol: Protocol,
+}
+
+impl MetricsHandle {
+    pub(crate) fn bytes_read(&self, size: usize) {
+        if size > 0 {
+            self.metrics.bytes_read(self.protocol, size);
+        }
+    }
+
+    pub(crate) fn bytes_written(&self, size: usize) {
+        if size > 0 {
+            self.metrics.bytes_written(self.protocol, size);
+        }
+    }
+
+    /// Report request start, request end is reported by returned value
+    pub(crate) fn request_start(&self, head: &RequestHead) -> RequestMetrics {
+        self.metrics.request_start(self.protocol, head);
+        RequestMetrics {
+            handle: self.clone(),
+            start: Instant::now(),
+            status: None,
+            done: false,
+        }
+    }
+}
+
+/// Reports connection open on creation and connection close on drop
+pub(crate) struct ConnectionMetrics(MetricsHandle);
+
+impl ConnectionMetrics {
+    /// Connection metrics, if metrics are configured
+    pub(crate) fn new(
+        config: &ServiceConfig,
+        protocol: Protocol,
+        peer_addr: Option<net::SocketAddr>,
+    ) -> Option<Self> {
+        config.metrics().map(|metrics| {
+            metrics.connection_open(protocol, peer_addr);
+            ConnectionMetrics(MetricsHandle {
+                metrics: metrics.clone(),
+                protocol,
+            })
+        })
+    }
+
+    pub(crate) fn handle(&self) -> &MetricsHandle {
+        &self.0
+    }
+}
+
+impl Drop for ConnectionMetrics {
+    fn drop(&mut self) {
+        self.0.metrics.connection_close(self.0.protocol);
+    }
+}
+
+/// Reports request end, request that is dropped before `end()` is reported
+/// without status
+pub(crate) struct RequestMetrics {
+    handle: MetricsHandle,
+    start: Instant,
+    status: Option<StatusCode>,
+    done: bool,
+}
+
+impl RequestMetrics {
+    /// Response head is sent
+    pub(crate) fn set_status(&mut self, status: StatusCode) {
+        self.status = Some(status);
+    }
+
+    /// Response is sent
+    pub(crate) fn end(mut self) {
+        self.report(self.status);
+    }
+
+    pub(crate) fn bytes_written(&self, size: usize) {
+        self.handle.bytes_written(size);
+    }
+
+    fn report(&mut self, status: Option<StatusCode>) {
+        if !self.done {
+            self.done = true;
+            self.handle.metrics.request_end(
+                self.handle.protocol,
+                status,
+                self.start.elapsed(),
+            );
+        }
+    }
+}
+
+impl Drop for RequestMetrics {
+    fn drop(&mut self) {
+        self.report(None);
+    }
+}
@@ -6,6 +6,7 @@ use futures_core::Stream;
 use h2::RecvStream;
 
 use crate::error::PayloadError;
+use crate::header::HeaderMap;
 
 /// Type represent boxed payload
 pub type PayloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
@@ -47,6 +48,16 @@ impl<S> Payload<S> {
     pub fn take(&mut self) -> Payload<S> {
         std::mem::replace(self, Payload::None)
     }
+
+    /// Trailer fields of h1 chunked payload.
+    ///
+    /// Trailers are available after the payload is read to the end.
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Payload::H1(ref mut pl) => pl.take_trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl<S> Stream for Payload<S>
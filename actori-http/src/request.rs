@@ -162,6 +162,15 @@ impl<P> Request<P> {
     pub fn peer_addr(&self) -> Option<net::SocketAddr> {
         self.head().peer_addr
     }
+
+    /// Connection data item.
+    ///
+    /// Connection data is shared by all requests of a connection, check
+    /// `RequestHead::conn_data()` for available items.
+    #[inline]
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.head().conn_data()
+    }
 }
 
 impl<P> fmt::Debug for Request<P> {
@@ -9,14 +9,18 @@ use actori_service::{pipeline_factory, IntoServiceFactory, Service, ServiceFacto
 use bytes::Bytes;
 use futures_core::{ready, Future};
 use futures_util::future::ok;
-use h2::server::{self, Handshake};
+use h2::server::Handshake;
 use pin_project::{pin_project, project};
 
 use crate::body::MessageBody;
 use crate::builder::HttpServiceBuilder;
 use crate::cloneable::CloneableService;
 use crate::config::{KeepAlive, ServiceConfig};
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::conn::PeerCertificates;
 use crate::error::{DispatchError, Error};
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::extensions::Extensions;
 use crate::helpers::DataFactory;
 use crate::request::Request;
 use crate::response::Response;
@@ -152,6 +156,44 @@ where
     }
 }
 
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+impl<T: 'static, S, B, X, U> HttpService<T, S, B, X, U> {
+    /// Store tls peer certificates in request extensions along with
+    /// on connect callback data
+    fn peer_certificates<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&T) -> Option<PeerCertificates> + 'static,
+    {
+        let on_connect = self.on_connect.take();
+        self.on_connect = Some(rc::Rc::new(move |io: &T| {
+            Box::new(TlsConnectData {
+                certs: f(io),
+                data: on_connect.as_ref().map(|f| f(io)),
+            }) as Box<dyn DataFactory>
+        }));
+        self
+    }
+}
+
+/// On connect data of tls connection
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+struct TlsConnectData {
+    certs: Option<PeerCertificates>,
+    data: Option<Box<dyn DataFactory>>,
+}
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+impl DataFactory for TlsConnectData {
+    fn set(&self, ext: &mut Extensions) {
+        if let Some(ref certs) = self.certs {
+            ext.insert(certs.clone());
+        }
+        if let Some(ref data) = self.data {
+            data.set(ext);
+        }
+    }
+}
+
 impl<S, B, X, U> HttpService<TcpStream, S, B, X, U>
 where
     S: ServiceFactory<Config = (), Request = Request>,
@@ -183,7 +225,12 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        pipeline_factory(|io: TcpStream| {
+        let nodelay = self.cfg.write_batch().is_some();
+        pipeline_factory(move |io: TcpStream| {
+            // writes are batched by dispatcher, no need to delay them again
+            if nodelay {
+                let _ = io.set_nodelay(true);
+            }
             let peer_addr = io.peer_addr().ok();
             ok((io, Protocol::Http1, peer_addr))
         })
@@ -196,6 +243,9 @@ mod openssl {
     use super::*;
     use actori_tls::openssl::{Acceptor, SslAcceptor, SslStream};
     use actori_tls::{openssl::HandshakeError, SslError};
+    use actori_utils::timeout::TimeoutError;
+
+    use crate::tls::{HandshakeGuard, Reload, TlsReload};
 
     impl<S, B, X, U> HttpService<SslStream<TcpStream>, S, B, X, U>
     where
@@ -219,20 +269,51 @@ mod openssl {
         <U::Service as Service>::Future: 'static,
     {
         /// Create openssl based service
-        pub fn openssl(
+        ///
+        /// Handshakes are limited by tls settings of the service configuration.
+        /// Verified client certificates are stored in request extensions
+        /// as `PeerCertificates`. Acceptor could be replaced at runtime
+        /// if it is passed as `TlsReload` handle.
+        pub fn openssl<A: Into<TlsReload<SslAcceptor>>>(
             self,
-            acceptor: SslAcceptor,
+            acceptor: A,
         ) -> impl ServiceFactory<
             Config = (),
             Request = TcpStream,
             Response = (),
-            Error = SslError<HandshakeError<TcpStream>, DispatchError>,
+            Error = SslError<TimeoutError<HandshakeError<TcpStream>>, DispatchError>,
             InitError = (),
         > {
+            let limits = self.cfg.tls_limits().clone();
+            let service = self.peer_certificates(|io: &SslStream<TcpStream>| {
+                let ssl = io.ssl();
+                // X509_V_OK
+                if ssl.verify_result().as_raw() != 0 {
+                    return None;
+                }
+                let leaf = ssl.peer_certificate()?.to_der().ok()?;
+
+                // server side chain does not include leaf certificate
+                let mut chain = vec![leaf];
+                if let Some(certs) = ssl.peer_cert_chain() {
+                    for cert in certs {
+                        if let Ok(der) = cert.to_der() {
+                            if der != chain[0] {
+                                chain.push(der);
+                            }
+                        }
+                    }
+                }
+                Some(PeerCertificates::new(chain))
+            });
+
             pipeline_factory(
-                Acceptor::new(acceptor)
-                    .map_err(SslError::Ssl)
-                    .map_init_err(|_| panic!()),
+                HandshakeGuard::new(
+                    Reload::new(acceptor.into(), Acceptor::new),
+                    &limits,
+                )
+                .map_err(SslError::Ssl)
+                .map_init_err(|_| panic!()),
             )
             .and_then(|io: SslStream<TcpStream>| {
                 let proto = if let Some(protos) = io.ssl().selected_alpn_protocol() {
@@ -247,7 +328,7 @@ mod openssl {
                 let peer_addr = io.get_ref().peer_addr().ok();
                 ok((io, proto, peer_addr))
             })
-            .and_then(self.map_err(SslError::Service))
+            .and_then(service.map_err(SslError::Service))
         }
     }
 }
@@ -257,8 +338,11 @@ mod rustls {
     use super::*;
     use actori_tls::rustls::{Acceptor, ServerConfig, Session, TlsStream};
     use actori_tls::SslError;
+    use actori_utils::timeout::TimeoutError;
     use std::io;
 
+    use crate::tls::{HandshakeGuard, Reload, TlsReload};
+
     impl<S, B, X, U> HttpService<TlsStream<TcpStream>, S, B, X, U>
     where
         S: ServiceFactory<Config = (), Request = Request>,
@@ -280,10 +364,17 @@ mod rustls {
         U::InitError: fmt::Debug,
         <U::Service as Service>::Future: 'static,
     {
-        /// Create openssl based service
-        pub fn rustls(
+        /// Create rustls based service
+        ///
+        /// Handshakes are limited by tls settings of the service configuration,
+        /// handshake timeout is reported as `io::ErrorKind::TimedOut` error.
+        /// Client certificates verified by `ServerConfig` client verifier
+        /// are stored in request extensions as `PeerCertificates`.
+        /// Configuration could be replaced at runtime if it is passed
+        /// as `TlsReload` handle.
+        pub fn rustls<C: Into<TlsReload<ServerConfig>>>(
             self,
-            mut config: ServerConfig,
+            config: C,
         ) -> impl ServiceFactory<
             Config = (),
             Request = TcpStream,
@@ -291,12 +382,37 @@ mod rustls {
             Error = SslError<io::Error, DispatchError>,
             InitError = (),
         > {
-            let protos = vec!["h2".to_string().into(), "http/1.1".to_string().into()];
-            config.set_protocols(&protos);
+            let acceptor = |mut config: ServerConfig| {
+                let protos =
+                    vec!["h2".to_string().into(), "http/1.1".to_string().into()];
+                config.set_protocols(&protos);
+                Acceptor::new(config)
+            };
+
+            let limits = self.cfg.tls_limits().clone();
+            let service = self.peer_certificates(|io: &TlsStream<TcpStream>| {
+                io.get_ref()
+                    .1
+                    .get_peer_certificates()
+                    .filter(|certs| !certs.is_empty())
+                    .map(|certs| {
+                        PeerCertificates::new(
+                            certs.into_iter().map(|cert| cert.0).collect(),
+                        )
+                    })
+            });
 
             pipeline_factory(
-                Acceptor::new(config)
-                    .map_err(SslError::Ssl)
+                HandshakeGuard::new(Reload::new(config.into(), acceptor), &limits)
+                    .map_err(|e| {
+                        SslError::Ssl(match e {
+                            TimeoutError::Service(e) => e,
+                            TimeoutError::Timeout => io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                "Tls handshake timeout",
+                            ),
+                        })
+                    })
                     .map_init_err(|_| panic!()),
             )
             .and_then(|io: TlsStream<TcpStream>| {
@@ -312,14 +428,14 @@ mod rustls {
                 let peer_addr = io.get_ref().0.peer_addr().ok();
                 ok((io, proto, peer_addr))
             })
-            .and_then(self.map_err(SslError::Service))
+            .and_then(service.map_err(SslError::Service))
         }
     }
 }
 
 impl<T, S, B, X, U> ServiceFactory for HttpService<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: ServiceFactory<Config = (), Request = Request>,
     S::Error: Into<Error> + 'static,
     S::InitError: fmt::Debug,
@@ -385,7 +501,7 @@ pub struct HttpServiceResponse<
 
 impl<T, S, B, X, U> Future for HttpServiceResponse<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: ServiceFactory<Request = Request>,
     S::Error: Into<Error> + 'static,
     S::InitError: fmt::Debug,
@@ -484,7 +600,7 @@ where
 
 impl<T, S, B, X, U> Service for HttpServiceHandler<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: Service<Request = Request>,
     S::Error: Into<Error> + 'static,
     S::Future: 'static,
@@ -552,7 +668,7 @@ where
         match proto {
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake(Some((
-                    server::handshake(io),
+                    crate::h2::handshake(io, &self.cfg),
                     self.cfg.clone(),
                     self.srv.clone(),
                     on_connect,
@@ -580,7 +696,7 @@ where
     S: Service<Request = Request>,
     S::Future: 'static,
     S::Error: Into<Error>,
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     B: MessageBody,
     X: Service<Request = Request, Response = Request>,
     X::Error: Into<Error>,
@@ -603,7 +719,7 @@ where
 #[pin_project]
 pub struct HttpServiceHandlerResponse<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: Service<Request = Request>,
     S::Error: Into<Error> + 'static,
     S::Future: 'static,
@@ -620,7 +736,7 @@ where
 
 impl<T, S, B, X, U> Future for HttpServiceHandlerResponse<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: Service<Request = Request>,
     S::Error: Into<Error> + 'static,
     S::Future: 'static,
@@ -640,7 +756,7 @@ where
 
 impl<T, S, B, X, U> State<T, S, B, X, U>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin + 'static,
     S: Service<Request = Request>,
     S::Error: Into<Error> + 'static,
     S::Response: Into<Response<B>> + 'static,
@@ -3,16 +3,20 @@ use std::convert::TryFrom;
 use std::fmt::Write as FmtWrite;
 use std::io::{self, Read, Write};
 use std::pin::Pin;
+use std::rc::Rc;
 use std::str::FromStr;
 use std::task::{Context, Poll};
 
-use actori_codec::{AsyncRead, AsyncWrite};
+use actori_codec::{AsyncRead, AsyncWrite, Decoder};
 use bytes::{Bytes, BytesMut};
 use http::header::{self, HeaderName, HeaderValue};
 use http::{Error as HttpError, Method, Uri, Version};
 use percent_encoding::percent_encode;
 
 use crate::cookie::{Cookie, CookieJar, USERINFO};
+use crate::error::ParseError;
+use crate::extensions::Extensions;
+use crate::h1::{Codec, Message, MessageType};
 use crate::header::HeaderMap;
 use crate::header::{Header, IntoHeaderValue};
 use crate::payload::Payload;
@@ -50,6 +54,7 @@ struct Inner {
     headers: HeaderMap,
     cookies: CookieJar,
     payload: Option<Payload>,
+    conn_data: Extensions,
 }
 
 impl Default for TestRequest {
@@ -61,6 +66,7 @@ impl Default for TestRequest {
             headers: HeaderMap::new(),
             cookies: CookieJar::new(),
             payload: None,
+            conn_data: Extensions::new(),
         }))
     }
 }
@@ -86,6 +92,51 @@ impl TestRequest {
         TestRequest::default().header(key, value).take()
     }
 
+    /// Create TestRequest from raw HTTP/1 request bytes.
+    ///
+    /// Request head and payload are parsed by the same decoder h1 server
+    /// uses, parse error is returned as is. Incomplete request returns
+    /// `ParseError::Incomplete`, bytes after the end of the first request
+    /// are ignored.
+    pub fn from_raw<T: AsRef<[u8]>>(data: T) -> Result<TestRequest, ParseError> {
+        let mut buf = BytesMut::from(data.as_ref());
+        let mut codec = Codec::default();
+
+        let req = match codec.decode(&mut buf)? {
+            Some(Message::Item(req)) => req,
+            _ => return Err(ParseError::Incomplete),
+        };
+
+        let mut body = BytesMut::new();
+        match codec.message_type() {
+            MessageType::None => (),
+            MessageType::Payload => loop {
+                match codec.decode(&mut buf)? {
+                    Some(Message::Chunk(Some(chunk))) => body.extend_from_slice(&chunk),
+                    Some(Message::Chunk(None)) => break,
+                    _ => return Err(ParseError::Incomplete),
+                }
+            },
+            // upgrade and connect requests own the rest of the stream
+            MessageType::Stream => body.extend_from_slice(&buf),
+        }
+
+        let head = req.head();
+        let mut test_req = TestRequest(Some(Inner {
+            method: head.method.clone(),
+            uri: head.uri.clone(),
+            version: head.version,
+            headers: head.headers.clone(),
+            cookies: CookieJar::new(),
+            payload: None,
+            conn_data: Extensions::new(),
+        }));
+        if !body.is_empty() {
+            test_req.set_payload(body.freeze());
+        }
+        Ok(test_req)
+    }
+
     /// Set HTTP version of this request
     pub fn version(&mut self, ver: Version) -> &mut Self {
         parts(&mut self.0).version = ver;
@@ -143,6 +194,12 @@ impl TestRequest {
         self
     }
 
+    /// Set connection data item
+    pub fn conn_data<T: 'static>(&mut self, data: T) -> &mut Self {
+        parts(&mut self.0).conn_data.insert(data);
+        self
+    }
+
     pub fn take(&mut self) -> TestRequest {
         TestRequest(self.0.take())
     }
@@ -162,6 +219,7 @@ impl TestRequest {
         head.method = inner.method;
         head.version = inner.version;
         head.headers = inner.headers;
+        head.conn_data = Some(Rc::new(inner.conn_data));
 
         let mut cookie = String::new();
         for c in inner.cookies.delta() {
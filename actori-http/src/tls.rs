@@ -0,0 +1,355 @@
+//! Tls handshake protection and acceptor reload
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{fmt, future::Future, pin::Pin};
+
+use actori_rt::time::delay_for;
+use actori_service::{Service, ServiceFactory};
+use actori_utils::task::LocalWaker;
+use actori_utils::timeout::TimeoutError;
+use futures_util::future::{select, Either, FutureExt, LocalBoxFuture};
+
+use crate::config::TlsLimits;
+
+/// Limits time and number of concurrent handshakes of tls acceptor
+pub(crate) struct HandshakeGuard<T> {
+    factory: T,
+    limits: TlsLimits,
+}
+
+impl<T> HandshakeGuard<T> {
+    pub(crate) fn new(factory: T, limits: &TlsLimits) -> Self {
+        HandshakeGuard {
+            factory,
+            limits: limits.clone(),
+        }
+    }
+}
+
+impl<T> ServiceFactory for HandshakeGuard<T>
+where
+    T: ServiceFactory<Config = ()>,
+    T::Future: 'static,
+    T::Service: 'static,
+{
+    type Config = ();
+    type Request = T::Request;
+    type Response = T::Response;
+    type Error = TimeoutError<T::Error>;
+    type InitError = T::InitError;
+    type Service = HandshakeGuardService<T::Service>;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let limits = self.limits.clone();
+        self.factory
+            .new_service(())
+            .map(move |res| {
+                res.map(|service| HandshakeGuardService {
+                    service,
+                    timeout: if limits.handshake_timeout == 0 {
+                        None
+                    } else {
+                        Some(Duration::from_millis(limits.handshake_timeout))
+                    },
+                    max: limits.max_handshakes,
+                    inflight: Rc::new(Inflight {
+                        count: Cell::new(0),
+                        waker: LocalWaker::new(),
+                    }),
+                })
+            })
+            .boxed_local()
+    }
+}
+
+/// Number of handshakes in progress
+struct Inflight {
+    count: Cell<usize>,
+    waker: LocalWaker,
+}
+
+/// Decrements number of handshakes once handshake is done
+struct InflightGuard(Rc<Inflight>);
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.0.count.set(self.0.count.get() - 1);
+        self.0.waker.wake();
+    }
+}
+
+pub(crate) struct HandshakeGuardService<S> {
+    service: S,
+    timeout: Option<Duration>,
+    max: usize,
+    inflight: Rc<Inflight>,
+}
+
+impl<S> Service for HandshakeGuardService<S>
+where
+    S: Service,
+    S::Future: 'static,
+{
+    type Request = S::Request;
+    type Response = S::Response;
+    type Error = TimeoutError<S::Error>;
+    type Future = LocalBoxFuture<'static, Result<S::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.max != 0 && self.inflight.count.get() >= self.max {
+            self.inflight.waker.register(cx.waker());
+            return Poll::Pending;
+        }
+        self.service.poll_ready(cx).map_err(TimeoutError::Service)
+    }
+
+    fn call(&mut self, req: S::Request) -> Self::Future {
+        self.inflight.count.set(self.inflight.count.get() + 1);
+        let guard = InflightGuard(self.inflight.clone());
+        let fut = self.service.call(req);
+        let timeout = self.timeout;
+
+        async move {
+            let _guard = guard;
+            if let Some(timeout) = timeout {
+                match select(Box::pin(fut), delay_for(timeout)).await {
+                    Either::Left((res, _)) => res.map_err(TimeoutError::Service),
+                    Either::Right(_) => {
+                        trace!("Tls handshake timeout");
+                        Err(TimeoutError::Timeout)
+                    }
+                }
+            } else {
+                fut.await.map_err(TimeoutError::Service)
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Tls acceptor configuration that could be replaced at runtime.
+///
+/// Handle is shared between workers, configuration set with `TlsReload::set()`
+/// is used for new connections, established connections are not affected.
+/// I.e. renewed certificates could be loaded without server restart.
+pub struct TlsReload<C> {
+    inner: Arc<ReloadInner<C>>,
+}
+
+struct ReloadInner<C> {
+    generation: AtomicUsize,
+    config: RwLock<C>,
+}
+
+impl<C: Clone> TlsReload<C> {
+    /// Create handle with initial configuration
+    pub fn new(config: C) -> Self {
+        TlsReload {
+            inner: Arc::new(ReloadInner {
+                generation: AtomicUsize::new(0),
+                config: RwLock::new(config),
+            }),
+        }
+    }
+
+    /// Replace configuration, new connections use new configuration
+    pub fn set(&self, config: C) {
+        *self.inner.config.write().unwrap() = config;
+        self.inner.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Current configuration
+    pub fn get(&self) -> C {
+        self.inner.config.read().unwrap().clone()
+    }
+
+    fn generation(&self) -> usize {
+        self.inner.generation.load(Ordering::Acquire)
+    }
+}
+
+impl<C> Clone for TlsReload<C> {
+    fn clone(&self) -> Self {
+        TlsReload {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<C: Clone> From<C> for TlsReload<C> {
+    fn from(config: C) -> Self {
+        TlsReload::new(config)
+    }
+}
+
+impl<C> fmt::Debug for TlsReload<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsReload")
+            .field("generation", &self.inner.generation.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Acceptor factory that re-creates acceptor service once configuration
+/// of `TlsReload` changes
+pub(crate) struct Reload<C, F> {
+    config: TlsReload<C>,
+    factory: Rc<F>,
+}
+
+impl<C, F> Reload<C, F> {
+    pub(crate) fn new(config: TlsReload<C>, factory: F) -> Self {
+        Reload {
+            config,
+            factory: Rc::new(factory),
+        }
+    }
+}
+
+impl<C, F, T> ServiceFactory for Reload<C, F>
+where
+    C: Clone + 'static,
+    F: Fn(C) -> T + 'static,
+    T: ServiceFactory<Config = ()> + 'static,
+    T::Future: 'static,
+    T::Service: 'static,
+    T::InitError: fmt::Debug,
+{
+    type Config = ();
+    type Request = T::Request;
+    type Response = T::Response;
+    type Error = T::Error;
+    type InitError = T::InitError;
+    type Service = ReloadService<C, F, T>;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let config = self.config.clone();
+        let factory = self.factory.clone();
+        let generation = config.generation();
+        let fut = (factory)(config.get()).new_service(());
+
+        async move {
+            let service = fut.await?;
+            Ok(ReloadService {
+                config,
+                factory,
+                generation,
+                service,
+                pending: None,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+pub(crate) struct ReloadService<C, F, T: ServiceFactory> {
+    config: TlsReload<C>,
+    factory: Rc<F>,
+    generation: usize,
+    service: T::Service,
+    pending: Option<Pin<Box<dyn Future<Output = Result<T::Service, T::InitError>>>>>,
+}
+
+impl<C, F, T> Service for ReloadService<C, F, T>
+where
+    C: Clone + 'static,
+    F: Fn(C) -> T + 'static,
+    T: ServiceFactory<Config = ()> + 'static,
+    T::Future: 'static,
+    T::Service: 'static,
+    T::InitError: fmt::Debug,
+{
+    type Request = T::Request;
+    type Response = T::Response;
+    type Error = T::Error;
+    type Future = <T::Service as Service>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.pending.is_none() && self.generation != self.config.generation() {
+            self.generation = self.config.generation();
+            let fut = (self.factory)(self.config.get()).new_service(());
+            self.pending = Some(Box::pin(fut));
+        }
+
+        if let Some(ref mut fut) = self.pending {
+            match fut.as_mut().poll(cx) {
+                Poll::Ready(Ok(service)) => {
+                    trace!("Tls acceptor is reloaded");
+                    self.service = service;
+                }
+                Poll::Ready(Err(e)) => {
+                    // keep previous acceptor
+                    error!("Can not reload tls acceptor: {:?}", e);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+            self.pending = None;
+        }
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: T::Request) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_service::{fn_factory, fn_service};
+    use futures_util::future::{ok, pending, poll_fn, Either as FEither};
+
+    use super::*;
+
+    #[actori_rt::test]
+    async fn test_reload() {
+        let config = TlsReload::new("first");
+        let factory = Reload::new(config.clone(), |name: &'static str| {
+            fn_factory(move || ok::<_, ()>(fn_service(move |_: ()| ok::<_, ()>(name))))
+        });
+        let mut srv = factory.new_service(()).await.unwrap();
+        assert_eq!(srv.call(()).await, Ok("first"));
+
+        config.set("second");
+        assert_eq!(config.get(), "second");
+        assert!(poll_fn(|cx| Poll::Ready(srv.poll_ready(cx).is_ready())).await);
+        assert_eq!(srv.call(()).await, Ok("second"));
+    }
+
+    #[actori_rt::test]
+    async fn test_handshake_timeout() {
+        let limits = TlsLimits {
+            handshake_timeout: 10,
+            max_handshakes: 1,
+        };
+        let factory = HandshakeGuard::new(
+            fn_factory(|| {
+                ok::<_, ()>(fn_service(|fast: bool| {
+                    if fast {
+                        FEither::Left(ok::<_, ()>(()))
+                    } else {
+                        FEither::Right(pending())
+                    }
+                }))
+            }),
+            &limits,
+        );
+        let mut srv = factory.new_service(()).await.unwrap();
+
+        assert!(srv.call(true).await.is_ok());
+
+        let fut = srv.call(false);
+        // handshake slot is taken
+        assert!(poll_fn(|cx| Poll::Ready(srv.poll_ready(cx).is_pending())).await);
+        match fut.await {
+            Err(TimeoutError::Timeout) => (),
+            _ => panic!(),
+        }
+        assert!(poll_fn(|cx| Poll::Ready(srv.poll_ready(cx).is_ready())).await);
+    }
+}
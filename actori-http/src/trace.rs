@@ -0,0 +1,289 @@
+//! Distributed tracing context propagation
+use std::cell::Cell;
+use std::fmt;
+
+use http::header::HeaderMap;
+
+/// W3C trace context header
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Zipkin single header
+pub const B3: &str = "b3";
+
+thread_local! {
+    static CURRENT: Cell<Option<TraceContext>> = Cell::new(None);
+}
+
+/// Trace context of the request.
+///
+/// Context is parsed from W3C `traceparent` header or from Zipkin `b3`
+/// headers, both single and multi header formats are supported.
+///
+/// Context of the request that is currently processed by the worker is
+/// available with `TraceContext::current()`, http client uses it to
+/// propagate trace to the outgoing requests.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    parent_id: Option<u64>,
+    sampled: bool,
+}
+
+impl TraceContext {
+    /// Create new sampled trace
+    pub fn new_root() -> Self {
+        let trace_id = loop {
+            let id = rand::random::<u128>();
+            if id != 0 {
+                break id;
+            }
+        };
+        TraceContext {
+            trace_id,
+            span_id: new_span_id(),
+            parent_id: None,
+            sampled: true,
+        }
+    }
+
+    /// Parse trace context from `traceparent` or `b3` headers
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        if let Some(val) = headers.get(TRACEPARENT) {
+            if let Some(ctx) = val.to_str().ok().and_then(parse_traceparent) {
+                return Some(ctx);
+            }
+        }
+        if let Some(val) = headers.get(B3) {
+            if let Some(ctx) = val.to_str().ok().and_then(parse_b3) {
+                return Some(ctx);
+            }
+        }
+        parse_b3_multi(headers)
+    }
+
+    /// Create context of the child span
+    pub fn child(&self) -> Self {
+        TraceContext {
+            trace_id: self.trace_id,
+            span_id: new_span_id(),
+            parent_id: Some(self.span_id),
+            sampled: self.sampled,
+        }
+    }
+
+    /// Trace id
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// Span id
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Id of the parent span
+    pub fn parent_id(&self) -> Option<u64> {
+        self.parent_id
+    }
+
+    /// Check if trace is sampled
+    pub fn is_sampled(&self) -> bool {
+        self.sampled
+    }
+
+    /// Value of the `traceparent` header
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.span_id, self.sampled as u8
+        )
+    }
+
+    /// Value of the single `b3` header
+    pub fn b3(&self) -> String {
+        format!(
+            "{:032x}-{:016x}-{}",
+            self.trace_id, self.span_id, self.sampled as u8
+        )
+    }
+
+    /// Trace context of the request that is currently processed
+    pub fn current() -> Option<Self> {
+        CURRENT.with(|cur| cur.get())
+    }
+
+    /// Set trace context of the current request, returns previous context.
+    pub fn set_current(ctx: Option<Self>) -> Option<Self> {
+        CURRENT.with(|cur| cur.replace(ctx))
+    }
+}
+
+impl fmt::Debug for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceContext")
+            .field("trace_id", &format_args!("{:032x}", self.trace_id))
+            .field("span_id", &format_args!("{:016x}", self.span_id))
+            .field(
+                "parent_id",
+                &self.parent_id.map(|id| format!("{:016x}", id)),
+            )
+            .field("sampled", &self.sampled)
+            .finish()
+    }
+}
+
+fn new_span_id() -> u64 {
+    loop {
+        let id = rand::random::<u64>();
+        if id != 0 {
+            return id;
+        }
+    }
+}
+
+fn parse_hex(s: &str, len: usize) -> Option<u128> {
+    if s.len() != len || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    match u128::from_str_radix(s, 16) {
+        Ok(0) | Err(_) => None,
+        Ok(id) => Some(id),
+    }
+}
+
+fn parse_trace_id(s: &str) -> Option<u128> {
+    // b3 allows 64bit trace ids
+    let len = if s.len() == 16 { 16 } else { 32 };
+    parse_hex(s, len)
+}
+
+fn parse_span_id(s: &str) -> Option<u64> {
+    parse_hex(s, 16).map(|id| id as u64)
+}
+
+fn parse_traceparent(s: &str) -> Option<TraceContext> {
+    let mut parts = s.trim().split('-');
+    let version = parts.next()?;
+    if version.len() != 2 || version == "ff" {
+        return None;
+    }
+    let trace_id = parse_hex(parts.next()?, 32)?;
+    let span_id = parse_span_id(parts.next()?)?;
+    let flags = u8::from_str_radix(parts.next()?, 16).ok()?;
+    if version == "00" && parts.next().is_some() {
+        return None;
+    }
+    Some(TraceContext {
+        trace_id,
+        span_id,
+        parent_id: None,
+        sampled: flags & 1 == 1,
+    })
+}
+
+fn parse_b3(s: &str) -> Option<TraceContext> {
+    let mut parts = s.trim().split('-');
+    let trace_id = parse_trace_id(parts.next()?)?;
+    let span_id = parse_span_id(parts.next()?)?;
+    let sampled = parts.next().map(|s| s == "1" || s == "d").unwrap_or(true);
+    Some(TraceContext {
+        trace_id,
+        span_id,
+        parent_id: None,
+        sampled,
+    })
+}
+
+fn parse_b3_multi(headers: &HeaderMap) -> Option<TraceContext> {
+    let get = |name: &str| headers.get(name).and_then(|val| val.to_str().ok());
+
+    let trace_id = parse_trace_id(get("x-b3-traceid")?)?;
+    let span_id = parse_span_id(get("x-b3-spanid")?)?;
+    let sampled = match (get("x-b3-sampled"), get("x-b3-flags")) {
+        (_, Some("1")) => true,
+        (Some(s), _) => s == "1" || s == "true",
+        (None, _) => true,
+    };
+    Some(TraceContext {
+        trace_id,
+        span_id,
+        parent_id: None,
+        sampled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::HeaderValue;
+
+    #[test]
+    fn test_traceparent() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TRACEPARENT,
+            HeaderValue::from_static(
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            ),
+        );
+        let ctx = TraceContext::from_headers(&headers).unwrap();
+        assert_eq!(ctx.trace_id(), 0x0af7651916cd43dd8448eb211c80319c);
+        assert_eq!(ctx.span_id(), 0xb7ad6b7169203331);
+        assert!(ctx.is_sampled());
+        assert_eq!(
+            ctx.traceparent(),
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+        );
+
+        let child = ctx.child();
+        assert_eq!(child.trace_id(), ctx.trace_id());
+        assert_eq!(child.parent_id(), Some(ctx.span_id()));
+        assert_ne!(child.span_id(), ctx.span_id());
+
+        for val in &[
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b71692033-01",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c",
+        ] {
+            headers.insert(TRACEPARENT, HeaderValue::from_static(*val));
+            assert!(TraceContext::from_headers(&headers).is_none());
+        }
+    }
+
+    #[test]
+    fn test_b3() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            B3,
+            HeaderValue::from_static(
+                "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0",
+            ),
+        );
+        let ctx = TraceContext::from_headers(&headers).unwrap();
+        assert_eq!(ctx.trace_id(), 0x80f198ee56343ba864fe8b2a57d3eff7);
+        assert_eq!(ctx.span_id(), 0xe457b5a2e4d86bd1);
+        assert!(!ctx.is_sampled());
+        assert_eq!(
+            ctx.b3(),
+            "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-0"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-b3-traceid", HeaderValue::from_static("64fe8b2a57d3eff7"));
+        headers.insert("x-b3-spanid", HeaderValue::from_static("e457b5a2e4d86bd1"));
+        let ctx = TraceContext::from_headers(&headers).unwrap();
+        assert_eq!(ctx.trace_id(), 0x64fe8b2a57d3eff7);
+        assert!(ctx.is_sampled());
+    }
+
+    #[test]
+    fn test_current() {
+        assert!(TraceContext::current().is_none());
+        let ctx = TraceContext::new_root();
+        assert!(TraceContext::set_current(Some(ctx)).is_none());
+        assert_eq!(TraceContext::current(), Some(ctx));
+        assert_eq!(TraceContext::set_current(None), Some(ctx));
+    }
+}
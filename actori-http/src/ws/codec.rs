@@ -50,11 +50,13 @@ pub enum Item {
     Last(Bytes),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 /// WebSockets protocol codec
 pub struct Codec {
     flags: Flags,
     max_size: usize,
+    max_aggregate: Option<usize>,
+    partial: Option<(OpCode, BytesMut)>,
 }
 
 bitflags::bitflags! {
@@ -71,6 +73,8 @@ impl Codec {
         Codec {
             max_size: 65_536,
             flags: Flags::SERVER,
+            max_aggregate: None,
+            partial: None,
         }
     }
 
@@ -82,6 +86,19 @@ impl Codec {
         self
     }
 
+    /// Aggregate continuation frames.
+    ///
+    /// Fragments of the message are collected by the decoder and the complete
+    /// message is returned as `Frame::Text` or `Frame::Binary`, control frames
+    /// received between fragments are returned as usual. Decoder fails with
+    /// `ProtocolError::Overflow` if size of the message exceeds `max_size`.
+    ///
+    /// By default continuation frames are returned as `Frame::Continuation`.
+    pub fn aggregate_continuations(mut self, max_size: usize) -> Self {
+        self.max_aggregate = Some(max_size);
+        self
+    }
+
     /// Set decoder to client mode.
     ///
     /// By default decoder works in server mode.
@@ -137,7 +154,7 @@ impl Encoder for Codec {
                         Parser::write_message(
                             dst,
                             &data[..],
-                            OpCode::Binary,
+                            OpCode::Text,
                             false,
                             !self.flags.contains(Flags::SERVER),
                         )
@@ -151,7 +168,7 @@ impl Encoder for Codec {
                         Parser::write_message(
                             dst,
                             &data[..],
-                            OpCode::Text,
+                            OpCode::Binary,
                             false,
                             !self.flags.contains(Flags::SERVER),
                         )
@@ -196,6 +213,75 @@ impl Decoder for Codec {
     type Error = ProtocolError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let frame = match self.decode_frame(src)? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            match (self.max_aggregate, frame) {
+                (Some(max_size), Frame::Continuation(item)) => {
+                    // incomplete message, next frame could be in the buffer already
+                    if let Some(frame) = self.aggregate(item, max_size)? {
+                        return Ok(Some(frame));
+                    }
+                }
+                (_, frame) => return Ok(Some(frame)),
+            }
+        }
+    }
+}
+
+impl Codec {
+    /// Collect continuation item, returns complete message
+    fn aggregate(
+        &mut self,
+        item: Item,
+        max_size: usize,
+    ) -> Result<Option<Frame>, ProtocolError> {
+        let (data, last) = match item {
+            Item::FirstText(data) => {
+                self.partial = Some((OpCode::Text, BytesMut::new()));
+                (data, false)
+            }
+            Item::FirstBinary(data) => {
+                self.partial = Some((OpCode::Binary, BytesMut::new()));
+                (data, false)
+            }
+            Item::Continue(data) => (data, false),
+            Item::Last(data) => (data, true),
+        };
+
+        let overflow = match self.partial {
+            Some((_, ref mut buf)) => {
+                if buf.len() + data.len() > max_size {
+                    true
+                } else {
+                    buf.extend_from_slice(&data);
+                    false
+                }
+            }
+            None => return Err(ProtocolError::ContinuationNotStarted),
+        };
+        if overflow {
+            self.partial = None;
+            return Err(ProtocolError::Overflow);
+        }
+        if !last {
+            return Ok(None);
+        }
+
+        match self.partial.take() {
+            Some((OpCode::Text, buf)) => Ok(Some(Frame::Text(buf.freeze()))),
+            Some((_, buf)) => Ok(Some(Frame::Binary(buf.freeze()))),
+            None => Err(ProtocolError::ContinuationNotStarted),
+        }
+    }
+
+    /// Decode single frame
+    fn decode_frame(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<Frame>, ProtocolError> {
         match Parser::parse(src, self.flags.contains(Flags::SERVER), self.max_size) {
             Ok(Some((finished, opcode, payload))) => {
                 // continuation is not supported
@@ -282,3 +368,113 @@ impl Decoder for Codec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(codec: &mut Codec, messages: Vec<Message>) -> BytesMut {
+        let mut buf = BytesMut::new();
+        for msg in messages {
+            codec.encode(msg, &mut buf).unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn test_continuation_opcodes() {
+        let mut client = Codec::new().client_mode();
+        let mut buf = encode(
+            &mut client,
+            vec![
+                Message::Continuation(Item::FirstText(Bytes::from_static(b"a"))),
+                Message::Continuation(Item::Last(Bytes::from_static(b"b"))),
+                Message::Continuation(Item::FirstBinary(Bytes::from_static(b"c"))),
+                Message::Continuation(Item::Last(Bytes::from_static(b"d"))),
+            ],
+        );
+
+        let mut server = Codec::new();
+        let mut frames = Vec::new();
+        while let Some(frame) = server.decode(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+        assert_eq!(
+            frames,
+            vec![
+                Frame::Continuation(Item::FirstText(Bytes::from_static(b"a"))),
+                Frame::Continuation(Item::Last(Bytes::from_static(b"b"))),
+                Frame::Continuation(Item::FirstBinary(Bytes::from_static(b"c"))),
+                Frame::Continuation(Item::Last(Bytes::from_static(b"d"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_continuations() {
+        let mut client = Codec::new().client_mode();
+        let mut buf = encode(
+            &mut client,
+            vec![
+                Message::Continuation(Item::FirstText(Bytes::from_static(b"hello"))),
+                Message::Ping(Bytes::from_static(b"ping")),
+                Message::Continuation(Item::Continue(Bytes::from_static(b", "))),
+                Message::Continuation(Item::Last(Bytes::from_static(b"world"))),
+                Message::Continuation(Item::FirstBinary(Bytes::from_static(b"\x01"))),
+                Message::Continuation(Item::Last(Bytes::from_static(b"\x02"))),
+                Message::Binary(Bytes::from_static(b"\x03")),
+            ],
+        );
+
+        let mut server = Codec::new().aggregate_continuations(16);
+        let mut frames = Vec::new();
+        while let Some(frame) = server.decode(&mut buf).unwrap() {
+            frames.push(frame);
+        }
+        assert_eq!(
+            frames,
+            vec![
+                Frame::Ping(Bytes::from_static(b"ping")),
+                Frame::Text(Bytes::from_static(b"hello, world")),
+                Frame::Binary(Bytes::from_static(b"\x01\x02")),
+                Frame::Binary(Bytes::from_static(b"\x03")),
+            ]
+        );
+
+        // partial message is kept until the next frame arrives
+        let mut buf = encode(
+            &mut client,
+            vec![Message::Continuation(Item::FirstText(Bytes::from_static(
+                b"a",
+            )))],
+        );
+        assert_eq!(server.decode(&mut buf).unwrap(), None);
+        let mut buf = encode(
+            &mut client,
+            vec![Message::Continuation(Item::Last(Bytes::from_static(b"b")))],
+        );
+        assert_eq!(
+            server.decode(&mut buf).unwrap(),
+            Some(Frame::Text(Bytes::from_static(b"ab")))
+        );
+    }
+
+    #[test]
+    fn test_aggregate_overflow() {
+        let mut client = Codec::new().client_mode();
+        let mut buf = encode(
+            &mut client,
+            vec![
+                Message::Continuation(Item::FirstBinary(Bytes::from_static(b"1234"))),
+                Message::Continuation(Item::Continue(Bytes::from_static(b"5678"))),
+                Message::Continuation(Item::Last(Bytes::from_static(b"9"))),
+            ],
+        );
+
+        let mut server = Codec::new().aggregate_continuations(8);
+        match server.decode(&mut buf) {
+            Err(ProtocolError::Overflow) => (),
+            res => panic!("{:?}", res),
+        }
+    }
+}
@@ -3,6 +3,11 @@
 //! To setup a `WebSocket`, first do web socket handshake then on success
 //! convert `Payload` into a `WsStream` stream and then use `WsWriter` to
 //! communicate with the peer.
+//!
+//! Only HTTP/1.1 upgrade handshake is supported. WebSockets over HTTP/2
+//! (RFC 8441 extended `CONNECT`) are not implemented, `h2` 0.2 does not
+//! advertise `SETTINGS_ENABLE_CONNECT_PROTOCOL` and does not expose
+//! `:protocol` pseudo-header. HTTP/2 clients fall back to HTTP/1.1.
 use std::io;
 
 use derive_more::{Display, From};
@@ -87,7 +92,7 @@ impl ResponseError for HandshakeError {
     fn error_response(&self) -> Response {
         match *self {
             HandshakeError::GetMethodRequired => Response::MethodNotAllowed()
-                .header(header::ALLOW, "GET")
+                .insert_header((header::ALLOW, "GET"))
                 .finish(),
             HandshakeError::NoWebsocketUpgrade => Response::BadRequest()
                 .reason("No WebSocket UPGRADE header found")
@@ -179,8 +184,8 @@ pub fn handshake_response(req: &RequestHead) -> ResponseBuilder {
 
     Response::build(StatusCode::SWITCHING_PROTOCOLS)
         .upgrade("websocket")
-        .header(header::TRANSFER_ENCODING, "chunked")
-        .header(header::SEC_WEBSOCKET_ACCEPT, key.as_str())
+        .insert_header((header::TRANSFER_ENCODING, "chunked"))
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, key.as_str()))
         .take()
 }
 
@@ -188,7 +193,26 @@ pub fn handshake_response(req: &RequestHead) -> ResponseBuilder {
 mod tests {
     use super::*;
     use crate::test::TestRequest;
-    use http::{header, Method};
+    use http::{header, Method, Version};
+
+    #[test]
+    fn test_handshake_extended_connect() {
+        // RFC 8441 handshake is rejected, only GET upgrade is accepted
+        let req = TestRequest::default()
+            .version(Version::HTTP_2)
+            .method(Method::CONNECT)
+            .header(
+                header::SEC_WEBSOCKET_VERSION,
+                header::HeaderValue::from_static("13"),
+            )
+            .finish();
+        assert_eq!(
+            HandshakeError::GetMethodRequired,
+            verify_handshake(req.head()).err().unwrap()
+        );
+        let resp = HandshakeError::GetMethodRequired.error_response();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
 
     #[test]
     fn test_handshake() {
@@ -1,13 +1,17 @@
 #![cfg(feature = "openssl")]
 use std::io;
+use std::time::Duration;
 
 use actori_http_test::test_server;
+use actori_rt::time::delay_for;
 use actori_service::{fn_service, ServiceFactory};
 
 use bytes::{Bytes, BytesMut};
 use futures::future::{err, ok, ready};
 use futures::stream::{once, Stream, StreamExt};
-use open_ssl::ssl::{AlpnError, SslAcceptor, SslFiletype, SslMethod};
+use open_ssl::ssl::{
+    AlpnError, SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod,
+};
 
 use actori_http::error::{ErrorBadRequest, PayloadError};
 use actori_http::http::header::{self, HeaderName, HeaderValue};
@@ -34,6 +38,10 @@ where
 }
 
 fn ssl_acceptor() -> SslAcceptor {
+    ssl_acceptor_builder().build()
+}
+
+fn ssl_acceptor_builder() -> SslAcceptorBuilder {
     // load ssl keys
     let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
     builder
@@ -57,7 +65,7 @@ fn ssl_acceptor() -> SslAcceptor {
         .set_alpn_protos(b"\x08http/1.1\x02h2")
         .expect("Can not contrust SslAcceptor");
 
-    builder.build()
+    builder
 }
 
 #[actori_rt::test]
@@ -74,6 +82,60 @@ async fn test_h2() -> io::Result<()> {
     Ok(())
 }
 
+#[actori_rt::test]
+async fn test_peer_certificates() -> io::Result<()> {
+    use std::io::{Read, Write};
+
+    use actori_http::PeerCertificates;
+    use open_ssl::ssl::{SslConnector, SslVerifyMode};
+    use open_ssl::x509::X509;
+
+    let srv = test_server(move || {
+        // client certificate is self-signed server certificate
+        let mut builder = ssl_acceptor_builder();
+        builder.set_verify(SslVerifyMode::PEER);
+        builder.set_ca_file("../tests/cert.pem").unwrap();
+
+        HttpService::build()
+            .finish(|req: Request| {
+                let cert =
+                    X509::from_pem(include_bytes!("../../tests/cert.pem")).unwrap();
+                let certs = req.extensions().get::<PeerCertificates>().cloned();
+                let res = match certs {
+                    Some(ref certs) if certs.leaf() == &cert.to_der().unwrap()[..] => {
+                        Response::Ok().finish()
+                    }
+                    _ => Response::BadRequest().finish(),
+                };
+                ok::<_, Error>(res)
+            })
+            .openssl(builder.build())
+            .map_err(|_| ())
+    });
+
+    // client without certificate
+    let response = srv.sget("/").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+    connector.set_verify(SslVerifyMode::NONE);
+    connector
+        .set_certificate_file("../tests/cert.pem", SslFiletype::PEM)
+        .unwrap();
+    connector
+        .set_private_key_file("../tests/key.pem", SslFiletype::PEM)
+        .unwrap();
+    let stream = std::net::TcpStream::connect(srv.addr())?;
+    let mut stream = connector.build().connect("localhost", stream).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")?;
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK"));
+    Ok(())
+}
+
 #[actori_rt::test]
 async fn test_h2_1() -> io::Result<()> {
     let srv = test_server(move || {
@@ -115,6 +177,41 @@ async fn test_h2_body() -> io::Result<()> {
     Ok(())
 }
 
+#[actori_rt::test]
+async fn test_h2_max_payload_size() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .max_payload_size(16)
+            .h2(|mut req: Request<_>| {
+                async move {
+                    let mut pl = req.take_payload();
+                    let mut size = 0;
+                    while let Some(chunk) = pl.next().await {
+                        size += chunk?.len();
+                    }
+                    Ok::<_, Error>(Response::Ok().body(format!("size={}", size)))
+                }
+            })
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv.sget("/").send_body("x".repeat(8)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // declared size
+    let response = srv.sget("/").send_body("x".repeat(64)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // streaming payload of unknown size
+    let body = futures::stream::iter(vec![
+        Ok::<_, Error>(Bytes::from_static(b"0123456789")),
+        Ok(Bytes::from_static(b"0123456789")),
+    ]);
+    let response = srv.sget("/").send_stream(body).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
 #[actori_rt::test]
 async fn test_h2_content_length() {
     let srv = test_server(move || {
@@ -173,7 +270,7 @@ async fn test_h2_headers() {
         HttpService::build().h2(move |_| {
             let mut builder = Response::Ok();
             for idx in 0..90 {
-                builder.header(
+                builder.insert_header((
                     format!("X-TEST-{}", idx).as_str(),
                     "TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
@@ -188,7 +285,7 @@ async fn test_h2_headers() {
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST ",
-                );
+                ));
             }
             ok::<_, ()>(builder.body(data.clone()))
         })
@@ -338,7 +435,7 @@ async fn test_h2_body_chunked_explicit() {
                 let body = once(ok::<_, Error>(Bytes::from_static(STR.as_ref())));
                 ok::<_, ()>(
                     Response::Ok()
-                        .header(header::TRANSFER_ENCODING, "chunked")
+                        .insert_header((header::TRANSFER_ENCODING, "chunked"))
                         .streaming(body),
                 )
             })
@@ -365,7 +462,7 @@ async fn test_h2_response_http_error_handling() {
                 let broken_header = Bytes::from_static(b"\0\0\0");
                 ok::<_, ()>(
                     Response::Ok()
-                        .header(header::CONTENT_TYPE, broken_header)
+                        .insert_header((header::CONTENT_TYPE, broken_header))
                         .body(STR),
                 )
             }))
@@ -414,3 +511,45 @@ async fn test_h2_on_connect() {
     let response = srv.sget("/").send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[actori_rt::test]
+async fn test_h2_max_stream_duration() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .h2_max_stream_duration(Duration::from_millis(100))
+            .h2(|req: Request| {
+                async move {
+                    if req.path() == "/slow" {
+                        delay_for(Duration::from_secs(2)).await;
+                    }
+                    Ok::<_, Error>(Response::Ok().finish())
+                }
+            })
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    let response = srv.sget("/").send().await.unwrap();
+    assert!(response.status().is_success());
+
+    // stream is reset before service responds
+    assert!(srv.sget("/slow").send().await.is_err());
+}
+
+#[actori_rt::test]
+async fn test_h2_stream_rate_limit() {
+    let srv = test_server(move || {
+        HttpService::build()
+            .h2_stream_rate_limit(2, Duration::from_secs(60))
+            .h2(|_| ok::<_, Error>(Response::Ok().finish()))
+            .openssl(ssl_acceptor())
+            .map_err(|_| ())
+    });
+
+    // all requests are multiplexed over one connection
+    for _ in 0..2 {
+        let response = srv.sget("/").send().await.unwrap();
+        assert!(response.status().is_success());
+    }
+    assert!(srv.sget("/").send().await.is_err());
+}
@@ -2,17 +2,20 @@ use std::io::{Read, Write};
 use std::time::Duration;
 use std::{net, thread};
 
+use actori_codec::{BytesCodec, Framed};
 use actori_http_test::test_server;
 use actori_rt::time::delay_for;
 use actori_service::fn_service;
 use bytes::Bytes;
 use futures::future::{self, err, ok, ready, FutureExt};
 use futures::stream::{once, StreamExt};
+use futures::SinkExt;
 use regex::Regex;
 
 use actori_http::httpmessage::HttpMessage;
 use actori_http::{
-    body, error, http, http::header, Error, HttpService, KeepAlive, Request, Response,
+    body, error, http, http::header, Error, HttpService, KeepAlive, ListenerName,
+    LocalAddr, PeerAddr, Request, Response,
 };
 
 #[actori_rt::test]
@@ -165,6 +168,121 @@ async fn test_chunked_payload() {
     assert_eq!(returned_size, total_size);
 }
 
+#[actori_rt::test]
+async fn test_h1_conn_data() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .local_addr("127.0.0.1:8080".parse().unwrap())
+            .listener_name("main")
+            .h1(|req: Request| {
+                assert!(req.conn_data::<PeerAddr>().is_some());
+                assert_eq!(
+                    req.conn_data::<LocalAddr>().unwrap().0,
+                    "127.0.0.1:8080".parse::<net::SocketAddr>().unwrap()
+                );
+                let name = req.conn_data::<ListenerName>().unwrap().as_str();
+                ok::<_, ()>(Response::Ok().body(name.to_owned()))
+            })
+            .tcp()
+    });
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = srv.load_body(response).await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"main"));
+}
+
+#[actori_rt::test]
+async fn test_h1_max_payload_size() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .max_payload_size(16)
+            .payload_drain_size(1024)
+            .h1(fn_service(|mut req: Request| {
+                let mut pl = req.take_payload();
+                async move {
+                    let mut size = 0;
+                    while let Some(chunk) = pl.next().await {
+                        size += chunk?.len();
+                    }
+                    Ok::<_, Error>(Response::Ok().body(format!("size={}", size)))
+                }
+            }))
+            .tcp()
+    });
+
+    // declared size is checked before payload is sent,
+    // small payload is drained and connection stays alive
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"POST /test HTTP/1.1\r\ncontent-length: 100\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let _ = stream.read(&mut data);
+    assert!(data.starts_with(b"HTTP/1.1 413 Payload Too Large\r\n"));
+
+    let _ = stream.write_all(&[b'x'; 100]);
+    let _ = stream.write_all(b"POST /test HTTP/1.1\r\ncontent-length: 4\r\n\r\ntest");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(data[..n].ends_with(b"size=4"));
+
+    // big payload is not drained
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"POST /test HTTP/1.1\r\ncontent-length: 100000\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    assert!(data.contains("connection: close\r\n"));
+
+    // limit is exceeded in the middle of chunked payload
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"POST /test HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+          a\r\n0123456789\r\na\r\n0123456789\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    assert!(data.contains("connection: close\r\n"));
+}
+
+#[actori_rt::test]
+async fn test_h1_on_upgrade() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h1(|_: Request| {
+                ok::<_, ()>(
+                    Response::build(http::StatusCode::SWITCHING_PROTOCOLS)
+                        .upgrade("echo")
+                        .on_upgrade(|io| async move {
+                            let mut framed = Framed::new(io, BytesCodec);
+                            while let Some(Ok(item)) = framed.next().await {
+                                if framed.send(item.freeze()).await.is_err() {
+                                    break;
+                                }
+                            }
+                        })
+                        .finish(),
+                )
+            })
+            .tcp()
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(
+        b"GET /test HTTP/1.1\r\nconnection: upgrade\r\nupgrade: echo\r\n\r\n",
+    );
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(data.starts_with(b"HTTP/1.1 101 Switching Protocols\r\n"));
+    assert!(data[..n].ends_with(b"\r\n\r\n"));
+
+    let _ = stream.write_all(b"ping");
+    let mut data = vec![0; 4];
+    stream.read_exact(&mut data).unwrap();
+    assert_eq!(data, b"ping");
+}
+
 #[actori_rt::test]
 async fn test_slow_request() {
     let srv = test_server(|| {
@@ -378,7 +496,7 @@ async fn test_h1_headers() {
         HttpService::build().h1(move |_| {
             let mut builder = Response::Ok();
             for idx in 0..90 {
-                builder.header(
+                builder.insert_header((
                     format!("X-TEST-{}", idx).as_str(),
                     "TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
@@ -393,7 +511,7 @@ async fn test_h1_headers() {
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST \
                         TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST TEST ",
-                );
+                ));
             }
             future::ok::<_, ()>(builder.body(data.clone()))
         }).tcp()
@@ -544,7 +662,7 @@ async fn test_h1_body_chunked_explicit() {
                 let body = once(ok::<_, Error>(Bytes::from_static(STR.as_ref())));
                 ok::<_, ()>(
                     Response::Ok()
-                        .header(header::TRANSFER_ENCODING, "chunked")
+                        .insert_header((header::TRANSFER_ENCODING, "chunked"))
                         .streaming(body),
                 )
             })
@@ -606,7 +724,7 @@ async fn test_h1_response_http_error_handling() {
                 let broken_header = Bytes::from_static(b"\0\0\0");
                 ok::<_, ()>(
                     Response::Ok()
-                        .header(http::header::CONTENT_TYPE, broken_header)
+                        .insert_header((http::header::CONTENT_TYPE, broken_header))
                         .body(STR),
                 )
             }))
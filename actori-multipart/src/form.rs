@@ -0,0 +1,336 @@
+//! Typed multipart form support
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, Write};
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::{fmt, str};
+
+use actori_web::error::BlockingError;
+use actori_web::{dev::Payload, web, Error, FromRequest, HttpRequest};
+use bytes::BytesMut;
+use futures::future::{FutureExt, LocalBoxFuture};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use tempfile::NamedTempFile;
+
+use crate::{Field, Multipart, MultipartError};
+
+pub use actori_web_codegen::MultipartForm;
+
+/// Default size limit of `Text` fields, 256Kb.
+const DEFAULT_TEXT_LIMIT: usize = 262_144;
+
+/// Types that could be constructed from a multipart stream.
+///
+/// Usually implemented with `#[derive(MultipartForm)]`.
+pub trait FromMultipart: Sized {
+    /// Read multipart stream.
+    fn from_multipart(
+        multipart: Multipart,
+    ) -> LocalBoxFuture<'static, Result<Self, MultipartError>>;
+}
+
+/// Types that could be read from a single multipart field.
+pub trait FieldReader: Sized + 'static {
+    /// Read field content.
+    ///
+    /// `limit` is a max size of the field content, if it is configured
+    /// with `#[multipart(limit = ...)]` attribute.
+    fn read_field(
+        field: Field,
+        limit: Option<usize>,
+    ) -> LocalBoxFuture<'static, Result<Self, MultipartError>>;
+}
+
+/// Extract typed information from the multipart/form-data request's body.
+///
+/// Form fields are mapped to struct fields by name, text fields are
+/// read with `Text<T>` and file fields with `TempFile`. Field could be
+/// optional with `Option<T>` or repeated with `Vec<T>`.
+///
+/// Unknown fields are ignored, unless `#[multipart(deny_unknown_fields)]`
+/// is set. Field name could be changed with `#[multipart(rename = "...")]`
+/// and max size of the field content with `#[multipart(limit = ...)]`.
+///
+/// ```rust
+/// use actori_multipart::form::{MultipartForm, TempFile, Text};
+/// use actori_web::{web, App, HttpResponse};
+///
+/// #[derive(MultipartForm)]
+/// struct Upload {
+///     #[multipart(limit = 256)]
+///     title: Text<String>,
+///     size: Option<Text<u64>>,
+///     #[multipart(rename = "file")]
+///     files: Vec<TempFile>,
+/// }
+///
+/// async fn upload(form: MultipartForm<Upload>) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("{}: {} files", *form.title, form.files.len()))
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/upload", web::post().to(upload));
+/// }
+/// ```
+pub struct MultipartForm<T>(pub T);
+
+impl<T> MultipartForm<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for MultipartForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for MultipartForm<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> FromRequest for MultipartForm<T>
+where
+    T: FromMultipart + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        T::from_multipart(Multipart::new(req.headers(), payload.take()))
+            .map(|res| res.map(MultipartForm).map_err(Error::from))
+            .boxed_local()
+    }
+}
+
+/// Text field, content is parsed to `T` with `serde`.
+///
+/// Any type that could be deserialized from a url-encoded value is
+/// supported, e.g. `String`, numbers, `bool` or unit enums.
+pub struct Text<T>(pub T);
+
+impl<T> Text<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Text<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Text<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Text<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> FieldReader for Text<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    fn read_field(
+        mut field: Field,
+        limit: Option<usize>,
+    ) -> LocalBoxFuture<'static, Result<Self, MultipartError>> {
+        let limit = limit.unwrap_or(DEFAULT_TEXT_LIMIT);
+
+        async move {
+            let name = field_name(&field);
+            let mut body = BytesMut::new();
+            while let Some(chunk) = field.next().await {
+                let chunk = chunk?;
+                if body.len() + chunk.len() > limit {
+                    return Err(MultipartError::FieldOverflow(name));
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            let text = str::from_utf8(&body)
+                .map_err(|_| MultipartError::FieldParse(name.clone()))?;
+
+            // url-encoded deserializer parses primitive values from strings
+            let value = serde_urlencoded::to_string(&[("v", text)])
+                .ok()
+                .and_then(|s| serde_urlencoded::from_str::<Vec<(String, T)>>(&s).ok())
+                .and_then(|mut items| items.pop())
+                .ok_or_else(|| MultipartError::FieldParse(name))?;
+            Ok(Text(value.1))
+        }
+        .boxed_local()
+    }
+}
+
+/// File field, content is stored to a temporary file.
+///
+/// File is deleted when `TempFile` is dropped, use `NamedTempFile::persist()`
+/// to keep it. Size of file fields is not limited by default.
+#[derive(Debug)]
+pub struct TempFile {
+    /// Temporary file with field content
+    pub file: NamedTempFile,
+    /// Content type of the field
+    pub content_type: mime::Mime,
+    /// File name from the `Content-Disposition` header
+    pub file_name: Option<String>,
+    /// Size of the file
+    pub size: usize,
+}
+
+impl FieldReader for TempFile {
+    fn read_field(
+        mut field: Field,
+        limit: Option<usize>,
+    ) -> LocalBoxFuture<'static, Result<Self, MultipartError>> {
+        let limit = limit.unwrap_or_else(usize::max_value);
+
+        async move {
+            let name = field_name(&field);
+            let content_type = field.content_type().clone();
+            let file_name = field
+                .content_disposition()
+                .and_then(|cd| cd.get_filename().map(|f| f.to_owned()));
+
+            let mut file = web::block(NamedTempFile::new).await.map_err(io_error)?;
+            let mut size = 0;
+            while let Some(chunk) = field.next().await {
+                let chunk = chunk?;
+                size += chunk.len();
+                if size > limit {
+                    return Err(MultipartError::FieldOverflow(name));
+                }
+                file = web::block(move || file.write_all(&chunk).map(|_| file))
+                    .await
+                    .map_err(io_error)?;
+            }
+
+            Ok(TempFile {
+                file,
+                content_type,
+                file_name,
+                size,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+fn field_name(field: &Field) -> String {
+    field
+        .content_disposition()
+        .and_then(|cd| cd.get_name().map(|name| name.to_owned()))
+        .unwrap_or_default()
+}
+
+fn io_error(err: BlockingError<io::Error>) -> MultipartError {
+    match err {
+        BlockingError::Error(e) => MultipartError::Io(e),
+        BlockingError::Canceled => MultipartError::Io(io::Error::new(
+            io::ErrorKind::Other,
+            "Thread pool is gone",
+        )),
+    }
+}
+
+#[doc(hidden)]
+/// Form fields storage, used by `#[derive(MultipartForm)]`
+#[derive(Default)]
+pub struct FormState {
+    fields: HashMap<String, Vec<Box<dyn Any>>>,
+}
+
+#[doc(hidden)]
+impl FormState {
+    pub async fn next_field(
+        &mut self,
+        multipart: &mut Multipart,
+    ) -> Result<Option<(String, Field)>, MultipartError> {
+        match multipart.next().await {
+            Some(field) => {
+                let field = field?;
+                Ok(Some((field_name(&field), field)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn read<T: FieldReader>(
+        &mut self,
+        name: &str,
+        field: Field,
+        limit: Option<usize>,
+    ) -> Result<(), MultipartError> {
+        let value = T::read_field(field, limit).await?;
+        self.fields
+            .entry(name.to_owned())
+            .or_insert_with(Vec::new)
+            .push(Box::new(value));
+        Ok(())
+    }
+
+    pub async fn skip(
+        &mut self,
+        name: &str,
+        mut field: Field,
+        deny_unknown: bool,
+    ) -> Result<(), MultipartError> {
+        if deny_unknown {
+            return Err(MultipartError::UnknownField(name.to_owned()));
+        }
+        while let Some(chunk) = field.next().await {
+            chunk?;
+        }
+        Ok(())
+    }
+
+    pub fn take_vec<T: 'static>(&mut self, name: &str) -> Vec<T> {
+        self.fields
+            .remove(name)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|value| value.downcast().ok().map(|value| *value))
+            .collect()
+    }
+
+    pub fn take_opt<T: 'static>(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<T>, MultipartError> {
+        let mut values = self.take_vec(name);
+        if values.len() > 1 {
+            Err(MultipartError::DuplicateField(name.to_owned()))
+        } else {
+            Ok(values.pop())
+        }
+    }
+
+    pub fn take<T: 'static>(&mut self, name: &str) -> Result<T, MultipartError> {
+        self.take_opt(name)?
+            .ok_or_else(|| MultipartError::MissingField(name.to_owned()))
+    }
+}
+
+#[doc(hidden)]
+pub type FormFuture<T> = Pin<Box<dyn Future<Output = Result<T, MultipartError>>>>;
@@ -1,8 +1,6 @@
 #![allow(clippy::borrow_interior_mutable_const)]
 
-mod error;
-mod extractor;
-mod server;
+pub mod form;
 
-pub use self::error::MultipartError;
-pub use self::server::{Field, Multipart};
+pub use actori_web::error::MultipartError;
+pub use actori_web::web::multipart::{Field, Multipart};
@@ -0,0 +1,125 @@
+use actori_multipart::form::{MultipartForm, TempFile, Text};
+use actori_web::http::{header, StatusCode};
+use actori_web::test::{call_service, init_service, read_body, TestRequest};
+use actori_web::{web, App, HttpResponse};
+use bytes::Bytes;
+
+#[derive(MultipartForm)]
+struct Upload {
+    title: Text<String>,
+    #[multipart(rename = "count", limit = 4)]
+    size: Option<Text<u32>>,
+    #[multipart(rename = "file")]
+    files: Vec<TempFile>,
+}
+
+#[derive(MultipartForm)]
+#[multipart(deny_unknown_fields)]
+struct Strict {
+    title: Text<String>,
+}
+
+fn form_request(uri: &str, body: &'static str) -> TestRequest {
+    TestRequest::post()
+        .uri(uri)
+        .header(
+            header::CONTENT_TYPE,
+            "multipart/form-data; boundary=\"abbc761f78ff4d7cb7573b5a23f96ef0\"",
+        )
+        .set_payload(Bytes::from_static(body.as_bytes()))
+}
+
+async fn upload(form: MultipartForm<Upload>) -> HttpResponse {
+    let form = form.into_inner();
+    let mut content = String::new();
+    for file in &form.files {
+        content.push_str(&std::fs::read_to_string(file.file.path()).unwrap());
+        content.push_str(file.file_name.as_ref().unwrap());
+    }
+    HttpResponse::Ok().body(format!(
+        "{} {:?} {}",
+        *form.title,
+        form.size.map(|s| s.into_inner()),
+        content
+    ))
+}
+
+#[actori_rt::test]
+async fn test_multipart_form() {
+    let mut srv = init_service(App::new().route("/", web::post().to(upload)).route(
+        "/strict",
+        web::post().to(|form: MultipartForm<Strict>| {
+            HttpResponse::Ok().body(form.into_inner().title.into_inner())
+        }),
+    ))
+    .await;
+
+    let req = form_request(
+        "/",
+        "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+         test\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"count\"\r\n\r\n\
+         12\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\n\
+         aaa\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"b.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\n\
+         bbb\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"unknown\"\r\n\r\n\
+         data\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+    )
+    .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = read_body(resp).await;
+    assert_eq!(body, Bytes::from_static(b"test Some(12) aaaa.txtbbbb.txt"));
+
+    // missing field
+    let req = form_request(
+        "/",
+        "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"count\"\r\n\r\n\
+         12\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+    )
+    .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // field limit
+    let req = form_request(
+        "/",
+        "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+         test\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"count\"\r\n\r\n\
+         123456\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+    )
+    .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // unknown field
+    let req = form_request(
+        "/strict",
+        "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+         test\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+         Content-Disposition: form-data; name=\"unknown\"\r\n\r\n\
+         data\r\n\
+         --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+    )
+    .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
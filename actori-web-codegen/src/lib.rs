@@ -1,7 +1,7 @@
 #![recursion_limit = "512"]
 //! Actori-web codegen module
 //!
-//! Generators for routes and scopes
+//! Generators for routes and scopes, and `MultipartForm` derive
 //!
 //! ## Route
 //!
@@ -16,11 +16,22 @@
 //! - [options](attr.options.html)
 //! - [trace](attr.trace.html)
 //! - [patch](attr.patch.html)
+//! - [route](attr.route.html)
 //!
 //! ### Attributes:
 //!
 //! - `"path"` - Raw literal string with path for which to register handle. Mandatory.
+//! - `method="HTTP_METHOD"` - Registers method guard, could be specified multiple times.
+//!   Supported by `route` macro only, mandatory there
+//! - `name="resource_name"` - Name of the resource for `HttpRequest::url_for()`, defaults
+//!   to the function name
 //! - `guard="function_name"` - Registers function as guard using `actori_web::guard::fn_guard`
+//! - `wrap="Middleware"` - Wraps resource with the middleware expression, could be specified
+//!   multiple times
+//! - `timeout="2s"` - Wraps resource with `actori_web::middleware::Timeout`, supported units
+//!   are `ms`, `s`, `m` and `h`
+//! - `payload_limit="64KB"` - Wraps resource with `actori_web::middleware::BodyLimit` and sets
+//!   limit of payload, json and form extractors, supported units are `B`, `KB`, `MB` and `GB`
 //!
 //! ## Notes
 //!
@@ -42,6 +53,7 @@
 
 extern crate proc_macro;
 
+mod multipart;
 mod route;
 
 use proc_macro::TokenStream;
@@ -54,7 +66,11 @@ use syn::parse_macro_input;
 /// ## Attributes:
 ///
 /// - `"path"` - Raw literal string with path for which to register handler. Mandatory.
+/// - `name="resource_name"` - Name of the resource for `HttpRequest::url_for()`
 /// - `guard="function_name"` - Registers function as guard using `actori_web::guard::fn_guard`
+/// - `wrap="Middleware"` - Registers resource middleware, i.e. `wrap="Logger::default()"`
+/// - `timeout="2s"` - Limits request processing time with `actori_web::middleware::Timeout`
+/// - `payload_limit="64KB"` - Limits request body size with `actori_web::middleware::BodyLimit`
 #[proc_macro_attribute]
 pub fn get(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as syn::AttributeArgs);
@@ -184,3 +200,52 @@ pub fn patch(args: TokenStream, input: TokenStream) -> TokenStream {
     };
     gen.generate()
 }
+
+/// Creates route handler with multiple method guards.
+///
+/// Syntax: `#[route("path", method="HTTP_METHOD"[, attributes])]`
+///
+/// ## Attributes:
+///
+/// - `"path"` - Raw literal string with path for which to register handler. Mandatory.
+/// - `method="HTTP_METHOD"` - Registers method guard, i.e. `method="GET"`. Could be specified
+///   multiple times, at least one method is mandatory.
+///
+/// Other attributes are the same as in [get](attr.get.html)
+///
+/// ## Example:
+///
+/// ```rust
+/// use actori_web::{web, HttpResponse};
+/// use actori_web_codegen::route;
+///
+/// #[route("/users/{id:\\d+}", method = "GET", method = "HEAD", name = "user")]
+/// async fn user(id: web::Path<u32>) -> HttpResponse {
+///     HttpResponse::Ok().body(id.to_string())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as syn::AttributeArgs);
+    let gen = match route::Route::new_multi(args, input) {
+        Ok(gen) => gen,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    gen.generate()
+}
+
+/// Derives `actori_multipart::form::FromMultipart` for a struct with named fields.
+///
+/// ## Attributes:
+///
+/// - `#[multipart(deny_unknown_fields)]` - Struct attribute, reject forms with unknown fields.
+/// - `#[multipart(rename = "name")]` - Field attribute, name of the form field.
+/// - `#[multipart(limit = 1024)]` - Field attribute, max size of the field content in bytes.
+#[proc_macro_derive(MultipartForm, attributes(multipart))]
+pub fn multipart_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match multipart::derive(input) {
+        Ok(gen) => gen.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
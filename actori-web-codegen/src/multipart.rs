@@ -0,0 +1,206 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// How many times field could appear in the form
+enum Occurs {
+    One,
+    Optional,
+    Many,
+}
+
+struct FormField {
+    ident: syn::Ident,
+    name: String,
+    reader: syn::Type,
+    occurs: Occurs,
+    limit: Option<syn::LitInt>,
+}
+
+/// Parse `#[multipart(...)]` attributes
+fn multipart_attrs(attrs: &[syn::Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("multipart") {
+            continue;
+        }
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "Unknown attribute",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "Attribute expects a list, e.g. #[multipart(rename = \"name\")]",
+                ))
+            }
+        }
+    }
+    Ok(metas)
+}
+
+/// Get inner type of `Option<T>` or `Vec<T>`
+fn inner_type(ty: &syn::Type) -> Option<(Occurs, syn::Type)> {
+    if let syn::Type::Path(ref path) = ty {
+        let segment = path.path.segments.last()?;
+        let occurs = if segment.ident == "Option" {
+            Occurs::Optional
+        } else if segment.ident == "Vec" {
+            Occurs::Many
+        } else {
+            return None;
+        };
+        if let syn::PathArguments::AngleBracketed(ref args) = segment.arguments {
+            if let Some(syn::GenericArgument::Type(ty)) = args.args.first() {
+                return Some((occurs, ty.clone()));
+            }
+        }
+    }
+    None
+}
+
+impl FormField {
+    fn new(field: &syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.clone().unwrap();
+        let mut name = ident.to_string();
+        let mut limit = None;
+
+        for meta in multipart_attrs(&field.attrs)? {
+            match meta {
+                Meta::NameValue(ref nv) if nv.path.is_ident("rename") => {
+                    if let Lit::Str(ref lit) = nv.lit {
+                        name = lit.value();
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "Attribute rename expects literal string!",
+                        ));
+                    }
+                }
+                Meta::NameValue(ref nv) if nv.path.is_ident("limit") => {
+                    if let Lit::Int(ref lit) = nv.lit {
+                        limit = Some(lit.clone());
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "Attribute limit expects literal integer!",
+                        ));
+                    }
+                }
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "Unknown attribute key is specified. Allowed: rename, limit",
+                    ));
+                }
+            }
+        }
+
+        let (occurs, reader) =
+            inner_type(&field.ty).unwrap_or_else(|| (Occurs::One, field.ty.clone()));
+
+        Ok(FormField {
+            ident,
+            name,
+            reader,
+            occurs,
+            limit,
+        })
+    }
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut deny_unknown = false;
+    for meta in multipart_attrs(&input.attrs)? {
+        match meta {
+            Meta::Path(ref path) if path.is_ident("deny_unknown_fields") => {
+                deny_unknown = true;
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "Unknown attribute key is specified. Allowed: deny_unknown_fields",
+                ));
+            }
+        }
+    }
+
+    let fields = match input.data {
+        Data::Struct(syn::DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => fields
+            .named
+            .iter()
+            .map(FormField::new)
+            .collect::<syn::Result<Vec<_>>>()?,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "MultipartForm can be derived only for structs with named fields",
+            ))
+        }
+    };
+
+    let read = fields.iter().map(|field| {
+        let name = &field.name;
+        let reader = &field.reader;
+        let limit = match field.limit {
+            Some(ref limit) => quote!(Some(#limit)),
+            None => quote!(None),
+        };
+        quote! {
+            #name => state.read::<#reader>(#name, field, #limit).await?,
+        }
+    });
+
+    let take = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        match field.occurs {
+            Occurs::One => quote!(#ident: state.take(#name)?,),
+            Occurs::Optional => quote!(#ident: state.take_opt(#name)?,),
+            Occurs::Many => quote!(#ident: state.take_vec(#name),),
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::actori_multipart::form::FromMultipart
+            for #name #ty_generics #where_clause
+        {
+            fn from_multipart(
+                mut multipart: ::actori_multipart::Multipart,
+            ) -> ::actori_multipart::form::FormFuture<Self> {
+                Box::pin(async move {
+                    let mut state = ::actori_multipart::form::FormState::default();
+
+                    while let Some((name, field)) =
+                        state.next_field(&mut multipart).await?
+                    {
+                        match name.as_str() {
+                            #(#read)*
+                            _ => state.skip(&name, field, #deny_unknown).await?,
+                        }
+                    }
+
+                    Ok(#name {
+                        #(#take)*
+                    })
+                })
+            }
+        }
+    })
+}
@@ -48,6 +48,21 @@ impl GuardType {
             GuardType::Patch => "Patch",
         }
     }
+
+    fn from_method(method: &str) -> Option<Self> {
+        match method.to_ascii_uppercase().as_str() {
+            "GET" => Some(GuardType::Get),
+            "POST" => Some(GuardType::Post),
+            "PUT" => Some(GuardType::Put),
+            "DELETE" => Some(GuardType::Delete),
+            "HEAD" => Some(GuardType::Head),
+            "CONNECT" => Some(GuardType::Connect),
+            "OPTIONS" => Some(GuardType::Options),
+            "TRACE" => Some(GuardType::Trace),
+            "PATCH" => Some(GuardType::Patch),
+            _ => None,
+        }
+    }
 }
 
 impl ToTokens for GuardType {
@@ -60,13 +75,23 @@ impl ToTokens for GuardType {
 
 struct Args {
     path: syn::LitStr,
+    name: Option<syn::LitStr>,
+    methods: Vec<GuardType>,
     guards: Vec<Ident>,
+    wraps: Vec<syn::Expr>,
+    timeout: Option<u64>,
+    payload_limit: Option<u64>,
 }
 
 impl Args {
     fn new(args: AttributeArgs) -> syn::Result<Self> {
         let mut path = None;
+        let mut name = None;
+        let mut methods = Vec::new();
         let mut guards = Vec::new();
+        let mut wraps = Vec::new();
+        let mut timeout = None;
+        let mut payload_limit = None;
         for arg in args {
             match arg {
                 NestedMeta::Lit(syn::Lit::Str(lit)) => match path {
@@ -90,10 +115,77 @@ impl Args {
                                 "Attribute guard expects literal string!",
                             ));
                         }
+                    } else if nv.path.is_ident("method") {
+                        if let syn::Lit::Str(ref lit) = nv.lit {
+                            match GuardType::from_method(&lit.value()) {
+                                Some(method) if methods.contains(&method) => {
+                                    return Err(syn::Error::new_spanned(
+                                        lit,
+                                        "Method is specified multiple times!",
+                                    ));
+                                }
+                                Some(method) => methods.push(method),
+                                None => {
+                                    return Err(syn::Error::new_spanned(
+                                        lit,
+                                        "Unknown method. Allowed: GET, POST, PUT, DELETE, \
+                                         HEAD, CONNECT, OPTIONS, TRACE, PATCH",
+                                    ));
+                                }
+                            }
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "Attribute method expects literal string!",
+                            ));
+                        }
+                    } else if nv.path.is_ident("name") {
+                        if let syn::Lit::Str(lit) = nv.lit {
+                            if name.is_some() {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "Multiple names specified! Should be only one!",
+                                ));
+                            }
+                            name = Some(lit);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "Attribute name expects literal string!",
+                            ));
+                        }
+                    } else if nv.path.is_ident("wrap") {
+                        if let syn::Lit::Str(ref lit) = nv.lit {
+                            wraps.push(lit.parse::<syn::Expr>()?);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "Attribute wrap expects literal string!",
+                            ));
+                        }
+                    } else if nv.path.is_ident("timeout") {
+                        if let syn::Lit::Str(ref lit) = nv.lit {
+                            timeout = Some(parse_duration(lit)?);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "Attribute timeout expects literal string!",
+                            ));
+                        }
+                    } else if nv.path.is_ident("payload_limit") {
+                        if let syn::Lit::Str(ref lit) = nv.lit {
+                            payload_limit = Some(parse_size(lit)?);
+                        } else {
+                            return Err(syn::Error::new_spanned(
+                                nv.lit,
+                                "Attribute payload_limit expects literal string!",
+                            ));
+                        }
                     } else {
                         return Err(syn::Error::new_spanned(
                             nv.path,
-                            "Unknown attribute key is specified. Allowed: guard",
+                            "Unknown attribute key is specified. \
+                             Allowed: guard, method, name, wrap, timeout, payload_limit",
                         ));
                     }
                 }
@@ -104,17 +196,73 @@ impl Args {
         }
         Ok(Args {
             path: path.unwrap(),
+            name,
+            methods,
             guards,
+            wraps,
+            timeout,
+            payload_limit,
         })
     }
 }
 
+/// Split value like `64KB` to number and unit
+fn split_unit(lit: &syn::LitStr) -> syn::Result<(u64, String)> {
+    let value = lit.value();
+    let value = value.trim();
+    let idx = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    match value[..idx].parse() {
+        Ok(num) => Ok((num, value[idx..].trim().to_ascii_lowercase())),
+        Err(_) => Err(syn::Error::new_spanned(lit, "Number is expected")),
+    }
+}
+
+/// Parse duration in milliseconds, supported units are `ms`, `s`, `m` and `h`
+fn parse_duration(lit: &syn::LitStr) -> syn::Result<u64> {
+    let (num, unit) = split_unit(lit)?;
+    let factor = match unit.as_str() {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "Unknown duration unit. Allowed: ms, s, m, h",
+            ))
+        }
+    };
+    num.checked_mul(factor)
+        .ok_or_else(|| syn::Error::new_spanned(lit, "Duration is too big"))
+}
+
+/// Parse size in bytes, units are powers of 1024
+fn parse_size(lit: &syn::LitStr) -> syn::Result<u64> {
+    let (num, unit) = split_unit(lit)?;
+    let factor = match unit.as_str() {
+        "" | "b" => 1,
+        "kb" | "k" => 1024,
+        "mb" | "m" => 1024 * 1024,
+        "gb" | "g" => 1024 * 1024 * 1024,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "Unknown size unit. Allowed: B, KB, MB, GB",
+            ))
+        }
+    };
+    num.checked_mul(factor)
+        .ok_or_else(|| syn::Error::new_spanned(lit, "Size is too big"))
+}
+
 pub struct Route {
     name: syn::Ident,
     args: Args,
     ast: syn::ItemFn,
     resource_type: ResourceType,
-    guard: GuardType,
+    methods: Vec<GuardType>,
 }
 
 fn guess_resource_type(typ: &syn::Type) -> ResourceType {
@@ -144,13 +292,44 @@ impl Route {
         args: AttributeArgs,
         input: TokenStream,
         guard: GuardType,
+    ) -> syn::Result<Self> {
+        let macro_name = guard.as_str().to_ascii_lowercase();
+        let route = Self::build(args, input, &macro_name, vec![guard])?;
+        if route.args.methods.is_empty() {
+            Ok(route)
+        } else {
+            Err(syn::Error::new(
+                Span::call_site(),
+                "Attribute method is supported by #[route] macro only",
+            ))
+        }
+    }
+
+    /// Route with methods specified by `method` attributes
+    pub fn new_multi(args: AttributeArgs, input: TokenStream) -> syn::Result<Self> {
+        let mut route = Self::build(args, input, "route", Vec::new())?;
+        if route.args.methods.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                r#"invalid route definition, expected #[route("<some path>", method="<method>")]"#,
+            ));
+        }
+        route.methods = std::mem::replace(&mut route.args.methods, Vec::new());
+        Ok(route)
+    }
+
+    fn build(
+        args: AttributeArgs,
+        input: TokenStream,
+        macro_name: &str,
+        methods: Vec<GuardType>,
     ) -> syn::Result<Self> {
         if args.is_empty() {
             return Err(syn::Error::new(
                 Span::call_site(),
                 format!(
                     r#"invalid server definition, expected #[{}("<some path>")]"#,
-                    guard.as_str().to_ascii_lowercase()
+                    macro_name
                 ),
             ));
         }
@@ -178,18 +357,47 @@ impl Route {
             args,
             ast,
             resource_type,
-            guard,
+            methods,
         })
     }
 
     pub fn generate(&self) -> TokenStream {
         let name = &self.name;
-        let resource_name = name.to_string();
-        let guard = &self.guard;
+        let resource_name = match self.args.name {
+            Some(ref name) => name.value(),
+            None => name.to_string(),
+        };
+        let guard = match self.methods.split_first() {
+            Some((first, [])) => quote! { actori_web::guard::#first() },
+            Some((first, rest)) => quote! {
+                actori_web::guard::Any(actori_web::guard::#first())
+                    #(.or(actori_web::guard::#rest()))*
+            },
+            None => unreachable!(),
+        };
         let ast = &self.ast;
         let path = &self.args.path;
         let extra_guards = &self.args.guards;
+        let wraps = &self.args.wraps;
         let resource_type = &self.resource_type;
+        let limits = self.args.payload_limit.map(|limit| {
+            let limit = limit as usize;
+            quote! {
+                .app_data(actori_web::web::PayloadConfig::new(#limit))
+                .app_data(actori_web::web::JsonConfig::default().limit(#limit))
+                .app_data(actori_web::web::FormConfig::default().limit(#limit))
+            }
+        });
+        let body_limit = self.args.payload_limit.map(|limit| {
+            quote! { .wrap(actori_web::middleware::BodyLimit::new(#limit)) }
+        });
+        let timeout = self.args.timeout.map(|ms| {
+            quote! {
+                .wrap(actori_web::middleware::Timeout::new(
+                    std::time::Duration::from_millis(#ms)
+                ))
+            }
+        });
         let stream = quote! {
             #[allow(non_camel_case_types)]
             pub struct #name;
@@ -199,9 +407,13 @@ impl Route {
                     #ast
                     let resource = actori_web::Resource::new(#path)
                         .name(#resource_name)
-                        .guard(actori_web::guard::#guard())
+                        .guard(#guard)
                         #(.guard(actori_web::guard::fn_guard(#extra_guards)))*
-                        .#resource_type(#name);
+                        #limits
+                        .#resource_type(#name)
+                        #(.wrap(#wraps))*
+                        #body_limit
+                        #timeout;
 
                     actori_web::dev::HttpServiceFactory::register(resource, config)
                 }
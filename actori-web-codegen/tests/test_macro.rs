@@ -1,5 +1,9 @@
-use actori_web::{http, test, web::Path, App, HttpResponse, Responder};
-use actori_web_codegen::{connect, delete, get, head, options, patch, post, put, trace};
+use actori_web::{
+    http, test, web, web::Path, App, HttpRequest, HttpResponse, Responder,
+};
+use actori_web_codegen::{
+    connect, delete, get, head, options, patch, post, put, route, trace,
+};
 use futures::{future, Future};
 
 #[get("/test")]
@@ -67,6 +71,29 @@ async fn get_param_test(_: Path<String>) -> impl Responder {
     HttpResponse::Ok()
 }
 
+#[get("/slow", timeout = "50ms")]
+async fn slow_test() -> impl Responder {
+    actori_rt::time::delay_for(std::time::Duration::from_millis(500)).await;
+    HttpResponse::Ok()
+}
+
+#[post("/upload", payload_limit = "1KB")]
+async fn upload_test(body: web::Bytes) -> impl Responder {
+    HttpResponse::Ok().body(body.len().to_string())
+}
+
+#[route(
+    "/multi/{id:\\d+}",
+    method = "GET",
+    method = "POST",
+    name = "multi",
+    wrap = "actori_web::middleware::DefaultHeaders::new().header(\"x-test\", \"1\")"
+)]
+async fn multi_test(req: HttpRequest) -> impl Responder {
+    let url = req.url_for("multi", &["1"]).unwrap();
+    HttpResponse::Ok().body(url.path().to_owned())
+}
+
 #[actori_rt::test]
 async fn test_params() {
     let srv = test::start(|| {
@@ -149,3 +176,50 @@ async fn test_auto_async() {
     let response = request.send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[actori_rt::test]
+async fn test_limits() {
+    let srv = test::start(|| App::new().service(slow_test).service(upload_test));
+
+    let request = srv.request(http::Method::GET, srv.url("/slow"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::GATEWAY_TIMEOUT);
+
+    let request = srv.request(http::Method::POST, srv.url("/upload"));
+    let mut response = request.send_body(vec![b'x'; 1024]).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.body().await.unwrap(),
+        web::Bytes::from_static(b"1024")
+    );
+
+    let request = srv.request(http::Method::POST, srv.url("/upload"));
+    let response = request.send_body(vec![b'x'; 1025]).await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[actori_rt::test]
+async fn test_route() {
+    let srv = test::start(|| App::new().service(multi_test));
+
+    let request = srv.request(http::Method::GET, srv.url("/multi/10"));
+    let mut response = request.send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get("x-test").unwrap(), "1");
+    assert_eq!(
+        response.body().await.unwrap(),
+        web::Bytes::from_static(b"/multi/1")
+    );
+
+    let request = srv.request(http::Method::POST, srv.url("/multi/10"));
+    let response = request.send().await.unwrap();
+    assert!(response.status().is_success());
+
+    let request = srv.request(http::Method::PUT, srv.url("/multi/10"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+    let request = srv.request(http::Method::GET, srv.url("/multi/abc"));
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+}
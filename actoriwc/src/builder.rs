@@ -5,11 +5,16 @@ use std::rc::Rc;
 use std::time::Duration;
 
 use actori_http::client::{Connect, ConnectError, Connection, Connector};
-use actori_http::http::{header, Error as HttpError, HeaderMap, HeaderName};
-use actori_service::Service;
+use actori_http::http::{header, Error as HttpError, HeaderMap, HeaderName, Version};
+use actori_service::{boxed, Service, Transform};
+use futures_util::FutureExt;
 
+use crate::cache::{Cache, CacheStore};
 use crate::connect::ConnectorWrapper;
-use crate::{Client, ClientConfig};
+use crate::error::SendRequestError;
+use crate::middleware::{ClientService, ConnectRequest, ConnectService};
+use crate::response::ClientResponse;
+use crate::{Client, ClientConfig, RequestSigner, RetryPolicy};
 
 /// An HTTP Client builder
 ///
@@ -36,10 +41,13 @@ impl ClientBuilder {
             max_redirects: 10,
             config: ClientConfig {
                 headers: HeaderMap::new(),
+                version: Version::HTTP_11,
                 timeout: Some(Duration::from_secs(5)),
-                connector: RefCell::new(Box::new(ConnectorWrapper(
+                signer: None,
+                connector: Rc::new(RefCell::new(Box::new(ConnectorWrapper(
                     Connector::new().finish(),
-                ))),
+                )))),
+                middleware: None,
             },
         }
     }
@@ -52,7 +60,8 @@ impl ClientBuilder {
         <T::Response as Connection>::Future: 'static,
         T::Future: 'static,
     {
-        self.config.connector = RefCell::new(Box::new(ConnectorWrapper(connector)));
+        // middleware chain refers to the same connector cell
+        *self.config.connector.borrow_mut() = Box::new(ConnectorWrapper(connector));
         self
     }
 
@@ -87,6 +96,17 @@ impl ClientBuilder {
         self
     }
 
+    /// Set default HTTP version of the requests.
+    ///
+    /// Some embedded devices accept `HTTP/1.0` requests only. Version of
+    /// the request does not affect protocol of the connection, requests are
+    /// sent over http/2 if it is negotiated with the server, see
+    /// `Connector::max_http_version()`. Default version is `HTTP/1.1`.
+    pub fn http_version(mut self, version: Version) -> Self {
+        self.config.version = version;
+        self
+    }
+
     /// Do not add default request headers.
     /// By default `Date` and `User-Agent` headers are set.
     pub fn no_default_headers(mut self) -> Self {
@@ -138,6 +158,73 @@ impl ClientBuilder {
         self.header(header::AUTHORIZATION, format!("Bearer {}", token))
     }
 
+    /// Sign every request with provided signer.
+    ///
+    /// Signer is called right before request is sent, headers produced
+    /// by signer are added to the request. See `HmacSigner` for reference
+    /// implementation.
+    pub fn signer<S>(mut self, signer: S) -> Self
+    where
+        S: RequestSigner + 'static,
+    {
+        self.config.signer = Some(Rc::new(signer));
+        self
+    }
+
+    /// Register client middleware.
+    ///
+    /// Middleware is a `Transform` of a service that accepts `ConnectRequest`
+    /// and resolves to `ClientResponse`. Every request sent by the client,
+    /// except websocket handshakes, passes through the middleware after it
+    /// is signed and before it is sent with the connector. Middleware
+    /// registered last is called first, same as for `App::wrap()`.
+    ///
+    /// Middleware is created immediately, the future returned by
+    /// `Transform::new_transform()` must be ready.
+    ///
+    /// This method panics if middleware could not be created.
+    pub fn wrap<M>(mut self, mw: M) -> Self
+    where
+        M: Transform<
+            ClientService,
+            Request = ConnectRequest,
+            Response = ClientResponse,
+            Error = SendRequestError,
+        >,
+        M::Transform: 'static,
+        M::InitError: fmt::Debug,
+        <M::Transform as Service>::Future: 'static,
+    {
+        let service = match self.config.middleware.take() {
+            Some(service) => service.into_inner(),
+            None => boxed::service(ConnectService(self.config.connector.clone())),
+        };
+        let service = mw
+            .new_transform(service)
+            .now_or_never()
+            .expect("Client middleware must be created synchronously")
+            .expect("Can not create client middleware");
+        self.config.middleware = Some(RefCell::new(boxed::service(service)));
+        self
+    }
+
+    /// Retry failed requests with provided policy.
+    ///
+    /// Retry policy is a client middleware, it retries requests passed
+    /// through middleware registered before it. Request timeout covers
+    /// all attempts.
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        self.wrap(policy)
+    }
+
+    /// Cache responses with provided storage backend.
+    ///
+    /// Cache is a client middleware, see `cache::Cache` for details. Register
+    /// cache after retry policy, so responses served from cache are not retried.
+    pub fn cache<T: CacheStore + 'static>(self, store: T) -> Self {
+        self.wrap(Cache::new(store))
+    }
+
     /// Finish build process and create `Client` instance.
     pub fn finish(self) -> Client {
         Client(Rc::new(self.config))
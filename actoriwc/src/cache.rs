@@ -0,0 +1,591 @@
+//! Http cache for client responses
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use std::{cmp, fmt};
+
+use actori_http::error::PayloadError;
+use actori_http::http::header::{
+    CacheDirective, HeaderName, HeaderValue, HttpDate, AGE, CACHE_CONTROL,
+    CONTENT_LENGTH, DATE, ETAG, EXPIRES, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, TRANSFER_ENCODING, VARY,
+};
+use actori_http::http::{HeaderMap, Method, StatusCode};
+use actori_http::{HttpMessage, Payload, ResponseHead};
+use actori_service::{Service, Transform};
+use bytes::{Bytes, BytesMut};
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures_util::stream::{self, StreamExt};
+
+use crate::error::SendRequestError;
+use crate::middleware::ConnectRequest;
+use crate::response::ClientResponse;
+
+/// Response stored in the cache
+#[derive(Clone)]
+pub struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    stored: SystemTime,
+}
+
+impl CachedResponse {
+    /// Create cached response, `stored` is the time response is received.
+    pub fn new(
+        status: StatusCode,
+        headers: HeaderMap,
+        body: Bytes,
+        stored: SystemTime,
+    ) -> Self {
+        CachedResponse {
+            status,
+            headers,
+            body,
+            stored,
+        }
+    }
+
+    /// Response status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Response headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Response body, as it is received from the server.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Time response is received or revalidated.
+    pub fn stored(&self) -> SystemTime {
+        self.stored
+    }
+
+    /// Check if response could be used without revalidation.
+    pub fn is_fresh(&self) -> bool {
+        match self.freshness_lifetime() {
+            Some(lifetime) => self.age() < lifetime,
+            None => false,
+        }
+    }
+
+    /// Freshness lifetime from `Cache-Control` or `Expires` headers
+    fn freshness_lifetime(&self) -> Option<Duration> {
+        let directives = directives(self.headers.get_all(CACHE_CONTROL));
+        if directives.contains(&CacheDirective::NoCache) {
+            return Some(Duration::from_secs(0));
+        }
+        for directive in &directives {
+            if let CacheDirective::MaxAge(secs) = directive {
+                return Some(Duration::from_secs(u64::from(*secs)));
+            }
+        }
+
+        self.headers.get(EXPIRES).map(|expires| {
+            let date = http_date(self.headers.get(DATE)).unwrap_or(self.stored);
+            // invalid date means the response is already expired
+            http_date(Some(expires))
+                .and_then(|expires| expires.duration_since(date).ok())
+                .unwrap_or_else(|| Duration::from_secs(0))
+        })
+    }
+
+    /// Current age of the response
+    fn age(&self) -> Duration {
+        let age = self
+            .headers
+            .get(AGE)
+            .and_then(|age| age.to_str().ok())
+            .and_then(|age| age.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| Duration::from_secs(0));
+        let resident = SystemTime::now()
+            .duration_since(self.stored)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        age + resident
+    }
+
+    fn has_validators(&self) -> bool {
+        self.headers.contains_key(ETAG) || self.headers.contains_key(LAST_MODIFIED)
+    }
+
+    /// Update stored response with headers of *304 Not Modified* response
+    fn revalidated(&mut self, headers: &HeaderMap) {
+        for name in headers.keys() {
+            if *name != CONTENT_LENGTH && *name != TRANSFER_ENCODING {
+                self.headers.remove(name);
+                for value in headers.get_all(name) {
+                    self.headers.append(name.clone(), value.clone());
+                }
+            }
+        }
+        self.headers.remove(AGE);
+        self.stored = SystemTime::now();
+    }
+
+    fn to_response(&self) -> ClientResponse {
+        let mut head = ResponseHead::new(self.status);
+        head.headers = self.headers.clone();
+        head.headers
+            .insert(AGE, HeaderValue::from(self.age().as_secs()));
+        let body = stream::once(ok::<_, PayloadError>(self.body.clone()));
+        ClientResponse::new(head, Payload::Stream(Box::pin(body)))
+    }
+}
+
+impl fmt::Debug for CachedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("body_size", &self.body.len())
+            .field("stored", &self.stored)
+            .finish()
+    }
+}
+
+/// Storage backend of the client cache.
+///
+/// Responses are stored by absolute request url, only responses to `GET`
+/// requests are stored.
+pub trait CacheStore {
+    /// Get stored response.
+    fn get(&self, key: &str) -> Option<CachedResponse>;
+
+    /// Store response, replaces previously stored response.
+    fn put(&self, key: &str, response: CachedResponse);
+
+    /// Remove stored response.
+    fn remove(&self, key: &str);
+}
+
+impl<T: CacheStore + ?Sized> CacheStore for Rc<T> {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        (**self).put(key, response)
+    }
+
+    fn remove(&self, key: &str) {
+        (**self).remove(key)
+    }
+}
+
+/// In-memory cache store, least recently used responses are evicted
+/// once capacity is reached.
+pub struct MemoryCache {
+    capacity: usize,
+    inner: RefCell<MemoryInner>,
+}
+
+#[derive(Default)]
+struct MemoryInner {
+    entries: HashMap<String, (CachedResponse, u64)>,
+    /// keys ordered by last use
+    used: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl MemoryInner {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.used.remove(&entry.1);
+            self.used.insert(tick, key.to_owned());
+            entry.1 = tick;
+        }
+    }
+}
+
+impl MemoryCache {
+    /// Create memory store with max number of stored responses.
+    pub fn new(capacity: usize) -> Self {
+        MemoryCache {
+            capacity: cmp::max(capacity, 1),
+            inner: RefCell::new(MemoryInner::default()),
+        }
+    }
+
+    /// Number of stored responses.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Returns true if there are no stored responses.
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().entries.is_empty()
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut inner = self.inner.borrow_mut();
+        inner.touch(key);
+        inner.entries.get(key).map(|entry| entry.0.clone())
+    }
+
+    fn put(&self, key: &str, response: CachedResponse) {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.entries.contains_key(key) && inner.entries.len() >= self.capacity {
+            let oldest = inner.used.keys().next().cloned();
+            if let Some(key) = oldest.and_then(|tick| inner.used.remove(&tick)) {
+                inner.entries.remove(&key);
+            }
+        }
+        inner.entries.insert(key.to_owned(), (response, 0));
+        inner.touch(key);
+    }
+
+    fn remove(&self, key: &str) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some((_, tick)) = inner.entries.remove(key) {
+            inner.used.remove(&tick);
+        }
+    }
+}
+
+/// Client middleware that caches responses.
+///
+/// Cache follows `Cache-Control` and `Expires` headers of the response, fresh
+/// responses are returned without sending a request. Stale responses with
+/// `ETag` or `Last-Modified` headers are revalidated with a conditional
+/// request. Only `GET` requests are served from the cache, successful
+/// responses to other methods except `HEAD` and `OPTIONS` invalidate
+/// cached response of the url.
+///
+/// Responses with `Vary` header and responses without freshness information
+/// or validators are not stored. Requests with conditional headers or with
+/// `Cache-Control: no-store` directive bypass the cache.
+///
+/// ```rust
+/// use actoriwc::cache::{Cache, MemoryCache};
+/// use actoriwc::Client;
+///
+/// let client = Client::build()
+///     .wrap(Cache::new(MemoryCache::new(1024)).max_body_size(65_536))
+///     .finish();
+/// ```
+pub struct Cache {
+    store: Rc<dyn CacheStore>,
+    max_body_size: usize,
+}
+
+impl Cache {
+    /// Create cache middleware with provided storage backend.
+    pub fn new<T: CacheStore + 'static>(store: T) -> Self {
+        Cache {
+            store: Rc::new(store),
+            max_body_size: 1_048_576,
+        }
+    }
+
+    /// Set max size of the response body that could be stored.
+    ///
+    /// Default value is 1Mb.
+    pub fn max_body_size(mut self, size: usize) -> Self {
+        self.max_body_size = size;
+        self
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache::new(MemoryCache::new(256))
+    }
+}
+
+impl<S> Transform<S> for Cache
+where
+    S: Service<
+            Request = ConnectRequest,
+            Response = ClientResponse,
+            Error = SendRequestError,
+        > + 'static,
+    S::Future: 'static,
+{
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type InitError = ();
+    type Transform = CacheService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(CacheService {
+            service: Rc::new(RefCell::new(service)),
+            store: self.store.clone(),
+            max_body_size: self.max_body_size,
+        })
+    }
+}
+
+/// Client middleware that caches responses, see `Cache`
+pub struct CacheService<S> {
+    service: Rc<RefCell<S>>,
+    store: Rc<dyn CacheStore>,
+    max_body_size: usize,
+}
+
+impl<S> Service for CacheService<S>
+where
+    S: Service<
+            Request = ConnectRequest,
+            Response = ClientResponse,
+            Error = SendRequestError,
+        > + 'static,
+    S::Future: 'static,
+{
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future = LocalBoxFuture<'static, Result<ClientResponse, SendRequestError>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ConnectRequest) -> Self::Future {
+        let method = req.head().method.clone();
+        let key = req.head().uri.to_string();
+        let store = self.store.clone();
+
+        if method != Method::GET {
+            let fut = self.service.borrow_mut().call(req);
+            if method == Method::HEAD || method == Method::OPTIONS {
+                return fut.boxed_local();
+            }
+            return async move {
+                let res = fut.await?;
+                if res.status().is_success() || res.status().is_redirection() {
+                    store.remove(&key);
+                }
+                Ok(res)
+            }
+            .boxed_local();
+        }
+
+        let req_directives = directives(request_headers(&req, CACHE_CONTROL));
+        if req_directives.contains(&CacheDirective::NoStore)
+            || request_headers(&req, IF_NONE_MATCH).next().is_some()
+            || request_headers(&req, IF_MODIFIED_SINCE).next().is_some()
+        {
+            return self.service.borrow_mut().call(req).boxed_local();
+        }
+        let no_cache = req_directives.contains(&CacheDirective::NoCache)
+            || req_directives.contains(&CacheDirective::MaxAge(0));
+
+        let cached = store.get(&key);
+        if let Some(ref cached) = cached {
+            if !no_cache && cached.is_fresh() {
+                log::trace!("Response is served from cache: {}", key);
+                return ok(cached.to_response()).boxed_local();
+            }
+        }
+
+        // revalidate stale response
+        let cached = cached.filter(|cached| cached.has_validators());
+        if let Some(ref cached) = cached {
+            if let Some(etag) = cached.headers.get(ETAG) {
+                req.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(modified) = cached.headers.get(LAST_MODIFIED) {
+                req.headers_mut()
+                    .insert(IF_MODIFIED_SINCE, modified.clone());
+            }
+        }
+
+        let max_body_size = self.max_body_size;
+        let fut = self.service.borrow_mut().call(req);
+
+        async move {
+            let mut res = fut.await?;
+
+            if res.status() == StatusCode::NOT_MODIFIED {
+                if let Some(mut cached) = cached {
+                    log::trace!("Cached response is revalidated: {}", key);
+                    cached.revalidated(res.headers());
+                    let response = cached.to_response();
+                    store.put(&key, cached);
+                    return Ok(response);
+                }
+            }
+
+            if !is_storable(&res, max_body_size) {
+                return Ok(res);
+            }
+
+            match read_body(&mut res, max_body_size).await {
+                Ok(body) => {
+                    store.put(
+                        &key,
+                        CachedResponse::new(
+                            res.status(),
+                            res.headers().clone(),
+                            body.clone(),
+                            SystemTime::now(),
+                        ),
+                    );
+                    let body = stream::once(ok::<_, PayloadError>(body));
+                    res.payload = Payload::Stream(Box::pin(body));
+                }
+                Err(payload) => res.payload = payload,
+            }
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+/// Check if response could be stored
+fn is_storable(res: &ClientResponse, max_body_size: usize) -> bool {
+    match res.status() {
+        StatusCode::OK
+        | StatusCode::NON_AUTHORITATIVE_INFORMATION
+        | StatusCode::MOVED_PERMANENTLY
+        | StatusCode::NOT_FOUND
+        | StatusCode::GONE => (),
+        _ => return false,
+    }
+
+    let headers = res.headers();
+    let directives = directives(headers.get_all(CACHE_CONTROL));
+    if directives.contains(&CacheDirective::NoStore) || headers.contains_key(VARY) {
+        return false;
+    }
+
+    let too_large = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse::<usize>().ok())
+        .map(|len| len > max_body_size)
+        .unwrap_or(false);
+    if too_large {
+        return false;
+    }
+
+    directives.iter().any(|directive| match directive {
+        CacheDirective::MaxAge(_) => true,
+        _ => false,
+    }) || headers.contains_key(EXPIRES)
+        || headers.contains_key(ETAG)
+        || headers.contains_key(LAST_MODIFIED)
+}
+
+/// Read response body, if body is larger than limit, response payload is
+/// returned as is
+async fn read_body(res: &mut ClientResponse, limit: usize) -> Result<Bytes, Payload> {
+    let mut payload = res.take_payload();
+    let mut body = BytesMut::new();
+
+    loop {
+        match payload.next().await {
+            Some(Ok(chunk)) => {
+                body.extend_from_slice(&chunk);
+                if body.len() > limit {
+                    let head = stream::once(ok(body.freeze()));
+                    return Err(Payload::Stream(Box::pin(head.chain(payload))));
+                }
+            }
+            Some(Err(e)) => {
+                let head = stream::iter(vec![Ok(body.freeze()), Err(e)]);
+                return Err(Payload::Stream(Box::pin(head)));
+            }
+            None => return Ok(body.freeze()),
+        }
+    }
+}
+
+/// Request headers, including extra headers of frozen requests
+fn request_headers<'a>(
+    req: &'a ConnectRequest,
+    name: HeaderName,
+) -> impl Iterator<Item = &'a HeaderValue> + 'a {
+    let extra_name = name.clone();
+    let extra = req
+        .head
+        .extra_headers()
+        .into_iter()
+        .flat_map(move |headers| headers.get_all(extra_name.clone()));
+    extra.chain(req.head().headers.get_all(name))
+}
+
+/// Parse `Cache-Control` header values
+fn directives<'a, I>(values: I) -> Vec<CacheDirective>
+where
+    I: Iterator<Item = &'a HeaderValue>,
+{
+    values
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|directive| {
+            CacheDirective::from_str(&directive.trim().to_ascii_lowercase()).ok()
+        })
+        .collect()
+}
+
+fn http_date(value: Option<&HeaderValue>) -> Option<SystemTime> {
+    value
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| HttpDate::from_str(value).ok())
+        .map(SystemTime::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(headers: &[(HeaderName, &'static str)]) -> CachedResponse {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(name.clone(), HeaderValue::from_static(*value));
+        }
+        CachedResponse::new(StatusCode::OK, map, Bytes::new(), SystemTime::now())
+    }
+
+    #[test]
+    fn test_freshness() {
+        assert!(cached(&[(CACHE_CONTROL, "max-age=60")]).is_fresh());
+        assert!(cached(&[(CACHE_CONTROL, "public, max-age=60")]).is_fresh());
+        assert!(!cached(&[(CACHE_CONTROL, "no-cache, max-age=60")]).is_fresh());
+        assert!(!cached(&[(CACHE_CONTROL, "max-age=60"), (AGE, "61")]).is_fresh());
+        assert!(!cached(&[(EXPIRES, "0")]).is_fresh());
+        assert!(!cached(&[(ETAG, "\"1\"")]).is_fresh());
+
+        let expires = HttpDate::from(SystemTime::now() + Duration::from_secs(60));
+        let mut res = cached(&[]);
+        res.headers.insert(
+            EXPIRES,
+            HeaderValue::from_str(&expires.to_string()).unwrap(),
+        );
+        assert!(res.is_fresh());
+    }
+
+    #[test]
+    fn test_memory_cache() {
+        let store = MemoryCache::new(2);
+        store.put("a", cached(&[]));
+        store.put("b", cached(&[]));
+        assert!(store.get("a").is_some());
+
+        // "b" is least recently used
+        store.put("c", cached(&[]));
+        assert_eq!(store.len(), 2);
+        assert!(store.get("a").is_some());
+        assert!(store.get("b").is_none());
+        assert!(store.get("c").is_some());
+
+        store.remove("a");
+        assert_eq!(store.len(), 1);
+    }
+}
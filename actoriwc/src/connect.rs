@@ -7,7 +7,7 @@ use std::{fmt, io, mem, net};
 use actori_codec::{AsyncRead, AsyncWrite, Framed};
 use actori_http::body::Body;
 use actori_http::client::{
-    Connect as ClientConnect, ConnectError, Connection, SendRequestError,
+    Connect as ClientConnect, ConnectError, Connection, Proxy, SendRequestError,
 };
 use actori_http::h1::ClientCodec;
 use actori_http::http::HeaderMap;
@@ -84,10 +84,10 @@ where
         addr: Option<net::SocketAddr>,
     ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let proxy = head.extensions().get::<Proxy>().cloned();
+        let fut = self
+            .0
+            .call(ClientConnect::new(head.uri.clone(), addr).with_proxy(proxy));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -108,10 +108,10 @@ where
         addr: Option<net::SocketAddr>,
     ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let proxy = head.extensions().get::<Proxy>().cloned();
+        let fut = self
+            .0
+            .call(ClientConnect::new(head.uri.clone(), addr).with_proxy(proxy));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -140,10 +140,10 @@ where
         >,
     > {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let proxy = head.extensions().get::<Proxy>().cloned();
+        let fut = self
+            .0
+            .call(ClientConnect::new(head.uri.clone(), addr).with_proxy(proxy));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -173,10 +173,10 @@ where
         >,
     > {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let proxy = head.extensions().get::<Proxy>().cloned();
+        let fut = self
+            .0
+            .call(ClientConnect::new(head.uri.clone(), addr).with_proxy(proxy));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -10,7 +10,8 @@ pub use actori_http::ws::ProtocolError as WsProtocolError;
 use actori_http::ResponseError;
 use serde_json::error::Error as JsonError;
 
-use actori_http::http::{header::HeaderValue, StatusCode};
+use actori_http::http::{header::HeaderValue, HeaderMap, StatusCode};
+use bytes::Bytes;
 use derive_more::{Display, From};
 
 /// Websocket client error
@@ -70,3 +71,40 @@ pub enum JsonPayloadError {
 
 /// Return `InternalServerError` for `JsonPayloadError`
 impl ResponseError for JsonPayloadError {}
+
+/// Error status of a response, returned by `ClientResponse::error_for_status()`
+#[derive(Debug, Display)]
+#[display(fmt = "Response error status: {}", status)]
+pub struct ResponseStatusError {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl ResponseStatusError {
+    pub(crate) fn new(status: StatusCode, headers: HeaderMap, body: Bytes) -> Self {
+        ResponseStatusError {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Response status
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Response headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Captured response body, it could be truncated
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+}
+
+/// Return `InternalServerError` for `ResponseStatusError`
+impl ResponseError for ResponseStatusError {}
@@ -16,7 +16,7 @@
 //!    let mut client = Client::default();
 //!
 //!    let response = client.get("http://www.rust-lang.org") // <- Create request builder
-//!        .header("User-Agent", "Actori-web")
+//!        .insert_header(("User-Agent", "Actori-web"))
 //!        .send()                             // <- Send http request
 //!        .await;
 //!
@@ -28,18 +28,23 @@ use std::convert::TryFrom;
 use std::rc::Rc;
 use std::time::Duration;
 
-pub use actori_http::{client::Connector, cookie, http};
+pub use actori_http::client::{Connector, Proxy};
+pub use actori_http::{cookie, http};
 
-use actori_http::http::{Error as HttpError, HeaderMap, Method, Uri};
+use actori_http::http::{Error as HttpError, HeaderMap, Method, Uri, Version};
 use actori_http::RequestHead;
 
 mod builder;
+pub mod cache;
 mod connect;
 pub mod error;
 mod frozen;
+pub mod middleware;
 mod request;
 mod response;
+mod retry;
 mod sender;
+mod signer;
 pub mod test;
 pub mod ws;
 
@@ -47,10 +52,15 @@ pub use self::builder::ClientBuilder;
 pub use self::connect::BoxedSocket;
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
 pub use self::request::ClientRequest;
-pub use self::response::{ClientResponse, JsonBody, MessageBody};
+pub use self::response::{
+    ClientResponse, ErrorForStatus, JsonBody, MessageBody, TextBody,
+};
+pub use self::retry::RetryPolicy;
 pub use self::sender::SendClientRequest;
+pub use self::signer::{HmacSigner, RequestSigner, SignableBody, SignableRequest};
 
 use self::connect::{Connect, ConnectorWrapper};
+use self::middleware::ClientService;
 
 /// An HTTP Client
 ///
@@ -62,7 +72,7 @@ use self::connect::{Connect, ConnectorWrapper};
 ///     let mut client = Client::default();
 ///
 ///     let res = client.get("http://www.rust-lang.org") // <- Create request builder
-///         .header("User-Agent", "Actori-web")
+///         .insert_header(("User-Agent", "Actori-web"))
 ///         .send()                             // <- Send http request
 ///         .await;                             // <- send request and wait for response
 ///
@@ -73,19 +83,25 @@ use self::connect::{Connect, ConnectorWrapper};
 pub struct Client(Rc<ClientConfig>);
 
 pub(crate) struct ClientConfig {
-    pub(crate) connector: RefCell<Box<dyn Connect>>,
+    pub(crate) connector: Rc<RefCell<Box<dyn Connect>>>,
     pub(crate) headers: HeaderMap,
+    pub(crate) version: Version,
     pub(crate) timeout: Option<Duration>,
+    pub(crate) signer: Option<Rc<dyn RequestSigner>>,
+    pub(crate) middleware: Option<RefCell<ClientService>>,
 }
 
 impl Default for Client {
     fn default() -> Self {
         Client(Rc::new(ClientConfig {
-            connector: RefCell::new(Box::new(ConnectorWrapper(
+            connector: Rc::new(RefCell::new(Box::new(ConnectorWrapper(
                 Connector::new().finish(),
-            ))),
+            )))),
             headers: HeaderMap::new(),
+            version: Version::HTTP_11,
             timeout: Some(Duration::from_secs(5)),
+            signer: None,
+            middleware: None,
         }))
     }
 }
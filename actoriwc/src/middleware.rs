@@ -0,0 +1,134 @@
+//! Client middleware
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::{fmt, net};
+
+use actori_http::body::Body;
+use actori_http::http::HeaderMap;
+use actori_http::{RequestHead, RequestHeadType};
+use actori_service::boxed::BoxService;
+use actori_service::Service;
+
+use crate::connect::Connect;
+use crate::error::SendRequestError;
+use crate::response::ClientResponse;
+
+pub use crate::retry::RetryService;
+
+/// Boxed middleware chain of the client
+pub(crate) type ClientService =
+    BoxService<ConnectRequest, ClientResponse, SendRequestError>;
+
+/// Request that is passed through client middleware to the connector.
+///
+/// Middleware registered with `ClientBuilder::wrap()` is a `Transform` of
+/// a service that accepts `ConnectRequest` and resolves to `ClientResponse`.
+pub struct ConnectRequest {
+    pub(crate) head: RequestHeadType,
+    pub(crate) body: Body,
+    pub(crate) addr: Option<net::SocketAddr>,
+}
+
+impl ConnectRequest {
+    /// Create request from request head and body.
+    pub fn new(head: RequestHead, body: Body) -> Self {
+        ConnectRequest {
+            head: RequestHeadType::Owned(head),
+            body,
+            addr: None,
+        }
+    }
+
+    pub(crate) fn from_parts(
+        head: RequestHeadType,
+        body: Body,
+        addr: Option<net::SocketAddr>,
+    ) -> Self {
+        ConnectRequest { head, body, addr }
+    }
+
+    /// Request head.
+    pub fn head(&self) -> &RequestHead {
+        self.head.as_ref()
+    }
+
+    /// Mutable request headers.
+    ///
+    /// Head of frozen requests is shared, for such requests headers are
+    /// stored as extra headers that take precedence over headers of the
+    /// shared head.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        match self.head {
+            RequestHeadType::Owned(ref mut head) => &mut head.headers,
+            RequestHeadType::Rc(_, ref mut extra) => {
+                extra.get_or_insert_with(HeaderMap::new)
+            }
+        }
+    }
+
+    /// Request body.
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    /// Replace request body.
+    pub fn set_body(&mut self, body: Body) -> Body {
+        std::mem::replace(&mut self.body, body)
+    }
+
+    /// Server address, if it is set with `ClientRequest::address()`.
+    pub fn addr(&self) -> Option<net::SocketAddr> {
+        self.addr
+    }
+}
+
+impl fmt::Debug for ConnectRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.head();
+        writeln!(
+            f,
+            "\nConnectRequest {:?} {}:{}",
+            head.version, head.method, head.uri
+        )?;
+        writeln!(f, "  headers:")?;
+        for (key, val) in head.headers.iter() {
+            writeln!(f, "    {:?}: {:?}", key, val)?;
+        }
+        if let Some(extra) = self.head.extra_headers() {
+            for (key, val) in extra.iter() {
+                writeln!(f, "    {:?}: {:?}", key, val)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Innermost service of the middleware chain, sends request with connector
+pub(crate) struct ConnectService(pub(crate) Rc<RefCell<Box<dyn Connect>>>);
+
+impl Service for ConnectService {
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: ConnectRequest) -> Self::Future {
+        let mut connector = self.0.borrow_mut();
+        match req.head {
+            RequestHeadType::Owned(head) => {
+                connector.send_request(head, req.body, req.addr)
+            }
+            RequestHeadType::Rc(head, extra_headers) => {
+                connector.send_request_extra(head, extra_headers, req.body, req.addr)
+            }
+        }
+    }
+}
@@ -9,24 +9,41 @@ use futures_core::Stream;
 use percent_encoding::percent_encode;
 use serde::Serialize;
 
-use actori_http::body::Body;
+use actori_http::body::{Body, PayloadBody};
+use actori_http::client::{InformationalHandler, Proxy};
 use actori_http::cookie::{Cookie, CookieJar, USERINFO};
-use actori_http::http::header::{self, Header, IntoHeaderValue};
+use actori_http::http::header::{self, Header, IntoHeaderPair, IntoHeaderValue};
 use actori_http::http::{
     uri, ConnectionType, Error as HttpError, HeaderMap, HeaderName, HeaderValue, Method,
     Uri, Version,
 };
-use actori_http::{Error, RequestHead};
+use actori_http::{Error, Payload, RequestHead, ResponseHead};
 
 use crate::error::{FreezeRequestError, InvalidUrl};
 use crate::frozen::FrozenClientRequest;
 use crate::sender::{PrepForSendingError, RequestSender, SendClientRequest};
 use crate::ClientConfig;
 
-#[cfg(any(feature = "flate2-zlib", feature = "flate2-rust"))]
+#[cfg(all(
+    not(feature = "compress-zstd"),
+    any(feature = "flate2-zlib", feature = "flate2-rust")
+))]
 const HTTPS_ENCODING: &str = "br, gzip, deflate";
-#[cfg(not(any(feature = "flate2-zlib", feature = "flate2-rust")))]
+#[cfg(all(
+    not(feature = "compress-zstd"),
+    not(any(feature = "flate2-zlib", feature = "flate2-rust"))
+))]
 const HTTPS_ENCODING: &str = "br";
+#[cfg(all(
+    feature = "compress-zstd",
+    any(feature = "flate2-zlib", feature = "flate2-rust")
+))]
+const HTTPS_ENCODING: &str = "zstd, br, gzip, deflate";
+#[cfg(all(
+    feature = "compress-zstd",
+    not(any(feature = "flate2-zlib", feature = "flate2-rust"))
+))]
+const HTTPS_ENCODING: &str = "zstd, br";
 
 /// An HTTP Client request builder
 ///
@@ -40,7 +57,7 @@ const HTTPS_ENCODING: &str = "br";
 /// async fn main() {
 ///    let response = actoriwc::Client::new()
 ///         .get("http://www.rust-lang.org") // <- Create request builder
-///         .header("User-Agent", "Actori-web")
+///         .insert_header(("User-Agent", "Actori-web"))
 ///         .send()                          // <- Send http request
 ///         .await;
 ///
@@ -67,6 +84,7 @@ impl ClientRequest {
         Uri: TryFrom<U>,
         <Uri as TryFrom<U>>::Error: Into<HttpError>,
     {
+        let version = config.version;
         ClientRequest {
             config,
             head: RequestHead::default(),
@@ -78,6 +96,7 @@ impl ClientRequest {
         }
         .method(method)
         .uri(uri)
+        .version(version)
     }
 
     /// Set HTTP URI of request.
@@ -108,6 +127,15 @@ impl ClientRequest {
         self
     }
 
+    /// Send this request through the proxy.
+    ///
+    /// Overrides proxy of the client's connector, `Proxy::direct()`
+    /// bypasses connector's proxy.
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        self.head.extensions_mut().insert(proxy);
+        self
+    }
+
     /// Set HTTP method of this request.
     #[inline]
     pub fn method(mut self, method: Method) -> Self {
@@ -175,10 +203,9 @@ impl ClientRequest {
         self
     }
 
-    /// Append a header.
+    /// Insert a header, replacing any existing header with the same name.
     ///
-    /// Header gets appended to existing header.
-    /// To override header use `set_header()` method.
+    /// Accepts typed headers and `(name, value)` tuples.
     ///
     /// ```rust
     /// use actoriwc::{http, Client};
@@ -187,12 +214,53 @@ impl ClientRequest {
     /// # actori_rt::System::new("test").block_on(async {
     ///     let req = Client::new()
     ///         .get("http://www.rust-lang.org")
-    ///         .header("X-TEST", "value")
-    ///         .header(http::header::CONTENT_TYPE, "application/json");
+    ///         .insert_header(("X-TEST", "value"))
+    ///         .insert_header(http::header::ContentType(mime::APPLICATION_JSON));
     /// #   Ok::<_, ()>(())
     /// # });
     /// }
     /// ```
+    pub fn insert_header<H: IntoHeaderPair>(mut self, header: H) -> Self {
+        match header.try_into_header_pair() {
+            Ok((key, value)) => self.head.headers.insert(key, value),
+            Err(e) => self.err = Some(e),
+        }
+        self
+    }
+
+    /// Append a header, keeping any existing headers with the same name.
+    ///
+    /// Accepts typed headers and `(name, value)` tuples.
+    ///
+    /// ```rust
+    /// use actoriwc::{http, Client};
+    ///
+    /// fn main() {
+    /// # actori_rt::System::new("test").block_on(async {
+    ///     let req = Client::new()
+    ///         .get("http://www.rust-lang.org")
+    ///         .append_header((http::header::ACCEPT, "text/html"))
+    ///         .append_header((http::header::ACCEPT, "application/json"));
+    /// #   Ok::<_, ()>(())
+    /// # });
+    /// }
+    /// ```
+    pub fn append_header<H: IntoHeaderPair>(mut self, header: H) -> Self {
+        match header.try_into_header_pair() {
+            Ok((key, value)) => self.head.headers.append(key, value),
+            Err(e) => self.err = Some(e),
+        }
+        self
+    }
+
+    /// Append a header.
+    ///
+    /// Header gets appended to existing header.
+    /// To override header use `set_header()` method.
+    #[deprecated(
+        since = "1.0.2",
+        note = "use `insert_header()` or `append_header()` instead"
+    )]
     pub fn header<K, V>(mut self, key: K, value: V) -> Self
     where
         HeaderName: TryFrom<K>,
@@ -262,6 +330,47 @@ impl ClientRequest {
         self
     }
 
+    /// Enable or disable keep-alive of the connection for this request.
+    ///
+    /// Keep-alive is enabled by default for `HTTP/1.1` requests. Enabled
+    /// keep-alive of `HTTP/1.0` request is sent with `Connection: keep-alive`
+    /// header. This setting affect only http/1 connections.
+    #[inline]
+    pub fn keep_alive(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.head.set_connection_type(ConnectionType::KeepAlive);
+        } else {
+            self.head.set_connection_type(ConnectionType::Close);
+        }
+        self
+    }
+
+    /// Send `Expect: 100-continue` header, request body is sent after
+    /// server responds with `100 Continue`.
+    ///
+    /// If server responds with final status instead, body is not sent and
+    /// the response is returned. Body is sent anyway if server does not
+    /// respond in time. This setting affect only http/1 connections.
+    #[inline]
+    pub fn expect_continue(self) -> Self {
+        self.insert_header((header::EXPECT, "100-continue"))
+    }
+
+    /// Set handler for informational (1xx) responses received before
+    /// the final response, i.e. `103 Early Hints`.
+    ///
+    /// Informational responses are skipped if handler is not set.
+    /// This setting affect only http/1 connections.
+    pub fn on_informational<F>(self, f: F) -> Self
+    where
+        F: Fn(&ResponseHead) + 'static,
+    {
+        self.head
+            .extensions_mut()
+            .insert(InformationalHandler::new(f));
+        self
+    }
+
     /// Set request's content type
     #[inline]
     pub fn content_type<V>(mut self, value: V) -> Self
@@ -279,7 +388,7 @@ impl ClientRequest {
     /// Set content length
     #[inline]
     pub fn content_length(self, len: u64) -> Self {
-        self.header(header::CONTENT_LENGTH, len)
+        self.insert_header((header::CONTENT_LENGTH, len))
     }
 
     /// Set HTTP basic authorization header
@@ -291,10 +400,10 @@ impl ClientRequest {
             Some(password) => format!("{}:{}", username, password),
             None => format!("{}:", username),
         };
-        self.header(
+        self.insert_header((
             header::AUTHORIZATION,
             format!("Basic {}", base64::encode(&auth)),
-        )
+        ))
     }
 
     /// Set HTTP bearer authentication header
@@ -302,7 +411,7 @@ impl ClientRequest {
     where
         T: fmt::Display,
     {
-        self.header(header::AUTHORIZATION, format!("Bearer {}", token))
+        self.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
     }
 
     /// Set a cookie
@@ -490,6 +599,16 @@ impl ClientRequest {
         )
     }
 
+    /// Forward incoming request payload and generate `ClientRequest`.
+    ///
+    /// Payload chunks are sent as they arrive, without buffering. Trailers
+    /// of a chunked payload are forwarded after the last chunk. This is
+    /// useful for proxies, request headers like `Content-Length` or
+    /// `Transfer-Encoding` should be copied from the incoming request.
+    pub fn send_payload(self, payload: Payload) -> SendClientRequest {
+        self.send_body(Body::from_message(PayloadBody::new(payload)))
+    }
+
     /// Set an empty body and generate `ClientRequest`.
     pub fn send(self) -> SendClientRequest {
         let slf = match self.prep_for_sending() {
@@ -588,7 +707,7 @@ mod tests {
 
     #[test]
     fn test_debug() {
-        let request = Client::new().get("/").header("x-test", "111");
+        let request = Client::new().get("/").insert_header(("x-test", "111"));
         let repr = format!("{:?}", request);
         assert!(repr.contains("ClientRequest"));
         assert!(repr.contains("x-test"));
@@ -601,13 +720,13 @@ mod tests {
             .version(Version::HTTP_2)
             .set(header::Date(SystemTime::now().into()))
             .content_type("plain/text")
-            .if_true(true, |req| req.header(header::SERVER, "actoriwc"))
-            .if_true(false, |req| req.header(header::EXPECT, "actoriwc"))
+            .if_true(true, |req| req.insert_header((header::SERVER, "actoriwc")))
+            .if_true(false, |req| req.insert_header((header::EXPECT, "actoriwc")))
             .if_some(Some("server"), |val, req| {
-                req.header(header::USER_AGENT, val)
+                req.insert_header((header::USER_AGENT, val))
             })
             .if_some(Option::<&str>::None, |_, req| {
-                req.header(header::ALLOW, "1")
+                req.insert_header((header::ALLOW, "1"))
             })
             .content_length(100);
         assert!(req.headers().contains_key(header::CONTENT_TYPE));
@@ -621,6 +740,22 @@ mod tests {
         let _ = req.send_body("");
     }
 
+    #[test]
+    fn test_http_version() {
+        let client = Client::build().http_version(Version::HTTP_10).finish();
+        let req = client.get("/").keep_alive(true);
+        assert_eq!(req.head.version, Version::HTTP_10);
+        assert_eq!(req.head.connection_type(), ConnectionType::KeepAlive);
+
+        let req = client.get("/").version(Version::HTTP_11).keep_alive(false);
+        assert_eq!(req.head.version, Version::HTTP_11);
+        assert_eq!(req.head.connection_type(), ConnectionType::Close);
+
+        let req = Client::new().get("/");
+        assert_eq!(req.head.version, Version::HTTP_11);
+        assert_eq!(req.head.connection_type(), ConnectionType::KeepAlive);
+    }
+
     #[test]
     fn test_client_header() {
         let req = Client::build()
@@ -658,6 +793,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_insert_append_header() {
+        let req = Client::build()
+            .header(header::ACCEPT, "text/html")
+            .finish()
+            .get("/")
+            .append_header((header::ACCEPT, "application/json"))
+            .insert_header(("content-type", "text/plain"))
+            .insert_header(header::ContentType(mime::APPLICATION_JSON));
+
+        assert_eq!(req.head.headers.get_all(header::ACCEPT).count(), 2);
+        assert_eq!(
+            req.head.headers.get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert!(req.err.is_none());
+
+        let req = Client::new().get("/").insert_header(("bad header", "1"));
+        assert!(req.err.is_some());
+    }
+
     #[test]
     fn client_basic_auth() {
         let req = Client::new()
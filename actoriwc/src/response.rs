@@ -1,10 +1,11 @@
 use std::cell::{Ref, RefMut};
-use std::fmt;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::{cmp, fmt};
 
 use bytes::{Bytes, BytesMut};
+use encoding_rs::{Encoding, UTF_8};
 use futures_core::{ready, Future, Stream};
 
 use actori_http::cookie::Cookie;
@@ -14,7 +15,7 @@ use actori_http::http::{HeaderMap, StatusCode, Version};
 use actori_http::{Extensions, HttpMessage, Payload, PayloadStream, ResponseHead};
 use serde::de::DeserializeOwned;
 
-use crate::error::JsonPayloadError;
+use crate::error::{JsonPayloadError, ResponseStatusError};
 
 /// Client Response
 pub struct ClientResponse<S = PayloadStream> {
@@ -123,6 +124,37 @@ where
     pub fn json<T: DeserializeOwned>(&mut self) -> JsonBody<S, T> {
         JsonBody::new(self)
     }
+
+    /// Loads and parse `application/json` encoded body with the specified
+    /// max size of the body.
+    pub fn json_with_limit<T: DeserializeOwned>(
+        &mut self,
+        limit: usize,
+    ) -> JsonBody<S, T> {
+        JsonBody::new(self).limit(limit)
+    }
+
+    /// Loads http response's body and decodes it to a string.
+    ///
+    /// Body is decoded with a charset from `Content-Type` header, UTF-8 is
+    /// used if charset is not set or is unknown. Malformed sequences are
+    /// replaced with the replacement character.
+    pub fn text(&mut self) -> TextBody<S> {
+        TextBody::new(self)
+    }
+
+    /// Turn response with client or server error status into an error.
+    ///
+    /// Returned future resolves to the response itself, if status is not
+    /// an error. Otherwise, first bytes of the response body are read and
+    /// returned within `ResponseStatusError`, by default up to 8Kb.
+    pub fn error_for_status(self) -> ErrorForStatus<S> {
+        ErrorForStatus {
+            res: Some(self),
+            buf: BytesMut::new(),
+            limit: 8192,
+        }
+    }
 }
 
 impl<S> Stream for ClientResponse<S>
@@ -231,6 +263,7 @@ where
 /// * content length is greater than 64k
 pub struct JsonBody<S, U> {
     length: Option<usize>,
+    encoding: &'static Encoding,
     err: Option<JsonPayloadError>,
     fut: Option<ReadBody<S>>,
     _t: PhantomData<U>,
@@ -252,11 +285,14 @@ where
         if !json {
             return JsonBody {
                 length: None,
+                encoding: UTF_8,
                 fut: None,
                 err: Some(JsonPayloadError::ContentType),
                 _t: PhantomData,
             };
         }
+        // unknown charset is decoded as utf-8
+        let encoding = req.encoding().unwrap_or(UTF_8);
 
         let mut len = None;
         if let Some(l) = req.headers().get(&CONTENT_LENGTH) {
@@ -269,6 +305,7 @@ where
 
         JsonBody {
             length: len,
+            encoding,
             err: None,
             fut: Some(ReadBody::new(req.take_payload(), 65536)),
             _t: PhantomData,
@@ -311,8 +348,110 @@ where
             }
         }
 
-        let body = ready!(Pin::new(&mut self.get_mut().fut.as_mut().unwrap()).poll(cx))?;
-        Poll::Ready(serde_json::from_slice::<U>(&body).map_err(JsonPayloadError::from))
+        let this = self.get_mut();
+        let body = ready!(Pin::new(&mut this.fut.as_mut().unwrap()).poll(cx))?;
+        let res = if this.encoding == UTF_8 {
+            serde_json::from_slice::<U>(&body)
+        } else {
+            serde_json::from_str::<U>(
+                &this.encoding.decode_without_bom_handling(&body).0,
+            )
+        };
+        Poll::Ready(res.map_err(JsonPayloadError::from))
+    }
+}
+
+/// Future that resolves to a decoded response body.
+pub struct TextBody<S> {
+    body: MessageBody<S>,
+    encoding: &'static Encoding,
+}
+
+impl<S> TextBody<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    /// Create `TextBody` for response.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        TextBody {
+            encoding: res.encoding().unwrap_or(UTF_8),
+            body: MessageBody::new(res),
+        }
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.body = self.body.limit(limit);
+        self
+    }
+}
+
+impl<S> Future for TextBody<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Output = Result<String, PayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let body = ready!(Pin::new(&mut this.body).poll(cx))?;
+        let text = this.encoding.decode_without_bom_handling(&body).0;
+        Poll::Ready(Ok(text.into_owned()))
+    }
+}
+
+/// Future that checks response status, it resolves to a response or
+/// to a `ResponseStatusError`.
+pub struct ErrorForStatus<S> {
+    res: Option<ClientResponse<S>>,
+    buf: BytesMut,
+    limit: usize,
+}
+
+impl<S> ErrorForStatus<S> {
+    /// Change max size of captured error body. By default max size is 8Kb
+    ///
+    /// Rest of the body is discarded.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<S> Future for ErrorForStatus<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Output = Result<ClientResponse<S>, ResponseStatusError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let status = this.res.as_ref().unwrap().status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Poll::Ready(Ok(this.res.take().unwrap()));
+        }
+
+        // capture error body, read errors are ignored
+        while this.buf.len() < this.limit {
+            let payload = &mut this.res.as_mut().unwrap().payload;
+            match Pin::new(payload).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let len = cmp::min(chunk.len(), this.limit - this.buf.len());
+                    this.buf.extend_from_slice(&chunk[..len]);
+                }
+                Poll::Ready(Some(Err(_))) | Poll::Ready(None) => break,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let res = this.res.take().unwrap();
+        Poll::Ready(Err(ResponseStatusError::new(
+            status,
+            res.head.headers,
+            this.buf.split().freeze(),
+        )))
     }
 }
 
@@ -465,4 +604,87 @@ mod tests {
             }
         );
     }
+
+    #[actori_rt::test]
+    async fn test_json_with_limit() {
+        let mut req = TestResponse::default()
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .finish();
+        let json = req.json_with_limit::<MyObject>(10).await;
+        assert!(json_eq(
+            json.err().unwrap(),
+            JsonPayloadError::Payload(PayloadError::Overflow)
+        ));
+
+        // latin1 encoded body
+        let mut req = TestResponse::default()
+            .header(header::CONTENT_TYPE, "application/json; charset=iso-8859-1")
+            .set_payload(Bytes::from_static(b"{\"name\": \"caf\xe9\"}"))
+            .finish();
+        let json = req.json_with_limit::<MyObject>(100).await;
+        assert_eq!(
+            json.ok().unwrap(),
+            MyObject {
+                name: "café".to_owned()
+            }
+        );
+
+        // unknown charset
+        let mut req = TestResponse::default()
+            .header(header::CONTENT_TYPE, "application/json; charset=unknown")
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .finish();
+        let json = req.json_with_limit::<MyObject>(100).await;
+        assert_eq!(
+            json.ok().unwrap(),
+            MyObject {
+                name: "test".to_owned()
+            }
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_text() {
+        let mut req = TestResponse::default()
+            .set_payload(Bytes::from_static(b"test"))
+            .finish();
+        assert_eq!(req.text().await.unwrap(), "test");
+
+        let mut req = TestResponse::default()
+            .header(header::CONTENT_TYPE, "text/plain; charset=windows-1251")
+            .set_payload(Bytes::from_static(b"\xcf\xf0\xe8\xe2\xe5\xf2"))
+            .finish();
+        assert_eq!(req.text().await.unwrap(), "Привет");
+
+        let mut req = TestResponse::default()
+            .set_payload(Bytes::from_static(b"11111111111111"))
+            .finish();
+        match req.text().limit(5).await.err().unwrap() {
+            PayloadError::Overflow => (),
+            _ => unreachable!("error"),
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_error_for_status() {
+        let req = TestResponse::default()
+            .set_payload(Bytes::from_static(b"test"))
+            .finish();
+        let mut res = req.error_for_status().await.unwrap();
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"test"));
+
+        let req = TestResponse::default()
+            .status(StatusCode::NOT_FOUND)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .set_payload(Bytes::from_static(b"resource is not found"))
+            .finish();
+        let err = req.error_for_status().limit(8).await.err().unwrap();
+        assert_eq!(err.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            err.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(err.body(), &Bytes::from_static(b"resource"));
+    }
 }
@@ -0,0 +1,329 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actori_http::body::Body;
+use actori_http::http::{Method, StatusCode};
+use actori_http::RequestHeadType;
+use actori_rt::time::delay_for;
+use actori_service::{Service, Transform};
+use futures_util::future::{ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+use rand::Rng;
+
+use crate::error::SendRequestError;
+use crate::middleware::ConnectRequest;
+use crate::response::ClientResponse;
+
+/// Retry policy of the client.
+///
+/// Failed requests are sent again with exponential backoff. Request is
+/// retried if `SendRequestError::is_retryable()` returns true for send error,
+/// or if response status is in the policy's status set, 502, 503 and 504 by
+/// default. Every attempt acquires a connection from the connector's pool,
+/// failed connections are not reused.
+///
+/// Only requests with `Body::None`, `Body::Empty` or `Body::Bytes` payload
+/// are retried, streaming payload could not be sent twice.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actoriwc::{Client, RetryPolicy};
+///
+/// let client = Client::build()
+///     .retry(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .base_delay(Duration::from_millis(50)),
+///     )
+///     .finish();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    statuses: Vec<StatusCode>,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    idempotent_only: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Create retry policy with default settings.
+    pub fn new() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            statuses: vec![
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            idempotent_only: true,
+        }
+    }
+
+    /// Set max number of attempts, including the first one.
+    ///
+    /// By default request is sent at most 3 times.
+    pub fn max_attempts(mut self, num: usize) -> Self {
+        self.max_attempts = cmp::max(num, 1);
+        self
+    }
+
+    /// Set response statuses that are retried.
+    ///
+    /// By default 502, 503 and 504 responses are retried.
+    pub fn retry_on_status<I>(mut self, statuses: I) -> Self
+    where
+        I: IntoIterator<Item = StatusCode>,
+    {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Set delay before the first retry, delay is doubled for every
+    /// next retry.
+    ///
+    /// Default value is 100 milliseconds.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set max delay between retries.
+    ///
+    /// Default value is 10 seconds.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Randomize delays between retries.
+    ///
+    /// If jitter is enabled, delay is picked randomly between half and full
+    /// backoff delay. Jitter is enabled by default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Retry only requests with idempotent methods.
+    ///
+    /// Requests that fail to acquire a connection are retried regardless of
+    /// the method, such requests are not sent to the server. Enabled by default.
+    pub fn idempotent_only(mut self, idempotent_only: bool) -> Self {
+        self.idempotent_only = idempotent_only;
+        self
+    }
+
+    fn is_idempotent(method: &Method) -> bool {
+        match *method {
+            Method::GET
+            | Method::HEAD
+            | Method::PUT
+            | Method::DELETE
+            | Method::OPTIONS
+            | Method::TRACE => true,
+            _ => false,
+        }
+    }
+
+    fn retry_error(&self, err: &SendRequestError, resend: bool) -> bool {
+        match err {
+            SendRequestError::Connect(_) => err.is_retryable(),
+            _ => resend && err.is_retryable(),
+        }
+    }
+
+    fn retry_status(&self, status: StatusCode, resend: bool) -> bool {
+        resend && self.statuses.contains(&status)
+    }
+
+    /// Delay before retry, `attempt` is number of failed attempts
+    fn delay(&self, attempt: usize) -> Duration {
+        let exp = cmp::min(attempt.saturating_sub(1), 16) as u32;
+        let delay = self
+            .base_delay
+            .checked_mul(2u32.pow(exp))
+            .map(|delay| cmp::min(delay, self.max_delay))
+            .unwrap_or(self.max_delay);
+        if self.jitter && delay > Duration::from_millis(0) {
+            let half = delay / 2;
+            let nanos = rand::thread_rng().gen_range(0, half.as_nanos() as u64 + 1);
+            half + Duration::from_nanos(nanos)
+        } else {
+            delay
+        }
+    }
+}
+
+impl<S> Transform<S> for RetryPolicy
+where
+    S: Service<
+            Request = ConnectRequest,
+            Response = ClientResponse,
+            Error = SendRequestError,
+        > + 'static,
+    S::Future: 'static,
+{
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type InitError = ();
+    type Transform = RetryService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RetryService {
+            service: Rc::new(RefCell::new(service)),
+            policy: Rc::new(self.clone()),
+        })
+    }
+}
+
+/// Client middleware that sends failed requests again, see `RetryPolicy`
+pub struct RetryService<S> {
+    service: Rc<RefCell<S>>,
+    policy: Rc<RetryPolicy>,
+}
+
+impl<S> Service for RetryService<S>
+where
+    S: Service<
+            Request = ConnectRequest,
+            Response = ClientResponse,
+            Error = SendRequestError,
+        > + 'static,
+    S::Future: 'static,
+{
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future = LocalBoxFuture<'static, Result<ClientResponse, SendRequestError>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ConnectRequest) -> Self::Future {
+        let ConnectRequest { head, body, addr } = req;
+
+        let body = match body {
+            Body::None => Body::None,
+            Body::Empty => Body::Empty,
+            Body::Bytes(bytes) => Body::Bytes(bytes),
+            body => {
+                let req = ConnectRequest::from_parts(head, body, addr);
+                return self.service.borrow_mut().call(req).boxed_local();
+            }
+        };
+
+        // head is shared between attempts
+        let (head, extra_headers) = match head {
+            RequestHeadType::Owned(head) => (Rc::new(head), None),
+            RequestHeadType::Rc(head, extra_headers) => (head, extra_headers),
+        };
+        // request that reached the server could be sent again
+        let resend =
+            !self.policy.idempotent_only || RetryPolicy::is_idempotent(&head.method);
+        let service = self.service.clone();
+        let policy = self.policy.clone();
+        let fut = service.borrow_mut().call(ConnectRequest::from_parts(
+            RequestHeadType::Rc(head.clone(), extra_headers.clone()),
+            clone_body(&body),
+            addr,
+        ));
+
+        async move {
+            let mut fut = fut;
+            let mut attempt = 1;
+            loop {
+                let res = fut.await;
+                if attempt >= policy.max_attempts {
+                    return res;
+                }
+                match res {
+                    Ok(ref res) if policy.retry_status(res.status(), resend) => {
+                        log::trace!("Retry request, response status: {}", res.status())
+                    }
+                    Err(ref e) if policy.retry_error(e, resend) => {
+                        log::trace!("Retry request, error: {}", e)
+                    }
+                    res => return res,
+                }
+
+                delay_for(policy.delay(attempt)).await;
+                attempt += 1;
+
+                poll_fn(|cx| service.borrow_mut().poll_ready(cx)).await?;
+                fut = service.borrow_mut().call(ConnectRequest::from_parts(
+                    RequestHeadType::Rc(head.clone(), extra_headers.clone()),
+                    clone_body(&body),
+                    addr,
+                ));
+            }
+        }
+        .boxed_local()
+    }
+}
+
+fn clone_body(body: &Body) -> Body {
+    match body {
+        Body::Bytes(bytes) => Body::Bytes(bytes.clone()),
+        Body::Empty => Body::Empty,
+        _ => Body::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ConnectError;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(350))
+            .jitter(false);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+        assert_eq!(policy.delay(100), Duration::from_millis(350));
+
+        let policy = policy.jitter(true);
+        for _ in 0..10 {
+            let delay = policy.delay(2);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let policy = RetryPolicy::new();
+        assert!(policy.retry_status(StatusCode::SERVICE_UNAVAILABLE, true));
+        assert!(!policy.retry_status(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(!policy.retry_status(StatusCode::INTERNAL_SERVER_ERROR, true));
+
+        let err = SendRequestError::Connect(ConnectError::Disconnected);
+        assert!(policy.retry_error(&err, false));
+        let err = SendRequestError::Send(std::io::ErrorKind::BrokenPipe.into());
+        assert!(policy.retry_error(&err, true));
+        assert!(!policy.retry_error(&err, false));
+        assert!(!policy.retry_error(&SendRequestError::Timeout, true));
+
+        assert!(RetryPolicy::is_idempotent(&Method::PUT));
+        assert!(!RetryPolicy::is_idempotent(&Method::POST));
+    }
+}
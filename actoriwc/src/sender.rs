@@ -14,7 +14,9 @@ use serde_json;
 use actori_http::body::{Body, BodyStream};
 use actori_http::http::header::{self, IntoHeaderValue};
 use actori_http::http::{Error as HttpError, HeaderMap, HeaderName};
-use actori_http::{Error, RequestHead};
+use actori_http::trace::{self, TraceContext};
+use actori_http::{Error, RequestHead, RequestHeadType};
+use actori_service::Service;
 
 #[cfg(feature = "compress")]
 use actori_http::encoding::Decoder;
@@ -24,7 +26,9 @@ use actori_http::http::header::ContentEncoding;
 use actori_http::{Payload, PayloadStream};
 
 use crate::error::{FreezeRequestError, InvalidUrl, SendRequestError};
+use crate::middleware::ConnectRequest;
 use crate::response::ClientResponse;
+use crate::signer::{RequestSigner, SignableBody, SignableRequest};
 use crate::ClientConfig;
 
 #[derive(Debug, From)]
@@ -172,7 +176,7 @@ pub(crate) enum RequestSender {
 
 impl RequestSender {
     pub(crate) fn send_body<B>(
-        self,
+        mut self,
         addr: Option<net::SocketAddr>,
         response_decompress: bool,
         timeout: Option<Duration>,
@@ -182,14 +186,36 @@ impl RequestSender {
     where
         B: Into<Body>,
     {
-        let mut connector = config.connector.borrow_mut();
-
-        let fut = match self {
-            RequestSender::Owned(head) => {
-                connector.send_request(head, body.into(), addr)
+        let body = body.into();
+        // propagate trace of the request that is processed by the server
+        if let Some(ctx) = TraceContext::current() {
+            if let Err(e) = self.set_trace_context(ctx.child()) {
+                return e.into();
             }
-            RequestSender::Rc(head, extra_headers) => {
-                connector.send_request_extra(head, extra_headers, body.into(), addr)
+        }
+        if let Some(ref signer) = config.signer {
+            if let Err(e) = self.sign(&**signer, &body) {
+                return e.into();
+            }
+        }
+
+        let fut = if let Some(ref middleware) = config.middleware {
+            let head = match self {
+                RequestSender::Owned(head) => RequestHeadType::Owned(head),
+                RequestSender::Rc(head, extra_headers) => {
+                    RequestHeadType::Rc(head, extra_headers)
+                }
+            };
+            middleware
+                .borrow_mut()
+                .call(ConnectRequest::from_parts(head, body, addr))
+        } else {
+            let mut connector = config.connector.borrow_mut();
+            match self {
+                RequestSender::Owned(head) => connector.send_request(head, body, addr),
+                RequestSender::Rc(head, extra_headers) => {
+                    connector.send_request_extra(head, extra_headers, body, addr)
+                }
             }
         };
 
@@ -288,6 +314,63 @@ impl RequestSender {
         self.send_body(addr, response_decompress, timeout, config, Body::Empty)
     }
 
+    /// Add signature headers to the finalized request
+    fn sign(
+        &mut self,
+        signer: &dyn RequestSigner,
+        body: &Body,
+    ) -> Result<(), HttpError> {
+        let body = match body {
+            Body::None | Body::Empty => SignableBody::Bytes(b""),
+            Body::Bytes(ref bytes) => SignableBody::Bytes(bytes),
+            Body::Message(_) => SignableBody::Unsigned,
+        };
+        let headers = match self {
+            RequestSender::Owned(head) => {
+                signer.sign(&SignableRequest::new(head, None, body))?
+            }
+            RequestSender::Rc(head, extra_headers) => {
+                signer.sign(&SignableRequest::new(head, extra_headers.as_ref(), body))?
+            }
+        };
+
+        let target = match self {
+            RequestSender::Owned(head) => &mut head.headers,
+            RequestSender::Rc(_, extra_headers) => {
+                extra_headers.get_or_insert(HeaderMap::new())
+            }
+        };
+        for name in headers.keys() {
+            target.remove(name);
+        }
+        for (name, value) in headers.iter() {
+            target.append(name.clone(), value.clone());
+        }
+        Ok(())
+    }
+
+    fn set_trace_context(&mut self, ctx: TraceContext) -> Result<(), HttpError> {
+        let traceparent = HeaderName::from_static(trace::TRACEPARENT);
+        let b3 = HeaderName::from_static(trace::B3);
+        let propagated = match self {
+            RequestSender::Owned(head) => {
+                head.headers.contains_key(&traceparent) || head.headers.contains_key(&b3)
+            }
+            RequestSender::Rc(head, extra_headers) => {
+                head.headers.contains_key(&traceparent)
+                    || head.headers.contains_key(&b3)
+                    || extra_headers
+                        .iter()
+                        .any(|h| h.contains_key(&traceparent) || h.contains_key(&b3))
+            }
+        };
+        if !propagated {
+            self.set_header_if_none(traceparent, ctx.traceparent())?;
+            self.set_header_if_none(b3, ctx.b3())?;
+        }
+        Ok(())
+    }
+
     fn set_header_if_none<V>(
         &mut self,
         key: HeaderName,
@@ -0,0 +1,335 @@
+//! Request signing
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actori_http::http::header::{HeaderName, HeaderValue, AUTHORIZATION, HOST};
+use actori_http::http::{Error as HttpError, HeaderMap, Method, Uri};
+use actori_http::RequestHead;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Request signing hook.
+///
+/// Signer is invoked right before request is sent, after all headers are
+/// set and body is serialized. Headers returned by signer replace headers
+/// with the same name of the request.
+///
+/// ```rust
+/// use actoriwc::http::{header, Error, HeaderMap, HeaderValue};
+/// use actoriwc::{Client, RequestSigner, SignableRequest};
+///
+/// struct ApiKey(&'static str);
+///
+/// impl RequestSigner for ApiKey {
+///     fn sign(&self, _: &SignableRequest<'_>) -> Result<HeaderMap, Error> {
+///         let mut headers = HeaderMap::new();
+///         headers.insert(
+///             header::HeaderName::from_static("x-api-key"),
+///             HeaderValue::from_static(self.0),
+///         );
+///         Ok(headers)
+///     }
+/// }
+///
+/// let client = Client::build().signer(ApiKey("secret")).finish();
+/// ```
+pub trait RequestSigner {
+    /// Compute signature headers for the request.
+    fn sign(&self, req: &SignableRequest<'_>) -> Result<HeaderMap, HttpError>;
+}
+
+/// Request body as seen by a signer
+#[derive(Debug, Clone, Copy)]
+pub enum SignableBody<'a> {
+    /// Body is fully known before sending
+    Bytes(&'a [u8]),
+    /// Streaming body, it is not available before sending
+    Unsigned,
+}
+
+/// Finalized request that is about to be sent
+pub struct SignableRequest<'a> {
+    head: &'a RequestHead,
+    extra_headers: Option<&'a HeaderMap>,
+    body: SignableBody<'a>,
+}
+
+impl<'a> SignableRequest<'a> {
+    pub(crate) fn new(
+        head: &'a RequestHead,
+        extra_headers: Option<&'a HeaderMap>,
+        body: SignableBody<'a>,
+    ) -> Self {
+        SignableRequest {
+            head,
+            extra_headers,
+            body,
+        }
+    }
+
+    /// Request method
+    pub fn method(&self) -> &Method {
+        &self.head.method
+    }
+
+    /// Request url
+    pub fn uri(&self) -> &Uri {
+        &self.head.uri
+    }
+
+    /// Get first value of the request header
+    pub fn header(&self, name: &HeaderName) -> Option<&HeaderValue> {
+        self.extra_headers
+            .and_then(|headers| headers.get(name))
+            .or_else(|| self.head.headers.get(name))
+    }
+
+    /// Iterate over all request headers
+    pub fn headers(
+        &self,
+    ) -> impl Iterator<Item = (&'a HeaderName, &'a HeaderValue)> + 'a {
+        let head: &'a RequestHead = self.head;
+        let extra = self.extra_headers;
+        head.headers
+            .iter()
+            .filter(move |(name, _)| {
+                !extra.map(|h| h.contains_key(*name)).unwrap_or(false)
+            })
+            .chain(extra.into_iter().flat_map(|h| h.iter()))
+    }
+
+    /// Request body
+    pub fn body(&self) -> SignableBody<'a> {
+        self.body
+    }
+}
+
+/// Reference HMAC-SHA256 request signer.
+///
+/// Signature scheme is modeled after AWS Signature Version 4. Signer adds
+/// `x-date` header with unix timestamp, `x-content-sha256` header with hex
+/// encoded sha256 hash of the body, or `UNSIGNED-PAYLOAD` for streaming
+/// bodies, and `Authorization` header:
+///
+/// ```text
+/// HMAC-SHA256 Credential=<key id>, SignedHeaders=<names>, Signature=<hex>
+/// ```
+///
+/// Signature is computed over canonical request:
+///
+/// ```text
+/// <method>\n<path>\n<query>\n<name:value\n for each signed header>\n<names>\n<body hash>
+/// ```
+///
+/// `host`, `x-content-sha256` and `x-date` headers are always signed,
+/// additional headers could be added with `sign_header()`. String to sign
+/// is `HMAC-SHA256\n<timestamp>\n<hex sha256 of canonical request>`.
+///
+/// ```rust
+/// use actoriwc::{Client, HmacSigner};
+/// use actoriwc::http::header::CONTENT_TYPE;
+///
+/// let client = Client::build()
+///     .signer(HmacSigner::new("key-id", "secret").sign_header(CONTENT_TYPE))
+///     .finish();
+/// ```
+pub struct HmacSigner {
+    key_id: String,
+    secret: Vec<u8>,
+    headers: Vec<HeaderName>,
+}
+
+const X_DATE: &str = "x-date";
+const X_CONTENT_SHA256: &str = "x-content-sha256";
+
+impl HmacSigner {
+    /// Create signer with key id and shared secret.
+    pub fn new<K, S>(key_id: K, secret: S) -> Self
+    where
+        K: Into<String>,
+        S: AsRef<[u8]>,
+    {
+        HmacSigner {
+            key_id: key_id.into(),
+            secret: secret.as_ref().to_vec(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Include request header into signature.
+    pub fn sign_header(mut self, name: HeaderName) -> Self {
+        self.headers.push(name);
+        self
+    }
+
+    fn sign_at(
+        &self,
+        req: &SignableRequest<'_>,
+        timestamp: u64,
+    ) -> Result<HeaderMap, HttpError> {
+        let body_hash = match req.body() {
+            SignableBody::Bytes(body) => hex(&Sha256::digest(body)),
+            SignableBody::Unsigned => "UNSIGNED-PAYLOAD".to_owned(),
+        };
+        let date = timestamp.to_string();
+
+        let mut signed: Vec<(String, Vec<u8>)> = vec![
+            (HOST.as_str().to_owned(), host(req)),
+            (X_CONTENT_SHA256.to_owned(), body_hash.clone().into_bytes()),
+            (X_DATE.to_owned(), date.clone().into_bytes()),
+        ];
+        for name in &self.headers {
+            if signed.iter().any(|(n, _)| n == name.as_str()) {
+                continue;
+            }
+            let mut value = Vec::new();
+            for (n, v) in req.headers() {
+                if n == name {
+                    if !value.is_empty() {
+                        value.push(b',');
+                    }
+                    value.extend_from_slice(trim(v.as_bytes()));
+                }
+            }
+            signed.push((name.as_str().to_owned(), value));
+        }
+        signed.sort_by(|a, b| a.0.cmp(&b.0));
+        let names = signed
+            .iter()
+            .map(|(n, _)| n.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+
+        // canonical request
+        let mut canonical = Vec::new();
+        canonical.extend_from_slice(req.method().as_str().as_bytes());
+        canonical.push(b'\n');
+        canonical.extend_from_slice(req.uri().path().as_bytes());
+        canonical.push(b'\n');
+        canonical.extend_from_slice(req.uri().query().unwrap_or("").as_bytes());
+        canonical.push(b'\n');
+        for (name, value) in &signed {
+            canonical.extend_from_slice(name.as_bytes());
+            canonical.push(b':');
+            canonical.extend_from_slice(value);
+            canonical.push(b'\n');
+        }
+        canonical.push(b'\n');
+        canonical.extend_from_slice(names.as_bytes());
+        canonical.push(b'\n');
+        canonical.extend_from_slice(body_hash.as_bytes());
+
+        let to_sign = format!(
+            "HMAC-SHA256\n{}\n{}",
+            date,
+            hex(&Sha256::digest(&canonical))
+        );
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.secret)
+            .expect("HMAC accepts keys of any size");
+        mac.input(to_sign.as_bytes());
+        let signature = hex(&mac.result().code());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static(X_DATE),
+            HeaderValue::from_str(&date)?,
+        );
+        headers.insert(
+            HeaderName::from_static(X_CONTENT_SHA256),
+            HeaderValue::from_str(&body_hash)?,
+        );
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!(
+                "HMAC-SHA256 Credential={}, SignedHeaders={}, Signature={}",
+                self.key_id, names, signature
+            ))?,
+        );
+        Ok(headers)
+    }
+}
+
+impl RequestSigner for HmacSigner {
+    fn sign(&self, req: &SignableRequest<'_>) -> Result<HeaderMap, HttpError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|dur| dur.as_secs())
+            .unwrap_or(0);
+        self.sign_at(req, timestamp)
+    }
+}
+
+/// Host header value, same as set by client connection if missing
+fn host(req: &SignableRequest<'_>) -> Vec<u8> {
+    if let Some(host) = req.header(&HOST) {
+        return host.as_bytes().to_vec();
+    }
+    match (req.uri().host(), req.uri().port_u16()) {
+        (Some(host), None) | (Some(host), Some(80)) | (Some(host), Some(443)) => {
+            host.as_bytes().to_vec()
+        }
+        (Some(host), Some(port)) => format!("{}:{}", host, port).into_bytes(),
+        (None, _) => Vec::new(),
+    }
+}
+
+fn trim(value: &[u8]) -> &[u8] {
+    let start = value
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or_else(|| value.len());
+    let end = value
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map(|pos| pos + 1)
+        .unwrap_or(start);
+    &value[start..end]
+}
+
+fn hex(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len() * 2);
+    for b in data {
+        let _ = write!(s, "{:02x}", b);
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actori_http::http::header::CONTENT_TYPE;
+
+    #[test]
+    fn test_hmac_signer() {
+        let mut head = RequestHead::default();
+        head.method = Method::POST;
+        head.uri = Uri::from_static("http://example.com:8080/path?b=2&a=1");
+        head.headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static(" text/plain "));
+
+        let signer = HmacSigner::new("key-id", "secret").sign_header(CONTENT_TYPE);
+        let req = SignableRequest::new(&head, None, SignableBody::Bytes(b"hello"));
+        let headers = signer.sign_at(&req, 1_577_836_800).unwrap();
+
+        assert_eq!(headers.get(X_DATE).unwrap(), "1577836800");
+        assert_eq!(
+            headers.get(X_CONTENT_SHA256).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(
+            headers.get(AUTHORIZATION).unwrap(),
+            "HMAC-SHA256 Credential=key-id, \
+             SignedHeaders=content-type;host;x-content-sha256;x-date, \
+             Signature=f05de7083716579b974821f30735a5168663b8e22bbf04cb8163f7b96bc75f6a"
+        );
+
+        // extra headers override head headers, streaming body is not hashed
+        let mut extra = HeaderMap::new();
+        extra.insert(HOST, HeaderValue::from_static("example.org"));
+        let req = SignableRequest::new(&head, Some(&extra), SignableBody::Unsigned);
+        assert_eq!(req.header(&HOST).unwrap(), "example.org");
+        assert_eq!(req.headers().count(), 2);
+        let headers = signer.sign_at(&req, 1_577_836_800).unwrap();
+        assert_eq!(headers.get(X_CONTENT_SHA256).unwrap(), "UNSIGNED-PAYLOAD");
+    }
+}
@@ -39,6 +39,12 @@ impl TestResponse {
         Self::default().header(key, value)
     }
 
+    /// Set status of this response
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.head.status = status;
+        self
+    }
+
     /// Set HTTP version of this response
     pub fn version(mut self, ver: Version) -> Self {
         self.head.version = ver;
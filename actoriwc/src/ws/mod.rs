@@ -1,4 +1,7 @@
 //! Websockets client
+//!
+//! Handshake is always sent as HTTP/1.1 upgrade request, WebSockets over
+//! HTTP/2 extended `CONNECT` are not supported.
 use std::convert::TryFrom;
 use std::fmt::Write as FmtWrite;
 use std::net::SocketAddr;
@@ -14,6 +17,9 @@ use percent_encoding::percent_encode;
 use actori_http::cookie::USERINFO;
 pub use actori_http::ws::{CloseCode, CloseReason, Codec, Frame, Message};
 
+mod reconnect;
+pub use self::reconnect::{ConnectionState, ReconnectingClient};
+
 use crate::connect::BoxedSocket;
 use crate::error::{InvalidUrl, SendRequestError, WsClientError};
 use crate::http::header::{
@@ -0,0 +1,292 @@
+//! Websockets client with automatic reconnection
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use actori_codec::Framed;
+use actori_rt::time::delay_for;
+use futures_util::{SinkExt, StreamExt};
+
+use super::{CloseReason, Codec, Frame, Message, WebsocketsRequest};
+use crate::connect::BoxedSocket;
+use crate::error::WsClientError;
+
+/// State of the `ReconnectingClient` connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Connection attempt, attempts are counted from 1
+    Connecting(usize),
+    /// Handshake is completed and resume messages are sent
+    Connected,
+    /// Connection is lost, client reconnects on next operation
+    Disconnected,
+    /// Connection is closed with `ReconnectingClient::close()` or
+    /// maximum number of attempts is reached
+    Closed,
+}
+
+/// Websockets client that re-establishes connection with exponential
+/// backoff.
+///
+/// Request is created for every connection attempt by the factory function.
+/// Messages returned by the resume hook are sent after each successful
+/// connection, i.e. to restore subscriptions.
+///
+/// ```rust,no_run
+/// use actoriwc::ws::{Frame, Message, ReconnectingClient};
+/// use actoriwc::Client;
+///
+/// #[actori_rt::main]
+/// async fn main() {
+///     let client = Client::new();
+///     let mut ws = ReconnectingClient::new(move || client.ws("ws://localhost/feed"))
+///         .resume(|| vec![Message::Text("subscribe".to_string())])
+///         .on_state(|state| println!("Connection state: {:?}", state));
+///
+///     while let Some(item) = ws.recv().await {
+///         if let Ok(Frame::Text(text)) = item {
+///             println!("Received: {:?}", text);
+///         }
+///     }
+/// }
+/// ```
+pub struct ReconnectingClient {
+    factory: Box<dyn Fn() -> WebsocketsRequest>,
+    resume: Option<Box<dyn Fn() -> Vec<Message>>>,
+    on_state: Option<Box<dyn Fn(ConnectionState)>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_attempts: usize,
+    framed: Option<Framed<BoxedSocket, Codec>>,
+    closed: bool,
+}
+
+impl ReconnectingClient {
+    /// Create client, factory is called for each connection attempt
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn() -> WebsocketsRequest + 'static,
+    {
+        ReconnectingClient {
+            factory: Box::new(factory),
+            resume: None,
+            on_state: None,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            max_attempts: 0,
+            framed: None,
+            closed: false,
+        }
+    }
+
+    /// Set delay before the second attempt, delay doubles with every
+    /// failed attempt.
+    ///
+    /// By default initial delay is 100 milliseconds.
+    pub fn initial_backoff(mut self, delay: Duration) -> Self {
+        self.initial_backoff = delay;
+        self
+    }
+
+    /// Set maximum delay between attempts.
+    ///
+    /// By default maximum delay is 30 seconds.
+    pub fn max_backoff(mut self, delay: Duration) -> Self {
+        self.max_backoff = delay;
+        self
+    }
+
+    /// Set maximum number of consecutive connection attempts, client is
+    /// closed once limit is reached.
+    ///
+    /// To disable limit set value to 0. By default limit is disabled.
+    pub fn max_attempts(mut self, attempts: usize) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Set hook that returns messages to send after every successful
+    /// connection.
+    pub fn resume<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> Vec<Message> + 'static,
+    {
+        self.resume = Some(Box::new(f));
+        self
+    }
+
+    /// Set handler for connection state changes.
+    pub fn on_state<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ConnectionState) + 'static,
+    {
+        self.on_state = Some(Box::new(f));
+        self
+    }
+
+    /// Check if connection is established
+    pub fn is_connected(&self) -> bool {
+        self.framed.is_some()
+    }
+
+    /// Establish connection if it is not established yet.
+    pub async fn connect(&mut self) -> Result<(), WsClientError> {
+        if self.framed.is_some() {
+            return Ok(());
+        }
+        self.closed = false;
+
+        let mut attempt = 0;
+        let mut backoff = self.initial_backoff;
+        loop {
+            attempt += 1;
+            self.set_state(ConnectionState::Connecting(attempt));
+
+            match self.try_connect().await {
+                Ok(framed) => {
+                    self.framed = Some(framed);
+                    self.set_state(ConnectionState::Connected);
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::debug!(
+                        "Websocket connection attempt {} failed: {}",
+                        attempt,
+                        e
+                    );
+                    if self.max_attempts != 0 && attempt >= self.max_attempts {
+                        self.closed = true;
+                        self.set_state(ConnectionState::Closed);
+                        return Err(e);
+                    }
+                }
+            }
+
+            delay_for(backoff).await;
+            backoff = std::cmp::min(backoff * 2, self.max_backoff);
+        }
+    }
+
+    async fn try_connect(&self) -> Result<Framed<BoxedSocket, Codec>, WsClientError> {
+        let (_, mut framed) = (self.factory)().connect().await?;
+        if let Some(ref resume) = self.resume {
+            for msg in resume() {
+                framed.send(msg).await?;
+            }
+        }
+        Ok(framed)
+    }
+
+    /// Send message, connection is re-established if it is lost.
+    ///
+    /// Message is sent once, it is not re-sent after reconnection if
+    /// sending fails.
+    pub async fn send(&mut self, msg: Message) -> Result<(), WsClientError> {
+        self.connect().await?;
+
+        let res = self.framed.as_mut().unwrap().send(msg).await;
+        if let Err(e) = res {
+            self.disconnected();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Receive next frame, connection is re-established if it is lost.
+    ///
+    /// `Close` frame of the peer is returned to the caller, next call
+    /// reconnects. Returns `None` if client is closed.
+    pub async fn recv(&mut self) -> Option<Result<Frame, WsClientError>> {
+        loop {
+            if self.closed {
+                return None;
+            }
+            if let Err(e) = self.connect().await {
+                return Some(Err(e));
+            }
+
+            match self.framed.as_mut().unwrap().next().await {
+                Some(Ok(Frame::Close(reason))) => {
+                    self.disconnected();
+                    return Some(Ok(Frame::Close(reason)));
+                }
+                Some(Ok(frame)) => return Some(Ok(frame)),
+                Some(Err(e)) => {
+                    log::debug!("Websocket connection error: {}", e);
+                    self.disconnected();
+                }
+                None => self.disconnected(),
+            }
+        }
+    }
+
+    /// Send `Close` message and drop connection, client does not
+    /// reconnect until `connect()` is called.
+    pub async fn close(
+        &mut self,
+        reason: Option<CloseReason>,
+    ) -> Result<(), WsClientError> {
+        self.closed = true;
+        let res = if let Some(mut framed) = self.framed.take() {
+            framed.send(Message::Close(reason)).await
+        } else {
+            Ok(())
+        };
+        self.set_state(ConnectionState::Closed);
+        res.map_err(WsClientError::from)
+    }
+
+    fn disconnected(&mut self) {
+        self.framed = None;
+        self.set_state(ConnectionState::Disconnected);
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        if let Some(ref on_state) = self.on_state {
+            (on_state)(state);
+        }
+    }
+}
+
+impl fmt::Debug for ReconnectingClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingClient")
+            .field("connected", &self.framed.is_some())
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("max_attempts", &self.max_attempts)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::Client;
+
+    #[actori_rt::test]
+    async fn test_max_attempts() {
+        let states = Rc::new(RefCell::new(Vec::new()));
+        let states2 = states.clone();
+
+        let client = Client::new();
+        let mut ws = ReconnectingClient::new(move || client.ws("hmm://test.com/"))
+            .initial_backoff(Duration::from_millis(1))
+            .max_attempts(2)
+            .on_state(move |state| states2.borrow_mut().push(state));
+
+        assert!(ws.connect().await.is_err());
+        assert!(!ws.is_connected());
+        assert!(ws.recv().await.is_none());
+        assert_eq!(
+            *states.borrow(),
+            vec![
+                ConnectionState::Connecting(1),
+                ConnectionState::Connecting(2),
+                ConnectionState::Closed
+            ]
+        );
+    }
+}
@@ -1,27 +1,33 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 
 use brotli2::write::BrotliEncoder;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use futures::future::ok;
+use futures::future::{err, ok, Ready};
+use futures::StreamExt;
 use rand::Rng;
 
-use actori_http::HttpService;
+use actori_http::{HttpService, Request, Response};
 use actori_http_test::test_server;
-use actori_service::{map_config, pipeline_factory};
+use actori_service::{fn_service, map_config, pipeline_factory, Service, Transform};
 use actori_web::dev::{AppConfig, BodyEncoding};
 use actori_web::http::Cookie;
 use actori_web::middleware::Compress;
 use actori_web::{
     http::header, test, web, App, Error, HttpMessage, HttpRequest, HttpResponse,
 };
-use actoriwc::error::SendRequestError;
+use actoriwc::error::{ConnectError, SendRequestError};
+use actoriwc::middleware::ConnectRequest;
+use actoriwc::ClientResponse;
 
 const STR: &str = "Hello World Hello World Hello World Hello World Hello World \
                    Hello World Hello World Hello World Hello World Hello World \
@@ -52,7 +58,7 @@ async fn test_simple() {
             .service(web::resource("/").route(web::to(|| HttpResponse::Ok().body(STR))))
     });
 
-    let request = srv.get("/").header("x-test", "111").send();
+    let request = srv.get("/").insert_header(("x-test", "111")).send();
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
 
@@ -82,7 +88,7 @@ async fn test_json() {
 
     let request = srv
         .get("/")
-        .header("x-test", "111")
+        .insert_header(("x-test", "111"))
         .send_json(&"TEST".to_string());
     let response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -99,7 +105,7 @@ async fn test_form() {
     let mut data = HashMap::new();
     let _ = data.insert("key".to_string(), "TEST".to_string());
 
-    let request = srv.get("/").header("x-test", "111").send_form(&data);
+    let request = srv.get("/").insert_header(("x-test", "111")).send_form(&data);
     let response = request.await.unwrap();
     assert!(response.status().is_success());
 }
@@ -366,6 +372,264 @@ async fn test_connection_wait_queue_force_close() {
     assert_eq!(num.load(Ordering::Relaxed), 2);
 }
 
+#[actori_rt::test]
+async fn test_connection_wait_queue_limits() {
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").to(|| HttpResponse::Ok().body(STR)))
+    });
+
+    let connector = actoriwc::Connector::new()
+        .limit(1)
+        .max_waiters(1)
+        .acquire_timeout(Duration::from_millis(200));
+    let metrics = connector.pool_metrics();
+    let client = actoriwc::Client::build()
+        .connector(connector.finish())
+        .finish();
+
+    // req 1 holds the only connection until body is read
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+
+    // req 2 waits for a connection, req 3 does not fit into waiters queue
+    let req2 = client.get(srv.url("/")).send();
+    let req3 = client.get(srv.url("/")).send();
+    let (res2, res3) = futures::future::join(req2, req3).await;
+    match res2 {
+        Err(SendRequestError::Connect(ConnectError::PoolTimeout)) => (),
+        _ => panic!("expected pool timeout"),
+    }
+    match res3 {
+        Err(SendRequestError::Connect(ConnectError::PoolOverloaded)) => (),
+        _ => panic!("expected pool overload"),
+    }
+    assert_eq!(metrics.waiting(), 0);
+    assert_eq!(metrics.waits(), 1);
+    assert_eq!(metrics.timeouts(), 1);
+    assert_eq!(metrics.rejected(), 1);
+    assert!(metrics.max_wait_time() >= Duration::from_millis(200));
+
+    // connection is released, next request does not wait
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+    let response = client.get(srv.url("/")).send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(metrics.waits(), 1);
+}
+
+#[actori_rt::test]
+async fn test_request_signer() {
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").to(|req: HttpRequest| {
+            let auth = req.headers().get(header::AUTHORIZATION).unwrap();
+            assert!(auth
+                .to_str()
+                .unwrap()
+                .starts_with("HMAC-SHA256 Credential=key-id, SignedHeaders="));
+            assert!(req.headers().contains_key("x-date"));
+            HttpResponse::Ok().body(
+                req.headers()
+                    .get("x-content-sha256")
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_owned(),
+            )
+        }))
+    });
+
+    let client = actoriwc::Client::build()
+        .signer(actoriwc::HmacSigner::new("key-id", "secret"))
+        .finish();
+
+    let mut res = client.post(srv.url("/")).send_body("hello").await.unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(
+        bytes,
+        Bytes::from_static(
+            b"2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        )
+    );
+
+    // frozen requests are signed on every send
+    let req = client.get(srv.url("/")).freeze().unwrap();
+    for _ in 0..2 {
+        let mut res = req.send().await.unwrap();
+        assert!(res.status().is_success());
+        let bytes = res.body().await.unwrap();
+        assert_eq!(
+            bytes,
+            Bytes::from_static(
+                b"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            )
+        );
+    }
+}
+
+/// Client middleware that adds `x-middleware` header
+struct AddHeader(&'static str);
+
+impl<S> Transform<S> for AddHeader
+where
+    S: Service<
+        Request = ConnectRequest,
+        Response = ClientResponse,
+        Error = SendRequestError,
+    >,
+{
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type InitError = ();
+    type Transform = AddHeaderService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AddHeaderService(service, self.0))
+    }
+}
+
+struct AddHeaderService<S>(S, &'static str);
+
+impl<S> Service for AddHeaderService<S>
+where
+    S: Service<
+        Request = ConnectRequest,
+        Response = ClientResponse,
+        Error = SendRequestError,
+    >,
+{
+    type Request = ConnectRequest;
+    type Response = ClientResponse;
+    type Error = SendRequestError;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ConnectRequest) -> Self::Future {
+        req.headers_mut()
+            .append("x-middleware".parse().unwrap(), self.1.parse().unwrap());
+        self.0.call(req)
+    }
+}
+
+#[actori_rt::test]
+async fn test_client_middleware() {
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").to(|req: HttpRequest| {
+            let values: Vec<_> = req
+                .headers()
+                .get_all("x-middleware")
+                .map(|v| v.to_str().unwrap().to_owned())
+                .collect();
+            HttpResponse::Ok().body(values.join(","))
+        }))
+    });
+
+    let client = actoriwc::Client::build()
+        .wrap(AddHeader("first"))
+        .wrap(AddHeader("second"))
+        .finish();
+
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"second,first"));
+
+    // frozen requests pass through middleware as well
+    let req = client.get(srv.url("/")).freeze().unwrap();
+    let mut res = req.send().await.unwrap();
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"second,first"));
+}
+
+#[actori_rt::test]
+async fn test_client_retry() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+    let srv = test::start(move || {
+        let num = num2.clone();
+        App::new().service(web::resource("/").to(move || {
+            if num.fetch_add(1, Ordering::Relaxed) < 2 {
+                HttpResponse::ServiceUnavailable()
+            } else {
+                HttpResponse::Ok()
+            }
+        }))
+    });
+
+    let client = actoriwc::Client::build()
+        .retry(
+            actoriwc::RetryPolicy::new()
+                .max_attempts(3)
+                .base_delay(Duration::from_millis(10)),
+        )
+        .finish();
+
+    let res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(num.load(Ordering::Relaxed), 3);
+
+    // non idempotent requests are not retried
+    num.store(0, Ordering::Relaxed);
+    let res = client.post(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.status(), 503);
+    assert_eq!(num.load(Ordering::Relaxed), 1);
+}
+
+#[actori_rt::test]
+async fn test_client_cache() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+    let srv = test::start(move || {
+        let num = num2.clone();
+        App::new()
+            .service(web::resource("/fresh").to(|| {
+                HttpResponse::Ok()
+                    .insert_header((header::CACHE_CONTROL, "max-age=60"))
+                    .body("fresh")
+            }))
+            .service(web::resource("/etag").to(move |req: HttpRequest| {
+                num.fetch_add(1, Ordering::Relaxed);
+                if req.headers().get(header::IF_NONE_MATCH)
+                    == Some(&header::HeaderValue::from_static("\"1\""))
+                {
+                    HttpResponse::NotModified().finish()
+                } else {
+                    HttpResponse::Ok()
+                        .insert_header((header::CACHE_CONTROL, "no-cache"))
+                        .insert_header((header::ETAG, "\"1\""))
+                        .body("etag")
+                }
+            }))
+    });
+
+    let store = Rc::new(actoriwc::cache::MemoryCache::new(16));
+    let client = actoriwc::Client::build().cache(store.clone()).finish();
+
+    let mut res = client.get(srv.url("/fresh")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"fresh"));
+    assert_eq!(store.len(), 1);
+    let mut res = client.get(srv.url("/fresh")).send().await.unwrap();
+    assert!(res.headers().contains_key(header::AGE));
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"fresh"));
+
+    // stale response is revalidated
+    for _ in 0..2 {
+        let mut res = client.get(srv.url("/etag")).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"etag"));
+    }
+    assert_eq!(num.load(Ordering::Relaxed), 2);
+
+    // unsafe method invalidates cached response
+    let _ = client.post(srv.url("/etag")).send().await.unwrap();
+    assert_eq!(store.len(), 1);
+}
+
 #[actori_rt::test]
 async fn test_with_query_parameter() {
     let srv = test::start(|| {
@@ -386,6 +650,81 @@ async fn test_with_query_parameter() {
     assert!(res.status().is_success());
 }
 
+#[actori_rt::test]
+async fn test_expect_continue() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .expect(fn_service(|req: Request| {
+                if req.head().uri.query() == Some("yes=") {
+                    ok(req)
+                } else {
+                    err(actori_http::error::ErrorPreconditionFailed("error"))
+                }
+            }))
+            .finish(|mut req: Request| {
+                async move {
+                    let mut body = BytesMut::new();
+                    let mut pl = req.take_payload();
+                    while let Some(chunk) = pl.next().await {
+                        body.extend_from_slice(&chunk?);
+                    }
+                    Ok::<_, Error>(Response::Ok().body(body.freeze()))
+                }
+            })
+            .tcp()
+    });
+
+    let client = actoriwc::Client::new();
+
+    let mut res = client
+        .post(srv.url("/?yes="))
+        .expect_continue()
+        .send_body(STR)
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+
+    // body is not sent
+    let res = client
+        .post(srv.url("/"))
+        .expect_continue()
+        .send_body(STR)
+        .await
+        .unwrap();
+    assert_eq!(
+        res.status(),
+        actori_web::http::StatusCode::PRECONDITION_FAILED
+    );
+}
+
+#[actori_rt::test]
+async fn test_informational_response() {
+    let lst = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = lst.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = lst.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf);
+        let _ = stream.write_all(
+            b"HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload\r\n\r\n\
+              HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+        );
+    });
+
+    let hints = Rc::new(RefCell::new(Vec::new()));
+    let hints2 = hints.clone();
+    let res = actoriwc::Client::new()
+        .get(format!("http://{}/", addr))
+        .on_informational(move |head| hints2.borrow_mut().push(head.status.as_u16()))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(*hints.borrow(), vec![103]);
+}
+
 #[actori_rt::test]
 async fn test_no_decompress() {
     let srv = test::start(|| {
@@ -439,7 +778,7 @@ async fn test_client_gzip_encoding() {
             let data = e.finish().unwrap();
 
             HttpResponse::Ok()
-                .header("content-encoding", "gzip")
+                .insert_header(("content-encoding", "gzip"))
                 .body(data)
         })))
     });
@@ -462,7 +801,7 @@ async fn test_client_gzip_encoding_large() {
             let data = e.finish().unwrap();
 
             HttpResponse::Ok()
-                .header("content-encoding", "gzip")
+                .insert_header(("content-encoding", "gzip"))
                 .body(data)
         })))
     });
@@ -489,7 +828,7 @@ async fn test_client_gzip_encoding_large_random() {
             e.write_all(&data).unwrap();
             let data = e.finish().unwrap();
             HttpResponse::Ok()
-                .header("content-encoding", "gzip")
+                .insert_header(("content-encoding", "gzip"))
                 .body(data)
         })))
     });
@@ -511,7 +850,7 @@ async fn test_client_brotli_encoding() {
             e.write_all(&data).unwrap();
             let data = e.finish().unwrap();
             HttpResponse::Ok()
-                .header("content-encoding", "br")
+                .insert_header(("content-encoding", "br"))
                 .body(data)
         })))
     });
@@ -538,7 +877,7 @@ async fn test_client_brotli_encoding_large_random() {
             e.write_all(&data).unwrap();
             let data = e.finish().unwrap();
             HttpResponse::Ok()
-                .header("content-encoding", "br")
+                .insert_header(("content-encoding", "br"))
                 .body(data)
         })))
     });
@@ -730,6 +1069,55 @@ async fn test_client_cookie_handling() {
     assert_eq!(c2, cookie2);
 }
 
+#[actori_rt::test]
+async fn test_client_trace_context() {
+    use actori_http::trace::TraceContext;
+
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").route(web::to(|req: HttpRequest| {
+            let ctx = TraceContext::from_headers(req.headers()).unwrap();
+            let b3 = TraceContext::from_headers(&{
+                let mut headers = req.headers().clone();
+                headers.remove("traceparent");
+                headers
+            })
+            .unwrap();
+            assert_eq!(ctx, b3);
+            HttpResponse::Ok().body(ctx.traceparent())
+        })))
+    });
+
+    let ctx = TraceContext::new_root();
+    TraceContext::set_current(Some(ctx));
+    let mut response = srv.get("/").send().await.unwrap();
+    TraceContext::set_current(None);
+    assert!(response.status().is_success());
+
+    let bytes = response.body().await.unwrap();
+    let headers = {
+        let mut headers = actori_http::http::HeaderMap::new();
+        headers.insert(
+            actori_http::http::header::HeaderName::from_static("traceparent"),
+            actori_http::http::HeaderValue::from_bytes(&bytes).unwrap(),
+        );
+        headers
+    };
+    let sent = TraceContext::from_headers(&headers).unwrap();
+    assert_eq!(sent.trace_id(), ctx.trace_id());
+    assert_ne!(sent.span_id(), ctx.span_id());
+
+    // explicit headers are not replaced
+    let mut response = srv
+        .get("/")
+        .insert_header(("traceparent", ctx.traceparent()))
+        .insert_header(("b3", ctx.b3()))
+        .send()
+        .await
+        .unwrap();
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from(ctx.traceparent()));
+}
+
 // #[actori_rt::test]
 // fn client_read_until_eof() {
 //     let addr = test::TestServer::unused_addr();
@@ -1,4 +1,6 @@
+use std::cell::RefCell;
 use std::io;
+use std::rc::Rc;
 
 use actori_codec::Framed;
 use actori_http::{body::BodySize, h1, ws, Error, HttpService, Request, Response};
@@ -68,3 +70,61 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[actori_rt::test]
+async fn test_reconnecting_client() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let res = ws::handshake_response(req.head()).finish();
+                    framed
+                        .send(h1::Message::Item((res.drop_body(), BodySize::None)))
+                        .await?;
+
+                    let framed = framed.into_framed(ws::Codec::new());
+                    ws::Dispatcher::with(framed, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    });
+
+    let states = Rc::new(RefCell::new(Vec::new()));
+    let states2 = states.clone();
+    let client = actoriwc::Client::new();
+    let url = srv.url("/");
+    let mut ws = actoriwc::ws::ReconnectingClient::new(move || client.ws(url.as_str()))
+        .resume(|| vec![ws::Message::Text("subscribe".to_string())])
+        .on_state(move |state| states2.borrow_mut().push(state));
+
+    // resume messages are sent after connect
+    let item = ws.recv().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"subscribe")));
+
+    // server closes connection
+    ws.send(ws::Message::Close(None)).await.unwrap();
+    let item = ws.recv().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(None));
+    assert!(!ws.is_connected());
+
+    // reconnect and resume
+    let item = ws.recv().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Text(Bytes::from_static(b"subscribe")));
+
+    ws.close(None).await.unwrap();
+    assert!(ws.recv().await.is_none());
+
+    use actoriwc::ws::ConnectionState::*;
+    assert_eq!(
+        *states.borrow(),
+        vec![
+            Connecting(1),
+            Connected,
+            Disconnected,
+            Connecting(1),
+            Connected,
+            Closed
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+//! Extractor dispatch benchmarks
+//!
+//! Run with `cargo bench --features bench --bench handler`
+use actori_service::Service;
+use actori_web::test::TestRequest;
+use actori_web::{bench, web, HttpRequest, HttpResponse};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Message {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Info {
+    name: String,
+    id: u32,
+}
+
+async fn plaintext() -> HttpResponse {
+    HttpResponse::Ok().body("Hello, World!")
+}
+
+async fn request(req: HttpRequest) -> HttpResponse {
+    HttpResponse::Ok().body(req.path().to_owned())
+}
+
+async fn path(info: web::Path<Info>) -> HttpResponse {
+    HttpResponse::Ok().body(format!("{} {}", info.name, info.id))
+}
+
+async fn query(info: web::Query<Info>) -> HttpResponse {
+    HttpResponse::Ok().body(format!("{} {}", info.name, info.id))
+}
+
+async fn json(msg: web::Json<Message>) -> HttpResponse {
+    HttpResponse::Ok().json(msg.into_inner())
+}
+
+fn bench_handler(c: &mut Criterion) {
+    let mut rt = actori_rt::System::new("handler");
+    let mut group = c.benchmark_group("handler");
+
+    macro_rules! bench {
+        ($name:expr, $handler:expr, $req:expr) => {
+            let mut srv = rt.block_on(bench::handler($handler));
+            group.bench_function($name, |b| {
+                b.iter_batched(
+                    || $req.to_srv_request(),
+                    |req| rt.block_on(srv.call(req)).ok().unwrap(),
+                    BatchSize::SmallInput,
+                )
+            });
+        };
+    }
+
+    bench!("no_args", plaintext, TestRequest::default());
+    bench!("request", request, TestRequest::with_uri("/index.html"));
+    bench!(
+        "path",
+        path,
+        TestRequest::default()
+            .param("name", "user")
+            .param("id", "42")
+    );
+    bench!("query", query, TestRequest::with_uri("/?name=user&id=42"));
+    bench!(
+        "json",
+        json,
+        TestRequest::post().set_json(&Message {
+            message: "Hello, World!".to_owned(),
+        })
+    );
+
+    group.finish();
+}
+
+criterion_group!(handler, bench_handler);
+criterion_main!(handler);
@@ -0,0 +1,51 @@
+//! Router matching benchmarks
+//!
+//! Run with `cargo bench --features bench --bench router`
+use actori_web::bench::Router;
+use actori_web::guard;
+use actori_web::test::TestRequest;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const RESOURCES: &[&str] = &[
+    "/",
+    "/plaintext",
+    "/json",
+    "/users",
+    "/users/{id}",
+    "/users/{id}/posts",
+    "/users/{id}/posts/{post}",
+    "/users/{id}/followers",
+    "/repos/{owner}/{repo}",
+    "/repos/{owner}/{repo}/issues",
+    "/repos/{owner}/{repo}/issues/{number}",
+    "/repos/{owner}/{repo}/pulls/{number:\\d+}",
+    "/static/{tail}*",
+];
+
+fn bench_router(c: &mut Criterion) {
+    let mut group = c.benchmark_group("router");
+    let mut router = Router::new(RESOURCES);
+
+    for (name, path) in &[
+        ("static", "/plaintext"),
+        ("dynamic", "/users/42/posts/17"),
+        ("regex", "/repos/actori/actori-web/pulls/1234"),
+        ("tail", "/static/css/main.css"),
+        ("not_found", "/unknown/path"),
+    ] {
+        let mut req = TestRequest::with_uri(path).to_srv_request();
+        group.bench_function(*name, |b| b.iter(|| router.recognize(&mut req)));
+    }
+
+    let mut router = Router::with_guards(RESOURCES.iter().map(|path| {
+        let guards: Vec<Box<dyn guard::Guard>> = vec![Box::new(guard::Get())];
+        (path, guards)
+    }));
+    let mut req = TestRequest::with_uri("/users/42/posts/17").to_srv_request();
+    group.bench_function("guards", |b| b.iter(|| router.recognize(&mut req)));
+
+    group.finish();
+}
+
+criterion_group!(router, bench_router);
+criterion_main!(router);
@@ -0,0 +1,62 @@
+//! Plaintext and json benchmark application.
+//!
+//! Responses follow TechEmpower "plaintext" and "json" test rules. Server
+//! runs without logging and middlewares, one worker per cpu core.
+//!
+//! Build in release mode and use a load generator that keeps connections
+//! open, e.g. [wrk](https://github.com/wg/wrk):
+//!
+//! ```sh
+//! cargo run --release --example benchmark
+//!
+//! wrk -t4 -c256 -d15s http://127.0.0.1:8080/plaintext
+//! wrk -t4 -c256 -d15s http://127.0.0.1:8080/json
+//! ```
+//!
+//! Connections are persistent by default. Pass `--close` to disable keep-alive,
+//! in that case connection is closed after each response and benchmark measures
+//! connection setup cost as well.
+use actori_web::http::header::{CONTENT_TYPE, SERVER};
+use actori_web::{web, App, HttpResponse, HttpServer};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Message {
+    message: &'static str,
+}
+
+async fn plaintext() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((SERVER, "actori"))
+        .insert_header((CONTENT_TYPE, "text/plain"))
+        .body("Hello, World!")
+}
+
+async fn json() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((SERVER, "actori"))
+        .json(Message {
+            message: "Hello, World!",
+        })
+}
+
+#[actori_rt::main]
+async fn main() -> std::io::Result<()> {
+    let keep_alive = !std::env::args().any(|arg| arg == "--close");
+
+    println!(
+        "Started benchmark server at 127.0.0.1:8080, keep-alive: {}",
+        keep_alive
+    );
+
+    HttpServer::new(|| {
+        App::new()
+            .service(web::resource("/plaintext").to(plaintext))
+            .service(web::resource("/json").to(json))
+    })
+    .keep_alive(if keep_alive { Some(75) } else { None })
+    .backlog(1024)
+    .bind("127.0.0.1:8080")?
+    .run()
+    .await
+}
@@ -10,7 +10,7 @@ async fn main() -> Result<(), Error> {
     // Create request builder, configure request and send
     let mut response = client
         .get("https://www.rust-lang.org/")
-        .header("User-Agent", "Actori-web")
+        .insert_header(("User-Agent", "Actori-web"))
         .send()
         .await?;
 
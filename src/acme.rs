@@ -0,0 +1,798 @@
+//! Automatic certificate provisioning with ACME protocol (RFC 8555).
+//!
+//! Certificates are issued by an ACME server, i.e. Let's Encrypt, with
+//! `http-01` challenge and renewed before expiration. Issued certificates are
+//! stored in the cache directory and are replaced in the running server
+//! without restart.
+//!
+//! ```rust,no_run
+//! use actori_web::{web, App, HttpResponse, HttpServer};
+//!
+//! #[actori_rt::main]
+//! async fn main() -> std::io::Result<()> {
+//!     HttpServer::new(|| App::new().route("/", web::get().to(|| HttpResponse::Ok())))
+//!         .bind_acme(&["example.com", "www.example.com"], "/var/cache/acme")?
+//!         .run()
+//!         .await
+//! }
+//! ```
+//!
+//! This module is available with `acme` feature.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use std::{fs, io, net, thread};
+
+use actori_http::http::header::{self, HeaderValue};
+use actori_http::http::StatusCode;
+use actori_http::{Error, Request, Response, TlsReload};
+use actori_rt::time::delay_for;
+use actori_service::{fn_service, ServiceFactory};
+use bytes::Bytes;
+use derive_more::{Display, From};
+use futures::future::ok;
+use open_ssl::asn1::Asn1Time;
+use open_ssl::bn::{BigNum, BigNumContext, MsbOption};
+use open_ssl::ec::{EcGroup, EcKey};
+use open_ssl::ecdsa::EcdsaSig;
+use open_ssl::error::ErrorStack;
+use open_ssl::hash::MessageDigest;
+use open_ssl::nid::Nid;
+use open_ssl::pkey::{PKey, Private};
+use open_ssl::sha::sha256;
+use open_ssl::sign::Signer;
+use open_ssl::ssl::{SslAcceptor, SslMethod};
+use open_ssl::stack::Stack;
+use open_ssl::x509::extension::SubjectAlternativeName;
+use open_ssl::x509::{X509Name, X509Req, X509};
+use serde_json::{json, Value};
+
+/// Let's Encrypt production directory
+pub const LETS_ENCRYPT_PRODUCTION: &str =
+    "https://acme-v02.api.letsencrypt.org/directory";
+
+/// Let's Encrypt staging directory, certificates are not trusted
+pub const LETS_ENCRYPT_STAGING: &str =
+    "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+const CHALLENGE_PATH: &str = "/.well-known/acme-challenge/";
+
+/// Delay between checks of certificate expiration
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Delay before next attempt if provisioning fails
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Errors of certificate provisioning
+#[derive(Debug, Display, From)]
+pub enum AcmeError {
+    /// Io error, i.e. cache directory is not writable
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
+    /// Openssl error
+    #[display(fmt = "{}", _0)]
+    Ssl(ErrorStack),
+    /// Request to ACME server failed
+    #[display(fmt = "Request to ACME server failed: {}", _0)]
+    #[from(ignore)]
+    Request(String),
+    /// ACME server responded with an error or unexpected response
+    #[display(fmt = "ACME server error: {}", _0)]
+    #[from(ignore)]
+    Server(String),
+}
+
+impl std::error::Error for AcmeError {}
+
+/// ACME provisioning configuration.
+///
+/// `HttpServer::bind_acme()` uses default configuration.
+#[derive(Clone, Debug)]
+pub struct AcmeConfig {
+    pub(crate) domains: Vec<String>,
+    pub(crate) cache_dir: PathBuf,
+    pub(crate) directory: String,
+    pub(crate) contact: Vec<String>,
+    pub(crate) renew_before: u32,
+    pub(crate) http_addr: net::SocketAddr,
+    pub(crate) https_addr: net::SocketAddr,
+}
+
+impl AcmeConfig {
+    /// Create configuration for the domains, certificates and account key
+    /// are stored in the cache directory.
+    pub fn new<P: AsRef<Path>>(domains: &[&str], cache_dir: P) -> Self {
+        AcmeConfig {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            cache_dir: cache_dir.as_ref().to_path_buf(),
+            directory: LETS_ENCRYPT_PRODUCTION.to_string(),
+            contact: Vec::new(),
+            renew_before: 30,
+            http_addr: "0.0.0.0:80".parse().unwrap(),
+            https_addr: "0.0.0.0:443".parse().unwrap(),
+        }
+    }
+
+    /// Set ACME server directory url.
+    ///
+    /// By default Let's Encrypt production directory is used.
+    pub fn directory(mut self, url: &str) -> Self {
+        self.directory = url.to_string();
+        self
+    }
+
+    /// Add account contact email.
+    pub fn contact(mut self, email: &str) -> Self {
+        self.contact.push(format!("mailto:{}", email));
+        self
+    }
+
+    /// Set number of days before expiration when certificate is renewed.
+    ///
+    /// By default certificate is renewed 30 days before expiration.
+    pub fn renew_before(mut self, days: u32) -> Self {
+        self.renew_before = days;
+        self
+    }
+
+    /// Set address of the listener that serves `http-01` challenges,
+    /// other requests are redirected to https.
+    ///
+    /// By default `0.0.0.0:80` is used, ACME server always validates
+    /// challenges on port 80.
+    pub fn http_addr(mut self, addr: net::SocketAddr) -> Self {
+        self.http_addr = addr;
+        self
+    }
+
+    /// Set address of the tls listener.
+    ///
+    /// By default `0.0.0.0:443` is used.
+    pub fn https_addr(mut self, addr: net::SocketAddr) -> Self {
+        self.https_addr = addr;
+        self
+    }
+
+    fn cert_path(&self) -> PathBuf {
+        self.cache_dir.join("cert.pem")
+    }
+
+    fn key_path(&self) -> PathBuf {
+        self.cache_dir.join("key.pem")
+    }
+
+    fn account_path(&self) -> PathBuf {
+        self.cache_dir.join("account.pem")
+    }
+}
+
+/// Pending `http-01` challenges, token to key authorization
+#[derive(Clone, Default)]
+pub(crate) struct Challenges(Arc<RwLock<HashMap<String, String>>>);
+
+impl Challenges {
+    fn insert(&self, token: String, key_authorization: String) {
+        self.0.write().unwrap().insert(token, key_authorization);
+    }
+
+    fn remove(&self, token: &str) {
+        self.0.write().unwrap().remove(token);
+    }
+
+    fn get(&self, token: &str) -> Option<String> {
+        self.0.read().unwrap().get(token).cloned()
+    }
+}
+
+/// Service of the http listener, responds to `http-01` challenges and
+/// redirects other requests to https
+pub(crate) fn challenge_service(
+    challenges: Challenges,
+) -> impl ServiceFactory<
+    Config = (),
+    Request = Request,
+    Response = Response,
+    Error = Error,
+    InitError = (),
+> {
+    fn_service(move |req: Request| {
+        let path = req.path();
+        let res = if path.starts_with(CHALLENGE_PATH) {
+            match challenges.get(&path[CHALLENGE_PATH.len()..]) {
+                Some(key_authorization) => Response::Ok()
+                    .content_type("application/octet-stream")
+                    .body(key_authorization),
+                None => Response::NotFound().finish(),
+            }
+        } else {
+            let host = req
+                .headers()
+                .get(header::HOST)
+                .and_then(|h| h.to_str().ok())
+                .map(|h| h.split(':').next().unwrap_or(h));
+            match host {
+                Some(host) => Response::MovedPermanently()
+                    .insert_header((
+                        header::LOCATION,
+                        format!("https://{}{}", host, req.uri()),
+                    ))
+                    .finish(),
+                None => Response::BadRequest().finish(),
+            }
+        };
+        ok::<_, Error>(res)
+    })
+}
+
+/// Acceptor for the cached certificate, or temporary self-signed
+/// certificate until certificate is issued
+pub(crate) fn initial_acceptor(config: &AcmeConfig) -> io::Result<SslAcceptor> {
+    if let Some((cert, key)) = load_cached(config) {
+        if let Ok(acceptor) = acceptor(&cert, &key) {
+            return Ok(acceptor);
+        }
+    }
+    self_signed(&config.domains)
+        .map_err(AcmeError::from)
+        .and_then(|(cert, key)| acceptor(&cert, &key))
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+}
+
+/// Start provisioning thread, acceptor is replaced once certificate
+/// is issued or renewed
+pub(crate) fn spawn(
+    config: AcmeConfig,
+    challenges: Challenges,
+    reload: TlsReload<SslAcceptor>,
+) {
+    let _ = thread::Builder::new()
+        .name("actori-web-acme".to_string())
+        .spawn(move || {
+            let mut sys = actori_rt::System::new("actori-web-acme");
+            sys.block_on(async move {
+                loop {
+                    let delay = match provision(&config, &challenges, &reload).await {
+                        Ok(()) => CHECK_INTERVAL,
+                        Err(e) => {
+                            log::error!("Certificate provisioning failed: {}", e);
+                            RETRY_INTERVAL
+                        }
+                    };
+                    delay_for(delay).await;
+                }
+            })
+        });
+}
+
+/// Issue certificate if it is missing or expires soon
+async fn provision(
+    config: &AcmeConfig,
+    challenges: &Challenges,
+    reload: &TlsReload<SslAcceptor>,
+) -> Result<(), AcmeError> {
+    if let Some((cert, _)) = load_cached(config) {
+        let renew_at = Asn1Time::days_from_now(config.renew_before)?;
+        let cert = X509::from_pem(cert.as_bytes())?;
+        if cert.not_after() > renew_at {
+            return Ok(());
+        }
+    }
+
+    log::info!("Requesting certificate for {:?}", config.domains);
+    create_cache_dir(&config.cache_dir)?;
+    let mut client = AcmeClient::new(config).await?;
+    let (cert, key) = client.order(&config.domains, challenges).await?;
+
+    // check certificate before it is stored
+    let acceptor = acceptor(&cert, &key)?;
+    write_private(&config.key_path(), key.as_bytes())?;
+    fs::write(config.cert_path(), &cert)?;
+    reload.set(acceptor);
+    log::info!("Certificate for {:?} is installed", config.domains);
+    Ok(())
+}
+
+/// Create cache directory, accessible by the owner only
+fn create_cache_dir(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+/// Write private key, file is readable by the owner only
+fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(data)
+}
+
+fn load_cached(config: &AcmeConfig) -> Option<(String, String)> {
+    let cert = fs::read_to_string(config.cert_path()).ok()?;
+    let key = fs::read_to_string(config.key_path()).ok()?;
+    Some((cert, key))
+}
+
+/// Build acceptor from pem encoded certificate chain and private key
+fn acceptor(cert: &str, key: &str) -> Result<SslAcceptor, AcmeError> {
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+    builder.set_private_key(&PKey::private_key_from_pem(key.as_bytes())?)?;
+
+    let mut chain = X509::stack_from_pem(cert.as_bytes())?.into_iter();
+    if let Some(leaf) = chain.next() {
+        builder.set_certificate(&leaf)?;
+    }
+    for cert in chain {
+        builder.add_extra_chain_cert(cert)?;
+    }
+    builder.check_private_key()?;
+
+    Ok(crate::server::openssl_acceptor(builder)?)
+}
+
+fn generate_key() -> Result<PKey<Private>, ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    PKey::from_ec_key(EcKey::generate(&group)?)
+}
+
+/// Subject name with the first domain as common name
+fn subject(domains: &[String]) -> Result<X509Name, ErrorStack> {
+    let mut name = X509Name::builder()?;
+    name.append_entry_by_nid(
+        Nid::COMMONNAME,
+        domains.first().map_or("", |d| d.as_str()),
+    )?;
+    Ok(name.build())
+}
+
+/// Self-signed certificate valid for one day
+fn self_signed(domains: &[String]) -> Result<(String, String), ErrorStack> {
+    let key = generate_key()?;
+
+    let name = subject(domains)?;
+
+    let mut serial = BigNum::new()?;
+    serial.rand(64, MsbOption::MAYBE_ZERO, false)?;
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial.to_asn1_integer()?)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&Asn1Time::days_from_now(0)?)?;
+    builder.set_not_after(&Asn1Time::days_from_now(1)?)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+    let cert = builder.build();
+
+    Ok((
+        String::from_utf8_lossy(&cert.to_pem()?).into_owned(),
+        String::from_utf8_lossy(&key.private_key_to_pem_pkcs8()?).into_owned(),
+    ))
+}
+
+/// Certificate signing request for the domains, DER encoded
+fn csr(domains: &[String], key: &PKey<Private>) -> Result<Vec<u8>, ErrorStack> {
+    let name = subject(domains)?;
+
+    let mut builder = X509Req::builder()?;
+    builder.set_subject_name(&name)?;
+    builder.set_pubkey(key)?;
+
+    let mut san = SubjectAlternativeName::new();
+    for domain in domains {
+        san.dns(domain);
+    }
+    let san = san.build(&builder.x509v3_context(None))?;
+    let mut extensions = Stack::new()?;
+    extensions.push(san)?;
+    builder.add_extensions(&extensions)?;
+
+    builder.sign(key, MessageDigest::sha256())?;
+    builder.build().to_der()
+}
+
+fn b64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+/// Response of ACME server
+struct AcmeResponse {
+    location: Option<String>,
+    body: Bytes,
+}
+
+impl AcmeResponse {
+    fn json(&self) -> Result<Value, AcmeError> {
+        serde_json::from_slice(&self.body)
+            .map_err(|e| AcmeError::Server(format!("Invalid json: {}", e)))
+    }
+}
+
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+/// ACME client, requests are signed with account key (ES256)
+struct AcmeClient {
+    client: actoriwc::Client,
+    key: PKey<Private>,
+    jwk: Value,
+    kid: Option<String>,
+    nonce: Option<String>,
+    directory: Directory,
+}
+
+impl AcmeClient {
+    /// Load or create account key and register account
+    async fn new(config: &AcmeConfig) -> Result<AcmeClient, AcmeError> {
+        let key = match fs::read(config.account_path()) {
+            Ok(pem) => PKey::private_key_from_pem(&pem)?,
+            Err(_) => {
+                let key = generate_key()?;
+                let pem = key.private_key_to_pem_pkcs8()?;
+                write_private(&config.account_path(), &pem)?;
+                key
+            }
+        };
+
+        let ec = key.ec_key()?;
+        let mut ctx = BigNumContext::new()?;
+        let mut x = BigNum::new()?;
+        let mut y = BigNum::new()?;
+        ec.public_key()
+            .affine_coordinates_gfp(ec.group(), &mut x, &mut y, &mut ctx)?;
+        // members are ordered lexicographically for the thumbprint
+        let jwk = json!({
+            "crv": "P-256",
+            "kty": "EC",
+            "x": b64(&x.to_vec_padded(32)?),
+            "y": b64(&y.to_vec_padded(32)?),
+        });
+
+        let client = actoriwc::Client::build()
+            .timeout(Duration::from_secs(30))
+            .finish();
+        let mut res = client
+            .get(config.directory.as_str())
+            .send()
+            .await
+            .map_err(|e| AcmeError::Request(e.to_string()))?;
+        let body = res
+            .body()
+            .await
+            .map_err(|e| AcmeError::Request(e.to_string()))?;
+        let directory: Value = serde_json::from_slice(&body)
+            .map_err(|e| AcmeError::Server(format!("Invalid directory: {}", e)))?;
+        let url = |name: &str| {
+            directory[name]
+                .as_str()
+                .map(|s| s.to_string())
+                .ok_or_else(|| AcmeError::Server(format!("Directory misses {}", name)))
+        };
+
+        let mut client = AcmeClient {
+            directory: Directory {
+                new_nonce: url("newNonce")?,
+                new_account: url("newAccount")?,
+                new_order: url("newOrder")?,
+            },
+            client,
+            key,
+            jwk,
+            kid: None,
+            nonce: None,
+        };
+
+        let payload = json!({
+            "termsOfServiceAgreed": true,
+            "contact": config.contact,
+        });
+        let url = client.directory.new_account.clone();
+        let res = client.post(&url, Some(&payload)).await?;
+        client.kid = res.location;
+        if client.kid.is_none() {
+            return Err(AcmeError::Server("Account url is missing".to_string()));
+        }
+        Ok(client)
+    }
+
+    /// Key authorization for the challenge token
+    fn key_authorization(&self, token: &str) -> String {
+        let thumbprint = sha256(self.jwk.to_string().as_bytes());
+        format!("{}.{}", token, b64(&thumbprint))
+    }
+
+    async fn nonce(&mut self) -> Result<String, AcmeError> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let res = self
+            .client
+            .head(self.directory.new_nonce.as_str())
+            .send()
+            .await
+            .map_err(|e| AcmeError::Request(e.to_string()))?;
+        res.headers()
+            .get("replay-nonce")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+            .ok_or_else(|| AcmeError::Server("Nonce is missing".to_string()))
+    }
+
+    /// Signed request, POST-as-GET if payload is not set
+    async fn post(
+        &mut self,
+        url: &str,
+        payload: Option<&Value>,
+    ) -> Result<AcmeResponse, AcmeError> {
+        // request is repeated once if nonce is rejected
+        let mut retry = true;
+        loop {
+            let nonce = self.nonce().await?;
+            let mut protected = json!({
+                "alg": "ES256",
+                "nonce": nonce,
+                "url": url,
+            });
+            match self.kid {
+                Some(ref kid) => protected["kid"] = json!(kid),
+                None => protected["jwk"] = self.jwk.clone(),
+            }
+            let protected = b64(protected.to_string().as_bytes());
+            let payload = payload.map(|p| b64(p.to_string().as_bytes()));
+            let payload = payload.unwrap_or_default();
+
+            let mut signer = Signer::new(MessageDigest::sha256(), &self.key)?;
+            signer.update(format!("{}.{}", protected, payload).as_bytes())?;
+            let sig = EcdsaSig::from_der(&signer.sign_to_vec()?)?;
+            let mut signature = sig.r().to_vec_padded(32)?;
+            signature.extend(sig.s().to_vec_padded(32)?);
+
+            let body = json!({
+                "protected": protected,
+                "payload": payload,
+                "signature": b64(&signature),
+            });
+
+            let mut res = self
+                .client
+                .post(url)
+                .insert_header((header::CONTENT_TYPE, "application/jose+json"))
+                .send_body(body.to_string())
+                .await
+                .map_err(|e| AcmeError::Request(e.to_string()))?;
+            let body = res
+                .body()
+                .limit(1024 * 1024)
+                .await
+                .map_err(|e| AcmeError::Request(e.to_string()))?;
+
+            let header = |name: &str| {
+                res.headers()
+                    .get(name)
+                    .and_then(|v: &HeaderValue| v.to_str().ok())
+                    .map(|v| v.to_string())
+            };
+            self.nonce = header("replay-nonce");
+            let location = header("location");
+
+            if res.status().is_success() {
+                return Ok(AcmeResponse { location, body });
+            }
+
+            let problem: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            if retry
+                && res.status() == StatusCode::BAD_REQUEST
+                && problem["type"] == "urn:ietf:params:acme:error:badNonce"
+            {
+                retry = false;
+                continue;
+            }
+            return Err(AcmeError::Server(format!(
+                "{} {}",
+                res.status(),
+                problem["detail"].as_str().unwrap_or("")
+            )));
+        }
+    }
+
+    /// Poll resource until its status is not pending or processing
+    async fn poll(&mut self, url: &str) -> Result<Value, AcmeError> {
+        for _ in 0..30 {
+            let res = self.post(url, None).await?.json()?;
+            match res["status"].as_str() {
+                Some("pending") | Some("processing") => {
+                    delay_for(Duration::from_secs(2)).await
+                }
+                _ => return Ok(res),
+            }
+        }
+        Err(AcmeError::Server(format!("Timeout while polling {}", url)))
+    }
+
+    /// Order certificate, returns pem encoded certificate chain and
+    /// private key
+    async fn order(
+        &mut self,
+        domains: &[String],
+        challenges: &Challenges,
+    ) -> Result<(String, String), AcmeError> {
+        let identifiers: Vec<_> = domains
+            .iter()
+            .map(|d| json!({"type": "dns", "value": d}))
+            .collect();
+        let url = self.directory.new_order.clone();
+        let res = self
+            .post(&url, Some(&json!({ "identifiers": identifiers })))
+            .await?;
+        let order_url = res
+            .location
+            .clone()
+            .ok_or_else(|| AcmeError::Server("Order url is missing".to_string()))?;
+        let order = res.json()?;
+
+        let authorizations: Vec<String> = order["authorizations"]
+            .as_array()
+            .map(|auths| {
+                auths
+                    .iter()
+                    .filter_map(|a| a.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for auth_url in authorizations {
+            self.authorize(&auth_url, challenges).await?;
+        }
+
+        // finalize order
+        let key = generate_key()?;
+        let csr = csr(domains, &key)?;
+        let finalize = order["finalize"]
+            .as_str()
+            .ok_or_else(|| AcmeError::Server("Finalize url is missing".to_string()))?
+            .to_string();
+        self.post(&finalize, Some(&json!({ "csr": b64(&csr) })))
+            .await?;
+
+        let order = self.poll(&order_url).await?;
+        let cert_url = match (order["status"].as_str(), order["certificate"].as_str()) {
+            (Some("valid"), Some(url)) => url.to_string(),
+            _ => return Err(AcmeError::Server(format!("Order failed: {}", order))),
+        };
+        let cert = self.post(&cert_url, None).await?;
+
+        Ok((
+            String::from_utf8_lossy(&cert.body).into_owned(),
+            String::from_utf8_lossy(&key.private_key_to_pem_pkcs8()?).into_owned(),
+        ))
+    }
+
+    /// Complete `http-01` challenge of the authorization
+    async fn authorize(
+        &mut self,
+        url: &str,
+        challenges: &Challenges,
+    ) -> Result<(), AcmeError> {
+        let auth = self.post(url, None).await?.json()?;
+        if auth["status"] == "valid" {
+            return Ok(());
+        }
+
+        let challenge = auth["challenges"]
+            .as_array()
+            .and_then(|c| c.iter().find(|c| c["type"] == "http-01"))
+            .ok_or_else(|| {
+                AcmeError::Server("http-01 challenge is missing".to_string())
+            })?;
+        let token = challenge["token"].as_str().unwrap_or("").to_string();
+        let challenge_url = challenge["url"].as_str().unwrap_or("").to_string();
+
+        challenges.insert(token.clone(), self.key_authorization(&token));
+        let res = async {
+            self.post(&challenge_url, Some(&json!({}))).await?;
+            self.poll(url).await
+        }
+        .await;
+        challenges.remove(&token);
+
+        let auth = res?;
+        if auth["status"] == "valid" {
+            Ok(())
+        } else {
+            Err(AcmeError::Server(format!(
+                "Authorization of {} failed",
+                auth["identifier"]["value"]
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_http::test::TestRequest;
+    use actori_service::Service;
+
+    use super::*;
+
+    #[actori_rt::test]
+    async fn test_challenge_service() {
+        let challenges = Challenges::default();
+        challenges.insert("token".to_string(), "token.thumbprint".to_string());
+        let mut srv = challenge_service(challenges.clone())
+            .new_service(())
+            .await
+            .unwrap();
+
+        let req = TestRequest::with_uri("/.well-known/acme-challenge/token").finish();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        challenges.remove("token");
+        let req = TestRequest::with_uri("/.well-known/acme-challenge/token").finish();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/index.html?q=1")
+            .header(header::HOST, "example.com:80")
+            .finish();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "https://example.com/index.html?q=1"
+        );
+    }
+
+    #[test]
+    fn test_initial_acceptor() {
+        let dir = std::env::temp_dir().join(format!(
+            "actori-web-acme-test-{}-initial",
+            std::process::id()
+        ));
+        let config = AcmeConfig::new(&["localhost"], &dir);
+        assert!(initial_acceptor(&config).is_ok());
+
+        let key = generate_key().unwrap();
+        let der = csr(&config.domains, &key).unwrap();
+        let req = X509Req::from_der(&der).unwrap();
+        assert!(req.verify(&key).unwrap());
+    }
+
+    #[test]
+    fn test_private_files() {
+        let dir = std::env::temp_dir().join(format!(
+            "actori-web-acme-test-{}-private/cache",
+            std::process::id()
+        ));
+        let config = AcmeConfig::new(&["localhost"], &dir);
+        create_cache_dir(&config.cache_dir).unwrap();
+        write_private(&config.key_path(), b"key").unwrap();
+        write_private(&config.key_path(), b"k").unwrap();
+        assert_eq!(fs::read(config.key_path()).unwrap(), b"k");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode(&config.cache_dir) & 0o777, 0o700);
+            assert_eq!(mode(&config.key_path()) & 0o777, 0o600);
+        }
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+}
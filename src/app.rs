@@ -1,3 +1,4 @@
+use std::any::TypeId;
 use std::cell::RefCell;
 use std::fmt;
 use std::future::Future;
@@ -5,18 +6,18 @@ use std::marker::PhantomData;
 use std::rc::Rc;
 
 use actori_http::body::{Body, MessageBody};
-use actori_http::Extensions;
+use actori_http::{DynExtensions, Extensions};
 use actori_service::boxed::{self, BoxServiceFactory};
 use actori_service::{
     apply, apply_fn_factory, IntoServiceFactory, ServiceFactory, Transform,
 };
-use futures::future::{FutureExt, LocalBoxFuture};
+use futures::future::{ready, FutureExt, LocalBoxFuture};
 
-use crate::app_service::{AppEntry, AppInit, AppRoutingFactory};
+use crate::app_service::{AppEntry, AppInit, AppRoutingFactory, ShutdownHook};
 use crate::config::ServiceConfig;
-use crate::data::{Data, DataFactory};
+use crate::data::{Data, DataCleanup, DataFactory, ShardedData};
 use crate::dev::ResourceDef;
-use crate::error::Error;
+use crate::error::{Error, ErrorEvent, ErrorObserver};
 use crate::resource::Resource;
 use crate::route::Route;
 use crate::service::{
@@ -37,8 +38,14 @@ pub struct App<T, B> {
     factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     data: Vec<Box<dyn DataFactory>>,
     data_factories: Vec<FnDataFactory>,
+    data_factory_types: Vec<TypeId>,
     external: Vec<ResourceDef>,
     extensions: Extensions,
+    prefix: Option<String>,
+    warmup: Vec<String>,
+    drain_head: bool,
+    shutdown: Vec<ShutdownHook>,
+    observer: Option<ErrorObserver>,
     _t: PhantomData<B>,
 }
 
@@ -50,11 +57,17 @@ impl App<AppEntry, Body> {
             endpoint: AppEntry::new(fref.clone()),
             data: Vec::new(),
             data_factories: Vec::new(),
+            data_factory_types: Vec::new(),
             services: Vec::new(),
             default: None,
             factory_ref: fref,
             external: Vec::new(),
             extensions: Extensions::new(),
+            prefix: None,
+            warmup: Vec::new(),
+            drain_head: true,
+            shutdown: Vec::new(),
+            observer: None,
             _t: PhantomData,
         }
     }
@@ -106,6 +119,19 @@ where
         self
     }
 
+    /// Set application data with a separate instance per worker.
+    ///
+    /// Shard is created with `ShardedData` factory once application
+    /// instance starts, and is available with `web::ShardedData<T>`
+    /// extractor. See `ShardedData` documentation for an example.
+    pub fn sharded_data<U>(mut self, data: ShardedData<U>) -> Self
+    where
+        U: Send + Sync + 'static,
+    {
+        self.data.push(Box::new(data));
+        self
+    }
+
     /// Set application data factory. This function is
     /// similar to `.data()` but it accepts data factory. Data object get
     /// constructed asynchronously during application initialization.
@@ -134,6 +160,7 @@ where
             }
             .boxed_local()
         }));
+        self.data_factory_types.push(TypeId::of::<Data<D>>());
         self
     }
 
@@ -149,6 +176,39 @@ where
         self
     }
 
+    /// Set application level data item under the string key.
+    ///
+    /// Data stored with `App::dyn_data()` method is available via
+    /// `HttpRequest::app_dyn_data()` method at runtime. Plugins could use
+    /// keys in the `"{namespace}::{key}"` form, see `DynExtensions::namespace()`.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpRequest, HttpResponse};
+    ///
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     let greeting = req.app_dyn_data::<String>("my_plugin::greeting").unwrap();
+    ///     HttpResponse::Ok().body(greeting.clone())
+    /// }
+    ///
+    /// let app = App::new()
+    ///     .dyn_data("my_plugin::greeting", "hello".to_string())
+    ///     .route("/", web::get().to(index));
+    /// ```
+    pub fn dyn_data<K, U>(mut self, key: K, data: U) -> Self
+    where
+        K: Into<String>,
+        U: 'static,
+    {
+        if !self.extensions.contains::<DynExtensions>() {
+            self.extensions.insert(DynExtensions::new());
+        }
+        self.extensions
+            .get_mut::<DynExtensions>()
+            .unwrap()
+            .insert(key, data);
+        self
+    }
+
     /// Run external configuration as part of the application building
     /// process
     ///
@@ -222,7 +282,7 @@ where
     ///
     /// * *Resource* is an entry in resource table which corresponds to requested URL.
     /// * *Scope* is a set of resources with common root path.
-    /// * "StaticFiles" is a service for static files support
+    /// * `web::Files` is a service for static files support
     pub fn service<F>(mut self, factory: F) -> Self
     where
         F: HttpServiceFactory + 'static,
@@ -318,6 +378,211 @@ where
         self
     }
 
+    /// Set mount prefix of the application.
+    ///
+    /// Mount prefix gets removed from the request path before routing,
+    /// and gets prepended to urls generated with `HttpRequest::url_for()`.
+    /// This allows to deploy application under a sub-path without changing
+    /// every route. Requests with path that does not start with the prefix
+    /// are not routed, such requests get 404 (Not Found) response without
+    /// calling application middlewares.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpRequest, HttpResponse};
+    ///
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     // generated url is "http://localhost:8080/myapp/index.html"
+    ///     let url = req.url_for_static("index");
+    ///     HttpResponse::Ok().into()
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .strip_prefix("/myapp")
+    ///         // handles "/myapp/index.html" requests
+    ///         .service(web::resource("/index.html").name("index").to(index));
+    /// }
+    /// ```
+    pub fn strip_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        self.prefix = if prefix.is_empty() {
+            None
+        } else if prefix.starts_with('/') {
+            Some(prefix.to_owned())
+        } else {
+            Some(format!("/{}", prefix))
+        };
+        self
+    }
+
+    /// Warm up application on worker start.
+    ///
+    /// After application instance is created, each worker sends internal
+    /// `GET` request to each of the listed paths through the normal service
+    /// chain, including middlewares, and waits for the response. This allows
+    /// to populate caches and lazily initialized data before worker starts
+    /// handling client requests. Request errors and error responses are
+    /// logged, they do not prevent worker start.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// async fn index() -> HttpResponse {
+    ///     HttpResponse::Ok().finish()
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .warmup(vec!["/", "/index.html?lang=en"])
+    ///         .route("/", web::get().to(index))
+    ///         .route("/index.html", web::get().to(index));
+    /// }
+    /// ```
+    pub fn warmup<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.warmup
+            .extend(paths.into_iter().map(|p| p.as_ref().to_owned()));
+        self
+    }
+
+    /// Read response body of `HEAD` requests to the end.
+    ///
+    /// Handlers may return full response for `HEAD` request, server sends
+    /// only response headers, including `Content-Length` and `ETag`, and
+    /// discards the body. By default server reads the body to the end, so
+    /// middlewares see full body size, i.e. `Logger` reports it with `%b`.
+    /// If draining is disabled, body is dropped without reading as soon as
+    /// response headers are sent.
+    ///
+    /// By default draining is enabled.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// async fn index() -> HttpResponse {
+    ///     HttpResponse::Ok().body("large body")
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .drain_head_body(false)
+    ///         .route("/", web::get().to(index))
+    ///         .route("/", web::head().to(index));
+    /// }
+    /// ```
+    pub fn drain_head_body(mut self, val: bool) -> Self {
+        self.drain_head = val;
+        self
+    }
+
+    /// Register a hook that runs when the server shuts down gracefully.
+    ///
+    /// Server constructs an application instance for each worker, hooks of
+    /// the instance run concurrently on its worker after in-flight requests
+    /// are completed or the graceful shutdown timeout has expired, see
+    /// [Shutdown](./dev/struct.Shutdown.html). Hooks do not run when the
+    /// instance is dropped or the server is stopped immediately.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .on_shutdown(|| async { log::info!("worker stopped") })
+    ///         .route("/", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.shutdown
+            .push(Box::new(move |_: &Extensions| hook().boxed_local()));
+        self
+    }
+
+    /// Run `DataCleanup::cleanup()` of the application data of type `U`
+    /// when the server shuts down gracefully.
+    ///
+    /// Data could be registered with `App::data()`, `App::data_factory()`
+    /// or `App::app_data()`, cleanup is skipped if data is not registered.
+    /// Check [`on_shutdown()`](#method.on_shutdown) for more information.
+    ///
+    /// ```rust
+    /// use actori_web::web::{self, DataCleanup};
+    /// use actori_web::{App, HttpResponse};
+    /// use futures::future::{FutureExt, LocalBoxFuture};
+    ///
+    /// struct Pool;
+    ///
+    /// impl DataCleanup for Pool {
+    ///     fn cleanup(&self) -> LocalBoxFuture<'_, ()> {
+    ///         async move {
+    ///             // close connections
+    ///         }
+    ///         .boxed_local()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .data(Pool)
+    ///         .data_cleanup::<Pool>()
+    ///         .route("/", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn data_cleanup<U: DataCleanup>(mut self) -> Self {
+        self.shutdown
+            .push(Box::new(|data: &Extensions| match data.get::<Data<U>>() {
+                Some(data) => {
+                    let data = data.clone();
+                    async move { data.cleanup().await }.boxed_local()
+                }
+                None => ready(()).boxed_local(),
+            }));
+        self
+    }
+
+    /// Set error observer of the application.
+    ///
+    /// Observer is called for every error that is converted to a response by
+    /// the application, including errors returned by middlewares. It could
+    /// be used to capture error telemetry. Observer replaces previously set
+    /// observer.
+    ///
+    /// Complete responses used as errors, i.e. `Result<T, HttpResponse>`,
+    /// are not observed.
+    ///
+    /// ```rust
+    /// use actori_web::{error::ErrorEvent, web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .error_observer(|ev: &ErrorEvent| {
+    ///             log::error!(
+    ///                 "{} {} {:?} {:?}: {}",
+    ///                 ev.status(),
+    ///                 ev.error_type(),
+    ///                 ev.match_pattern(),
+    ///                 ev.request_id(),
+    ///                 ev.error()
+    ///             );
+    ///         })
+    ///         .route("/", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    pub fn error_observer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ErrorEvent<'_>) + 'static,
+    {
+        self.observer = Some(Rc::new(f));
+        self
+    }
+
     /// Registers middleware, in the form of a middleware component (type),
     /// that runs during inbound and/or outbound processing in the request
     /// lifecycle (request -> response), modifying request/response as
@@ -375,11 +640,17 @@ where
             endpoint: apply(mw, self.endpoint),
             data: self.data,
             data_factories: self.data_factories,
+            data_factory_types: self.data_factory_types,
             services: self.services,
             default: self.default,
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            prefix: self.prefix,
+            warmup: self.warmup,
+            drain_head: self.drain_head,
+            shutdown: self.shutdown,
+            observer: self.observer,
             _t: PhantomData,
         }
     }
@@ -437,11 +708,17 @@ where
             endpoint: apply_fn_factory(self.endpoint, mw),
             data: self.data,
             data_factories: self.data_factories,
+            data_factory_types: self.data_factory_types,
             services: self.services,
             default: self.default,
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            prefix: self.prefix,
+            warmup: self.warmup,
+            drain_head: self.drain_head,
+            shutdown: self.shutdown,
+            observer: self.observer,
             _t: PhantomData,
         }
     }
@@ -459,8 +736,18 @@ where
     >,
 {
     fn into_factory(self) -> AppInit<T, B> {
+        // types of the application data, used for data requirements check
+        let data_types: Vec<TypeId> = self
+            .data
+            .iter()
+            .map(|f| f.data_type())
+            .chain(self.data_factory_types)
+            .chain(self.extensions.type_ids())
+            .collect();
+
         AppInit {
             data: Rc::new(self.data),
+            data_types: Rc::new(data_types),
             data_factories: Rc::new(self.data_factories),
             endpoint: self.endpoint,
             services: Rc::new(RefCell::new(self.services)),
@@ -468,12 +755,20 @@ where
             default: self.default,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
+            prefix: self.prefix,
+            warmup: Rc::new(self.warmup),
+            drain_head: self.drain_head,
+            shutdown: Rc::new(self.shutdown),
+            observer: self.observer,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
     use actori_service::Service;
     use bytes::Bytes;
     use futures::future::ok;
@@ -659,6 +954,38 @@ mod tests {
         );
     }
 
+    #[actori_rt::test]
+    async fn test_strip_prefix() {
+        let mut srv = init_service(
+            App::new().strip_prefix("/myapp/").service(
+                web::resource("/test/{id}")
+                    .name("test")
+                    .route(web::get().to(|req: HttpRequest| {
+                        HttpResponse::Ok()
+                            .body(format!("{}", req.url_for("test", &["12"]).unwrap()))
+                    })),
+            ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/myapp/test/1?q=1").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(b"http://localhost:8080/myapp/test/12")
+        );
+
+        let req = TestRequest::with_uri("/myappx/test/1").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // path without prefix is not routed
+        let req = TestRequest::with_uri("/test/1").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actori_rt::test]
     async fn test_external_resource() {
         let mut srv = init_service(
@@ -681,4 +1008,58 @@ mod tests {
         let body = read_body(resp).await;
         assert_eq!(body, Bytes::from_static(b"https://youtube.com/watch/12345"));
     }
+
+    #[actori_rt::test]
+    async fn test_warmup() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let c = counter.clone();
+        let mut srv = init_service(
+            App::new()
+                .warmup(vec!["/test", "/unknown", "invalid path"])
+                .warmup(Some("/test?q=1"))
+                .route(
+                    "/test",
+                    web::get().to(move || {
+                        c.fetch_add(1, Ordering::Relaxed);
+                        async { HttpResponse::Ok() }
+                    }),
+                ),
+        )
+        .await;
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(counter.load(Ordering::Relaxed), 3);
+    }
+
+    #[actori_rt::test]
+    async fn test_drain_head_body() {
+        let mut srv = init_service(
+            App::new()
+                .route("/test", web::head().to(|| HttpResponse::Ok().body("body"))),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.response().head().drain_head_body());
+
+        let mut srv = init_service(
+            App::new()
+                .drain_head_body(false)
+                .route("/test", web::head().to(|| HttpResponse::Ok().body("body"))),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.response().head().drain_head_body());
+        let body = read_body(resp).await;
+        assert_eq!(body, Bytes::from_static(b"body"));
+    }
 }
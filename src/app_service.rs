@@ -1,3 +1,4 @@
+use std::any::TypeId;
 use std::cell::RefCell;
 use std::future::Future;
 use std::marker::PhantomData;
@@ -5,26 +6,32 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
+use actori_http::http::Uri;
 use actori_http::{Extensions, Request, Response};
 use actori_router::{Path, ResourceDef, ResourceInfo, Router, Url};
 use actori_service::boxed::{self, BoxService, BoxServiceFactory};
 use actori_service::{fn_service, Service, ServiceFactory};
 use futures::future::{ok, FutureExt, LocalBoxFuture};
+use futures::ready;
 
 use crate::config::{AppConfig, AppService};
 use crate::data::DataFactory;
-use crate::error::Error;
+use crate::error::{self, Error, ErrorObserver, RequestMeta};
 use crate::guard::Guard;
 use crate::request::{HttpRequest, HttpRequestPool};
 use crate::rmap::ResourceMap;
 use crate::service::{AppServiceFactory, ServiceRequest, ServiceResponse};
+use crate::shutdown;
+#[cfg(feature = "tracing")]
+use crate::trace::RequestSpan;
 
-type Guards = Vec<Box<dyn Guard>>;
+pub(crate) type Guards = Vec<Box<dyn Guard>>;
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
 type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
 type BoxResponse = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
 type FnDataFactory =
     Box<dyn Fn() -> LocalBoxFuture<'static, Result<Box<dyn DataFactory>, ()>>>;
+pub(crate) type ShutdownHook = Box<dyn Fn(&Extensions) -> LocalBoxFuture<'static, ()>>;
 
 /// Service factory to convert `Request` to a `ServiceRequest<S>`.
 /// It also executes data factories.
@@ -41,11 +48,17 @@ where
     pub(crate) endpoint: T,
     pub(crate) extensions: RefCell<Option<Extensions>>,
     pub(crate) data: Rc<Vec<Box<dyn DataFactory>>>,
+    pub(crate) data_types: Rc<Vec<TypeId>>,
     pub(crate) data_factories: Rc<Vec<FnDataFactory>>,
     pub(crate) services: Rc<RefCell<Vec<Box<dyn AppServiceFactory>>>>,
     pub(crate) default: Option<Rc<HttpNewService>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) prefix: Option<String>,
+    pub(crate) warmup: Rc<Vec<String>>,
+    pub(crate) drain_head: bool,
+    pub(crate) shutdown: Rc<Vec<ShutdownHook>>,
+    pub(crate) observer: Option<ErrorObserver>,
 }
 
 impl<T, B> ServiceFactory for AppInit<T, B>
@@ -74,15 +87,33 @@ where
             })))
         });
 
+        // data shared by all application instances
+        let data_types = if config.shared_data().is_empty() {
+            self.data_types.clone()
+        } else {
+            let shared = config.shared_data().iter().map(|f| f.data_type());
+            Rc::new(self.data_types.iter().cloned().chain(shared).collect())
+        };
+
         // App config
-        let mut config = AppService::new(config, default.clone(), self.data.clone());
+        let mut config =
+            AppService::new(config, default.clone(), self.data.clone(), data_types);
 
         // register services
         std::mem::replace(&mut *self.services.borrow_mut(), Vec::new())
             .into_iter()
             .for_each(|mut srv| srv.register(&mut config));
 
-        let mut rmap = ResourceMap::new(ResourceDef::new(""));
+        // data required by scopes and resources must be registered
+        let missing_data = config.missing_data();
+        for msg in missing_data.iter() {
+            log::error!("{}", msg);
+        }
+
+        // mount prefix is used as a root for url generation
+        let mut rmap = ResourceMap::new(ResourceDef::new(
+            self.prefix.as_ref().map(|s| s.as_str()).unwrap_or(""),
+        ));
 
         let (config, services) = config.into_services();
 
@@ -92,8 +123,8 @@ where
             services: Rc::new(
                 services
                     .into_iter()
-                    .map(|(mut rdef, srv, guards, nested)| {
-                        rmap.add(&mut rdef, nested);
+                    .map(|(mut rdef, srv, guards, nested, meta)| {
+                        rmap.add_resource(&mut rdef, nested, meta);
                         (rdef, srv, RefCell::new(guards))
                     })
                     .collect(),
@@ -110,6 +141,7 @@ where
         rmap.finish(rmap.clone());
 
         AppInitResult {
+            missing_data: !missing_data.is_empty(),
             endpoint: None,
             endpoint_fut: self.endpoint.new_service(()),
             data: self.data.clone(),
@@ -123,6 +155,14 @@ where
             ),
             config,
             rmap,
+            prefix: self.prefix.clone(),
+            service: None,
+            warmup: self.warmup.clone(),
+            warmup_idx: 0,
+            warmup_fut: None,
+            drain_head: self.drain_head,
+            shutdown: self.shutdown.clone(),
+            observer: self.observer.clone(),
             _t: PhantomData,
         }
     }
@@ -131,8 +171,15 @@ where
 #[pin_project::pin_project]
 pub struct AppInitResult<T, B>
 where
-    T: ServiceFactory,
+    T: ServiceFactory<
+        Config = (),
+        Request = ServiceRequest,
+        Response = ServiceResponse<B>,
+        Error = Error,
+        InitError = (),
+    >,
 {
+    missing_data: bool,
     endpoint: Option<T::Service>,
     #[pin]
     endpoint_fut: T::Future,
@@ -142,6 +189,15 @@ where
     data_factories: Vec<Box<dyn DataFactory>>,
     data_factories_fut: Vec<LocalBoxFuture<'static, Result<Box<dyn DataFactory>, ()>>>,
     extensions: Option<Extensions>,
+    prefix: Option<String>,
+    service: Option<AppInitService<T::Service, B>>,
+    warmup: Rc<Vec<String>>,
+    warmup_idx: usize,
+    warmup_fut:
+        Option<Pin<Box<AppInitServiceResponse<<T::Service as Service>::Future>>>>,
+    drain_head: bool,
+    shutdown: Rc<Vec<ShutdownHook>>,
+    observer: Option<ErrorObserver>,
     _t: PhantomData<B>,
 }
 
@@ -160,25 +216,33 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
 
-        // async data factories
-        let mut idx = 0;
-        while idx < this.data_factories_fut.len() {
-            match Pin::new(&mut this.data_factories_fut[idx]).poll(cx)? {
-                Poll::Ready(f) => {
-                    this.data_factories.push(f);
-                    let _ = this.data_factories_fut.remove(idx);
+        if *this.missing_data {
+            return Poll::Ready(Err(()));
+        }
+
+        if this.service.is_none() {
+            // async data factories
+            let mut idx = 0;
+            while idx < this.data_factories_fut.len() {
+                match Pin::new(&mut this.data_factories_fut[idx]).poll(cx)? {
+                    Poll::Ready(f) => {
+                        this.data_factories.push(f);
+                        let _ = this.data_factories_fut.remove(idx);
+                    }
+                    Poll::Pending => idx += 1,
                 }
-                Poll::Pending => idx += 1,
             }
-        }
 
-        if this.endpoint.is_none() {
-            if let Poll::Ready(srv) = this.endpoint_fut.poll(cx)? {
-                *this.endpoint = Some(srv);
+            if this.endpoint.is_none() {
+                if let Poll::Ready(srv) = this.endpoint_fut.poll(cx)? {
+                    *this.endpoint = Some(srv);
+                }
+            }
+
+            if this.endpoint.is_none() || !this.data_factories_fut.is_empty() {
+                return Poll::Pending;
             }
-        }
 
-        if this.endpoint.is_some() && this.data_factories_fut.is_empty() {
             // create app data container
             let mut data = this.extensions.take().unwrap();
             for f in this.data.iter() {
@@ -189,15 +253,69 @@ where
                 f.create(&mut data);
             }
 
-            Poll::Ready(Ok(AppInitService {
+            // data of the application takes precedence over shared data
+            for f in this.config.shared_data().iter() {
+                f.create(&mut data);
+            }
+
+            let data = Rc::new(data);
+            shutdown::register_hooks(this.shutdown, &data);
+
+            *this.service = Some(AppInitService {
                 service: this.endpoint.take().unwrap(),
                 rmap: this.rmap.clone(),
                 config: this.config.clone(),
-                data: Rc::new(data),
+                data,
+                prefix: this.prefix.take(),
+                drain_head: *this.drain_head,
+                observer: this.observer.take(),
                 pool: HttpRequestPool::create(),
-            }))
-        } else {
-            Poll::Pending
+            });
+        }
+
+        // warm up application, requests are sent one by one
+        loop {
+            if let Some(ref mut fut) = this.warmup_fut {
+                let path = &this.warmup[*this.warmup_idx - 1];
+                match fut.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(res)) => {
+                        let status = res.status();
+                        if status.is_client_error() || status.is_server_error() {
+                            log::warn!("Warmup request {:?} failed: {}", path, status);
+                        }
+                    }
+                    Poll::Ready(Err(e)) => {
+                        log::warn!("Warmup request {:?} failed: {}", path, e)
+                    }
+                }
+                *this.warmup_fut = None;
+            }
+
+            if *this.warmup_idx == this.warmup.len() {
+                return Poll::Ready(Ok(this.service.take().unwrap()));
+            }
+
+            let srv = this.service.as_mut().unwrap();
+            match srv.poll_ready(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(_)) => (),
+                Poll::Ready(Err(e)) => {
+                    log::error!("Application is not ready for warmup: {}", e);
+                    return Poll::Ready(Ok(this.service.take().unwrap()));
+                }
+            }
+
+            let path = &this.warmup[*this.warmup_idx];
+            *this.warmup_idx += 1;
+            match path.parse::<Uri>() {
+                Ok(uri) => {
+                    let mut req = Request::new();
+                    req.head_mut().uri = uri;
+                    *this.warmup_fut = Some(Box::pin(srv.call(req)));
+                }
+                Err(e) => log::error!("Invalid warmup path {:?}: {}", path, e),
+            }
         }
     }
 }
@@ -211,6 +329,9 @@ where
     rmap: Rc<ResourceMap>,
     config: AppConfig,
     data: Rc<Extensions>,
+    prefix: Option<String>,
+    drain_head: bool,
+    observer: Option<ErrorObserver>,
     pool: &'static HttpRequestPool,
 }
 
@@ -221,14 +342,23 @@ where
     type Request = Request;
     type Response = ServiceResponse<B>;
     type Error = T::Error;
-    type Future = T::Future;
+    type Future = AppInitServiceResponse<T::Future>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.service.poll_ready(cx)
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
-        let (head, payload) = req.into_parts();
+        let (mut head, payload) = req.into_parts();
+
+        // remove mount prefix, requests outside of the prefix are not routed
+        let mut mounted = true;
+        if let Some(ref prefix) = self.prefix {
+            match strip_prefix(&head.uri, prefix) {
+                Some(uri) => head.uri = uri,
+                None => mounted = false,
+            }
+        }
 
         let req = if let Some(mut req) = self.pool.get_request() {
             let inner = Rc::get_mut(&mut req.0).unwrap();
@@ -236,7 +366,9 @@ where
             inner.path.reset();
             inner.head = head;
             inner.payload = payload;
-            inner.app_data = self.data.clone();
+            inner.router = None;
+            inner.app_data.clear();
+            inner.app_data.push(self.data.clone());
             req
         } else {
             HttpRequest::new(
@@ -249,10 +381,117 @@ where
                 self.pool,
             )
         };
-        self.service.call(ServiceRequest::new(req))
+        // request is not kept, errors returned by service are observed
+        // with request data captured in advance
+        let observed = self
+            .observer
+            .as_ref()
+            .map(|observer| (observer.clone(), RequestMeta::new(&req)));
+        #[cfg(feature = "tracing")]
+        let span = RequestSpan::new(&req);
+        let (fut, unmounted) = if mounted {
+            (Some(self.service.call(ServiceRequest::new(req))), None)
+        } else {
+            (None, Some(req))
+        };
+        AppInitServiceResponse {
+            fut,
+            unmounted,
+            observed,
+            drain_head: self.drain_head,
+            #[cfg(feature = "tracing")]
+            span,
+        }
     }
 }
 
+#[doc(hidden)]
+#[pin_project::pin_project]
+pub struct AppInitServiceResponse<F> {
+    #[pin]
+    fut: Option<F>,
+    unmounted: Option<HttpRequest>,
+    observed: Option<(ErrorObserver, RequestMeta)>,
+    drain_head: bool,
+    #[cfg(feature = "tracing")]
+    span: RequestSpan,
+}
+
+impl<F, B> Future for AppInitServiceResponse<F>
+where
+    F: Future<Output = Result<ServiceResponse<B>, Error>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = match this.fut.as_pin_mut() {
+            #[cfg(not(feature = "tracing"))]
+            Some(fut) => ready!(fut.poll(cx)),
+            #[cfg(feature = "tracing")]
+            Some(fut) => ready!(this.span.poll(fut, cx)),
+            // path does not start with mount prefix
+            None => Ok(ServiceResponse::new(
+                this.unmounted.take().unwrap(),
+                Response::NotFound().finish().into_body(),
+            )),
+        };
+        #[cfg(feature = "tracing")]
+        match res {
+            Ok(ref resp) => this.span.finish(resp.status(), Some(resp.request())),
+            Err(ref err) => {
+                let status = err.as_response_error().status_code();
+                this.span.finish(status, None)
+            }
+        }
+
+        if !*this.drain_head {
+            if let Ok(ref mut resp) = res {
+                resp.response_mut().head_mut().set_drain_head_body(false);
+            }
+        }
+
+        if let Some((observer, meta)) = this.observed.take() {
+            match res {
+                Ok(ref resp) => {
+                    if let Some(err) = resp.response().error() {
+                        let req = Some(resp.request());
+                        error::observe(&observer, err, resp.status(), &meta, req);
+                    }
+                }
+                Err(ref err) => {
+                    let status = err.as_response_error().status_code();
+                    error::observe(&observer, err, status, &meta, None);
+                }
+            }
+        }
+        Poll::Ready(res)
+    }
+}
+
+/// Remove prefix from the uri path, returns `None` if path does not start with prefix
+fn strip_prefix(uri: &Uri, prefix: &str) -> Option<Uri> {
+    let path = uri.path();
+    if !path.starts_with(prefix) {
+        return None;
+    }
+
+    let path = match &path[prefix.len()..] {
+        "" => "/",
+        path if path.starts_with('/') => path,
+        _ => return None,
+    };
+    let path = if let Some(query) = uri.query() {
+        format!("{}?{}", path, query)
+    } else {
+        path.to_owned()
+    };
+
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
 impl<T, B> Drop for AppInitService<T, B>
 where
     T: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
@@ -363,8 +602,10 @@ impl Future for AppRoutingFactoryResponse {
                 });
             Poll::Ready(Ok(AppRouting {
                 ready: None,
-                router: router.finish(),
-                default: self.default.take(),
+                inner: Rc::new(RefCell::new(AppRouter {
+                    router: router.finish(),
+                    default: self.default.take(),
+                })),
             }))
         } else {
             Poll::Pending
@@ -373,11 +614,43 @@ impl Future for AppRoutingFactoryResponse {
 }
 
 pub struct AppRouting {
-    router: Router<HttpService, Guards>,
+    inner: Rc<RefCell<AppRouter>>,
     ready: Option<(ServiceRequest, ResourceInfo)>,
+}
+
+/// Application router, it is shared with requests for internal forwarding
+pub(crate) struct AppRouter {
+    router: Router<HttpService, Guards>,
     default: Option<HttpService>,
 }
 
+/// Check resource guards, used by router to match resources
+pub(crate) fn check_guards(req: &ServiceRequest, guards: &Option<Guards>) -> bool {
+    if let Some(ref guards) = guards {
+        for f in guards {
+            if !f.check(req.head()) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+impl AppRouter {
+    pub(crate) fn call(&mut self, mut req: ServiceRequest) -> BoxResponse {
+        let res = self.router.recognize_mut_checked(&mut req, check_guards);
+
+        if let Some((srv, _info)) = res {
+            srv.call(req)
+        } else if let Some(ref mut default) = self.default {
+            default.call(req)
+        } else {
+            let req = req.into_parts().0;
+            ok(ServiceResponse::new(req, Response::NotFound().finish())).boxed_local()
+        }
+    }
+}
+
 impl Service for AppRouting {
     type Request = ServiceRequest;
     type Response = ServiceResponse;
@@ -393,25 +666,8 @@ impl Service for AppRouting {
     }
 
     fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_mut_checked(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
-                    }
-                }
-            }
-            true
-        });
-
-        if let Some((srv, _info)) = res {
-            srv.call(req)
-        } else if let Some(ref mut default) = self.default {
-            default.call(req)
-        } else {
-            let req = req.into_parts().0;
-            ok(ServiceResponse::new(req, Response::NotFound().finish())).boxed_local()
-        }
+        req.set_router(self.inner.clone());
+        self.inner.borrow_mut().call(req)
     }
 }
 
@@ -473,4 +729,94 @@ mod tests {
         }
         assert!(data.load(Ordering::Relaxed));
     }
+
+    #[actori_rt::test]
+    async fn test_shutdown_hooks() {
+        use std::cell::Cell;
+
+        use futures::future::{FutureExt, LocalBoxFuture};
+
+        use crate::data::DataCleanup;
+        use crate::shutdown::run_worker_hooks;
+
+        // hooks run on the worker, data does not have to be `Send`
+        struct Pool(Rc<Cell<usize>>);
+
+        impl DataCleanup for Pool {
+            fn cleanup(&self) -> LocalBoxFuture<'_, ()> {
+                async move { self.0.set(self.0.get() + 1) }.boxed_local()
+            }
+        }
+
+        let closed = Rc::new(Cell::new(0));
+        let stopped = Rc::new(Cell::new(0));
+        let app = |closed: &Rc<Cell<usize>>, stopped: &Rc<Cell<usize>>| {
+            let stopped = stopped.clone();
+            App::new()
+                .data(Pool(closed.clone()))
+                .data_cleanup::<Pool>()
+                .on_shutdown(move || {
+                    let stopped = stopped.clone();
+                    async move { stopped.set(stopped.get() + 1) }
+                })
+                .service(web::resource("/test").to(|| HttpResponse::Ok()))
+        };
+
+        // dropped instance does not run hooks
+        drop(init_service(app(&closed, &stopped)).await);
+        assert_eq!(closed.get(), 0);
+        assert_eq!(stopped.get(), 0);
+        run_worker_hooks().await;
+        assert_eq!(closed.get(), 0);
+        assert_eq!(stopped.get(), 0);
+
+        let srv = init_service(app(&closed, &stopped)).await;
+        assert_eq!(closed.get(), 0);
+        assert_eq!(stopped.get(), 0);
+        run_worker_hooks().await;
+        assert_eq!(closed.get(), 1);
+        assert_eq!(stopped.get(), 1);
+
+        // hooks run once
+        run_worker_hooks().await;
+        drop(srv);
+        assert_eq!(closed.get(), 1);
+        assert_eq!(stopped.get(), 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[actori_rt::test]
+    async fn test_trace_context() {
+        use actori_http::trace::TraceContext;
+        use bytes::Bytes;
+
+        use crate::test::read_body;
+        use crate::HttpRequest;
+
+        let mut app = init_service(App::new().service(web::resource("/test").to(
+            |req: HttpRequest| {
+                let ctx = *req.extensions().get::<TraceContext>().unwrap();
+                assert_eq!(TraceContext::current(), Some(ctx));
+                HttpResponse::Ok().body(format!(
+                    "{:032x}-{:016x}",
+                    ctx.trace_id(),
+                    ctx.parent_id().unwrap_or(0)
+                ))
+            },
+        )))
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .header(
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            )
+            .to_request();
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331")
+        );
+        assert!(TraceContext::current().is_none());
+    }
 }
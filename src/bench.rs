@@ -0,0 +1,84 @@
+//! Request routing and dispatch hot paths, exposed for benchmarks.
+//!
+//! Available with `bench` feature only. This is not a part of public api,
+//! functions could be changed or removed in any release.
+use std::future::Future;
+
+use actori_router::ResourceDef;
+use actori_service::{Service, ServiceFactory};
+
+use crate::app_service::{check_guards, Guards};
+use crate::error::Error;
+use crate::extract::FromRequest;
+use crate::guard::Guard;
+use crate::handler::{Extract, Factory, Handler};
+use crate::responder::Responder;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+pub use actori_http::h1::bench as h1;
+
+/// Resource router, requests are matched the same way as `App` and `Scope`
+/// match resources: in registration order, checking resource guards.
+pub struct Router(actori_router::Router<usize, Guards>);
+
+impl Router {
+    /// Build router from resource patterns. Resource id is pattern's index.
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Router::with_guards(patterns.into_iter().map(|p| (p, Vec::new())))
+    }
+
+    /// Build router from resource patterns and resource guards.
+    pub fn with_guards<I, S>(resources: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Vec<Box<dyn Guard>>)>,
+        S: AsRef<str>,
+    {
+        let mut router = actori_router::Router::build();
+        for (idx, (pattern, guards)) in resources.into_iter().enumerate() {
+            let guards = if guards.is_empty() {
+                None
+            } else {
+                Some(guards)
+            };
+            router.rdef(ResourceDef::new(pattern.as_ref()), idx).2 = guards;
+        }
+        Router(router.finish())
+    }
+
+    /// Match request and return id of the matched resource.
+    ///
+    /// Request match info is reset before matching, so the same request
+    /// could be matched repeatedly.
+    pub fn recognize(&mut self, req: &mut ServiceRequest) -> Option<usize> {
+        req.match_info_mut().reset();
+        self.0
+            .recognize_mut_checked(req, check_guards)
+            .map(|(idx, _)| *idx)
+    }
+}
+
+/// Create handler service, the same service `Route::to()` uses:
+/// it extracts handler arguments, calls handler and converts its result
+/// to a response.
+pub async fn handler<F, T, R, U>(
+    handler: F,
+) -> impl Service<
+    Request = ServiceRequest,
+    Response = ServiceResponse,
+    Error = (Error, ServiceRequest),
+>
+where
+    F: Factory<T, R, U>,
+    T: FromRequest + 'static,
+    R: Future<Output = U> + 'static,
+    U: Responder + 'static,
+{
+    Extract::new(Handler::new(handler))
+        .new_service(())
+        .await
+        .unwrap()
+}
@@ -0,0 +1,370 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll};
+use std::{fmt, thread};
+
+use actori_threadpool::CpuFuture;
+use futures::channel::oneshot;
+
+use crate::error::BlockingError;
+
+/// Priority class of a blocking task.
+///
+/// `BlockingPool` always picks queued task with the highest priority,
+/// tasks of the same priority are executed in submission order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background work, executed when there are no other tasks
+    Low,
+    /// Default priority
+    Normal,
+    /// Latency sensitive work
+    High,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Normal
+    }
+}
+
+/// Handle of a blocking task, resolves to result of the function execution.
+///
+/// Dropping or canceling the handle before the task starts removes the task
+/// from execution, so work that nobody waits for does not occupy the pool.
+/// Task that is already running completes, but its result is discarded.
+#[must_use = "futures do nothing unless polled"]
+pub struct BlockingHandle<I, E> {
+    state: HandleState<I, E>,
+}
+
+enum HandleState<I, E> {
+    Shared(CpuFuture<I, E>),
+    Pool(oneshot::Receiver<Result<I, E>>),
+    Canceled,
+}
+
+impl<I, E> BlockingHandle<I, E> {
+    pub(crate) fn shared<F>(f: F) -> Self
+    where
+        F: FnOnce() -> Result<I, E> + Send + 'static,
+        I: Send + 'static,
+        E: Send + fmt::Debug + 'static,
+    {
+        BlockingHandle {
+            state: HandleState::Shared(actori_threadpool::run(f)),
+        }
+    }
+
+    /// Cancel the task.
+    ///
+    /// Task that has not started yet is never executed, handle resolves
+    /// to `BlockingError::Canceled`.
+    pub fn cancel(&mut self) {
+        match self.state {
+            HandleState::Pool(ref mut rx) => rx.close(),
+            HandleState::Shared(_) => self.state = HandleState::Canceled,
+            HandleState::Canceled => (),
+        }
+    }
+}
+
+impl<I, E: fmt::Debug> Future for BlockingHandle<I, E> {
+    type Output = Result<I, BlockingError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.get_mut().state {
+            HandleState::Shared(ref mut fut) => Pin::new(fut).poll(cx),
+            HandleState::Pool(ref mut rx) => match Pin::new(rx).poll(cx) {
+                Poll::Ready(Ok(res)) => Poll::Ready(res.map_err(BlockingError::Error)),
+                Poll::Ready(Err(_)) => Poll::Ready(Err(BlockingError::Canceled)),
+                Poll::Pending => Poll::Pending,
+            },
+            HandleState::Canceled => Poll::Ready(Err(BlockingError::Canceled)),
+        }
+    }
+}
+
+/// Dedicated thread pool for blocking tasks with priority classes.
+///
+/// `web::block()` uses a thread pool shared by the whole process, long
+/// running tasks could delay everything else. Dedicated pool isolates a
+/// class of work, for example expensive reports, and allows to prioritize
+/// tasks within the pool. Pool could be cloned, threads are stopped after
+/// last clone is dropped and queued tasks are completed.
+///
+/// ```rust
+/// use actori_web::{web, App, HttpResponse};
+///
+/// async fn report(pool: web::Data<web::BlockingPool>) -> HttpResponse {
+///     let res = pool
+///         .spawn(web::Priority::Low, || Ok::<_, ()>("report"))
+///         .await;
+///     match res {
+///         Ok(body) => HttpResponse::Ok().body(body),
+///         Err(_) => HttpResponse::InternalServerError().finish(),
+///     }
+/// }
+///
+/// fn main() {
+///     let pool = web::BlockingPool::new(2);
+///     let app = App::new()
+///         .data(pool)
+///         .route("/report", web::get().to(report));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BlockingPool {
+    inner: Arc<PoolHandle>,
+}
+
+struct PoolHandle(Arc<PoolShared>);
+
+struct PoolShared {
+    queue: Mutex<PoolQueue>,
+    cond: Condvar,
+}
+
+struct PoolQueue {
+    tasks: BinaryHeap<Task>,
+    seq: u64,
+    shutdown: bool,
+}
+
+struct Task {
+    priority: Priority,
+    seq: u64,
+    job: Box<dyn FnOnce() + Send>,
+}
+
+impl PartialEq for Task {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for Task {}
+
+impl PartialOrd for Task {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Task {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // higher priority first, then earlier submitted task
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl BlockingPool {
+    /// Start pool with specified number of threads.
+    ///
+    /// Panics if `threads` is zero.
+    pub fn new(threads: usize) -> Self {
+        assert!(threads > 0, "BlockingPool requires at least one thread");
+
+        let shared = Arc::new(PoolShared {
+            queue: Mutex::new(PoolQueue {
+                tasks: BinaryHeap::new(),
+                seq: 0,
+                shutdown: false,
+            }),
+            cond: Condvar::new(),
+        });
+
+        for idx in 0..threads {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("actori-web-blocking-{}", idx))
+                .spawn(move || shared.run())
+                .expect("Can not spawn blocking pool thread");
+        }
+
+        BlockingPool {
+            inner: Arc::new(PoolHandle(shared)),
+        }
+    }
+
+    /// Execute blocking function on the pool with specified priority,
+    /// returns handle that resolves to result of the function execution.
+    pub fn spawn<F, I, E>(&self, priority: Priority, f: F) -> BlockingHandle<I, E>
+    where
+        F: FnOnce() -> Result<I, E> + Send + 'static,
+        I: Send + 'static,
+        E: Send + fmt::Debug + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = Box::new(move || {
+            if !tx.is_canceled() {
+                let _ = tx.send(f());
+            }
+        });
+
+        let shared = &self.inner.0;
+        let mut queue = shared.queue.lock().unwrap();
+        queue.seq += 1;
+        let seq = queue.seq;
+        queue.tasks.push(Task { priority, seq, job });
+        drop(queue);
+        shared.cond.notify_one();
+
+        BlockingHandle {
+            state: HandleState::Pool(rx),
+        }
+    }
+
+    /// Number of tasks waiting for execution.
+    pub fn queued(&self) -> usize {
+        self.inner.0.queue.lock().unwrap().tasks.len()
+    }
+}
+
+impl PoolShared {
+    fn run(&self) {
+        loop {
+            let task = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    if let Some(task) = queue.tasks.pop() {
+                        break task;
+                    }
+                    if queue.shutdown {
+                        return;
+                    }
+                    queue = self.cond.wait(queue).unwrap();
+                }
+            };
+
+            // panic drops result sender, handle resolves to `Canceled`
+            let _ = panic::catch_unwind(AssertUnwindSafe(task.job));
+        }
+    }
+}
+
+impl Drop for PoolHandle {
+    fn drop(&mut self) {
+        self.0.queue.lock().unwrap().shutdown = true;
+        self.0.cond.notify_all();
+    }
+}
+
+impl fmt::Debug for BlockingPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingPool")
+            .field("queued", &self.queued())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[actori_rt::test]
+    async fn test_pool_priority() {
+        let pool = BlockingPool::new(1);
+
+        // occupy the only thread
+        let (start_tx, start_rx) = mpsc::channel::<()>();
+        let (block_tx, block_rx) = mpsc::channel::<()>();
+        let first = pool.spawn(Priority::Normal, move || {
+            start_tx.send(()).unwrap();
+            block_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            Ok::<_, ()>(0)
+        });
+        start_rx.recv().unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        for (priority, id) in &[
+            (Priority::Low, 1),
+            (Priority::High, 2),
+            (Priority::Normal, 3),
+            (Priority::High, 4),
+        ] {
+            let order = order.clone();
+            let id = *id;
+            handles.push(pool.spawn(*priority, move || {
+                order.lock().unwrap().push(id);
+                Ok::<_, ()>(id)
+            }));
+        }
+        assert_eq!(pool.queued(), 4);
+
+        block_tx.send(()).unwrap();
+        assert_eq!(first.await.unwrap(), 0);
+        for (handle, id) in handles.into_iter().zip(1..) {
+            assert_eq!(handle.await.unwrap(), id);
+        }
+        assert_eq!(*order.lock().unwrap(), vec![2, 4, 3, 1]);
+    }
+
+    #[actori_rt::test]
+    async fn test_pool_cancel() {
+        let pool = BlockingPool::new(1);
+
+        let (start_tx, start_rx) = mpsc::channel::<()>();
+        let (block_tx, block_rx) = mpsc::channel::<()>();
+        let first = pool.spawn(Priority::Normal, move || {
+            start_tx.send(()).unwrap();
+            block_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            Ok::<_, ()>(())
+        });
+        start_rx.recv().unwrap();
+
+        let executed = Arc::new(Mutex::new(Vec::new()));
+        let ex = executed.clone();
+        let mut canceled = pool.spawn(Priority::High, move || {
+            ex.lock().unwrap().push(1);
+            Ok::<_, ()>(())
+        });
+        let ex = executed.clone();
+        let dropped = pool.spawn(Priority::High, move || {
+            ex.lock().unwrap().push(2);
+            Ok::<_, ()>(())
+        });
+        let ex = executed.clone();
+        let last = pool.spawn(Priority::Low, move || {
+            ex.lock().unwrap().push(3);
+            Ok::<_, ()>(())
+        });
+
+        canceled.cancel();
+        drop(dropped);
+        block_tx.send(()).unwrap();
+
+        first.await.unwrap();
+        last.await.unwrap();
+        match canceled.await {
+            Err(BlockingError::Canceled) => (),
+            _ => panic!(),
+        }
+        assert_eq!(*executed.lock().unwrap(), vec![3]);
+    }
+
+    #[actori_rt::test]
+    async fn test_pool_panic() {
+        let pool = BlockingPool::new(1);
+        let res = pool.spawn(Priority::Normal, || -> Result<(), ()> { panic!() });
+        match res.await {
+            Err(BlockingError::Canceled) => (),
+            _ => panic!(),
+        }
+        let res = pool.spawn(Priority::Normal, || Err::<(), _>("error"));
+        match res.await {
+            Err(BlockingError::Error("error")) => (),
+            _ => panic!(),
+        }
+    }
+}
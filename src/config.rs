@@ -1,15 +1,20 @@
+use std::any::TypeId;
+use std::cell::RefCell;
 use std::net::SocketAddr;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use actori_http::Extensions;
 use actori_router::ResourceDef;
 use actori_service::{boxed, IntoServiceFactory, ServiceFactory};
+use url::Url;
 
-use crate::data::{Data, DataFactory};
+use crate::data::{AppDataItem, Data, DataFactory, DataRequirement};
 use crate::error::Error;
 use crate::guard::Guard;
+use crate::middleware::TrustedProxies;
 use crate::resource::Resource;
-use crate::rmap::ResourceMap;
+use crate::rmap::{ResourceMap, ResourceMeta};
 use crate::route::Route;
 use crate::service::{
     AppServiceFactory, HttpServiceFactory, ServiceFactoryWrapper, ServiceRequest,
@@ -17,6 +22,7 @@ use crate::service::{
 };
 
 type Guards = Vec<Box<dyn Guard>>;
+pub(crate) type SharedData = Arc<dyn DataFactory + Send + Sync>;
 type HttpNewService =
     boxed::BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
 
@@ -30,8 +36,11 @@ pub struct AppService {
         HttpNewService,
         Option<Guards>,
         Option<Rc<ResourceMap>>,
+        ResourceMeta,
     )>,
     service_data: Rc<Vec<Box<dyn DataFactory>>>,
+    data_types: Rc<Vec<TypeId>>,
+    missing_data: Rc<RefCell<Vec<String>>>,
 }
 
 impl AppService {
@@ -40,13 +49,16 @@ impl AppService {
         config: AppConfig,
         default: Rc<HttpNewService>,
         service_data: Rc<Vec<Box<dyn DataFactory>>>,
+        data_types: Rc<Vec<TypeId>>,
     ) -> Self {
         AppService {
             config,
             default,
             service_data,
+            data_types,
             root: true,
             services: Vec::new(),
+            missing_data: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
@@ -64,6 +76,7 @@ impl AppService {
             HttpNewService,
             Option<Guards>,
             Option<Rc<ResourceMap>>,
+            ResourceMeta,
         )>,
     ) {
         (self.config, self.services)
@@ -76,9 +89,51 @@ impl AppService {
             services: Vec::new(),
             root: false,
             service_data: self.service_data.clone(),
+            data_types: self.data_types.clone(),
+            missing_data: self.missing_data.clone(),
         }
     }
 
+    /// Set data types visible to nested services of a scope
+    pub(crate) fn set_scope_data(&mut self, data: Option<&Extensions>, inherit: bool) {
+        let mut types = if inherit {
+            self.data_types.as_ref().clone()
+        } else {
+            Vec::new()
+        };
+        if let Some(data) = data {
+            types.extend(data.type_ids());
+        }
+        self.data_types = Rc::new(types);
+    }
+
+    /// Check that data required by a scope or a resource is registered
+    /// on the service itself, on the enclosing scopes or on the application
+    pub(crate) fn check_data(
+        &self,
+        requires: &[DataRequirement],
+        data: Option<&Extensions>,
+        path: &str,
+    ) {
+        for req in requires {
+            let found = self.data_types.contains(&req.id)
+                || data
+                    .map(|data| data.type_ids().any(|id| id == req.id))
+                    .unwrap_or(false);
+            if !found {
+                self.missing_data.borrow_mut().push(format!(
+                    "Data type `{}` required by {:?} is not registered",
+                    req.name, path
+                ));
+            }
+        }
+    }
+
+    /// Errors of data requirements check
+    pub(crate) fn missing_data(&self) -> Vec<String> {
+        self.missing_data.borrow().clone()
+    }
+
     /// Service configuration
     pub fn config(&self) -> &AppConfig {
         &self.config
@@ -113,12 +168,35 @@ impl AppService {
                 Error = Error,
                 InitError = (),
             > + 'static,
+    {
+        self.register_resource(rdef, guards, factory, nested, ResourceMeta::default())
+    }
+
+    /// Register http service with resource metadata, allowed http methods
+    /// and descriptions
+    pub(crate) fn register_resource<F, S>(
+        &mut self,
+        rdef: ResourceDef,
+        guards: Option<Vec<Box<dyn Guard>>>,
+        factory: F,
+        nested: Option<Rc<ResourceMap>>,
+        meta: ResourceMeta,
+    ) where
+        F: IntoServiceFactory<S>,
+        S: ServiceFactory<
+                Config = (),
+                Request = ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+                InitError = (),
+            > + 'static,
     {
         self.services.push((
             rdef,
             boxed::factory(factory.into_factory()),
             guards,
             nested,
+            meta,
         ));
     }
 }
@@ -126,15 +204,73 @@ impl AppService {
 #[derive(Clone)]
 pub struct AppConfig(Rc<AppConfigInner>);
 
+#[derive(Clone)]
 struct AppConfigInner {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    public_url: Option<Url>,
+    trusted_proxies: Option<TrustedProxies>,
+    shared_data: Vec<SharedData>,
 }
 
 impl AppConfig {
     pub(crate) fn new(secure: bool, addr: SocketAddr, host: String) -> Self {
-        AppConfig(Rc::new(AppConfigInner { secure, addr, host }))
+        AppConfig(Rc::new(AppConfigInner {
+            secure,
+            addr,
+            host,
+            public_url: None,
+            trusted_proxies: None,
+            shared_data: Vec::new(),
+        }))
+    }
+
+    /// Set public base url of the application.
+    ///
+    /// If public url is set, it is used for url generation instead of
+    /// connection information, so application deployed behind a proxy
+    /// generates externally correct urls without trusting forwarded headers.
+    /// Scheme, host and port of the generated urls are taken from the public url,
+    /// path of the public url is used as a prefix.
+    pub fn set_public_url(&mut self, url: Url) {
+        Rc::make_mut(&mut self.0).public_url = Some(url);
+    }
+
+    /// Public base url of the application, if configured.
+    ///
+    /// Check [`set_public_url()`](#method.set_public_url) for more information.
+    pub fn public_url(&self) -> Option<&Url> {
+        self.0.public_url.as_ref()
+    }
+
+    /// Set trusted proxies of the application.
+    ///
+    /// If trusted proxies are set, `ConnectionInfo` takes scheme, host and
+    /// client address from `Forwarded` and `X-Forwarded-*` headers only if
+    /// peer address belongs to one of the trusted networks, the same way
+    /// as `TrustedProxies` middleware does. Otherwise forwarded headers
+    /// are trusted unconditionally.
+    pub fn set_trusted_proxies(&mut self, proxies: TrustedProxies) {
+        Rc::make_mut(&mut self.0).trusted_proxies = Some(proxies);
+    }
+
+    /// Trusted proxies of the application, if configured.
+    ///
+    /// Check [`set_trusted_proxies()`](#method.set_trusted_proxies) for more
+    /// information.
+    pub fn trusted_proxies(&self) -> Option<&TrustedProxies> {
+        self.0.trusted_proxies.as_ref()
+    }
+
+    /// Add data shared by all application instances
+    pub(crate) fn add_shared_data(&mut self, data: SharedData) {
+        Rc::make_mut(&mut self.0).shared_data.push(data);
+    }
+
+    /// Data shared by all application instances, see `HttpServer::shared_data()`
+    pub(crate) fn shared_data(&self) -> &[SharedData] {
+        &self.0.shared_data
     }
 
     /// Server host name.
@@ -197,6 +333,16 @@ impl ServiceConfig {
         self
     }
 
+    /// Set arbitrary data item, for example extractor configuration.
+    ///
+    /// This is same as `App::app_data()` method. Item is registered on
+    /// the application or the scope that runs this configuration, it does
+    /// not override an item of the same type registered there directly.
+    pub fn app_data<U: Clone + 'static>(&mut self, ext: U) -> &mut Self {
+        self.data.push(Box::new(AppDataItem(ext)));
+        self
+    }
+
     /// Configure route for a specific path.
     ///
     /// This is same as `App::route()` method.
@@ -265,6 +411,24 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actori_rt::test]
+    async fn test_app_data() {
+        let cfg = |cfg: &mut ServiceConfig| {
+            cfg.app_data(10usize);
+        };
+
+        let mut srv = init_service(App::new().configure(cfg).service(
+            web::resource("/").to(|req: HttpRequest| {
+                assert_eq!(*req.app_data::<usize>().unwrap(), 10);
+                HttpResponse::Ok()
+            }),
+        ))
+        .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     // #[actori_rt::test]
     // async fn test_data_factory() {
     //     let cfg = |cfg: &mut ServiceConfig| {
@@ -1,9 +1,10 @@
+use std::any::{type_name, TypeId};
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use actori_http::error::{Error, ErrorInternalServerError};
 use actori_http::Extensions;
-use futures::future::{err, ok, Ready};
+use futures::future::{err, ok, LocalBoxFuture, Ready};
 
 use crate::dev::Payload;
 use crate::extract::FromRequest;
@@ -12,6 +13,25 @@ use crate::request::HttpRequest;
 /// Application data factory
 pub(crate) trait DataFactory {
     fn create(&self, extensions: &mut Extensions) -> bool;
+
+    /// Type of the item stored in extensions
+    fn data_type(&self) -> TypeId;
+}
+
+/// Data type required by a scope or a resource
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DataRequirement {
+    pub(crate) id: TypeId,
+    pub(crate) name: &'static str,
+}
+
+impl DataRequirement {
+    pub(crate) fn of<T: 'static>() -> Self {
+        DataRequirement {
+            id: TypeId::of::<T>(),
+            name: type_name::<T>(),
+        }
+    }
 }
 
 /// Application data.
@@ -31,7 +51,8 @@ pub(crate) trait DataFactory {
 /// threads, a shareable object should be used, e.g. `Send + Sync`. Application
 /// data does not need to be `Send` or `Sync`. Internally `Data` type
 /// uses `Arc`. if your data implements `Send` + `Sync` traits you can
-/// use `web::Data::new()` and avoid double `Arc`.
+/// use `web::Data::new()` and avoid double `Arc`. To construct a single
+/// instance for all workers use `HttpServer::shared_data()`.
 ///
 /// If route data is not set for a handler, using `Data<T>` extractor would
 /// cause *Internal Server Error* response.
@@ -75,6 +96,14 @@ impl<T> Data<T> {
         Data(Arc::new(state))
     }
 
+    /// Create `Data` instance from an existing `Arc`.
+    ///
+    /// Data is not copied, so all application instances that are
+    /// configured with a clone of the same `Arc` share it.
+    pub fn from_shared(state: Arc<T>) -> Data<T> {
+        Data(state)
+    }
+
     /// Get reference to inner app data.
     pub fn get_ref(&self) -> &T {
         self.0.as_ref()
@@ -122,6 +151,34 @@ impl<T: 'static> FromRequest for Data<T> {
     }
 }
 
+/// Asynchronous cleanup of the application data.
+///
+/// Cleanup runs on the worker when the server shuts down gracefully, it is
+/// registered for the data type with `App::data_cleanup()`. It could be used
+/// to flush buffers or to close pooled connections.
+pub trait DataCleanup: 'static {
+    /// Release resources held by the data.
+    fn cleanup(&self) -> LocalBoxFuture<'_, ()>;
+}
+
+/// Arbitrary data item registered with `ServiceConfig::app_data()`
+pub(crate) struct AppDataItem<T>(pub(crate) T);
+
+impl<T: Clone + 'static> DataFactory for AppDataItem<T> {
+    fn create(&self, extensions: &mut Extensions) -> bool {
+        if !extensions.contains::<T>() {
+            extensions.insert(self.0.clone());
+            true
+        } else {
+            false
+        }
+    }
+
+    fn data_type(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+}
+
 impl<T: 'static> DataFactory for Data<T> {
     fn create(&self, extensions: &mut Extensions) -> bool {
         if !extensions.contains::<Data<T>>() {
@@ -131,6 +188,157 @@ impl<T: 'static> DataFactory for Data<T> {
             false
         }
     }
+
+    fn data_type(&self) -> TypeId {
+        TypeId::of::<Data<T>>()
+    }
+}
+
+/// Application data with a separate instance for each worker.
+///
+/// `ShardedData` creates one `T` per application instance, i.e. one per
+/// worker thread, with the provided factory. Handlers access the shard of
+/// the worker that serves the request, so no cross-thread locking is
+/// required. All shards could be inspected with `ShardedData::shards()`,
+/// for example to collect metrics.
+///
+/// Sharded data is registered with `App::sharded_data()` method and
+/// could be accessed by using `ShardedData<T>` extractor. Shards are kept
+/// for the lifetime of the `ShardedData` instance.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use actori_web::{web, App, HttpResponse, HttpServer};
+///
+/// async fn index(hits: web::ShardedData<AtomicUsize>) -> HttpResponse {
+///     hits.fetch_add(1, Ordering::Relaxed);
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// async fn metrics(hits: web::ShardedData<AtomicUsize>) -> HttpResponse {
+///     let total = hits.aggregate(0, |sum, hits| sum + hits.load(Ordering::Relaxed));
+///     HttpResponse::Ok().body(format!("hits: {}", total))
+/// }
+///
+/// fn main() {
+///     let hits = web::ShardedData::new(|| AtomicUsize::new(0));
+///
+///     HttpServer::new(move || {
+///         App::new()
+///             .sharded_data(hits.clone())
+///             .route("/", web::get().to(index))
+///             .route("/metrics", web::get().to(metrics))
+///     });
+/// }
+/// ```
+pub struct ShardedData<T> {
+    shards: Arc<Shards<T>>,
+    local: Option<Arc<T>>,
+}
+
+struct Shards<T> {
+    factory: Box<dyn Fn() -> T + Send + Sync>,
+    items: Mutex<Vec<Arc<T>>>,
+}
+
+impl<T: Send + Sync + 'static> ShardedData<T> {
+    /// Create new `ShardedData` instance with the shard factory.
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        ShardedData {
+            shards: Arc::new(Shards {
+                factory: Box::new(factory),
+                items: Mutex::new(Vec::new()),
+            }),
+            local: None,
+        }
+    }
+
+    /// Get reference to the shard of the current worker.
+    ///
+    /// Panics if instance is not obtained with `ShardedData<T>` extractor,
+    /// a shard is not created until application instance is started.
+    pub fn get_ref(&self) -> &T {
+        self.local
+            .as_ref()
+            .expect("ShardedData shard is available within a worker only")
+    }
+
+    /// Get shards of all workers.
+    pub fn shards(&self) -> Vec<Arc<T>> {
+        self.shards.items.lock().unwrap().clone()
+    }
+
+    /// Fold shards of all workers into a single value.
+    pub fn aggregate<R, F>(&self, init: R, mut f: F) -> R
+    where
+        F: FnMut(R, &T) -> R,
+    {
+        self.shards()
+            .iter()
+            .fold(init, |acc, shard| f(acc, shard.as_ref()))
+    }
+}
+
+impl<T: Send + Sync + 'static> Deref for ShardedData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get_ref()
+    }
+}
+
+impl<T> Clone for ShardedData<T> {
+    fn clone(&self) -> ShardedData<T> {
+        ShardedData {
+            shards: self.shards.clone(),
+            local: self.local.clone(),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> FromRequest for ShardedData<T> {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(st) = req.app_data::<ShardedData<T>>() {
+            ok(st.clone())
+        } else {
+            log::debug!(
+                "Failed to construct App-level ShardedData extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Sharded data is not configured, to configure use App::sharded_data()",
+            ))
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> DataFactory for ShardedData<T> {
+    fn create(&self, extensions: &mut Extensions) -> bool {
+        if !extensions.contains::<ShardedData<T>>() {
+            let shard = Arc::new((self.shards.factory)());
+            self.shards.items.lock().unwrap().push(shard.clone());
+            extensions.insert(ShardedData {
+                shards: self.shards.clone(),
+                local: Some(shard),
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    fn data_type(&self) -> TypeId {
+        TypeId::of::<ShardedData<T>>()
+    }
 }
 
 #[cfg(test)]
@@ -278,4 +486,90 @@ mod tests {
 
         assert_eq!(num.load(Ordering::SeqCst), 0);
     }
+
+    #[actori_rt::test]
+    async fn test_sharded_data() {
+        let hits = ShardedData::new(|| AtomicUsize::new(0));
+
+        // application instance per worker
+        let app = |hits: ShardedData<AtomicUsize>| {
+            init_service(App::new().sharded_data(hits).service(web::resource("/").to(
+                |hits: ShardedData<AtomicUsize>| {
+                    let local = hits.fetch_add(1, Ordering::SeqCst) + 1;
+                    let total =
+                        hits.aggregate(0, |sum, hits| sum + hits.load(Ordering::SeqCst));
+                    HttpResponse::Ok().body(format!("{} {}", local, total))
+                },
+            )))
+        };
+        let mut srv1 = app(hits.clone()).await;
+        let mut srv2 = app(hits.clone()).await;
+        assert_eq!(hits.shards().len(), 2);
+
+        let req = TestRequest::default().to_request();
+        let body = test::read_response(&mut srv1, req).await;
+        assert_eq!(body, "1 1");
+
+        let req = TestRequest::default().to_request();
+        let body = test::read_response(&mut srv1, req).await;
+        assert_eq!(body, "2 2");
+
+        let req = TestRequest::default().to_request();
+        let body = test::read_response(&mut srv2, req).await;
+        assert_eq!(body, "1 3");
+
+        let mut srv =
+            init_service(App::new().service(
+                web::resource("/").to(|_: ShardedData<usize>| HttpResponse::Ok()),
+            ))
+            .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[actori_rt::test]
+    async fn test_shared_data() {
+        use actori_service::{IntoServiceFactory, ServiceFactory};
+
+        use crate::config::AppConfig;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let mut cfg = AppConfig::default();
+        cfg.add_shared_data(Arc::new(Data::from_shared(hits.clone())));
+
+        // application instance per worker
+        let app = |cfg: AppConfig| {
+            App::new()
+                .service(web::resource("/").requires::<Data<AtomicUsize>>().to(
+                    |hits: web::Data<AtomicUsize>| {
+                        let hits = hits.fetch_add(1, Ordering::SeqCst) + 1;
+                        HttpResponse::Ok().body(format!("{}", hits))
+                    },
+                ))
+                .into_factory()
+                .new_service(cfg)
+        };
+        let mut srv1 = app(cfg.clone()).await.unwrap();
+        let mut srv2 = app(cfg.clone()).await.unwrap();
+
+        let req = TestRequest::default().to_request();
+        assert_eq!(test::read_response(&mut srv1, req).await, "1");
+        let req = TestRequest::default().to_request();
+        assert_eq!(test::read_response(&mut srv2, req).await, "2");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // application data takes precedence
+        let mut srv = App::new()
+            .data(AtomicUsize::new(10))
+            .service(web::resource("/").to(|hits: web::Data<AtomicUsize>| {
+                HttpResponse::Ok().body(format!("{}", hits.load(Ordering::SeqCst)))
+            }))
+            .into_factory()
+            .new_service(cfg)
+            .await
+            .unwrap();
+        let req = TestRequest::default().to_request();
+        assert_eq!(test::read_response(&mut srv, req).await, "10");
+    }
 }
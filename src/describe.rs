@@ -0,0 +1,124 @@
+//! Documentation metadata of resources and routes.
+//!
+//! Descriptions attached with `Resource::describe()` and `Route::describe()`
+//! are stored in the `ResourceMap` and could be used by tooling, i.e.
+//! OpenAPI generators or admin interfaces.
+//!
+//! ```rust
+//! use actori_web::describe::Description;
+//! use actori_web::{web, App, HttpRequest, HttpResponse};
+//!
+//! async fn resources(req: HttpRequest) -> HttpResponse {
+//!     let summaries: Vec<_> = req
+//!         .resource_map()
+//!         .resources()
+//!         .into_iter()
+//!         .filter_map(|res| res.description)
+//!         .collect();
+//!     HttpResponse::Ok().json(summaries)
+//! }
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .service(
+//!             web::resource("/users/{id}")
+//!                 .describe(Description::new().summary("User").tag("users"))
+//!                 .route(
+//!                     web::get()
+//!                         .describe(Description::new().summary("Get user"))
+//!                         .to(|| HttpResponse::Ok()),
+//!                 ),
+//!         )
+//!         .route("/resources", web::get().to(resources));
+//! }
+//! ```
+use actori_http::http::Method;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::sitemap::SitemapEntry;
+
+/// Structured description of a resource or a route.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Description {
+    summary: Option<String>,
+    tags: Vec<String>,
+    request_schema: Option<Value>,
+    response_schema: Option<Value>,
+}
+
+impl Description {
+    /// Create empty description.
+    pub fn new() -> Self {
+        Description::default()
+    }
+
+    /// Set short summary.
+    pub fn summary(mut self, summary: &str) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Add a tag, tags are used for grouping.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Set schema of the request payload.
+    pub fn request_schema(mut self, schema: Value) -> Self {
+        self.request_schema = Some(schema);
+        self
+    }
+
+    /// Set schema of the response payload.
+    pub fn response_schema(mut self, schema: Value) -> Self {
+        self.response_schema = Some(schema);
+        self
+    }
+
+    /// Get summary.
+    pub fn get_summary(&self) -> Option<&str> {
+        self.summary.as_ref().map(|s| s.as_str())
+    }
+
+    /// Get tags.
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Get schema of the request payload.
+    pub fn get_request_schema(&self) -> Option<&Value> {
+        self.request_schema.as_ref()
+    }
+
+    /// Get schema of the response payload.
+    pub fn get_response_schema(&self) -> Option<&Value> {
+        self.response_schema.as_ref()
+    }
+}
+
+/// Resource registered in the `ResourceMap`.
+#[derive(Clone, Debug)]
+pub struct ResourceDescription {
+    /// Full pattern, patterns of enclosing scopes are included
+    pub pattern: String,
+    /// Resource name
+    pub name: Option<String>,
+    /// Resource description
+    pub description: Option<Description>,
+    /// Routes of the resource
+    pub routes: Vec<RouteDescription>,
+    /// Sitemap metadata attached with `Resource::sitemap()`
+    pub sitemap: Option<SitemapEntry>,
+}
+
+/// Route of the resource.
+#[derive(Clone, Debug)]
+pub struct RouteDescription {
+    /// Methods registered with `Route::method()`, empty if route
+    /// matches any method
+    pub methods: Vec<Method>,
+    /// Route description
+    pub description: Option<Description>,
+}
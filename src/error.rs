@@ -1,11 +1,15 @@
 //! Error and Result module
+use std::rc::Rc;
+use std::{fmt, io};
+
 pub use actori_http::error::*;
 use derive_more::{Display, From};
+use serde::Serialize;
 use serde_json::error::Error as JsonError;
 use url::ParseError as UrlParseError;
 
-use crate::http::StatusCode;
-use crate::HttpResponse;
+use crate::http::{Method, StatusCode};
+use crate::{HttpRequest, HttpResponse};
 
 /// Errors which can occur when attempting to generate resource uri.
 #[derive(Debug, PartialEq, Display, From)]
@@ -55,7 +59,10 @@ pub enum UrlencodedError {
 impl ResponseError for UrlencodedError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            UrlencodedError::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            UrlencodedError::Overflow { .. }
+            | UrlencodedError::Payload(PayloadError::Overflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
             UrlencodedError::UnknownLength => StatusCode::LENGTH_REQUIRED,
             _ => StatusCode::BAD_REQUEST,
         }
@@ -83,7 +90,8 @@ pub enum JsonPayloadError {
 impl ResponseError for JsonPayloadError {
     fn error_response(&self) -> HttpResponse {
         match *self {
-            JsonPayloadError::Overflow => {
+            JsonPayloadError::Overflow
+            | JsonPayloadError::Payload(PayloadError::Overflow) => {
                 HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE)
             }
             _ => HttpResponse::new(StatusCode::BAD_REQUEST),
@@ -91,6 +99,41 @@ impl ResponseError for JsonPayloadError {
     }
 }
 
+/// A set of errors that can occur during parsing xml payloads
+#[cfg(feature = "xml")]
+#[derive(Debug, Display, From)]
+pub enum XmlPayloadError {
+    /// Payload size is bigger than allowed. (default: 32kB)
+    #[display(fmt = "Xml payload size is bigger than allowed")]
+    Overflow,
+    /// Content type error
+    #[display(fmt = "Content type error")]
+    ContentType,
+    /// Payload could not be decoded with the content type charset
+    #[display(fmt = "Xml payload does not match the content type charset")]
+    Encoding,
+    /// Deserialize error
+    #[display(fmt = "Xml deserialize error: {}", _0)]
+    Deserialize(serde_xml_rs::Error),
+    /// Payload error
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+/// Return `BadRequest` for `XmlPayloadError`
+#[cfg(feature = "xml")]
+impl ResponseError for XmlPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            XmlPayloadError::Overflow
+            | XmlPayloadError::Payload(PayloadError::Overflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
 /// A set of errors that can occur during parsing request paths
 #[derive(Debug, Display, From)]
 pub enum PathError {
@@ -112,12 +155,42 @@ pub enum QueryPayloadError {
     /// Deserialize error
     #[display(fmt = "Query deserialize error: {}", _0)]
     Deserialize(serde::de::value::Error),
+    /// Deserialize error of nested query, see `QueryMode::Nested`
+    #[display(fmt = "Query deserialize error: {}", _0)]
+    NestedDeserialize(serde_qs::Error),
+    /// Deserialize error of the query parameter, parameter is missing or
+    /// its value is not valid
+    #[display(fmt = "Query parameter `{}` deserialize error: {}", name, error)]
+    Param {
+        name: String,
+        error: serde::de::value::Error,
+    },
+    /// Query string is longer than allowed, see `QueryConfig::max_length()`
+    #[display(
+        fmt = "Query string length ({} bytes) is bigger than allowed ({} bytes)",
+        length,
+        limit
+    )]
+    TooLong { length: usize, limit: usize },
+}
+
+impl QueryPayloadError {
+    /// Name of the query parameter that failed deserialization
+    pub fn param(&self) -> Option<&str> {
+        match self {
+            QueryPayloadError::Param { ref name, .. } => Some(name),
+            _ => None,
+        }
+    }
 }
 
 /// Return `BadRequest` for `QueryPayloadError`
 impl ResponseError for QueryPayloadError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match *self {
+            QueryPayloadError::TooLong { .. } => StatusCode::URI_TOO_LONG,
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
@@ -149,6 +222,257 @@ impl ResponseError for ReadlinesError {
     }
 }
 
+/// A set of errors that can occur during parsing multipart streams
+#[derive(Debug, Display, From)]
+pub enum MultipartError {
+    /// Content-Type header is not found
+    #[display(fmt = "No Content-type header found")]
+    NoContentType,
+    /// Can not parse Content-Type header
+    #[display(fmt = "Can not parse Content-Type header")]
+    ParseContentType,
+    /// Multipart boundary is not found
+    #[display(fmt = "Multipart boundary is not found")]
+    Boundary,
+    /// Nested multipart is not supported
+    #[display(fmt = "Nested multipart is not supported")]
+    Nested,
+    /// Multipart stream is incomplete
+    #[display(fmt = "Multipart stream is incomplete")]
+    Incomplete,
+    /// Error during field parsing
+    #[display(fmt = "{}", _0)]
+    Parse(ParseError),
+    /// Payload error
+    #[display(fmt = "{}", _0)]
+    Payload(PayloadError),
+    /// Not consumed
+    #[display(fmt = "Multipart stream is not consumed")]
+    NotConsumed,
+    /// Form field is larger than allowed size
+    #[display(fmt = "Field \"{}\" is larger than allowed size", _0)]
+    #[from(ignore)]
+    FieldOverflow(String),
+    /// Can not parse form field
+    #[display(fmt = "Can not parse field \"{}\"", _0)]
+    #[from(ignore)]
+    FieldParse(String),
+    /// Required form field is not found
+    #[display(fmt = "Field \"{}\" is not found", _0)]
+    #[from(ignore)]
+    MissingField(String),
+    /// Form field is repeated
+    #[display(fmt = "Field \"{}\" is repeated", _0)]
+    #[from(ignore)]
+    DuplicateField(String),
+    /// Form field is not expected
+    #[display(fmt = "Unknown field \"{}\"", _0)]
+    #[from(ignore)]
+    UnknownField(String),
+    /// Io error
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
+}
+
+impl From<BlockingError<io::Error>> for MultipartError {
+    fn from(err: BlockingError<io::Error>) -> Self {
+        match err {
+            BlockingError::Error(e) => MultipartError::Io(e),
+            BlockingError::Canceled => MultipartError::Io(io::Error::new(
+                io::ErrorKind::Other,
+                "Thread pool is gone",
+            )),
+        }
+    }
+}
+
+/// Return `BadRequest` for `MultipartError`
+impl ResponseError for MultipartError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            MultipartError::FieldOverflow(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            MultipartError::Payload(PayloadError::Overflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            MultipartError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Field errors of the input that failed validation, see `web::Validated`.
+///
+/// Error is rendered as *422 Unprocessable Entity* response with json body
+/// `{"errors": [{"field": "name", "message": "must not be empty"}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+/// Validation error of a single field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// Name of the field
+    pub field: String,
+    /// Description of the violation
+    pub message: String,
+}
+
+impl ValidationErrors {
+    /// Create empty set of errors
+    pub fn new() -> Self {
+        ValidationErrors::default()
+    }
+
+    /// Add error of the field
+    pub fn add<F, M>(&mut self, field: F, message: M)
+    where
+        F: Into<String>,
+        M: Into<String>,
+    {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Check if there are no errors
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Errors of the fields
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// `Ok(())` if there are no errors
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Validation failed")?;
+        for (idx, err) in self.errors.iter().enumerate() {
+            let sep = if idx == 0 { ": " } else { ", " };
+            write!(f, "{}{} {}", sep, err.field, err.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Return `UnprocessableEntity` with field errors for `ValidationErrors`
+impl ResponseError for ValidationErrors {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNPROCESSABLE_ENTITY
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::UnprocessableEntity().json(self)
+    }
+}
+
+/// Error observer of an application, see `App::error_observer()`
+pub(crate) type ErrorObserver = Rc<dyn Fn(&ErrorEvent<'_>)>;
+
+/// Request data captured for error observer before request is handled
+pub(crate) struct RequestMeta {
+    method: Method,
+    path: String,
+    request_id: Option<String>,
+}
+
+impl RequestMeta {
+    pub(crate) fn new(req: &HttpRequest) -> Self {
+        RequestMeta {
+            method: req.method().clone(),
+            path: req.path().to_owned(),
+            request_id: req
+                .headers()
+                .get("x-request-id")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned()),
+        }
+    }
+}
+
+/// Error converted to a response, it is passed to error observer.
+pub struct ErrorEvent<'a> {
+    error: &'a Error,
+    status: StatusCode,
+    meta: &'a RequestMeta,
+    req: Option<&'a HttpRequest>,
+}
+
+impl<'a> ErrorEvent<'a> {
+    /// Error that is converted to a response
+    pub fn error(&self) -> &Error {
+        self.error
+    }
+
+    /// Type name of the error, i.e. `actori_web::error::JsonPayloadError`
+    pub fn error_type(&self) -> &'static str {
+        self.error.type_name()
+    }
+
+    /// Response status
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Request method
+    pub fn method(&self) -> &Method {
+        &self.meta.method
+    }
+
+    /// Request path, without mount prefix of the application
+    pub fn path(&self) -> &str {
+        &self.meta.path
+    }
+
+    /// Request that caused the error.
+    ///
+    /// Request is not available for errors returned by middlewares, these
+    /// errors are converted to a response after request is dropped.
+    pub fn request(&self) -> Option<&HttpRequest> {
+        self.req
+    }
+
+    /// Pattern of the matched resource, see `HttpRequest::match_pattern()`
+    pub fn match_pattern(&self) -> Option<String> {
+        self.req.and_then(|req| req.match_pattern())
+    }
+
+    /// Value of `x-request-id` request header
+    pub fn request_id(&self) -> Option<&str> {
+        self.meta.request_id.as_ref().map(|s| s.as_str())
+    }
+}
+
+/// Pass error to error observer
+pub(crate) fn observe(
+    observer: &ErrorObserver,
+    error: &Error,
+    status: StatusCode,
+    meta: &RequestMeta,
+    req: Option<&HttpRequest>,
+) {
+    if !error.is_response() {
+        (**observer)(&ErrorEvent {
+            error,
+            status,
+            meta,
+            req,
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,6 +482,9 @@ mod tests {
         let resp: HttpResponse =
             UrlencodedError::Overflow { size: 0, limit: 0 }.error_response();
         assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse =
+            UrlencodedError::Payload(PayloadError::Overflow).error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
         let resp: HttpResponse = UrlencodedError::UnknownLength.error_response();
         assert_eq!(resp.status(), StatusCode::LENGTH_REQUIRED);
         let resp: HttpResponse = UrlencodedError::ContentType.error_response();
@@ -168,6 +495,9 @@ mod tests {
     fn test_json_payload_error() {
         let resp: HttpResponse = JsonPayloadError::Overflow.error_response();
         assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp: HttpResponse =
+            JsonPayloadError::Payload(PayloadError::Overflow).error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
         let resp: HttpResponse = JsonPayloadError::ContentType.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
@@ -179,6 +509,91 @@ mod tests {
         )
         .error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: HttpResponse = QueryPayloadError::TooLong {
+            length: 100,
+            limit: 10,
+        }
+        .error_response();
+        assert_eq!(resp.status(), StatusCode::URI_TOO_LONG);
+    }
+
+    #[actori_rt::test]
+    async fn test_error_observer() {
+        use std::cell::RefCell;
+
+        use actori_service::Service;
+        use futures::future::{err, Either};
+
+        use crate::test::{init_service, TestRequest};
+        use crate::{web, App};
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let events2 = events.clone();
+
+        let mut srv = init_service(
+            App::new()
+                .error_observer(move |ev: &ErrorEvent<'_>| {
+                    events2.borrow_mut().push((
+                        ev.error_type(),
+                        ev.status(),
+                        ev.match_pattern(),
+                        ev.path().to_owned(),
+                        ev.request_id().map(|s| s.to_owned()),
+                        ev.request().is_some(),
+                    ));
+                })
+                .wrap_fn(|req, srv| {
+                    if req.path() == "/mw" {
+                        Either::Left(err(ErrorForbidden("mw")))
+                    } else {
+                        Either::Right(srv.call(req))
+                    }
+                })
+                .service(
+                    web::resource("/user/{id}")
+                        .to(|| async { Err::<HttpResponse, _>(ErrorBadRequest("bad")) }),
+                )
+                .service(
+                    web::resource("/json").to(|_: web::Json<String>| HttpResponse::Ok()),
+                )
+                .service(web::resource("/early").to(|| async {
+                    Err::<HttpResponse, _>(HttpResponse::Conflict().finish())
+                })),
+        )
+        .await;
+
+        for path in &["/user/1", "/json", "/early", "/mw"] {
+            let req = TestRequest::with_uri(path)
+                .header("x-request-id", "observer-test")
+                .to_request();
+            let _ = srv.call(req).await;
+        }
+
+        let events = events.borrow();
+        assert_eq!(events.len(), 3);
+        assert!(events[0].0.contains("InternalError"));
+        assert_eq!(events[0].1, StatusCode::BAD_REQUEST);
+        assert_eq!(events[0].2, Some("/user/{id}".to_owned()));
+        assert_eq!(events[0].3, "/user/1");
+        assert_eq!(events[0].4, Some("observer-test".to_owned()));
+        assert!(events[0].5);
+        assert_eq!(events[1].0, "actori_web::error::JsonPayloadError");
+        assert_eq!(events[1].1, StatusCode::BAD_REQUEST);
+        assert_eq!(events[1].2, Some("/json".to_owned()));
+        assert_eq!(events[2].1, StatusCode::FORBIDDEN);
+        assert_eq!(events[2].2, None);
+        assert_eq!(events[2].3, "/mw");
+        assert_eq!(events[2].4, Some("observer-test".to_owned()));
+        assert!(!events[2].5);
+    }
+
+    #[test]
+    fn test_multipart_error() {
+        let resp: HttpResponse = MultipartError::Boundary.error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: HttpResponse =
+            MultipartError::Payload(PayloadError::Overflow).error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 
     #[test]
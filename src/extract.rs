@@ -12,6 +12,40 @@ use crate::request::HttpRequest;
 /// Trait implemented by types that can be extracted from request.
 ///
 /// Types that implement this trait can be used with `Route` handlers.
+///
+/// `HttpResponse` could be used as extractor's error type. In this case
+/// extraction is short-circuited and response is sent to the peer as is,
+/// without defining custom error type.
+///
+/// ```rust
+/// use actori_web::{dev, web, App, FromRequest, HttpRequest, HttpResponse};
+/// use futures::future::{err, ok, Ready};
+///
+/// struct ApiKey(String);
+///
+/// impl FromRequest for ApiKey {
+///     type Error = HttpResponse;
+///     type Future = Ready<Result<Self, HttpResponse>>;
+///     type Config = ();
+///
+///     fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+///         match req.headers().get("x-api-key").and_then(|v| v.to_str().ok()) {
+///             Some(key) => ok(ApiKey(key.to_owned())),
+///             None => err(HttpResponse::Unauthorized()
+///                 .insert_header(("www-authenticate", "ApiKey"))
+///                 .finish()),
+///         }
+///     }
+/// }
+///
+/// async fn index(key: ApiKey) -> String {
+///     format!("key: {}", key.0)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(web::resource("/").to(index));
+/// }
+/// ```
 pub trait FromRequest: Sized {
     /// The associated error which can be returned.
     type Error: Into<Error>;
@@ -25,6 +59,15 @@ pub trait FromRequest: Sized {
     /// Convert request to a Self
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future;
 
+    /// Check if extractor could handle the request judging by request head,
+    /// i.e. by content type. Returns `None` if it is unknown.
+    ///
+    /// `Either` extractor uses it to select extractor without buffering
+    /// request payload.
+    fn accepts(_: &HttpRequest) -> Option<bool> {
+        None
+    }
+
     /// Convert request to a Self
     ///
     /// This method uses `Payload::None` as payload stream.
@@ -0,0 +1,690 @@
+//! Static files service
+use std::cmp;
+use std::fmt::Write;
+use std::fs::{File, Metadata};
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::UNIX_EPOCH;
+
+use actori_router::ResourceDef;
+use actori_service::{Service, ServiceFactory};
+use bytes::Bytes;
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::Stream;
+
+use crate::dev::{AppService, HttpServiceFactory, ServiceRequest, ServiceResponse};
+use crate::error::{BlockingError, Error, ErrorInternalServerError};
+use crate::http::header::{
+    ContentEncoding, EntityTag, ALLOW, CONTENT_ENCODING, LOCATION, VARY,
+};
+use crate::http::{HeaderMap, HeaderValue, Method};
+use crate::responder::Responder;
+use crate::types::RangedBody;
+use crate::{web, HttpResponse};
+
+/// Static files service.
+///
+/// Files of the directory are served under the mount path with `ETag` and
+/// `Last-Modified` headers, range and conditional requests are supported,
+/// content type is guessed from the file extension. Only `GET` and `HEAD`
+/// requests are accepted.
+///
+/// Paths with `..` segments, hidden files and paths that resolve outside
+/// of the directory, i.e. via symlinks, are answered with
+/// *404 Not Found* response. Directories are served with index file or
+/// files listing, if enabled, requests for directories without trailing
+/// slash are redirected.
+///
+/// Precompressed siblings of the files, i.e. `app.js.br` and `app.js.gz`,
+/// are served instead of the files if enabled, see `use_precompressed()`.
+///
+/// Filesystem operations are executed on the blocking thread pool.
+///
+/// ```rust
+/// use actori_web::{web, App};
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::files("/static", "./assets")
+///             .index_file("index.html")
+///             .show_files_listing(),
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Files {
+    path: String,
+    inner: Rc<FilesInner>,
+}
+
+#[derive(Debug, Clone)]
+struct FilesInner {
+    directory: PathBuf,
+    index: Option<String>,
+    show_index: bool,
+    precompressed: bool,
+}
+
+impl Files {
+    /// Create files service for the directory mounted under the path.
+    pub fn new<T: Into<PathBuf>>(path: &str, dir: T) -> Self {
+        let dir = dir.into();
+        let directory = match dir.canonicalize() {
+            Ok(dir) => dir,
+            Err(e) => {
+                log::error!("Files directory {:?} is not available: {}", dir, e);
+                dir
+            }
+        };
+
+        Files {
+            path: path.to_string(),
+            inner: Rc::new(FilesInner {
+                directory,
+                index: None,
+                show_index: false,
+                precompressed: false,
+            }),
+        }
+    }
+
+    /// Serve index file for directories, i.e. `index.html`.
+    ///
+    /// Index file takes precedence over files listing.
+    pub fn index_file<T: Into<String>>(mut self, index: T) -> Self {
+        Rc::make_mut(&mut self.inner).index = Some(index.into());
+        self
+    }
+
+    /// Show files listing for directories.
+    ///
+    /// By default files listing is disabled.
+    pub fn show_files_listing(mut self) -> Self {
+        Rc::make_mut(&mut self.inner).show_index = true;
+        self
+    }
+
+    #[cfg(feature = "compress")]
+    /// Serve precompressed siblings of the files.
+    ///
+    /// If `file.br` or `file.gz` exists next to the requested `file` and
+    /// the client accepts its encoding, the sibling is served with
+    /// `Content-Encoding` header and content type of the original file,
+    /// `br` is preferred over `gzip`. Otherwise the original file is served.
+    /// Responses carry `Vary: Accept-Encoding` header. Siblings older than
+    /// the original file are considered stale and ignored.
+    ///
+    /// This method is available with `compress` feature.
+    pub fn use_precompressed(mut self) -> Self {
+        Rc::make_mut(&mut self.inner).precompressed = true;
+        self
+    }
+}
+
+impl HttpServiceFactory for Files {
+    fn register(self, config: &mut AppService) {
+        let rdef = if config.is_root() {
+            ResourceDef::root_prefix(&self.path)
+        } else {
+            ResourceDef::prefix(&self.path)
+        };
+        config.register_service(rdef, None, self, None)
+    }
+}
+
+impl ServiceFactory for Files {
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = FilesService;
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ok(FilesService {
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct FilesService {
+    inner: Rc<FilesInner>,
+}
+
+impl Service for FilesService {
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            let res = HttpResponse::MethodNotAllowed()
+                .insert_header((ALLOW, "GET, HEAD"))
+                .finish();
+            return ok(req.into_response(res)).boxed_local();
+        }
+
+        let rel = match sanitize_path(req.match_info().path()) {
+            Some(rel) => rel,
+            None => {
+                return ok(req.into_response(HttpResponse::NotFound().finish()))
+                    .boxed_local()
+            }
+        };
+
+        let inner = FilesInner::clone(&self.inner);
+        let path = req.path().to_owned();
+        let precompressed = inner.precompressed;
+        let encodings = if precompressed {
+            accepted_encodings(req.headers())
+        } else {
+            Vec::new()
+        };
+        async move {
+            let (req, _) = req.into_parts();
+            match web::block(move || lookup(&inner, &rel, &path, &encodings)).await {
+                Ok(Lookup::File(file, md, path, encoding)) => {
+                    let mut res = serve_file(file, &md, &path, encoding)
+                        .respond_to(&req)
+                        .await?;
+                    if precompressed {
+                        res.headers_mut()
+                            .append(VARY, HeaderValue::from_static("accept-encoding"));
+                        if let Some(enc) = encoding {
+                            if res.status().is_success() {
+                                res.headers_mut().insert(
+                                    CONTENT_ENCODING,
+                                    HeaderValue::from_static(enc.as_str()),
+                                );
+                            }
+                        }
+                    }
+                    Ok(ServiceResponse::new(req, res))
+                }
+                Ok(Lookup::Listing(html)) => {
+                    let res = HttpResponse::Ok()
+                        .content_type("text/html; charset=utf-8")
+                        .body(html);
+                    Ok(ServiceResponse::new(req, res))
+                }
+                Ok(Lookup::Redirect) => {
+                    let location = match req.query_string() {
+                        "" => format!("{}/", req.path()),
+                        query => format!("{}/?{}", req.path(), query),
+                    };
+                    let res = HttpResponse::Found()
+                        .insert_header((LOCATION, location))
+                        .finish();
+                    Ok(ServiceResponse::new(req, res))
+                }
+                Err(BlockingError::Error(ref e))
+                    if e.kind() == io::ErrorKind::NotFound
+                        || e.kind() == io::ErrorKind::PermissionDenied =>
+                {
+                    log::debug!("Files: {} is not found: {}", req.path(), e);
+                    Ok(ServiceResponse::new(req, HttpResponse::NotFound().finish()))
+                }
+                Err(e) => Err(handle_error(e)),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Result of the filesystem lookup
+enum Lookup {
+    File(File, Metadata, PathBuf, Option<ContentEncoding>),
+    Listing(String),
+    Redirect,
+}
+
+/// Relative path of the request, `None` if path is not allowed
+fn sanitize_path(path: &str) -> Option<PathBuf> {
+    let mut buf = PathBuf::new();
+    for segment in path.split('/') {
+        if segment.is_empty() {
+            continue;
+        } else if segment.starts_with('.')
+            || segment.starts_with('*')
+            || segment.ends_with(':')
+            || segment.ends_with('>')
+            || segment.ends_with('<')
+            || segment.contains('\\')
+            || segment.contains('\0')
+        {
+            return None;
+        } else {
+            buf.push(segment)
+        }
+    }
+    Some(buf)
+}
+
+fn lookup(
+    inner: &FilesInner,
+    rel: &Path,
+    req_path: &str,
+    encodings: &[ContentEncoding],
+) -> io::Result<Lookup> {
+    let not_found = || io::Error::from(io::ErrorKind::NotFound);
+
+    let path = inner.directory.join(rel).canonicalize()?;
+    if !path.starts_with(&inner.directory) {
+        return Err(not_found());
+    }
+
+    if !path.is_dir() {
+        return open(inner, path, encodings);
+    }
+    if inner.index.is_none() && !inner.show_index {
+        return Err(not_found());
+    }
+    if !req_path.ends_with('/') {
+        return Ok(Lookup::Redirect);
+    }
+    if let Some(ref index) = inner.index {
+        let index = path.join(index);
+        if index.is_file() {
+            return open(inner, index, encodings);
+        } else if !inner.show_index {
+            return Err(not_found());
+        }
+    }
+    directory_listing(&path, req_path).map(Lookup::Listing)
+}
+
+fn open(
+    inner: &FilesInner,
+    path: PathBuf,
+    encodings: &[ContentEncoding],
+) -> io::Result<Lookup> {
+    let file = File::open(&path)?;
+    let md = file.metadata()?;
+    if !md.is_file() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+    match open_precompressed(inner, &path, &md, encodings) {
+        Some((file, md, enc)) => Ok(Lookup::File(file, md, path, Some(enc))),
+        None => Ok(Lookup::File(file, md, path, None)),
+    }
+}
+
+/// Open precompressed sibling of the file with the first acceptable encoding
+fn open_precompressed(
+    inner: &FilesInner,
+    path: &Path,
+    md: &Metadata,
+    encodings: &[ContentEncoding],
+) -> Option<(File, Metadata, ContentEncoding)> {
+    for enc in encodings {
+        let ext = match enc {
+            ContentEncoding::Br => ".br",
+            ContentEncoding::Gzip => ".gz",
+            _ => continue,
+        };
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(ext);
+
+        let sibling = match Path::new(&sibling).canonicalize() {
+            Ok(sibling) if sibling.starts_with(&inner.directory) => sibling,
+            _ => continue,
+        };
+        let file = match File::open(sibling) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        let sibling_md = match file.metadata() {
+            Ok(sibling_md) if sibling_md.is_file() => sibling_md,
+            _ => continue,
+        };
+        match (sibling_md.modified(), md.modified()) {
+            (Ok(sibling_mtime), Ok(mtime)) if sibling_mtime < mtime => continue,
+            _ => return Some((file, sibling_md, *enc)),
+        }
+    }
+    None
+}
+
+#[cfg(feature = "compress")]
+/// Encodings of precompressed files accepted by the client, in order of
+/// server preference
+fn accepted_encodings(headers: &HeaderMap) -> Vec<ContentEncoding> {
+    use actori_http::encoding::negotiate;
+
+    [ContentEncoding::Br, ContentEncoding::Gzip]
+        .iter()
+        .cloned()
+        .filter(|enc| negotiate(headers, *enc) == *enc)
+        .collect()
+}
+
+#[cfg(not(feature = "compress"))]
+fn accepted_encodings(_: &HeaderMap) -> Vec<ContentEncoding> {
+    Vec::new()
+}
+
+fn serve_file(
+    file: File,
+    md: &Metadata,
+    path: &Path,
+    encoding: Option<ContentEncoding>,
+) -> RangedBody<impl FnOnce(u64, u64) -> ChunkedReadFile> {
+    let content_type = mime_guess::from_path(path).first_or_octet_stream();
+    let mut body = RangedBody::new(md.len(), move |offset, length| ChunkedReadFile {
+        file: Some(file),
+        offset,
+        remaining: length,
+        fut: None,
+    })
+    .content_type(content_type.to_string());
+
+    if let Ok(modified) = md.modified() {
+        if let Ok(dur) = modified.duration_since(UNIX_EPOCH) {
+            let mut tag = format!(
+                "{:x}:{:x}:{:x}",
+                dur.as_secs(),
+                dur.subsec_nanos(),
+                md.len()
+            );
+            // representations with different encodings must not share etag
+            if let Some(enc) = encoding {
+                tag.push(':');
+                tag.push_str(enc.as_str());
+            }
+            body = body.etag(EntityTag::strong(tag));
+        }
+        body = body.last_modified(modified);
+    }
+    body
+}
+
+fn directory_listing(dir: &Path, req_path: &str) -> io::Result<String> {
+    let mut entries = Vec::new();
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let is_dir = entry.metadata().map(|md| md.is_dir()).unwrap_or(false);
+        entries.push((name, is_dir));
+    }
+    entries.sort();
+
+    let title = escape_html(&format!("Index of {}", req_path));
+    let mut html = format!(
+        "<html><head><title>{}</title></head><body><h1>{}</h1><ul>",
+        title, title
+    );
+    for (name, is_dir) in entries {
+        let slash = if is_dir { "/" } else { "" };
+        let _ = write!(
+            html,
+            "<li><a href=\"{}{}\">{}{}</a></li>",
+            encode_url(&name),
+            slash,
+            escape_html(&name),
+            slash
+        );
+    }
+    html.push_str("</ul></body>\n</html>");
+    Ok(html)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn encode_url(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{:02X}", b);
+        }
+    }
+    out
+}
+
+fn handle_error(err: BlockingError<io::Error>) -> Error {
+    match err {
+        BlockingError::Error(err) => err.into(),
+        BlockingError::Canceled => ErrorInternalServerError("Unexpected error"),
+    }
+}
+
+/// Reads part of the file chunk by chunk on the blocking thread pool
+struct ChunkedReadFile {
+    file: Option<File>,
+    offset: u64,
+    remaining: u64,
+    fut:
+        Option<LocalBoxFuture<'static, Result<(File, Bytes), BlockingError<io::Error>>>>,
+}
+
+impl Stream for ChunkedReadFile {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if let Some(ref mut fut) = self.fut {
+            return match Pin::new(fut).poll(cx) {
+                Poll::Ready(Ok((file, bytes))) => {
+                    self.fut.take();
+                    self.file = Some(file);
+                    self.offset += bytes.len() as u64;
+                    self.remaining -= bytes.len() as u64;
+                    Poll::Ready(Some(Ok(bytes)))
+                }
+                Poll::Ready(Err(e)) => {
+                    self.fut.take();
+                    Poll::Ready(Some(Err(handle_error(e))))
+                }
+                Poll::Pending => Poll::Pending,
+            };
+        }
+
+        if self.remaining == 0 {
+            return Poll::Ready(None);
+        }
+        let mut file = match self.file.take() {
+            Some(file) => file,
+            None => return Poll::Ready(None),
+        };
+        let offset = self.offset;
+        let size = cmp::min(self.remaining, 65_536);
+        self.fut = Some(
+            web::block(move || {
+                let mut buf = Vec::with_capacity(size as usize);
+                file.seek(SeekFrom::Start(offset))?;
+                let n = file.by_ref().take(size).read_to_end(&mut buf)?;
+                if n == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                Ok((file, Bytes::from(buf)))
+            })
+            .boxed_local(),
+        );
+        self.poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::App;
+
+    #[test]
+    fn test_sanitize_path() {
+        assert_eq!(sanitize_path("/a/b.txt"), Some(PathBuf::from("a/b.txt")));
+        assert_eq!(sanitize_path("//a//"), Some(PathBuf::from("a")));
+        assert_eq!(sanitize_path("/../Cargo.toml"), None);
+        assert_eq!(sanitize_path("/.git/config"), None);
+        assert_eq!(sanitize_path("/a\\..\\b"), None);
+    }
+
+    #[actori_rt::test]
+    async fn test_files() {
+        let mut srv = init_service(App::new().service(web::files("/static", "."))).await;
+
+        let req = TestRequest::with_uri("/static/Cargo.toml").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            mime_guess::from_ext("toml")
+                .first_or_octet_stream()
+                .to_string()
+                .as_str()
+        );
+        assert!(resp.headers().contains_key(header::ETAG));
+        assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        let body = read_body(resp).await;
+        assert_eq!(body, std::fs::read("Cargo.toml").unwrap());
+
+        let req = TestRequest::with_uri("/static/Cargo.toml")
+            .header(header::IF_NONE_MATCH, etag)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_uri("/static/Cargo.toml")
+            .header(header::RANGE, "bytes=0-8")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"[package]"));
+
+        let req = TestRequest::post().uri("/static/Cargo.toml").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        for path in &[
+            "/static/missing.txt",
+            "/static/../Cargo.toml",
+            "/static/%2e%2e/Cargo.toml",
+            "/static/.gitignore",
+            "/static/src",
+        ] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{}", path);
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_files_listing() {
+        let mut srv = init_service(
+            App::new().service(web::files("/", "./src").show_files_listing()),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/middleware").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        assert_eq!(resp.headers().get(LOCATION).unwrap(), "/middleware/");
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<a href=\"files.rs\">files.rs</a>"));
+        assert!(body.contains("<a href=\"middleware/\">middleware/</a>"));
+    }
+
+    #[actori_rt::test]
+    async fn test_files_index() {
+        let mut srv = init_service(
+            App::new().service(web::files("/", "./src").index_file("lib.rs")),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, std::fs::read("src/lib.rs").unwrap());
+
+        // no index file
+        let req = TestRequest::with_uri("/middleware/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "compress")]
+    #[actori_rt::test]
+    async fn test_files_precompressed() {
+        let dir = std::env::temp_dir().join("actori-web-files-precompressed");
+        let _ = std::fs::create_dir_all(&dir);
+        std::fs::write(dir.join("app.js"), "plain").unwrap();
+        std::fs::write(dir.join("app.js.gz"), "gzipped").unwrap();
+
+        let mut srv =
+            init_service(App::new().service(web::files("/", &dir).use_precompressed()))
+                .await;
+
+        let req = TestRequest::with_uri("/app.js")
+            .header(header::ACCEPT_ENCODING, "gzip, br")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(resp.headers().get(VARY).unwrap(), "accept-encoding");
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            mime_guess::from_ext("js")
+                .first_or_octet_stream()
+                .to_string()
+                .as_str()
+        );
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"gzipped"));
+
+        // not acceptable, original file is served
+        let req = TestRequest::with_uri("/app.js")
+            .header(header::ACCEPT_ENCODING, "br, gzip;q=0")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(resp.headers().get(VARY).unwrap(), "accept-encoding");
+        assert_ne!(resp.headers().get(header::ETAG).unwrap(), &etag);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"plain"));
+
+        // disabled by default
+        let mut srv = init_service(App::new().service(web::files("/", &dir))).await;
+        let req = TestRequest::with_uri("/app.js")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert!(resp.headers().get(VARY).is_none());
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"plain"));
+    }
+}
@@ -4,7 +4,7 @@ use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use actori_http::{Error, Response};
+use actori_http::Error;
 use actori_service::{Service, ServiceFactory};
 use futures::future::{ok, Ready};
 use futures::ready;
@@ -129,8 +129,9 @@ where
                 }
                 Poll::Pending => Poll::Pending,
                 Poll::Ready(Err(e)) => {
-                    let res: Response = e.into().into();
-                    Poll::Ready(Ok(ServiceResponse::new(this.req.take().unwrap(), res)))
+                    let req = this.req.take().unwrap();
+                    let res = crate::i18n::localize(&req, e.into().into());
+                    Poll::Ready(Ok(ServiceResponse::new(req, res)))
                 }
             };
         }
@@ -0,0 +1,304 @@
+//! Liveness and readiness endpoints
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actori_rt::time::timeout;
+use futures::future::{join_all, FutureExt, LocalBoxFuture};
+use serde::Serialize;
+
+use crate::http::StatusCode;
+use crate::request::HttpRequest;
+use crate::scope::Scope;
+use crate::{web, HttpResponse};
+
+type Check = Arc<dyn Fn() -> LocalBoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// Registry of health checks, see `web::health_checks()`.
+///
+/// Registry is registered with `App::app_data()`. Liveness checks are run
+/// by `/live` endpoint, readiness checks are run by `/ready` endpoint. Checks
+/// of the endpoint run concurrently, check fails if it returns an error or
+/// if it does not complete within the timeout.
+///
+/// ```rust
+/// use actori_web::{web, App};
+///
+/// async fn ping_db() -> Result<(), String> {
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .app_data(
+///             web::HealthRegistry::new()
+///                 .readiness("db", || ping_db())
+///                 .readiness("queue", || async {
+///                     let depth = 10;
+///                     if depth < 1000 {
+///                         Ok(())
+///                     } else {
+///                         Err(format!("queue depth is {}", depth))
+///                     }
+///                 }),
+///         )
+///         .service(web::health_checks());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct HealthRegistry {
+    liveness: Vec<(String, Check)>,
+    readiness: Vec<(String, Check)>,
+    timeout: Duration,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        HealthRegistry::new()
+    }
+}
+
+impl HealthRegistry {
+    /// Create registry without checks
+    pub fn new() -> Self {
+        HealthRegistry {
+            liveness: Vec::new(),
+            readiness: Vec::new(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Add liveness check, it is run by `/live` endpoint.
+    pub fn liveness<F, Fut, E>(mut self, name: &str, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: fmt::Display,
+    {
+        self.liveness.push((name.to_owned(), wrap(check)));
+        self
+    }
+
+    /// Add readiness check, it is run by `/ready` endpoint.
+    pub fn readiness<F, Fut, E>(mut self, name: &str, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: fmt::Display,
+    {
+        self.readiness.push((name.to_owned(), wrap(check)));
+        self
+    }
+
+    /// Set timeout of a single check.
+    ///
+    /// By default timeout is set to 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl fmt::Debug for HealthRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |checks: &[(String, Check)]| {
+            checks
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>()
+        };
+        f.debug_struct("HealthRegistry")
+            .field("liveness", &names(&self.liveness))
+            .field("readiness", &names(&self.readiness))
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+fn wrap<F, Fut, E>(check: F) -> Check
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), E>> + 'static,
+    E: fmt::Display,
+{
+    Arc::new(move || {
+        check()
+            .map(|res| res.map_err(|e| e.to_string()))
+            .boxed_local()
+    })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Up,
+    Down,
+}
+
+#[derive(Serialize)]
+struct CheckStatus {
+    name: String,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    status: Status,
+    checks: Vec<CheckStatus>,
+}
+
+/// Create scope with health check endpoints mounted under `/health` path.
+///
+/// * `GET /health/live` runs liveness checks
+/// * `GET /health/ready` runs readiness checks
+///
+/// Checks are registered with [`HealthRegistry`](struct.HealthRegistry.html)
+/// application data. Endpoints respond with *200 OK* if all checks
+/// succeed and with *503 Service Unavailable* otherwise, json body lists
+/// status of the individual checks:
+///
+/// ```json
+/// {
+///   "status": "down",
+///   "checks": [
+///     {"name": "db", "status": "up"},
+///     {"name": "queue", "status": "down", "error": "queue depth is 1000"}
+///   ]
+/// }
+/// ```
+///
+/// Endpoints respond with *200 OK* if registry is not registered.
+pub fn health_checks() -> Scope {
+    web::scope("/health")
+        .route("/live", web::get().to(live))
+        .route("/ready", web::get().to(ready))
+}
+
+async fn live(req: HttpRequest) -> HttpResponse {
+    let (checks, dur) = req
+        .app_data::<HealthRegistry>()
+        .map(|reg| (reg.liveness.clone(), reg.timeout))
+        .unwrap_or_default();
+    report(checks, dur).await
+}
+
+async fn ready(req: HttpRequest) -> HttpResponse {
+    let (checks, dur) = req
+        .app_data::<HealthRegistry>()
+        .map(|reg| (reg.readiness.clone(), reg.timeout))
+        .unwrap_or_default();
+    report(checks, dur).await
+}
+
+/// Run checks concurrently and render the report
+async fn report(checks: Vec<(String, Check)>, dur: Duration) -> HttpResponse {
+    let results = join_all(checks.iter().map(|(_, check)| timeout(dur, check()))).await;
+
+    let mut up = true;
+    let checks = checks
+        .into_iter()
+        .zip(results)
+        .map(|((name, _), res)| {
+            let error = match res {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e),
+                Err(_) => Some("Check timed out".to_owned()),
+            };
+            if error.is_some() {
+                log::warn!("Health check {:?} failed: {:?}", name, error);
+                up = false;
+            }
+            CheckStatus {
+                name,
+                status: if error.is_some() {
+                    Status::Down
+                } else {
+                    Status::Up
+                },
+                error,
+            }
+        })
+        .collect();
+
+    let (code, status) = if up {
+        (StatusCode::OK, Status::Up)
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Status::Down)
+    };
+    HttpResponse::build(code).json(Report { status, checks })
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_rt::time::delay_for;
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::App;
+
+    #[actori_rt::test]
+    async fn test_health_checks() {
+        let mut srv = init_service(
+            App::new()
+                .app_data(
+                    HealthRegistry::new()
+                        .liveness("loop", || async { Ok::<_, String>(()) })
+                        .readiness("db", || async { Ok::<_, String>(()) })
+                        .readiness("queue", || async { Err("queue is full") })
+                        .readiness("slow", || {
+                            delay_for(Duration::from_secs(1)).map(Ok::<_, String>)
+                        })
+                        .timeout(Duration::from_millis(50)),
+                )
+                .service(health_checks()),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/health/live").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value =
+            serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": "up",
+                "checks": [{"name": "loop", "status": "up"}],
+            })
+        );
+
+        let req = TestRequest::with_uri("/health/ready").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value =
+            serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": "down",
+                "checks": [
+                    {"name": "db", "status": "up"},
+                    {"name": "queue", "status": "down", "error": "queue is full"},
+                    {"name": "slow", "status": "down", "error": "Check timed out"},
+                ],
+            })
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_health_checks_without_registry() {
+        let mut srv = init_service(App::new().service(health_checks())).await;
+
+        let req = TestRequest::with_uri("/health/ready").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"{\"status\":\"up\",\"checks\":[]}")
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Localization of messages and error responses
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actori_http::body::{Body, ResponseBody};
+use actori_http::error::{Error, ErrorInternalServerError};
+use futures::future::{err, ok, Ready};
+
+use crate::dev::Payload;
+use crate::error::ValidationErrors;
+use crate::extract::FromRequest;
+use crate::http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_TYPE};
+use crate::http::{HeaderMap, HeaderValue};
+use crate::request::HttpRequest;
+use crate::HttpResponse;
+
+/// Catalog of localized messages.
+///
+/// Translator is registered with `App::app_data()`, or per scope and
+/// resource. Language of the request is negotiated with `Accept-Language`
+/// header among the languages of the catalog, the default language is used
+/// if none is acceptable. Messages missing in the negotiated language fall
+/// back to the default language.
+///
+/// Error responses of handlers and extractors are localized with the
+/// translator of the request:
+///
+/// * default `text/plain` bodies of error responses are replaced with
+///   `error.<status code>` message, i.e. `error.404`
+/// * messages of `ValidationErrors` are translated, message text is the key
+///
+/// Localized responses carry `Content-Language` header. Messages are
+/// available in handlers with `web::I18n` extractor.
+///
+/// ```rust
+/// use actori_web::{web, App, HttpResponse};
+///
+/// async fn index(i18n: web::I18n) -> HttpResponse {
+///     HttpResponse::Ok().body(i18n.t("welcome").to_owned())
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .app_data(
+///             web::Translator::new("en")
+///                 .message("en", "welcome", "Welcome!")
+///                 .message("de", "welcome", "Willkommen!")
+///                 .message("de", "error.404", "Nicht gefunden"),
+///         )
+///         .route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Translator {
+    inner: Arc<TranslatorInner>,
+}
+
+#[derive(Debug, Clone)]
+struct TranslatorInner {
+    default: String,
+    catalogs: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translator {
+    /// Create translator with the default language.
+    pub fn new<T: Into<String>>(default_lang: T) -> Self {
+        Translator {
+            inner: Arc::new(TranslatorInner {
+                default: default_lang.into().to_ascii_lowercase(),
+                catalogs: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Add message of the language.
+    ///
+    /// Language tags are case insensitive.
+    pub fn message<L, K, V>(mut self, lang: L, key: K, text: V) -> Self
+    where
+        L: Into<String>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Arc::make_mut(&mut self.inner)
+            .catalogs
+            .entry(lang.into().to_ascii_lowercase())
+            .or_insert_with(HashMap::new)
+            .insert(key.into(), text.into());
+        self
+    }
+
+    /// Add messages of the language.
+    pub fn messages<L, I, K, V>(mut self, lang: L, messages: I) -> Self
+    where
+        L: Into<String>,
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let catalog = Arc::make_mut(&mut self.inner)
+            .catalogs
+            .entry(lang.into().to_ascii_lowercase())
+            .or_insert_with(HashMap::new);
+        for (key, text) in messages {
+            catalog.insert(key.into(), text.into());
+        }
+        self
+    }
+
+    /// Default language
+    pub fn default_lang(&self) -> &str {
+        &self.inner.default
+    }
+
+    /// Message of the language, falls back to the default language
+    pub fn translate(&self, lang: &str, key: &str) -> Option<&str> {
+        let inner = &*self.inner;
+        inner
+            .catalogs
+            .get(lang)
+            .and_then(|catalog| catalog.get(key))
+            .or_else(|| {
+                inner
+                    .catalogs
+                    .get(&inner.default)
+                    .and_then(|catalog| catalog.get(key))
+            })
+            .map(|text| text.as_str())
+    }
+
+    /// Negotiate language with `Accept-Language` header.
+    ///
+    /// Language ranges are matched exactly, or by the primary subtag,
+    /// i.e. `de-AT` matches `de` catalog. Default language is returned if
+    /// no language is acceptable.
+    pub fn negotiate(&self, headers: &HeaderMap) -> &str {
+        let mut ranges = Vec::new();
+        for val in headers.get_all(ACCEPT_LANGUAGE) {
+            if let Ok(val) = val.to_str() {
+                ranges.extend(val.split(',').filter_map(parse_range));
+            }
+        }
+        // stable sort keeps header order for equal quality
+        ranges.sort_by(|a, b| b.1.cmp(&a.1));
+
+        for (range, _) in ranges {
+            if range == "*" {
+                break;
+            }
+            let primary = range.split('-').next().unwrap_or("");
+            for candidate in &[range.as_str(), primary] {
+                if let Some(lang) = self.inner.catalogs.keys().find(|l| l == candidate) {
+                    return lang;
+                }
+            }
+        }
+        &self.inner.default
+    }
+}
+
+/// Parse `de-AT;q=0.8` item, ranges with `q=0` are not acceptable
+fn parse_range(item: &str) -> Option<(String, u16)> {
+    let mut parts = item.split(';');
+    let range = parts.next()?.trim();
+    if range.is_empty() {
+        return None;
+    }
+
+    let mut q = 1000;
+    for param in parts {
+        let param = param.trim();
+        if param.len() > 2 && param[..2].eq_ignore_ascii_case("q=") {
+            let val = param[2..].parse::<f32>().ok()?;
+            if val < 0.0 || val > 1.0 {
+                return None;
+            }
+            q = (val * 1000.0) as u16;
+        }
+    }
+    if q == 0 {
+        None
+    } else {
+        Some((range.to_ascii_lowercase(), q))
+    }
+}
+
+/// Localize error response with the translator of the request
+pub(crate) fn localize(req: &HttpRequest, res: HttpResponse) -> HttpResponse {
+    let translator = match req.app_data::<Translator>() {
+        Some(translator) => translator,
+        None => return res,
+    };
+    let lang = translator.negotiate(req.headers());
+
+    let body = match res.error() {
+        Some(e) => {
+            if let Some(errors) = e.as_error::<ValidationErrors>() {
+                let mut localized = ValidationErrors::new();
+                for err in errors.errors() {
+                    let message = translator
+                        .translate(lang, &err.message)
+                        .unwrap_or(&err.message);
+                    localized.add(err.field.as_str(), message);
+                }
+                match serde_json::to_string(&localized) {
+                    Ok(body) => body,
+                    Err(_) => return res,
+                }
+            } else if is_default_body(&res) {
+                let key = format!("error.{}", res.status().as_u16());
+                match translator.translate(lang, &key) {
+                    Some(text) => text.to_owned(),
+                    None => return res,
+                }
+            } else {
+                return res;
+            }
+        }
+        None => return res,
+    };
+
+    let lang = HeaderValue::from_str(lang);
+    res.map_body(move |head, _| {
+        if let Ok(lang) = lang {
+            head.headers_mut().insert(CONTENT_LANGUAGE, lang);
+        }
+        ResponseBody::Body(Body::from(body))
+    })
+}
+
+/// Error response is rendered by the default `ResponseError::error_response()`
+fn is_default_body(res: &HttpResponse) -> bool {
+    res.headers()
+        .get(CONTENT_TYPE)
+        .map(|ct| ct == "text/plain; charset=utf-8")
+        .unwrap_or(false)
+}
+
+/// Messages localized for the request, see `web::Translator`.
+///
+/// Extraction fails with *500 Internal Server Error* if translator is not
+/// registered.
+#[derive(Debug, Clone)]
+pub struct I18n {
+    lang: String,
+    translator: Translator,
+}
+
+impl I18n {
+    /// Negotiated language of the request
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    /// Localized message, the key is returned if message is not found
+    pub fn t<'a>(&'a self, key: &'a str) -> &'a str {
+        self.translator.translate(&self.lang, key).unwrap_or(key)
+    }
+
+    /// Translator of the request
+    pub fn translator(&self) -> &Translator {
+        &self.translator
+    }
+}
+
+impl FromRequest for I18n {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(translator) = req.app_data::<Translator>() {
+            ok(I18n {
+                lang: translator.negotiate(req.headers()).to_owned(),
+                translator: translator.clone(),
+            })
+        } else {
+            log::debug!(
+                "Failed to construct I18n extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError("Translator is not configured"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::error::ErrorNotFound;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App};
+
+    fn translator() -> Translator {
+        Translator::new("en")
+            .message("en", "hello", "Hello")
+            .message("en", "bye", "Bye")
+            .messages(
+                "de",
+                vec![("hello", "Hallo"), ("error.404", "Nicht gefunden")],
+            )
+            .message("de", "must not be empty", "darf nicht leer sein")
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let tr = translator();
+        let lang = |val: &'static str| {
+            let req = TestRequest::default()
+                .header(ACCEPT_LANGUAGE, val)
+                .to_http_request();
+            tr.negotiate(req.headers()).to_owned()
+        };
+        assert_eq!(tr.negotiate(&HeaderMap::new()), "en");
+        assert_eq!(lang("de"), "de");
+        assert_eq!(lang("de-AT, en;q=0.5"), "de");
+        assert_eq!(lang("fr, en;q=0.9, de;q=0.8"), "en");
+        assert_eq!(lang("en;q=0.5, DE;q=0.7"), "de");
+        assert_eq!(lang("de;q=0, fr"), "en");
+
+        assert_eq!(tr.translate("de", "hello"), Some("Hallo"));
+        assert_eq!(tr.translate("de", "bye"), Some("Bye"));
+        assert_eq!(tr.translate("de", "missing"), None);
+    }
+
+    #[actori_rt::test]
+    async fn test_i18n() {
+        let mut srv = init_service(
+            App::new()
+                .app_data(translator())
+                .route(
+                    "/",
+                    web::get().to(|i18n: I18n| {
+                        HttpResponse::Ok().body(format!(
+                            "{} {}",
+                            i18n.lang(),
+                            i18n.t("hello")
+                        ))
+                    }),
+                )
+                .route(
+                    "/missing",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(ErrorNotFound("not found"))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/")
+            .header(ACCEPT_LANGUAGE, "de-CH")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"de Hallo"));
+
+        let req = TestRequest::with_uri("/missing")
+            .header(ACCEPT_LANGUAGE, "de")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers().get(CONTENT_LANGUAGE).unwrap(), "de");
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"Nicht gefunden"));
+
+        // no message for the status
+        let req = TestRequest::with_uri("/missing").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_LANGUAGE).is_none());
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"not found"));
+    }
+
+    #[actori_rt::test]
+    async fn test_i18n_validation() {
+        use crate::types::Validate;
+
+        #[derive(serde_derive::Deserialize)]
+        struct Info {
+            name: String,
+        }
+
+        impl Validate for Info {
+            fn validate(&self) -> Result<(), ValidationErrors> {
+                let mut errors = ValidationErrors::new();
+                if self.name.is_empty() {
+                    errors.add("name", "must not be empty");
+                }
+                errors.into_result()
+            }
+        }
+
+        let mut srv = init_service(App::new().app_data(translator()).route(
+            "/",
+            web::get().to(|_: web::Validated<web::Query<Info>>| HttpResponse::Ok()),
+        ))
+        .await;
+
+        let req = TestRequest::with_uri("/?name=")
+            .header(ACCEPT_LANGUAGE, "de")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value =
+            serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"errors": [
+                {"field": "name", "message": "darf nicht leer sein"},
+            ]})
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_i18n_not_configured() {
+        let req = TestRequest::default().to_http_request();
+        assert!(I18n::from_request(&req, &mut Payload::None).await.is_err());
+    }
+}
@@ -1,11 +1,12 @@
 use std::cell::Ref;
+use std::net::{IpAddr, SocketAddr};
 
 use crate::dev::{AppConfig, RequestHead};
 use crate::http::header::{self, HeaderName};
 
-const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
-const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
-const X_FORWARDED_PROTO: &[u8] = b"x-forwarded-proto";
+pub(crate) const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
+pub(crate) const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
+pub(crate) const X_FORWARDED_PROTO: &[u8] = b"x-forwarded-proto";
 
 /// `HttpRequest` connection information
 #[derive(Debug, Clone, Default)]
@@ -18,13 +19,34 @@ pub struct ConnectionInfo {
 
 impl ConnectionInfo {
     /// Create *ConnectionInfo* instance for a request.
+    ///
+    /// If request contains `ClientMeta` computed by `TrustedProxies`
+    /// middleware, connection info is taken from it. Otherwise, if trusted
+    /// proxies are configured with `AppConfig::set_trusted_proxies()`,
+    /// forwarded headers are used only for requests from trusted proxies.
     pub fn get<'a>(req: &'a RequestHead, cfg: &AppConfig) -> Ref<'a, Self> {
         if !req.extensions().contains::<ConnectionInfo>() {
-            req.extensions_mut().insert(ConnectionInfo::new(req, cfg));
+            let info = if let Some(meta) = req.extensions().get::<ClientMeta>() {
+                ConnectionInfo::from_meta(meta)
+            } else if let Some(proxies) = cfg.trusted_proxies() {
+                ConnectionInfo::from_meta(&proxies.client_meta(req, cfg))
+            } else {
+                ConnectionInfo::new(req, cfg)
+            };
+            req.extensions_mut().insert(info);
         }
         Ref::map(req.extensions(), |e| e.get().unwrap())
     }
 
+    fn from_meta(meta: &ClientMeta) -> ConnectionInfo {
+        ConnectionInfo {
+            scheme: meta.scheme.clone(),
+            host: meta.host.clone(),
+            remote: meta.ip.map(|ip| ip.to_string()),
+            peer: None,
+        }
+    }
+
     #[allow(clippy::cognitive_complexity)]
     fn new(req: &RequestHead, cfg: &AppConfig) -> ConnectionInfo {
         let mut host = None;
@@ -180,9 +202,54 @@ impl ConnectionInfo {
     }
 }
 
+/// Client information resolved by `TrustedProxies` middleware.
+///
+/// Forwarding headers are taken into account only if request is received
+/// from a trusted proxy. Client information is computed once per request
+/// and is stored in request extensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientMeta {
+    pub(crate) scheme: String,
+    pub(crate) host: String,
+    pub(crate) ip: Option<IpAddr>,
+    pub(crate) peer_addr: Option<SocketAddr>,
+    pub(crate) proxied: bool,
+}
+
+impl ClientMeta {
+    /// Scheme of the request
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Hostname of the request
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Client ip address.
+    ///
+    /// Address is taken from forwarding headers if request is received
+    /// from a trusted proxy, otherwise it is ip address of the peer.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.ip
+    }
+
+    /// Socket address of the peer
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Check if request is received from a trusted proxy
+    pub fn is_proxied(&self) -> bool {
+        self.proxied
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::middleware::TrustedProxies;
     use crate::test::TestRequest;
 
     #[test]
@@ -232,4 +299,37 @@ mod tests {
         let info = req.connection_info();
         assert_eq!(info.scheme(), "https");
     }
+
+    #[test]
+    fn test_trusted_proxies_config() {
+        let proxies = TrustedProxies::new().trust("10.0.0.0/8");
+
+        // untrusted peer, forwarded headers are ignored
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("192.0.2.1:1234".parse().unwrap())
+            .header(header::HOST, "example.com")
+            .header(
+                header::FORWARDED,
+                "for=192.0.2.60;proto=https;host=rust-lang.org",
+            )
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "example.com");
+        assert_eq!(info.remote(), Some("192.0.2.1"));
+
+        // trusted peer
+        let req = TestRequest::default()
+            .trusted_proxies(proxies)
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .header(X_FORWARDED_FOR, "192.0.2.60, 10.0.0.2")
+            .header(X_FORWARDED_PROTO, "https")
+            .header(X_FORWARDED_HOST, "rust-lang.org")
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+    }
 }
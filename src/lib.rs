@@ -72,22 +72,44 @@
 //!
 //! * `client` - enables http client (default enabled)
 //! * `compress` - enables content encoding compression support (default enabled)
+//! * `compress-zstd` - enables `zstd` content encoding, enables `compress` feature
 //! * `openssl` - enables ssl support via `openssl` crate, supports `http/2`
 //! * `rustls` - enables ssl support via `rustls` crate, supports `http/2`
+//! * `acme` - automatic certificate provisioning with Let's Encrypt,
+//!   enables `openssl` feature
 //! * `secure-cookies` - enables secure cookies support, includes `ring` crate as
 //!   dependency
+//! * `tower` - enables integration with `tower` services
+//! * `http-body` - enables `body::FromHttpBody` and `body::IntoHttpBody` adapters
+//!   for `http_body::Body` bodies of hyper ecosystem libraries
+//! * `xml` - enables `web::Xml` extractor and responder
+//! * `tracing` - creates `tracing` span for every request and propagates
+//!   `traceparent` and `b3` trace context to http client requests
+//! * `debug-dump` - enables `middleware::DebugDump` in release builds
 #![allow(clippy::type_complexity, clippy::new_without_default)]
 
+#[cfg(feature = "acme")]
+pub mod acme;
 mod app;
 mod app_service;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod blocking;
 mod config;
 mod data;
+pub mod describe;
 pub mod error;
 mod extract;
+mod files;
 pub mod guard;
 mod handler;
+mod health;
+mod i18n;
 mod info;
+mod listeners;
 pub mod middleware;
+pub mod mixed_replace;
 mod request;
 mod resource;
 mod responder;
@@ -96,9 +118,17 @@ mod route;
 mod scope;
 mod server;
 mod service;
+mod shutdown;
+mod sitemap;
+pub mod sse;
 pub mod test;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "tracing")]
+mod trace;
 mod types;
 pub mod web;
+pub mod ws;
 
 #[doc(hidden)]
 pub use actori_web_codegen::*;
@@ -111,7 +141,7 @@ pub use crate::app::App;
 pub use crate::extract::FromRequest;
 pub use crate::request::HttpRequest;
 pub use crate::resource::Resource;
-pub use crate::responder::{Either, Responder};
+pub use crate::responder::{Either, Responder, SeeOther};
 pub use crate::route::Route;
 pub use crate::scope::Scope;
 pub use crate::server::HttpServer;
@@ -130,22 +160,34 @@ pub mod dev {
     pub use crate::config::{AppConfig, AppService};
     #[doc(hidden)]
     pub use crate::handler::Factory;
-    pub use crate::info::ConnectionInfo;
+    pub use crate::info::{ClientMeta, ConnectionInfo};
+    pub use crate::listeners::{ListenerStats, Listeners};
     pub use crate::rmap::ResourceMap;
     pub use crate::service::{
         HttpServiceFactory, ServiceRequest, ServiceResponse, WebService,
     };
+    pub use crate::shutdown::Shutdown;
 
     pub use crate::types::form::UrlEncoded;
     pub use crate::types::json::JsonBody;
     pub use crate::types::readlines::Readlines;
+    #[cfg(feature = "xml")]
+    pub use crate::types::xml::XmlBody;
 
-    pub use actori_http::body::{Body, BodySize, MessageBody, ResponseBody, SizedStream};
+    pub use actori_http::body::{
+        Body, BodyClosed, BodySender, BodySize, ChannelBody, MessageBody, ResponseBody,
+        SizedStream,
+    };
     #[cfg(feature = "compress")]
     pub use actori_http::encoding::Decoder as Decompress;
+    pub use actori_http::trace::TraceContext;
     pub use actori_http::ResponseBuilder as HttpResponseBuilder;
+    #[cfg(any(feature = "openssl", feature = "rustls"))]
+    pub use actori_http::TlsReload;
     pub use actori_http::{
-        Extensions, Payload, PayloadStream, RequestHead, ResponseHead,
+        DynExtensions, DynNamespace, DynNamespaceMut, Extensions, ListenerName,
+        LocalAddr, Payload, PayloadStream, PeerAddr, PeerCertificates, RequestHead,
+        ResponseHead,
     };
     pub use actori_router::{Path, ResourceDef, ResourcePath, Url};
     pub use actori_server::Server;
@@ -163,7 +205,9 @@ pub mod dev {
     use crate::http::header::ContentEncoding;
     use actori_http::{Response, ResponseBuilder};
 
-    struct Enc(ContentEncoding);
+    /// Encoding override, stored in response extensions or in request
+    /// extensions by resources and routes
+    pub(crate) struct Enc(pub(crate) ContentEncoding);
 
     /// Helper trait that allows to set specific encoding for response.
     pub trait BodyEncoding {
@@ -217,7 +261,7 @@ pub mod client {
     //!
     //!    // Create request builder and send request
     //!    let response = client.get("http://www.rust-lang.org")
-    //!       .header("User-Agent", "Actori-web")
+    //!       .insert_header(("User-Agent", "Actori-web"))
     //!       .send().await;                      // <- Send http request
     //!
     //!    println!("Response: {:?}", response);
@@ -0,0 +1,273 @@
+//! Per-listener connection counters and accept pause
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use actori_service::{Service, ServiceFactory};
+use futures::future::{ok, Either, FutureExt, LocalBoxFuture, Ready};
+
+#[derive(Debug, Default)]
+pub(crate) struct ListenerState {
+    paused: AtomicBool,
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+    active: AtomicUsize,
+}
+
+/// Counters of the listener, see `Listeners::stats()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenerStats {
+    /// Number of connections accepted and served by the listener
+    pub accepted: u64,
+    /// Number of connections closed because the listener was paused
+    pub rejected: u64,
+    /// Number of connections currently served by the listener
+    pub active: usize,
+    /// Listener is paused
+    pub paused: bool,
+}
+
+/// Handle to the listeners of the http server.
+///
+/// Handle is created with `HttpServer::listeners()`, it is cheap to clone
+/// and can be used from any thread while the server is running. Listeners
+/// are identified by the name set with `HttpServer::listener_name()`, or by
+/// the socket address if no name is set. Listeners with the same name share
+/// counters and pause state.
+///
+/// While the listener is paused, new connections are still accepted by the
+/// system but closed immediately without being served. Established
+/// connections are not affected.
+///
+/// ```rust,no_run
+/// use actori_web::{web, App, HttpResponse, HttpServer};
+///
+/// #[actori_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     let server = HttpServer::new(|| App::new().route("/", web::to(|| HttpResponse::Ok())))
+///         .listener_name("public")
+///         .bind("0.0.0.0:8080")?
+///         .listener_name("admin")
+///         .bind("127.0.0.1:9090")?;
+///     let listeners = server.listeners();
+///
+///     // during an incident
+///     listeners.pause("public");
+///     println!("{:?}", listeners.stats("public"));
+///
+///     server.run().await
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Listeners {
+    inner: Arc<Mutex<HashMap<String, Arc<ListenerState>>>>,
+}
+
+impl Listeners {
+    /// Get or create state of the listener
+    pub(crate) fn register(&self, name: &str) -> Arc<ListenerState> {
+        self.inner
+            .lock()
+            .unwrap()
+            .entry(name.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    fn get(&self, name: &str) -> Option<Arc<ListenerState>> {
+        self.inner.lock().unwrap().get(name).cloned()
+    }
+
+    /// Names of all listeners
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.inner.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Stop serving new connections of the listener.
+    ///
+    /// Returns `false` if there is no listener with this name.
+    pub fn pause(&self, name: &str) -> bool {
+        self.get(name)
+            .map(|state| state.paused.store(true, Ordering::Relaxed))
+            .is_some()
+    }
+
+    /// Resume serving new connections of the paused listener.
+    ///
+    /// Returns `false` if there is no listener with this name.
+    pub fn resume(&self, name: &str) -> bool {
+        self.get(name)
+            .map(|state| state.paused.store(false, Ordering::Relaxed))
+            .is_some()
+    }
+
+    /// Counters of the listener
+    pub fn stats(&self, name: &str) -> Option<ListenerStats> {
+        self.get(name).map(|state| ListenerStats {
+            accepted: state.accepted.load(Ordering::Relaxed),
+            rejected: state.rejected.load(Ordering::Relaxed),
+            active: state.active.load(Ordering::Relaxed),
+            paused: state.paused.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Number of connections currently served by all listeners
+    pub(crate) fn active(&self) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .values()
+            .map(|state| state.active.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+/// Service factory that counts connections of the listener and closes
+/// new connections while the listener is paused
+pub(crate) struct ListenerGate<T> {
+    state: Arc<ListenerState>,
+    factory: T,
+}
+
+impl<T> ListenerGate<T> {
+    pub(crate) fn new(state: Arc<ListenerState>, factory: T) -> Self {
+        ListenerGate { state, factory }
+    }
+}
+
+impl<T> ServiceFactory for ListenerGate<T>
+where
+    T: ServiceFactory<Config = (), Response = ()>,
+    T::Future: 'static,
+    T::Service: 'static,
+    <T::Service as Service>::Future: 'static,
+{
+    type Config = ();
+    type Request = T::Request;
+    type Response = ();
+    type Error = T::Error;
+    type InitError = T::InitError;
+    type Service = ListenerGateService<T::Service>;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let state = self.state.clone();
+        self.factory
+            .new_service(())
+            .map(move |res| res.map(|service| ListenerGateService { state, service }))
+            .boxed_local()
+    }
+}
+
+pub(crate) struct ListenerGateService<S> {
+    state: Arc<ListenerState>,
+    service: S,
+}
+
+impl<S> Service for ListenerGateService<S>
+where
+    S: Service<Response = ()>,
+    S::Future: 'static,
+{
+    type Request = S::Request;
+    type Response = ();
+    type Error = S::Error;
+    type Future = Either<
+        Ready<Result<(), S::Error>>,
+        LocalBoxFuture<'static, Result<(), S::Error>>,
+    >;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, io: S::Request) -> Self::Future {
+        if self.state.paused.load(Ordering::Relaxed) {
+            // dropping the io object closes the connection
+            self.state.rejected.fetch_add(1, Ordering::Relaxed);
+            drop(io);
+            return Either::Left(ok(()));
+        }
+
+        self.state.accepted.fetch_add(1, Ordering::Relaxed);
+        self.state.active.fetch_add(1, Ordering::Relaxed);
+        let guard = ActiveGuard(self.state.clone());
+        let fut = self.service.call(io);
+        Either::Right(
+            async move {
+                let _guard = guard;
+                fut.await
+            }
+            .boxed_local(),
+        )
+    }
+}
+
+/// Decrements active connections counter, connection future could be
+/// dropped before completion on worker shutdown
+struct ActiveGuard(Arc<ListenerState>);
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_service::fn_service;
+    use futures::channel::oneshot;
+
+    use super::*;
+
+    #[actori_rt::test]
+    async fn test_listener_gate() {
+        let listeners = Listeners::default();
+        let state = listeners.register("public");
+        assert_eq!(listeners.names(), vec!["public".to_owned()]);
+        assert!(listeners.stats("admin").is_none());
+        assert!(!listeners.pause("admin"));
+
+        let factory = ListenerGate::new(
+            state,
+            fn_service(|rx: oneshot::Receiver<()>| async move {
+                let _ = rx.await;
+                Ok::<_, ()>(())
+            }),
+        );
+        let mut srv = factory.new_service(()).await.unwrap();
+
+        let (tx, rx) = oneshot::channel();
+        let fut = srv.call(rx);
+        let stats = listeners.stats("public").unwrap();
+        assert_eq!((stats.accepted, stats.active, stats.paused), (1, 1, false));
+        let _ = tx.send(());
+        fut.await.unwrap();
+        assert_eq!(listeners.stats("public").unwrap().active, 0);
+
+        // connections are closed while paused
+        assert!(listeners.pause("public"));
+        let (tx, rx) = oneshot::channel();
+        srv.call(rx).await.unwrap();
+        assert!(tx.send(()).is_err());
+        assert_eq!(
+            listeners.stats("public").unwrap(),
+            ListenerStats {
+                accepted: 1,
+                rejected: 1,
+                active: 0,
+                paused: true,
+            }
+        );
+
+        assert!(listeners.resume("public"));
+        let (tx, rx) = oneshot::channel();
+        let fut = srv.call(rx);
+        let _ = tx.send(());
+        fut.await.unwrap();
+        assert_eq!(listeners.stats("public").unwrap().accepted, 2);
+    }
+}
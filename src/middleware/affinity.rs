@@ -0,0 +1,349 @@
+//! `Middleware` for sticky sessions behind load balancers
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actori_http::cookie::{Cookie, CookieJar, Key};
+use actori_http::http::ConnectionType;
+use actori_rt::time::delay_for;
+use actori_service::{Service, Transform};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::{Server, ServiceRequest, ServiceResponse};
+use crate::error::Error;
+use crate::HttpMessage;
+
+/// Affinity of the request, available in request extensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Affinity {
+    /// Id of the instance that handles the request.
+    pub instance: String,
+    /// Affinity state of the client.
+    pub status: AffinityStatus,
+    /// Instance is draining, client is asked to move to another instance.
+    pub draining: bool,
+}
+
+/// Affinity state of the client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AffinityStatus {
+    /// Request does not carry a valid affinity cookie.
+    New,
+    /// Client is bound to the current instance.
+    Bound,
+    /// Client was bound to another instance, i.e. the instance is draining
+    /// or it is not available. Session state that is kept in memory of
+    /// that instance is lost.
+    Moved(String),
+}
+
+/// Draining state shared between workers.
+///
+/// Handle could be cloned, clones share state. Create it outside of the
+/// application factory and pass clones to `SessionAffinity` of every worker.
+#[derive(Debug, Clone, Default)]
+pub struct Draining(Arc<AtomicBool>);
+
+impl Draining {
+    /// Create handle, instance is not draining.
+    pub fn new() -> Self {
+        Draining::default()
+    }
+
+    /// Check if instance is draining.
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Mark instance as draining or bring it back.
+    pub fn set_draining(&self, draining: bool) {
+        self.0.store(draining, Ordering::Release)
+    }
+
+    /// Mark instance as draining and stop the server gracefully after
+    /// the grace period.
+    ///
+    /// During grace period affinity cookies are removed and keep-alive
+    /// connections are closed after each response, so the load balancer
+    /// moves clients to other instances.
+    pub async fn shutdown(self, server: Server, grace: Duration) {
+        self.set_draining(true);
+        delay_for(grace).await;
+        server.stop(true).await
+    }
+}
+
+/// `Middleware` for sticky sessions behind load balancers.
+///
+/// Middleware sets a signed cookie with the id of the instance, a load
+/// balancer routes requests with the cookie to the same instance. Cookie
+/// of another instance is replaced, cookies with invalid signatures are
+/// ignored. Request affinity is available as `Affinity` in request
+/// extensions.
+///
+/// When the instance is marked as draining with the `Draining` handle,
+/// affinity cookies are removed from responses and connections are not
+/// kept alive. `Draining::shutdown()` marks instance as draining and stops
+/// the server after a grace period, which eases blue/green deployments.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actori_web::cookie::Key;
+/// use actori_web::middleware::{Draining, SessionAffinity};
+/// use actori_web::{web, App, HttpResponse, HttpServer};
+///
+/// #[actori_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     let key = Key::generate();
+///     let draining = Draining::new();
+///
+///     let drain = draining.clone();
+///     let srv = HttpServer::new(move || {
+///         App::new()
+///             .wrap(SessionAffinity::new("blue-1", &key).draining(drain.clone()))
+///             .route("/", web::get().to(|| HttpResponse::Ok()))
+///     })
+///     .disable_signals()
+///     .bind("127.0.0.1:0")?
+///     .run();
+///
+///     // on deployment of a new version
+///     draining.shutdown(srv, Duration::from_millis(10)).await;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SessionAffinity {
+    instance: String,
+    key: Key,
+    name: String,
+    path: String,
+    secure: bool,
+    draining: Draining,
+}
+
+impl SessionAffinity {
+    /// Create `SessionAffinity` middleware for the instance id, cookies
+    /// are signed with the key.
+    ///
+    /// Instances behind the same load balancer must use the same key.
+    pub fn new(instance: &str, key: &Key) -> Self {
+        SessionAffinity {
+            instance: instance.to_string(),
+            key: key.clone(),
+            name: "affinity".to_string(),
+            path: "/".to_string(),
+            secure: false,
+            draining: Draining::new(),
+        }
+    }
+
+    /// Set cookie name. By default name is `affinity`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set cookie path. By default path is `/`.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Send cookie over https only.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Use shared draining state.
+    pub fn draining(mut self, draining: Draining) -> Self {
+        self.draining = draining;
+        self
+    }
+
+    /// Instance id from the signed request cookie
+    fn instance_of(&self, req: &ServiceRequest) -> Option<String> {
+        let cookie = req.cookie(&self.name)?;
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie);
+        jar.signed(&self.key)
+            .get(&self.name)
+            .map(|c| c.value().to_string())
+    }
+
+    fn cookie(&self) -> Cookie<'static> {
+        let mut jar = CookieJar::new();
+        jar.signed(&self.key)
+            .add(Cookie::new(self.name.clone(), self.instance.clone()));
+
+        let mut cookie = jar.get(&self.name).unwrap().clone();
+        cookie.set_path(self.path.clone());
+        cookie.set_http_only(true);
+        cookie.set_secure(self.secure);
+        cookie
+    }
+
+    fn removal(&self) -> Cookie<'static> {
+        let mut cookie = Cookie::named(self.name.clone());
+        cookie.set_path(self.path.clone());
+
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie.clone());
+        jar.remove(cookie);
+        jar.delta().next().unwrap().clone()
+    }
+}
+
+impl<S, B> Transform<S> for SessionAffinity
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SessionAffinityMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(SessionAffinityMiddleware {
+            service,
+            inner: self.clone(),
+        })
+    }
+}
+
+pub struct SessionAffinityMiddleware<S> {
+    service: S,
+    inner: SessionAffinity,
+}
+
+impl<S, B> Service for SessionAffinityMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let status = match self.inner.instance_of(&req) {
+            None => AffinityStatus::New,
+            Some(ref id) if *id == self.inner.instance => AffinityStatus::Bound,
+            Some(id) => AffinityStatus::Moved(id),
+        };
+        let draining = self.inner.draining.is_draining();
+
+        // draining instance removes only its own cookies, cookies of other
+        // instances are left to them
+        let cookie = match status {
+            AffinityStatus::Bound if draining => Some(self.inner.removal()),
+            AffinityStatus::New | AffinityStatus::Moved(_) if !draining => {
+                Some(self.inner.cookie())
+            }
+            _ => None,
+        };
+
+        req.extensions_mut().insert(Affinity {
+            instance: self.inner.instance.clone(),
+            status,
+            draining,
+        });
+        let fut = self.service.call(req);
+
+        async move {
+            let mut res = fut.await?;
+            if let Some(cookie) = cookie {
+                res.response_mut().add_cookie(&cookie)?;
+            }
+            if draining {
+                res.response_mut()
+                    .head_mut()
+                    .set_connection_type(ConnectionType::Close);
+            }
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::SET_COOKIE;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    fn affinity(req: crate::HttpRequest) -> HttpResponse {
+        let affinity = req.extensions().get::<Affinity>().unwrap().clone();
+        HttpResponse::Ok().body(format!("{:?}", affinity.status))
+    }
+
+    #[actori_rt::test]
+    async fn test_affinity() {
+        let key = Key::generate();
+        let draining = Draining::new();
+        let mut srv = init_service(
+            App::new()
+                .wrap(SessionAffinity::new("one", &key).draining(draining.clone()))
+                .route("/", web::get().to(affinity)),
+        )
+        .await;
+
+        // new client gets a signed cookie
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        let cookie = resp.response().cookies().next().unwrap().into_owned();
+        assert_eq!(cookie.name(), "affinity");
+        assert!(cookie.value().ends_with("one"));
+        assert_eq!(read_body(resp).await, "New");
+
+        // bound client, cookie is not re-sent
+        let req = TestRequest::default().cookie(cookie.clone()).to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(SET_COOKIE).is_none());
+        assert_eq!(read_body(resp).await, "Bound");
+
+        // cookie of another instance is replaced
+        let other = SessionAffinity::new("two", &key).cookie();
+        let req = TestRequest::default().cookie(other).to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp
+            .response()
+            .cookies()
+            .next()
+            .unwrap()
+            .value()
+            .ends_with("one"));
+        assert_eq!(read_body(resp).await, "Moved(\"two\")");
+
+        // tampered cookie is ignored
+        let tampered = Cookie::new("affinity", cookie.value().replace("one", "two"));
+        let req = TestRequest::default().cookie(tampered).to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, "New");
+
+        // draining instance removes its cookie and closes connection
+        draining.set_draining(true);
+        let req = TestRequest::default().cookie(cookie).to_request();
+        let resp = call_service(&mut srv, req).await;
+        let removal = resp.response().cookies().next().unwrap().into_owned();
+        assert_eq!(removal.value(), "");
+        assert!(!resp.response().keep_alive());
+
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(SET_COOKIE).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+//! `Middleware` for limiting request body size
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actori_service::{Service, Transform};
+use bytes::Bytes;
+use futures::future::{ok, Either, Ready};
+use futures::Stream;
+
+use crate::dev::{Payload, ServiceRequest, ServiceResponse};
+use crate::error::{Error, PayloadError};
+use crate::http::header::CONTENT_LENGTH;
+use crate::HttpMessage;
+
+/// `Middleware` for limiting request body size.
+///
+/// Requests with `Content-Length` bigger than the limit are rejected with
+/// *413 Payload Too Large* response before the handler runs. Streamed
+/// payload fails with `PayloadError::Overflow` once the limit is exceeded,
+/// `Json`, `Form`, `Bytes`, `String`, `Multipart` and `Payload` extractors
+/// render it as *413 Payload Too Large* response.
+///
+/// Limit applies in addition to the limits of extractor configurations.
+///
+/// ```rust
+/// use actori_web::{middleware::BodyLimit, web, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(BodyLimit::new(1_048_576))
+///         .service(
+///             web::resource("/upload")
+///                 .wrap(BodyLimit::new(16_777_216))
+///                 .to(|| HttpResponse::Ok()),
+///         );
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit {
+    limit: u64,
+}
+
+impl BodyLimit {
+    /// Create `BodyLimit` middleware with max body size in bytes.
+    pub fn new(limit: u64) -> Self {
+        BodyLimit { limit }
+    }
+}
+
+impl<S, B> Transform<S> for BodyLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = BodyLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(BodyLimitMiddleware {
+            service,
+            limit: self.limit,
+        })
+    }
+}
+
+pub struct BodyLimitMiddleware<S> {
+    service: S,
+    limit: u64,
+}
+
+impl<S, B> Service for BodyLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let len = req
+            .headers()
+            .get(&CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok());
+
+        if let Some(len) = len {
+            if len > self.limit {
+                log::debug!(
+                    "Request body is bigger than allowed: {} > {}",
+                    len,
+                    self.limit
+                );
+                return Either::Right(ok(req.error_response(PayloadError::Overflow)));
+            }
+        }
+
+        let payload = LimitPayload {
+            payload: req.take_payload(),
+            remaining: self.limit,
+        };
+        req.set_payload(Payload::Stream(Box::pin(payload)));
+        Either::Left(self.service.call(req))
+    }
+}
+
+struct LimitPayload {
+    payload: Payload,
+    remaining: u64,
+}
+
+impl Stream for LimitPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        match Pin::new(&mut this.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                let len = chunk.len() as u64;
+                if len > this.remaining {
+                    this.remaining = 0;
+                    Poll::Ready(Some(Err(PayloadError::Overflow)))
+                } else {
+                    this.remaining -= len;
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actori_rt::test]
+    async fn test_content_length() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(BodyLimit::new(4))
+                .route("/", web::post().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_LENGTH, "5")
+            .set_payload(Bytes::from_static(b"12345"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::post()
+            .header(CONTENT_LENGTH, "4")
+            .set_payload(Bytes::from_static(b"1234"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actori_rt::test]
+    async fn test_streamed_payload() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(BodyLimit::new(4))
+                .route(
+                    "/bytes",
+                    web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+                )
+                .route(
+                    "/payload",
+                    web::post().to(|mut pl: web::Payload| async move {
+                        let mut size = 0;
+                        while let Some(chunk) = pl.next().await {
+                            size += chunk?.len();
+                        }
+                        Ok::<_, Error>(HttpResponse::Ok().body(size.to_string()))
+                    }),
+                ),
+        )
+        .await;
+
+        // payload without content length
+        for path in &["/bytes", "/payload"] {
+            let req = TestRequest::post()
+                .uri(path)
+                .set_payload(Bytes::from_static(b"12345"))
+                .to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        let req = TestRequest::post()
+            .uri("/payload")
+            .set_payload(Bytes::from_static(b"123"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"3"));
+    }
+}
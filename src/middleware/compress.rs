@@ -1,34 +1,51 @@
 //! `Middleware` for compressing response body.
-use std::cmp;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
-use std::str::FromStr;
+use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actori_http::body::MessageBody;
-use actori_http::encoding::Encoder;
-use actori_http::http::header::{ContentEncoding, ACCEPT_ENCODING};
-use actori_http::Error;
+use actori_http::body::{BodySize, MessageBody};
+use actori_http::encoding::{negotiate, Encoder};
+use actori_http::http::header::{ContentEncoding, CONTENT_TYPE};
+use actori_http::{Error, ResponseHead};
 use actori_service::{Service, Transform};
 use futures::future::{ok, Ready};
 use pin_project::pin_project;
 
-use crate::dev::BodyEncoding;
+use crate::dev::{BodyEncoding, Enc};
 use crate::service::{ServiceRequest, ServiceResponse};
 
 #[derive(Debug, Clone)]
 /// `Middleware` for compressing response body.
 ///
+/// Encoding is negotiated with `Accept-Encoding` request header, quality
+/// values are honored, encodings with `q=0` are never used.
+///
 /// Use `BodyEncoding` trait for overriding response compression.
 /// To disable compression set encoding to `ContentEncoding::Identity` value.
 ///
+/// Encoding could be overridden for a whole resource or for a route with
+/// `Resource::encoding()` and `Route::encoding()` methods, override is
+/// applied to error responses as well. Encoding of the response takes
+/// precedence over route encoding, route encoding takes precedence over
+/// resource encoding.
+///
+/// Bodies smaller than `min_size()` and responses with excluded content
+/// types, i.e. already compressed images, are sent as is. Encoding set
+/// with `BodyEncoding` is applied regardless of these settings.
+///
 /// ```rust
 /// use actori_web::{web, middleware, App, HttpResponse};
 ///
 /// fn main() {
 ///     let app = App::new()
-///         .wrap(middleware::Compress::default())
+///         .wrap(
+///             middleware::Compress::default()
+///                 .min_size(1024)
+///                 .exclude_content_type("image/*")
+///                 .exclude_content_type("application/zip"),
+///         )
 ///         .service(
 ///             web::resource("/test")
 ///                 .route(web::get().to(|| HttpResponse::Ok()))
@@ -36,12 +53,67 @@ use crate::service::{ServiceRequest, ServiceResponse};
 ///         );
 /// }
 /// ```
-pub struct Compress(ContentEncoding);
+pub struct Compress {
+    encoding: ContentEncoding,
+    min_size: u64,
+    exclude: Vec<String>,
+}
 
 impl Compress {
     /// Create new `Compress` middleware with default encoding.
     pub fn new(encoding: ContentEncoding) -> Self {
-        Compress(encoding)
+        Compress {
+            encoding,
+            min_size: 0,
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Do not compress bodies smaller than `size` bytes.
+    ///
+    /// Only bodies with known size are checked, streaming bodies are always
+    /// compressed. By default all bodies are compressed.
+    pub fn min_size(mut self, size: u64) -> Self {
+        self.min_size = size;
+        self
+    }
+
+    /// Do not compress responses with the content type.
+    ///
+    /// Content type is either full mime type, i.e. `image/jpeg`, or a type
+    /// with wildcard subtype, i.e. `image/*`. Parameters of response content
+    /// type are ignored.
+    pub fn exclude_content_type<T: AsRef<str>>(mut self, mime: T) -> Self {
+        self.exclude.push(mime.as_ref().trim().to_ascii_lowercase());
+        self
+    }
+
+    /// Check if response should be sent as is
+    fn skip(&self, head: &ResponseHead, size: BodySize) -> bool {
+        match size {
+            BodySize::Sized(size) if (size as u64) < self.min_size => return true,
+            BodySize::Sized64(size) if size < self.min_size => return true,
+            _ => (),
+        }
+
+        if self.exclude.is_empty() {
+            return false;
+        }
+        let ct = match head
+            .headers()
+            .get(&CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(ct) => ct.split(';').next().unwrap().trim().to_ascii_lowercase(),
+            None => return false,
+        };
+        self.exclude.iter().any(|mime| {
+            if mime.ends_with("/*") {
+                ct.starts_with(&mime[..mime.len() - 1])
+            } else {
+                *mime == ct
+            }
+        })
     }
 }
 
@@ -66,14 +138,14 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(CompressMiddleware {
             service,
-            encoding: self.0,
+            config: Rc::new(self.clone()),
         })
     }
 }
 
 pub struct CompressMiddleware<S> {
     service: S,
-    encoding: ContentEncoding,
+    config: Rc<Compress>,
 }
 
 impl<S, B> Service for CompressMiddleware<S>
@@ -92,18 +164,11 @@ where
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
         // negotiate content-encoding
-        let encoding = if let Some(val) = req.headers().get(&ACCEPT_ENCODING) {
-            if let Ok(enc) = val.to_str() {
-                AcceptEncoding::parse(enc, self.encoding)
-            } else {
-                ContentEncoding::Identity
-            }
-        } else {
-            ContentEncoding::Identity
-        };
+        let encoding = negotiate(req.headers(), self.config.encoding);
 
         CompressResponse {
             encoding,
+            config: self.config.clone(),
             fut: self.service.call(req),
             _t: PhantomData,
         }
@@ -120,6 +185,7 @@ where
     #[pin]
     fut: S::Future,
     encoding: ContentEncoding,
+    config: Rc<Compress>,
     _t: PhantomData<B>,
 }
 
@@ -133,90 +199,143 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
 
-        match futures::ready!(this.fut.poll(cx)) {
-            Ok(resp) => {
-                let enc = if let Some(enc) = resp.response().get_encoding() {
-                    enc
-                } else {
-                    *this.encoding
-                };
-
-                Poll::Ready(Ok(
-                    resp.map_body(move |head, body| Encoder::response(enc, head, body))
-                ))
-            }
-            Err(e) => Poll::Ready(Err(e)),
-        }
+        // errors are passed through, request is not kept while inner service runs
+        let resp = futures::ready!(this.fut.poll(cx))?;
+
+        let (enc, explicit) = if let Some(enc) = resp.response().get_encoding() {
+            (enc, true)
+        } else if let Some(enc) = resp.request().extensions().get::<Enc>() {
+            // resource or route override
+            (negotiate(resp.request().headers(), enc.0), false)
+        } else {
+            (*this.encoding, false)
+        };
+        let config = this.config.clone();
+
+        Poll::Ready(Ok(resp.map_body(move |head, body| {
+            let enc = if !explicit && config.skip(head, body.size()) {
+                ContentEncoding::Identity
+            } else {
+                enc
+            };
+            Encoder::response(enc, head, body)
+        })))
     }
 }
 
-struct AcceptEncoding {
-    encoding: ContentEncoding,
-    quality: f64,
-}
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
 
-impl Eq for AcceptEncoding {}
+    use super::*;
+    use crate::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpRequest, HttpResponse};
 
-impl Ord for AcceptEncoding {
-    #[allow(clippy::comparison_chain)]
-    fn cmp(&self, other: &AcceptEncoding) -> cmp::Ordering {
-        if self.quality > other.quality {
-            cmp::Ordering::Less
-        } else if self.quality < other.quality {
-            cmp::Ordering::Greater
-        } else {
-            cmp::Ordering::Equal
+    #[actori_rt::test]
+    async fn test_min_size_and_exclude() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(
+                    Compress::default()
+                        .min_size(16)
+                        .exclude_content_type("image/*")
+                        .exclude_content_type("application/zip"),
+                )
+                .route("/small", web::get().to(|| HttpResponse::Ok().body("small")))
+                .route(
+                    "/large",
+                    web::get().to(|| HttpResponse::Ok().body("large".repeat(10))),
+                )
+                .route(
+                    "/image",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .content_type("image/jpeg")
+                            .body("jpeg".repeat(10))
+                    }),
+                )
+                .route(
+                    "/zip",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .content_type("Application/Zip; charset=binary")
+                            .body("zip".repeat(10))
+                    }),
+                ),
+        )
+        .await;
+
+        for (path, compressed) in &[
+            ("/small", false),
+            ("/large", true),
+            ("/image", false),
+            ("/zip", false),
+        ] {
+            let req = TestRequest::with_uri(path)
+                .header(ACCEPT_ENCODING, "gzip")
+                .to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(
+                resp.headers().get(CONTENT_ENCODING).is_some(),
+                *compressed,
+                "{}",
+                path
+            );
         }
-    }
-}
 
-impl PartialOrd for AcceptEncoding {
-    fn partial_cmp(&self, other: &AcceptEncoding) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
+        let req = TestRequest::with_uri("/small")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"small"));
     }
-}
 
-impl PartialEq for AcceptEncoding {
-    fn eq(&self, other: &AcceptEncoding) -> bool {
-        self.quality == other.quality
-    }
-}
+    #[actori_rt::test]
+    async fn test_quality() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(Compress::default())
+                .route("/", web::get().to(|| HttpResponse::Ok().body("data"))),
+        )
+        .await;
 
-impl AcceptEncoding {
-    fn new(tag: &str) -> Option<AcceptEncoding> {
-        let parts: Vec<&str> = tag.split(';').collect();
-        let encoding = match parts.len() {
-            0 => return None,
-            _ => ContentEncoding::from(parts[0]),
-        };
-        let quality = match parts.len() {
-            1 => encoding.quality(),
-            _ => match f64::from_str(parts[1]) {
-                Ok(q) => q,
-                Err(_) => 0.0,
-            },
-        };
-        Some(AcceptEncoding { encoding, quality })
+        for (accept, encoding) in &[
+            ("gzip;q=1.0, br;q=0.5", Some("gzip")),
+            ("br;q=0, deflate;q=0.1", Some("deflate")),
+            ("gzip;q=0", None),
+        ] {
+            let req = TestRequest::default()
+                .header(ACCEPT_ENCODING, *accept)
+                .to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(
+                resp.headers()
+                    .get(CONTENT_ENCODING)
+                    .map(|v| v.to_str().unwrap()),
+                *encoding
+            );
+        }
     }
 
-    /// Parse a raw Accept-Encoding header value into an ordered list.
-    pub fn parse(raw: &str, encoding: ContentEncoding) -> ContentEncoding {
-        let mut encodings: Vec<_> = raw
-            .replace(' ', "")
-            .split(',')
-            .map(|l| AcceptEncoding::new(l))
-            .collect();
-        encodings.sort();
-
-        for enc in encodings {
-            if let Some(enc) = enc {
-                if encoding == ContentEncoding::Auto {
-                    return enc.encoding;
-                } else if encoding == enc.encoding {
-                    return encoding;
-                }
-            }
-        }
-        ContentEncoding::Identity
+    #[actori_rt::test]
+    async fn test_routing() {
+        let mut srv = init_service(
+            App::new().wrap(Compress::default()).service(
+                web::resource("/user/{id}")
+                    .encoding(ContentEncoding::Identity)
+                    .route(web::get().to(|req: HttpRequest| {
+                        HttpResponse::Ok().body(req.match_info()["id"].to_owned())
+                    })),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/user/1")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
     }
 }
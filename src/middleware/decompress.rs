@@ -0,0 +1,240 @@
+//! `Middleware` for decompressing request payloads
+use std::task::{Context, Poll};
+
+use actori_service::{Service, Transform};
+use futures::future::{ok, Ready};
+
+use crate::dev::{Payload, ServiceRequest, ServiceResponse};
+use crate::error::Error;
+use crate::http::header::{ContentEncoding, CONTENT_ENCODING, CONTENT_LENGTH};
+use crate::types::decompressed::GuardStream;
+use crate::HttpMessage;
+
+/// `Middleware` for decompressing request payloads.
+///
+/// Payload of a request with `gzip`, `deflate` or `br` (and `zstd` with
+/// `compress-zstd` feature) `Content-Encoding` is decoded while it streams
+/// to the handler, so every extractor, including `web::Payload` and
+/// `Multipart`, sees decompressed data. `Content-Encoding` and
+/// `Content-Length` headers are removed from decoded requests. Requests
+/// with other encodings are passed as is.
+///
+/// Decoding fails with `PayloadError::Overflow`, rendered as
+/// *413 Payload Too Large* response by extractors, once decompressed size
+/// exceeds the limit, or once ratio of decompressed to compressed size
+/// exceeds max ratio, ratio is checked after the first 64Kb of output.
+/// Default limit is 4Mb, default max ratio is 100.
+///
+/// Use `web::Decompressed` extractor to decode payloads of specific
+/// handlers only.
+///
+/// ```rust
+/// use actori_web::{middleware::Decompress, web, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(Decompress::new().limit(16_777_216).max_ratio(20.0))
+///         .route("/upload", web::post().to(|| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Decompress {
+    limit: u64,
+    max_ratio: f64,
+}
+
+impl Default for Decompress {
+    fn default() -> Self {
+        Decompress {
+            limit: 4_194_304,
+            max_ratio: 100.0,
+        }
+    }
+}
+
+impl Decompress {
+    /// Create `Decompress` middleware with default limits.
+    pub fn new() -> Self {
+        Decompress::default()
+    }
+
+    /// Change max size of decompressed payload. By default max size is 4Mb
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Change max ratio of decompressed to compressed size. By default
+    /// max ratio is 100
+    pub fn max_ratio(mut self, ratio: f64) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Decompress
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = DecompressMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(DecompressMiddleware {
+            service,
+            cfg: *self,
+        })
+    }
+}
+
+pub struct DecompressMiddleware<S> {
+    service: S,
+    cfg: Decompress,
+}
+
+impl<S, B> Service for DecompressMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let encoding = req
+            .headers()
+            .get(&CONTENT_ENCODING)
+            .and_then(|enc| enc.to_str().ok())
+            .map(ContentEncoding::from)
+            .unwrap_or(ContentEncoding::Identity);
+
+        if is_supported(encoding) {
+            let payload = GuardStream::new(
+                req.take_payload(),
+                encoding,
+                self.cfg.limit,
+                self.cfg.max_ratio,
+            );
+            req.set_payload(Payload::Stream(Box::pin(payload)));
+
+            // payload is decoded, extractors must not decode it again
+            req.headers_mut().remove(&CONTENT_ENCODING);
+            req.headers_mut().remove(&CONTENT_LENGTH);
+        }
+        self.service.call(req)
+    }
+}
+
+/// Encodings with available decoder
+fn is_supported(encoding: ContentEncoding) -> bool {
+    match encoding {
+        ContentEncoding::Br | ContentEncoding::Gzip | ContentEncoding::Deflate => true,
+        #[cfg(feature = "compress-zstd")]
+        ContentEncoding::Zstd => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use bytes::Bytes;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(data).unwrap();
+        Bytes::from(enc.finish().unwrap())
+    }
+
+    #[actori_rt::test]
+    async fn test_decompress() {
+        let mut srv = init_service(App::new().wrap(Decompress::new()).route(
+            "/",
+            web::post().to(|req: web::HttpRequest, mut pl: web::Payload| async move {
+                assert!(req.headers().get(CONTENT_ENCODING).is_none());
+                let mut body = Vec::new();
+                while let Some(chunk) = pl.next().await {
+                    body.extend_from_slice(&chunk?);
+                }
+                Ok::<_, Error>(HttpResponse::Ok().body(body))
+            }),
+        ))
+        .await;
+
+        let body = gzip(b"hello world");
+        let req = TestRequest::post()
+            .header(CONTENT_ENCODING, "gzip")
+            .header(CONTENT_LENGTH, body.len().to_string())
+            .set_payload(body)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"hello world"));
+
+        let req = TestRequest::post()
+            .set_payload(Bytes::from_static(b"plain"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"plain"));
+    }
+
+    #[actori_rt::test]
+    async fn test_decompress_limits() {
+        let mut srv =
+            init_service(App::new().wrap(Decompress::new().limit(1024)).route(
+                "/",
+                web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+            ))
+            .await;
+
+        let req = TestRequest::post()
+            .header(CONTENT_ENCODING, "gzip")
+            .set_payload(gzip(&[b' '; 2048]))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::post()
+            .header(CONTENT_ENCODING, "gzip")
+            .set_payload(gzip(&[b' '; 512]))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await.len(), 512);
+
+        // ratio guard
+        let mut srv = init_service(App::new().wrap(Decompress::new()).route(
+            "/",
+            web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+        ))
+        .await;
+        let req = TestRequest::post()
+            .header(CONTENT_ENCODING, "gzip")
+            .set_payload(gzip(&[b' '; 200_000]))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
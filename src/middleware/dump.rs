@@ -0,0 +1,314 @@
+//! `Middleware` for dumping requests and responses to the log
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actori_service::{Service, Transform};
+use bytes::{Bytes, BytesMut};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::Stream;
+
+use crate::dev::{Payload, ServiceRequest, ServiceResponse};
+use crate::error::{Error, PayloadError};
+use crate::http::header::{
+    HeaderName, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE,
+};
+use crate::http::HeaderMap;
+use crate::HttpMessage;
+
+/// `Middleware` for dumping requests and responses to the log, for local
+/// debugging.
+///
+/// Request head, hex dump of the request body and response head are logged
+/// with `debug` level. Body is dumped as it is read by the application,
+/// dump is limited to the first 1024 bytes by default. Values of sensitive
+/// headers are replaced with `[redacted]`, by default `Authorization`,
+/// `Proxy-Authorization`, `Cookie` and `Set-Cookie` headers are redacted.
+///
+/// Middleware is available in debug builds, or with the `debug-dump`
+/// feature.
+///
+/// ```rust
+/// use actori_web::{middleware::DebugDump, web, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(DebugDump::new().body_limit(256).redact("x-api-key"))
+///         .route("/", web::post().to(|| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DebugDump(Rc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    body_limit: usize,
+    redacted: HashSet<HeaderName>,
+}
+
+impl DebugDump {
+    /// Create `DebugDump` middleware with default settings.
+    pub fn new() -> Self {
+        let mut redacted = HashSet::new();
+        redacted.insert(AUTHORIZATION);
+        redacted.insert(PROXY_AUTHORIZATION);
+        redacted.insert(COOKIE);
+        redacted.insert(SET_COOKIE);
+
+        DebugDump(Rc::new(Inner {
+            body_limit: 1024,
+            redacted,
+        }))
+    }
+
+    /// Set max number of dumped request body bytes, zero disables
+    /// body dump. By default limit is 1024 bytes.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().body_limit = limit;
+        self
+    }
+
+    /// Redact value of the header.
+    ///
+    /// Panics if header name is not valid.
+    pub fn redact(mut self, name: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).unwrap();
+        Rc::get_mut(&mut self.0).unwrap().redacted.insert(name);
+        self
+    }
+}
+
+impl Default for DebugDump {
+    fn default() -> Self {
+        DebugDump::new()
+    }
+}
+
+impl<S, B> Transform<S> for DebugDump
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = DebugDumpMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(DebugDumpMiddleware {
+            service,
+            inner: self.0.clone(),
+        })
+    }
+}
+
+pub struct DebugDumpMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for DebugDumpMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let mut head = format!("{} {} {:?}\n", req.method(), req.uri(), req.version());
+        self.inner.write_headers(&mut head, req.headers());
+        log::debug!("Request:\n{}", head);
+
+        if self.inner.body_limit > 0 {
+            let payload = DumpPayload {
+                payload: req.take_payload(),
+                buf: BytesMut::new(),
+                limit: self.inner.body_limit,
+                size: 0,
+                done: false,
+            };
+            req.set_payload(Payload::Stream(Box::pin(payload)));
+        }
+
+        let inner = self.inner.clone();
+        let fut = self.service.call(req);
+
+        async move {
+            let res = fut.await?;
+            let mut head =
+                format!("{:?} {}\n", res.response().head().version, res.status());
+            inner.write_headers(&mut head, res.headers());
+            log::debug!("Response:\n{}", head);
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+impl Inner {
+    fn write_headers(&self, buf: &mut String, headers: &HeaderMap) {
+        for (name, value) in headers.iter() {
+            if self.redacted.contains(name) {
+                let _ = writeln!(buf, "{}: [redacted]", name);
+            } else {
+                let _ = writeln!(buf, "{}: {:?}", name, value);
+            }
+        }
+    }
+}
+
+/// Request payload that copies first `limit` bytes for the dump
+struct DumpPayload {
+    payload: Payload,
+    buf: BytesMut,
+    limit: usize,
+    size: usize,
+    done: bool,
+}
+
+impl DumpPayload {
+    fn dump(&mut self, complete: bool) {
+        if !self.done {
+            self.done = true;
+            log::debug!(
+                "Request body, {} bytes{}:\n{}",
+                self.size,
+                if complete { "" } else { " read" },
+                hexdump(&self.buf)
+            );
+        }
+    }
+}
+
+impl Stream for DumpPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        match Pin::new(&mut this.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.size += chunk.len();
+                let n = this.limit.saturating_sub(this.buf.len()).min(chunk.len());
+                this.buf.extend_from_slice(&chunk[..n]);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                log::debug!("Request body error: {}", e);
+                this.dump(false);
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                this.dump(true);
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for DumpPayload {
+    fn drop(&mut self) {
+        // application did not read whole body
+        if self.size > 0 {
+            self.dump(false);
+        }
+    }
+}
+
+/// Hex dump with offsets and printable ascii characters, 16 bytes per line
+fn hexdump(data: &[u8]) -> String {
+    let mut s = String::new();
+    for (idx, line) in data.chunks(16).enumerate() {
+        let _ = write!(s, "{:08x} ", idx * 16);
+        for i in 0..16 {
+            match line.get(i) {
+                Some(b) => {
+                    let _ = write!(s, " {:02x}", b);
+                }
+                None => s.push_str("   "),
+            }
+        }
+        s.push_str("  |");
+        for b in line {
+            s.push(if b.is_ascii_graphic() || *b == b' ' {
+                *b as char
+            } else {
+                '.'
+            });
+        }
+        s.push_str("|\n");
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::CONTENT_TYPE;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[test]
+    fn test_hexdump() {
+        assert_eq!(hexdump(b""), "");
+        assert_eq!(
+            hexdump(b"hello world\r\n0123456789"),
+            "00000000  68 65 6c 6c 6f 20 77 6f 72 6c 64 0d 0a 30 31 32  \
+             |hello world..012|\n\
+             00000010  33 34 35 36 37 38 39                             \
+             |3456789|\n"
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        let dump = DebugDump::new().redact("x-api-key");
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Basic secret".parse().unwrap());
+        headers.insert(
+            HeaderName::from_static("x-api-key"),
+            "secret".parse().unwrap(),
+        );
+        headers.insert(CONTENT_TYPE, "text/plain".parse().unwrap());
+
+        let mut buf = String::new();
+        dump.0.write_headers(&mut buf, &headers);
+        assert!(!buf.contains("secret"));
+        assert!(buf.contains("authorization: [redacted]"));
+        assert!(buf.contains("x-api-key: [redacted]"));
+        assert!(buf.contains("content-type: \"text/plain\""));
+    }
+
+    #[actori_rt::test]
+    async fn test_dump_body() {
+        let mut srv =
+            init_service(App::new().wrap(DebugDump::new().body_limit(4)).route(
+                "/",
+                web::post().to(|body: Bytes| HttpResponse::Ok().body(body)),
+            ))
+            .await;
+
+        // payload is passed unchanged
+        let req = TestRequest::post()
+            .set_payload(Bytes::from_static(b"hello world"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"hello world"));
+    }
+}
@@ -0,0 +1,860 @@
+//! `Middleware` for replaying responses of duplicate submissions
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use actori_http::body::to_bytes_limited;
+use actori_http::h1;
+use actori_service::{Service, Transform};
+use bytes::{Bytes, BytesMut};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use futures::StreamExt;
+
+use crate::dev::{
+    BodySize, MessageBody, Payload, ResponseBody, ServiceRequest, ServiceResponse,
+};
+use crate::error::{Error, ErrorInternalServerError, PayloadError};
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::http::{HeaderMap, Method, StatusCode};
+use crate::{HttpMessage, HttpResponse};
+
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+type ClientFn = Box<dyn Fn(&ServiceRequest) -> Option<String>>;
+
+/// Response stored for an idempotency key.
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    /// Response status code
+    pub status: StatusCode,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body
+    pub body: Bytes,
+}
+
+/// State of an idempotency key.
+#[derive(Debug, Clone)]
+pub enum KeyState {
+    /// Key is claimed by the current request.
+    New,
+    /// Request with the same key is being processed, value is the
+    /// fingerprint of its payload.
+    Pending(u64),
+    /// Request with the same key is completed, value is the fingerprint
+    /// of its payload and its response.
+    Completed(u64, StoredResponse),
+}
+
+/// Storage for idempotency keys and responses.
+///
+/// Keys could be kept in an external storage, i.e. redis, to detect
+/// duplicates that are sent to different server instances.
+pub trait IdempotencyStore {
+    /// Claim the key for processing.
+    ///
+    /// If key does not exist or it is expired, store must create it in
+    /// pending state with the payload fingerprint that expires after `ttl`
+    /// and return `KeyState::New`. For redis it is a
+    /// `SET key fingerprint NX PX ttl`.
+    fn claim(
+        &self,
+        key: &str,
+        fingerprint: u64,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<KeyState, Error>>;
+
+    /// Store response of the claimed key, response expires after `ttl`.
+    fn complete(
+        &self,
+        key: &str,
+        res: StoredResponse,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), Error>>;
+
+    /// Remove the claimed key, the next request with the key is processed.
+    fn release(&self, key: &str) -> LocalBoxFuture<'static, Result<(), Error>>;
+}
+
+/// In-memory idempotency store.
+///
+/// Store could be cloned, clones share keys. Create store outside of
+/// the application factory to share keys between workers.
+#[derive(Clone, Default)]
+pub struct MemoryIdempotencyStore(Arc<Mutex<MemoryInner>>);
+
+#[derive(Default)]
+struct MemoryInner {
+    keys: HashMap<String, (u64, Option<StoredResponse>, Instant)>,
+    ops: usize,
+}
+
+impl MemoryIdempotencyStore {
+    /// Create empty store.
+    pub fn new() -> Self {
+        MemoryIdempotencyStore::default()
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn claim(
+        &self,
+        key: &str,
+        fingerprint: u64,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<KeyState, Error>> {
+        let now = Instant::now();
+        let mut inner = self.0.lock().unwrap();
+
+        // purge expired keys from time to time
+        inner.ops += 1;
+        if inner.ops % 4096 == 0 {
+            inner.keys.retain(|_, (_, _, expires)| *expires > now);
+        }
+
+        let state = match inner.keys.get(key) {
+            Some((fp, Some(res), expires)) if *expires > now => {
+                KeyState::Completed(*fp, res.clone())
+            }
+            Some((fp, None, expires)) if *expires > now => KeyState::Pending(*fp),
+            _ => {
+                let entry = (fingerprint, None, now + ttl);
+                inner.keys.insert(key.to_owned(), entry);
+                KeyState::New
+            }
+        };
+        ok(state).boxed_local()
+    }
+
+    fn complete(
+        &self,
+        key: &str,
+        res: StoredResponse,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<(), Error>> {
+        let expires = Instant::now() + ttl;
+        let mut inner = self.0.lock().unwrap();
+        if let Some(entry) = inner.keys.get_mut(key) {
+            entry.1 = Some(res);
+            entry.2 = expires;
+        }
+        ok(()).boxed_local()
+    }
+
+    fn release(&self, key: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        self.0.lock().unwrap().keys.remove(key);
+        ok(()).boxed_local()
+    }
+}
+
+/// `Middleware` for detecting duplicate submissions, i.e. resubmitted
+/// forms or retried api requests, as described by IETF
+/// *The Idempotency-Key HTTP Header Field* draft.
+///
+/// Client marks a request with a unique key in `Idempotency-Key` header or
+/// in a cookie. Response of the first request with the key is stored for
+/// `ttl`, duplicates with the same method, path and key receive the stored
+/// response with `idempotent-replayed: true` header, handler is not called.
+/// Duplicate of a request that is still processed receives
+/// *409 Conflict* response. Request that reuses the key with a different
+/// payload receives *422 Unprocessable Entity* response.
+///
+/// Key is locked while the request is processed, lock is released if the
+/// request fails or is cancelled, and expires after `lock_ttl()`
+/// (default 30 seconds) if release does not reach the store.
+///
+/// Keys are shared by all clients unless they are scoped with `client()`,
+/// so a client that knows the key of another client could read its
+/// response. Keys should be scoped by an authenticated identity.
+///
+/// By default `POST` and `PATCH` requests are checked, requests without
+/// key are processed as usual unless key is `required()`. Request payload
+/// is buffered to compute its fingerprint, payload bigger than the limit
+/// (default 256Kb) is rejected with *413 Payload Too Large* response.
+///
+/// Server error responses, streaming responses and responses with body
+/// bigger than the limit (default 64Kb) are not stored, request could be
+/// repeated. If store fails or does not respond within timeout
+/// (default 1 second), request is processed as usual.
+///
+/// Middleware could be registered for an application, a scope or a
+/// resource, i.e. only for api scope.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actori_web::middleware::{Idempotency, MemoryIdempotencyStore};
+/// use actori_web::{web, App, HttpMessage, SeeOther};
+///
+/// fn main() {
+///     let store = MemoryIdempotencyStore::new();
+///
+///     let app = App::new().service(
+///         web::resource("/orders")
+///             .wrap(
+///                 Idempotency::new(store.clone(), Duration::from_secs(600))
+///                     .cookie("form-token")
+///                     .client(|req| {
+///                         req.cookie("session").map(|c| c.value().to_owned())
+///                     }),
+///             )
+///             .route(web::post().to(|| async { SeeOther::to("/orders/1") })),
+///     );
+/// }
+/// ```
+pub struct Idempotency(Rc<Inner>);
+
+struct Inner {
+    store: Box<dyn IdempotencyStore>,
+    ttl: Duration,
+    lock_ttl: Duration,
+    header: HeaderName,
+    client: Option<ClientFn>,
+    cookie: Option<String>,
+    methods: Vec<Method>,
+    required: bool,
+    payload_limit: usize,
+    limit: usize,
+    timeout: Duration,
+}
+
+impl Idempotency {
+    /// Create `Idempotency` middleware, responses are stored for `ttl`.
+    pub fn new<T>(store: T, ttl: Duration) -> Self
+    where
+        T: IdempotencyStore + 'static,
+    {
+        Idempotency(Rc::new(Inner {
+            store: Box::new(store),
+            ttl,
+            lock_ttl: Duration::from_secs(30),
+            header: HeaderName::from_static(IDEMPOTENCY_KEY),
+            client: None,
+            cookie: None,
+            methods: vec![Method::POST, Method::PATCH],
+            required: false,
+            payload_limit: 262_144,
+            limit: 65_536,
+            timeout: Duration::from_secs(1),
+        }))
+    }
+
+    /// Set name of the header with idempotency key.
+    ///
+    /// By default `Idempotency-Key` header is used.
+    pub fn header(mut self, name: HeaderName) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().header = name;
+        self
+    }
+
+    /// Scope keys by client identity, i.e. by authorization header, session
+    /// cookie or peer address.
+    ///
+    /// Requests for which function returns `None` are processed as requests
+    /// without idempotency key.
+    pub fn client<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Rc::get_mut(&mut self.0).unwrap().client = Some(Box::new(f));
+        self
+    }
+
+    /// Set max time the key stays locked while the request is processed.
+    ///
+    /// Lock is released when request completes or is cancelled, lock ttl
+    /// applies only if release does not reach the store, i.e. if worker
+    /// is stopped. By default lock ttl is 30 seconds.
+    pub fn lock_ttl(mut self, ttl: Duration) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().lock_ttl = ttl;
+        self
+    }
+
+    /// Read idempotency key from the cookie if request has no key header.
+    pub fn cookie<T: Into<String>>(mut self, name: T) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().cookie = Some(name.into());
+        self
+    }
+
+    /// Set methods of the checked requests.
+    ///
+    /// By default `POST` and `PATCH` requests are checked.
+    pub fn methods(mut self, methods: &[Method]) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().methods = methods.to_vec();
+        self
+    }
+
+    /// Reject checked requests without idempotency key with
+    /// *400 Bad Request* response. By default it is false.
+    pub fn required(mut self, required: bool) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().required = required;
+        self
+    }
+
+    /// Set max size of request payload, default is 256Kb.
+    pub fn payload_limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().payload_limit = limit;
+        self
+    }
+
+    /// Set max size of stored response body, default is 64Kb.
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().limit = limit;
+        self
+    }
+
+    /// Set max time to wait for store response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().timeout = timeout;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Idempotency
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IdempotencyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(IdempotencyMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.0.clone(),
+        })
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for IdempotencyMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        if !self.inner.methods.contains(req.method()) {
+            return self.service.borrow_mut().call(req).boxed_local();
+        }
+        let key = match self.inner.key(&req) {
+            Some(key) => key,
+            None if self.inner.required => {
+                let res = HttpResponse::BadRequest().body("Idempotency key is required");
+                return ok(req.into_response(res.into_body())).boxed_local();
+            }
+            None => return self.service.borrow_mut().call(req).boxed_local(),
+        };
+        let inner = self.inner.clone();
+        let srv = self.service.clone();
+        let payload = req.take_payload();
+
+        async move {
+            let body = match read_payload(payload, inner.payload_limit).await {
+                Ok(body) => body,
+                Err(PayloadError::Overflow) => {
+                    let res = HttpResponse::PayloadTooLarge().finish();
+                    return Ok(req.into_response(res.into_body()));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let fingerprint = fxhash::hash64(&body);
+            req.set_payload(bytes_payload(body));
+
+            let fut = inner.store.claim(&key, fingerprint, inner.lock_ttl);
+            match actori_rt::time::timeout(inner.timeout, fut).await {
+                Ok(Ok(KeyState::New)) => (),
+                Ok(Ok(KeyState::Pending(fp))) | Ok(Ok(KeyState::Completed(fp, _)))
+                    if fp != fingerprint =>
+                {
+                    let res = HttpResponse::UnprocessableEntity()
+                        .body("Idempotency key is reused with a different payload");
+                    return Ok(req.into_response(res.into_body()));
+                }
+                Ok(Ok(KeyState::Pending(_))) => {
+                    let res = HttpResponse::Conflict().finish();
+                    return Ok(req.into_response(res.into_body()));
+                }
+                Ok(Ok(KeyState::Completed(_, res))) => {
+                    return Ok(req.into_response(replay(res)));
+                }
+                Ok(Err(e)) => {
+                    log::warn!("Idempotency store error: {}", e);
+                    let fut = srv.borrow_mut().call(req);
+                    return fut.await;
+                }
+                Err(_) => {
+                    log::warn!("Idempotency store timed out");
+                    let fut = srv.borrow_mut().call(req);
+                    return fut.await;
+                }
+            }
+
+            // release the key if request is cancelled
+            let mut guard = ClaimGuard(Some((inner.clone(), key.clone())));
+
+            let fut = srv.borrow_mut().call(req);
+            let res = match fut.await {
+                Ok(res) => res,
+                Err(e) => {
+                    inner.release(&key).await;
+                    guard.disarm();
+                    return Err(e);
+                }
+            };
+            let res = inner.store_response(&key, res).await;
+            guard.disarm();
+            res
+        }
+        .boxed_local()
+    }
+}
+
+impl Inner {
+    /// Idempotency key of the request, scoped by client, method and path
+    fn key(&self, req: &ServiceRequest) -> Option<String> {
+        let key = match req.headers().get(&self.header) {
+            Some(val) => val.to_str().ok()?.to_owned(),
+            None => req
+                .cookie(self.cookie.as_ref()?)
+                .map(|c| c.value().to_owned())?,
+        };
+        if key.is_empty() {
+            return None;
+        }
+        match self.client {
+            Some(ref client) => {
+                let client = client(req)?;
+                Some(format!(
+                    "{} {} {} {}",
+                    client,
+                    req.method(),
+                    req.path(),
+                    key
+                ))
+            }
+            None => Some(format!("{} {} {}", req.method(), req.path(), key)),
+        }
+    }
+
+    async fn store_response<B: MessageBody>(
+        &self,
+        key: &str,
+        mut res: ServiceResponse<B>,
+    ) -> Result<ServiceResponse<B>, Error> {
+        let fits = match res.response().body().size() {
+            BodySize::None | BodySize::Empty => true,
+            BodySize::Sized(len) => len <= self.limit,
+            BodySize::Sized64(len) => len <= self.limit as u64,
+            BodySize::Stream => false,
+        };
+        if !fits || res.status().is_server_error() {
+            self.release(key).await;
+            return Ok(res);
+        }
+
+        let body = match to_bytes_limited(res.take_body(), self.limit).await {
+            Ok(body) => body,
+            Err(e) => {
+                self.release(key).await;
+                return Err(ErrorInternalServerError(e));
+            }
+        };
+        let stored = StoredResponse {
+            status: res.status(),
+            headers: res.headers().clone(),
+            body: body.clone(),
+        };
+        let fut = self.store.complete(key, stored, self.ttl);
+        match actori_rt::time::timeout(self.timeout, fut).await {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => log::warn!("Idempotency store error: {}", e),
+            Err(_) => log::warn!("Idempotency store timed out"),
+        }
+        Ok(res.map_body(|_, _| ResponseBody::Other(body.into())))
+    }
+
+    async fn release(&self, key: &str) {
+        let fut = self.store.release(key);
+        match actori_rt::time::timeout(self.timeout, fut).await {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => log::warn!("Idempotency store error: {}", e),
+            Err(_) => log::warn!("Idempotency store timed out"),
+        }
+    }
+}
+
+/// Releases claimed key if request future is dropped before completion
+struct ClaimGuard(Option<(Rc<Inner>, String)>);
+
+impl ClaimGuard {
+    fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        if let Some((inner, key)) = self.0.take() {
+            actori_rt::spawn(async move { inner.release(&key).await });
+        }
+    }
+}
+
+/// Read request payload up to the limit
+async fn read_payload(
+    mut payload: Payload,
+    limit: usize,
+) -> Result<Bytes, PayloadError> {
+    let mut body = BytesMut::new();
+    while let Some(item) = payload.next().await {
+        let chunk = item?;
+        if body.len() + chunk.len() > limit {
+            return Err(PayloadError::Overflow);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
+
+fn bytes_payload(body: Bytes) -> Payload {
+    let (_, mut payload) = h1::Payload::create(true);
+    payload.unread_data(body);
+    Payload::from(payload)
+}
+
+/// Response for a duplicate of the completed request
+fn replay<B>(res: StoredResponse) -> actori_http::Response<B> {
+    let mut resp = HttpResponse::build(res.status).body(res.body);
+    *resp.headers_mut() = res.headers;
+    resp.headers_mut().insert(
+        HeaderName::from_static(IDEMPOTENT_REPLAYED),
+        HeaderValue::from_static("true"),
+    );
+    resp.into_body()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::http::header::{AUTHORIZATION, COOKIE, LOCATION};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, SeeOther};
+
+    #[actori_rt::test]
+    async fn test_idempotency() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let cnt = counter.clone();
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/orders")
+                    .wrap(Idempotency::new(
+                        MemoryIdempotencyStore::new(),
+                        Duration::from_secs(60),
+                    ))
+                    .route(web::post().to(move || {
+                        let n = cnt.fetch_add(1, Ordering::SeqCst) + 1;
+                        HttpResponse::Created().body(n.to_string())
+                    }))
+                    .route(web::get().to(|| HttpResponse::Ok())),
+            ),
+        )
+        .await;
+
+        let req = || {
+            TestRequest::post()
+                .uri("/orders")
+                .header(IDEMPOTENCY_KEY, "key-1")
+                .to_request()
+        };
+        let resp = call_service(&mut srv, req()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(resp.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+
+        let resp = call_service(&mut srv, req()).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers().get(IDEMPOTENT_REPLAYED).unwrap(), "true");
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        // different key
+        let req = TestRequest::post()
+            .uri("/orders")
+            .header(IDEMPOTENCY_KEY, "key-2")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"2"));
+
+        // requests without key and safe requests are not checked
+        for _ in 0..2 {
+            let req = TestRequest::post().uri("/orders").to_request();
+            call_service(&mut srv, req).await;
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+
+        let req = TestRequest::with_uri("/orders")
+            .method(Method::GET)
+            .header(IDEMPOTENCY_KEY, "key-1")
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actori_rt::test]
+    async fn test_idempotency_cookie() {
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/form")
+                    .wrap(
+                        Idempotency::new(
+                            MemoryIdempotencyStore::new(),
+                            Duration::from_secs(60),
+                        )
+                        .cookie("form-token"),
+                    )
+                    .to(|| async { SeeOther::to("/done") }),
+            ),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::post()
+                .uri("/form")
+                .header(COOKIE, "form-token=abc")
+                .to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+            assert_eq!(resp.headers().get(LOCATION).unwrap(), "/done");
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_idempotency_payload() {
+        let mut srv = init_service(
+            App::new().service(
+                web::scope("/api")
+                    .wrap(
+                        Idempotency::new(
+                            MemoryIdempotencyStore::new(),
+                            Duration::from_secs(60),
+                        )
+                        .required(true)
+                        .payload_limit(16),
+                    )
+                    .route(
+                        "/slow",
+                        web::post().to(|| async {
+                            actori_rt::time::delay_for(Duration::from_millis(100)).await;
+                            HttpResponse::Ok()
+                        }),
+                    )
+                    .route("/echo", web::post().to(|body: Bytes| async move { body })),
+            ),
+        )
+        .await;
+
+        let req = |path: &str, key: &str, body: &'static [u8]| {
+            TestRequest::post()
+                .uri(path)
+                .header(IDEMPOTENCY_KEY, key)
+                .set_payload(body)
+                .to_request()
+        };
+
+        // payload is available to the handler
+        let resp = call_service(&mut srv, req("/api/echo", "1", b"data")).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"data"));
+        let resp = call_service(&mut srv, req("/api/echo", "1", b"data")).await;
+        assert_eq!(resp.headers().get(IDEMPOTENT_REPLAYED).unwrap(), "true");
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"data"));
+
+        // key is reused with different payload
+        let resp = call_service(&mut srv, req("/api/echo", "1", b"other")).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let resp = call_service(&mut srv, req("/api/echo", "2", &[b'x'; 17])).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req_without_key = TestRequest::post().uri("/api/echo").to_request();
+        let resp = call_service(&mut srv, req_without_key).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // concurrent duplicate
+        let first = srv.call(req("/api/slow", "3", b""));
+        let second = srv.call(req("/api/slow", "3", b""));
+        let (first, second) = futures::future::join(first, second).await;
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(second.unwrap().status(), StatusCode::CONFLICT);
+    }
+
+    #[actori_rt::test]
+    async fn test_idempotency_cancelled() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let cnt = counter.clone();
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/slow")
+                    .wrap(Idempotency::new(
+                        MemoryIdempotencyStore::new(),
+                        Duration::from_secs(60),
+                    ))
+                    .to(move || {
+                        let n = cnt.fetch_add(1, Ordering::SeqCst);
+                        async move {
+                            if n == 0 {
+                                actori_rt::time::delay_for(Duration::from_secs(10))
+                                    .await;
+                            }
+                            HttpResponse::Ok()
+                        }
+                    }),
+            ),
+        )
+        .await;
+
+        let req = || {
+            TestRequest::post()
+                .uri("/slow")
+                .header(IDEMPOTENCY_KEY, "key")
+                .to_request()
+        };
+
+        // request is cancelled, i.e. client disconnected
+        let fut = srv.call(req());
+        assert!(actori_rt::time::timeout(Duration::from_millis(50), fut)
+            .await
+            .is_err());
+        actori_rt::time::delay_for(Duration::from_millis(10)).await;
+
+        // key is released, retry is processed
+        let resp = call_service(&mut srv, req()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[actori_rt::test]
+    async fn test_idempotency_client() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let cnt = counter.clone();
+        let mut srv = init_service(
+            App::new().service(
+                web::resource("/orders")
+                    .wrap(
+                        Idempotency::new(
+                            MemoryIdempotencyStore::new(),
+                            Duration::from_secs(60),
+                        )
+                        .client(|req| {
+                            req.headers()
+                                .get(AUTHORIZATION)
+                                .and_then(|v| v.to_str().ok())
+                                .map(|v| v.to_owned())
+                        }),
+                    )
+                    .to(move || {
+                        let n = cnt.fetch_add(1, Ordering::SeqCst) + 1;
+                        HttpResponse::Ok().body(n.to_string())
+                    }),
+            ),
+        )
+        .await;
+
+        let req = |client: &str| {
+            TestRequest::post()
+                .uri("/orders")
+                .header(IDEMPOTENCY_KEY, "key")
+                .header(AUTHORIZATION, client)
+                .to_request()
+        };
+
+        let resp = call_service(&mut srv, req("alice")).await;
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+
+        // same key of a different client is not replayed
+        let resp = call_service(&mut srv, req("bob")).await;
+        assert!(resp.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"2"));
+
+        let resp = call_service(&mut srv, req("alice")).await;
+        assert_eq!(resp.headers().get(IDEMPOTENT_REPLAYED).unwrap(), "true");
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+
+        // request without client is not checked
+        for _ in 0..2 {
+            let req = TestRequest::post()
+                .uri("/orders")
+                .header(IDEMPOTENCY_KEY, "key")
+                .to_request();
+            call_service(&mut srv, req).await;
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+    }
+
+    #[actori_rt::test]
+    async fn test_memory_store() {
+        let store = MemoryIdempotencyStore::new();
+        let ttl = Duration::from_secs(60);
+
+        match store.claim("key", 1, ttl).await.unwrap() {
+            KeyState::New => (),
+            _ => panic!(),
+        }
+        match store.claim("key", 2, ttl).await.unwrap() {
+            KeyState::Pending(1) => (),
+            _ => panic!(),
+        }
+
+        let res = StoredResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(b"body"),
+        };
+        store.complete("key", res, ttl).await.unwrap();
+        match store.clone().claim("key", 1, ttl).await.unwrap() {
+            KeyState::Completed(1, res) => assert_eq!(res.body, "body"),
+            _ => panic!(),
+        }
+
+        store.release("key").await.unwrap();
+        match store
+            .claim("key", 1, Duration::from_millis(0))
+            .await
+            .unwrap()
+        {
+            KeyState::New => (),
+            _ => panic!(),
+        }
+        // expired claim
+        match store.claim("key", 1, ttl).await.unwrap() {
+            KeyState::New => (),
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! `Middleware` for planned maintenance windows
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use actori_service::{Service, Transform};
+use futures::future::{ok, Either, Ready};
+
+use crate::dev::{ServiceRequest, ServiceResponse};
+use crate::error::Error;
+use crate::http::header::{HeaderValue, RETRY_AFTER};
+use crate::HttpResponse;
+
+/// Maintenance mode switch.
+///
+/// While maintenance mode is enabled, requests are answered with
+/// *503 Service Unavailable* response, `Retry-After` header contains number
+/// of seconds left until the end of the maintenance window. Excluded paths,
+/// i.e. health checks, are still served. Maintenance mode is disabled
+/// automatically when the window ends.
+///
+/// Switch could be cloned, clones share state. Create it outside of the
+/// application factory, register clones for an application or a scope with
+/// `wrap()` and toggle it at runtime, server restart is not required.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actori_web::middleware::MaintenanceMode;
+/// use actori_web::{web, App, HttpResponse, HttpServer};
+///
+/// #[actori_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     let maintenance = MaintenanceMode::new().exclude("/health");
+///
+///     let mode = maintenance.clone();
+///     let srv = HttpServer::new(move || {
+///         App::new()
+///             .wrap(mode.clone())
+///             .route("/health", web::get().to(|| HttpResponse::Ok()))
+///             .route("/", web::get().to(|| HttpResponse::Ok()))
+///     })
+///     .disable_signals()
+///     .bind("127.0.0.1:0")?
+///     .run();
+///
+///     // planned maintenance
+///     maintenance.enable(Duration::from_secs(600));
+///     // ...
+///     maintenance.disable();
+///     srv.stop(true).await;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceMode {
+    until: Arc<Mutex<Option<Instant>>>,
+    exclude: Vec<String>,
+}
+
+impl MaintenanceMode {
+    /// Create switch, maintenance mode is disabled.
+    pub fn new() -> Self {
+        MaintenanceMode::default()
+    }
+
+    /// Serve requests for the path and its sub-paths during maintenance.
+    pub fn exclude<T: Into<String>>(mut self, path: T) -> Self {
+        let path = path.into();
+        let path = match path.trim_end_matches('/') {
+            "" => "/",
+            path => path,
+        };
+        self.exclude.push(path.to_owned());
+        self
+    }
+
+    /// Enable maintenance mode for the duration of maintenance window.
+    ///
+    /// Window of already enabled maintenance mode is replaced.
+    pub fn enable(&self, duration: Duration) {
+        *self.until.lock().unwrap() = Some(Instant::now() + duration);
+    }
+
+    /// Disable maintenance mode.
+    pub fn disable(&self) {
+        *self.until.lock().unwrap() = None;
+    }
+
+    /// Check if maintenance mode is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.remaining().is_some()
+    }
+
+    /// Time left until the end of maintenance window.
+    pub fn remaining(&self) -> Option<Duration> {
+        let mut until = self.until.lock().unwrap();
+        match *until {
+            Some(t) => {
+                let now = Instant::now();
+                if t > now {
+                    Some(t - now)
+                } else {
+                    *until = None;
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.iter().any(|p| {
+            path.starts_with(p.as_str())
+                && (path.len() == p.len() || path[p.len()..].starts_with('/'))
+        })
+    }
+}
+
+impl<S, B> Transform<S> for MaintenanceMode
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MaintenanceMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(MaintenanceMiddleware {
+            service,
+            mode: self.clone(),
+        })
+    }
+}
+
+pub struct MaintenanceMiddleware<S> {
+    service: S,
+    mode: MaintenanceMode,
+}
+
+impl<S, B> Service for MaintenanceMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if let Some(remaining) = self.mode.remaining() {
+            if !self.mode.is_excluded(req.path()) {
+                // round up, client should not retry before the window ends
+                let secs = remaining.as_secs() + (remaining.subsec_nanos() > 0) as u64;
+                let mut res = HttpResponse::ServiceUnavailable().finish();
+                res.headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(secs));
+                return Either::Right(ok(req.into_response(res.into_body())));
+            }
+        }
+        Either::Left(self.service.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App};
+
+    #[actori_rt::test]
+    async fn test_maintenance() {
+        let mode = MaintenanceMode::new().exclude("/health/");
+        let mut srv = init_service(
+            App::new()
+                .wrap(mode.clone())
+                .route("/", web::get().to(|| HttpResponse::Ok()))
+                .route("/health", web::get().to(|| HttpResponse::Ok()))
+                .route("/health/live", web::get().to(|| HttpResponse::Ok()))
+                .route("/healthz", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        mode.enable(Duration::from_millis(2500));
+        assert!(mode.is_enabled());
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "3");
+
+        for path in &["/health", "/health/live"] {
+            let req = TestRequest::with_uri(path).to_request();
+            let resp = call_service(&mut srv, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let req = TestRequest::with_uri("/healthz").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        mode.disable();
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // window is over
+        mode.enable(Duration::from_millis(0));
+        assert!(!mode.is_enabled());
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actori_rt::test]
+    async fn test_maintenance_scope() {
+        let mode = MaintenanceMode::new();
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/api")
+                        .wrap(mode.clone())
+                        .route("/", web::get().to(|| HttpResponse::Ok())),
+                )
+                .route("/", web::get().to(|| HttpResponse::Ok())),
+        )
+        .await;
+
+        mode.enable(Duration::from_secs(60));
+        let req = TestRequest::with_uri("/api/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "60");
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -3,15 +3,49 @@
 #[cfg(feature = "compress")]
 mod compress;
 #[cfg(feature = "compress")]
+mod decompress;
+#[cfg(feature = "compress")]
 pub use self::compress::Compress;
+#[cfg(feature = "compress")]
+pub use self::decompress::Decompress;
+
+#[cfg(feature = "secure-cookies")]
+mod affinity;
+#[cfg(feature = "secure-cookies")]
+pub use self::affinity::{Affinity, AffinityStatus, Draining, SessionAffinity};
 
+mod bodylimit;
 mod condition;
 mod defaultheaders;
+#[cfg(any(debug_assertions, feature = "debug-dump"))]
+mod dump;
 pub mod errhandlers;
+mod idempotency;
 mod logger;
+mod maintenance;
 mod normalize;
+mod prefix;
+mod ratelimit;
+mod rewrite;
+mod tee;
+mod timeout;
+mod trusted;
 
+pub use self::bodylimit::BodyLimit;
 pub use self::condition::Condition;
 pub use self::defaultheaders::DefaultHeaders;
+#[cfg(any(debug_assertions, feature = "debug-dump"))]
+pub use self::dump::DebugDump;
+pub use self::idempotency::{
+    Idempotency, IdempotencyStore, KeyState, MemoryIdempotencyStore, StoredResponse,
+};
 pub use self::logger::Logger;
+pub use self::maintenance::MaintenanceMode;
 pub use self::normalize::NormalizePath;
+pub(crate) use self::prefix::PathRewrites;
+pub use self::prefix::{RewritePath, StripPrefix};
+pub use self::ratelimit::{MemoryStore, Quota, QuotaStore, RateLimit};
+pub use self::rewrite::{BodyTransform, Inject, Rewrite, RewriteBody, Text};
+pub use self::tee::{Tee, TeeOverflow, TeeWriter};
+pub use self::timeout::Timeout;
+pub use self::trusted::TrustedProxies;
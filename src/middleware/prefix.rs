@@ -0,0 +1,322 @@
+//! `Middleware` for rewriting path prefix before routing
+use std::task::{Context, Poll};
+
+use actori_http::http::{PathAndQuery, Uri};
+use actori_service::{Service, Transform};
+use bytes::Bytes;
+use futures::future::{ok, Ready};
+
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::{Error, HttpMessage};
+
+/// Prefixes rewritten by `RewritePath` and `StripPrefix` middlewares,
+/// `HttpRequest::absolute_url_for()` maps generated paths back.
+#[derive(Clone, Default)]
+pub(crate) struct PathRewrites(Vec<(String, String)>);
+
+impl PathRewrites {
+    /// Map internal path to external path
+    pub(crate) fn reverse(&self, path: &str) -> String {
+        let mut path = path.to_owned();
+        for (external, internal) in self.0.iter().rev() {
+            if let Some(rest) = match_prefix(&path, internal) {
+                path = join(external, rest);
+            }
+        }
+        path
+    }
+}
+
+/// `Middleware` for rewriting path prefix of the request before routing.
+///
+/// Request path that starts with `from` prefix is rewritten to start with
+/// `to` prefix, prefixes match whole path segments. Other requests are not
+/// changed. Paths generated with `HttpRequest::url_for()` are mapped back,
+/// i.e. they start with `from` prefix.
+///
+/// Registered for a scope, middleware rewrites the part of the path
+/// after scope's prefix.
+///
+/// ```rust
+/// use actori_web::{middleware::RewritePath, web, App, HttpResponse};
+///
+/// fn main() {
+///     // `/legacy/users` is served by `/v2/users` resource
+///     let app = App::new()
+///         .wrap(RewritePath::new("/legacy", "/v2"))
+///         .route("/v2/users", web::get().to(|| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RewritePath {
+    from: String,
+    to: String,
+}
+
+impl RewritePath {
+    /// Create `RewritePath` middleware, `from` prefix is replaced with `to`.
+    pub fn new<F: Into<String>, T: Into<String>>(from: F, to: T) -> Self {
+        RewritePath {
+            from: normalize(from.into()),
+            to: normalize(to.into()),
+        }
+    }
+}
+
+/// `Middleware` for removing path prefix of the request before routing.
+///
+/// It is used for applications that are mounted at a sub-path by a reverse
+/// proxy which does not strip the prefix. Requests without the prefix are not
+/// changed. Paths generated with `HttpRequest::url_for()` include the prefix.
+///
+/// If public url of the application is configured with
+/// `AppConfig::set_public_url()`, it should not include the prefix.
+///
+/// ```rust
+/// use actori_web::{middleware::StripPrefix, web, App, HttpResponse};
+///
+/// fn main() {
+///     // application is available at `https://example.com/app/`
+///     let app = App::new()
+///         .wrap(StripPrefix::new("/app"))
+///         .route("/users", web::get().to(|| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StripPrefix {
+    prefix: String,
+}
+
+impl StripPrefix {
+    /// Create `StripPrefix` middleware.
+    pub fn new<T: Into<String>>(prefix: T) -> Self {
+        StripPrefix {
+            prefix: normalize(prefix.into()),
+        }
+    }
+}
+
+impl<S, B> Transform<S> for RewritePath
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RewritePathMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RewritePathMiddleware {
+            service,
+            from: self.from.clone(),
+            to: self.to.clone(),
+        })
+    }
+}
+
+impl<S, B> Transform<S> for StripPrefix
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RewritePathMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RewritePathMiddleware {
+            service,
+            from: self.prefix.clone(),
+            to: String::new(),
+        })
+    }
+}
+
+pub struct RewritePathMiddleware<S> {
+    service: S,
+    from: String,
+    to: String,
+}
+
+impl<S, B> Service for RewritePathMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let path = req.match_info().get_ref().path();
+        let unmatched = req.match_info().path();
+        // part of the path that is matched by enclosing scopes
+        let matched = &path[..path.len() - unmatched.len()];
+        let rewritten = match_prefix(unmatched, &self.from)
+            .map(|rest| (matched.to_owned(), join(&self.to, rest)));
+
+        if let Some((matched, rest)) = rewritten {
+            let head = req.head_mut();
+            let mut parts = head.uri.clone().into_parts();
+            let pq = parts.path_and_query.as_ref().unwrap();
+
+            let path = if let Some(q) = pq.query() {
+                Bytes::from(format!("{}{}?{}", matched, rest, q))
+            } else {
+                Bytes::from(format!("{}{}", matched, rest))
+            };
+            parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
+
+            let uri = Uri::from_parts(parts).unwrap();
+            req.match_info_mut().get_mut().update(&uri);
+            req.head_mut().uri = uri;
+
+            let mut ext = req.extensions_mut();
+            if !ext.contains::<PathRewrites>() {
+                ext.insert(PathRewrites::default());
+            }
+            ext.get_mut::<PathRewrites>().unwrap().0.push((
+                format!("{}{}", matched, self.from),
+                format!("{}{}", matched, self.to),
+            ));
+        }
+
+        self.service.call(req)
+    }
+}
+
+/// Remove trailing slashes of the prefix
+fn normalize(prefix: String) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() || prefix.starts_with('/') {
+        prefix.to_owned()
+    } else {
+        format!("/{}", prefix)
+    }
+}
+
+/// Rest of the path if it starts with prefix segments
+fn match_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if path.starts_with(prefix) {
+        let rest = &path[prefix.len()..];
+        if rest.is_empty() || rest.starts_with('/') {
+            return Some(rest);
+        }
+    }
+    None
+}
+
+fn join(prefix: &str, rest: &str) -> String {
+    if prefix.is_empty() && rest.is_empty() {
+        "/".to_owned()
+    } else {
+        format!("{}{}", prefix, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpRequest, HttpResponse};
+
+    #[actori_rt::test]
+    async fn test_strip_prefix() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(StripPrefix::new("/app/"))
+                .service(web::resource("/").to(|| HttpResponse::Ok()))
+                .service(web::resource("/users/{id}").name("user").to(
+                    |req: HttpRequest| {
+                        let url = req.url_for("user", &["1"]).unwrap();
+                        HttpResponse::Ok().body(format!("{} {}", req.path(), url.path()))
+                    },
+                )),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/app/users/2?q=1").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(
+            read_body(resp).await,
+            web::Bytes::from_static(b"/users/2 /app/users/1")
+        );
+
+        let req = TestRequest::with_uri("/app").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert!(resp.status().is_success());
+
+        // prefix matches whole segments
+        let req = TestRequest::with_uri("/application/users/2").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), crate::http::StatusCode::NOT_FOUND);
+
+        // requests without prefix are not changed
+        let req = TestRequest::with_uri("/users/2").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            web::Bytes::from_static(b"/users/2 /users/1")
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_rewrite_path_in_scope() {
+        let mut srv = init_service(
+            App::new().service(
+                web::scope("/{tenant}")
+                    .wrap(RewritePath::new("/legacy", "/v2"))
+                    .service(web::resource("/v2/users").name("users").to(
+                        |req: HttpRequest| {
+                            let url = req.url_for("users", &["acme"]).unwrap();
+                            HttpResponse::Ok().body(format!(
+                                "{} {} {}",
+                                &req.match_info()["tenant"],
+                                req.path(),
+                                url.path()
+                            ))
+                        },
+                    )),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/acme/legacy/users").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            web::Bytes::from_static(b"acme /acme/v2/users /acme/legacy/users")
+        );
+
+        let req = TestRequest::with_uri("/acme/v2/users").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(
+            read_body(resp).await,
+            web::Bytes::from_static(b"acme /acme/v2/users /acme/v2/users")
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let rewrites = PathRewrites(vec![
+            ("/app".to_owned(), "".to_owned()),
+            ("/old".to_owned(), "/new".to_owned()),
+        ]);
+        assert_eq!(rewrites.reverse("/"), "/app/");
+        assert_eq!(rewrites.reverse("/users"), "/app/users");
+        assert_eq!(rewrites.reverse("/new/users"), "/app/old/users");
+        assert_eq!(rewrites.reverse("/newer"), "/app/newer");
+    }
+}
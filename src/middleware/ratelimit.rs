@@ -0,0 +1,415 @@
+//! `Middleware` for request rate limiting
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use actori_service::{Service, Transform};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::{ServiceRequest, ServiceResponse};
+use crate::error::{Error, ErrorServiceUnavailable};
+use crate::http::header::{HeaderName, HeaderValue, RETRY_AFTER};
+use crate::http::HeaderMap;
+use crate::HttpResponse;
+
+const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
+const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// State of a rate limit counter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    /// Number of requests within current window, including current one.
+    pub count: u64,
+    /// Time left until counter expires.
+    pub reset: Duration,
+}
+
+/// Storage for rate limit counters.
+///
+/// Counters could be kept in an external storage, i.e. redis or memcached,
+/// to share quota between server instances. Store operations are
+/// asynchronous, request processing is suspended until store responds or
+/// `RateLimit` timeout expires.
+pub trait QuotaStore {
+    /// Increment counter for the key and return its new state.
+    ///
+    /// If counter does not exist or it is expired, store must create it
+    /// with value of 1 that expires after `ttl`. For redis it is an `INCR`
+    /// followed by `PEXPIRE` for new keys.
+    fn incr(
+        &self,
+        key: &str,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<Quota, Error>>;
+}
+
+/// In-memory quota store.
+///
+/// Store could be cloned, clones share counters. Create store outside of
+/// the application factory to share counters between workers.
+#[derive(Clone, Default)]
+pub struct MemoryStore(Arc<Mutex<MemoryInner>>);
+
+#[derive(Default)]
+struct MemoryInner {
+    counters: HashMap<String, (u64, Instant)>,
+    ops: usize,
+}
+
+impl MemoryStore {
+    /// Create empty store.
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+impl QuotaStore for MemoryStore {
+    fn incr(
+        &self,
+        key: &str,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<Quota, Error>> {
+        let now = Instant::now();
+        let mut inner = self.0.lock().unwrap();
+
+        // purge expired counters from time to time
+        inner.ops += 1;
+        if inner.ops % 4096 == 0 {
+            inner.counters.retain(|_, (_, expires)| *expires > now);
+        }
+
+        let counter = inner
+            .counters
+            .entry(key.to_owned())
+            .or_insert((0, now + ttl));
+        if counter.1 <= now {
+            *counter = (0, now + ttl);
+        }
+        counter.0 += 1;
+
+        ok(Quota {
+            count: counter.0,
+            reset: counter.1 - now,
+        })
+        .boxed_local()
+    }
+}
+
+/// `Middleware` for limiting number of requests per client within
+/// fixed time window.
+///
+/// By default clients are identified by peer ip address, requests without
+/// key are not limited. Requests over the limit are rejected with
+/// *429 Too Many Requests* response. Responses contain
+/// `x-ratelimit-limit`, `x-ratelimit-remaining` and `x-ratelimit-reset`
+/// headers.
+///
+/// If store fails or does not respond within timeout (default 1 second),
+/// request is processed without limiting, unless `fail_open(false)` is set.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actori_web::middleware::{MemoryStore, RateLimit};
+/// use actori_web::{web, App, HttpResponse};
+///
+/// fn main() {
+///     let store = MemoryStore::new();
+///
+///     let app = App::new()
+///         .wrap(
+///             RateLimit::new(store.clone(), 100, Duration::from_secs(60))
+///                 .key(|req| {
+///                     req.headers()
+///                         .get("x-api-key")
+///                         .and_then(|v| v.to_str().ok())
+///                         .map(|key| format!("api:{}", key))
+///                 }),
+///         )
+///         .service(web::resource("/").to(|| HttpResponse::Ok()));
+/// }
+/// ```
+pub struct RateLimit(Rc<Inner>);
+
+struct Inner {
+    store: Box<dyn QuotaStore>,
+    limit: u64,
+    window: Duration,
+    key: Box<dyn Fn(&ServiceRequest) -> Option<String>>,
+    timeout: Duration,
+    fail_open: bool,
+}
+
+impl RateLimit {
+    /// Create `RateLimit` middleware, `limit` requests are allowed within
+    /// each `window`.
+    pub fn new<T>(store: T, limit: u64, window: Duration) -> Self
+    where
+        T: QuotaStore + 'static,
+    {
+        RateLimit(Rc::new(Inner {
+            store: Box::new(store),
+            limit,
+            window,
+            key: Box::new(|req| req.peer_addr().map(|addr| addr.ip().to_string())),
+            timeout: Duration::from_secs(1),
+            fail_open: true,
+        }))
+    }
+
+    /// Set function that computes counter key for a request.
+    ///
+    /// Request is not limited if function returns `None`.
+    pub fn key<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Rc::get_mut(&mut self.0).unwrap().key = Box::new(f);
+        self
+    }
+
+    /// Set max time to wait for store response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().timeout = timeout;
+        self
+    }
+
+    /// Process requests if store fails or times out. By default it is true,
+    /// otherwise *503 Service Unavailable* response is returned.
+    pub fn fail_open(mut self, fail_open: bool) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().fail_open = fail_open;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for RateLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.0.clone(),
+        })
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for RateLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let key = match (self.inner.key)(&req) {
+            Some(key) => key,
+            None => return self.service.borrow_mut().call(req).boxed_local(),
+        };
+        let inner = self.inner.clone();
+        let srv = self.service.clone();
+        let fut = inner.store.incr(&key, inner.window);
+
+        async move {
+            let quota = match actori_rt::time::timeout(inner.timeout, fut).await {
+                Ok(Ok(quota)) => quota,
+                Ok(Err(e)) => {
+                    log::warn!("Rate limit store error: {}", e);
+                    return inner.failure(srv, req).await;
+                }
+                Err(_) => {
+                    log::warn!("Rate limit store timed out");
+                    return inner.failure(srv, req).await;
+                }
+            };
+
+            if quota.count > inner.limit {
+                let mut res = HttpResponse::TooManyRequests().finish();
+                inner.headers(&quota, res.headers_mut());
+                res.headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(reset_secs(quota.reset)));
+                return Ok(req.into_response(res.into_body()));
+            }
+
+            let fut = srv.borrow_mut().call(req);
+            let mut res = fut.await?;
+            inner.headers(&quota, res.headers_mut());
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+impl Inner {
+    async fn failure<S, B>(
+        &self,
+        srv: Rc<RefCell<S>>,
+        req: ServiceRequest,
+    ) -> Result<ServiceResponse<B>, Error>
+    where
+        S: Service<
+            Request = ServiceRequest,
+            Response = ServiceResponse<B>,
+            Error = Error,
+        >,
+    {
+        if self.fail_open {
+            let fut = srv.borrow_mut().call(req);
+            fut.await
+        } else {
+            Ok(req
+                .error_response(ErrorServiceUnavailable("Rate limit is not available")))
+        }
+    }
+
+    fn headers(&self, quota: &Quota, headers: &mut HeaderMap) {
+        headers.insert(
+            HeaderName::from_static(X_RATELIMIT_LIMIT),
+            HeaderValue::from(self.limit),
+        );
+        headers.insert(
+            HeaderName::from_static(X_RATELIMIT_REMAINING),
+            HeaderValue::from(self.limit.saturating_sub(quota.count)),
+        );
+        headers.insert(
+            HeaderName::from_static(X_RATELIMIT_RESET),
+            HeaderValue::from(reset_secs(quota.reset)),
+        );
+    }
+}
+
+/// Seconds until reset, rounded up
+fn reset_secs(reset: Duration) -> u64 {
+    reset.as_secs() + if reset.subsec_nanos() > 0 { 1 } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_rt::time::delay_for;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{ok_service, TestRequest};
+
+    fn req(addr: &str) -> ServiceRequest {
+        TestRequest::default()
+            .peer_addr(addr.parse().unwrap())
+            .to_srv_request()
+    }
+
+    #[actori_rt::test]
+    async fn test_rate_limit() {
+        let mut mw = RateLimit::new(MemoryStore::new(), 2, Duration::from_secs(60))
+            .new_transform(ok_service())
+            .await
+            .unwrap();
+
+        let resp = mw.call(req("127.0.0.1:8080")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(X_RATELIMIT_LIMIT).unwrap(), "2");
+        assert_eq!(resp.headers().get(X_RATELIMIT_REMAINING).unwrap(), "1");
+        assert_eq!(resp.headers().get(X_RATELIMIT_RESET).unwrap(), "60");
+
+        // port is not a part of the key
+        let resp = mw.call(req("127.0.0.1:8081")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(X_RATELIMIT_REMAINING).unwrap(), "0");
+
+        let resp = mw.call(req("127.0.0.1:8080")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "60");
+
+        let resp = mw.call(req("127.0.0.2:8080")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // requests without key are not limited
+        let resp = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(X_RATELIMIT_LIMIT).is_none());
+    }
+
+    #[actori_rt::test]
+    async fn test_memory_store_expire() {
+        let store = MemoryStore::new();
+        let ttl = Duration::from_millis(50);
+
+        let quota = store.incr("key", ttl).await.unwrap();
+        assert_eq!(quota.count, 1);
+        assert!(quota.reset <= ttl);
+        assert_eq!(store.incr("key", ttl).await.unwrap().count, 2);
+        assert_eq!(store.clone().incr("key", ttl).await.unwrap().count, 3);
+
+        delay_for(Duration::from_millis(100)).await;
+        assert_eq!(store.incr("key", ttl).await.unwrap().count, 1);
+    }
+
+    struct SlowStore;
+
+    impl QuotaStore for SlowStore {
+        fn incr(
+            &self,
+            _: &str,
+            _: Duration,
+        ) -> LocalBoxFuture<'static, Result<Quota, Error>> {
+            async {
+                delay_for(Duration::from_secs(10)).await;
+                Ok(Quota {
+                    count: 1,
+                    reset: Duration::from_secs(1),
+                })
+            }
+            .boxed_local()
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_store_timeout() {
+        let mut mw = RateLimit::new(SlowStore, 1, Duration::from_secs(1))
+            .timeout(Duration::from_millis(50))
+            .new_transform(ok_service())
+            .await
+            .unwrap();
+        let resp = mw.call(req("127.0.0.1:8080")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(X_RATELIMIT_LIMIT).is_none());
+
+        let mut mw = RateLimit::new(SlowStore, 1, Duration::from_secs(1))
+            .timeout(Duration::from_millis(50))
+            .fail_open(false)
+            .new_transform(ok_service())
+            .await
+            .unwrap();
+        let resp = mw.call(req("127.0.0.1:8080")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
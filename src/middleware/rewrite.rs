@@ -0,0 +1,493 @@
+//! `Middleware` for rewriting response bodies
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actori_service::{Service, Transform};
+use bytes::{Bytes, BytesMut};
+use encoding_rs::{Decoder, Encoding, UTF_8};
+use futures::future::{ok, Ready};
+
+use crate::dev::{BodySize, MessageBody, ResponseBody, ResponseHead};
+use crate::error::Error;
+use crate::http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use crate::request::HttpRequest;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// Transformation of a response body.
+///
+/// Transform is created for each response by the `Rewrite` factory
+/// function. Chunks are passed to the transform as they are streamed to
+/// the peer, transform could hold back part of a chunk and return it with
+/// one of the next chunks or on finalization.
+pub trait BodyTransform {
+    /// Transform a chunk of the response body.
+    fn transform(&mut self, chunk: Bytes) -> Result<Bytes, Error>;
+
+    /// Called once the response body is complete, returns the rest of
+    /// the transformed body.
+    fn finish(&mut self) -> Result<Bytes, Error> {
+        Ok(Bytes::new())
+    }
+}
+
+/// Charset-aware transform of a text body.
+///
+/// Body is decoded with the response charset, text is passed to the
+/// function by chunks and the result is encoded back. Multi-byte sequences
+/// split between chunks are handled by the decoder.
+pub struct Text<F> {
+    encoding: &'static Encoding,
+    decoder: Decoder,
+    f: F,
+}
+
+impl<F> Text<F>
+where
+    F: FnMut(&str) -> String,
+{
+    /// Create transform for the text in the specified encoding.
+    pub fn new(encoding: &'static Encoding, f: F) -> Self {
+        Text {
+            encoding,
+            decoder: encoding.new_decoder_without_bom_handling(),
+            f,
+        }
+    }
+
+    /// Create transform for the text in the charset of the response
+    /// `Content-Type`, UTF-8 is used if charset is not set or is unknown.
+    pub fn for_response(head: &ResponseHead, f: F) -> Self {
+        let encoding = head
+            .headers()
+            .get(&CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .and_then(|ct| ct.parse::<mime::Mime>().ok())
+            .and_then(|ct| {
+                ct.get_param(mime::CHARSET).and_then(|charset| {
+                    Encoding::for_label_no_replacement(charset.as_str().as_bytes())
+                })
+            })
+            .unwrap_or(UTF_8);
+        Text::new(encoding, f)
+    }
+
+    fn process(&mut self, chunk: &[u8], last: bool) -> Bytes {
+        let len = self
+            .decoder
+            .max_utf8_buffer_length(chunk.len())
+            .unwrap_or(chunk.len() * 3 + 16);
+        let mut text = String::with_capacity(len);
+        let _ = self.decoder.decode_to_string(chunk, &mut text, last);
+        if text.is_empty() {
+            return Bytes::new();
+        }
+
+        let text = (self.f)(&text);
+        if self.encoding == UTF_8 {
+            Bytes::from(text)
+        } else {
+            let (encoded, _, _) = self.encoding.encode(&text);
+            Bytes::copy_from_slice(&encoded)
+        }
+    }
+}
+
+impl<F> BodyTransform for Text<F>
+where
+    F: FnMut(&str) -> String,
+{
+    fn transform(&mut self, chunk: Bytes) -> Result<Bytes, Error> {
+        Ok(self.process(&chunk, false))
+    }
+
+    fn finish(&mut self) -> Result<Bytes, Error> {
+        Ok(self.process(&[], true))
+    }
+}
+
+/// Insert a snippet before or after the first occurrence of a marker,
+/// e.g. before `</head>` tag.
+///
+/// Marker is matched ignoring ASCII case, marker split between chunks is
+/// recognized. Only the tail of the body that could contain the start of
+/// the marker is held back. Body is passed unchanged if marker is not
+/// found. Marker must be ASCII, charset of the body must be ASCII
+/// compatible.
+pub struct Inject {
+    marker: Bytes,
+    snippet: Bytes,
+    after: bool,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl Inject {
+    /// Insert snippet before the marker.
+    pub fn before<M: Into<Bytes>, S: Into<Bytes>>(marker: M, snippet: S) -> Self {
+        Inject::new(marker.into(), snippet.into(), false)
+    }
+
+    /// Insert snippet after the marker.
+    pub fn after<M: Into<Bytes>, S: Into<Bytes>>(marker: M, snippet: S) -> Self {
+        Inject::new(marker.into(), snippet.into(), true)
+    }
+
+    fn new(marker: Bytes, snippet: Bytes, after: bool) -> Self {
+        Inject {
+            done: marker.is_empty(),
+            marker,
+            snippet,
+            after,
+            buf: BytesMut::new(),
+        }
+    }
+}
+
+impl BodyTransform for Inject {
+    fn transform(&mut self, chunk: Bytes) -> Result<Bytes, Error> {
+        if self.done {
+            return Ok(chunk);
+        }
+        self.buf.extend_from_slice(&chunk);
+
+        let pos = self
+            .buf
+            .windows(self.marker.len())
+            .position(|w| w.eq_ignore_ascii_case(&self.marker));
+
+        if let Some(pos) = pos {
+            self.done = true;
+            let pos = if self.after {
+                pos + self.marker.len()
+            } else {
+                pos
+            };
+            let mut out = BytesMut::with_capacity(self.buf.len() + self.snippet.len());
+            out.extend_from_slice(&self.buf[..pos]);
+            out.extend_from_slice(&self.snippet);
+            out.extend_from_slice(&self.buf[pos..]);
+            self.buf.clear();
+            Ok(out.freeze())
+        } else {
+            // keep tail that could contain the start of the marker
+            let keep = self.marker.len() - 1;
+            let len = self.buf.len().saturating_sub(keep);
+            Ok(self.buf.split_to(len).freeze())
+        }
+    }
+
+    fn finish(&mut self) -> Result<Bytes, Error> {
+        Ok(self.buf.split().freeze())
+    }
+}
+
+/// `Middleware` for transforming streamed response bodies, e.g. to inject
+/// analytics snippets or CSP nonces into HTML pages.
+///
+/// Factory function is called for each response, once response head is
+/// available. If it returns `None`, response body is passed unchanged.
+/// Transformed body is not buffered by the middleware, `Content-Length`
+/// header is removed and the body is sent chunked.
+///
+/// Responses with `Content-Encoding` are not transformed, `Compress`
+/// middleware has to be registered after `Rewrite`.
+///
+/// ```rust
+/// use actori_web::middleware::{Compress, Inject, Rewrite};
+/// use actori_web::{web, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(Rewrite::new(|_, head| {
+///             if head.headers().get("content-type")? == "text/html" {
+///                 Some(Inject::before("</head>", "<script src=\"/a.js\"></script>"))
+///             } else {
+///                 None
+///             }
+///         }))
+///         .wrap(Compress::default())
+///         .route(
+///             "/",
+///             web::get().to(|| {
+///                 HttpResponse::Ok()
+///                     .content_type("text/html")
+///                     .body("<html><head></head></html>")
+///             }),
+///         );
+/// }
+/// ```
+pub struct Rewrite<F>(Rc<F>);
+
+impl<F, T> Rewrite<F>
+where
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<T> + 'static,
+    T: BodyTransform + 'static,
+{
+    /// Create `Rewrite` middleware with the specified transform factory.
+    pub fn new(factory: F) -> Self {
+        Rewrite(Rc::new(factory))
+    }
+}
+
+impl<S, B, F, T> Transform<S> for Rewrite<F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<T> + 'static,
+    T: BodyTransform + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<RewriteBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RewriteMiddleware<S, F>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RewriteMiddleware {
+            service,
+            factory: self.0.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RewriteMiddleware<S, F> {
+    service: S,
+    factory: Rc<F>,
+}
+
+impl<S, B, F, T> Service for RewriteMiddleware<S, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<T> + 'static,
+    T: BodyTransform + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<RewriteBody<B>>;
+    type Error = Error;
+    type Future = RewriteResponse<S, B, F>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        RewriteResponse {
+            fut: self.service.call(req),
+            factory: self.factory.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[pin_project::pin_project]
+pub struct RewriteResponse<S, B, F>
+where
+    S: Service,
+{
+    #[pin]
+    fut: S::Future,
+    factory: Rc<F>,
+    _t: PhantomData<(B,)>,
+}
+
+impl<S, B, F, T> Future for RewriteResponse<S, B, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<T> + 'static,
+    T: BodyTransform + 'static,
+{
+    type Output = Result<ServiceResponse<RewriteBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let res = futures::ready!(this.fut.poll(cx))?;
+
+        let head = res.response().head();
+        let transform = if head.headers().contains_key(&CONTENT_ENCODING) {
+            None
+        } else {
+            (this.factory)(res.request(), head)
+                .map(|t| Box::new(t) as Box<dyn BodyTransform>)
+        };
+
+        Poll::Ready(Ok(res.map_body(move |head, body| {
+            if transform.is_some() {
+                head.headers_mut().remove(&CONTENT_LENGTH);
+            }
+            ResponseBody::Body(RewriteBody {
+                body,
+                transform,
+                done: false,
+            })
+        })))
+    }
+}
+
+/// Response body that is passed through a `BodyTransform`.
+pub struct RewriteBody<B> {
+    body: ResponseBody<B>,
+    transform: Option<Box<dyn BodyTransform>>,
+    done: bool,
+}
+
+impl<B: MessageBody> MessageBody for RewriteBody<B> {
+    fn size(&self) -> BodySize {
+        if self.transform.is_some() {
+            BodySize::Stream
+        } else {
+            self.body.size()
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        let transform = if let Some(ref mut transform) = self.transform {
+            transform
+        } else {
+            return self.body.poll_next(cx);
+        };
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            let chunk = match futures::ready!(self.body.poll_next(cx)) {
+                Some(Ok(chunk)) => transform.transform(chunk),
+                Some(Err(e)) => Err(e),
+                None => {
+                    self.done = true;
+                    transform.finish()
+                }
+            };
+
+            // empty chunk terminates chunked body
+            match chunk {
+                Ok(ref chunk) if chunk.is_empty() => {
+                    if self.done {
+                        return Poll::Ready(None);
+                    }
+                }
+                Ok(chunk) => return Poll::Ready(Some(Ok(chunk))),
+                Err(e) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_service::IntoService;
+    use encoding_rs::WINDOWS_1252;
+
+    use super::*;
+    use crate::test::{read_body, TestRequest};
+    use crate::HttpResponse;
+
+    #[test]
+    fn test_inject() {
+        let mut inject = Inject::before("</head>", "<script></script>");
+        let mut out = BytesMut::new();
+        for chunk in &["<html><head><title>t</title></HE", "AD><body></body>"] {
+            out.extend_from_slice(
+                &inject
+                    .transform(Bytes::from_static(chunk.as_bytes()))
+                    .unwrap(),
+            );
+        }
+        out.extend_from_slice(&inject.finish().unwrap());
+        assert_eq!(
+            out,
+            &b"<html><head><title>t</title><script></script></HEAD><body></body>"[..]
+        );
+
+        let mut inject = Inject::after("<body>", "!");
+        let mut out = BytesMut::new();
+        out.extend_from_slice(&inject.transform(Bytes::from_static(b"<bo")).unwrap());
+        out.extend_from_slice(&inject.finish().unwrap());
+        assert_eq!(out, &b"<bo"[..]);
+    }
+
+    #[test]
+    fn test_text() {
+        let mut text = Text::new(UTF_8, |s| s.to_uppercase());
+        // multi-byte sequence split between chunks
+        let body = "grüße".as_bytes();
+        let mut out = BytesMut::new();
+        out.extend_from_slice(
+            &text.transform(Bytes::copy_from_slice(&body[..3])).unwrap(),
+        );
+        out.extend_from_slice(
+            &text.transform(Bytes::copy_from_slice(&body[3..])).unwrap(),
+        );
+        out.extend_from_slice(&text.finish().unwrap());
+        assert_eq!(out, "GRÜSSE".as_bytes());
+
+        let mut head = ResponseHead::new(crate::http::StatusCode::OK);
+        head.headers_mut().insert(
+            CONTENT_TYPE,
+            "text/plain; charset=windows-1252".parse().unwrap(),
+        );
+        let mut text = Text::for_response(&head, |s| s.replace("ü", "ue"));
+        assert_eq!(text.encoding, WINDOWS_1252);
+        let out = text.transform(Bytes::from_static(b"gr\xfc\xdfe")).unwrap();
+        assert_eq!(out, &b"grue\xdfe"[..]);
+    }
+
+    #[actori_rt::test]
+    async fn test_rewrite() {
+        let srv = |req: ServiceRequest| {
+            ok(req.into_response(
+                HttpResponse::Ok()
+                    .content_type("text/html")
+                    .body("<html><head></head></html>"),
+            ))
+        };
+        let mut mw = Rewrite::new(|_, _| Some(Inject::before("</head>", "<x>")))
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let resp = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert!(resp.headers().get(CONTENT_LENGTH).is_none());
+        assert_eq!(resp.response().body().size(), BodySize::Stream);
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(b"<html><head><x></head></html>")
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_rewrite_encoded() {
+        let srv = |req: ServiceRequest| {
+            ok(req.into_response(
+                HttpResponse::Ok()
+                    .insert_header((CONTENT_ENCODING, "gzip"))
+                    .body("</head>"),
+            ))
+        };
+        let mut mw = Rewrite::new(|_, _| Some(Inject::before("</head>", "<x>")))
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let resp = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"</head>"));
+    }
+}
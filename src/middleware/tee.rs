@@ -0,0 +1,397 @@
+//! `Middleware` for copying response body to an external sink
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actori_service::{Service, Transform};
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::future::{ok, Ready};
+
+use crate::dev::{BodySize, MessageBody, ResponseBody, ResponseHead};
+use crate::error::{Error, ErrorInternalServerError};
+use crate::request::HttpRequest;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// Destination for a copy of a response body.
+///
+/// Writer is created for each response by the `Tee` factory function.
+/// Chunks are passed to the writer as they are streamed to the peer,
+/// the body is never buffered by the middleware.
+pub trait TeeWriter {
+    /// Try to write a chunk of the response body.
+    ///
+    /// Writer must return the chunk back if it can not accept it,
+    /// in that case the `Tee` overflow policy is applied.
+    fn try_write(&mut self, chunk: Bytes) -> Result<(), Bytes>;
+
+    /// Called once the copy is done.
+    ///
+    /// `complete` is false if some of response chunks are not copied,
+    /// or if the response body did not finish successfully.
+    fn finish(&mut self, _complete: bool) {}
+}
+
+/// Bounded channel could be used as a writer, `finish` disconnects the sender.
+///
+/// Channel is closed once all senders are disconnected or dropped, so
+/// clones of the same sender could be used for all responses.
+impl TeeWriter for mpsc::Sender<Bytes> {
+    fn try_write(&mut self, chunk: Bytes) -> Result<(), Bytes> {
+        self.try_send(chunk).map_err(|e| e.into_inner())
+    }
+
+    fn finish(&mut self, _: bool) {
+        self.disconnect();
+    }
+}
+
+/// Behavior of the `Tee` middleware when a chunk could not be copied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TeeOverflow {
+    /// Stop copying, the rest of the response is sent unchanged.
+    Stop,
+    /// Drop the chunk that writer can not accept and continue with
+    /// the next chunks. Copy stops if size limit is reached.
+    Skip,
+    /// Abort response stream with an error.
+    Fail,
+}
+
+/// `Middleware` for copying response bodies to an external sink, e.g. for
+/// audit or archival purposes.
+///
+/// Factory function is called for each response, once response head is
+/// available. If it returns `None`, response body is not copied.
+/// Number of copied bytes per response is limited, default limit is 256Kb.
+///
+/// ```rust
+/// use actori_web::middleware::{Tee, TeeOverflow};
+/// use actori_web::{web, App, HttpResponse};
+/// use futures::channel::mpsc;
+///
+/// fn main() {
+///     let (tx, _rx) = mpsc::channel(16);
+///
+///     let app = App::new().service(
+///         web::resource("/audit")
+///             .wrap(
+///                 Tee::new(move |_, _| Some(tx.clone()))
+///                     .limit(65_536)
+///                     .overflow(TeeOverflow::Skip),
+///             )
+///             .to(|| HttpResponse::Ok()),
+///     );
+/// }
+/// ```
+pub struct Tee<F>(Rc<Inner<F>>);
+
+struct Inner<F> {
+    factory: F,
+    limit: usize,
+    overflow: TeeOverflow,
+}
+
+impl<F, W> Tee<F>
+where
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<W> + 'static,
+    W: TeeWriter + 'static,
+{
+    /// Create `Tee` middleware with the specified writer factory.
+    pub fn new(factory: F) -> Self {
+        Tee(Rc::new(Inner {
+            factory,
+            limit: 262_144,
+            overflow: TeeOverflow::Stop,
+        }))
+    }
+
+    /// Set max number of bytes copied per response.
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().limit = limit;
+        self
+    }
+
+    /// Set overflow policy. By default copy stops on overflow.
+    pub fn overflow(mut self, overflow: TeeOverflow) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().overflow = overflow;
+        self
+    }
+}
+
+impl<S, B, F, W> Transform<S> for Tee<F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<W> + 'static,
+    W: TeeWriter + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<TeeBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TeeMiddleware<S, F>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TeeMiddleware {
+            service,
+            inner: self.0.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct TeeMiddleware<S, F> {
+    service: S,
+    inner: Rc<Inner<F>>,
+}
+
+impl<S, B, F, W> Service for TeeMiddleware<S, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<W> + 'static,
+    W: TeeWriter + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<TeeBody<B>>;
+    type Error = Error;
+    type Future = TeeResponse<S, B, F>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        TeeResponse {
+            fut: self.service.call(req),
+            inner: self.inner.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[pin_project::pin_project]
+pub struct TeeResponse<S, B, F>
+where
+    S: Service,
+{
+    #[pin]
+    fut: S::Future,
+    inner: Rc<Inner<F>>,
+    _t: PhantomData<(B,)>,
+}
+
+impl<S, B, F, W> Future for TeeResponse<S, B, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+    F: Fn(&HttpRequest, &ResponseHead) -> Option<W> + 'static,
+    W: TeeWriter + 'static,
+{
+    type Output = Result<ServiceResponse<TeeBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let res = futures::ready!(this.fut.poll(cx))?;
+
+        let writer = (this.inner.factory)(res.request(), res.response().head())
+            .map(|w| Box::new(w) as Box<dyn TeeWriter>);
+        let limit = this.inner.limit;
+        let overflow = this.inner.overflow;
+
+        Poll::Ready(Ok(res.map_body(move |_, body| {
+            ResponseBody::Body(TeeBody {
+                body,
+                writer,
+                limit,
+                overflow,
+                size: 0,
+                complete: true,
+            })
+        })))
+    }
+}
+
+/// Response body that copies chunks to a `TeeWriter`.
+pub struct TeeBody<B> {
+    body: ResponseBody<B>,
+    writer: Option<Box<dyn TeeWriter>>,
+    limit: usize,
+    overflow: TeeOverflow,
+    size: usize,
+    complete: bool,
+}
+
+impl<B> TeeBody<B> {
+    fn copy(&mut self, chunk: &Bytes) -> Result<(), Error> {
+        let writer = if let Some(ref mut writer) = self.writer {
+            writer
+        } else {
+            return Ok(());
+        };
+
+        if self.size + chunk.len() <= self.limit {
+            if writer.try_write(chunk.clone()).is_ok() {
+                self.size += chunk.len();
+                return Ok(());
+            }
+            // writer is full, chunk could be skipped
+            if self.overflow == TeeOverflow::Skip {
+                self.complete = false;
+                return Ok(());
+            }
+        }
+
+        self.finish(false);
+        if self.overflow == TeeOverflow::Fail {
+            Err(ErrorInternalServerError("Response copy overflow"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn finish(&mut self, complete: bool) {
+        if let Some(mut writer) = self.writer.take() {
+            writer.finish(complete && self.complete);
+        }
+    }
+}
+
+impl<B> Drop for TeeBody<B> {
+    fn drop(&mut self) {
+        self.finish(false);
+    }
+}
+
+impl<B: MessageBody> MessageBody for TeeBody<B> {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        match self.body.poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Err(e) = self.copy(&chunk) {
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                self.finish(false);
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => {
+                self.finish(true);
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_service::IntoService;
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::test::{read_body, TestRequest};
+    use crate::HttpResponse;
+
+    #[actori_rt::test]
+    async fn test_tee() {
+        let (tx, rx) = mpsc::channel(16);
+        let srv =
+            |req: ServiceRequest| ok(req.into_response(HttpResponse::Ok().body("TEST")));
+        let mut mw = Tee::new(move |_, _| Some(tx.clone()))
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let resp = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"TEST"));
+        drop(mw);
+
+        let copy: Vec<_> = rx.collect().await;
+        assert_eq!(copy, vec![Bytes::from_static(b"TEST")]);
+    }
+
+    #[actori_rt::test]
+    async fn test_tee_shared_sender() {
+        let (tx, rx) = mpsc::channel(16);
+        let srv = |req: ServiceRequest| {
+            let body = req.path().to_owned();
+            ok(req.into_response(HttpResponse::Ok().body(body)))
+        };
+        let mut mw = Tee::new(move |_, _| Some(tx.clone()))
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        for path in &["/first", "/second"] {
+            let req = TestRequest::with_uri(path).to_srv_request();
+            let resp = mw.call(req).await.unwrap();
+            assert_eq!(read_body(resp).await, Bytes::from(*path));
+        }
+        drop(mw);
+
+        let copy: Vec<_> = rx.collect().await;
+        assert_eq!(
+            copy,
+            vec![
+                Bytes::from_static(b"/first"),
+                Bytes::from_static(b"/second")
+            ]
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_tee_limit() {
+        let (tx, rx) = mpsc::channel(16);
+        let srv =
+            |req: ServiceRequest| ok(req.into_response(HttpResponse::Ok().body("TEST")));
+        let mut mw = Tee::new(move |_, _| Some(tx.clone()))
+            .limit(2)
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let resp = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"TEST"));
+        drop(mw);
+
+        let copy: Vec<Bytes> = rx.collect().await;
+        assert!(copy.is_empty());
+    }
+
+    #[actori_rt::test]
+    async fn test_tee_overflow_fail() {
+        let srv =
+            |req: ServiceRequest| ok(req.into_response(HttpResponse::Ok().body("TEST")));
+        let mut mw = Tee::new(|_, _| Some(mpsc::channel::<Bytes>(0).0))
+            .overflow(TeeOverflow::Fail)
+            .limit(2)
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let mut resp = mw
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        let mut body = resp.take_body();
+        assert!(body.next().await.unwrap().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! `Middleware` for limiting request processing time
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actori_rt::time::timeout;
+use actori_service::{Service, Transform};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::error::{Error, InternalError};
+use crate::http::StatusCode;
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for limiting request processing time.
+///
+/// If inner service does not respond within timeout, processing is
+/// cancelled and request fails with *504 Gateway Timeout* error. Error status
+/// could be changed with `status()` method. Middleware could be registered
+/// for an application, a scope or a resource.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use actori_web::{web, http, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::Timeout::new(Duration::from_secs(30)))
+///         .service(
+///             web::resource("/report")
+///                 .wrap(
+///                     middleware::Timeout::new(Duration::from_secs(5))
+///                         .status(http::StatusCode::SERVICE_UNAVAILABLE),
+///                 )
+///                 .to(|| HttpResponse::Ok()),
+///         );
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timeout {
+    timeout: Duration,
+    status: StatusCode,
+}
+
+impl Timeout {
+    /// Create `Timeout` middleware with the specified duration.
+    pub fn new(timeout: Duration) -> Self {
+        Timeout {
+            timeout,
+            status: StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// Set response status for timed out requests.
+    ///
+    /// By default it is *504 Gateway Timeout*.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Timeout
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TimeoutMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TimeoutMiddleware {
+            service,
+            timeout: self.timeout,
+            status: self.status,
+        })
+    }
+}
+
+pub struct TimeoutMiddleware<S> {
+    service: S,
+    timeout: Duration,
+    status: StatusCode,
+}
+
+impl<S, B> Service for TimeoutMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let status = self.status;
+        // request must not be shared while inner service runs
+        let path = req.path().to_owned();
+        let fut = timeout(self.timeout, self.service.call(req));
+
+        async move {
+            match fut.await {
+                Ok(res) => res,
+                Err(_) => {
+                    log::debug!("Request timed out: {}", path);
+                    Err(InternalError::new("Request timed out", status).into())
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actori_rt::time::delay_for;
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::test::{init_service, read_body, TestRequest};
+    use crate::{web, App, HttpRequest, HttpResponse};
+
+    async fn slow() -> HttpResponse {
+        delay_for(Duration::from_millis(500)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    #[actori_rt::test]
+    async fn test_timeout() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_millis(100)))
+                .service(web::resource("/fast").to(|| HttpResponse::Ok()))
+                .service(web::resource("/slow").to(slow))
+                .service(
+                    web::scope("/scope")
+                        .wrap(
+                            Timeout::new(Duration::from_millis(50))
+                                .status(StatusCode::SERVICE_UNAVAILABLE),
+                        )
+                        .route("/slow", web::get().to(slow)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/fast").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/slow").to_request();
+        let err = srv.call(req).await.err().unwrap();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert_eq!(err.to_string(), "Request timed out");
+
+        let req = TestRequest::with_uri("/scope/slow").to_request();
+        let err = srv.call(req).await.err().unwrap();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_timeout_routing() {
+        let mut srv = init_service(
+            App::new()
+                .wrap(Timeout::new(Duration::from_millis(100)))
+                .service(web::resource("/user/{id}").to(|req: HttpRequest| {
+                    HttpResponse::Ok().body(req.match_info()["id"].to_owned())
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/user/1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"1"));
+    }
+}
@@ -0,0 +1,453 @@
+//! `Middleware` for resolving client information behind trusted proxies
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use actori_service::{Service, Transform};
+use futures::future::{ok, Ready};
+
+use crate::dev::{AppConfig, RequestHead};
+use crate::error::Error;
+use crate::http::header::{self, HeaderName};
+use crate::info::{
+    ClientMeta, ConnectionInfo, X_FORWARDED_FOR, X_FORWARDED_HOST, X_FORWARDED_PROTO,
+};
+use crate::service::{ServiceRequest, ServiceResponse};
+
+/// `Middleware` for resolving client information behind trusted proxies.
+///
+/// Middleware computes scheme, host and client ip address of the request
+/// once and stores them as `dev::ClientMeta` in request extensions,
+/// `ConnectionInfo` is built from it. `Forwarded` and `X-Forwarded-*`
+/// headers are used only if peer address belongs to one of the trusted
+/// networks. Client ip address is the first untrusted address of the
+/// forwarding chain, counted from the peer. Scheme and host are taken from
+/// the element added by the last trusted proxy, elements added by the client
+/// are ignored.
+///
+/// If request is already processed by another `TrustedProxies` middleware,
+/// client information is not changed.
+///
+/// Trusted proxies could be configured for all applications of the server
+/// with `HttpServer::trusted_proxies()`, in that case `ConnectionInfo` uses
+/// them without the middleware.
+///
+/// ```rust
+/// use actori_web::{dev::ClientMeta, middleware, web, App, HttpRequest};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             middleware::TrustedProxies::new()
+///                 .trust("10.0.0.0/8")
+///                 .trust("::1"),
+///         )
+///         .route(
+///             "/",
+///             web::get().to(|req: HttpRequest| async move {
+///                 let ip = req.extensions().get::<ClientMeta>().and_then(|m| m.ip());
+///                 format!("{:?}", ip)
+///             }),
+///         );
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    nets: Arc<Vec<IpNet>>,
+}
+
+impl TrustedProxies {
+    /// Create `TrustedProxies` middleware without trusted networks
+    pub fn new() -> Self {
+        TrustedProxies::default()
+    }
+
+    /// Trust network in CIDR notation, i.e. `10.0.0.0/8`. Address without
+    /// prefix length is a single host.
+    ///
+    /// Panics if network can not be parsed.
+    pub fn trust(mut self, cidr: &str) -> Self {
+        let net = IpNet::parse(cidr)
+            .unwrap_or_else(|| panic!("Can not parse network: {:?}", cidr));
+        Arc::get_mut(&mut self.nets)
+            .expect("Multiple copies exist")
+            .push(net);
+        self
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.nets.iter().any(|net| net.contains(ip))
+    }
+
+    pub(crate) fn client_meta(&self, req: &RequestHead, cfg: &AppConfig) -> ClientMeta {
+        let peer_ip = req.peer_addr.map(|addr| canonical(addr.ip()));
+        let proxied = peer_ip.map(|ip| self.is_trusted(ip)).unwrap_or(false);
+
+        let mut ip = peer_ip;
+        let mut scheme = None;
+        let mut host = None;
+        if proxied {
+            let hops = Forwarded::from_head(req).hops;
+            // walk the chain starting from the nearest proxy, element without
+            // address stops the walk
+            let mut idx = hops.len();
+            while idx > 0 {
+                idx -= 1;
+                match hops[idx].node {
+                    Some(node) => ip = parse_node(node),
+                    None => break,
+                }
+                match ip {
+                    Some(addr) if self.is_trusted(addr) => continue,
+                    _ => break,
+                }
+            }
+            // element of the client is added by the last trusted proxy,
+            // elements on the left of it are controlled by the client
+            scheme = hops[idx..].iter().find_map(|hop| hop.proto);
+            host = hops[idx..].iter().find_map(|hop| hop.host);
+        }
+
+        let scheme = scheme.or_else(|| req.uri.scheme_str()).unwrap_or_else(|| {
+            if cfg.secure() {
+                "https"
+            } else {
+                "http"
+            }
+        });
+        let host = host
+            .or_else(|| req.headers.get(&header::HOST).and_then(|h| h.to_str().ok()))
+            .or_else(|| req.uri.authority().map(|a| a.as_str()))
+            .unwrap_or_else(|| cfg.host());
+
+        ClientMeta {
+            scheme: scheme.to_owned(),
+            host: host.to_owned(),
+            ip,
+            peer_addr: req.peer_addr,
+            proxied,
+        }
+    }
+}
+
+impl<S, B> Transform<S> for TrustedProxies
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TrustedProxiesMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(TrustedProxiesMiddleware {
+            service,
+            proxies: self.clone(),
+        })
+    }
+}
+
+pub struct TrustedProxiesMiddleware<S> {
+    service: S,
+    proxies: TrustedProxies,
+}
+
+impl<S, B> Service for TrustedProxiesMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if !req.head().extensions().contains::<ClientMeta>() {
+            let meta = self.proxies.client_meta(req.head(), req.app_config());
+            let mut ext = req.head().extensions_mut();
+            // connection info could be computed before
+            ext.remove::<ConnectionInfo>();
+            ext.insert(meta);
+        }
+        self.service.call(req)
+    }
+}
+
+/// Forwarding information of the request
+struct Forwarded<'a> {
+    hops: Vec<Hop<'a>>,
+}
+
+/// Element of the forwarding chain, added by a proxy
+#[derive(Default)]
+struct Hop<'a> {
+    node: Option<&'a str>,
+    proto: Option<&'a str>,
+    host: Option<&'a str>,
+}
+
+impl<'a> Forwarded<'a> {
+    fn from_head(head: &'a RequestHead) -> Self {
+        let mut hops = Vec::new();
+
+        for hdr in head.headers.get_all(&header::FORWARDED) {
+            if let Ok(val) = hdr.to_str() {
+                for el in val.split(',') {
+                    let mut hop = Hop::default();
+                    for pair in el.split(';') {
+                        let mut items = pair.trim().splitn(2, '=');
+                        if let (Some(name), Some(val)) = (items.next(), items.next()) {
+                            let val = Some(val.trim().trim_matches('"'));
+                            match &name.to_lowercase() as &str {
+                                "for" => hop.node = val,
+                                "proto" => hop.proto = val,
+                                "host" => hop.host = val,
+                                _ => (),
+                            }
+                        }
+                    }
+                    hops.push(hop);
+                }
+            }
+        }
+        if !hops.is_empty() {
+            return Forwarded { hops };
+        }
+
+        // values of x-forwarded-* headers are aligned by the nearest proxy
+        let list = |name: &'static [u8]| {
+            let mut values = Vec::new();
+            for hdr in head
+                .headers
+                .get_all(&HeaderName::from_lowercase(name).unwrap())
+            {
+                if let Ok(val) = hdr.to_str() {
+                    values.extend(val.split(',').map(|v| v.trim()));
+                }
+            }
+            values
+        };
+        let nodes = list(X_FORWARDED_FOR);
+        let protos = list(X_FORWARDED_PROTO);
+        let hosts = list(X_FORWARDED_HOST);
+
+        let len = nodes.len().max(protos.len()).max(hosts.len());
+        let at = |values: &[&'a str], idx: usize| {
+            (idx + values.len()).checked_sub(len).map(|idx| values[idx])
+        };
+        hops.extend((0..len).map(|idx| Hop {
+            node: at(&nodes, idx),
+            proto: at(&protos, idx),
+            host: at(&hosts, idx),
+        }));
+        Forwarded { hops }
+    }
+}
+
+/// Parse node of the forwarding chain, port is ignored
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if node.starts_with('[') {
+        let end = node.find(']')?;
+        return node[1..end].parse::<Ipv6Addr>().ok().map(IpAddr::V6);
+    }
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(canonical(ip));
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(canonical(addr.ip()));
+    }
+    None
+}
+
+/// Convert ipv4-mapped ipv6 address to ipv4 address
+fn canonical(ip: IpAddr) -> IpAddr {
+    if let IpAddr::V6(v6) = ip {
+        if let [0, 0, 0, 0, 0, 0xffff, _, _] = v6.segments() {
+            if let Some(v4) = v6.to_ipv4() {
+                return IpAddr::V4(v4);
+            }
+        }
+    }
+    ip
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    fn parse(s: &str) -> Option<IpNet> {
+        let mut parts = s.trim().splitn(2, '/');
+        let addr = canonical(parts.next()?.parse().ok()?);
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match parts.next() {
+            Some(prefix) => prefix.parse().ok().filter(|p| *p <= max)?,
+            None => max,
+        };
+        Some(IpNet { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::max_value()
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::max_value()
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{ok_service, TestRequest};
+
+    #[test]
+    fn test_ip_net() {
+        let net = IpNet::parse("10.0.0.0/8").unwrap();
+        assert!(net.contains("10.1.2.3".parse().unwrap()));
+        assert!(!net.contains("11.0.0.1".parse().unwrap()));
+        assert!(!net.contains("::1".parse().unwrap()));
+
+        let net = IpNet::parse("::1").unwrap();
+        assert!(net.contains("::1".parse().unwrap()));
+        assert!(!net.contains("::2".parse().unwrap()));
+
+        let net = IpNet::parse("0.0.0.0/0").unwrap();
+        assert!(net.contains("192.0.2.1".parse().unwrap()));
+        assert!(IpNet::parse("10.0.0.0/33").is_none());
+        assert!(IpNet::parse("localhost").is_none());
+
+        assert_eq!(
+            parse_node("[2001:db8::1]:4711"),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(
+            parse_node("192.0.2.60:1234"),
+            Some("192.0.2.60".parse().unwrap())
+        );
+        assert_eq!(parse_node("unknown"), None);
+    }
+
+    #[actori_rt::test]
+    async fn test_trusted_proxies() {
+        let mut mw = TrustedProxies::new()
+            .trust("10.0.0.0/8")
+            .new_transform(ok_service())
+            .await
+            .unwrap();
+
+        // untrusted peer, headers are ignored
+        let req = TestRequest::default()
+            .peer_addr("192.0.2.1:1234".parse().unwrap())
+            .header("x-forwarded-for", "203.0.113.1")
+            .header("x-forwarded-proto", "https")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        let meta = resp.request().extensions().get::<ClientMeta>().cloned();
+        let meta = meta.unwrap();
+        assert!(!meta.is_proxied());
+        assert_eq!(meta.ip(), Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(meta.scheme(), "http");
+        assert_eq!(resp.request().connection_info().remote(), Some("192.0.2.1"));
+
+        // trusted chain
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:1234".parse().unwrap())
+            .header("x-forwarded-for", "198.51.100.7, 203.0.113.1, 10.0.0.1")
+            .header("x-forwarded-proto", "https")
+            .header("x-forwarded-host", "rust-lang.org")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        let meta = resp.request().extensions().get::<ClientMeta>().cloned();
+        assert!(meta.unwrap().is_proxied());
+        let info = resp.request().connection_info();
+        assert_eq!(info.remote(), Some("203.0.113.1"));
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+        drop(info);
+
+        // forwarded header
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:1234".parse().unwrap())
+            .header(
+                header::FORWARDED,
+                "for=\"[2001:db8::1]:4711\";proto=https, for=10.0.0.1",
+            )
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        let info = resp.request().connection_info();
+        assert_eq!(info.remote(), Some("2001:db8::1"));
+        assert_eq!(info.scheme(), "https");
+    }
+
+    #[actori_rt::test]
+    async fn test_trusted_proxies_spoofing() {
+        let mut mw = TrustedProxies::new()
+            .trust("10.0.0.0/8")
+            .new_transform(ok_service())
+            .await
+            .unwrap();
+
+        // client injects element ahead of the element of the proxy
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:1234".parse().unwrap())
+            .header(
+                header::FORWARDED,
+                "proto=https;host=evil, for=198.51.100.7;proto=http;host=rust-lang.org",
+            )
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        let info = resp.request().connection_info();
+        assert_eq!(info.remote(), Some("198.51.100.7"));
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "rust-lang.org");
+        drop(info);
+
+        // element of the edge proxy is used, nearest proxy adds address only
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:1234".parse().unwrap())
+            .header(
+                header::FORWARDED,
+                "for=203.0.113.1;proto=http;host=evil, \
+                 for=198.51.100.7;proto=https;host=rust-lang.org, for=10.0.0.1",
+            )
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        let info = resp.request().connection_info();
+        assert_eq!(info.remote(), Some("198.51.100.7"));
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "rust-lang.org");
+        drop(info);
+
+        // values of x-forwarded-* headers are taken from the right
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:1234".parse().unwrap())
+            .header("x-forwarded-for", "198.51.100.7")
+            .header("x-forwarded-proto", "https, http")
+            .header("x-forwarded-host", "evil, rust-lang.org")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        let info = resp.request().connection_info();
+        assert_eq!(info.remote(), Some("198.51.100.7"));
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "rust-lang.org");
+    }
+}
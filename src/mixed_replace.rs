@@ -0,0 +1,321 @@
+//! `multipart/x-mixed-replace` streaming responses
+//!
+//! Each frame replaces the previous one on the client, commonly used for
+//! MJPEG camera and preview endpoints.
+//!
+//! ```rust
+//! use actori_web::{mixed_replace::MixedReplace, web, App, Responder};
+//! use bytes::Bytes;
+//! use futures::stream;
+//!
+//! async fn camera() -> impl Responder {
+//!     let frames = stream::iter(vec![
+//!         ("image/jpeg", Bytes::from_static(b"first frame")),
+//!         ("image/jpeg", Bytes::from_static(b"second frame")),
+//!     ]);
+//!     MixedReplace::new(frames).on_disconnect(|| println!("viewer left"))
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().route("/camera", web::get().to(camera));
+//! }
+//! ```
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures::future::{ok, Ready};
+use futures::Stream;
+
+use crate::dev::BodyEncoding;
+use crate::error::Error;
+use crate::http::header::{ContentEncoding, CACHE_CONTROL, CONTENT_TYPE};
+use crate::request::HttpRequest;
+use crate::responder::Responder;
+use crate::HttpResponse;
+
+/// Default parts boundary
+const BOUNDARY: &str = "actori-mixed-replace-frame";
+
+/// Single frame of `multipart/x-mixed-replace` response.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    content_type: String,
+    data: Bytes,
+}
+
+impl Frame {
+    /// Create frame with content type and data.
+    pub fn new<T: Into<String>>(content_type: T, data: Bytes) -> Self {
+        Frame {
+            content_type: content_type.into(),
+            data,
+        }
+    }
+}
+
+impl<T: Into<String>> From<(T, Bytes)> for Frame {
+    fn from((content_type, data): (T, Bytes)) -> Self {
+        Frame::new(content_type, data)
+    }
+}
+
+/// `multipart/x-mixed-replace` responder.
+///
+/// Every item of the stream is sent as a separate part with `Content-Type`
+/// and `Content-Length` headers, closing boundary is sent when the stream
+/// ends. Response is not compressed.
+///
+/// If client disconnects, the stream of frames is dropped and disconnect
+/// callback is called. Dropping the stream notifies frame producers, i.e.
+/// sender of a channel gets an error on the next send.
+pub struct MixedReplace<S> {
+    stream: S,
+    boundary: String,
+    on_disconnect: Option<Box<dyn FnOnce()>>,
+}
+
+impl<S, T> MixedReplace<S>
+where
+    S: Stream<Item = T> + 'static,
+    T: Into<Frame>,
+{
+    /// Create responder for the stream of frames.
+    pub fn new(stream: S) -> Self {
+        MixedReplace {
+            stream,
+            boundary: BOUNDARY.to_owned(),
+            on_disconnect: None,
+        }
+    }
+
+    /// Set parts boundary.
+    ///
+    /// Boundary must not appear in frames data. By default
+    /// `actori-mixed-replace-frame` is used.
+    ///
+    /// Panics if boundary is empty, longer than 70 characters or contains
+    /// characters other than alphanumerics, `-`, `_` and `.`.
+    pub fn boundary<B: Into<String>>(mut self, boundary: B) -> Self {
+        let boundary = boundary.into();
+        assert!(
+            !boundary.is_empty()
+                && boundary.len() <= 70
+                && boundary
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"-_.".contains(&b)),
+            "Invalid multipart boundary: {:?}",
+            boundary
+        );
+        self.boundary = boundary;
+        self
+    }
+
+    /// Set callback, it is called if client disconnects before the stream
+    /// of frames ends.
+    pub fn on_disconnect<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce() + 'static,
+    {
+        self.on_disconnect = Some(Box::new(f));
+        self
+    }
+}
+
+impl<S, T> Responder for MixedReplace<S>
+where
+    S: Stream<Item = T> + 'static,
+    T: Into<Frame>,
+{
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(HttpResponse::Ok()
+            .insert_header((
+                CONTENT_TYPE,
+                format!("multipart/x-mixed-replace; boundary={}", self.boundary),
+            ))
+            .insert_header((CACHE_CONTROL, "no-cache, no-store"))
+            .encoding(ContentEncoding::Identity)
+            .streaming(FrameStream {
+                stream: self.stream,
+                boundary: self.boundary,
+                pending: None,
+                first: true,
+                done: false,
+                on_disconnect: self.on_disconnect,
+            }))
+    }
+}
+
+#[pin_project::pin_project(PinnedDrop)]
+struct FrameStream<S> {
+    #[pin]
+    stream: S,
+    boundary: String,
+    pending: Option<Bytes>,
+    first: bool,
+    done: bool,
+    on_disconnect: Option<Box<dyn FnOnce()>>,
+}
+
+#[pin_project::pinned_drop]
+impl<S> PinnedDrop for FrameStream<S> {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        if !*this.done {
+            if let Some(f) = this.on_disconnect.take() {
+                f();
+            }
+        }
+    }
+}
+
+impl<S, T> Stream for FrameStream<S>
+where
+    S: Stream<Item = T>,
+    T: Into<Frame>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        // frame data is sent as is, without copying
+        if let Some(data) = this.pending.take() {
+            return Poll::Ready(Some(Ok(data)));
+        }
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        // crlf before the boundary belongs to the delimiter
+        let mut buf = BytesMut::new();
+        if !*this.first {
+            buf.put_slice(b"\r\n");
+        }
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(frame)) => {
+                let frame = frame.into();
+                buf.put_slice(
+                    format!(
+                        "--{}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+                        this.boundary,
+                        frame.content_type,
+                        frame.data.len()
+                    )
+                    .as_bytes(),
+                );
+                *this.first = false;
+                if !frame.data.is_empty() {
+                    *this.pending = Some(frame.data);
+                }
+            }
+            Poll::Ready(None) => {
+                *this.done = true;
+                buf.put_slice(format!("--{}--\r\n", this.boundary).as_bytes());
+            }
+            Poll::Pending => return Poll::Pending,
+        }
+        Poll::Ready(Some(Ok(buf.freeze())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use futures::{stream, StreamExt};
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{read_body, TestRequest};
+
+    #[actori_rt::test]
+    async fn test_mixed_replace() {
+        let req = TestRequest::default().to_http_request();
+        let frames = stream::iter(vec![
+            ("image/jpeg", Bytes::from_static(b"frame1")),
+            ("text/plain", Bytes::from_static(b"frame2")),
+        ]);
+        let resp = MixedReplace::new(frames)
+            .boundary("frame")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "multipart/x-mixed-replace; boundary=frame"
+        );
+
+        let body = read_body(crate::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(
+                b"--frame\r\nContent-Type: image/jpeg\r\nContent-Length: 6\r\n\r\n\
+                  frame1\r\n\
+                  --frame\r\nContent-Type: text/plain\r\nContent-Length: 6\r\n\r\n\
+                  frame2\r\n\
+                  --frame--\r\n"
+            )
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_mixed_replace_disconnect() {
+        let req = TestRequest::default().to_http_request();
+        let disconnected = Rc::new(Cell::new(false));
+        let flag = disconnected.clone();
+        let frames =
+            stream::iter(vec![Frame::new("image/jpeg", Bytes::from_static(b"a"))])
+                .chain(stream::pending());
+        let mut resp = MixedReplace::new(frames)
+            .on_disconnect(move || flag.set(true))
+            .respond_to(&req)
+            .await
+            .unwrap();
+
+        let mut body = resp.take_body();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert!(chunk.starts_with(b"--actori-mixed-replace-frame\r\n"));
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(chunk, Bytes::from_static(b"a"));
+        assert!(!disconnected.get());
+
+        // client is gone
+        drop(body);
+        drop(resp);
+        assert!(disconnected.get());
+    }
+
+    #[actori_rt::test]
+    async fn test_mixed_replace_complete() {
+        let req = TestRequest::default().to_http_request();
+        let disconnected = Rc::new(Cell::new(false));
+        let flag = disconnected.clone();
+        let resp = MixedReplace::new(stream::empty::<Frame>())
+            .on_disconnect(move || flag.set(true))
+            .respond_to(&req)
+            .await
+            .unwrap();
+
+        let body = read_body(crate::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(b"--actori-mixed-replace-frame--\r\n")
+        );
+        assert!(!disconnected.get());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_boundary() {
+        let _ = MixedReplace::new(stream::empty::<Frame>()).boundary("a b");
+    }
+}
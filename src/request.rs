@@ -3,14 +3,19 @@ use std::rc::Rc;
 use std::{fmt, net};
 
 use actori_http::http::{HeaderMap, Method, Uri, Version};
-use actori_http::{Error, Extensions, HttpMessage, Message, Payload, RequestHead};
+use actori_http::{
+    DynExtensions, Error, Extensions, HttpMessage, Message, Payload, RequestHead,
+};
 use actori_router::{Path, Url};
 use futures::future::{ok, Ready};
 
+use crate::app_service::AppRouter;
 use crate::config::AppConfig;
+use crate::data::Data;
 use crate::error::UrlGenerationError;
 use crate::extract::FromRequest;
 use crate::info::ConnectionInfo;
+use crate::middleware::PathRewrites;
 use crate::rmap::ResourceMap;
 
 #[derive(Clone)]
@@ -21,7 +26,8 @@ pub(crate) struct HttpRequestInner {
     pub(crate) head: Message<RequestHead>,
     pub(crate) path: Path<Url>,
     pub(crate) payload: Payload,
-    pub(crate) app_data: Rc<Extensions>,
+    pub(crate) app_data: Vec<Rc<Extensions>>,
+    pub(crate) router: Option<Rc<RefCell<AppRouter>>>,
     rmap: Rc<ResourceMap>,
     config: AppConfig,
     pool: &'static HttpRequestPool,
@@ -44,7 +50,8 @@ impl HttpRequest {
             payload,
             rmap,
             config,
-            app_data,
+            app_data: vec![app_data],
+            router: None,
             pool,
         }))
     }
@@ -134,8 +141,33 @@ impl HttpRequest {
         self.head().extensions_mut()
     }
 
+    /// Request's string keyed extensions
+    ///
+    /// Container is stored in request extensions and is created on first access.
+    pub fn dyn_extensions(&self) -> Ref<'_, DynExtensions> {
+        self.init_dyn_extensions();
+        Ref::map(self.extensions(), |ext| ext.get::<DynExtensions>().unwrap())
+    }
+
+    /// Mutable reference to a the request's string keyed extensions
+    pub fn dyn_extensions_mut(&self) -> RefMut<'_, DynExtensions> {
+        self.init_dyn_extensions();
+        RefMut::map(self.extensions_mut(), |ext| {
+            ext.get_mut::<DynExtensions>().unwrap()
+        })
+    }
+
+    fn init_dyn_extensions(&self) {
+        if !self.extensions().contains::<DynExtensions>() {
+            self.extensions_mut().insert(DynExtensions::new());
+        }
+    }
+
     /// Generate url for named resource
     ///
+    /// If public url of the application is configured, it is used as a base for
+    /// generated url, check [`absolute_url_for()`](#method.absolute_url_for).
+    ///
     /// ```rust
     /// # extern crate actori_web;
     /// # use actori_web::{web, App, HttpRequest, HttpResponse};
@@ -174,12 +206,67 @@ impl HttpRequest {
         self.url_for(name, &NO_PARAMS)
     }
 
+    /// Generate absolute url for the path
+    ///
+    /// Scheme, host and port of the url are taken from the application's
+    /// public url, if it is configured with `AppConfig::set_public_url()`.
+    /// Otherwise connection information is used. Path of the public url is
+    /// prepended to the provided path. Prefixes rewritten by `StripPrefix`
+    /// and `RewritePath` middlewares are mapped back.
+    ///
+    /// ```rust
+    /// # use actori_web::{HttpRequest, HttpResponse};
+    /// #
+    /// fn index(req: HttpRequest) -> HttpResponse {
+    ///     let url = req.absolute_url_for("/static/logo.png"); // <- generate absolute url
+    ///     HttpResponse::Ok().into()
+    /// }
+    /// ```
+    pub fn absolute_url_for(&self, path: &str) -> Result<url::Url, UrlGenerationError> {
+        let rewritten;
+        let path = match self.extensions().get::<PathRewrites>() {
+            Some(rewrites) => {
+                rewritten = rewrites.reverse(path);
+                rewritten.as_str()
+            }
+            None => path,
+        };
+        let sep = if path.starts_with('/') { "" } else { "/" };
+
+        if let Some(base) = self.app_config().public_url() {
+            let base = &base[..url::Position::AfterPath];
+            Ok(url::Url::parse(&format!(
+                "{}{}{}",
+                base.trim_end_matches('/'),
+                sep,
+                path
+            ))?)
+        } else {
+            let conn = self.connection_info();
+            Ok(url::Url::parse(&format!(
+                "{}://{}{}{}",
+                conn.scheme(),
+                conn.host(),
+                sep,
+                path
+            ))?)
+        }
+    }
+
     #[inline]
     /// Get a reference to a `ResourceMap` of current application.
     pub fn resource_map(&self) -> &ResourceMap {
         &self.0.rmap
     }
 
+    /// Pattern of the resource that matches request path, i.e.
+    /// `/user/{id}/info`. Patterns of enclosing scopes are included.
+    ///
+    /// Returns `None` if no resource matches the path.
+    pub fn match_pattern(&self) -> Option<String> {
+        self.0.rmap.match_pattern(self.path())
+    }
+
     /// Peer socket address
     ///
     /// Peer address is actual socket address, if proxy is used in front of
@@ -191,6 +278,28 @@ impl HttpRequest {
         self.head().peer_addr
     }
 
+    /// Connection data item.
+    ///
+    /// Connection data is shared by all requests of a connection. Server
+    /// stores `dev::PeerAddr`, `dev::LocalAddr` and `dev::ListenerName`
+    /// items, so application that listens on several sockets could tell
+    /// which listener accepted the request.
+    ///
+    /// ```rust
+    /// use actori_web::{dev, HttpRequest};
+    ///
+    /// async fn index(req: HttpRequest) -> &'static str {
+    ///     match req.conn_data::<dev::ListenerName>() {
+    ///         Some(name) if name.as_str().ends_with(":8443") => "admin",
+    ///         _ => "public",
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.head().conn_data()
+    }
+
     /// Get *ConnectionInfo* for the current request.
     ///
     /// This method panics if request's extensions container is already
@@ -215,11 +324,57 @@ impl HttpRequest {
     /// let opt_t = req.app_data::<Data<T>>();
     /// ```
     pub fn app_data<T: 'static>(&self) -> Option<&T> {
-        if let Some(st) = self.0.app_data.get::<T>() {
-            Some(&st)
-        } else {
-            None
+        for container in self.0.app_data.iter().rev() {
+            if let Some(st) = container.get::<T>() {
+                return Some(st);
+            }
+        }
+        None
+    }
+
+    /// Get an application data object stored with `App::dyn_data` under the key.
+    ///
+    /// Value is returned only if it is of type `T`.
+    pub fn app_dyn_data<T: 'static>(&self, key: &str) -> Option<&T> {
+        for container in self.0.app_data.iter().rev() {
+            if let Some(st) = container
+                .get::<DynExtensions>()
+                .and_then(|ext| ext.get::<T>(key))
+            {
+                return Some(st);
+            }
+        }
+        None
+    }
+
+    /// Get type names of all application data objects visible to this request.
+    ///
+    /// Result contains data registered on the application and on all scopes
+    /// and resources that matched the request, data that is shadowed by a
+    /// more specific scope or resource is reported only once. This method
+    /// is intended for diagnostics and tests.
+    pub fn app_data_types(&self) -> Vec<&'static str> {
+        let mut types: Vec<&'static str> = Vec::new();
+        for container in self.0.app_data.iter().rev() {
+            for name in container.type_names() {
+                if !types.contains(&name) {
+                    types.push(name);
+                }
+            }
         }
+        types
+    }
+
+    /// Iterate over extractor configuration objects of type `T` visible to
+    /// this request, from the most specific one to the application level one.
+    ///
+    /// Configuration stored with `data()` as `Data<T>` is included as well.
+    pub(crate) fn config_chain<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.0.app_data.iter().rev().filter_map(|container| {
+            container
+                .get::<T>()
+                .or_else(|| container.get::<Data<T>>().map(|data| data.get_ref()))
+        })
     }
 }
 
@@ -343,9 +498,10 @@ impl HttpRequestPool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::dev::{ResourceDef, ResourceMap};
+    use crate::dev::{ListenerName, LocalAddr, ResourceDef, ResourceMap};
     use crate::http::{header, StatusCode};
-    use crate::test::{call_service, init_service, TestRequest};
+    use crate::middleware::TrustedProxies;
+    use crate::test::{call_service, init_service, read_response, TestRequest};
     use crate::{web, App, HttpResponse};
 
     #[test]
@@ -393,6 +549,18 @@ mod tests {
         assert_eq!(req.query_string(), "id=test");
     }
 
+    #[test]
+    fn test_conn_data() {
+        let req = TestRequest::default()
+            .conn_data(ListenerName("main".to_owned()))
+            .to_http_request();
+        assert_eq!(req.conn_data::<ListenerName>().unwrap().as_str(), "main");
+        assert!(req.conn_data::<LocalAddr>().is_none());
+
+        let req = TestRequest::default().to_srv_request();
+        assert!(req.conn_data::<ListenerName>().is_none());
+    }
+
     #[test]
     fn test_url_for() {
         let mut res = ResourceDef::new("/user/{name}.{ext}");
@@ -422,6 +590,39 @@ mod tests {
         );
     }
 
+    #[actori_rt::test]
+    async fn test_match_pattern() {
+        async fn pattern(req: HttpRequest) -> String {
+            req.match_pattern().unwrap_or_default()
+        }
+
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/user/{id}")
+                        .service(web::resource("/info").to(pattern))
+                        .service(
+                            web::scope("/posts")
+                                .route("/{post}", web::get().to(pattern)),
+                        ),
+                )
+                .service(web::resource("/").to(pattern))
+                .default_service(web::to(pattern)),
+        )
+        .await;
+
+        for (path, expected) in &[
+            ("/", "/"),
+            ("/user/1/info", "/user/{id}/info"),
+            ("/user/1/posts/2", "/user/{id}/posts/{post}"),
+            ("/unknown", ""),
+        ] {
+            let req = TestRequest::with_uri(path).to_request();
+            let body = read_response(&mut srv, req).await;
+            assert_eq!(body, expected.as_bytes());
+        }
+    }
+
     #[test]
     fn test_url_for_static() {
         let mut rdef = ResourceDef::new("/index.html");
@@ -443,6 +644,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_url_for_trusted_proxies() {
+        let mut rdef = ResourceDef::new("/index.html");
+        *rdef.name_mut() = "index".to_string();
+
+        let mut rmap = ResourceMap::new(ResourceDef::new(""));
+        rmap.add(&mut rdef, None);
+
+        // client injects element ahead of the element of the proxy
+        let proxies = TrustedProxies::new().trust("10.0.0.0/8");
+        let req = TestRequest::with_uri("/test")
+            .trusted_proxies(proxies)
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .header(header::HOST, "10.0.0.1")
+            .header(
+                header::FORWARDED,
+                "for=192.0.2.1;proto=http;host=evil, \
+                 for=192.0.2.60;proto=https;host=www.rust-lang.org",
+            )
+            .rmap(rmap)
+            .to_http_request();
+
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "www.rust-lang.org");
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+        drop(info);
+
+        let url = req.url_for_static("index");
+        assert_eq!(
+            url.ok().unwrap().as_str(),
+            "https://www.rust-lang.org/index.html"
+        );
+        let url = req.absolute_url_for("/static/logo.png");
+        assert_eq!(
+            url.ok().unwrap().as_str(),
+            "https://www.rust-lang.org/static/logo.png"
+        );
+    }
+
     #[test]
     fn test_url_for_external() {
         let mut rdef = ResourceDef::new("https://youtube.com/watch/{video_id}");
@@ -461,6 +702,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_url_for_public_url() {
+        let mut res = ResourceDef::new("/user/{name}.{ext}");
+        *res.name_mut() = "index".to_string();
+
+        let mut rmap = ResourceMap::new(ResourceDef::new(""));
+        rmap.add(&mut res, None);
+
+        let req = TestRequest::with_header(header::HOST, "internal:8080")
+            .public_url(url::Url::parse("https://www.rust-lang.org/app/").unwrap())
+            .rmap(rmap)
+            .to_http_request();
+
+        let url = req.url_for("index", &["test", "html"]);
+        assert_eq!(
+            url.ok().unwrap().as_str(),
+            "https://www.rust-lang.org/app/user/test.html"
+        );
+        let url = req.absolute_url_for("static/logo.png");
+        assert_eq!(
+            url.ok().unwrap().as_str(),
+            "https://www.rust-lang.org/app/static/logo.png"
+        );
+
+        let req = TestRequest::with_header(header::HOST, "www.rust-lang.org")
+            .to_http_request();
+        let url = req.absolute_url_for("/static/logo.png");
+        assert_eq!(
+            url.ok().unwrap().as_str(),
+            "http://www.rust-lang.org/static/logo.png"
+        );
+    }
+
     #[actori_rt::test]
     async fn test_data() {
         let mut srv = init_service(App::new().app_data(10usize).service(
@@ -494,6 +768,35 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[actori_rt::test]
+    async fn test_dyn_data() {
+        let mut srv = init_service(
+            App::new()
+                .dyn_data("plugin::limit", 10usize)
+                .dyn_data("plugin::name", "test".to_string())
+                .service(web::resource("/").to(|req: HttpRequest| {
+                    req.dyn_extensions_mut()
+                        .namespace_mut("plugin")
+                        .insert("seen", true);
+                    let ext = req.dyn_extensions();
+                    if req.app_dyn_data::<usize>("plugin::limit") == Some(&10)
+                        && req.app_dyn_data::<u32>("plugin::limit").is_none()
+                        && req.app_dyn_data::<usize>("limit").is_none()
+                        && ext.namespace("plugin").get::<bool>("seen") == Some(&true)
+                    {
+                        HttpResponse::Ok()
+                    } else {
+                        HttpResponse::BadRequest()
+                    }
+                })),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[actori_rt::test]
     async fn test_extensions_dropped() {
         struct Tracker {
@@ -5,7 +5,9 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actori_http::{Error, Extensions, Response};
+use actori_http::http::header::{ContentEncoding, HeaderValue, ALLOW};
+use actori_http::http::Method;
+use actori_http::{Error, Extensions, HttpMessage, Response};
 use actori_router::IntoPattern;
 use actori_service::boxed::{self, BoxService, BoxServiceFactory};
 use actori_service::{
@@ -13,14 +15,17 @@ use actori_service::{
 };
 use futures::future::{ok, Either, LocalBoxFuture, Ready};
 
-use crate::data::Data;
-use crate::dev::{insert_slash, AppService, HttpServiceFactory, ResourceDef};
+use crate::data::{Data, DataRequirement};
+use crate::describe::{Description, RouteDescription};
+use crate::dev::{insert_slash, AppService, Enc, HttpServiceFactory, ResourceDef};
 use crate::extract::FromRequest;
 use crate::guard::Guard;
 use crate::handler::Factory;
 use crate::responder::Responder;
+use crate::rmap::ResourceMeta;
 use crate::route::{CreateRouteService, Route, RouteService};
 use crate::service::{ServiceRequest, ServiceResponse};
+use crate::sitemap::SitemapEntry;
 
 type HttpService = BoxService<ServiceRequest, ServiceResponse, Error>;
 type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Error, ()>;
@@ -47,14 +52,21 @@ type HttpNewService = BoxServiceFactory<(), ServiceRequest, ServiceResponse, Err
 ///
 /// If no matching route could be found, *405* response code get returned.
 /// Default behavior could be overriden with `default_resource()` method.
+/// *405* response lists methods of the resource in `Allow` header, if every
+/// route of the resource has method guard.
 pub struct Resource<T = ResourceEndpoint> {
     endpoint: T,
     rdef: Vec<String>,
     name: Option<String>,
+    description: Option<Description>,
+    sitemap: Option<SitemapEntry>,
     routes: Vec<Route>,
     data: Option<Extensions>,
+    requires: Vec<DataRequirement>,
     guards: Vec<Box<dyn Guard>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
+    auto_options: bool,
+    encoding: Option<ContentEncoding>,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
 }
 
@@ -66,11 +78,16 @@ impl Resource {
             routes: Vec::new(),
             rdef: path.patterns(),
             name: None,
+            description: None,
+            sitemap: None,
             endpoint: ResourceEndpoint::new(fref.clone()),
             factory_ref: fref,
             guards: Vec::new(),
             data: None,
+            requires: Vec::new(),
             default: Rc::new(RefCell::new(None)),
+            auto_options: false,
+            encoding: None,
         }
     }
 }
@@ -93,6 +110,78 @@ where
         self
     }
 
+    /// Attach documentation metadata to the resource.
+    ///
+    /// Descriptions of the resource and its routes are stored in
+    /// `ResourceMap` and are available at runtime via
+    /// `ResourceMap::resources()`.
+    ///
+    /// ```rust
+    /// use actori_web::describe::Description;
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/users")
+    ///             .describe(Description::new().summary("Users").tag("users"))
+    ///             .route(web::get().to(|| HttpResponse::Ok())),
+    ///     );
+    /// }
+    /// ```
+    pub fn describe(mut self, description: Description) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Attach sitemap metadata to the resource.
+    ///
+    /// Metadata is used by `web::sitemap()` service, it is ignored for
+    /// resources with variable path segments.
+    ///
+    /// ```rust
+    /// use actori_web::web::{self, ChangeFreq, SitemapEntry};
+    /// use actori_web::{App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/news")
+    ///             .sitemap(SitemapEntry::new().changefreq(ChangeFreq::Hourly))
+    ///             .route(web::get().to(|| HttpResponse::Ok())),
+    ///     );
+    /// }
+    /// ```
+    pub fn sitemap(mut self, entry: SitemapEntry) -> Self {
+        self.sitemap = Some(entry);
+        self
+    }
+
+    /// Override response encoding of the `Compress` middleware for
+    /// responses of this resource, including error responses.
+    ///
+    /// Encoding set with `BodyEncoding` on the response and encoding of
+    /// the route take precedence.
+    ///
+    /// ```rust
+    /// use actori_web::http::ContentEncoding;
+    /// use actori_web::{middleware, web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::scope("/api")
+    ///             .wrap(middleware::Compress::default())
+    ///             .service(
+    ///                 web::resource("/events")
+    ///                     .encoding(ContentEncoding::Identity)
+    ///                     .route(web::get().to(|| HttpResponse::Ok())),
+    ///             ),
+    ///     );
+    /// }
+    /// ```
+    pub fn encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
     /// Add match guard to a resource.
     ///
     /// ```rust
@@ -207,6 +296,16 @@ where
         self
     }
 
+    /// Declare data type required by routes of this resource.
+    ///
+    /// Requirement is verified during application initialization, check
+    /// [`Scope::requires()`](struct.Scope.html#method.requires) for
+    /// more information.
+    pub fn requires<U: 'static>(mut self) -> Self {
+        self.requires.push(DataRequirement::of::<U>());
+        self
+    }
+
     /// Register a new route and add handler. This route matches all requests.
     ///
     /// ```rust
@@ -270,10 +369,15 @@ where
             endpoint: apply(mw, self.endpoint),
             rdef: self.rdef,
             name: self.name,
+            description: self.description,
+            sitemap: self.sitemap,
             guards: self.guards,
             routes: self.routes,
             default: self.default,
+            auto_options: self.auto_options,
+            encoding: self.encoding,
             data: self.data,
+            requires: self.requires,
             factory_ref: self.factory_ref,
         }
     }
@@ -332,10 +436,15 @@ where
             endpoint: apply_fn_factory(self.endpoint, mw),
             rdef: self.rdef,
             name: self.name,
+            description: self.description,
+            sitemap: self.sitemap,
             guards: self.guards,
             routes: self.routes,
             default: self.default,
+            auto_options: self.auto_options,
+            encoding: self.encoding,
             data: self.data,
+            requires: self.requires,
             factory_ref: self.factory_ref,
         }
     }
@@ -363,6 +472,47 @@ where
 
         self
     }
+
+    /// Respond to `OPTIONS` requests that do not match any route.
+    ///
+    /// Response lists methods of the resource in `Allow` header. Methods are
+    /// known only if every route of the resource has method guard, otherwise
+    /// request is passed to the default service. By default automatic
+    /// `OPTIONS` responses are disabled.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new().service(
+    ///         web::resource("/users")
+    ///             .auto_options(true)
+    ///             .route(web::get().to(|| HttpResponse::Ok()))
+    ///             .route(web::post().to(|| HttpResponse::Created())),
+    ///     );
+    /// }
+    /// ```
+    pub fn auto_options(mut self, enabled: bool) -> Self {
+        self.auto_options = enabled;
+        self
+    }
+
+    /// Methods of the resource, known only if every route has method guard
+    fn allowed_methods(&self) -> Option<Vec<Method>> {
+        if self.routes.is_empty()
+            || self.routes.iter().any(|route| route.methods().is_empty())
+        {
+            return None;
+        }
+
+        let mut methods = Vec::new();
+        for method in self.routes.iter().flat_map(|route| route.methods()) {
+            if !methods.contains(method) {
+                methods.push(method.clone());
+            }
+        }
+        Some(methods)
+    }
 }
 
 impl<T> HttpServiceFactory for Resource<T>
@@ -389,11 +539,22 @@ where
         if let Some(ref name) = self.name {
             *rdef.name_mut() = name.clone();
         }
-        // custom app data storage
-        if let Some(ref mut ext) = self.data {
-            config.set_service_data(ext);
-        }
-        config.register_service(rdef, guards, self, None)
+        config.check_data(&self.requires, self.data.as_ref(), rdef.pattern());
+
+        let meta = ResourceMeta {
+            methods: self.allowed_methods(),
+            description: self.description.take(),
+            routes: self
+                .routes
+                .iter()
+                .map(|route| RouteDescription {
+                    methods: route.methods().to_vec(),
+                    description: route.description().cloned(),
+                })
+                .collect(),
+            sitemap: self.sitemap.take(),
+        };
+        config.register_resource(rdef, guards, self, None, meta)
     }
 }
 
@@ -408,10 +569,22 @@ where
     >,
 {
     fn into_factory(self) -> T {
+        let auto_options = self.auto_options;
+        let allow = self.allowed_methods().and_then(|mut methods| {
+            if auto_options && !methods.contains(&Method::OPTIONS) {
+                methods.push(Method::OPTIONS);
+            }
+            let methods: Vec<_> = methods.iter().map(|m| m.as_str()).collect();
+            HeaderValue::from_str(&methods.join(", ")).ok()
+        });
+
         *self.factory_ref.borrow_mut() = Some(ResourceFactory {
             routes: self.routes,
             data: self.data.map(Rc::new),
             default: self.default,
+            allow,
+            auto_options,
+            encoding: self.encoding,
         });
 
         self.endpoint
@@ -422,6 +595,9 @@ pub struct ResourceFactory {
     routes: Vec<Route>,
     data: Option<Rc<Extensions>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
+    allow: Option<HeaderValue>,
+    auto_options: bool,
+    encoding: Option<ContentEncoding>,
 }
 
 impl ServiceFactory for ResourceFactory {
@@ -449,6 +625,9 @@ impl ServiceFactory for ResourceFactory {
             data: self.data.clone(),
             default: None,
             default_fut,
+            allow: self.allow.clone(),
+            auto_options: self.auto_options,
+            encoding: self.encoding,
         }
     }
 }
@@ -463,6 +642,9 @@ pub struct CreateResourceService {
     data: Option<Rc<Extensions>>,
     default: Option<HttpService>,
     default_fut: Option<LocalBoxFuture<'static, Result<HttpService, ()>>>,
+    allow: Option<HeaderValue>,
+    auto_options: bool,
+    encoding: Option<ContentEncoding>,
 }
 
 impl Future for CreateResourceService {
@@ -506,6 +688,9 @@ impl Future for CreateResourceService {
                 routes,
                 data: self.data.clone(),
                 default: self.default.take(),
+                allow: self.allow.take(),
+                auto_options: self.auto_options,
+                encoding: self.encoding,
             }))
         } else {
             Poll::Pending
@@ -517,6 +702,9 @@ pub struct ResourceService {
     routes: Vec<RouteService>,
     data: Option<Rc<Extensions>>,
     default: Option<HttpService>,
+    allow: Option<HeaderValue>,
+    auto_options: bool,
+    encoding: Option<ContentEncoding>,
 }
 
 impl Service for ResourceService {
@@ -533,22 +721,32 @@ impl Service for ResourceService {
     }
 
     fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        if let Some(encoding) = self.encoding {
+            req.extensions_mut().insert(Enc(encoding));
+        }
         for route in self.routes.iter_mut() {
             if route.check(&mut req) {
                 if let Some(ref data) = self.data {
-                    req.set_data_container(data.clone());
+                    req.add_data_container(data.clone());
                 }
                 return Either::Right(route.call(req));
             }
         }
+        if self.auto_options && req.method() == Method::OPTIONS {
+            if let Some(ref allow) = self.allow {
+                let mut res = Response::Ok().finish();
+                res.headers_mut().insert(ALLOW, allow.clone());
+                return Either::Left(ok(req.into_response(res)));
+            }
+        }
         if let Some(ref mut default) = self.default {
             Either::Right(default.call(req))
         } else {
-            let req = req.into_parts().0;
-            Either::Left(ok(ServiceResponse::new(
-                req,
-                Response::MethodNotAllowed().finish(),
-            )))
+            let mut res = Response::MethodNotAllowed().finish();
+            if let Some(ref allow) = self.allow {
+                res.headers_mut().insert(ALLOW, allow.clone());
+            }
+            Either::Left(ok(req.into_response(res)))
         }
     }
 }
@@ -722,6 +920,105 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[actori_rt::test]
+    async fn test_allowed_methods() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(|| HttpResponse::Ok()))
+                        .route(web::post().to(|| HttpResponse::Ok()))
+                        .route(web::get().to(|| HttpResponse::Ok())),
+                )
+                .service(web::resource("/any").to(|| HttpResponse::Ok()))
+                .service(web::scope("/scope").service(
+                    web::resource("/put").route(web::put().to(|| HttpResponse::Ok())),
+                ))
+                .default_service(web::to(|req: crate::HttpRequest| {
+                    let rmap = req.resource_map();
+                    assert_eq!(
+                        rmap.allowed_methods("/test"),
+                        Some(&[Method::GET, Method::POST][..])
+                    );
+                    assert_eq!(
+                        rmap.allowed_methods("/scope/put"),
+                        Some(&[Method::PUT][..])
+                    );
+                    assert_eq!(rmap.allowed_methods("/any"), None);
+                    assert_eq!(rmap.allowed_methods("/unknown"), None);
+                    HttpResponse::Ok()
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actori_rt::test]
+    async fn test_describe() {
+        use crate::describe::Description;
+
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/users")
+                        .name("users")
+                        .describe(Description::new().summary("Users").tag("users"))
+                        .route(
+                            web::post()
+                                .describe(
+                                    Description::new()
+                                        .summary("Create user")
+                                        .request_schema(
+                                            serde_json::json!({"type": "object"}),
+                                        ),
+                                )
+                                .to(|| HttpResponse::Ok()),
+                        )
+                        .route(web::get().to(|| HttpResponse::Ok())),
+                )
+                .service(
+                    web::scope("/admin")
+                        .service(web::resource("/stats").to(|| HttpResponse::Ok())),
+                )
+                .default_service(web::to(|req: crate::HttpRequest| {
+                    let resources = req.resource_map().resources();
+                    assert_eq!(resources.len(), 2);
+
+                    let users = &resources[0];
+                    assert_eq!(users.pattern, "/users");
+                    assert_eq!(users.name.as_ref().map(|s| s.as_str()), Some("users"));
+                    let desc = users.description.as_ref().unwrap();
+                    assert_eq!(desc.get_summary(), Some("Users"));
+                    assert_eq!(desc.get_tags(), &["users".to_string()][..]);
+                    assert_eq!(users.routes.len(), 2);
+                    assert_eq!(users.routes[0].methods, vec![Method::POST]);
+                    assert_eq!(
+                        users.routes[0]
+                            .description
+                            .as_ref()
+                            .and_then(|d| d.get_request_schema()),
+                        Some(&serde_json::json!({"type": "object"}))
+                    );
+                    assert!(users.routes[1].description.is_none());
+
+                    let stats = &resources[1];
+                    assert_eq!(stats.pattern, "/admin/stats");
+                    assert!(stats.name.is_none());
+                    assert!(stats.description.is_none());
+                    assert!(stats.routes[0].methods.is_empty());
+                    HttpResponse::Ok()
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[actori_rt::test]
     async fn test_resource_guards() {
         let mut srv = init_service(
@@ -793,4 +1090,51 @@ mod tests {
         let resp = call_service(&mut srv, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[actori_rt::test]
+    async fn test_auto_options() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .auto_options(true)
+                        .route(web::get().to(|| HttpResponse::Ok()))
+                        .route(web::post().to(|| HttpResponse::Created())),
+                )
+                .service(
+                    web::resource("/any")
+                        .auto_options(true)
+                        .to(|| HttpResponse::Ok()),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            "GET, POST, OPTIONS"
+        );
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::DELETE)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            resp.headers().get(header::ALLOW).unwrap(),
+            "GET, POST, OPTIONS"
+        );
+
+        // methods are not known, request is handled by the route
+        let req = TestRequest::with_uri("/any")
+            .method(Method::OPTIONS)
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(header::ALLOW).is_none());
+    }
 }
@@ -6,7 +6,8 @@ use std::task::{Context, Poll};
 
 use actori_http::error::InternalError;
 use actori_http::http::{
-    header::IntoHeaderValue, Error as HttpError, HeaderMap, HeaderName, StatusCode,
+    header::{IntoHeaderValue, LOCATION},
+    Error as HttpError, HeaderMap, HeaderName, StatusCode,
 };
 use actori_http::{Error, Response, ResponseBuilder};
 use bytes::{Bytes, BytesMut};
@@ -103,6 +104,8 @@ where
     }
 }
 
+/// Error branch is converted to `Error`. If error is a complete response,
+/// i.e. `Result<T, HttpResponse>`, response is sent as is.
 impl<T, E> Responder for Result<T, E>
 where
     T: Responder,
@@ -214,6 +217,57 @@ impl Responder for BytesMut {
     }
 }
 
+/// Responder for *303 See Other* redirect.
+///
+/// It is used for post/redirect/get pattern, handler of a form submission
+/// redirects client to a page that is safe to reload. Browser follows the
+/// redirect with `GET` request.
+///
+/// ```rust
+/// use actori_web::{web, App, HttpResponse, SeeOther};
+///
+/// async fn submit() -> SeeOther {
+///     // process the form...
+///     SeeOther::to("/orders/1")
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .route("/orders", web::post().to(submit))
+///         .route("/orders/{id}", web::get().to(|| HttpResponse::Ok()));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeeOther {
+    location: String,
+}
+
+impl SeeOther {
+    /// Redirect to the url, i.e. a path or result of
+    /// `HttpRequest::url_for()`.
+    pub fn to<U: Into<String>>(url: U) -> Self {
+        SeeOther {
+            location: url.into(),
+        }
+    }
+
+    /// Redirect location
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+}
+
+impl Responder for SeeOther {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(Response::SeeOther()
+            .insert_header((LOCATION, self.location))
+            .finish())
+    }
+}
+
 /// Allows to override status code and headers for a responder.
 pub struct CustomResponder<T> {
     responder: T,
@@ -457,10 +511,10 @@ pub(crate) mod tests {
     use bytes::{Bytes, BytesMut};
 
     use super::*;
-    use crate::dev::{Body, ResponseBody};
+    use crate::dev::{Body, Payload, ResponseBody};
     use crate::http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
-    use crate::test::{init_service, TestRequest};
-    use crate::{error, web, App, HttpResponse};
+    use crate::test::{init_service, read_body, TestRequest};
+    use crate::{error, web, App, FromRequest, HttpResponse};
 
     #[actori_rt::test]
     async fn test_option_responder() {
@@ -603,6 +657,57 @@ pub(crate) mod tests {
         assert!(res.is_err());
     }
 
+    #[actori_rt::test]
+    async fn test_result_response_error() {
+        struct Token;
+
+        impl FromRequest for Token {
+            type Error = HttpResponse;
+            type Future = Ready<Result<Self, HttpResponse>>;
+            type Config = ();
+
+            fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+                if req.headers().contains_key("x-token") {
+                    ok(Token)
+                } else {
+                    err(HttpResponse::Unauthorized()
+                        .insert_header(("x-reason", "token"))
+                        .finish())
+                }
+            }
+        }
+
+        let mut srv = init_service(App::new().service(web::resource("/").to(
+            |_: Token, req: HttpRequest| async move {
+                if req.query_string().is_empty() {
+                    Ok(HttpResponse::Ok().body("ok"))
+                } else {
+                    Err(HttpResponse::Conflict().body("conflict"))
+                }
+            },
+        )))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(resp.headers().get("x-reason").unwrap(), "token");
+        assert!(resp.response().error().is_none());
+
+        let req = TestRequest::with_header("x-token", "1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/?fail")
+            .header("x-token", "1")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert!(resp.response().error().is_none());
+        let body = read_body(resp).await;
+        assert_eq!(body, Bytes::from_static(b"conflict"));
+    }
+
     #[actori_rt::test]
     async fn test_custom_responder() {
         let req = TestRequest::default().to_http_request();
@@ -653,4 +758,21 @@ pub(crate) mod tests {
             HeaderValue::from_static("json")
         );
     }
+
+    #[actori_rt::test]
+    async fn test_see_other() {
+        let req = TestRequest::default().to_http_request();
+        let res = SeeOther::to("/orders/1").respond_to(&req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            HeaderValue::from_static("/orders/1")
+        );
+
+        let res = SeeOther::to("invalid\nlocation")
+            .respond_to(&req)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
 }
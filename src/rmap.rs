@@ -1,12 +1,25 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use actori_http::http::Method;
 use actori_router::ResourceDef;
 use fxhash::FxHashMap;
 use url::Url;
 
+use crate::describe::{Description, ResourceDescription, RouteDescription};
 use crate::error::UrlGenerationError;
 use crate::request::HttpRequest;
+use crate::sitemap::SitemapEntry;
+
+/// Metadata of the registered resource
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ResourceMeta {
+    /// Methods of the resource, known only if every route has method guard
+    pub(crate) methods: Option<Vec<Method>>,
+    pub(crate) description: Option<Description>,
+    pub(crate) routes: Vec<RouteDescription>,
+    pub(crate) sitemap: Option<SitemapEntry>,
+}
 
 #[derive(Clone, Debug)]
 pub struct ResourceMap {
@@ -14,6 +27,7 @@ pub struct ResourceMap {
     parent: RefCell<Option<Rc<ResourceMap>>>,
     named: FxHashMap<String, ResourceDef>,
     patterns: Vec<(ResourceDef, Option<Rc<ResourceMap>>)>,
+    meta: FxHashMap<usize, ResourceMeta>,
 }
 
 impl ResourceMap {
@@ -23,6 +37,7 @@ impl ResourceMap {
             parent: RefCell::new(None),
             named: FxHashMap::default(),
             patterns: Vec::new(),
+            meta: FxHashMap::default(),
         }
     }
 
@@ -35,6 +50,16 @@ impl ResourceMap {
         }
     }
 
+    pub(crate) fn add_resource(
+        &mut self,
+        pattern: &mut ResourceDef,
+        nested: Option<Rc<ResourceMap>>,
+        meta: ResourceMeta,
+    ) {
+        self.meta.insert(self.patterns.len(), meta);
+        self.add(pattern, nested);
+    }
+
     pub(crate) fn finish(&self, current: Rc<ResourceMap>) {
         for (_, nested) in &self.patterns {
             if let Some(ref nested) = nested {
@@ -65,13 +90,7 @@ impl ResourceMap {
 
         if self.patterns_for(name, &mut path, &mut elements)?.is_some() {
             if path.starts_with('/') {
-                let conn = req.connection_info();
-                Ok(Url::parse(&format!(
-                    "{}://{}{}",
-                    conn.scheme(),
-                    conn.host(),
-                    path
-                ))?)
+                req.absolute_url_for(&path)
             } else {
                 Ok(Url::parse(&path)?)
             }
@@ -80,6 +99,38 @@ impl ResourceMap {
         }
     }
 
+    /// Generate path for named resource relative to application root
+    ///
+    /// External resources are not supported.
+    pub(crate) fn path_for<U, I>(
+        &self,
+        name: &str,
+        elements: U,
+    ) -> Result<String, UrlGenerationError>
+    where
+        U: IntoIterator<Item = I>,
+        I: AsRef<str>,
+    {
+        let mut path = String::new();
+        let mut elements = elements.into_iter();
+
+        if self.patterns_for(name, &mut path, &mut elements)?.is_some()
+            && path.starts_with('/')
+        {
+            // remove application mount prefix
+            let prefix = self.root.pattern();
+            if !prefix.is_empty() && path.starts_with(prefix) {
+                path = match &path[prefix.len()..] {
+                    "" => "/".to_owned(),
+                    p => p.to_owned(),
+                };
+            }
+            Ok(path)
+        } else {
+            Err(UrlGenerationError::ResourceNotFound)
+        }
+    }
+
     pub fn has_resource(&self, path: &str) -> bool {
         let path = if path.is_empty() { "/" } else { path };
 
@@ -95,6 +146,95 @@ impl ResourceMap {
         false
     }
 
+    /// Http methods allowed by the resource that matches the path.
+    ///
+    /// Returns `None` if no resource matches the path or if the resource
+    /// has routes without method guards.
+    pub fn allowed_methods(&self, path: &str) -> Option<&[Method]> {
+        let path = if path.is_empty() { "/" } else { path };
+
+        for (idx, (pattern, rmap)) in self.patterns.iter().enumerate() {
+            if let Some(ref rmap) = rmap {
+                if let Some(plen) = pattern.is_prefix_match(path) {
+                    return rmap.allowed_methods(&path[plen..]);
+                }
+            } else if pattern.is_match(path) {
+                return self
+                    .meta
+                    .get(&idx)
+                    .and_then(|meta| meta.methods.as_ref())
+                    .map(|methods| methods.as_slice());
+            }
+        }
+        None
+    }
+
+    /// Full pattern of the resource that matches the path.
+    ///
+    /// Patterns of enclosing scopes are included.
+    pub fn match_pattern(&self, path: &str) -> Option<String> {
+        let path = if path.is_empty() { "/" } else { path };
+
+        for (pattern, rmap) in &self.patterns {
+            if let Some(ref rmap) = rmap {
+                if let Some(plen) = pattern.is_prefix_match(path) {
+                    return rmap
+                        .match_pattern(&path[plen..])
+                        .map(|tail| format!("{}{}", pattern.pattern(), tail));
+                }
+            } else if pattern.is_match(path) {
+                return Some(pattern.pattern().to_owned());
+            }
+        }
+        None
+    }
+
+    /// Resources registered in the map and in the nested scopes.
+    ///
+    /// Patterns of enclosing scopes are included, external resources
+    /// are not listed.
+    pub fn resources(&self) -> Vec<ResourceDescription> {
+        let mut resources = Vec::new();
+        self.collect_resources("", &mut resources);
+        resources
+    }
+
+    /// Resources with static paths that respond to `GET` requests.
+    ///
+    /// Resources with variable segments in the pattern are skipped, resource
+    /// responds to `GET` requests if it has a route for `GET` method or a
+    /// route without method guard. It is used by `web::sitemap()`.
+    pub fn static_resources(&self) -> impl Iterator<Item = ResourceDescription> {
+        self.resources().into_iter().filter(|res| {
+            !res.pattern.contains('{')
+                && res.routes.iter().any(|route| {
+                    route.methods.is_empty() || route.methods.contains(&Method::GET)
+                })
+        })
+    }
+
+    fn collect_resources(&self, prefix: &str, resources: &mut Vec<ResourceDescription>) {
+        for (idx, (pattern, rmap)) in self.patterns.iter().enumerate() {
+            let path = format!("{}{}", prefix, pattern.pattern());
+            if let Some(ref rmap) = rmap {
+                rmap.collect_resources(&path, resources);
+            } else if let Some(meta) = self.meta.get(&idx) {
+                let name = pattern.name();
+                resources.push(ResourceDescription {
+                    pattern: path,
+                    name: if name.is_empty() {
+                        None
+                    } else {
+                        Some(name.to_string())
+                    },
+                    description: meta.description.clone(),
+                    routes: meta.routes.clone(),
+                    sitemap: meta.sitemap.clone(),
+                });
+            }
+        }
+    }
+
     fn patterns_for<U, I>(
         &self,
         name: &str,
@@ -3,10 +3,13 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actori_http::{http::Method, Error};
+use actori_http::http::{header::ContentEncoding, Method};
+use actori_http::{Error, HttpMessage};
 use actori_service::{Service, ServiceFactory};
 use futures::future::{ready, FutureExt, LocalBoxFuture};
 
+use crate::describe::Description;
+use crate::dev::Enc;
 use crate::extract::FromRequest;
 use crate::guard::{self, Guard};
 use crate::handler::{Extract, Factory, Handler};
@@ -42,6 +45,9 @@ type BoxedRouteNewService<Req, Res> = Box<
 pub struct Route {
     service: BoxedRouteNewService<ServiceRequest, ServiceResponse>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    methods: Vec<Method>,
+    description: Option<Description>,
+    encoding: Option<ContentEncoding>,
 }
 
 impl Route {
@@ -52,12 +58,25 @@ impl Route {
                 ready(HttpResponse::NotFound())
             })))),
             guards: Rc::new(Vec::new()),
+            methods: Vec::new(),
+            description: None,
+            encoding: None,
         }
     }
 
     pub(crate) fn take_guards(&mut self) -> Vec<Box<dyn Guard>> {
         std::mem::replace(Rc::get_mut(&mut self.guards).unwrap(), Vec::new())
     }
+
+    /// Methods registered with `Route::method()`
+    pub(crate) fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    /// Description set with `Route::describe()`
+    pub(crate) fn description(&self) -> Option<&Description> {
+        self.description.as_ref()
+    }
 }
 
 impl ServiceFactory for Route {
@@ -73,6 +92,7 @@ impl ServiceFactory for Route {
         CreateRouteService {
             fut: self.service.new_service(()),
             guards: self.guards.clone(),
+            encoding: self.encoding,
         }
     }
 }
@@ -87,6 +107,7 @@ pub struct CreateRouteService {
     #[pin]
     fut: RouteFuture,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    encoding: Option<ContentEncoding>,
 }
 
 impl Future for CreateRouteService {
@@ -99,6 +120,7 @@ impl Future for CreateRouteService {
             Poll::Ready(service) => Poll::Ready(Ok(RouteService {
                 service,
                 guards: this.guards.clone(),
+                encoding: *this.encoding,
             })),
             Poll::Pending => Poll::Pending,
         }
@@ -108,6 +130,7 @@ impl Future for CreateRouteService {
 pub struct RouteService {
     service: BoxedRouteService<ServiceRequest, ServiceResponse>,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    encoding: Option<ContentEncoding>,
 }
 
 impl RouteService {
@@ -132,6 +155,9 @@ impl Service for RouteService {
     }
 
     fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if let Some(encoding) = self.encoding {
+            req.extensions_mut().insert(Enc(encoding));
+        }
         self.service.call(req).boxed_local()
     }
 }
@@ -153,7 +179,8 @@ impl Route {
     pub fn method(mut self, method: Method) -> Self {
         Rc::get_mut(&mut self.guards)
             .unwrap()
-            .push(Box::new(guard::Method(method)));
+            .push(Box::new(guard::Method(method.clone())));
+        self.methods.push(method);
         self
     }
 
@@ -175,6 +202,52 @@ impl Route {
         self
     }
 
+    /// Attach documentation metadata to the route.
+    ///
+    /// Description is available at runtime via `ResourceMap::resources()`.
+    ///
+    /// ```rust
+    /// # use actori_web::*;
+    /// use actori_web::describe::Description;
+    ///
+    /// # fn main() {
+    /// App::new().service(web::resource("/users").route(
+    ///     web::post()
+    ///         .describe(
+    ///             Description::new()
+    ///                 .summary("Create user")
+    ///                 .request_schema(serde_json::json!({"type": "object"})),
+    ///         )
+    ///         .to(|| HttpResponse::Created()))
+    /// );
+    /// # }
+    /// ```
+    pub fn describe(mut self, description: Description) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Override response encoding of the `Compress` middleware for
+    /// responses of this route, including error responses.
+    ///
+    /// ```rust
+    /// # use actori_web::*;
+    /// use actori_web::http::ContentEncoding;
+    ///
+    /// # fn main() {
+    /// App::new().wrap(middleware::Compress::default()).service(
+    ///     web::resource("/stream").route(
+    ///         web::get()
+    ///             .encoding(ContentEncoding::Identity)
+    ///             .to(|| HttpResponse::Ok())),
+    /// );
+    /// # }
+    /// ```
+    pub fn encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
     /// Set handler function, use request extractors for parameters.
     ///
     /// ```rust
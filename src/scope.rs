@@ -12,8 +12,9 @@ use actori_service::{
 };
 use futures::future::{ok, Either, Future, LocalBoxFuture, Ready};
 
+use crate::app_service::check_guards;
 use crate::config::ServiceConfig;
-use crate::data::Data;
+use crate::data::{Data, DataRequirement};
 use crate::dev::{AppService, HttpServiceFactory};
 use crate::error::Error;
 use crate::guard::Guard;
@@ -62,6 +63,8 @@ pub struct Scope<T = ScopeEndpoint> {
     endpoint: T,
     rdef: String,
     data: Option<Extensions>,
+    inherit_data: bool,
+    requires: Vec<DataRequirement>,
     services: Vec<Box<dyn AppServiceFactory>>,
     guards: Vec<Box<dyn Guard>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
@@ -77,6 +80,8 @@ impl Scope {
             endpoint: ScopeEndpoint::new(fref.clone()),
             rdef: path.to_string(),
             data: None,
+            inherit_data: true,
+            requires: Vec::new(),
             guards: Vec::new(),
             services: Vec::new(),
             default: Rc::new(RefCell::new(None)),
@@ -162,6 +167,68 @@ where
         self
     }
 
+    /// Control visibility of data registered by enclosing scopes and the application.
+    ///
+    /// By default scope data is merged with data of the enclosing scopes and
+    /// the application. Scope data shadows outer data of the same type, other
+    /// outer data stays accessible. If inheritance is disabled, handlers
+    /// registered within this scope could access only data registered with
+    /// this scope and its nested scopes and resources.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// async fn index(data: web::Data<usize>) -> HttpResponse {
+    ///     HttpResponse::Ok().body(format!("{}", data))
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .data(String::from("app"))
+    ///         .service(
+    ///             web::scope("/app")
+    ///                 .inherit_data(false)
+    ///                 .data(10usize)
+    ///                 // `web::Data<String>` is not available here
+    ///                 .route("/index.html", web::get().to(index)));
+    /// }
+    /// ```
+    pub fn inherit_data(mut self, inherit: bool) -> Self {
+        self.inherit_data = inherit;
+        self
+    }
+
+    /// Declare data type required by services of this scope.
+    ///
+    /// Type is the type of the stored item, i.e. `Data<T>` for data
+    /// registered with `data()` method. Requirement is verified during
+    /// application initialization, if data is not registered on this scope,
+    /// on enclosing scopes or on the application, error is logged and
+    /// application fails to start instead of responding with
+    /// *500 Internal Server Error* at runtime.
+    ///
+    /// ```rust
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// struct Db;
+    ///
+    /// async fn index(db: web::Data<Db>) -> HttpResponse {
+    ///     HttpResponse::Ok().finish()
+    /// }
+    ///
+    /// fn main() {
+    ///     let app = App::new().data(Db).service(
+    ///         web::scope("/app")
+    ///             .requires::<web::Data<Db>>()
+    ///             .route("/index.html", web::get().to(index)),
+    ///     );
+    /// }
+    /// ```
+    pub fn requires<U: 'static>(mut self) -> Self {
+        self.requires.push(DataRequirement::of::<U>());
+        self
+    }
+
     /// Run external configuration as part of the scope building
     /// process
     ///
@@ -220,7 +287,7 @@ where
     ///
     /// * *Resource* is an entry in resource table which corresponds to requested URL.
     /// * *Scope* is a set of resources with common root path.
-    /// * "StaticFiles" is a service for static files support
+    /// * `web::Files` is a service for static files support
     ///
     /// ```rust
     /// use actori_web::{web, App, HttpRequest};
@@ -335,6 +402,8 @@ where
             endpoint: apply(mw, self.endpoint),
             rdef: self.rdef,
             data: self.data,
+            inherit_data: self.inherit_data,
+            requires: self.requires,
             guards: self.guards,
             services: self.services,
             default: self.default,
@@ -395,6 +464,8 @@ where
             endpoint: apply_fn_factory(self.endpoint, mw),
             rdef: self.rdef,
             data: self.data,
+            inherit_data: self.inherit_data,
+            requires: self.requires,
             guards: self.guards,
             services: self.services,
             default: self.default,
@@ -420,8 +491,15 @@ where
             *self.default.borrow_mut() = Some(config.default_service());
         }
 
-        // register nested services
+        // scope that does not inherit data always replaces data container
+        if !self.inherit_data && self.data.is_none() {
+            self.data = Some(Extensions::new());
+        }
+
+        // check required data and register nested services
         let mut cfg = config.clone_config();
+        cfg.set_scope_data(self.data.as_ref(), self.inherit_data);
+        cfg.check_data(&self.requires, None, &self.rdef);
         self.services
             .into_iter()
             .for_each(|mut srv| srv.register(&mut cfg));
@@ -433,21 +511,17 @@ where
             rmap.add(&mut rdef, None);
         }
 
-        // custom app data storage
-        if let Some(ref mut ext) = self.data {
-            config.set_service_data(ext);
-        }
-
         // complete scope pipeline creation
         *self.factory_ref.borrow_mut() = Some(ScopeFactory {
             data: self.data.take().map(Rc::new),
+            inherit_data: self.inherit_data,
             default: self.default.clone(),
             services: Rc::new(
                 cfg.into_services()
                     .1
                     .into_iter()
-                    .map(|(mut rdef, srv, guards, nested)| {
-                        rmap.add(&mut rdef, nested);
+                    .map(|(mut rdef, srv, guards, nested, meta)| {
+                        rmap.add_resource(&mut rdef, nested, meta);
                         (rdef, srv, RefCell::new(guards))
                     })
                     .collect(),
@@ -473,6 +547,7 @@ where
 
 pub struct ScopeFactory {
     data: Option<Rc<Extensions>>,
+    inherit_data: bool,
     services: Rc<Vec<(ResourceDef, HttpNewService, RefCell<Option<Guards>>)>>,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
 }
@@ -507,6 +582,7 @@ impl ServiceFactory for ScopeFactory {
                 .collect(),
             default: None,
             data: self.data.clone(),
+            inherit_data: self.inherit_data,
             default_fut,
         }
     }
@@ -518,6 +594,7 @@ impl ServiceFactory for ScopeFactory {
 pub struct ScopeFactoryResponse {
     fut: Vec<CreateScopeServiceItem>,
     data: Option<Rc<Extensions>>,
+    inherit_data: bool,
     default: Option<HttpService>,
     default_fut: Option<LocalBoxFuture<'static, Result<HttpService, ()>>>,
 }
@@ -581,6 +658,7 @@ impl Future for ScopeFactoryResponse {
                 });
             Poll::Ready(Ok(ScopeService {
                 data: self.data.clone(),
+                inherit_data: self.inherit_data,
                 router: router.finish(),
                 default: self.default.take(),
                 _ready: None,
@@ -593,6 +671,7 @@ impl Future for ScopeFactoryResponse {
 
 pub struct ScopeService {
     data: Option<Rc<Extensions>>,
+    inherit_data: bool,
     router: Router<HttpService, Vec<Box<dyn Guard>>>,
     default: Option<HttpService>,
     _ready: Option<(ServiceRequest, ResourceInfo)>,
@@ -609,20 +688,15 @@ impl Service for ScopeService {
     }
 
     fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_mut_checked(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
-                    }
-                }
-            }
-            true
-        });
+        let res = self.router.recognize_mut_checked(&mut req, check_guards);
 
         if let Some((srv, _info)) = res {
             if let Some(ref data) = self.data {
-                req.set_data_container(data.clone());
+                if self.inherit_data {
+                    req.add_data_container(data.clone());
+                } else {
+                    req.set_data_container(data.clone());
+                }
             }
             Either::Left(srv.call(req))
         } else if let Some(ref mut default) = self.default {
@@ -661,10 +735,11 @@ impl ServiceFactory for ScopeEndpoint {
 
 #[cfg(test)]
 mod tests {
-    use actori_service::Service;
+    use actori_service::{IntoServiceFactory, Service, ServiceFactory};
     use bytes::Bytes;
     use futures::future::ok;
 
+    use crate::config::AppConfig;
     use crate::dev::{Body, ResponseBody};
     use crate::http::{header, HeaderValue, Method, StatusCode};
     use crate::middleware::DefaultHeaders;
@@ -1138,6 +1213,118 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actori_rt::test]
+    async fn test_inherit_data() {
+        let mut srv = init_service(
+            App::new()
+                .data(1usize)
+                .app_data(web::Data::new(2u32))
+                .service(web::scope("app").data(10usize).service(
+                    web::scope("v1").data(20u64).route(
+                        "/t",
+                        web::get().to(|req: HttpRequest| {
+                            assert_eq!(
+                                **req.app_data::<web::Data<usize>>().unwrap(),
+                                10
+                            );
+                            assert_eq!(**req.app_data::<web::Data<u32>>().unwrap(), 2);
+                            assert_eq!(**req.app_data::<web::Data<u64>>().unwrap(), 20);
+                            assert_eq!(req.app_data_types().len(), 3);
+                            HttpResponse::Ok()
+                        }),
+                    ),
+                )),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/app/v1/t").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actori_rt::test]
+    async fn test_inherit_data_disabled() {
+        let mut srv = init_service(
+            App::new()
+                .data(1usize)
+                .app_data(web::Data::new(2u32))
+                .service(web::scope("app").inherit_data(false).data(10u64).route(
+                    "/t",
+                    web::get().to(|req: HttpRequest| {
+                        assert!(req.app_data::<web::Data<usize>>().is_none());
+                        assert!(req.app_data::<web::Data<u32>>().is_none());
+                        assert_eq!(**req.app_data::<web::Data<u64>>().unwrap(), 10);
+                        HttpResponse::Ok()
+                    }),
+                ))
+                .service(web::scope("empty").inherit_data(false).route(
+                    "/t",
+                    web::get().to(|req: HttpRequest| {
+                        assert!(req.app_data_types().is_empty());
+                        HttpResponse::Ok()
+                    }),
+                )),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/app/t").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/empty/t").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actori_rt::test]
+    async fn test_requires_data() {
+        let mut srv = init_service(
+            App::new()
+                .data(1usize)
+                .app_data(2u32)
+                .data_factory(|| ok::<_, ()>(3u64))
+                .service(
+                    web::scope("app")
+                        .requires::<web::Data<usize>>()
+                        .requires::<u32>()
+                        .data('*')
+                        .service(
+                            web::resource("/t")
+                                .requires::<web::Data<char>>()
+                                .requires::<web::Data<u64>>()
+                                .data(1.0f64)
+                                .requires::<web::Data<f64>>()
+                                .to(|| HttpResponse::Ok()),
+                        ),
+                ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/app/t").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let app = App::new().data(1usize).service(
+            web::scope("app")
+                .inherit_data(false)
+                .requires::<web::Data<usize>>()
+                .route("/t", web::get().to(|| HttpResponse::Ok())),
+        );
+        let srv = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(srv.is_err());
+
+        let app = App::new().service(
+            web::scope("app").service(
+                web::scope("v1").data(1usize).service(
+                    web::resource("/t")
+                        .requires::<usize>()
+                        .to(|| HttpResponse::Ok()),
+                ),
+            ),
+        );
+        let srv = app.into_factory().new_service(AppConfig::default()).await;
+        assert!(srv.is_err());
+    }
+
     #[actori_rt::test]
     async fn test_scope_config() {
         let mut srv =
@@ -1,12 +1,23 @@
+use std::future::Future;
 use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{fmt, io, net};
 
-use actori_http::{body::MessageBody, Error, HttpService, KeepAlive, Request, Response};
+use actori_codec::Framed;
+use actori_http::h1::{Codec, ExpectHandler};
+use actori_http::http::HeaderValue;
+use actori_http::{
+    body::MessageBody, Error, HttpService, KeepAlive, Request, Response,
+    DEFAULT_OPTIONS_ALLOW,
+};
+use actori_rt::net::TcpStream;
 use actori_server::{Server, ServerBuilder};
+use actori_service::boxed::{self, BoxServiceFactory};
 use actori_service::{map_config, IntoServiceFactory, Service, ServiceFactory};
 
 use net2::TcpBuilder;
+use url::Url;
 
 #[cfg(unix)]
 use actori_http::Protocol;
@@ -15,12 +26,22 @@ use actori_service::pipeline_factory;
 #[cfg(unix)]
 use futures::future::ok;
 
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use actori_http::TlsReload;
 #[cfg(feature = "openssl")]
 use actori_tls::openssl::{AlpnError, SslAcceptor, SslAcceptorBuilder};
 #[cfg(feature = "rustls")]
 use actori_tls::rustls::ServerConfig as RustlsServerConfig;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, SharedData};
+use crate::data::Data;
+use crate::listeners::{ListenerGate, ListenerState, Listeners};
+use crate::middleware::TrustedProxies;
+use crate::shutdown::Shutdown;
+
+type ExpectFactory = BoxServiceFactory<(), Request, Request, Error, ()>;
+type UpgradeFactory =
+    BoxServiceFactory<(), (Request, Framed<TcpStream, Codec>), (), Error, ()>;
 
 struct Socket {
     scheme: &'static str,
@@ -32,6 +53,47 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    tls_handshake_timeout: u64,
+    max_tls_handshakes: usize,
+    options_allow: Option<HeaderValue>,
+    max_payload_size: u64,
+    payload_drain_size: u64,
+    close_on_error: bool,
+    public_url: Option<Url>,
+    trusted_proxies: Option<TrustedProxies>,
+    shared_data: Vec<SharedData>,
+    on_expect: Option<Arc<dyn Fn() -> ExpectFactory + Send + Sync>>,
+    on_upgrade: Option<Arc<dyn Fn() -> UpgradeFactory + Send + Sync>>,
+}
+
+impl Config {
+    /// Create application config for the listener
+    fn app_config(&self, secure: bool, addr: net::SocketAddr) -> AppConfig {
+        let mut cfg = AppConfig::new(
+            secure,
+            addr,
+            self.host.clone().unwrap_or_else(|| format!("{}", addr)),
+        );
+        if let Some(ref url) = self.public_url {
+            cfg.set_public_url(url.clone());
+        }
+        if let Some(ref proxies) = self.trusted_proxies {
+            cfg.set_trusted_proxies(proxies.clone());
+        }
+        for data in self.shared_data.iter() {
+            cfg.add_shared_data(data.clone());
+        }
+        cfg
+    }
+
+    /// Create expect handler for the listener
+    fn expect(&self) -> ExpectFactory {
+        if let Some(ref f) = self.on_expect {
+            f()
+        } else {
+            boxed::factory(ExpectHandler.map_init_err(|_| ()))
+        }
+    }
 }
 
 /// An HTTP Server.
@@ -66,6 +128,11 @@ where
     backlog: i32,
     sockets: Vec<Socket>,
     builder: ServerBuilder,
+    listener_name: Option<String>,
+    listeners: Listeners,
+    shutdown: Shutdown,
+    signals: bool,
+    shutdown_timeout: u64,
     _t: PhantomData<(S, B)>,
 }
 
@@ -82,6 +149,7 @@ where
 {
     /// Create new http server with application factory
     pub fn new(factory: F) -> Self {
+        let listeners = Listeners::default();
         HttpServer {
             factory,
             config: Arc::new(Mutex::new(Config {
@@ -89,10 +157,26 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                tls_handshake_timeout: 3000,
+                max_tls_handshakes: 0,
+                options_allow: Some(HeaderValue::from_static(DEFAULT_OPTIONS_ALLOW)),
+                max_payload_size: 0,
+                payload_drain_size: 65_536,
+                close_on_error: false,
+                public_url: None,
+                trusted_proxies: None,
+                shared_data: Vec::new(),
+                on_expect: None,
+                on_upgrade: None,
             })),
             backlog: 1024,
             sockets: Vec::new(),
             builder: ServerBuilder::default(),
+            listener_name: None,
+            shutdown: Shutdown::new(listeners.clone()),
+            listeners,
+            signals: true,
+            shutdown_timeout: 30,
             _t: PhantomData,
         }
     }
@@ -136,7 +220,8 @@ where
     /// Sets the maximum per-worker concurrent connection establish process.
     ///
     /// All listeners will stop accepting connections when this limit is reached. It
-    /// can be used to limit the global SSL CPU usage.
+    /// can be used to limit the global SSL CPU usage. Limit is shared by all servers
+    /// of the process, see `max_tls_handshakes()` for a per-listener limit.
     ///
     /// By default max connections is set to a 256.
     pub fn maxconnrate(self, num: usize) -> Self {
@@ -179,6 +264,257 @@ where
         self
     }
 
+    /// Set max time in milliseconds to complete tls handshake.
+    ///
+    /// Connection is closed if client does not complete tls handshake within
+    /// this time. Without the timeout clients that stall handshakes hold
+    /// handshake slots, see `maxconnrate()`, and tie up workers.
+    ///
+    /// To disable timeout set value to 0.
+    ///
+    /// By default handshake timeout is set to 3000 milliseconds.
+    pub fn tls_handshake_timeout(self, val: u64) -> Self {
+        self.config.lock().unwrap().tls_handshake_timeout = val;
+        self
+    }
+
+    /// Sets the maximum per-worker number of concurrent tls handshakes of
+    /// each tls listener.
+    ///
+    /// Listener of the worker stops accepting connections when this limit is
+    /// reached, until one of the handshakes completes or times out.
+    ///
+    /// To disable limit set value to 0.
+    ///
+    /// By default number of handshakes is limited by `maxconnrate()` only.
+    pub fn max_tls_handshakes(self, num: usize) -> Self {
+        self.config.lock().unwrap().max_tls_handshakes = num;
+        self
+    }
+
+    /// Set `Allow` header value of the response for server-wide `OPTIONS *` requests.
+    ///
+    /// `OPTIONS` requests with asterisk-form request target do not refer to any
+    /// resource, such requests are answered by the server directly and never
+    /// reach application. To pass these requests to the application
+    /// set value to `None`.
+    ///
+    /// By default `Allow` header is set to
+    /// `OPTIONS, GET, HEAD, POST, PUT, PATCH, DELETE`.
+    pub fn options_allow(self, allow: Option<HeaderValue>) -> Self {
+        self.config.lock().unwrap().options_allow = allow;
+        self
+    }
+
+    /// Set max size of request payload in bytes.
+    ///
+    /// Request payload that is bigger fails with `PayloadError::Overflow`
+    /// error, so extractors respond with 413 (Payload Too Large) error
+    /// without waiting for the rest of the payload.
+    ///
+    /// By default payload size is not limited.
+    pub fn max_payload_size(self, size: u64) -> Self {
+        self.config.lock().unwrap().max_payload_size = size;
+        self
+    }
+
+    /// Set max size of unread request payload in bytes that is discarded
+    /// after early response to keep http/1 connection alive.
+    ///
+    /// By default drain size is set to 64Kb.
+    pub fn payload_drain_size(self, size: u64) -> Self {
+        self.config.lock().unwrap().payload_drain_size = size;
+        self
+    }
+
+    /// Close http/1 connections after server error (5xx) responses.
+    ///
+    /// Response could keep connection alive with `HttpResponseBuilder::keep_alive()`.
+    ///
+    /// By default connections are kept alive.
+    pub fn close_on_error(self, close: bool) -> Self {
+        self.config.lock().unwrap().close_on_error = close;
+        self
+    }
+
+    /// Set public base url of the application.
+    ///
+    /// Public url is used for url generation instead of connection information,
+    /// check [AppConfig](./dev/struct.AppConfig.html#method.set_public_url)
+    /// documentation for more information.
+    pub fn public_url(self, url: Url) -> Self {
+        self.config.lock().unwrap().public_url = Some(url);
+        self
+    }
+
+    /// Set trusted proxies of the applications.
+    ///
+    /// Forwarded headers are used for connection information only if
+    /// peer address is trusted, check
+    /// [AppConfig](./dev/struct.AppConfig.html#method.set_trusted_proxies)
+    /// documentation for more information.
+    ///
+    /// ```rust
+    /// use actori_web::{middleware::TrustedProxies, web, App, HttpResponse, HttpServer};
+    ///
+    /// fn main() {
+    ///     let srv = HttpServer::new(|| {
+    ///         App::new().route("/", web::get().to(|| HttpResponse::Ok()))
+    ///     })
+    ///     .trusted_proxies(TrustedProxies::new().trust("10.0.0.0/8"));
+    /// }
+    /// ```
+    pub fn trusted_proxies(self, proxies: TrustedProxies) -> Self {
+        self.config.lock().unwrap().trusted_proxies = Some(proxies);
+        self
+    }
+
+    /// Set application data shared by all workers.
+    ///
+    /// Unlike `App::data()`, that constructs data for each worker, data is
+    /// created once and injected into every application instance, so it is
+    /// shared without wrapping it into an external `Arc`. Data is accessed
+    /// with `Data<T>` extractor as usual. Data registered with `App::data()`
+    /// or `App::app_data()` takes precedence over shared data of the same type.
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use actori_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// async fn index(hits: web::Data<AtomicUsize>) -> HttpResponse {
+    ///     let hits = hits.fetch_add(1, Ordering::Relaxed) + 1;
+    ///     HttpResponse::Ok().body(format!("{} hits across all workers", hits))
+    /// }
+    ///
+    /// fn main() {
+    ///     let srv = HttpServer::new(|| App::new().route("/", web::get().to(index)))
+    ///         .shared_data(AtomicUsize::new(0));
+    /// }
+    /// ```
+    pub fn shared_data<T: Send + Sync + 'static>(self, data: T) -> Self {
+        self.config
+            .lock()
+            .unwrap()
+            .shared_data
+            .push(Arc::new(Data::new(data)));
+        self
+    }
+
+    /// Set application data shared by all workers, data is constructed
+    /// asynchronously.
+    ///
+    /// Factory is awaited before workers start, error of the factory is
+    /// returned to the caller. Check [`shared_data()`](#method.shared_data)
+    /// for more information.
+    ///
+    /// ```rust,no_run
+    /// use actori_web::{web, App, HttpResponse, HttpServer};
+    ///
+    /// struct Pool;
+    ///
+    /// async fn connect() -> std::io::Result<Pool> {
+    ///     Ok(Pool)
+    /// }
+    ///
+    /// async fn index(_pool: web::Data<Pool>) -> HttpResponse {
+    ///     HttpResponse::Ok().finish()
+    /// }
+    ///
+    /// #[actori_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     HttpServer::new(|| App::new().route("/", web::get().to(index)))
+    ///         .shared_data_factory(connect)
+    ///         .await?
+    ///         .bind("127.0.0.1:59090")?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub async fn shared_data_factory<D, Fut, T, E>(self, factory: D) -> Result<Self, E>
+    where
+        D: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        T: Send + Sync + 'static,
+    {
+        let data = factory().await?;
+        Ok(self.shared_data(data))
+    }
+
+    /// Provide service for `EXPECT: 100-Continue` support.
+    ///
+    /// Factory function is called for each worker. Service get called with
+    /// request that contains `EXPECT` header. Service must return request
+    /// in case of success, in that case request will be forwarded to
+    /// the application.
+    ///
+    /// ```rust,no_run
+    /// use actori_http::Request;
+    /// use actori_web::{error, web, App, HttpResponse, HttpServer};
+    /// use futures::future::{err, ok};
+    ///
+    /// #[actori_rt::main]
+    /// async fn main() -> std::io::Result<()> {
+    ///     HttpServer::new(|| App::new().service(web::resource("/").to(|| HttpResponse::Ok())))
+    ///         .on_expect(|| {
+    ///             actori_service::fn_service(|req: Request| {
+    ///                 if req.headers().contains_key("authorization") {
+    ///                     ok(req)
+    ///                 } else {
+    ///                     err(error::ErrorUnauthorized("unauthorized"))
+    ///                 }
+    ///             })
+    ///         })
+    ///         .bind("127.0.0.1:59090")?
+    ///         .run()
+    ///         .await
+    /// }
+    /// ```
+    pub fn on_expect<XF, X>(self, f: XF) -> Self
+    where
+        XF: Fn() -> X + Send + Sync + 'static,
+        X: ServiceFactory<Config = (), Request = Request, Response = Request> + 'static,
+        X::Error: Into<Error> + 'static,
+        X::InitError: fmt::Debug + 'static,
+        X::Service: 'static,
+        <X::Service as Service>::Future: 'static,
+    {
+        self.config.lock().unwrap().on_expect = Some(Arc::new(move || {
+            boxed::factory(f().map_err(|e| e.into()).map_init_err(|e| {
+                log::error!("Can not construct expect service: {:?}", e)
+            }))
+        }));
+        self
+    }
+
+    /// Provide service for custom `Connection: UPGRADE` support.
+    ///
+    /// Factory function is called for each worker. If service is provided
+    /// then normal requests handling get halted and this service get called
+    /// with original request and framed object.
+    ///
+    /// Upgrade service is used only for plain-text tcp listeners, encrypted
+    /// connections negotiate protocol during tls handshake.
+    pub fn on_upgrade<UF, U>(self, f: UF) -> Self
+    where
+        UF: Fn() -> U + Send + Sync + 'static,
+        U: ServiceFactory<
+                Config = (),
+                Request = (Request, Framed<TcpStream, Codec>),
+                Response = (),
+            > + 'static,
+        U::Error: Into<Error> + 'static,
+        U::InitError: fmt::Debug + 'static,
+        U::Service: 'static,
+        <U::Service as Service>::Future: 'static,
+    {
+        self.config.lock().unwrap().on_upgrade = Some(Arc::new(move || {
+            boxed::factory(f().map_err(|e| e.into()).map_init_err(|e| {
+                log::error!("Can not construct upgrade service: {:?}", e)
+            }))
+        }));
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -198,8 +534,12 @@ where
     }
 
     /// Disable signal handling
+    ///
+    /// By default server stops gracefully on `SIGTERM`, see
+    /// [Shutdown](./dev/struct.Shutdown.html), and immediately on `SIGINT`
+    /// and `SIGQUIT`.
     pub fn disable_signals(mut self) -> Self {
-        self.builder = self.builder.disable_signals();
+        self.signals = false;
         self
     }
 
@@ -211,6 +551,7 @@ where
     ///
     /// By default shutdown timeout sets to 30 seconds.
     pub fn shutdown_timeout(mut self, sec: u64) -> Self {
+        self.shutdown_timeout = sec;
         self.builder = self.builder.shutdown_timeout(sec);
         self
     }
@@ -230,12 +571,48 @@ where
         self.sockets.iter().map(|s| (s.addr, s.scheme)).collect()
     }
 
+    /// Set name of the listeners started by the following `bind*()` and
+    /// `listen*()` calls.
+    ///
+    /// Name identifies listeners in the `Listeners` handle, see `listeners()`.
+    /// By default listener is identified by its socket address.
+    pub fn listener_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.listener_name = Some(name.into());
+        self
+    }
+
+    /// Get handle to the listeners of the server.
+    ///
+    /// Handle allows to pause and resume serving of new connections on
+    /// specific listeners at runtime and provides per-listener counters of
+    /// accepted and active connections, see [Listeners](./dev/struct.Listeners.html).
+    pub fn listeners(&self) -> Listeners {
+        self.listeners.clone()
+    }
+
+    /// Get handle to the graceful shutdown of the server.
+    ///
+    /// Graceful shutdown runs shutdown hooks of the applications on the
+    /// workers, see [Shutdown](./dev/struct.Shutdown.html).
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Register state of the next listener
+    fn listener_state(&self, addr: &str) -> Arc<ListenerState> {
+        match self.listener_name {
+            Some(ref name) => self.listeners.register(name),
+            None => self.listeners.register(addr),
+        }
+    }
+
     /// Use listener for accepting incoming connection requests
     ///
     /// HttpServer does not change any configuration for TcpListener,
     /// it needs to be configured before passing it to listen() method.
     pub fn listen(mut self, lst: net::TcpListener) -> io::Result<Self> {
         let cfg = self.config.clone();
+        let shutdown = self.shutdown.clone();
         let factory = self.factory.clone();
         let addr = lst.local_addr().unwrap();
         self.sockets.push(Socket {
@@ -243,25 +620,28 @@ where
             scheme: "http",
         });
 
-        self.builder = self.builder.listen(
-            format!("actori-web-service-{}", addr),
-            lst,
-            move || {
-                let c = cfg.lock().unwrap();
-                let cfg = AppConfig::new(
-                    false,
-                    addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                );
+        let state = self.listener_state(&addr.to_string());
+        let name = format!("actori-web-service-{}", addr);
+        self.builder = self.builder.listen(name.clone(), lst, move || {
+            shutdown.register_worker();
+            let c = cfg.lock().unwrap();
+            let cfg = c.app_config(false, addr);
 
-                HttpService::build()
-                    .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout)
-                    .local_addr(addr)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
-                    .tcp()
-            },
-        )?;
+            let service = HttpService::build()
+                .keep_alive(c.keep_alive)
+                .client_timeout(c.client_timeout)
+                .options_allow(c.options_allow.clone())
+                .max_payload_size(c.max_payload_size)
+                .payload_drain_size(c.payload_drain_size)
+                .close_on_error(c.close_on_error)
+                .local_addr(addr)
+                .listener_name(name.clone())
+                .expect(c.expect())
+                .finish(map_config(factory(), move |_| cfg.clone()))
+                .upgrade(c.on_upgrade.as_ref().map(|f| f()))
+                .tcp();
+            ListenerGate::new(state.clone(), service)
+        })?;
         Ok(self)
     }
 
@@ -274,41 +654,61 @@ where
         lst: net::TcpListener,
         builder: SslAcceptorBuilder,
     ) -> io::Result<Self> {
-        self.listen_ssl_inner(lst, openssl_acceptor(builder)?)
+        self.listen_ssl_inner(lst, TlsReload::new(openssl_acceptor(builder)?))
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Use listener for accepting incoming tls connection requests,
+    /// acceptor could be replaced at runtime with `TlsReload::set()`.
+    ///
+    /// Acceptor has to be configured for alpn negotiation by the caller,
+    /// otherwise only "http/1.1" is used.
+    pub fn listen_openssl_reload(
+        self,
+        lst: net::TcpListener,
+        acceptor: TlsReload<SslAcceptor>,
+    ) -> io::Result<Self> {
+        self.listen_ssl_inner(lst, acceptor)
     }
 
     #[cfg(feature = "openssl")]
     fn listen_ssl_inner(
         mut self,
         lst: net::TcpListener,
-        acceptor: SslAcceptor,
+        acceptor: TlsReload<SslAcceptor>,
     ) -> io::Result<Self> {
         let factory = self.factory.clone();
         let cfg = self.config.clone();
+        let shutdown = self.shutdown.clone();
         let addr = lst.local_addr().unwrap();
         self.sockets.push(Socket {
             addr,
             scheme: "https",
         });
 
-        self.builder = self.builder.listen(
-            format!("actori-web-service-{}", addr),
-            lst,
-            move || {
-                let c = cfg.lock().unwrap();
-                let cfg = AppConfig::new(
-                    true,
-                    addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                );
-                HttpService::build()
-                    .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout)
-                    .client_disconnect(c.client_shutdown)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
-                    .openssl(acceptor.clone())
-            },
-        )?;
+        let state = self.listener_state(&addr.to_string());
+        let name = format!("actori-web-service-{}", addr);
+        self.builder = self.builder.listen(name.clone(), lst, move || {
+            shutdown.register_worker();
+            let c = cfg.lock().unwrap();
+            let cfg = c.app_config(true, addr);
+            let service = HttpService::build()
+                .keep_alive(c.keep_alive)
+                .client_timeout(c.client_timeout)
+                .options_allow(c.options_allow.clone())
+                .max_payload_size(c.max_payload_size)
+                .payload_drain_size(c.payload_drain_size)
+                .close_on_error(c.close_on_error)
+                .client_disconnect(c.client_shutdown)
+                .tls_handshake_timeout(c.tls_handshake_timeout)
+                .max_tls_handshakes(c.max_tls_handshakes)
+                .local_addr(addr)
+                .listener_name(name.clone())
+                .expect(c.expect())
+                .finish(map_config(factory(), move |_| cfg.clone()))
+                .openssl(acceptor.clone());
+            ListenerGate::new(state.clone(), service)
+        })?;
         Ok(self)
     }
 
@@ -320,6 +720,19 @@ where
         self,
         lst: net::TcpListener,
         config: RustlsServerConfig,
+    ) -> io::Result<Self> {
+        self.listen_rustls_inner(lst, TlsReload::new(config))
+    }
+
+    #[cfg(feature = "rustls")]
+    /// Use listener for accepting incoming tls connection requests,
+    /// configuration could be replaced at runtime with `TlsReload::set()`.
+    ///
+    /// This method sets alpn protocols to "h2" and "http/1.1"
+    pub fn listen_rustls_reload(
+        self,
+        lst: net::TcpListener,
+        config: TlsReload<RustlsServerConfig>,
     ) -> io::Result<Self> {
         self.listen_rustls_inner(lst, config)
     }
@@ -328,34 +741,40 @@ where
     fn listen_rustls_inner(
         mut self,
         lst: net::TcpListener,
-        config: RustlsServerConfig,
+        config: TlsReload<RustlsServerConfig>,
     ) -> io::Result<Self> {
         let factory = self.factory.clone();
         let cfg = self.config.clone();
+        let shutdown = self.shutdown.clone();
         let addr = lst.local_addr().unwrap();
         self.sockets.push(Socket {
             addr,
             scheme: "https",
         });
 
-        self.builder = self.builder.listen(
-            format!("actori-web-service-{}", addr),
-            lst,
-            move || {
-                let c = cfg.lock().unwrap();
-                let cfg = AppConfig::new(
-                    true,
-                    addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", addr)),
-                );
-                HttpService::build()
-                    .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout)
-                    .client_disconnect(c.client_shutdown)
-                    .finish(map_config(factory(), move |_| cfg.clone()))
-                    .rustls(config.clone())
-            },
-        )?;
+        let state = self.listener_state(&addr.to_string());
+        let name = format!("actori-web-service-{}", addr);
+        self.builder = self.builder.listen(name.clone(), lst, move || {
+            shutdown.register_worker();
+            let c = cfg.lock().unwrap();
+            let cfg = c.app_config(true, addr);
+            let service = HttpService::build()
+                .keep_alive(c.keep_alive)
+                .client_timeout(c.client_timeout)
+                .options_allow(c.options_allow.clone())
+                .max_payload_size(c.max_payload_size)
+                .payload_drain_size(c.payload_drain_size)
+                .close_on_error(c.close_on_error)
+                .client_disconnect(c.client_shutdown)
+                .tls_handshake_timeout(c.tls_handshake_timeout)
+                .max_tls_handshakes(c.max_tls_handshakes)
+                .local_addr(addr)
+                .listener_name(name.clone())
+                .expect(c.expect())
+                .finish(map_config(factory(), move |_| cfg.clone()))
+                .rustls(config.clone());
+            ListenerGate::new(state.clone(), service)
+        })?;
         Ok(self)
     }
 
@@ -416,7 +835,7 @@ where
         A: net::ToSocketAddrs,
     {
         let sockets = self.bind2(addr)?;
-        let acceptor = openssl_acceptor(builder)?;
+        let acceptor = TlsReload::new(openssl_acceptor(builder)?);
 
         for lst in sockets {
             self = self.listen_ssl_inner(lst, acceptor.clone())?;
@@ -425,6 +844,83 @@ where
         Ok(self)
     }
 
+    #[cfg(feature = "openssl")]
+    /// Start listening for incoming tls connections, acceptor could be
+    /// replaced at runtime with `TlsReload::set()`.
+    ///
+    /// Acceptor has to be configured for alpn negotiation by the caller,
+    /// otherwise only "http/1.1" is used.
+    pub fn bind_openssl_reload<A>(
+        mut self,
+        addr: A,
+        acceptor: TlsReload<SslAcceptor>,
+    ) -> io::Result<Self>
+    where
+        A: net::ToSocketAddrs,
+    {
+        let sockets = self.bind2(addr)?;
+        for lst in sockets {
+            self = self.listen_ssl_inner(lst, acceptor.clone())?;
+        }
+        Ok(self)
+    }
+
+    #[cfg(feature = "acme")]
+    /// Start listening for incoming tls connections with certificates
+    /// issued by Let's Encrypt.
+    ///
+    /// Challenges of the ACME server are served on `0.0.0.0:80`, other
+    /// requests to this address are redirected to https. Tls connections
+    /// are accepted on `0.0.0.0:443`. Certificate and account key are stored
+    /// in the cache directory, certificate is renewed without server restart.
+    /// Temporary self-signed certificate is used until certificate is issued.
+    ///
+    /// This method is available with `acme` feature.
+    pub fn bind_acme<P: AsRef<std::path::Path>>(
+        self,
+        domains: &[&str],
+        cache_dir: P,
+    ) -> io::Result<Self> {
+        self.bind_acme_config(crate::acme::AcmeConfig::new(domains, cache_dir))
+    }
+
+    #[cfg(feature = "acme")]
+    /// Start listening for incoming tls connections with certificates
+    /// issued by ACME server, see `bind_acme()`.
+    ///
+    /// This method is available with `acme` feature.
+    pub fn bind_acme_config(
+        mut self,
+        config: crate::acme::AcmeConfig,
+    ) -> io::Result<Self> {
+        use crate::acme;
+
+        let challenges = acme::Challenges::default();
+        let acceptor = TlsReload::new(acme::initial_acceptor(&config)?);
+
+        for lst in self.bind2(config.http_addr)? {
+            let addr = lst.local_addr().unwrap();
+            self.sockets.push(Socket {
+                addr,
+                scheme: "http",
+            });
+
+            let challenges = challenges.clone();
+            let name = format!("actori-web-acme-{}", addr);
+            self.builder = self.builder.listen(name, lst, move || {
+                HttpService::build()
+                    .finish(acme::challenge_service(challenges.clone()))
+                    .tcp()
+            })?;
+        }
+        for lst in self.bind2(config.https_addr)? {
+            self = self.listen_ssl_inner(lst, acceptor.clone())?;
+        }
+
+        acme::spawn(config, challenges, acceptor);
+        Ok(self)
+    }
+
     #[cfg(feature = "rustls")]
     /// Start listening for incoming tls connections.
     ///
@@ -433,6 +929,36 @@ where
         mut self,
         addr: A,
         config: RustlsServerConfig,
+    ) -> io::Result<Self> {
+        let config = TlsReload::new(config);
+        let sockets = self.bind2(addr)?;
+        for lst in sockets {
+            self = self.listen_rustls_inner(lst, config.clone())?;
+        }
+        Ok(self)
+    }
+
+    #[cfg(feature = "rustls")]
+    /// Start listening for incoming tls connections, configuration could be
+    /// replaced at runtime with `TlsReload::set()`.
+    ///
+    /// ```rust,ignore
+    /// use actori_web::dev::TlsReload;
+    ///
+    /// let config = TlsReload::new(load_config());
+    /// HttpServer::new(|| App::new())
+    ///     .bind_rustls_reload("0.0.0.0:443", config.clone())?
+    ///     .run();
+    ///
+    /// // later, once certificates are renewed
+    /// config.set(load_config());
+    /// ```
+    ///
+    /// This method sets alpn protocols to "h2" and "http/1.1"
+    pub fn bind_rustls_reload<A: net::ToSocketAddrs>(
+        mut self,
+        addr: A,
+        config: TlsReload<RustlsServerConfig>,
     ) -> io::Result<Self> {
         let sockets = self.bind2(addr)?;
         for lst in sockets {
@@ -452,6 +978,7 @@ where
         use actori_rt::net::UnixStream;
 
         let cfg = self.config.clone();
+        let shutdown = self.shutdown.clone();
         let factory = self.factory.clone();
         let socket_addr = net::SocketAddr::new(
             net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)),
@@ -462,21 +989,27 @@ where
             addr: socket_addr,
         });
 
-        let addr = format!("actori-web-service-{:?}", lst.local_addr()?);
+        let addr = lst.local_addr()?;
+        let state = self.listener_state(&format!("{:?}", addr));
+        let name = format!("actori-web-service-{:?}", addr);
 
-        self.builder = self.builder.listen_uds(addr, lst, move || {
+        self.builder = self.builder.listen_uds(name.clone(), lst, move || {
+            shutdown.register_worker();
             let c = cfg.lock().unwrap();
-            let config = AppConfig::new(
-                false,
-                socket_addr,
-                c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-            );
-            pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None))).and_then(
-                HttpService::build()
-                    .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout)
-                    .finish(map_config(factory(), move |_| config.clone())),
-            )
+            let config = c.app_config(false, socket_addr);
+            let http = HttpService::build()
+                .keep_alive(c.keep_alive)
+                .client_timeout(c.client_timeout)
+                .options_allow(c.options_allow.clone())
+                .max_payload_size(c.max_payload_size)
+                .payload_drain_size(c.payload_drain_size)
+                .close_on_error(c.close_on_error)
+                .listener_name(name.clone())
+                .expect(c.expect())
+                .finish(map_config(factory(), move |_| config.clone()));
+            let service =
+                pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None)));
+            ListenerGate::new(state.clone(), service.and_then(http))
         })?;
         Ok(self)
     }
@@ -492,6 +1025,7 @@ where
         use actori_rt::net::UnixStream;
 
         let cfg = self.config.clone();
+        let shutdown = self.shutdown.clone();
         let factory = self.factory.clone();
         let socket_addr = net::SocketAddr::new(
             net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)),
@@ -502,25 +1036,26 @@ where
             addr: socket_addr,
         });
 
-        self.builder = self.builder.bind_uds(
-            format!("actori-web-service-{:?}", addr.as_ref()),
-            addr,
-            move || {
-                let c = cfg.lock().unwrap();
-                let config = AppConfig::new(
-                    false,
-                    socket_addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-                );
-                pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None)))
-                    .and_then(
-                        HttpService::build()
-                            .keep_alive(c.keep_alive)
-                            .client_timeout(c.client_timeout)
-                            .finish(map_config(factory(), move |_| config.clone())),
-                    )
-            },
-        )?;
+        let state = self.listener_state(&format!("{:?}", addr.as_ref()));
+        let name = format!("actori-web-service-{:?}", addr.as_ref());
+        self.builder = self.builder.bind_uds(name.clone(), addr, move || {
+            shutdown.register_worker();
+            let c = cfg.lock().unwrap();
+            let config = c.app_config(false, socket_addr);
+            let http = HttpService::build()
+                .keep_alive(c.keep_alive)
+                .client_timeout(c.client_timeout)
+                .options_allow(c.options_allow.clone())
+                .max_payload_size(c.max_payload_size)
+                .payload_drain_size(c.payload_drain_size)
+                .close_on_error(c.close_on_error)
+                .listener_name(name.clone())
+                .expect(c.expect())
+                .finish(map_config(factory(), move |_| config.clone()));
+            let service =
+                pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None)));
+            ListenerGate::new(state.clone(), service.and_then(http))
+        })?;
         Ok(self)
     }
 }
@@ -558,7 +1093,15 @@ where
     /// }
     /// ```
     pub fn run(self) -> Server {
-        self.builder.start()
+        // signals are handled by the server to run shutdown hooks
+        let server = self.builder.disable_signals().start();
+        if self.signals {
+            self.shutdown.handle_signals(
+                server.clone(),
+                Duration::from_secs(self.shutdown_timeout),
+            );
+        }
+        server
     }
 }
 
@@ -577,7 +1120,9 @@ fn create_tcp_listener(
 
 #[cfg(feature = "openssl")]
 /// Configure `SslAcceptorBuilder` with custom server flags.
-fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor> {
+pub(crate) fn openssl_acceptor(
+    mut builder: SslAcceptorBuilder,
+) -> io::Result<SslAcceptor> {
     builder.set_alpn_select_callback(|_, protos| {
         const H2: &[u8] = b"\x02h2";
         const H11: &[u8] = b"\x08http/1.1";
@@ -1,19 +1,24 @@
-use std::cell::{Ref, RefMut};
+use std::cell::{Ref, RefCell, RefMut};
 use std::rc::Rc;
 use std::{fmt, net};
 
 use actori_http::body::{Body, MessageBody, ResponseBody};
-use actori_http::http::{HeaderMap, Method, StatusCode, Uri, Version};
+use actori_http::http::{
+    Error as HttpError, HeaderMap, Method, StatusCode, Uri, Version,
+};
 use actori_http::{
     Error, Extensions, HttpMessage, Payload, PayloadStream, RequestHead, Response,
     ResponseHead,
 };
 use actori_router::{IntoPattern, Path, Resource, ResourceDef, Url};
 use actori_service::{IntoServiceFactory, ServiceFactory};
+use futures::future::{ok, FutureExt, LocalBoxFuture};
 
+use crate::app_service::AppRouter;
 use crate::config::{AppConfig, AppService};
 use crate::data::Data;
 use crate::dev::insert_slash;
+use crate::error::{ErrorInternalServerError, UrlGenerationError};
 use crate::guard::Guard;
 use crate::info::ConnectionInfo;
 use crate::request::HttpRequest;
@@ -104,7 +109,7 @@ impl ServiceRequest {
     /// Create service response for error
     #[inline]
     pub fn error_response<B, E: Into<Error>>(self, err: E) -> ServiceResponse<B> {
-        let res: Response = err.into().into();
+        let res = crate::i18n::localize(&self.0, err.into().into());
         ServiceResponse::new(self.0, res.into_body())
     }
 
@@ -179,6 +184,12 @@ impl ServiceRequest {
         self.head().peer_addr
     }
 
+    /// Connection data item, same as `HttpRequest::conn_data()`.
+    #[inline]
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.head().conn_data()
+    }
+
     /// Get *ConnectionInfo* for the current request.
     #[inline]
     pub fn connection_info(&self) -> Ref<'_, ConnectionInfo> {
@@ -217,11 +228,7 @@ impl ServiceRequest {
     /// Get an application data stored with `App::data()` method during
     /// application configuration.
     pub fn app_data<T: 'static>(&self) -> Option<Data<T>> {
-        if let Some(st) = (self.0).0.app_data.get::<Data<T>>() {
-            Some(st.clone())
-        } else {
-            None
-        }
+        self.0.app_data::<Data<T>>().cloned()
     }
 
     /// Set request payload.
@@ -229,10 +236,147 @@ impl ServiceRequest {
         Rc::get_mut(&mut (self.0).0).unwrap().payload = payload;
     }
 
+    /// Rewrite path and query string of the request.
+    ///
+    /// Scheme and authority of the request uri are preserved. Match info is
+    /// reset, request is routed by the new path, so rewrite has to happen
+    /// before routing, i.e. in middleware registered with `App::wrap()`.
+    ///
+    /// ```rust
+    /// use actori_service::Service;
+    /// use actori_web::{web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .wrap_fn(|mut req, srv| {
+    ///             // legacy url mapping
+    ///             if req.path() == "/index.php" {
+    ///                 let pq = format!("/home?{}", req.query_string());
+    ///                 req.set_path_and_query(&pq).unwrap();
+    ///             }
+    ///             srv.call(req)
+    ///         })
+    ///         .route("/home", web::get().to(|| HttpResponse::Ok()));
+    /// }
+    /// ```
+    ///
+    /// Fails if `path_and_query` is not valid or request is shared, i.e.
+    /// a clone of `HttpRequest` is held elsewhere.
+    pub fn set_path_and_query(&mut self, path_and_query: &str) -> Result<(), Error> {
+        let mut parts = self.uri().clone().into_parts();
+        parts.path_and_query = Some(path_and_query.parse().map_err(HttpError::from)?);
+        let uri = Uri::from_parts(parts).map_err(HttpError::from)?;
+        self.set_uri(uri)
+    }
+
+    /// Replace request uri and reset routing state
+    fn set_uri(&mut self, uri: Uri) -> Result<(), Error> {
+        let inner = Rc::get_mut(&mut (self.0).0).ok_or_else(|| {
+            ErrorInternalServerError("Request is shared, uri can not be changed")
+        })?;
+        inner.path.get_mut().update(&uri);
+        inner.path.reset();
+        inner.head.uri = uri;
+        Ok(())
+    }
+
+    /// Forward request to the named resource.
+    ///
+    /// Request path is replaced with the path of the named resource generated from
+    /// `elements`, query string is preserved. Request is dispatched by the
+    /// application router again, application level middlewares are not called
+    /// for forwarded request. This could be used for internal redirects or
+    /// soft 404 pages without an extra http round-trip.
+    ///
+    /// ```rust
+    /// use actori_web::{dev, web, App, HttpResponse};
+    ///
+    /// fn main() {
+    ///     let app = App::new()
+    ///         .service(web::resource("/user/{id}").name("user").to(|| HttpResponse::Ok()))
+    ///         .service(
+    ///             web::resource("/not-found")
+    ///                 .name("not_found")
+    ///                 .to(|| HttpResponse::NotFound()))
+    ///         .default_service(|req: dev::ServiceRequest| {
+    ///             req.forward_to("not_found", &[] as &[&str])
+    ///         });
+    /// }
+    /// ```
+    ///
+    /// Forwarding loops are not detected.
+    pub fn forward_to<U, I>(
+        mut self,
+        name: &str,
+        elements: U,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse, Error>>
+    where
+        U: IntoIterator<Item = I>,
+        I: AsRef<str>,
+    {
+        let path = match self.resource_map().path_for(name, elements) {
+            Ok(path) => path,
+            Err(e) => return ok(self.error_response(e)).boxed_local(),
+        };
+        let router = match (self.0).0.router.clone() {
+            Some(router) => router,
+            None => {
+                return ok(self.error_response(UrlGenerationError::ResourceNotFound))
+                    .boxed_local()
+            }
+        };
+
+        let uri = if let Some(query) = self.uri().query() {
+            format!("{}?{}", path, query)
+        } else {
+            path
+        };
+        let uri: Uri = match uri.parse() {
+            Ok(uri) => uri,
+            Err(e) => return ok(self.error_response(HttpError::from(e))).boxed_local(),
+        };
+
+        // reset routing state
+        if let Err(e) = self.set_uri(uri) {
+            return ok(self.error_response(e)).boxed_local();
+        }
+        Rc::get_mut(&mut (self.0).0).unwrap().app_data.truncate(1);
+
+        async move {
+            // router is borrowed only during synchronous part of the call
+            let fut = match router.try_borrow_mut() {
+                Ok(mut router) => router.call(self),
+                Err(_) => {
+                    return Ok(self.error_response(ErrorInternalServerError(
+                        "Application router is busy",
+                    )))
+                }
+            };
+            fut.await
+        }
+        .boxed_local()
+    }
+
+    pub(crate) fn set_router(&mut self, router: Rc<RefCell<AppRouter>>) {
+        Rc::get_mut(&mut (self.0).0).unwrap().router = Some(router);
+    }
+
     #[doc(hidden)]
-    /// Set new app data container
+    /// Set new app data container, previously set containers get dropped
     pub fn set_data_container(&mut self, extensions: Rc<Extensions>) {
-        Rc::get_mut(&mut (self.0).0).unwrap().app_data = extensions;
+        let data = &mut Rc::get_mut(&mut (self.0).0).unwrap().app_data;
+        data.clear();
+        data.push(extensions);
+    }
+
+    #[doc(hidden)]
+    /// Add app data container, data from the new container takes
+    /// precedence over previously added containers
+    pub fn add_data_container(&mut self, extensions: Rc<Extensions>) {
+        Rc::get_mut(&mut (self.0).0)
+            .unwrap()
+            .app_data
+            .push(extensions);
     }
 }
 
@@ -306,7 +450,7 @@ impl<B> ServiceResponse<B> {
     /// Create service response from the error
     pub fn from_err<E: Into<Error>>(err: E, request: HttpRequest) -> Self {
         let e: Error = err.into();
-        let res: Response = e.into();
+        let res = crate::i18n::localize(&request, e.into());
         ServiceResponse {
             request,
             response: res.into_body(),
@@ -579,6 +723,122 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
     }
 
+    #[actori_rt::test]
+    async fn test_forward_to() {
+        let mut srv =
+            init_service(
+                App::new()
+                    .service(web::resource("/user/{id}").name("user").to(
+                        |req: crate::HttpRequest| {
+                            HttpResponse::Ok().body(format!(
+                                "{}?{}",
+                                &req.match_info()["id"],
+                                req.query_string()
+                            ))
+                        },
+                    ))
+                    .service(web::scope("/app").service(
+                        web::service("/me").finish(|req: ServiceRequest| {
+                            req.forward_to("user", &["1"])
+                        }),
+                    ))
+                    .default_service(|req: ServiceRequest| {
+                        req.forward_to("unknown", &[] as &[&str])
+                    }),
+            )
+            .await;
+
+        let req = TestRequest::with_uri("/app/me?q=1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body = crate::test::read_body(resp).await;
+        assert_eq!(body, bytes::Bytes::from_static(b"1?q=1"));
+
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[cfg(feature = "compress")]
+    #[actori_rt::test]
+    async fn test_forward_to_compress() {
+        // request is forwarded inside of app level middleware
+        let mut srv = init_service(
+            App::new()
+                .wrap(crate::middleware::Compress::default())
+                .service(web::resource("/user/{id}").name("user").to(
+                    |req: crate::HttpRequest| {
+                        HttpResponse::Ok().body(format!(
+                            "{}?{}",
+                            &req.match_info()["id"],
+                            req.query_string()
+                        ))
+                    },
+                ))
+                .default_service(|req: ServiceRequest| req.forward_to("user", &["1"])),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/user/2").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body = crate::test::read_body(resp).await;
+        assert_eq!(body, bytes::Bytes::from_static(b"2?"));
+
+        let req = TestRequest::with_uri("/me?q=1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body = crate::test::read_body(resp).await;
+        assert_eq!(body, bytes::Bytes::from_static(b"1?q=1"));
+    }
+
+    #[actori_rt::test]
+    async fn test_set_path_and_query() {
+        let mut srv = init_service(
+            App::new()
+                .wrap_fn(|mut req, srv| {
+                    if req.path().starts_with("/en/") {
+                        let pq = format!("{}?lang=en", &req.path()[3..]);
+                        req.set_path_and_query(&pq).unwrap();
+                    }
+                    assert!(req.set_path_and_query("invalid path").is_err());
+                    srv.call(req)
+                })
+                .service(web::resource("/user/{id}").to(|req: crate::HttpRequest| {
+                    HttpResponse::Ok().body(format!(
+                        "{}?{}",
+                        &req.match_info()["id"],
+                        req.query_string()
+                    ))
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/en/user/1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(resp.request().path(), "/user/1");
+        let body = crate::test::read_body(resp).await;
+        assert_eq!(body, bytes::Bytes::from_static(b"1?lang=en"));
+
+        let req = TestRequest::with_uri("/user/2?q=1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        let body = crate::test::read_body(resp).await;
+        assert_eq!(body, bytes::Bytes::from_static(b"2?q=1"));
+    }
+
+    #[test]
+    fn test_set_path_and_query_shared() {
+        let mut req = TestRequest::with_uri("/index.php").to_srv_request();
+        let shared = req.request().clone();
+        assert!(req.set_path_and_query("/home").is_err());
+        assert_eq!(req.path(), "/index.php");
+
+        drop(shared);
+        assert!(req.set_path_and_query("/home").is_ok());
+        assert_eq!(req.path(), "/home");
+    }
+
     #[test]
     fn test_fmt_debug() {
         let req = TestRequest::get()
@@ -590,7 +850,7 @@ mod tests {
         assert!(s.contains("test=1"));
         assert!(s.contains("x-test"));
 
-        let res = HttpResponse::Ok().header("x-test", "111").finish();
+        let res = HttpResponse::Ok().insert_header(("x-test", "111")).finish();
         let res = TestRequest::post()
             .uri("/index.html?test=1")
             .to_srv_response(res);
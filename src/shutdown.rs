@@ -0,0 +1,196 @@
+//! Graceful shutdown of the http server workers
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actori_http::Extensions;
+use actori_rt::time::{delay_for, timeout};
+use actori_rt::Arbiter;
+use actori_server::Server;
+use futures::channel::oneshot;
+use futures::future::join_all;
+
+use crate::app_service::ShutdownHook;
+use crate::listeners::Listeners;
+
+thread_local! {
+    /// Shutdown hooks of the application instances of the worker
+    static HOOKS: RefCell<Vec<(Rc<Vec<ShutdownHook>>, Weak<Extensions>)>> =
+        RefCell::new(Vec::new());
+    /// Handles the worker is registered with
+    static WORKER: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+}
+
+/// Register shutdown hooks of the application instance of current worker.
+pub(crate) fn register_hooks(hooks: &Rc<Vec<ShutdownHook>>, data: &Rc<Extensions>) {
+    if !hooks.is_empty() {
+        HOOKS.with(|h| h.borrow_mut().push((hooks.clone(), Rc::downgrade(data))));
+    }
+}
+
+/// Run shutdown hooks of the application instances of current worker.
+///
+/// Hooks run once, instances that are already dropped are skipped.
+pub(crate) async fn run_worker_hooks() {
+    let hooks: Vec<_> = HOOKS.with(|h| {
+        h.borrow_mut()
+            .drain(..)
+            .filter_map(|(hooks, data)| data.upgrade().map(|data| (hooks, data)))
+            .flat_map(|(hooks, data)| {
+                hooks.iter().map(|hook| hook(&data)).collect::<Vec<_>>()
+            })
+            .collect()
+    });
+    join_all(hooks).await;
+}
+
+/// Handle to the graceful shutdown of the http server.
+///
+/// Handle is created with `HttpServer::shutdown_handle()`. Graceful shutdown
+/// pauses accepting of new connections, waits until established connections
+/// are closed, then runs shutdown hooks registered with `App::on_shutdown()`
+/// and `App::data_cleanup()` on each worker and stops the server.
+///
+/// Server runs graceful shutdown on `SIGTERM` unless signal handling is
+/// disabled with `HttpServer::disable_signals()`, the handle could be used
+/// to trigger it from the application.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use actori_web::{web, App, HttpResponse, HttpServer};
+///
+/// #[actori_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     let server = HttpServer::new(|| {
+///         App::new()
+///             .on_shutdown(|| async { log::info!("worker stopped") })
+///             .route("/", web::to(|| HttpResponse::Ok()))
+///     })
+///     .disable_signals()
+///     .bind("127.0.0.1:8080")?;
+///     let shutdown = server.shutdown_handle();
+///     let server = server.run();
+///
+///     let srv = server.clone();
+///     actori_rt::spawn(async move {
+///         let _ = actori_rt::signal::ctrl_c().await;
+///         shutdown.graceful(srv, Duration::from_secs(30)).await;
+///     });
+///     server.await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Shutdown {
+    workers: Arc<Mutex<Vec<Arbiter>>>,
+    listeners: Listeners,
+}
+
+impl Shutdown {
+    pub(crate) fn new(listeners: Listeners) -> Self {
+        Shutdown {
+            workers: Arc::default(),
+            listeners,
+        }
+    }
+
+    /// Register current worker, called from the service factory of each
+    /// listener on the worker thread.
+    pub(crate) fn register_worker(&self) {
+        let id = &*self.workers as *const _ as usize;
+        if WORKER.with(|w| w.borrow_mut().insert(id)) {
+            self.workers.lock().unwrap().push(Arbiter::current());
+        }
+    }
+
+    /// Gracefully stop the server.
+    ///
+    /// Established connections are served until they are closed or the
+    /// timeout expires, shutdown hooks of the workers have the same time
+    /// to complete.
+    pub async fn graceful(self, server: Server, timeout: Duration) {
+        server.pause().await;
+
+        let deadline = Instant::now() + timeout;
+        while self.listeners.active() > 0 && Instant::now() < deadline {
+            delay_for(Duration::from_millis(50)).await;
+        }
+
+        self.run_hooks(timeout).await;
+        server.stop(true).await;
+    }
+
+    /// Run shutdown hooks on the worker arbiters
+    async fn run_hooks(&self, max: Duration) {
+        let workers = self.workers.lock().unwrap().clone();
+        let done: Vec<_> = workers
+            .iter()
+            .map(|arbiter| {
+                let (tx, rx) = oneshot::channel();
+                arbiter.exec_fn(move || {
+                    actori_rt::spawn(async move {
+                        run_worker_hooks().await;
+                        let _ = tx.send(());
+                    })
+                });
+                rx
+            })
+            .collect();
+
+        if timeout(max, join_all(done)).await.is_err() {
+            log::warn!("Shutdown hooks did not complete within {:?}", max);
+        }
+    }
+
+    /// Run graceful shutdown on `SIGTERM`, stop the server immediately
+    /// on `SIGINT` and `SIGQUIT`.
+    #[cfg(unix)]
+    pub(crate) fn handle_signals(self, server: Server, timeout: Duration) {
+        use actori_rt::signal::unix::{signal, SignalKind};
+        use futures::future::{select_all, FutureExt};
+
+        actori_rt::spawn(async move {
+            let mut signals = Vec::new();
+            for (kind, graceful) in vec![
+                (SignalKind::terminate(), true),
+                (SignalKind::interrupt(), false),
+                (SignalKind::quit(), false),
+            ] {
+                match signal(kind) {
+                    Ok(mut sig) => signals.push(
+                        async move {
+                            sig.recv().await;
+                            graceful
+                        }
+                        .boxed_local(),
+                    ),
+                    Err(e) => log::error!("Can not initialize signal handler: {}", e),
+                }
+            }
+            if signals.is_empty() {
+                return;
+            }
+
+            let (graceful, _, _) = select_all(signals).await;
+            if graceful {
+                log::info!("SIGTERM received, stopping");
+                self.graceful(server, timeout).await;
+            } else {
+                log::info!("Signal received, exiting");
+                server.stop(false).await;
+            }
+        });
+    }
+
+    /// Stop the server immediately on `Ctrl-C`.
+    #[cfg(not(unix))]
+    pub(crate) fn handle_signals(self, server: Server, _: Duration) {
+        actori_rt::spawn(async move {
+            if actori_rt::signal::ctrl_c().await.is_ok() {
+                log::info!("Ctrl-C received, exiting");
+                server.stop(false).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,250 @@
+//! Sitemap endpoint
+use std::fmt::Write;
+
+use actori_http::http::header::CONTENT_TYPE;
+
+use crate::request::HttpRequest;
+use crate::resource::Resource;
+use crate::{web, HttpResponse};
+
+/// How frequently the page is likely to change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeFreq {
+    Always,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
+
+impl ChangeFreq {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeFreq::Always => "always",
+            ChangeFreq::Hourly => "hourly",
+            ChangeFreq::Daily => "daily",
+            ChangeFreq::Weekly => "weekly",
+            ChangeFreq::Monthly => "monthly",
+            ChangeFreq::Yearly => "yearly",
+            ChangeFreq::Never => "never",
+        }
+    }
+}
+
+/// Sitemap metadata of a resource, see `Resource::sitemap()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SitemapEntry {
+    changefreq: Option<ChangeFreq>,
+    priority: Option<f32>,
+    lastmod: Option<String>,
+    exclude: bool,
+}
+
+impl SitemapEntry {
+    /// Create entry without metadata.
+    pub fn new() -> Self {
+        SitemapEntry::default()
+    }
+
+    /// Set change frequency of the page.
+    pub fn changefreq(mut self, freq: ChangeFreq) -> Self {
+        self.changefreq = Some(freq);
+        self
+    }
+
+    /// Set priority of the page, value is clamped to `0.0..=1.0` range.
+    pub fn priority(mut self, priority: f32) -> Self {
+        self.priority = Some(priority.max(0.0).min(1.0));
+        self
+    }
+
+    /// Set date of last modification in W3C Datetime format,
+    /// i.e. `2020-01-30`.
+    pub fn lastmod(mut self, lastmod: &str) -> Self {
+        self.lastmod = Some(lastmod.to_string());
+        self
+    }
+
+    /// Exclude the resource from the sitemap.
+    pub fn exclude(mut self) -> Self {
+        self.exclude = true;
+        self
+    }
+
+    /// Get change frequency.
+    pub fn get_changefreq(&self) -> Option<ChangeFreq> {
+        self.changefreq
+    }
+
+    /// Get priority.
+    pub fn get_priority(&self) -> Option<f32> {
+        self.priority
+    }
+
+    /// Get date of last modification.
+    pub fn get_lastmod(&self) -> Option<&str> {
+        self.lastmod.as_ref().map(|s| s.as_str())
+    }
+
+    /// Check if the resource is excluded from the sitemap.
+    pub fn is_excluded(&self) -> bool {
+        self.exclude
+    }
+}
+
+/// Create resource that renders `/sitemap.xml`.
+///
+/// Sitemap lists resources returned by `ResourceMap::static_resources()`,
+/// i.e. resources with static paths that respond to `GET` requests.
+/// Metadata is attached with `Resource::sitemap()`, resources are excluded
+/// with `SitemapEntry::exclude()`. Urls are generated with
+/// `HttpRequest::absolute_url_for()`, so public url of the application
+/// should be configured with `AppConfig::set_public_url()`.
+///
+/// ```rust
+/// use actori_web::web::{self, ChangeFreq, SitemapEntry};
+/// use actori_web::{App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .service(
+///             web::resource("/")
+///                 .sitemap(SitemapEntry::new().changefreq(ChangeFreq::Daily))
+///                 .route(web::get().to(|| HttpResponse::Ok())),
+///         )
+///         .service(
+///             web::resource("/admin")
+///                 .sitemap(SitemapEntry::new().exclude())
+///                 .route(web::get().to(|| HttpResponse::Ok())),
+///         )
+///         .service(web::sitemap());
+/// }
+/// ```
+pub fn sitemap() -> Resource {
+    web::resource("/sitemap.xml")
+        .sitemap(SitemapEntry::new().exclude())
+        .route(web::get().to(render))
+}
+
+async fn render(req: HttpRequest) -> HttpResponse {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    for res in req.resource_map().static_resources() {
+        let entry = res.sitemap.unwrap_or_default();
+        if entry.exclude {
+            continue;
+        }
+        let url = match req.absolute_url_for(&res.pattern) {
+            Ok(url) => url,
+            Err(e) => {
+                log::warn!("Can not generate sitemap url for {:?}: {}", res.pattern, e);
+                continue;
+            }
+        };
+
+        xml.push_str("  <url>\n");
+        let _ = writeln!(xml, "    <loc>{}</loc>", escape(url.as_str()));
+        if let Some(ref lastmod) = entry.lastmod {
+            let _ = writeln!(xml, "    <lastmod>{}</lastmod>", escape(lastmod));
+        }
+        if let Some(freq) = entry.changefreq {
+            let _ = writeln!(xml, "    <changefreq>{}</changefreq>", freq.as_str());
+        }
+        if let Some(priority) = entry.priority {
+            let _ = writeln!(xml, "    <priority>{:.1}</priority>", priority);
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, "application/xml"))
+        .body(xml)
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::App;
+
+    #[actori_rt::test]
+    async fn test_sitemap() {
+        let mut srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/")
+                        .sitemap(
+                            SitemapEntry::new()
+                                .changefreq(ChangeFreq::Daily)
+                                .priority(2.0)
+                                .lastmod("2020-01-30"),
+                        )
+                        .route(web::get().to(|| HttpResponse::Ok())),
+                )
+                .service(
+                    web::resource("/users/{id}")
+                        .route(web::get().to(|| HttpResponse::Ok())),
+                )
+                .service(
+                    web::resource("/login").route(web::post().to(|| HttpResponse::Ok())),
+                )
+                .service(
+                    web::resource("/admin")
+                        .sitemap(SitemapEntry::new().exclude())
+                        .to(|| HttpResponse::Ok()),
+                )
+                .service(
+                    web::scope("/docs")
+                        .route("/a&b", web::get().to(|| HttpResponse::Ok())),
+                )
+                .service(sitemap()),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/sitemap.xml").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "application/xml");
+        assert_eq!(
+            read_body(resp).await,
+            Bytes::from_static(
+                b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                  <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n  \
+                  <url>\n    \
+                  <loc>http://localhost:8080/</loc>\n    \
+                  <lastmod>2020-01-30</lastmod>\n    \
+                  <changefreq>daily</changefreq>\n    \
+                  <priority>1.0</priority>\n  \
+                  </url>\n  \
+                  <url>\n    \
+                  <loc>http://localhost:8080/docs/a&amp;b</loc>\n  \
+                  </url>\n\
+                  </urlset>\n"
+            )
+        );
+    }
+}
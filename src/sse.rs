@@ -0,0 +1,270 @@
+//! Server-Sent Events
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use actori_web::{sse, web, App, Responder};
+//! use futures::stream;
+//!
+//! async fn events() -> impl Responder {
+//!     let events = stream::iter(vec![
+//!         sse::Event::data("first").id("1"),
+//!         sse::Event::data("second").id("2").event("update"),
+//!     ]);
+//!     sse::Sse::new(events).keep_alive(Duration::from_secs(15))
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().route("/events", web::get().to(events));
+//! }
+//! ```
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use actori_rt::time::{delay_for, Delay, Instant};
+use bytes::{Bytes, BytesMut};
+use futures::future::{ok, Ready};
+use futures::Stream;
+
+use crate::dev::BodyEncoding;
+use crate::error::Error;
+use crate::http::header::{ContentEncoding, CACHE_CONTROL, CONTENT_TYPE};
+use crate::request::HttpRequest;
+use crate::responder::Responder;
+use crate::HttpResponse;
+
+/// Server-sent event.
+///
+/// Multi-line data is sent as multiple `data` fields.
+#[derive(Debug, Clone, Default)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+    comment: Option<String>,
+}
+
+impl Event {
+    /// Create event with data.
+    pub fn data<T: Into<String>>(data: T) -> Self {
+        Event {
+            data: Some(data.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Create comment event, comments are ignored by clients.
+    pub fn comment<T: Into<String>>(text: T) -> Self {
+        Event {
+            comment: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Set event id, client sends last received id on reconnect
+    /// with `Last-Event-ID` header.
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set event type.
+    pub fn event<T: Into<String>>(mut self, event: T) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set client reconnection time.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    fn write(&self, buf: &mut BytesMut) {
+        if let Some(ref comment) = self.comment {
+            for line in comment.lines() {
+                let _ = writeln!(buf, ":{}", line);
+            }
+        }
+        if let Some(ref event) = self.event {
+            let _ = writeln!(buf, "event: {}", first_line(event));
+        }
+        if let Some(ref data) = self.data {
+            if data.is_empty() {
+                buf.extend_from_slice(b"data\n");
+            }
+            for line in data.lines() {
+                let _ = writeln!(buf, "data: {}", line);
+            }
+        }
+        if let Some(ref id) = self.id {
+            let _ = writeln!(buf, "id: {}", first_line(id));
+        }
+        if let Some(retry) = self.retry {
+            let _ = writeln!(buf, "retry: {}", retry.as_millis());
+        }
+        buf.extend_from_slice(b"\n");
+    }
+}
+
+/// New lines are not allowed in single line fields
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or("")
+}
+
+/// Server-sent events responder.
+///
+/// Response is sent with `text/event-stream` content type, it is not
+/// compressed. Each event is sent as a separate chunk. Response ends when
+/// the stream of events ends, if client disconnects the stream is dropped.
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<Duration>,
+}
+
+impl<S> Sse<S>
+where
+    S: Stream<Item = Event> + 'static,
+{
+    /// Create responder for the stream of events.
+    pub fn new(stream: S) -> Self {
+        Sse {
+            stream,
+            keep_alive: None,
+        }
+    }
+
+    /// Send comment if no events were sent during the interval.
+    ///
+    /// Keep-alive comments prevent proxies from closing idle connections.
+    /// By default keep-alive is disabled.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+}
+
+impl<S> Responder for Sse<S>
+where
+    S: Stream<Item = Event> + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(HttpResponse::Ok()
+            .insert_header((CONTENT_TYPE, "text/event-stream"))
+            .insert_header((CACHE_CONTROL, "no-cache"))
+            .encoding(ContentEncoding::Identity)
+            .streaming(SseStream {
+                stream: self.stream,
+                delay: self.keep_alive.map(delay_for),
+                keep_alive: self.keep_alive,
+            }))
+    }
+}
+
+#[pin_project::pin_project]
+struct SseStream<S> {
+    #[pin]
+    stream: S,
+    delay: Option<Delay>,
+    keep_alive: Option<Duration>,
+}
+
+impl<S> Stream for SseStream<S>
+where
+    S: Stream<Item = Event>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        let mut buf = BytesMut::new();
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(event)) => event.write(&mut buf),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {
+                if let Some(delay) = this.delay.as_mut() {
+                    match Pin::new(delay).poll(cx) {
+                        Poll::Ready(_) => buf.extend_from_slice(b":\n\n"),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                } else {
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        if let (Some(delay), Some(keep_alive)) = (this.delay, this.keep_alive) {
+            delay.reset(Instant::now() + *keep_alive);
+        }
+        Poll::Ready(Some(Ok(buf.freeze())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream, StreamExt};
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{read_body, TestRequest};
+
+    #[test]
+    fn test_event() {
+        let mut buf = BytesMut::new();
+        Event::data("line1\nline2")
+            .id("1")
+            .event("update")
+            .retry(Duration::from_secs(1))
+            .write(&mut buf);
+        assert_eq!(
+            buf,
+            &b"event: update\ndata: line1\ndata: line2\nid: 1\nretry: 1000\n\n"[..]
+        );
+
+        let mut buf = BytesMut::new();
+        Event::comment("ping").write(&mut buf);
+        assert_eq!(buf, &b":ping\n\n"[..]);
+    }
+
+    #[actori_rt::test]
+    async fn test_sse() {
+        let req = TestRequest::default().to_http_request();
+        let events = stream::iter(vec![Event::data("a"), Event::data("b").id("2")]);
+        let resp = Sse::new(events).respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+
+        let body = read_body(crate::dev::ServiceResponse::new(req, resp)).await;
+        assert_eq!(body, Bytes::from_static(b"data: a\n\ndata: b\nid: 2\n\n"));
+    }
+
+    #[actori_rt::test]
+    async fn test_sse_keep_alive() {
+        let req = TestRequest::default().to_http_request();
+        let events = stream::pending::<Event>();
+        let mut resp = Sse::new(events)
+            .keep_alive(Duration::from_millis(10))
+            .respond_to(&req)
+            .await
+            .unwrap();
+
+        let mut body = resp.take_body();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(chunk, Bytes::from_static(b":\n\n"));
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(chunk, Bytes::from_static(b":\n\n"));
+    }
+}
@@ -6,6 +6,8 @@ use std::sync::mpsc;
 use std::{fmt, net, thread, time};
 
 use actori_codec::{AsyncRead, AsyncWrite, Framed};
+use actori_http::body::to_bytes_limited;
+use actori_http::error::ParseError;
 use actori_http::http::header::{ContentType, Header, HeaderName, IntoHeaderValue};
 use actori_http::http::{Error as HttpError, Method, StatusCode, Uri, Version};
 use actori_http::test::TestRequest as HttpTestRequest;
@@ -27,9 +29,13 @@ use serde_json;
 
 pub use actori_http::test::TestBuffer;
 
+mod snapshot;
+pub use self::snapshot::{snapshot, Snapshot};
+
 use crate::config::AppConfig;
 use crate::data::Data;
 use crate::dev::{Body, MessageBody, Payload, Server};
+use crate::middleware::TrustedProxies;
 use crate::request::HttpRequestPool;
 use crate::rmap::ResourceMap;
 use crate::service::{ServiceRequest, ServiceResponse};
@@ -158,12 +164,9 @@ where
         .await
         .unwrap_or_else(|_| panic!("read_response failed at application call"));
 
-    let mut body = resp.take_body();
-    let mut bytes = BytesMut::new();
-    while let Some(item) = body.next().await {
-        bytes.extend_from_slice(&item.unwrap());
-    }
-    bytes.freeze()
+    to_bytes_limited(resp.take_body(), usize::max_value())
+        .await
+        .unwrap()
 }
 
 /// Helper function that returns a response body of a ServiceResponse.
@@ -196,12 +199,9 @@ pub async fn read_body<B>(mut res: ServiceResponse<B>) -> Bytes
 where
     B: MessageBody,
 {
-    let mut body = res.take_body();
-    let mut bytes = BytesMut::new();
-    while let Some(item) = body.next().await {
-        bytes.extend_from_slice(&item.unwrap());
-    }
-    bytes.freeze()
+    to_bytes_limited(res.take_body(), usize::max_value())
+        .await
+        .unwrap()
 }
 
 pub async fn load_stream<S>(mut stream: S) -> Result<Bytes, Error>
@@ -339,6 +339,32 @@ impl TestRequest {
         TestRequest::default().header(key, value)
     }
 
+    /// Create TestRequest from raw HTTP/1 request bytes.
+    ///
+    /// Request is parsed by the same decoder http server uses, so edge cases
+    /// of header formatting and parser behavior could be tested.
+    ///
+    /// ```rust
+    /// use actori_web::{http::Method, test};
+    ///
+    /// let req = test::TestRequest::from_raw(
+    ///     "POST /index.html HTTP/1.1\r\ncontent-length: 4\r\n\r\ntest",
+    /// )
+    /// .unwrap()
+    /// .to_http_request();
+    /// assert_eq!(req.method(), Method::POST);
+    /// assert_eq!(req.path(), "/index.html");
+    ///
+    /// let res = test::TestRequest::from_raw("GET / HTTP/1.1\r\nbad header\r\n\r\n");
+    /// assert!(res.is_err());
+    /// ```
+    pub fn from_raw<T: AsRef<[u8]>>(data: T) -> Result<TestRequest, ParseError> {
+        Ok(TestRequest {
+            req: HttpTestRequest::from_raw(data)?,
+            ..TestRequest::default()
+        })
+    }
+
     /// Create TestRequest and set method to `Method::GET`
     pub fn get() -> TestRequest {
         TestRequest::default().method(Method::GET)
@@ -417,6 +443,24 @@ impl TestRequest {
         self
     }
 
+    /// Set connection data item, for example `dev::ListenerName`
+    pub fn conn_data<T: 'static>(mut self, data: T) -> Self {
+        self.req.conn_data(data);
+        self
+    }
+
+    /// Set public base url of the application
+    pub fn public_url(mut self, url: url::Url) -> Self {
+        self.config.set_public_url(url);
+        self
+    }
+
+    /// Set trusted proxies of the application
+    pub fn trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.config.set_trusted_proxies(proxies);
+        self
+    }
+
     /// Set request payload
     pub fn set_payload<B: Into<Bytes>>(mut self, data: B) -> Self {
         self.req.set_payload(data);
@@ -988,6 +1032,51 @@ mod tests {
         assert_eq!(*data, 20);
     }
 
+    #[actori_rt::test]
+    async fn test_from_raw() {
+        const RAW: &str = "POST /index.html?q=1 HTTP/1.1\r\n\
+                           x-test: 1\r\n\
+                           x-test: 2\r\n\
+                           transfer-encoding: chunked\r\n\r\n\
+                           4\r\ntest\r\n5\r\n body\r\n0\r\n\r\n\
+                           GET / HTTP/1.1\r\n\r\n";
+
+        let req = TestRequest::from_raw(RAW).unwrap().to_http_request();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.path(), "/index.html");
+        assert_eq!(req.query_string(), "q=1");
+        assert_eq!(req.headers().get_all("x-test").count(), 2);
+
+        let mut app = init_service(App::new().route(
+            "/index.html",
+            web::post().to(|body: Bytes| ok::<_, Error>(body)),
+        ))
+        .await;
+        let req = TestRequest::from_raw(RAW).unwrap().to_request();
+        let res = call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"test body"));
+
+        let res = TestRequest::from_raw("GET / HTTP/1.1\r\nx-test: 1\r\n");
+        assert!(match res {
+            Err(ParseError::Incomplete) => true,
+            _ => false,
+        });
+
+        let res =
+            TestRequest::from_raw("POST / HTTP/1.1\r\ncontent-length: 10\r\n\r\ntest");
+        assert!(match res {
+            Err(ParseError::Incomplete) => true,
+            _ => false,
+        });
+
+        let res = TestRequest::from_raw("GET / HTTP/1.1\r\nx test: 1\r\n\r\n");
+        assert!(match res {
+            Err(ParseError::Header) => true,
+            _ => false,
+        });
+    }
+
     #[actori_rt::test]
     async fn test_request_methods() {
         let mut app = init_service(
@@ -0,0 +1,330 @@
+//! Golden response snapshots
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use actori_http::http::header::{HeaderName, CONTENT_TYPE};
+use actori_http::http::Error as HttpError;
+
+use crate::dev::MessageBody;
+use crate::service::ServiceResponse;
+
+/// Environment variable that forces snapshots update
+const UPDATE_SNAPSHOTS: &str = "ACTORI_UPDATE_SNAPSHOTS";
+
+/// Compare response with the stored snapshot, with default settings.
+///
+/// See `Snapshot` for details.
+///
+/// ```rust,no_run
+/// use actori_web::{test, web, App, HttpResponse};
+///
+/// #[actori_rt::test]
+/// async fn test_index() {
+///     let mut app = test::init_service(
+///         App::new().route("/", web::get().to(|| HttpResponse::Ok().body("hi"))),
+///     )
+///     .await;
+///
+///     let req = test::TestRequest::get().uri("/").to_request();
+///     let resp = test::call_service(&mut app, req).await;
+///     test::snapshot(resp, "tests/snapshots/index.snap").await;
+/// }
+/// ```
+pub async fn snapshot<B, P>(resp: ServiceResponse<B>, path: P)
+where
+    B: MessageBody,
+    P: AsRef<Path>,
+{
+    Snapshot::new().assert(resp, path).await
+}
+
+/// Response snapshot settings.
+///
+/// Response is rendered into canonical text form: status line, selected
+/// headers sorted by name and the body. Json bodies are pretty printed with
+/// sorted keys, non utf-8 bodies are rendered as hex dump. Only
+/// `Content-Type` header is included by default.
+///
+/// Rendered response is compared with the stored snapshot file, test panics
+/// if they differ. Missing snapshot is created, all snapshots are
+/// re-created if `ACTORI_UPDATE_SNAPSHOTS` environment variable is set.
+///
+/// Values that change between runs, i.e. ids or timestamps, could be
+/// replaced with redaction hooks.
+///
+/// ```rust,no_run
+/// use actori_web::{test, web, App, HttpResponse};
+///
+/// #[actori_rt::test]
+/// async fn test_user() {
+///     let mut app = test::init_service(App::new().route(
+///         "/user",
+///         web::post().to(|| {
+///             HttpResponse::Created()
+///                 .insert_header(("x-request-id", "4b3c"))
+///                 .insert_header(("location", "/user/1"))
+///                 .json(serde_json::json!({"id": 1, "token": "secret"}))
+///         }),
+///     ))
+///     .await;
+///
+///     let req = test::TestRequest::post().uri("/user").to_request();
+///     let resp = test::call_service(&mut app, req).await;
+///     test::Snapshot::new()
+///         .header("location")
+///         .redact_header("x-request-id")
+///         .redact(|text| text.replace("secret", "[token]"))
+///         .assert(resp, "tests/snapshots/user.snap")
+///         .await;
+/// }
+/// ```
+pub struct Snapshot {
+    headers: Vec<(HeaderName, bool)>,
+    redactions: Vec<Box<dyn Fn(&str) -> String>>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Snapshot {
+            headers: vec![(CONTENT_TYPE, false)],
+            redactions: Vec::new(),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Create snapshot settings with default settings.
+    pub fn new() -> Self {
+        Snapshot::default()
+    }
+
+    /// Include response header in the snapshot.
+    pub fn header<K>(self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        self.add_header(name, false)
+    }
+
+    /// Include response header in the snapshot, header value is replaced
+    /// with `[redacted]`.
+    pub fn redact_header<K>(self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        self.add_header(name, true)
+    }
+
+    /// Add redaction hook, hook is applied to the rendered response.
+    ///
+    /// Hooks are applied in the order of registration.
+    pub fn redact<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> String + 'static,
+    {
+        self.redactions.push(Box::new(f));
+        self
+    }
+
+    fn add_header<K>(mut self, name: K, redacted: bool) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        let name = match HeaderName::try_from(name) {
+            Ok(name) => name,
+            Err(e) => panic!("Invalid header name: {}", e.into()),
+        };
+        self.headers.retain(|(n, _)| *n != name);
+        self.headers.push((name, redacted));
+        self
+    }
+
+    /// Render response into canonical text form.
+    pub async fn render<B: MessageBody>(&self, resp: ServiceResponse<B>) -> String {
+        let mut out = format!("{}\n", resp.status());
+
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        for (name, redacted) in headers {
+            for value in resp.headers().get_all(name) {
+                if *redacted {
+                    let _ = writeln!(out, "{}: [redacted]", name);
+                } else if let Ok(value) = value.to_str() {
+                    let _ = writeln!(out, "{}: {}", name, value);
+                } else {
+                    let _ = writeln!(out, "{}: {:?}", name, value);
+                }
+            }
+        }
+        out.push('\n');
+
+        let json = resp
+            .headers()
+            .get(&CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .map(|ct| ct.starts_with("application/json") || ct.contains("+json"))
+            .unwrap_or(false);
+        let body = super::read_body(resp).await;
+
+        if json {
+            if let Ok(value) = serde_json::from_slice::<serde_json::Value>(&body) {
+                out.push_str(&serde_json::to_string_pretty(&value).unwrap());
+                out.push('\n');
+                return self.apply_redactions(out);
+            }
+        }
+        match std::str::from_utf8(&body) {
+            Ok(text) => {
+                out.push_str(text);
+                if !text.is_empty() && !text.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            Err(_) => {
+                let _ = writeln!(out, "[binary {} bytes]", body.len());
+                for chunk in body.chunks(32) {
+                    for b in chunk {
+                        let _ = write!(out, "{:02x}", b);
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+        self.apply_redactions(out)
+    }
+
+    /// Compare response with the stored snapshot.
+    ///
+    /// Panics if response does not match the snapshot. Missing snapshot is
+    /// created.
+    pub async fn assert<B, P>(&self, resp: ServiceResponse<B>, path: P)
+    where
+        B: MessageBody,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let actual = self.render(resp).await;
+
+        if std::env::var_os(UPDATE_SNAPSHOTS).is_some() || !path.exists() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).unwrap();
+            }
+            fs::write(path, &actual).unwrap();
+            return;
+        }
+
+        let expected = fs::read_to_string(path).unwrap().replace("\r\n", "\n");
+        if expected != actual {
+            panic!(
+                "Response does not match snapshot {}\n\
+                 --- expected\n{}\n+++ actual\n{}\n\
+                 Set {} environment variable to update snapshots",
+                path.display(),
+                expected,
+                actual,
+                UPDATE_SNAPSHOTS,
+            );
+        }
+    }
+
+    fn apply_redactions(&self, mut out: String) -> String {
+        for f in &self.redactions {
+            out = f(&out);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::test::{call_service, init_service, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[actori_rt::test]
+    async fn test_render() {
+        let mut srv = init_service(
+            App::new()
+                .route(
+                    "/json",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .insert_header(("x-id", "123"))
+                            .insert_header(("x-b", "1"))
+                            .insert_header(("x-a", "2"))
+                            .body(r#"{"b":1,"a":{"d":true,"c":null}}"#)
+                    }),
+                )
+                .route(
+                    "/bin",
+                    web::get().to(|| HttpResponse::NotFound().body(vec![0u8, 255, 16])),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let resp = call_service(&mut srv, req).await;
+        let text = Snapshot::new()
+            .header("x-b")
+            .header("x-a")
+            .redact_header("x-id")
+            .render(resp)
+            .await;
+        assert_eq!(
+            text,
+            "200 OK\nx-a: 2\nx-b: 1\nx-id: [redacted]\n\n\
+             {\"b\":1,\"a\":{\"d\":true,\"c\":null}}\n"
+        );
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let mut resp = call_service(&mut srv, req).await;
+        resp.headers_mut().insert(
+            CONTENT_TYPE,
+            crate::http::HeaderValue::from_static("application/json"),
+        );
+        let text = Snapshot::new()
+            .redact(|text| text.replace("true", "[bool]"))
+            .render(resp)
+            .await;
+        assert_eq!(
+            text,
+            "200 OK\ncontent-type: application/json\n\n{\n  \"a\": {\n    \
+             \"c\": null,\n    \"d\": [bool]\n  },\n  \"b\": 1\n}\n"
+        );
+
+        let req = TestRequest::with_uri("/bin").to_request();
+        let resp = call_service(&mut srv, req).await;
+        let text = Snapshot::new().render(resp).await;
+        assert_eq!(text, "404 Not Found\n\n[binary 3 bytes]\n00ff10\n");
+    }
+
+    #[actori_rt::test]
+    async fn test_assert() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "actori-web-snapshot-{}-{}/test.snap",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let resp = TestRequest::default()
+            .to_srv_response(HttpResponse::Ok().body(Bytes::from_static(b"data")));
+        snapshot(resp, &path).await;
+        assert_eq!(fs::read_to_string(&path).unwrap(), "200 OK\n\ndata\n");
+
+        let resp = TestRequest::default()
+            .to_srv_response(HttpResponse::Ok().body(Bytes::from_static(b"data")));
+        snapshot(resp, &path).await;
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
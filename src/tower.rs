@@ -0,0 +1,317 @@
+//! Integration with [tower](https://docs.rs/tower-service) services
+//!
+//! `TowerService` mounts a `tower_service::Service` under a path prefix of
+//! an application or a scope, `TowerApp` exposes an initialized application
+//! as a `tower_service::Service`.
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use actori_http::body::{Body, MessageBody, ResponseBody};
+use actori_http::{h1, Request};
+use actori_router::ResourceDef;
+use actori_service::{Service, ServiceFactory};
+use bytes::Bytes;
+use futures::future::{ok, poll_fn, FutureExt, LocalBoxFuture, Ready};
+
+use crate::dev::{AppService, HttpServiceFactory, Payload};
+use crate::error::{Error, ErrorInternalServerError};
+use crate::service::{ServiceRequest, ServiceResponse};
+use crate::HttpResponse;
+
+/// Tower service mounted under a path prefix.
+///
+/// Requests that match the prefix are converted to `http::Request` with
+/// streaming request payload, request uri is not modified. Response body
+/// must implement `MessageBody`. Service errors are rendered as
+/// *500 Internal Server Error* responses.
+///
+/// Service is cloned for every request and polled for readiness before
+/// it is called.
+pub struct TowerService<S> {
+    rdef: String,
+    service: S,
+}
+
+impl<S> TowerService<S> {
+    /// Create new `TowerService` for the path prefix.
+    pub fn new(path: &str, service: S) -> Self {
+        TowerService {
+            rdef: path.to_string(),
+            service,
+        }
+    }
+}
+
+impl<S, B> HttpServiceFactory for TowerService<S>
+where
+    S: tower_service::Service<http::Request<Payload>, Response = http::Response<B>>
+        + Clone
+        + 'static,
+    S::Future: 'static,
+    S::Error: fmt::Debug + fmt::Display + 'static,
+    B: MessageBody + 'static,
+{
+    fn register(self, config: &mut AppService) {
+        config.register_service(
+            ResourceDef::root_prefix(&self.rdef),
+            None,
+            TowerFactory(self.service),
+            None,
+        )
+    }
+}
+
+struct TowerFactory<S>(S);
+
+impl<S, B> ServiceFactory for TowerFactory<S>
+where
+    S: tower_service::Service<http::Request<Payload>, Response = http::Response<B>>
+        + Clone
+        + 'static,
+    S::Future: 'static,
+    S::Error: fmt::Debug + fmt::Display + 'static,
+    B: MessageBody + 'static,
+{
+    type Config = ();
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type InitError = ();
+    type Service = TowerWrapper<S>;
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ok(TowerWrapper(self.0.clone()))
+    }
+}
+
+struct TowerWrapper<S>(S);
+
+impl<S, B> Service for TowerWrapper<S>
+where
+    S: tower_service::Service<http::Request<Payload>, Response = http::Response<B>>
+        + Clone
+        + 'static,
+    S::Future: 'static,
+    S::Error: fmt::Debug + fmt::Display + 'static,
+    B: MessageBody + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let (req, payload) = req.into_parts();
+
+        let mut request = http::Request::new(payload);
+        *request.method_mut() = req.method().clone();
+        *request.uri_mut() = req.uri().clone();
+        *request.version_mut() = req.version();
+        for (name, value) in req.headers().iter() {
+            request.headers_mut().append(name.clone(), value.clone());
+        }
+
+        // router does not check readiness of nested services,
+        // tower service is cloned and polled before every call
+        let mut srv = self.0.clone();
+
+        async move {
+            let res = match poll_fn(|cx| srv.poll_ready(cx)).await {
+                Ok(_) => srv.call(request).await,
+                Err(e) => Err(e),
+            };
+
+            match res {
+                Ok(res) => {
+                    let (parts, body) = res.into_parts();
+                    let mut res =
+                        HttpResponse::with_body(parts.status, Body::from_message(body));
+                    res.head_mut().version = parts.version;
+                    for (name, value) in parts.headers.iter() {
+                        res.headers_mut().append(name.clone(), value.clone());
+                    }
+                    Ok(ServiceResponse::new(req, res))
+                }
+                Err(e) => {
+                    Ok(ServiceResponse::from_err(ErrorInternalServerError(e), req))
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Application exposed as tower service.
+///
+/// Request body is passed to the application as request payload.
+///
+/// ```rust
+/// use actori_web::{test, tower::TowerApp, web, App, HttpResponse};
+///
+/// #[actori_rt::main]
+/// async fn main() {
+///     let srv = test::init_service(
+///         App::new().route("/", web::get().to(|| HttpResponse::Ok())),
+///     )
+///     .await;
+///     let svc = TowerApp::new(srv);
+///     // use svc with tower middlewares
+/// }
+/// ```
+pub struct TowerApp<S>(Rc<RefCell<S>>);
+
+impl<S> TowerApp<S> {
+    /// Create tower service from the initialized application service.
+    pub fn new(service: S) -> Self {
+        TowerApp(Rc::new(RefCell::new(service)))
+    }
+}
+
+impl<S> Clone for TowerApp<S> {
+    fn clone(&self) -> Self {
+        TowerApp(self.0.clone())
+    }
+}
+
+impl<S, B> tower_service::Service<http::Request<Bytes>> for TowerApp<S>
+where
+    S: Service<Request = Request, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody,
+{
+    type Response = http::Response<ResponseBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.0.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<Bytes>) -> Self::Future {
+        let (parts, body) = req.into_parts();
+        let (_, mut payload) = h1::Payload::create(true);
+        payload.unread_data(body);
+
+        let mut request = Request::with_payload(payload.into());
+        let head = request.head_mut();
+        head.method = parts.method;
+        head.uri = parts.uri;
+        head.version = parts.version;
+        for (name, value) in parts.headers.iter() {
+            head.headers.append(name.clone(), value.clone());
+        }
+
+        let fut = self.0.borrow_mut().call(request);
+
+        async move {
+            let mut res = fut.await?;
+            let mut response = http::Response::new(res.take_body());
+            *response.status_mut() = res.status();
+            *response.version_mut() = res.response().head().version;
+            for (name, value) in res.headers().iter() {
+                response.headers_mut().append(name.clone(), value.clone());
+            }
+            Ok(response)
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App};
+
+    #[derive(Clone)]
+    struct Echo;
+
+    impl tower_service::Service<http::Request<Payload>> for Echo {
+        type Response = http::Response<String>;
+        type Error = Infallible;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<Payload>) -> Self::Future {
+            let (parts, mut payload) = req.into_parts();
+            async move {
+                let mut body = String::new();
+                while let Some(chunk) = payload.next().await {
+                    body.push_str(std::str::from_utf8(&chunk.unwrap()).unwrap());
+                }
+                let res = http::Response::builder()
+                    .status(http::StatusCode::CREATED)
+                    .header("x-path", parts.uri.path())
+                    .body(body)
+                    .unwrap();
+                Ok(res)
+            }
+            .boxed_local()
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_tower_service() {
+        let mut srv = init_service(
+            App::new()
+                .service(web::scope("/app").service(TowerService::new("/echo", Echo))),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/app/echo/test")
+            .set_payload(Bytes::from_static(b"data"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers().get("x-path").unwrap(), "/app/echo/test");
+        let body = read_body(resp).await;
+        assert_eq!(body, Bytes::from_static(b"data"));
+    }
+
+    #[actori_rt::test]
+    async fn test_tower_app() {
+        let srv = init_service(App::new().route(
+            "/test",
+            web::post().to(|body: Bytes| {
+                HttpResponse::Ok().insert_header(("x-test", "1")).body(body)
+            }),
+        ))
+        .await;
+        let mut svc = TowerApp::new(srv);
+
+        let req = http::Request::builder()
+            .method(http::Method::POST)
+            .uri("/test")
+            .body(Bytes::from_static(b"data"))
+            .unwrap();
+        poll_fn(|cx| tower_service::Service::poll_ready(&mut svc, cx))
+            .await
+            .unwrap();
+        let res = tower_service::Service::call(&mut svc, req).await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.headers().get("x-test").unwrap(), "1");
+
+        let mut body = res.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.next().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(bytes, b"data");
+    }
+}
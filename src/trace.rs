@@ -0,0 +1,77 @@
+//! Request spans for `tracing` feature
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use actori_http::http::StatusCode;
+use actori_http::trace::TraceContext;
+use trace_rs::field::Empty;
+
+use crate::request::HttpRequest;
+
+/// Span of the request processed by the application.
+///
+/// Trace context of the request is propagated from `traceparent` or `b3`
+/// headers, new trace is started otherwise. Context is stored in request
+/// extensions and is set as current while request is processed, so http
+/// client propagates it to outgoing requests.
+pub(crate) struct RequestSpan {
+    span: trace_rs::Span,
+    ctx: TraceContext,
+    start: Instant,
+}
+
+impl RequestSpan {
+    pub(crate) fn new(req: &HttpRequest) -> Self {
+        let ctx = TraceContext::from_headers(req.headers())
+            .map(|ctx| ctx.child())
+            .unwrap_or_else(TraceContext::new_root);
+        req.extensions_mut().insert(ctx);
+
+        let span = trace_rs::info_span!(
+            "http.request",
+            http.method = %req.method(),
+            http.target = %req.path(),
+            http.route = Empty,
+            http.status_code = Empty,
+            latency_ms = Empty,
+            trace_id = %format_args!("{:032x}", ctx.trace_id()),
+            span_id = %format_args!("{:016x}", ctx.span_id()),
+            parent_id = Empty,
+        );
+        if let Some(id) = ctx.parent_id() {
+            span.record("parent_id", &format_args!("{:016x}", id));
+        }
+
+        RequestSpan {
+            span,
+            ctx,
+            start: Instant::now(),
+        }
+    }
+
+    /// Poll future within the span
+    pub(crate) fn poll<F: Future>(
+        &self,
+        fut: Pin<&mut F>,
+        cx: &mut Context<'_>,
+    ) -> Poll<F::Output> {
+        let _enter = self.span.enter();
+        let prev = TraceContext::set_current(Some(self.ctx));
+        let res = fut.poll(cx);
+        TraceContext::set_current(prev);
+        res
+    }
+
+    /// Record response status, matched route and latency
+    pub(crate) fn finish(&self, status: StatusCode, req: Option<&HttpRequest>) {
+        if let Some(pattern) = req.and_then(|req| req.match_pattern()) {
+            self.span.record("http.route", &pattern.as_str());
+        }
+        self.span
+            .record("http.status_code", &u64::from(status.as_u16()));
+        self.span
+            .record("latency_ms", &(self.start.elapsed().as_millis() as u64));
+    }
+}
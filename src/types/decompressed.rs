@@ -0,0 +1,430 @@
+//! Decompressed extractor
+use std::cell::Cell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, ops};
+
+use bytes::Bytes;
+use futures::future::{FutureExt, LocalBoxFuture};
+use futures::Stream;
+
+use crate::dev::{Decompress, Payload};
+use crate::error::{Error, PayloadError};
+use crate::extract::FromRequest;
+use crate::http::header::{ContentEncoding, CONTENT_ENCODING};
+use crate::request::HttpRequest;
+use crate::types::InheritConfig;
+use crate::HttpMessage;
+
+/// Extract `T` from decompressed request payload with a guard against
+/// decompression bombs.
+///
+/// Payload is decoded according to `Content-Encoding` header before it is
+/// passed to the inner extractor, i.e. `Json`, `Form`, `Bytes`, `String`
+/// or `Payload`. Decoding fails with `PayloadError::Overflow` once
+/// decompressed size exceeds the limit, or once ratio of decompressed to
+/// compressed size exceeds max ratio, ratio is checked after the first
+/// 64Kb of output. Default limit is 4Mb, default max ratio is 100.
+///
+/// Limits and ratio observer are set with `DecompressedConfig`.
+///
+/// ```rust
+/// use actori_web::{web, App};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// async fn index(info: web::Decompressed<web::Json<Info>>) -> String {
+///     format!(
+///         "Welcome {}, ratio {:.1}",
+///         info.username,
+///         info.stats().ratio()
+///     )
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(
+///                 web::DecompressedConfig::default()
+///                     .limit(1_048_576)
+///                     .max_ratio(20.0)
+///                     .observer(|stats| log::info!("{:?}", stats)),
+///             )
+///             .route(web::post().to(index)),
+///     );
+/// }
+/// ```
+pub struct Decompressed<T> {
+    inner: T,
+    stats: DecompressStats,
+}
+
+impl<T> Decompressed<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Decompression statistics of the payload
+    pub fn stats(&self) -> &DecompressStats {
+        &self.stats
+    }
+}
+
+impl<T> ops::Deref for Decompressed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> ops::DerefMut for Decompressed<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Decompressed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decompressed")
+            .field("inner", &self.inner)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+/// Decompression statistics of a request payload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecompressStats {
+    /// Content encoding of the payload
+    pub encoding: ContentEncoding,
+    /// Number of compressed bytes read by the extractor
+    pub compressed: u64,
+    /// Number of decompressed bytes
+    pub decompressed: u64,
+}
+
+impl DecompressStats {
+    /// Ratio of decompressed to compressed size.
+    pub fn ratio(&self) -> f64 {
+        if self.compressed == 0 {
+            1.0
+        } else {
+            self.decompressed as f64 / self.compressed as f64
+        }
+    }
+}
+
+impl<T> FromRequest for Decompressed<T>
+where
+    T: FromRequest + 'static,
+    T::Future: 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = DecompressedConfig;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let cfg = DecompressedConfig::from_req(req);
+        let encoding = req
+            .headers()
+            .get(&CONTENT_ENCODING)
+            .and_then(|enc| enc.to_str().ok())
+            .map(ContentEncoding::from)
+            .unwrap_or(ContentEncoding::Identity);
+
+        let stream = GuardStream::new(
+            payload.take(),
+            encoding,
+            cfg.get_limit(),
+            cfg.get_max_ratio(),
+        );
+        let compressed = stream.compressed.clone();
+        let decompressed = stream.decompressed.clone();
+        let mut payload = Payload::Stream(Box::pin(stream));
+
+        // payload is decoded, inner extractor must not decode it again
+        req.extensions_mut().insert(PayloadDecoded);
+        let fut = T::from_request(req, &mut payload);
+
+        async move {
+            let res = fut.await;
+            let stats = DecompressStats {
+                encoding,
+                compressed: compressed.get(),
+                decompressed: decompressed.get(),
+            };
+            if let Some(ref observer) = cfg.observer {
+                (observer)(&stats);
+            }
+            match res {
+                Ok(inner) => Ok(Decompressed { inner, stats }),
+                Err(e) => Err(e.into()),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Marker of request payload decoded by `Decompressed` extractor
+struct PayloadDecoded;
+
+/// Decoder of request payload for body extractors
+pub(crate) fn decompress(req: &HttpRequest, payload: Payload) -> Decompress<Payload> {
+    if req.extensions().contains::<PayloadDecoded>() {
+        Decompress::new(payload, ContentEncoding::Identity)
+    } else {
+        Decompress::from_headers(payload, req.headers())
+    }
+}
+
+/// Counts bytes of compressed payload
+struct CountStream {
+    payload: Payload,
+    count: Rc<Cell<u64>>,
+}
+
+impl Stream for CountStream {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let res = Pin::new(&mut self.payload).poll_next(cx);
+        if let Poll::Ready(Some(Ok(ref chunk))) = res {
+            self.count.set(self.count.get() + chunk.len() as u64);
+        }
+        res
+    }
+}
+
+/// Checks size and ratio of decompressed payload
+pub(crate) struct GuardStream {
+    stream: Decompress<CountStream>,
+    compressed: Rc<Cell<u64>>,
+    decompressed: Rc<Cell<u64>>,
+    limit: u64,
+    max_ratio: f64,
+}
+
+impl GuardStream {
+    pub(crate) fn new(
+        payload: Payload,
+        encoding: ContentEncoding,
+        limit: u64,
+        max_ratio: f64,
+    ) -> Self {
+        let compressed = Rc::new(Cell::new(0));
+        GuardStream {
+            stream: Decompress::new(
+                CountStream {
+                    payload,
+                    count: compressed.clone(),
+                },
+                encoding,
+            ),
+            compressed,
+            decompressed: Rc::new(Cell::new(0)),
+            limit,
+            max_ratio,
+        }
+    }
+}
+
+impl Stream for GuardStream {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let chunk = match Pin::new(&mut self.stream).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => chunk,
+            res => return res,
+        };
+
+        let size = self.decompressed.get() + chunk.len() as u64;
+        self.decompressed.set(size);
+        if size > self.limit {
+            log::debug!("Decompressed payload is bigger than {}", self.limit);
+            return Poll::Ready(Some(Err(PayloadError::Overflow)));
+        }
+        let compressed = self.compressed.get().max(1);
+        if size > 65_536 && size as f64 / compressed as f64 > self.max_ratio {
+            log::debug!(
+                "Payload decompression ratio exceeds {}: {} / {}",
+                self.max_ratio,
+                size,
+                compressed
+            );
+            return Poll::Ready(Some(Err(PayloadError::Overflow)));
+        }
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+/// Decompressed extractor configuration.
+///
+/// Values that are not set are inherited from configurations registered
+/// on enclosing scopes and the application.
+#[derive(Clone, Default)]
+pub struct DecompressedConfig {
+    limit: Option<u64>,
+    max_ratio: Option<f64>,
+    observer: Option<Arc<dyn Fn(&DecompressStats) + Send + Sync>>,
+}
+
+impl DecompressedConfig {
+    /// Change max size of decompressed payload. By default max size is 4Mb
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Change max ratio of decompressed to compressed size. By default
+    /// max ratio is 100
+    pub fn max_ratio(mut self, ratio: f64) -> Self {
+        self.max_ratio = Some(ratio);
+        self
+    }
+
+    /// Set function that is called with decompression statistics of
+    /// every extracted payload, i.e. to record observed ratios
+    pub fn observer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DecompressStats) + Send + Sync + 'static,
+    {
+        self.observer = Some(Arc::new(f));
+        self
+    }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_limit(&self) -> u64 {
+        self.limit.unwrap_or(4_194_304)
+    }
+
+    fn get_max_ratio(&self) -> f64 {
+        self.max_ratio.unwrap_or(100.0)
+    }
+}
+
+impl InheritConfig for DecompressedConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.limit = self.limit.or(outer.limit);
+        self.max_ratio = self.max_ratio.or(outer.max_ratio);
+        if self.observer.is_none() {
+            self.observer = outer.observer.clone();
+        }
+    }
+}
+
+impl fmt::Debug for DecompressedConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecompressedConfig")
+            .field("limit", &self.get_limit())
+            .field("max_ratio", &self.get_max_ratio())
+            .field("observer", &self.observer.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::Mutex;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use serde_derive::Deserialize;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::TestRequest;
+    use crate::types::Json;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Info {
+        hello: String,
+    }
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(data).unwrap();
+        Bytes::from(enc.finish().unwrap())
+    }
+
+    #[actori_rt::test]
+    async fn test_decompressed() {
+        let stats = Arc::new(Mutex::new(None));
+        let stats2 = stats.clone();
+
+        let body = gzip(b"{\"hello\": \"world\"}");
+        let (req, mut pl) = TestRequest::default()
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .app_data(DecompressedConfig::default().observer(move |s| {
+                *stats2.lock().unwrap() = Some(*s);
+            }))
+            .set_payload(body.clone())
+            .to_http_parts();
+
+        let res = Decompressed::<Json<Info>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(res.hello, "world");
+        assert_eq!(res.stats().encoding, ContentEncoding::Gzip);
+        assert_eq!(res.stats().compressed, body.len() as u64);
+        assert_eq!(res.stats().decompressed, 18);
+        assert_eq!(*stats.lock().unwrap(), Some(*res.stats()));
+    }
+
+    #[actori_rt::test]
+    async fn test_decompression_guard() {
+        let body = gzip(&[b' '; 200_000]);
+
+        let (req, mut pl) = TestRequest::default()
+            .header("content-encoding", "gzip")
+            .app_data(DecompressedConfig::default().limit(1024))
+            .set_payload(body.clone())
+            .to_http_parts();
+        let e = Decompressed::<Bytes>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // ratio guard
+        let (req, mut pl) = TestRequest::default()
+            .header("content-encoding", "gzip")
+            .set_payload(body.clone())
+            .to_http_parts();
+        assert!(Decompressed::<Bytes>::from_request(&req, &mut pl)
+            .await
+            .is_err());
+
+        let (req, mut pl) = TestRequest::default()
+            .header("content-encoding", "gzip")
+            .app_data(DecompressedConfig::default().max_ratio(10_000.0))
+            .set_payload(body)
+            .to_http_parts();
+        let res = Decompressed::<Bytes>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 200_000);
+    }
+}
@@ -0,0 +1,307 @@
+//! Either extractor
+use std::fmt;
+
+use actori_http::error::{Error, PayloadError};
+use actori_http::h1;
+use bytes::{Bytes, BytesMut};
+use futures::future::{FutureExt, LocalBoxFuture};
+use futures::StreamExt;
+
+use crate::dev::Payload;
+use crate::extract::FromRequest;
+use crate::http::header::CONTENT_LENGTH;
+use crate::request::HttpRequest;
+use crate::responder::Either;
+use crate::types::InheritConfig;
+
+/// Extract one of two types from the request.
+///
+/// Variant is selected with `FromRequest::accepts()` before payload is
+/// read, i.e. `Json` and `Form` extractors are selected by request content
+/// type. If variant could not be selected, raw payload is buffered and `A`
+/// is extracted first, then `B` if `A` fails. Size of buffered payload is
+/// limited by `EitherConfig`, default limit is 256Kb.
+///
+/// More than two types could be extracted with nested `Either` or with
+/// `OneOf!` macro, i.e. `OneOf![A, B, C]` is `Either<A, Either<B, C>>`.
+///
+/// ```rust
+/// use actori_web::{web, App, Either, OneOf};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Info {
+///     name: String,
+/// }
+///
+/// /// Json and form payloads are accepted, payload is not buffered
+/// async fn index(info: Either<web::Json<Info>, web::Form<Info>>) -> String {
+///     let info = match info {
+///         Either::A(json) => json.into_inner(),
+///         Either::B(form) => form.into_inner(),
+///     };
+///     format!("Welcome {}!", info.name)
+/// }
+///
+/// /// Anything else is extracted as a string
+/// async fn raw(info: OneOf![web::Json<Info>, web::Form<Info>, String]) -> String {
+///     match info {
+///         Either::A(json) => json.into_inner().name,
+///         Either::B(Either::A(form)) => form.into_inner().name,
+///         Either::B(Either::B(s)) => s,
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .service(web::resource("/index").to(index))
+///         .service(
+///             web::resource("/raw")
+///                 .app_data(web::EitherConfig::default().limit(4096))
+///                 .to(raw),
+///         );
+/// }
+/// ```
+impl<A, B> FromRequest for Either<A, B>
+where
+    A: FromRequest + 'static,
+    A::Future: 'static,
+    B: FromRequest + 'static,
+    B::Future: 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = EitherConfig;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let a = A::accepts(req);
+        if a == Some(true) || (a.is_none() && B::accepts(req) == Some(false)) {
+            return A::from_request(req, payload)
+                .map(|res| res.map(Either::A).map_err(|e| e.into()))
+                .boxed_local();
+        }
+        if a == Some(false) {
+            return B::from_request(req, payload)
+                .map(|res| res.map(Either::B).map_err(|e| e.into()))
+                .boxed_local();
+        }
+
+        let req = req.clone();
+        let limit = EitherConfig::from_req(&req).get_limit();
+        let body = read_raw(&req, payload.take(), limit);
+
+        async move {
+            let body = body.await?;
+
+            match A::from_request(&req, &mut bytes_payload(body.clone())).await {
+                Ok(a) => Ok(Either::A(a)),
+                Err(e) => {
+                    log::debug!(
+                        "Either extractor falls back to second variant: {}",
+                        e.into()
+                    );
+                    B::from_request(&req, &mut bytes_payload(body))
+                        .await
+                        .map(Either::B)
+                        .map_err(|e| e.into())
+                }
+            }
+        }
+        .boxed_local()
+    }
+
+    fn accepts(req: &HttpRequest) -> Option<bool> {
+        match (A::accepts(req), B::accepts(req)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Read raw payload, content encoding is left to the variant extractors
+fn read_raw(
+    req: &HttpRequest,
+    mut payload: Payload,
+    limit: usize,
+) -> LocalBoxFuture<'static, Result<Bytes, PayloadError>> {
+    let len = req
+        .headers()
+        .get(&CONTENT_LENGTH)
+        .and_then(|l| l.to_str().ok())
+        .and_then(|s| s.parse::<usize>().ok());
+
+    async move {
+        if let Some(len) = len {
+            if len > limit {
+                return Err(PayloadError::Overflow);
+            }
+        }
+
+        let mut body = BytesMut::with_capacity(len.unwrap_or(8192).min(limit));
+        while let Some(item) = payload.next().await {
+            let chunk = item?;
+            if body.len() + chunk.len() > limit {
+                return Err(PayloadError::Overflow);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body.freeze())
+    }
+    .boxed_local()
+}
+
+fn bytes_payload(body: Bytes) -> Payload {
+    let (_, mut payload) = h1::Payload::create(true);
+    payload.unread_data(body);
+    Payload::from(payload)
+}
+
+/// Either extractor configuration.
+///
+/// Values that are not set are inherited from configurations registered
+/// on enclosing scopes and the application.
+#[derive(Clone, Default)]
+pub struct EitherConfig {
+    limit: Option<usize>,
+}
+
+impl EitherConfig {
+    /// Change max size of buffered payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(262_144)
+    }
+}
+
+impl InheritConfig for EitherConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.limit = self.limit.or(outer.limit);
+    }
+}
+
+impl fmt::Debug for EitherConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EitherConfig")
+            .field("limit", &self.get_limit())
+            .finish()
+    }
+}
+
+/// Type of nested `Either` extractor for the list of types.
+///
+/// `OneOf![A, B, C]` expands to `Either<A, Either<B, C>>`.
+#[macro_export]
+macro_rules! OneOf {
+    ($a:ty, $b:ty $(,)?) => {
+        $crate::Either<$a, $b>
+    };
+    ($a:ty, $($rest:ty),+ $(,)?) => {
+        $crate::Either<$a, $crate::OneOf![$($rest),+]>
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_derive::Deserialize;
+
+    use super::*;
+    use crate::http::header::CONTENT_TYPE;
+    use crate::test::TestRequest;
+    use crate::types::{Form, Json, Path};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Info {
+        hello: String,
+    }
+
+    #[actori_rt::test]
+    async fn test_either_content_type() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(b"{\"hello\": \"world\"}"))
+            .to_http_parts();
+        let res = Either::<Form<Info>, Json<Info>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        match res {
+            Either::B(json) => assert_eq!(json.hello, "world"),
+            Either::A(_) => panic!(),
+        }
+
+        // payload is not buffered, limit is not applied
+        let (req, mut pl) =
+            TestRequest::with_header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .app_data(EitherConfig::default().limit(1))
+                .set_payload(Bytes::from_static(b"hello=world"))
+                .to_http_parts();
+        let res = Either::<Json<Info>, Form<Info>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        match res {
+            Either::B(form) => assert_eq!(form.hello, "world"),
+            Either::A(_) => panic!(),
+        }
+
+        // content type of none of the variants
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "text/plain")
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        assert_eq!(Either::<Json<Info>, Form<Info>>::accepts(&req), Some(false));
+        assert!(
+            Either::<Json<Info>, Form<Info>>::from_request(&req, &mut pl)
+                .await
+                .is_err()
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_either_buffered() {
+        // first variant could not be selected by content type,
+        // second variant is extracted from buffered payload
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(b"{\"hello\": \"world\"}"))
+            .to_http_parts();
+        let res = Either::<Path<(u32,)>, Json<Info>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        match res {
+            Either::B(json) => assert_eq!(json.hello, "world"),
+            Either::A(_) => panic!(),
+        }
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "application/json")
+            .app_data(EitherConfig::default().limit(4))
+            .set_payload(Bytes::from_static(b"{\"hello\": \"world\"}"))
+            .to_http_parts();
+        assert!(
+            Either::<Path<(u32,)>, Json<Info>>::from_request(&req, &mut pl)
+                .await
+                .is_err()
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_one_of() {
+        let (req, mut pl) = TestRequest::with_header(CONTENT_TYPE, "text/plain")
+            .set_payload(Bytes::from_static(b"hello=world"))
+            .to_http_parts();
+        let res = <OneOf![Json<Info>, Form<Info>, String]>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        match res {
+            Either::B(Either::B(s)) => assert_eq!(s, "hello=world"),
+            _ => panic!(),
+        }
+    }
+}
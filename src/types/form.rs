@@ -24,6 +24,9 @@ use crate::http::{
 };
 use crate::request::HttpRequest;
 use crate::responder::Responder;
+#[cfg(feature = "compress")]
+use crate::types::decompressed::decompress;
+use crate::types::InheritConfig;
 
 /// Form data helper (`application/x-www-form-urlencoded`)
 ///
@@ -120,10 +123,8 @@ where
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let req2 = req.clone();
-        let (limit, err) = req
-            .app_data::<FormConfig>()
-            .map(|c| (c.limit, c.ehandler.clone()))
-            .unwrap_or((16384, None));
+        let cfg = FormConfig::from_req(req);
+        let (limit, err) = (cfg.get_limit(), cfg.ehandler);
 
         UrlEncoded::new(req, payload)
             .limit(limit)
@@ -139,6 +140,12 @@ where
             })
             .boxed_local()
     }
+
+    fn accepts(req: &HttpRequest) -> Option<bool> {
+        Some(req.content_type().eq_ignore_ascii_case(
+            "application/x-www-form-urlencoded",
+        ))
+    }
 }
 
 impl<T: fmt::Debug> fmt::Debug for Form<T> {
@@ -171,6 +178,9 @@ impl<T: Serialize> Responder for Form<T> {
 
 /// Form extractor configuration
 ///
+/// Values that are not set are inherited from configurations registered
+/// on enclosing scopes and the application.
+///
 /// ```rust
 /// use actori_web::{web, App, FromRequest, Result};
 /// use serde_derive::Deserialize;
@@ -199,14 +209,14 @@ impl<T: Serialize> Responder for Form<T> {
 /// ```
 #[derive(Clone)]
 pub struct FormConfig {
-    limit: usize,
+    limit: Option<usize>,
     ehandler: Option<Rc<dyn Fn(UrlencodedError, &HttpRequest) -> Error>>,
 }
 
 impl FormConfig {
     /// Change max size of payload. By default max size is 16Kb
     pub fn limit(mut self, limit: usize) -> Self {
-        self.limit = limit;
+        self.limit = Some(limit);
         self
     }
 
@@ -218,17 +228,45 @@ impl FormConfig {
         self.ehandler = Some(Rc::new(f));
         self
     }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(16_384)
+    }
+}
+
+impl InheritConfig for FormConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.limit = self.limit.or(outer.limit);
+        if self.ehandler.is_none() {
+            self.ehandler = outer.ehandler.clone();
+        }
+    }
 }
 
 impl Default for FormConfig {
     fn default() -> Self {
         FormConfig {
-            limit: 16384,
+            limit: None,
             ehandler: None,
         }
     }
 }
 
+impl fmt::Debug for FormConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormConfig")
+            .field("limit", &self.get_limit())
+            .field("error_handler", &self.ehandler.is_some())
+            .finish()
+    }
+}
+
 /// Future that resolves to a parsed urlencoded values.
 ///
 /// Parse `application/x-www-form-urlencoded` encoded request's body.
@@ -278,7 +316,7 @@ impl<U> UrlEncoded<U> {
         };
 
         #[cfg(feature = "compress")]
-        let payload = Decompress::from_headers(payload.take(), req.headers());
+        let payload = decompress(req, payload.take());
         #[cfg(not(feature = "compress"))]
         let payload = payload.take();
 
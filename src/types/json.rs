@@ -6,9 +6,10 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::{fmt, ops};
 
-use bytes::BytesMut;
+use bytes::buf::BufMutExt;
+use bytes::{Bytes, BytesMut};
 use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json;
@@ -22,6 +23,9 @@ use crate::error::{Error, JsonPayloadError};
 use crate::extract::FromRequest;
 use crate::request::HttpRequest;
 use crate::responder::Responder;
+#[cfg(feature = "compress")]
+use crate::types::decompressed::decompress;
+use crate::types::InheritConfig;
 
 /// Json helper
 ///
@@ -137,6 +141,122 @@ impl<T: Serialize> Responder for Json<T> {
     }
 }
 
+/// Streaming json array responder
+///
+/// Items of the stream are serialized one by one and response body is
+/// a well-formed json array, `[item,item,...]`. Next item is requested from
+/// the stream only once previous chunk is consumed by the response
+/// writer, so large result sets are never buffered.
+///
+/// If item could not be serialized, response body stream is aborted.
+///
+/// ```rust
+/// use actori_web::{web, App};
+/// use futures::stream::{self, Stream};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct MyObj {
+///     id: usize,
+/// }
+///
+/// async fn index() -> web::JsonStream<impl Stream<Item = MyObj>> {
+///     web::JsonStream(stream::iter((0..10_000).map(|id| MyObj { id })))
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/items", web::get().to(index));
+/// }
+/// ```
+pub struct JsonStream<S>(pub S);
+
+impl<S> JsonStream<S> {
+    /// Deconstruct to an inner stream
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S> Responder for JsonStream<S>
+where
+    S: Stream + 'static,
+    S::Item: Serialize,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/json")
+            .streaming(JsonArray {
+                stream: self.0,
+                state: JsonArrayState::Start,
+            }))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JsonArrayState {
+    Start,
+    Items,
+    Done,
+}
+
+/// Json array body
+#[pin_project::pin_project]
+struct JsonArray<S> {
+    #[pin]
+    stream: S,
+    state: JsonArrayState,
+}
+
+impl<S> Stream for JsonArray<S>
+where
+    S: Stream,
+    S::Item: Serialize,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.state == JsonArrayState::Done {
+            return Poll::Ready(None);
+        }
+
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                let mut buf = BytesMut::new();
+                if *this.state == JsonArrayState::Start {
+                    buf.extend_from_slice(b"[");
+                } else {
+                    buf.extend_from_slice(b",");
+                }
+
+                if let Err(e) = serde_json::to_writer((&mut buf).writer(), &item) {
+                    *this.state = JsonArrayState::Done;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                *this.state = JsonArrayState::Items;
+                Poll::Ready(Some(Ok(buf.freeze())))
+            }
+            Poll::Ready(None) => {
+                let end: &'static [u8] = if *this.state == JsonArrayState::Start {
+                    b"[]"
+                } else {
+                    b"]"
+                };
+                *this.state = JsonArrayState::Done;
+                Poll::Ready(Some(Ok(Bytes::from_static(end))))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 /// Json extractor. Allow to extract typed information from request's
 /// payload.
 ///
@@ -180,10 +300,8 @@ where
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let req2 = req.clone();
-        let (limit, err, ctype) = req
-            .app_data::<Self::Config>()
-            .map(|c| (c.limit, c.ehandler.clone(), c.content_type.clone()))
-            .unwrap_or((32768, None, None));
+        let cfg = JsonConfig::from_req(req);
+        let (limit, err, ctype) = (cfg.get_limit(), cfg.ehandler, cfg.content_type);
 
         JsonBody::new(req, payload, ctype)
             .limit(limit)
@@ -204,10 +322,18 @@ where
             })
             .boxed_local()
     }
+
+    fn accepts(req: &HttpRequest) -> Option<bool> {
+        Some(is_json(req, &JsonConfig::from_req(req).content_type))
+    }
 }
 
 /// Json extractor configuration
 ///
+/// Configuration could be registered on the application, scopes and
+/// resources. Values that are not set by the most specific configuration
+/// are inherited from enclosing scopes and the application.
+///
 /// ```rust
 /// use actori_web::{error, web, App, FromRequest, HttpResponse};
 /// use serde_derive::Deserialize;
@@ -243,7 +369,7 @@ where
 /// ```
 #[derive(Clone)]
 pub struct JsonConfig {
-    limit: usize,
+    limit: Option<usize>,
     ehandler: Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>,
     content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
 }
@@ -251,7 +377,7 @@ pub struct JsonConfig {
 impl JsonConfig {
     /// Change max size of payload. By default max size is 32Kb
     pub fn limit(mut self, limit: usize) -> Self {
-        self.limit = limit;
+        self.limit = Some(limit);
         self
     }
 
@@ -272,18 +398,52 @@ impl JsonConfig {
         self.content_type = Some(Arc::new(predicate));
         self
     }
+
+    /// Effective configuration for the request.
+    ///
+    /// Configurations registered on the matched resource, enclosing scopes
+    /// and the application are merged, the most specific value wins.
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(32_768)
+    }
+}
+
+impl InheritConfig for JsonConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.limit = self.limit.or(outer.limit);
+        if self.ehandler.is_none() {
+            self.ehandler = outer.ehandler.clone();
+        }
+        if self.content_type.is_none() {
+            self.content_type = outer.content_type.clone();
+        }
+    }
 }
 
 impl Default for JsonConfig {
     fn default() -> Self {
         JsonConfig {
-            limit: 32768,
+            limit: None,
             ehandler: None,
             content_type: None,
         }
     }
 }
 
+impl fmt::Debug for JsonConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonConfig")
+            .field("limit", &self.get_limit())
+            .field("error_handler", &self.ehandler.is_some())
+            .field("content_type", &self.content_type.is_some())
+            .finish()
+    }
+}
+
 /// Request's payload json parser, it resolves to a deserialized `T` value.
 /// This future could be used with `ServiceRequest` and `ServiceFromRequest`.
 ///
@@ -303,6 +463,20 @@ pub struct JsonBody<U> {
     fut: Option<LocalBoxFuture<'static, Result<U, JsonPayloadError>>>,
 }
 
+/// Check if request content type is json or is accepted by predicate
+fn is_json(
+    req: &HttpRequest,
+    ctype: &Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+) -> bool {
+    if let Ok(Some(mime)) = req.mime_type() {
+        mime.subtype() == mime::JSON
+            || mime.suffix() == Some(mime::JSON)
+            || ctype.as_ref().map_or(false, |predicate| predicate(mime))
+    } else {
+        false
+    }
+}
+
 impl<U> JsonBody<U>
 where
     U: DeserializeOwned + 'static,
@@ -314,15 +488,7 @@ where
         ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
     ) -> Self {
         // check content-type
-        let json = if let Ok(Some(mime)) = req.mime_type() {
-            mime.subtype() == mime::JSON
-                || mime.suffix() == Some(mime::JSON)
-                || ctype.as_ref().map_or(false, |predicate| predicate(mime))
-        } else {
-            false
-        };
-
-        if !json {
+        if !is_json(req, &ctype) {
             return JsonBody {
                 limit: 262_144,
                 length: None,
@@ -339,7 +505,7 @@ where
             .and_then(|s| s.parse::<usize>().ok());
 
         #[cfg(feature = "compress")]
-        let payload = Decompress::from_headers(payload.take(), req.headers());
+        let payload = decompress(req, payload.take());
         #[cfg(not(feature = "compress"))]
         let payload = payload.take();
 
@@ -451,6 +617,33 @@ mod tests {
         assert_eq!(resp.body().bin_ref(), b"{\"name\":\"test\"}");
     }
 
+    #[actori_rt::test]
+    async fn test_stream_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let items = futures::stream::iter(vec!["a", "b", "c"].into_iter().map(|name| {
+            MyObject {
+                name: name.to_string(),
+            }
+        }));
+        let mut resp = JsonStream(items).respond_to(&req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/json")
+        );
+        let body = load_stream(resp.take_body()).await.unwrap();
+        assert_eq!(
+            body,
+            Bytes::from_static(b"[{\"name\":\"a\"},{\"name\":\"b\"},{\"name\":\"c\"}]")
+        );
+
+        let items = futures::stream::empty::<MyObject>();
+        let mut resp = JsonStream(items).respond_to(&req).await.unwrap();
+        let body = load_stream(resp.take_body()).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"[]"));
+    }
+
     #[actori_rt::test]
     async fn test_custom_error_responder() {
         let (req, mut pl) = TestRequest::default()
@@ -542,6 +735,80 @@ mod tests {
         assert!(format!("{}", s.err().unwrap()).contains("Content type error"));
     }
 
+    #[actori_rt::test]
+    async fn test_config_inheritance() {
+        use actori_service::Service;
+
+        use crate::http::StatusCode;
+        use crate::test::init_service;
+        use crate::{web, App};
+
+        let mut srv = init_service(
+            App::new()
+                .app_data(JsonConfig::default().limit(10).error_handler(|err, _| {
+                    InternalError::from_response(err, HttpResponse::Conflict().finish())
+                        .into()
+                }))
+                .service(
+                    web::scope("/scope")
+                        .app_data(JsonConfig::default().limit(100))
+                        .route(
+                            "/",
+                            web::post().to(|_: Json<MyObject>| HttpResponse::Ok()),
+                        )
+                        .route(
+                            "/cfg",
+                            web::get().to(|req: HttpRequest| {
+                                HttpResponse::Ok()
+                                    .body(format!("{:?}", JsonConfig::from_req(&req)))
+                            }),
+                        ),
+                )
+                .route("/", web::post().to(|_: Json<MyObject>| HttpResponse::Ok())),
+        )
+        .await;
+
+        // scope limit is used
+        let req = TestRequest::post()
+            .uri("/scope/")
+            .set_json(&MyObject {
+                name: "test".to_string(),
+            })
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // error handler is inherited from application
+        let req = TestRequest::post()
+            .uri("/scope/")
+            .set_json(&MyObject {
+                name: "t".repeat(100),
+            })
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        // application config
+        let req = TestRequest::post()
+            .uri("/")
+            .set_json(&MyObject {
+                name: "test".to_string(),
+            })
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let req = TestRequest::with_uri("/scope/cfg").to_request();
+        let resp = srv.call(req).await.unwrap();
+        let body = crate::test::read_body(resp).await;
+        assert_eq!(
+            body,
+            Bytes::from_static(
+                b"JsonConfig { limit: 100, error_handler: true, content_type: false }"
+            )
+        );
+    }
+
     #[actori_rt::test]
     async fn test_json_body() {
         let (req, mut pl) = TestRequest::default().to_http_parts();
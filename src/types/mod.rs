@@ -1,15 +1,50 @@
 //! Helper types
+use crate::request::HttpRequest;
 
+#[cfg(feature = "compress")]
+pub(crate) mod decompressed;
+mod either;
 pub(crate) mod form;
 pub(crate) mod json;
+pub mod multipart;
 mod path;
 pub(crate) mod payload;
+mod peer_cert;
 mod query;
+mod ranged;
 pub(crate) mod readlines;
+mod validated;
+#[cfg(feature = "xml")]
+pub(crate) mod xml;
 
+#[cfg(feature = "compress")]
+pub use self::decompressed::{DecompressStats, Decompressed, DecompressedConfig};
+pub use self::either::EitherConfig;
 pub use self::form::{Form, FormConfig};
-pub use self::json::{Json, JsonConfig};
+pub use self::json::{Json, JsonConfig, JsonStream};
+pub use self::multipart::{Multipart, MultipartConfig};
 pub use self::path::{Path, PathConfig};
 pub use self::payload::{Payload, PayloadConfig};
-pub use self::query::{Query, QueryConfig};
+pub use self::peer_cert::PeerCert;
+pub use self::query::{Query, QueryConfig, QueryMode};
+pub use self::ranged::RangedBody;
 pub use self::readlines::Readlines;
+pub use self::validated::{Validate, Validated, ValidationConfig};
+#[cfg(feature = "xml")]
+pub use self::xml::{Xml, XmlConfig};
+
+/// Extractor configuration that inherits unset values from enclosing
+/// resources, scopes and the application.
+pub(crate) trait InheritConfig: Default + 'static {
+    /// Fill values that are not set with values of outer configuration
+    fn inherit(&mut self, outer: &Self);
+
+    /// Effective configuration for the request
+    fn resolve(req: &HttpRequest) -> Self {
+        let mut cfg = Self::default();
+        for outer in req.config_chain::<Self>() {
+            cfg.inherit(outer);
+        }
+        cfg
+    }
+}
@@ -1,33 +1,72 @@
 //! Multipart payload support
 use std::cell::{Cell, RefCell, RefMut};
 use std::convert::TryFrom;
+use std::io::Write;
 use std::marker::PhantomData;
+use std::path::Path;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
-use std::{cmp, fmt};
+use std::{cmp, fmt, fs};
 
+use actori_http::HttpMessage;
+use actori_utils::task::LocalWaker;
 use bytes::{Bytes, BytesMut};
+use futures::future::{ok, Ready};
 use futures::stream::{LocalBoxStream, Stream, StreamExt};
-use httparse;
-use mime;
 
-use actori_utils::task::LocalWaker;
-use actori_web::error::{ParseError, PayloadError};
-use actori_web::http::header::{
+use crate::dev;
+use crate::error::{Error, MultipartError, ParseError, PayloadError};
+use crate::extract::FromRequest;
+use crate::http::header::{
     self, ContentDisposition, HeaderMap, HeaderName, HeaderValue,
 };
-
-use crate::error::MultipartError;
+use crate::request::HttpRequest;
+use crate::types::InheritConfig;
+use crate::web;
 
 const MAX_HEADERS: usize = 32;
 
 /// The server-side implementation of `multipart/form-data` requests.
 ///
-/// This will parse the incoming stream into `MultipartItem` instances via its
-/// Stream implementation.
-/// `MultipartItem::Field` contains multipart field. `MultipartItem::Multipart`
-/// is used for nested multipart streams.
+/// This will parse the incoming stream into `Field` instances via its
+/// Stream implementation. Nested multipart streams are not supported.
+///
+/// `Multipart` is an extractor, size limits are configured with
+/// `MultipartConfig`.
+///
+/// ```rust
+/// use futures::StreamExt;
+/// use actori_web::{web, App, Error, HttpResponse};
+///
+/// async fn index(mut payload: web::Multipart) -> Result<HttpResponse, Error> {
+///     // iterate over multipart stream
+///     while let Some(item) = payload.next().await {
+///         let mut field = item?;
+///
+///         match field.content_disposition().and_then(|cd| cd.get_filename().map(|f| f.to_owned())) {
+///             // file fields are written to disk
+///             Some(name) => {
+///                 field.save(std::env::temp_dir().join(name)).await?;
+///             }
+///             // small fields are buffered to memory
+///             None => {
+///                 let text = field.text().await?;
+///                 println!("-- FIELD: {}", text);
+///             }
+///         }
+///     }
+///     Ok(HttpResponse::Ok().into())
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload")
+///             .app_data(web::MultipartConfig::default().total_limit(10 * 1024 * 1024))
+///             .route(web::post().to(index)),
+///     );
+/// }
+/// ```
 pub struct Multipart {
     safety: Safety,
     error: Option<MultipartError>,
@@ -56,11 +95,24 @@ struct InnerMultipart {
     boundary: String,
     state: InnerState,
     item: InnerMultipartItem,
+    memory_limit: usize,
 }
 
 impl Multipart {
     /// Create multipart instance for boundary.
     pub fn new<S>(headers: &HeaderMap, stream: S) -> Multipart
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+    {
+        Multipart::with_config(headers, stream, &MultipartConfig::default())
+    }
+
+    /// Create multipart instance with size limits of the configuration.
+    pub fn with_config<S>(
+        headers: &HeaderMap,
+        stream: S,
+        config: &MultipartConfig,
+    ) -> Multipart
     where
         S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
     {
@@ -70,9 +122,13 @@ impl Multipart {
                 safety: Safety::new(),
                 inner: Some(Rc::new(RefCell::new(InnerMultipart {
                     boundary,
-                    payload: PayloadRef::new(PayloadBuffer::new(Box::new(stream))),
+                    payload: PayloadRef::new(PayloadBuffer::with_limit(
+                        Box::new(stream),
+                        config.get_total_limit(),
+                    )),
                     state: InnerState::FirstBoundary,
                     item: InnerMultipartItem::None,
+                    memory_limit: config.get_memory_limit(),
                 }))),
             },
             Err(err) => Multipart {
@@ -110,7 +166,7 @@ impl Stream for Multipart {
 
     fn poll_next(
         mut self: Pin<&mut Self>,
-        cx: &mut Context,
+        cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
         if let Some(err) = self.error.take() {
             Poll::Ready(Some(Err(err)))
@@ -246,7 +302,7 @@ impl InnerMultipart {
     fn poll(
         &mut self,
         safety: &Safety,
-        cx: &mut Context,
+        cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Field, MultipartError>>> {
         if self.state == InnerState::Eof {
             Poll::Ready(None)
@@ -356,7 +412,13 @@ impl InnerMultipart {
                 )?));
                 self.item = InnerMultipartItem::Field(Rc::clone(&field));
 
-                Poll::Ready(Some(Ok(Field::new(safety.clone(cx), headers, mt, field))))
+                Poll::Ready(Some(Ok(Field::new(
+                    safety.clone(cx),
+                    headers,
+                    mt,
+                    field,
+                    self.memory_limit,
+                ))))
             }
         }
     }
@@ -375,6 +437,7 @@ pub struct Field {
     headers: HeaderMap,
     inner: Rc<RefCell<InnerField>>,
     safety: Safety,
+    memory_limit: usize,
 }
 
 impl Field {
@@ -383,12 +446,14 @@ impl Field {
         headers: HeaderMap,
         ct: mime::Mime,
         inner: Rc<RefCell<InnerField>>,
+        memory_limit: usize,
     ) -> Self {
         Field {
             ct,
             headers,
             inner,
             safety,
+            memory_limit,
         }
     }
 
@@ -413,12 +478,65 @@ impl Field {
             None
         }
     }
+
+    /// Read field content to memory.
+    ///
+    /// Content size is limited by `MultipartConfig::memory_limit()`,
+    /// default limit is 256Kb.
+    pub async fn bytes(&mut self) -> Result<Bytes, MultipartError> {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = self.next().await {
+            let chunk = chunk?;
+            if buf.len() + chunk.len() > self.memory_limit {
+                return Err(MultipartError::FieldOverflow(self.name()));
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Read field content to memory as utf-8 string, see `bytes()`.
+    pub async fn text(&mut self) -> Result<String, MultipartError> {
+        let buf = self.bytes().await?;
+        String::from_utf8(buf.to_vec())
+            .map_err(|_| MultipartError::FieldParse(self.name()))
+    }
+
+    /// Write field content to a file, returns size of the content.
+    ///
+    /// File is created or truncated. File operations are executed on
+    /// the blocking thread pool, content is not buffered to memory.
+    pub async fn save<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<u64, MultipartError> {
+        let path = path.as_ref().to_owned();
+        let mut file = web::block(move || fs::File::create(path)).await?;
+
+        let mut size = 0;
+        while let Some(chunk) = self.next().await {
+            let chunk = chunk?;
+            size += chunk.len() as u64;
+            file = web::block(move || file.write_all(&chunk).map(|_| file)).await?;
+        }
+        Ok(size)
+    }
+
+    /// Field name from the content disposition
+    fn name(&self) -> String {
+        self.content_disposition()
+            .and_then(|cd| cd.get_name().map(|name| name.to_owned()))
+            .unwrap_or_default()
+    }
 }
 
 impl Stream for Field {
     type Item = Result<Bytes, MultipartError>;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
         if self.safety.current() {
             let mut inner = self.inner.borrow_mut();
             if let Some(mut payload) =
@@ -436,7 +554,7 @@ impl Stream for Field {
 }
 
 impl fmt::Debug for Field {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "\nField: {}", self.ct)?;
         writeln!(f, "  boundary: {}", self.inner.borrow().boundary)?;
         writeln!(f, "  headers:")?;
@@ -691,7 +809,7 @@ impl Safety {
         self.clean.get()
     }
 
-    fn clone(&self, cx: &mut Context) -> Safety {
+    fn clone(&self, cx: &mut Context<'_>) -> Safety {
         let payload = Rc::clone(&self.payload);
         let s = Safety {
             task: LocalWaker::new(),
@@ -721,11 +839,22 @@ struct PayloadBuffer {
     eof: bool,
     buf: BytesMut,
     stream: LocalBoxStream<'static, Result<Bytes, PayloadError>>,
+    size: usize,
+    limit: usize,
 }
 
 impl PayloadBuffer {
     /// Create new `PayloadBuffer` instance
+    #[cfg(test)]
     fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        PayloadBuffer::with_limit(stream, usize::max_value())
+    }
+
+    /// Create new `PayloadBuffer` instance, total size of the stream is limited
+    fn with_limit<S>(stream: S, limit: usize) -> Self
     where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
@@ -733,13 +862,21 @@ impl PayloadBuffer {
             eof: false,
             buf: BytesMut::new(),
             stream: stream.boxed_local(),
+            size: 0,
+            limit,
         }
     }
 
-    fn poll_stream(&mut self, cx: &mut Context) -> Result<(), PayloadError> {
+    fn poll_stream(&mut self, cx: &mut Context<'_>) -> Result<(), PayloadError> {
         loop {
             match Pin::new(&mut self.stream).poll_next(cx) {
-                Poll::Ready(Some(Ok(data))) => self.buf.extend_from_slice(&data),
+                Poll::Ready(Some(Ok(data))) => {
+                    self.size += data.len();
+                    if self.size > self.limit {
+                        return Err(PayloadError::Overflow);
+                    }
+                    self.buf.extend_from_slice(&data)
+                }
                 Poll::Ready(Some(Err(e))) => return Err(e),
                 Poll::Ready(None) => {
                     self.eof = true;
@@ -806,16 +943,95 @@ impl PayloadBuffer {
     }
 }
 
+/// Get request's payload as multipart stream
+///
+/// Content-type: multipart/form-data;
+impl FromRequest for Multipart {
+    type Error = Error;
+    type Future = Ready<Result<Multipart, Error>>;
+    type Config = MultipartConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let cfg = MultipartConfig::from_req(req);
+        ok(Multipart::with_config(req.headers(), payload.take(), &cfg))
+    }
+
+    fn accepts(req: &HttpRequest) -> Option<bool> {
+        match req.mime_type() {
+            Ok(Some(mt)) => Some(mt.type_() == mime::MULTIPART),
+            _ => Some(false),
+        }
+    }
+}
+
+/// Multipart extractor configuration.
+///
+/// Values that are not set are inherited from configurations registered
+/// on enclosing scopes and the application.
+#[derive(Clone, Default)]
+pub struct MultipartConfig {
+    total_limit: Option<usize>,
+    memory_limit: Option<usize>,
+}
+
+impl MultipartConfig {
+    /// Change max size of the whole multipart stream. By default size is
+    /// not limited.
+    pub fn total_limit(mut self, limit: usize) -> Self {
+        self.total_limit = Some(limit);
+        self
+    }
+
+    /// Change max size of a field that is read to memory with
+    /// `Field::bytes()` or `Field::text()`. By default max size is 256Kb
+    pub fn memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = Some(limit);
+        self
+    }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_total_limit(&self) -> usize {
+        self.total_limit.unwrap_or_else(usize::max_value)
+    }
+
+    fn get_memory_limit(&self) -> usize {
+        self.memory_limit.unwrap_or(262_144)
+    }
+}
+
+impl InheritConfig for MultipartConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.total_limit = self.total_limit.or(outer.total_limit);
+        self.memory_limit = self.memory_limit.or(outer.memory_limit);
+    }
+}
+
+impl fmt::Debug for MultipartConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultipartConfig")
+            .field("total_limit", &self.total_limit)
+            .field("memory_limit", &self.get_memory_limit())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use actori_http::h1::Payload;
     use actori_utils::mpsc;
-    use actori_web::http::header::{DispositionParam, DispositionType};
     use bytes::Bytes;
     use futures::future::lazy;
 
+    use crate::http::header::{DispositionParam, DispositionType};
+
     #[actori_rt::test]
     async fn test_boundary() {
         let headers = HeaderMap::new();
@@ -889,7 +1105,7 @@ mod tests {
 
         fn poll_next(
             self: Pin<&mut Self>,
-            cx: &mut Context,
+            cx: &mut Context<'_>,
         ) -> Poll<Option<Self::Item>> {
             let this = self.get_mut();
             if !this.ready {
@@ -1008,6 +1224,64 @@ mod tests {
         }
     }
 
+    #[actori_rt::test]
+    async fn test_field_helpers() {
+        let (bytes, headers) = create_simple_request_with_header();
+        let payload = futures::stream::iter(vec![Ok::<_, PayloadError>(bytes)]);
+        let path = std::env::temp_dir().join("actori-web-multipart-test.txt");
+
+        let mut multipart = Multipart::new(&headers, payload);
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(field.save(&path).await.unwrap(), 4);
+        assert_eq!(std::fs::read(&path).unwrap(), b"test");
+        let _ = std::fs::remove_file(&path);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(field.text().await.unwrap(), "data");
+        assert!(multipart.next().await.is_none());
+    }
+
+    #[actori_rt::test]
+    async fn test_limits() {
+        let (bytes, headers) = create_simple_request_with_header();
+        let payload = futures::stream::iter(vec![Ok::<_, PayloadError>(bytes)]);
+        let cfg = MultipartConfig::default().memory_limit(3);
+
+        let mut multipart = Multipart::with_config(&headers, payload, &cfg);
+        let mut field = multipart.next().await.unwrap().unwrap();
+        match field.bytes().await {
+            Err(MultipartError::FieldOverflow(name)) => assert_eq!(name, "file"),
+            _ => unreachable!(),
+        }
+
+        let (bytes, headers) = create_simple_request_with_header();
+        let payload = futures::stream::iter(vec![Ok::<_, PayloadError>(bytes)]);
+        let cfg = MultipartConfig::default().total_limit(16);
+
+        let mut multipart = Multipart::with_config(&headers, payload, &cfg);
+        match multipart.next().await {
+            Some(Err(MultipartError::Payload(PayloadError::Overflow))) => (),
+            _ => unreachable!(),
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_extractor() {
+        let (bytes, _) = create_simple_request_with_header();
+        let (req, mut pl) = crate::test::TestRequest::with_header(
+            header::CONTENT_TYPE,
+            "multipart/form-data; boundary=\"abbc761f78ff4d7cb7573b5a23f96ef0\"",
+        )
+        .app_data(MultipartConfig::default().memory_limit(2))
+        .set_payload(bytes)
+        .to_http_parts();
+
+        assert_eq!(Multipart::accepts(&req), Some(true));
+        let mut multipart = Multipart::from_request(&req, &mut pl).await.unwrap();
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert!(field.bytes().await.is_err());
+    }
+
     // Loops, collecting all bytes until end-of-field
     async fn get_whole_field(field: &mut Field) -> BytesMut {
         let mut b = BytesMut::new();
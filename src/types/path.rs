@@ -10,6 +10,7 @@ use serde::de;
 use crate::dev::Payload;
 use crate::error::PathError;
 use crate::request::HttpRequest;
+use crate::types::InheritConfig;
 use crate::FromRequest;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -166,10 +167,7 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let error_handler = req
-            .app_data::<Self::Config>()
-            .map(|c| c.ehandler.clone())
-            .unwrap_or(None);
+        let error_handler = PathConfig::from_req(req).ehandler;
 
         ready(
             de::Deserialize::deserialize(PathDeserializer::new(req.match_info()))
@@ -239,6 +237,12 @@ impl PathConfig {
         self.ehandler = Some(Arc::new(f));
         self
     }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
 }
 
 impl Default for PathConfig {
@@ -247,6 +251,22 @@ impl Default for PathConfig {
     }
 }
 
+impl InheritConfig for PathConfig {
+    fn inherit(&mut self, outer: &Self) {
+        if self.ehandler.is_none() {
+            self.ehandler = outer.ehandler.clone();
+        }
+    }
+}
+
+impl fmt::Debug for PathConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathConfig")
+            .field("error_handler", &self.ehandler.is_some())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use actori_router::ResourceDef;
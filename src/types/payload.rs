@@ -1,8 +1,8 @@
 //! Payload/Bytes/String extractors
 use std::future::Future;
 use std::pin::Pin;
-use std::str;
 use std::task::{Context, Poll};
+use std::{fmt, str};
 
 use actori_http::error::{Error, ErrorBadRequest, PayloadError};
 use actori_http::HttpMessage;
@@ -16,6 +16,9 @@ use crate::dev;
 use crate::extract::FromRequest;
 use crate::http::header;
 use crate::request::HttpRequest;
+#[cfg(feature = "compress")]
+use crate::types::decompressed::decompress;
+use crate::types::InheritConfig;
 
 /// Payload extractor returns request 's payload stream.
 ///
@@ -138,19 +141,13 @@ impl FromRequest for Bytes {
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
-        let tmp;
-        let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
-            cfg
-        } else {
-            tmp = PayloadConfig::default();
-            &tmp
-        };
+        let cfg = PayloadConfig::from_req(req);
 
         if let Err(e) = cfg.check_mimetype(req) {
             return Either::Right(err(e));
         }
 
-        let limit = cfg.limit;
+        let limit = cfg.get_limit();
         let fut = HttpMessageBody::new(req, payload).limit(limit);
         Either::Left(async move { Ok(fut.await?) }.boxed_local())
     }
@@ -193,13 +190,7 @@ impl FromRequest for String {
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
-        let tmp;
-        let cfg = if let Some(cfg) = req.app_data::<PayloadConfig>() {
-            cfg
-        } else {
-            tmp = PayloadConfig::default();
-            &tmp
-        };
+        let cfg = PayloadConfig::from_req(req);
 
         // check content-type
         if let Err(e) = cfg.check_mimetype(req) {
@@ -211,7 +202,7 @@ impl FromRequest for String {
             Ok(enc) => enc,
             Err(e) => return Either::Right(err(e.into())),
         };
-        let limit = cfg.limit;
+        let limit = cfg.get_limit();
         let fut = HttpMessageBody::new(req, payload).limit(limit);
 
         Either::Left(
@@ -234,23 +225,24 @@ impl FromRequest for String {
     }
 }
 /// Payload configuration for request's payload.
+///
+/// Values that are not set are inherited from configurations registered
+/// on enclosing scopes and the application.
 #[derive(Clone)]
 pub struct PayloadConfig {
-    limit: usize,
+    limit: Option<usize>,
     mimetype: Option<Mime>,
 }
 
 impl PayloadConfig {
     /// Create `PayloadConfig` instance and set max size of payload.
     pub fn new(limit: usize) -> Self {
-        let mut cfg = Self::default();
-        cfg.limit = limit;
-        cfg
+        Self::default().limit(limit)
     }
 
     /// Change max size of payload. By default max size is 256Kb
     pub fn limit(mut self, limit: usize) -> Self {
-        self.limit = limit;
+        self.limit = Some(limit);
         self
     }
 
@@ -261,6 +253,16 @@ impl PayloadConfig {
         self
     }
 
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(262_144)
+    }
+
     fn check_mimetype(&self, req: &HttpRequest) -> Result<(), Error> {
         // check content-type
         if let Some(ref mt) = self.mimetype {
@@ -282,15 +284,33 @@ impl PayloadConfig {
     }
 }
 
+impl InheritConfig for PayloadConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.limit = self.limit.or(outer.limit);
+        if self.mimetype.is_none() {
+            self.mimetype = outer.mimetype.clone();
+        }
+    }
+}
+
 impl Default for PayloadConfig {
     fn default() -> Self {
         PayloadConfig {
-            limit: 262_144,
+            limit: None,
             mimetype: None,
         }
     }
 }
 
+impl fmt::Debug for PayloadConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadConfig")
+            .field("limit", &self.get_limit())
+            .field("mimetype", &self.mimetype)
+            .finish()
+    }
+}
+
 /// Future that resolves to a complete http message body.
 ///
 /// Load http message body.
@@ -326,7 +346,7 @@ impl HttpMessageBody {
         }
 
         #[cfg(feature = "compress")]
-        let stream = Some(dev::Decompress::from_headers(payload.take(), req.headers()));
+        let stream = Some(decompress(req, payload.take()));
         #[cfg(not(feature = "compress"))]
         let stream = Some(payload.take());
 
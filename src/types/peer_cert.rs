@@ -0,0 +1,93 @@
+//! Tls client certificate extractor
+use std::ops;
+
+use actori_http::error::{Error, ErrorUnauthorized};
+use actori_http::PeerCertificates;
+use futures::future::{ready, Ready};
+
+use crate::dev::Payload;
+use crate::request::HttpRequest;
+use crate::FromRequest;
+
+/// Extract verified tls client certificates of the connection.
+///
+/// Certificates are available only for connections accepted with
+/// `openssl` or `rustls` acceptor that requests client certificates.
+/// Extraction fails with `401 Unauthorized` response if client did not
+/// provide certificate. Use `Option<web::PeerCert>` to make certificate
+/// optional.
+///
+/// ## Example
+///
+/// ```rust
+/// use actori_web::{web, App};
+///
+/// async fn index(cert: web::PeerCert) -> String {
+///     format!("Client certificate: {} bytes", cert.leaf().len())
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/", web::get().to(index));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCert(PeerCertificates);
+
+impl PeerCert {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> PeerCertificates {
+        self.0
+    }
+}
+
+impl ops::Deref for PeerCert {
+    type Target = PeerCertificates;
+
+    fn deref(&self) -> &PeerCertificates {
+        &self.0
+    }
+}
+
+impl FromRequest for PeerCert {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(certs) = req.extensions().get::<PeerCertificates>() {
+            ready(Ok(PeerCert(certs.clone())))
+        } else {
+            log::debug!(
+                "Tls client certificate is not available. \
+                 Request path: {:?}",
+                req.path()
+            );
+            ready(Err(ErrorUnauthorized("Client certificate is required")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::test::TestRequest;
+
+    #[actori_rt::test]
+    async fn test_peer_cert() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = PeerCert::from_request(&req, &mut pl).await;
+        assert_eq!(
+            res.err().unwrap().as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let certs = PeerCertificates::new(vec![b"leaf".to_vec(), b"ca".to_vec()]);
+        req.extensions_mut().insert(certs.clone());
+        let cert = PeerCert::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(cert.leaf(), b"leaf");
+        assert_eq!(cert.chain().len(), 2);
+        assert_eq!(cert.into_inner(), certs);
+    }
+}
@@ -1,17 +1,22 @@
 //! Query extractor
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::{fmt, ops};
+use std::{error, fmt, ops};
 
 use actori_http::error::Error;
 use futures::future::{err, ok, Ready};
-use serde::de;
+use serde::de::{self, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
 use serde_urlencoded;
+use url::form_urlencoded;
 
 use crate::dev::Payload;
 use crate::error::QueryPayloadError;
 use crate::extract::FromRequest;
 use crate::request::HttpRequest;
+use crate::types::InheritConfig;
 
 /// Extract typed information from the request's query.
 ///
@@ -20,6 +25,8 @@ use crate::request::HttpRequest;
 /// Attempts to do so will *fail at runtime*.
 ///
 /// [**QueryConfig**](struct.QueryConfig.html) allows to configure extraction process.
+/// By default query is parsed as flat `key=value` pairs, arrays and nested structures
+/// are supported with `QueryMode::Nested` mode.
 ///
 /// ## Example
 ///
@@ -140,30 +147,231 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let error_handler = req
-            .app_data::<Self::Config>()
-            .map(|c| c.ehandler.clone())
-            .unwrap_or(None);
-
-        serde_urlencoded::from_str::<T>(req.query_string())
-            .map(|val| ok(Query(val)))
-            .unwrap_or_else(move |e| {
-                let e = QueryPayloadError::Deserialize(e);
-
-                log::debug!(
-                    "Failed during Query extractor deserialization. \
-                     Request path: {:?}",
-                    req.path()
-                );
-
-                let e = if let Some(error_handler) = error_handler {
-                    (error_handler)(e, req)
-                } else {
-                    e.into()
-                };
-
-                err(e)
-            })
+        let cfg = QueryConfig::from_req(req);
+        let error_handler = cfg.ehandler;
+        let query = req.query_string();
+
+        match cfg.max_length {
+            Some(limit) if query.len() > limit => Err(QueryPayloadError::TooLong {
+                length: query.len(),
+                limit,
+            }),
+            _ => cfg.mode.unwrap_or(QueryMode::Flat).deserialize::<T>(query),
+        }
+        .map(|val| ok(Query(val)))
+        .unwrap_or_else(move |e| {
+            log::debug!(
+                "Failed during Query extractor deserialization. \
+                 Request path: {:?}",
+                req.path()
+            );
+
+            let e = if let Some(error_handler) = error_handler {
+                (error_handler)(e, req)
+            } else {
+                e.into()
+            };
+
+            err(e)
+        })
+    }
+}
+
+/// Query string parsing mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryMode {
+    /// Flat `key=value` pairs, query is parsed with `serde_urlencoded`.
+    Flat,
+    /// Nested structures and arrays, i.e. `user[name]=x`, `id[]=1&id[]=2` or
+    /// `id[0]=1&id[1]=2`. Repeated keys `id=1&id=2` are parsed as arrays,
+    /// a single value for an array field must use brackets.
+    Nested,
+}
+
+impl QueryMode {
+    fn deserialize<T>(self, query: &str) -> Result<T, QueryPayloadError>
+    where
+        T: de::DeserializeOwned,
+    {
+        match self {
+            QueryMode::Flat => {
+                serde_urlencoded::from_str::<T>(query).map_err(
+                    |error| match failed_param::<T>(query) {
+                        Some(name) => QueryPayloadError::Param { name, error },
+                        None => QueryPayloadError::Deserialize(error),
+                    },
+                )
+            }
+            QueryMode::Nested => serde_qs::Config::new(5, false)
+                .deserialize_str::<T>(&index_arrays(query))
+                .map_err(QueryPayloadError::NestedDeserialize),
+        }
+    }
+}
+
+/// Rewrite repeated keys and empty brackets to indexed keys,
+/// `id=1&id=2&v[]=3` becomes `id[0]=1&id[1]=2&v[0]=3`
+fn index_arrays(query: &str) -> String {
+    let pairs: Vec<_> = form_urlencoded::parse(query.as_bytes()).collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (key, _) in &pairs {
+        *counts.entry(&**key).or_insert(0) += 1;
+    }
+
+    let mut indexes: HashMap<&str, usize> = HashMap::new();
+    let mut ser = form_urlencoded::Serializer::new(String::new());
+    for (key, value) in &pairs {
+        let name = if key.ends_with("[]") {
+            &key[..key.len() - 2]
+        } else if counts[&**key] > 1 && !key.contains('[') {
+            &**key
+        } else {
+            ser.append_pair(key, value);
+            continue;
+        };
+        let idx = indexes.entry(name).or_insert(0);
+        ser.append_pair(&format!("{}[{}]", name, idx), value);
+        *idx += 1;
+    }
+    ser.finish()
+}
+
+/// Name of the query parameter that fails deserialization of `T`.
+///
+/// Query is deserialized once again, values are parsed the same way
+/// as by `serde_urlencoded`, but errors keep the name of the parameter.
+fn failed_param<T: de::DeserializeOwned>(query: &str) -> Option<String> {
+    let pairs = form_urlencoded::parse(query.as_bytes())
+        .map(|(name, value)| (name.clone(), ParamValue { name, value }));
+    T::deserialize(de::value::MapDeserializer::new(pairs))
+        .err()
+        .and_then(|e| e.0)
+}
+
+/// Deserialize error with the name of failed parameter
+#[derive(Debug)]
+struct ParamError(Option<String>);
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(ref name) => write!(f, "Invalid query parameter `{}`", name),
+            None => write!(f, "Invalid query"),
+        }
+    }
+}
+
+impl error::Error for ParamError {}
+
+impl de::Error for ParamError {
+    fn custom<M: fmt::Display>(_: M) -> Self {
+        ParamError(None)
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        ParamError(Some(field.to_string()))
+    }
+
+    fn unknown_field(field: &str, _: &'static [&'static str]) -> Self {
+        ParamError(Some(field.to_string()))
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        ParamError(Some(field.to_string()))
+    }
+}
+
+/// Value of the query parameter
+struct ParamValue<'a> {
+    name: Cow<'a, str>,
+    value: Cow<'a, str>,
+}
+
+impl<'a> ParamValue<'a> {
+    fn error(&self) -> ParamError {
+        ParamError(Some(self.name.to_string()))
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, ParamError> for ParamValue<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+            match self.value.parse() {
+                Ok(val) => visitor.$visit(val).map_err(|_| self.error()),
+                Err(_) => Err(self.error()),
+            }
+        }
+    )*}
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ParamValue<'a> {
+    type Error = ParamError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        let err = self.error();
+        self.value
+            .into_deserializer()
+            .deserialize_any(visitor)
+            .map_err(|_: ParamError| err)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        let err = self.error();
+        self.value
+            .into_deserializer()
+            .deserialize_enum(name, variants, visitor)
+            .map_err(|_: ParamError| err)
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    forward_to_deserialize_any! {
+        char str string unit bytes byte_buf unit_struct tuple_struct
+        struct identifier tuple ignored_any seq map
     }
 }
 
@@ -201,12 +409,32 @@ where
 /// ```
 #[derive(Clone)]
 pub struct QueryConfig {
+    mode: Option<QueryMode>,
+    max_length: Option<usize>,
     ehandler:
         Option<Arc<dyn Fn(QueryPayloadError, &HttpRequest) -> Error + Send + Sync>>,
 }
 
 impl QueryConfig {
+    /// Set query string parsing mode. By default mode is `QueryMode::Flat`
+    pub fn mode(mut self, mode: QueryMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Set max length of query string in bytes.
+    ///
+    /// Extraction of longer query strings fails with
+    /// `QueryPayloadError::TooLong` error, by default length is not limited.
+    pub fn max_length(mut self, limit: usize) -> Self {
+        self.max_length = Some(limit);
+        self
+    }
+
     /// Set custom error handler
+    ///
+    /// Name of the parameter that failed deserialization is available with
+    /// `QueryPayloadError::param()`.
     pub fn error_handler<F>(mut self, f: F) -> Self
     where
         F: Fn(QueryPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
@@ -214,11 +442,41 @@ impl QueryConfig {
         self.ehandler = Some(Arc::new(f));
         self
     }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
 }
 
 impl Default for QueryConfig {
     fn default() -> Self {
-        QueryConfig { ehandler: None }
+        QueryConfig {
+            mode: None,
+            max_length: None,
+            ehandler: None,
+        }
+    }
+}
+
+impl InheritConfig for QueryConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.mode = self.mode.or(outer.mode);
+        self.max_length = self.max_length.or(outer.max_length);
+        if self.ehandler.is_none() {
+            self.ehandler = outer.ehandler.clone();
+        }
+    }
+}
+
+impl fmt::Debug for QueryConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryConfig")
+            .field("mode", &self.mode.unwrap_or(QueryMode::Flat))
+            .field("max_length", &self.max_length)
+            .field("error_handler", &self.ehandler.is_some())
+            .finish()
     }
 }
 
@@ -272,6 +530,122 @@ mod tests {
         assert_eq!(s.id, "test1");
     }
 
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Filter {
+        id: Vec<u32>,
+        user: User,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn test_index_arrays() {
+        assert_eq!(index_arrays("a=1&b=2"), "a=1&b=2");
+        assert_eq!(
+            index_arrays("id=1&id=2&v%5B%5D=3&w[x]=4"),
+            "id%5B0%5D=1&id%5B1%5D=2&v%5B0%5D=3&w%5Bx%5D=4"
+        );
+    }
+
+    #[actori_rt::test]
+    async fn test_nested_mode() {
+        let filter = Filter {
+            id: vec![1, 2],
+            user: User {
+                name: "x".to_string(),
+            },
+        };
+
+        for uri in &[
+            "/?id=1&id=2&user%5Bname%5D=x",
+            "/?id%5B%5D=1&id%5B%5D=2&user%5Bname%5D=x",
+            "/?id%5B0%5D=1&id%5B1%5D=2&user%5Bname%5D=x",
+        ] {
+            let (req, mut pl) = TestRequest::with_uri(uri)
+                .app_data(QueryConfig::default().mode(QueryMode::Nested))
+                .to_http_parts();
+            let q = Query::<Filter>::from_request(&req, &mut pl).await.unwrap();
+            assert_eq!(q.into_inner(), filter);
+        }
+
+        // flat mode is used by default
+        let (req, mut pl) =
+            TestRequest::with_uri("/?id=1&id=2&user%5Bname%5D=x").to_http_parts();
+        assert!(Query::<Filter>::from_request(&req, &mut pl).await.is_err());
+
+        let (req, mut pl) = TestRequest::with_uri("/?id=a")
+            .app_data(QueryConfig::default().mode(QueryMode::Nested))
+            .to_http_parts();
+        let e = Query::<Filter>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Page {
+        id: String,
+        page: Option<u32>,
+        kind: Option<Kind>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    enum Kind {
+        A,
+    }
+
+    #[test]
+    fn test_failed_param() {
+        let err = |query| {
+            QueryMode::Flat
+                .deserialize::<Page>(query)
+                .unwrap_err()
+                .param()
+                .map(|name| name.to_string())
+        };
+        assert_eq!(err("page=1"), Some("id".to_string()));
+        assert_eq!(err("id=1&page=x"), Some("page".to_string()));
+        assert_eq!(err("id=1&page=-1"), Some("page".to_string()));
+        assert_eq!(err("id=1&kind=B"), Some("kind".to_string()));
+        assert!(QueryMode::Flat
+            .deserialize::<Page>("id=1&page=2&kind=A")
+            .is_ok());
+    }
+
+    #[actori_rt::test]
+    async fn test_max_length() {
+        let (req, mut pl) = TestRequest::with_uri("/?id=0123456789")
+            .app_data(QueryConfig::default().max_length(10).error_handler(|e, _| {
+                match e {
+                    QueryPayloadError::TooLong { length, limit } => {
+                        assert_eq!((length, limit), (13, 10))
+                    }
+                    _ => panic!("unexpected error"),
+                }
+                let resp = HttpResponse::UriTooLong().finish();
+                InternalError::from_response(e, resp).into()
+            }))
+            .to_http_parts();
+        let e = Query::<Id>::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            e.as_response_error().error_response().status(),
+            StatusCode::URI_TOO_LONG
+        );
+
+        let (req, mut pl) = TestRequest::with_uri("/?id=01234")
+            .app_data(QueryConfig::default().max_length(10))
+            .to_http_parts();
+        let q = Query::<Id>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(q.id, "01234");
+    }
+
     #[actori_rt::test]
     async fn test_custom_error_responder() {
         let req = TestRequest::with_uri("/name/user1/")
@@ -0,0 +1,420 @@
+//! Range requests and conditional GET responder
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use futures::future::{ok, Ready};
+use futures::{Stream, TryStreamExt};
+
+use actori_http::body::SizedStream;
+use actori_http::http::header::{
+    self, ContentEncoding, EntityTag, HttpDate, ACCEPT_RANGES, CONTENT_RANGE,
+    IF_NONE_MATCH, RANGE,
+};
+use actori_http::http::{Method, StatusCode};
+use actori_http::{HttpMessage, Response};
+
+use crate::dev::BodyEncoding;
+use crate::error::Error;
+use crate::request::HttpRequest;
+use crate::responder::Responder;
+
+/// Responder for large bodies with range requests and conditional GET
+/// support.
+///
+/// Body of `len` bytes is provided by `open` function, it is called with
+/// offset and length of the requested part and returns stream of exactly
+/// `length` bytes starting at `offset`. Body is not opened if response
+/// does not need it.
+///
+/// * `If-Match` and `If-Unmodified-Since` preconditions fail with
+///   *412 Precondition Failed* response
+/// * `If-None-Match` and `If-Modified-Since` of `GET` and `HEAD` requests
+///   are answered with *304 Not Modified* response
+/// * single byte range of `GET` request is answered with
+///   *206 Partial Content* response, ranges outside of the body with
+///   *416 Range Not Satisfiable* response. `If-Range` is honored, multiple
+///   ranges are answered with the full body
+///
+/// ```rust
+/// use actori_web::{web, App, Responder};
+/// use actori_web::http::header::EntityTag;
+/// use bytes::Bytes;
+/// use futures::{future, stream};
+///
+/// async fn blob() -> impl Responder {
+///     let data = Bytes::from_static(b"large binary blob");
+///     let len = data.len() as u64;
+///     web::RangedBody::new(len, move |offset, length| {
+///         let part = data.slice(offset as usize..(offset + length) as usize);
+///         stream::once(future::ok::<_, actori_web::Error>(part))
+///     })
+///     .etag(EntityTag::strong("v1".to_owned()))
+///     .content_type("application/octet-stream")
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/blob", web::get().to(blob));
+/// }
+/// ```
+pub struct RangedBody<F> {
+    len: u64,
+    open: F,
+    etag: Option<EntityTag>,
+    last_modified: Option<HttpDate>,
+    content_type: Option<String>,
+}
+
+impl<F, S, E> RangedBody<F>
+where
+    F: FnOnce(u64, u64) -> S,
+    S: Stream<Item = Result<Bytes, E>> + 'static,
+    E: Into<Error> + 'static,
+{
+    /// Create responder for the body of `len` bytes.
+    pub fn new(len: u64, open: F) -> Self {
+        RangedBody {
+            len,
+            open,
+            etag: None,
+            last_modified: None,
+            content_type: None,
+        }
+    }
+
+    /// Set entity tag of the body.
+    pub fn etag(mut self, etag: EntityTag) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
+    /// Set modification time of the body.
+    pub fn last_modified(mut self, modified: SystemTime) -> Self {
+        self.last_modified = Some(modified.into());
+        self
+    }
+
+    /// Set content type of the body.
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    fn into_response(self, req: &HttpRequest) -> Response {
+        let mut resp = Response::Ok();
+        resp.insert_header((ACCEPT_RANGES, "bytes"));
+        if let Some(ref etag) = self.etag {
+            resp.set(header::ETag(etag.clone()));
+        }
+        if let Some(ref modified) = self.last_modified {
+            resp.set(header::LastModified(*modified));
+        }
+
+        if !self.is_unmodified(req) {
+            return resp.status(StatusCode::PRECONDITION_FAILED).finish();
+        }
+        if self.is_not_modified(req) {
+            return if req.method() == Method::GET || req.method() == Method::HEAD {
+                resp.status(StatusCode::NOT_MODIFIED).finish()
+            } else {
+                resp.status(StatusCode::PRECONDITION_FAILED).finish()
+            };
+        }
+
+        if let Some(ref content_type) = self.content_type {
+            resp.content_type(content_type.as_str());
+        }
+
+        let range = if req.method() == Method::GET && self.if_range(req) {
+            req.headers()
+                .get(&RANGE)
+                .and_then(|val| val.to_str().ok())
+                .and_then(|val| parse_range(val, self.len))
+        } else {
+            None
+        };
+
+        let (offset, length) = match range {
+            Some(Ok((offset, length))) => {
+                // partial content must not be compressed
+                resp.status(StatusCode::PARTIAL_CONTENT)
+                    .encoding(ContentEncoding::Identity)
+                    .insert_header((
+                        CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", offset, offset + length - 1, self.len),
+                    ));
+                (offset, length)
+            }
+            Some(Err(())) => {
+                return resp
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .insert_header((CONTENT_RANGE, format!("bytes */{}", self.len)))
+                    .finish();
+            }
+            None => (0, self.len),
+        };
+
+        let stream = (self.open)(offset, length).map_err(Into::into);
+        resp.body(SizedStream::new(length, stream))
+    }
+
+    /// `If-Match` and `If-Unmodified-Since` preconditions hold
+    fn is_unmodified(&self, req: &HttpRequest) -> bool {
+        match req.get_header::<header::IfMatch>() {
+            None | Some(header::IfMatch::Any) => (),
+            Some(header::IfMatch::Items(ref items)) => {
+                return match self.etag {
+                    Some(ref etag) => items.iter().any(|item| item.strong_eq(etag)),
+                    None => false,
+                };
+            }
+        }
+        match (
+            &self.last_modified,
+            req.get_header::<header::IfUnmodifiedSince>(),
+        ) {
+            (Some(modified), Some(header::IfUnmodifiedSince(since))) => {
+                SystemTime::from(*modified) <= SystemTime::from(since)
+            }
+            _ => true,
+        }
+    }
+
+    /// `If-None-Match` or `If-Modified-Since` shows that client has
+    /// the current body
+    fn is_not_modified(&self, req: &HttpRequest) -> bool {
+        match req.get_header::<header::IfNoneMatch>() {
+            Some(header::IfNoneMatch::Any) => return true,
+            Some(header::IfNoneMatch::Items(ref items)) => {
+                return match self.etag {
+                    Some(ref etag) => items.iter().any(|item| item.weak_eq(etag)),
+                    None => false,
+                };
+            }
+            // `If-Modified-Since` is ignored if `If-None-Match` is present
+            None if req.headers().contains_key(&IF_NONE_MATCH) => return false,
+            None => (),
+        }
+        match (
+            &self.last_modified,
+            req.get_header::<header::IfModifiedSince>(),
+        ) {
+            (Some(modified), Some(header::IfModifiedSince(since))) => {
+                SystemTime::from(*modified) <= SystemTime::from(since)
+            }
+            _ => false,
+        }
+    }
+
+    /// `If-Range` validator matches, range could be served
+    fn if_range(&self, req: &HttpRequest) -> bool {
+        match req.get_header::<header::IfRange>() {
+            None => true,
+            Some(header::IfRange::EntityTag(ref tag)) => match self.etag {
+                Some(ref etag) => tag.strong_eq(etag),
+                None => false,
+            },
+            Some(header::IfRange::Date(ref date)) => match self.last_modified {
+                Some(ref modified) => modified == date,
+                None => false,
+            },
+        }
+    }
+}
+
+impl<F, S, E> Responder for RangedBody<F>
+where
+    F: FnOnce(u64, u64) -> S,
+    S: Stream<Item = Result<Bytes, E>> + 'static,
+    E: Into<Error> + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        ok(self.into_response(req))
+    }
+}
+
+/// Parse single byte range, returns offset and length of the range.
+///
+/// `None` means range header should be ignored, it is invalid or contains
+/// multiple ranges. `Err` means range is not satisfiable.
+fn parse_range(header: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = header.trim();
+    if spec.len() < 6 || !spec[..6].eq_ignore_ascii_case("bytes=") {
+        return None;
+    }
+    let spec = spec[6..].trim();
+    if spec.contains(',') {
+        return None;
+    }
+
+    let mut parts = spec.splitn(2, '-');
+    let start = parts.next()?.trim();
+    let end = parts.next()?.trim();
+
+    if start.is_empty() {
+        // suffix range, last `end` bytes
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(()));
+        }
+        let length = suffix.min(len);
+        return Some(Ok((len - length, length)));
+    }
+
+    let start: u64 = start.parse().ok()?;
+    let end = if end.is_empty() {
+        None
+    } else {
+        Some(end.parse::<u64>().ok()?)
+    };
+    if let Some(end) = end {
+        if end < start {
+            return None;
+        }
+    }
+    if start >= len {
+        return Some(Err(()));
+    }
+    let end = end.map(|end| end.min(len - 1)).unwrap_or(len - 1);
+    Some(Ok((start, end - start + 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{future, stream};
+
+    use super::*;
+    use crate::test::{read_body, TestRequest};
+
+    const DATA: &[u8] = b"0123456789";
+
+    fn body() -> RangedBody<
+        impl FnOnce(u64, u64) -> stream::Once<future::Ready<Result<Bytes, Error>>>,
+    > {
+        RangedBody::new(DATA.len() as u64, |offset, length| {
+            let part = Bytes::from_static(DATA)
+                .slice(offset as usize..(offset + length) as usize);
+            stream::once(future::ok(part))
+        })
+        .etag(EntityTag::strong("v1".to_owned()))
+        .last_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+        .content_type("application/octet-stream")
+    }
+
+    async fn respond(req: TestRequest) -> crate::dev::ServiceResponse {
+        let req = req.to_http_request();
+        let resp = body().respond_to(&req).await.unwrap();
+        crate::dev::ServiceResponse::new(req, resp)
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-4", 10), Some(Ok((0, 5))));
+        assert_eq!(parse_range("bytes=5-", 10), Some(Ok((5, 5))));
+        assert_eq!(parse_range("bytes=-3", 10), Some(Ok((7, 3))));
+        assert_eq!(parse_range("bytes=-30", 10), Some(Ok((0, 10))));
+        assert_eq!(parse_range("bytes=8-20", 10), Some(Ok((8, 2))));
+        assert_eq!(parse_range("bytes=10-", 10), Some(Err(())));
+        assert_eq!(parse_range("bytes=-0", 10), Some(Err(())));
+        assert_eq!(parse_range("bytes=0-1,3-4", 10), None);
+        assert_eq!(parse_range("bytes=4-2", 10), None);
+        assert_eq!(parse_range("items=0-1", 10), None);
+        assert_eq!(parse_range("bytes=a-", 10), None);
+    }
+
+    #[actori_rt::test]
+    async fn test_ranged_body() {
+        let resp = respond(TestRequest::default()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), "\"v1\"");
+        assert_eq!(read_body(resp).await, Bytes::from_static(DATA));
+
+        let resp = respond(TestRequest::default().header(RANGE, "bytes=2-4")).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "bytes 2-4/10");
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"234"));
+
+        let resp = respond(TestRequest::default().header(RANGE, "bytes=20-")).await;
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "bytes */10");
+
+        // multiple ranges are served as full body
+        let resp = respond(TestRequest::default().header(RANGE, "bytes=0-1,3-4")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(DATA));
+    }
+
+    #[actori_rt::test]
+    async fn test_if_range() {
+        let resp = respond(
+            TestRequest::default()
+                .header(RANGE, "bytes=-2")
+                .header(header::IF_RANGE, "\"v1\""),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"89"));
+
+        let resp = respond(
+            TestRequest::default()
+                .header(RANGE, "bytes=-2")
+                .header(header::IF_RANGE, "\"v0\""),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(DATA));
+
+        let resp = respond(
+            TestRequest::default()
+                .header(RANGE, "bytes=-2")
+                .header(header::IF_RANGE, "Sun, 09 Sep 2001 01:46:40 GMT"),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+    }
+
+    #[actori_rt::test]
+    async fn test_conditional() {
+        let resp =
+            respond(TestRequest::default().header(header::IF_NONE_MATCH, "W/\"v1\""))
+                .await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), "\"v1\"");
+
+        let resp =
+            respond(TestRequest::default().header(header::IF_NONE_MATCH, "\"v0\""))
+                .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = respond(
+            TestRequest::default()
+                .header(header::IF_MODIFIED_SINCE, "Sun, 09 Sep 2001 01:46:40 GMT"),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let resp = respond(
+            TestRequest::default()
+                .header(header::IF_MODIFIED_SINCE, "Sat, 08 Sep 2001 01:46:40 GMT"),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp =
+            respond(TestRequest::default().header(header::IF_MATCH, "\"v0\"")).await;
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        let resp = respond(
+            TestRequest::default()
+                .method(Method::PUT)
+                .header(header::IF_NONE_MATCH, "*"),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+    }
+}
@@ -0,0 +1,319 @@
+//! Input validation for extractors
+use std::sync::Arc;
+use std::{fmt, ops};
+
+use actori_http::error::Error;
+use futures::future::{FutureExt, LocalBoxFuture};
+
+use crate::dev::Payload;
+use crate::error::ValidationErrors;
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+use crate::types::{Form, InheritConfig, Json, Path, Query};
+
+/// Validation of the extracted input, see `web::Validated`.
+pub trait Validate {
+    /// Check the value, violations are reported per field
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+impl<T: Validate> Validate for Json<T> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+impl<T: Validate> Validate for Form<T> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+impl<T: Validate> Validate for Query<T> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+impl<T: Validate> Validate for Path<T> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.0.validate()
+    }
+}
+
+/// Extractor that validates the value of the inner extractor.
+///
+/// Inner extractor, i.e. `Json`, `Form`, `Query` or `Path`, runs as usual,
+/// then `Validate::validate()` is called on the deserialized value. Field
+/// errors are rendered as *422 Unprocessable Entity* response with json body
+/// listing the errors, response could be changed per scope with
+/// [**ValidationConfig**](struct.ValidationConfig.html).
+///
+/// ```rust
+/// use actori_web::error::ValidationErrors;
+/// use actori_web::web::{self, Validate, Validated};
+/// use actori_web::App;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// impl Validate for User {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///         if self.name.is_empty() {
+///             errors.add("name", "must not be empty");
+///         }
+///         if self.age > 150 {
+///             errors.add("age", "must be at most 150");
+///         }
+///         errors.into_result()
+///     }
+/// }
+///
+/// async fn create(user: Validated<web::Json<User>>) -> String {
+///     format!("Welcome {}!", user.name)
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/users", web::post().to(create));
+/// }
+/// ```
+pub struct Validated<T>(pub T);
+
+impl<T> Validated<T> {
+    /// Deconstruct to the inner extractor
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Validated<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Validated<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> FromRequest for Validated<T>
+where
+    T: FromRequest + Validate + 'static,
+    T::Future: 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = ValidationConfig;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let fut = T::from_request(req, payload);
+        let req = req.clone();
+        fut.map(move |res| {
+            let val = res.map_err(Into::into)?;
+            match val.validate() {
+                Ok(()) => Ok(Validated(val)),
+                Err(errors) => {
+                    log::debug!(
+                        "Validation failed, request path: {:?}: {}",
+                        req.path(),
+                        errors
+                    );
+                    match ValidationConfig::from_req(&req).ehandler {
+                        Some(ehandler) => Err((*ehandler)(errors, &req)),
+                        None => Err(errors.into()),
+                    }
+                }
+            }
+        })
+        .boxed_local()
+    }
+}
+
+/// Validated extractor configuration
+///
+/// Configuration could be set for the application, scopes and resources,
+/// inner configuration takes precedence.
+///
+/// ```rust
+/// use actori_web::{error, web, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::scope("/api").app_data(
+///             web::ValidationConfig::default().error_handler(|errors, _| {
+///                 let body = format!("{} error(s)", errors.errors().len());
+///                 error::InternalError::from_response(
+///                     errors, HttpResponse::BadRequest().body(body)).into()
+///             }))
+///     );
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ValidationConfig {
+    ehandler: Option<Arc<dyn Fn(ValidationErrors, &HttpRequest) -> Error + Send + Sync>>,
+}
+
+impl ValidationConfig {
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ValidationErrors, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Effective configuration for the request, see
+    /// [`JsonConfig::from_req()`](struct.JsonConfig.html#method.from_req)
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+}
+
+impl InheritConfig for ValidationConfig {
+    fn inherit(&mut self, outer: &Self) {
+        if self.ehandler.is_none() {
+            self.ehandler = outer.ehandler.clone();
+        }
+    }
+}
+
+impl fmt::Debug for ValidationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationConfig")
+            .field("error_handler", &self.ehandler.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_derive::Deserialize;
+
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{web, App, HttpResponse};
+
+    #[derive(Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    impl Validate for User {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::new();
+            if self.name.is_empty() {
+                errors.add("name", "must not be empty");
+            }
+            if self.age > 150 {
+                errors.add("age", "must be at most 150");
+            }
+            errors.into_result()
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_validated() {
+        let mut srv = init_service(
+            App::new()
+                .route(
+                    "/json",
+                    web::post().to(|user: Validated<web::Json<User>>| {
+                        HttpResponse::Ok().body(user.name.clone())
+                    }),
+                )
+                .route(
+                    "/query",
+                    web::get().to(|user: Validated<web::Query<User>>| {
+                        HttpResponse::Ok().body(user.name.clone())
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/json")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(b"{\"name\":\"bob\",\"age\":30}"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"bob"));
+
+        let req = TestRequest::post()
+            .uri("/json")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(b"{\"name\":\"\",\"age\":200}"))
+            .to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value =
+            serde_json::from_slice(&read_body(resp).await).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"errors": [
+                {"field": "name", "message": "must not be empty"},
+                {"field": "age", "message": "must be at most 150"},
+            ]})
+        );
+
+        // deserialization errors are not affected
+        let req = TestRequest::with_uri("/query?name=bob").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/query?name=&age=1").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actori_rt::test]
+    async fn test_validation_config() {
+        let mut srv = init_service(
+            App::new().service(
+                web::scope("/api")
+                    .app_data(ValidationConfig::default().error_handler(|errors, _| {
+                        let body = errors.errors()[0].field.clone();
+                        crate::error::InternalError::from_response(
+                            errors,
+                            HttpResponse::BadRequest().body(body),
+                        )
+                        .into()
+                    }))
+                    .route(
+                        "/{name}/{age}",
+                        web::get()
+                            .to(|_: Validated<web::Path<User>>| HttpResponse::Ok()),
+                    ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/api/bob/20").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/api/bob/200").to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(read_body(resp).await, Bytes::from_static(b"age"));
+    }
+}
@@ -0,0 +1,512 @@
+//! Xml extractor/responder
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::{fmt, ops};
+
+use bytes::BytesMut;
+use encoding_rs::{Encoding, UTF_8};
+use futures::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use actori_http::http::{header::CONTENT_LENGTH, StatusCode};
+use actori_http::{HttpMessage, Payload, Response};
+
+#[cfg(feature = "compress")]
+use crate::dev::Decompress;
+use crate::error::{Error, ErrorInternalServerError, XmlPayloadError};
+use crate::extract::FromRequest;
+use crate::request::HttpRequest;
+use crate::responder::Responder;
+#[cfg(feature = "compress")]
+use crate::types::decompressed::decompress;
+use crate::types::InheritConfig;
+
+/// Xml helper
+///
+/// Xml can be used for xml response generation and for extracting typed
+/// information from request's payload. Type `T` must implement
+/// `Deserialize` trait from *serde* for extraction and `Serialize` for
+/// responses. Serialization is backed by the `serde-xml-rs` crate.
+///
+/// Request body is decoded according to the `charset` parameter of
+/// request's content type, utf-8 is used by default.
+///
+/// [**XmlConfig**](struct.XmlConfig.html) allows to configure extraction
+/// process.
+///
+/// This type is available with `xml` feature enabled.
+///
+/// ## Example
+///
+/// ```rust
+/// use actori_web::{web, App};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// /// deserialize `Info` from request's body and send it back
+/// async fn index(info: web::Xml<Info>) -> web::Xml<Info> {
+///     info
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///        web::resource("/index.html").route(
+///            web::post().to(index))
+///     );
+/// }
+/// ```
+pub struct Xml<T>(pub T);
+
+impl<T> Xml<T> {
+    /// Deconstruct to an inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Xml<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Xml<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Xml<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Xml: {:?}", self.0)
+    }
+}
+
+impl<T> fmt::Display for Xml<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<T: Serialize> Responder for Xml<T> {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        let body = match serde_xml_rs::to_string(&self.0) {
+            Ok(body) => body,
+            Err(e) => return err(ErrorInternalServerError(e.to_string())),
+        };
+
+        ok(Response::build(StatusCode::OK)
+            .content_type("application/xml; charset=utf-8")
+            .body(body))
+    }
+}
+
+impl<T> FromRequest for Xml<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = XmlConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req2 = req.clone();
+        let cfg = XmlConfig::from_req(req);
+        let (limit, err, ctype) = (cfg.get_limit(), cfg.ehandler, cfg.content_type);
+
+        XmlBody::new(req, payload, ctype)
+            .limit(limit)
+            .map(move |res| match res {
+                Err(e) => {
+                    log::debug!(
+                        "Failed to deserialize Xml from payload. \
+                         Request path: {}",
+                        req2.path()
+                    );
+                    if let Some(err) = err {
+                        Err((*err)(e, &req2))
+                    } else {
+                        Err(e.into())
+                    }
+                }
+                Ok(data) => Ok(Xml(data)),
+            })
+            .boxed_local()
+    }
+
+    fn accepts(req: &HttpRequest) -> Option<bool> {
+        Some(is_xml(req, &XmlConfig::from_req(req).content_type))
+    }
+}
+
+/// Xml extractor configuration
+///
+/// Configuration could be registered on the application, scopes and
+/// resources. Values that are not set by the most specific configuration
+/// are inherited from enclosing scopes and the application.
+///
+/// ```rust
+/// use actori_web::{error, web, App, FromRequest, HttpResponse};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// async fn index(info: web::Xml<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html")
+///             .app_data(
+///                 // change xml extractor configuration
+///                 web::Xml::<Info>::configure(|cfg| {
+///                     cfg.limit(4096)
+///                        .error_handler(|err, req| {  // <- create custom error response
+///                           error::InternalError::from_response(
+///                               err, HttpResponse::Conflict().finish()).into()
+///                        })
+///             }))
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct XmlConfig {
+    limit: Option<usize>,
+    ehandler: Option<Arc<dyn Fn(XmlPayloadError, &HttpRequest) -> Error + Send + Sync>>,
+    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+}
+
+impl XmlConfig {
+    /// Change max size of payload. By default max size is 32Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set custom error handler
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(XmlPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.ehandler = Some(Arc::new(f));
+        self
+    }
+
+    /// Set predicate for allowed content types
+    ///
+    /// `application/xml`, `text/xml` and `+xml` content types are always
+    /// accepted.
+    pub fn content_type<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Effective configuration for the request.
+    pub fn from_req(req: &HttpRequest) -> Self {
+        Self::resolve(req)
+    }
+
+    fn get_limit(&self) -> usize {
+        self.limit.unwrap_or(32_768)
+    }
+}
+
+impl InheritConfig for XmlConfig {
+    fn inherit(&mut self, outer: &Self) {
+        self.limit = self.limit.or(outer.limit);
+        if self.ehandler.is_none() {
+            self.ehandler = outer.ehandler.clone();
+        }
+        if self.content_type.is_none() {
+            self.content_type = outer.content_type.clone();
+        }
+    }
+}
+
+impl Default for XmlConfig {
+    fn default() -> Self {
+        XmlConfig {
+            limit: None,
+            ehandler: None,
+            content_type: None,
+        }
+    }
+}
+
+impl fmt::Debug for XmlConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XmlConfig")
+            .field("limit", &self.get_limit())
+            .field("error_handler", &self.ehandler.is_some())
+            .field("content_type", &self.content_type.is_some())
+            .finish()
+    }
+}
+
+/// Check if request content type is xml or is accepted by predicate
+fn is_xml(
+    req: &HttpRequest,
+    ctype: &Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+) -> bool {
+    if let Ok(Some(mime)) = req.mime_type() {
+        mime.subtype() == mime::XML
+            || mime.suffix() == Some(mime::XML)
+            || ctype.as_ref().map_or(false, |predicate| predicate(mime))
+    } else {
+        false
+    }
+}
+
+/// Request's payload xml parser, it resolves to a deserialized `T` value.
+///
+/// Returns error:
+///
+/// * content type is not xml
+///   (unless specified in [`XmlConfig`](struct.XmlConfig.html))
+/// * content type charset is not supported
+/// * content length is greater than 256k
+pub struct XmlBody<U> {
+    limit: usize,
+    length: Option<usize>,
+    encoding: &'static Encoding,
+    #[cfg(feature = "compress")]
+    stream: Option<Decompress<Payload>>,
+    #[cfg(not(feature = "compress"))]
+    stream: Option<Payload>,
+    err: Option<XmlPayloadError>,
+    fut: Option<LocalBoxFuture<'static, Result<U, XmlPayloadError>>>,
+}
+
+impl<U> XmlBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    /// Create `XmlBody` for request.
+    pub fn new(
+        req: &HttpRequest,
+        payload: &mut Payload,
+        ctype: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    ) -> Self {
+        // check content-type
+        if !is_xml(req, &ctype) {
+            return Self::err(XmlPayloadError::ContentType);
+        }
+        let encoding = match req.encoding() {
+            Ok(enc) => enc,
+            Err(_) => return Self::err(XmlPayloadError::ContentType),
+        };
+
+        let len = req
+            .headers()
+            .get(&CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok());
+
+        #[cfg(feature = "compress")]
+        let payload = decompress(req, payload.take());
+        #[cfg(not(feature = "compress"))]
+        let payload = payload.take();
+
+        XmlBody {
+            limit: 262_144,
+            length: len,
+            encoding,
+            stream: Some(payload),
+            fut: None,
+            err: None,
+        }
+    }
+
+    fn err(e: XmlPayloadError) -> Self {
+        XmlBody {
+            limit: 262_144,
+            length: None,
+            encoding: UTF_8,
+            stream: None,
+            fut: None,
+            err: Some(e),
+        }
+    }
+
+    /// Change max size of payload. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+impl<U> Future for XmlBody<U>
+where
+    U: DeserializeOwned + 'static,
+{
+    type Output = Result<U, XmlPayloadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(ref mut fut) = self.fut {
+            return Pin::new(fut).poll(cx);
+        }
+
+        if let Some(err) = self.err.take() {
+            return Poll::Ready(Err(err));
+        }
+
+        let limit = self.limit;
+        if let Some(len) = self.length.take() {
+            if len > limit {
+                return Poll::Ready(Err(XmlPayloadError::Overflow));
+            }
+        }
+        let encoding = self.encoding;
+        let mut stream = self.stream.take().unwrap();
+
+        self.fut = Some(
+            async move {
+                let mut body = BytesMut::with_capacity(8192);
+
+                while let Some(item) = stream.next().await {
+                    let chunk = item?;
+                    if (body.len() + chunk.len()) > limit {
+                        return Err(XmlPayloadError::Overflow);
+                    } else {
+                        body.extend_from_slice(&chunk);
+                    }
+                }
+
+                if encoding == UTF_8 {
+                    Ok(serde_xml_rs::from_reader::<_, U>(body.as_ref())?)
+                } else {
+                    let body = encoding
+                        .decode_without_bom_handling_and_without_replacement(&body)
+                        .ok_or(XmlPayloadError::Encoding)?;
+                    Ok(serde_xml_rs::from_str::<U>(&body)?)
+                }
+            }
+            .boxed_local(),
+        );
+
+        self.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::http::header;
+    use crate::test::TestRequest;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct MyObject {
+        name: String,
+    }
+
+    #[actori_rt::test]
+    async fn test_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let resp = Xml(MyObject {
+            name: "test".to_string(),
+        })
+        .respond_to(&req)
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            header::HeaderValue::from_static("application/xml; charset=utf-8")
+        );
+
+        use crate::responder::tests::BodyTest;
+        let body = std::str::from_utf8(resp.body().bin_ref()).unwrap();
+        assert!(body.contains("<MyObject><name>test</name></MyObject>"));
+    }
+
+    #[actori_rt::test]
+    async fn test_extract() {
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/soap+xml")
+            .set_payload(Bytes::from_static(
+                b"<MyObject><name>test</name></MyObject>",
+            ))
+            .to_http_parts();
+
+        let s = Xml::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.into_inner().name, "test");
+
+        // charset from content type
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "text/xml; charset=iso-8859-1")
+            .set_payload(Bytes::from_static(
+                b"<MyObject><name>caf\xe9</name></MyObject>",
+            ))
+            .to_http_parts();
+
+        let s = Xml::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "caf\u{e9}");
+
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(Bytes::from_static(
+                b"<MyObject><name>test</name></MyObject>",
+            ))
+            .to_http_parts();
+        let res = XmlBody::<MyObject>::new(&req, &mut pl, None).await;
+        match res {
+            Err(XmlPayloadError::ContentType) => (),
+            _ => panic!("content type error expected"),
+        }
+    }
+
+    #[actori_rt::test]
+    async fn test_limit() {
+        let (req, mut pl) = TestRequest::default()
+            .header(header::CONTENT_TYPE, "application/xml")
+            .header(header::CONTENT_LENGTH, "39")
+            .set_payload(Bytes::from_static(
+                b"<MyObject><name>test</name></MyObject>",
+            ))
+            .app_data(XmlConfig::default().limit(10).error_handler(|err, _| {
+                assert!(match err {
+                    XmlPayloadError::Overflow => true,
+                    _ => false,
+                });
+                crate::error::ErrorConflict("overflow")
+            }))
+            .to_http_parts();
+
+        let s = Xml::<MyObject>::from_request(&req, &mut pl).await;
+        let resp = Response::from_error(s.err().unwrap());
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+}
@@ -7,7 +7,6 @@ pub use actori_http::Response as HttpResponse;
 pub use bytes::{Bytes, BytesMut};
 pub use futures::channel::oneshot::Canceled;
 
-use crate::error::BlockingError;
 use crate::extract::FromRequest;
 use crate::handler::Factory;
 use crate::resource::Resource;
@@ -16,10 +15,16 @@ use crate::route::Route;
 use crate::scope::Scope;
 use crate::service::WebService;
 
+pub use crate::blocking::{BlockingHandle, BlockingPool, Priority};
 pub use crate::config::ServiceConfig;
-pub use crate::data::Data;
+pub use crate::data::{Data, DataCleanup, ShardedData};
+pub use crate::files::Files;
+pub use crate::health::{health_checks, HealthRegistry};
+pub use crate::i18n::{I18n, Translator};
 pub use crate::request::HttpRequest;
+pub use crate::sitemap::{sitemap, ChangeFreq, SitemapEntry};
 pub use crate::types::*;
+pub use crate::ws;
 
 /// Create resource for a specific path.
 ///
@@ -80,6 +85,17 @@ pub fn scope(path: &str) -> Scope {
     Scope::new(path)
 }
 
+/// Create static files service for the directory mounted under the path.
+///
+/// ```rust
+/// use actori_web::{web, App};
+///
+/// let app = App::new().service(web::files("/static", "./assets"));
+/// ```
+pub fn files<T: Into<std::path::PathBuf>>(path: &str, dir: T) -> Files {
+    Files::new(path, dir)
+}
+
 /// Create *route* without configuration.
 pub fn route() -> Route {
     Route::new()
@@ -254,13 +270,18 @@ pub fn service<T: IntoPattern>(path: T) -> WebService {
     WebService::new(path)
 }
 
-/// Execute blocking function on a thread pool, returns future that resolves
+/// Execute blocking function on a thread pool, returns handle that resolves
 /// to result of the function execution.
-pub async fn block<F, I, E>(f: F) -> Result<I, BlockingError<E>>
+///
+/// Function is queued on a thread pool shared by the whole process. If the
+/// handle gets dropped before function starts, for example because client
+/// disconnected and request was dropped, function is not executed at all.
+/// Use `BlockingPool` for a dedicated pool with priority classes.
+pub fn block<F, I, E>(f: F) -> BlockingHandle<I, E>
 where
     F: FnOnce() -> Result<I, E> + Send + 'static,
     I: Send + 'static,
     E: Send + std::fmt::Debug + 'static,
 {
-    actori_threadpool::run(f).await
+    BlockingHandle::shared(f)
 }
@@ -0,0 +1,497 @@
+//! Websocket handlers without actors
+//!
+//! Handler is an async function that receives the stream of client messages
+//! and the session for sending messages. Ping/pong keep-alive, close
+//! handshake and frame/message size limits are handled by the connection.
+//!
+//! ```rust
+//! use actori_web::web::{self, ws};
+//! use actori_web::{App, Error, HttpRequest, HttpResponse};
+//! use futures::StreamExt;
+//!
+//! async fn echo(req: HttpRequest, payload: web::Payload) -> Result<HttpResponse, Error> {
+//!     ws::start(&req, payload, |mut messages, session: ws::Session| async move {
+//!         while let Some(Ok(msg)) = messages.next().await {
+//!             let res = match msg {
+//!                 ws::Message::Text(text) => session.text(text),
+//!                 ws::Message::Binary(data) => session.binary(data),
+//!                 ws::Message::Close(_) => break,
+//!             };
+//!             if res.is_err() {
+//!                 break;
+//!             }
+//!         }
+//!     })
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().route("/ws", web::get().to(echo));
+//! }
+//! ```
+use std::cmp;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{io, str};
+
+use actori_codec::{Decoder, Encoder};
+use actori_http::ws::{self as proto, Codec, Frame};
+use actori_rt::time::{delay_for, Instant};
+use bytes::{Bytes, BytesMut};
+use derive_more::Display;
+use futures::channel::mpsc;
+use futures::future::{ready, FutureExt};
+use futures::{pin_mut, stream, SinkExt, Stream, StreamExt};
+
+pub use actori_http::ws::{CloseCode, CloseReason, ProtocolError};
+
+use crate::error::{Error, PayloadError};
+use crate::request::HttpRequest;
+use crate::HttpResponse;
+
+/// Websocket message received from the client
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Text message
+    Text(String),
+    /// Binary message
+    Binary(Bytes),
+    /// Client closed the connection, it is the last message of the stream
+    Close(Option<CloseReason>),
+}
+
+/// Websocket connection settings
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    max_frame_size: usize,
+    max_message_size: usize,
+    ping_interval: Duration,
+    timeout: Duration,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        WsConfig {
+            max_frame_size: 65_536,
+            max_message_size: 1_048_576,
+            ping_interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl WsConfig {
+    /// Create config with default settings.
+    pub fn new() -> Self {
+        WsConfig::default()
+    }
+
+    /// Set max size of a single frame. By default max size is 64Kb
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    /// Set max size of a message assembled from continuation frames.
+    /// By default max size is 1Mb
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Set interval of ping frames. By default ping is sent every 10 seconds
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+
+    /// Set time after which silent client is disconnected. By default
+    /// timeout is 30 seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Websocket connection is closed
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[display(fmt = "Websocket connection is closed")]
+pub struct Closed;
+
+impl std::error::Error for Closed {}
+
+/// Handle for sending messages to the client.
+///
+/// Session could be cloned and moved to other tasks and threads. Sending
+/// fails with `Closed` error once close message is sent, either by the
+/// handler or by the connection.
+#[derive(Debug, Clone)]
+pub struct Session {
+    tx: mpsc::UnboundedSender<proto::Message>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Session {
+    /// Send text message
+    pub fn text<T: Into<String>>(&self, text: T) -> Result<(), Closed> {
+        self.send(proto::Message::Text(text.into()))
+    }
+
+    /// Send binary message
+    pub fn binary<T: Into<Bytes>>(&self, data: T) -> Result<(), Closed> {
+        self.send(proto::Message::Binary(data.into()))
+    }
+
+    /// Send ping message
+    pub fn ping(&self, data: &[u8]) -> Result<(), Closed> {
+        self.send(proto::Message::Ping(Bytes::copy_from_slice(data)))
+    }
+
+    /// Send close message, no messages could be sent afterwards
+    pub fn close(&self, reason: Option<CloseReason>) -> Result<(), Closed> {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return Err(Closed);
+        }
+        self.tx
+            .unbounded_send(proto::Message::Close(reason))
+            .map_err(|_| Closed)
+    }
+
+    /// Check if close message is sent
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    fn send(&self, msg: proto::Message) -> Result<(), Closed> {
+        if self.is_closed() {
+            return Err(Closed);
+        }
+        self.tx.unbounded_send(msg).map_err(|_| Closed)
+    }
+}
+
+/// Stream of messages received from the client.
+///
+/// Ping and pong frames are handled by the connection, continuation frames
+/// are assembled into complete messages. Stream ends after
+/// `Message::Close`, after an error or once the connection is lost.
+pub struct MessageStream {
+    rx: mpsc::Receiver<Result<Message, ProtocolError>>,
+}
+
+impl Stream for MessageStream {
+    type Item = Result<Message, ProtocolError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
+
+/// Perform websocket handshake and start the handler with default settings.
+///
+/// Handler runs on a separate task, connection is closed with normal close
+/// code once the handler completes.
+pub fn start<F, Fut, S>(
+    req: &HttpRequest,
+    stream: S,
+    handler: F,
+) -> Result<HttpResponse, Error>
+where
+    F: FnOnce(MessageStream, Session) -> Fut + 'static,
+    Fut: Future<Output = ()> + 'static,
+    S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+{
+    start_with_config(req, stream, WsConfig::default(), handler)
+}
+
+/// Perform websocket handshake and start the handler with custom settings.
+pub fn start_with_config<F, Fut, S>(
+    req: &HttpRequest,
+    stream: S,
+    config: WsConfig,
+    handler: F,
+) -> Result<HttpResponse, Error>
+where
+    F: FnOnce(MessageStream, Session) -> Fut + 'static,
+    Fut: Future<Output = ()> + 'static,
+    S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+{
+    let mut res = proto::handshake(req.head())?;
+
+    let (tx, rx) = mpsc::unbounded();
+    let session = Session {
+        tx,
+        closed: Arc::new(AtomicBool::new(false)),
+    };
+    let (msg_tx, msg_rx) = mpsc::channel(16);
+
+    let sess = session.clone();
+    actori_rt::spawn(async move {
+        handler(MessageStream { rx: msg_rx }, sess.clone()).await;
+        let _ = sess.close(Some(CloseCode::Normal.into()));
+    });
+    actori_rt::spawn(async move {
+        drive(stream, &session, msg_tx, config).await;
+        // connection is lost or closed, response stream must complete
+        let _ = session.close(None);
+    });
+
+    Ok(res.streaming(WsBody {
+        rx,
+        codec: Codec::new(),
+        buf: BytesMut::new(),
+        done: false,
+    }))
+}
+
+enum Event {
+    Chunk(Result<Bytes, PayloadError>),
+    Eof,
+    Heartbeat,
+}
+
+/// Read client frames, answer pings and send heartbeat pings
+async fn drive<S>(
+    stream: S,
+    session: &Session,
+    mut tx: mpsc::Sender<Result<Message, ProtocolError>>,
+    config: WsConfig,
+) where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    let ping_interval = config.ping_interval;
+    let chunks = stream
+        .map(Event::Chunk)
+        .chain(stream::once(ready(Event::Eof)));
+    let heartbeat = stream::unfold((), move |_| {
+        delay_for(ping_interval).map(|_| Some((Event::Heartbeat, ())))
+    });
+    let events = stream::select(chunks, heartbeat);
+    pin_mut!(events);
+
+    // unfragmented message is a single frame
+    let mut codec = Codec::new()
+        .max_size(cmp::min(config.max_frame_size, config.max_message_size))
+        .aggregate_continuations(config.max_message_size);
+    let mut buf = BytesMut::new();
+    let mut last_seen = Instant::now();
+
+    while let Some(event) = events.next().await {
+        match event {
+            Event::Chunk(Ok(chunk)) => buf.extend_from_slice(&chunk),
+            Event::Chunk(Err(e)) => {
+                let err = io::Error::new(io::ErrorKind::Other, format!("{}", e));
+                let _ = tx.send(Err(ProtocolError::Io(err))).await;
+                return;
+            }
+            Event::Eof => return,
+            Event::Heartbeat => {
+                if session.is_closed() || last_seen.elapsed() > config.timeout {
+                    return;
+                }
+                let _ = session.ping(b"");
+                continue;
+            }
+        }
+        last_seen = Instant::now();
+
+        loop {
+            let msg = match codec.decode(&mut buf) {
+                Ok(Some(frame)) => message(frame, session),
+                Ok(None) => break,
+                Err(e) => Err(e),
+            };
+            match msg {
+                Ok(Some(Message::Close(reason))) => {
+                    let _ = tx.send(Ok(Message::Close(reason))).await;
+                    return;
+                }
+                Ok(Some(msg)) => {
+                    let _ = tx.send(Ok(msg)).await;
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    let code = match e {
+                        ProtocolError::Overflow => CloseCode::Size,
+                        ProtocolError::Io(ref e)
+                            if e.kind() == io::ErrorKind::InvalidData =>
+                        {
+                            CloseCode::Invalid
+                        }
+                        _ => CloseCode::Protocol,
+                    };
+                    let _ = session.close(Some(code.into()));
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Convert frame to the message, `None` for control frames
+fn message(frame: Frame, session: &Session) -> Result<Option<Message>, ProtocolError> {
+    match frame {
+        Frame::Text(data) => match str::from_utf8(&data) {
+            Ok(s) => Ok(Some(Message::Text(s.to_owned()))),
+            Err(e) => Err(ProtocolError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                e,
+            ))),
+        },
+        Frame::Binary(data) => Ok(Some(Message::Binary(data))),
+        Frame::Ping(data) => {
+            let _ = session.send(proto::Message::Pong(data));
+            Ok(None)
+        }
+        Frame::Pong(_) => Ok(None),
+        Frame::Close(reason) => {
+            // echo close message, unless it is already sent
+            let _ = session.close(reason.clone());
+            Ok(Some(Message::Close(reason)))
+        }
+        // continuations are aggregated by the codec
+        Frame::Continuation(_) => Err(ProtocolError::ContinuationNotStarted),
+    }
+}
+
+/// Response body, encodes messages sent with the session
+struct WsBody {
+    rx: mpsc::UnboundedReceiver<proto::Message>,
+    codec: Codec,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl Stream for WsBody {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        while !this.done {
+            match Pin::new(&mut this.rx).poll_next(cx) {
+                Poll::Ready(Some(msg)) => {
+                    // close message is the last one
+                    if let proto::Message::Close(_) = msg {
+                        this.done = true;
+                    }
+                    if let Err(e) = this.codec.encode(msg, &mut this.buf) {
+                        return Poll::Ready(Some(Err(e.into())));
+                    }
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if !this.buf.is_empty() {
+            Poll::Ready(Some(Ok(this.buf.split().freeze())))
+        } else if this.done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::test::TestRequest;
+
+    fn ws_request() -> HttpRequest {
+        TestRequest::default()
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "13")
+            .to_http_request()
+    }
+
+    fn encode(msg: proto::Message) -> Result<Bytes, PayloadError> {
+        let mut buf = BytesMut::new();
+        Codec::new().client_mode().encode(msg, &mut buf).unwrap();
+        Ok(buf.freeze())
+    }
+
+    fn decode(chunk: Bytes) -> Frame {
+        let mut buf = BytesMut::from(&chunk[..]);
+        Codec::new()
+            .client_mode()
+            .decode(&mut buf)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[actori_rt::test]
+    async fn test_ws_echo() {
+        let (tx, rx) = mpsc::unbounded();
+        let mut resp = start(&ws_request(), rx, |mut messages, session| async move {
+            while let Some(Ok(msg)) = messages.next().await {
+                if let Message::Text(text) = msg {
+                    session.text(text).unwrap();
+                }
+            }
+        })
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+        let mut body = resp.take_body();
+
+        tx.unbounded_send(encode(proto::Message::Text("hello".to_owned())))
+            .unwrap();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(decode(chunk), Frame::Text(Bytes::from_static(b"hello")));
+
+        // ping is answered by the connection
+        tx.unbounded_send(encode(proto::Message::Ping(Bytes::from_static(b"p"))))
+            .unwrap();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(decode(chunk), Frame::Pong(Bytes::from_static(b"p")));
+
+        // close handshake
+        let reason = Some(CloseReason::from(CloseCode::Normal));
+        tx.unbounded_send(encode(proto::Message::Close(reason.clone())))
+            .unwrap();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(decode(chunk), Frame::Close(reason));
+        assert!(body.next().await.is_none());
+    }
+
+    #[actori_rt::test]
+    async fn test_ws_message_size() {
+        let (tx, rx) = mpsc::unbounded();
+        let config = WsConfig::new().max_message_size(8);
+        let mut resp =
+            start_with_config(&ws_request(), rx, config, |mut messages, _| async move {
+                while let Some(Ok(_)) = messages.next().await {}
+            })
+            .unwrap();
+        let mut body = resp.take_body();
+
+        tx.unbounded_send(encode(proto::Message::Binary(Bytes::from_static(
+            b"too large message",
+        ))))
+        .unwrap();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(decode(chunk), Frame::Close(Some(CloseCode::Size.into())));
+        assert!(body.next().await.is_none());
+    }
+
+    #[test]
+    fn test_ws_handshake_error() {
+        let req = TestRequest::default().to_http_request();
+        let res = start(&req, stream::empty(), |_, _| async {});
+        assert!(res.is_err());
+    }
+}
@@ -144,3 +144,207 @@ async fn test_start_ssl() {
     thread::sleep(Duration::from_millis(100));
     let _ = sys.stop();
 }
+
+#[actori_rt::test]
+async fn test_on_expect() {
+    use std::io::{Read, Write};
+
+    use actori_web::error::ErrorForbidden;
+    use futures::future::{err, ok};
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actori_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|| HttpResponse::Ok()))
+        })
+        .workers(1)
+        .system_exit()
+        .disable_signals()
+        .on_expect(|| {
+            actori_service::fn_service(|req: actori_http::Request| {
+                if req.path() == "/" {
+                    ok(req)
+                } else {
+                    err(ErrorForbidden("forbidden"))
+                }
+            })
+        })
+        .bind(format!("{}", addr))
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actori_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    let _ = stream.write_all(
+        b"GET /private HTTP/1.1\r\nexpect: 100-continue\r\nconnection: close\r\n\r\n",
+    );
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 403 Forbidden"));
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[actori_rt::test]
+async fn test_pause_listener() {
+    use std::io::{Read, Write};
+
+    let addr = unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actori_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|| HttpResponse::Ok()))
+        })
+        .workers(1)
+        .system_exit()
+        .disable_signals()
+        .listener_name("public")
+        .bind(format!("{}", addr))
+        .unwrap();
+        let listeners = srv.listeners();
+        let srv = srv.run();
+
+        let _ = tx.send((srv, listeners, actori_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, listeners, sys) = rx.recv().unwrap();
+    assert_eq!(listeners.names(), vec!["public".to_owned()]);
+
+    let request = |addr| {
+        let mut stream = net::TcpStream::connect(addr).unwrap();
+        let _ = stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        data
+    };
+    assert!(request(addr).starts_with("HTTP/1.1 200 OK"));
+
+    assert!(listeners.pause("public"));
+    assert!(request(addr).is_empty());
+    let stats = listeners.stats("public").unwrap();
+    assert_eq!((stats.accepted, stats.rejected, stats.paused), (1, 1, true));
+
+    assert!(listeners.resume("public"));
+    assert!(request(addr).starts_with("HTTP/1.1 200 OK"));
+    assert_eq!(listeners.stats("public").unwrap().accepted, 2);
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[actori_rt::test]
+async fn test_graceful_shutdown() {
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let addr = unused_addr();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+
+    let hook = stopped.clone();
+    thread::spawn(move || {
+        let sys = actori_rt::System::new("test");
+
+        let srv = HttpServer::new(move || {
+            let hook = hook.clone();
+            App::new()
+                .on_shutdown(move || {
+                    let hook = hook.clone();
+                    async move { hook.store(true, Ordering::Relaxed) }
+                })
+                .service(web::resource("/").to(|| HttpResponse::Ok()))
+        })
+        .workers(1)
+        .system_exit()
+        .disable_signals()
+        .bind(format!("{}", addr))
+        .unwrap();
+        let shutdown = srv.shutdown_handle();
+        let srv = srv.run();
+
+        let _ = tx.send((srv, shutdown, actori_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, shutdown, sys) = rx.recv().unwrap();
+
+    let mut stream = net::TcpStream::connect(addr).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK"));
+    assert!(!stopped.load(Ordering::Relaxed));
+
+    shutdown.graceful(srv, Duration::from_secs(1)).await;
+    assert!(stopped.load(Ordering::Relaxed));
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+}
+
+#[cfg(all(unix, feature = "client"))]
+#[actori_rt::test]
+async fn test_start_uds() {
+    use actori_http::client;
+    use actori_web::HttpRequest;
+
+    let path = std::env::temp_dir()
+        .join(format!("actori-web-test-uds-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (tx, rx) = mpsc::channel();
+
+    let srv_path = path.clone();
+    thread::spawn(move || {
+        let sys = actori_rt::System::new("test");
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|req: HttpRequest| {
+                HttpResponse::Ok().body(req.connection_info().host().to_owned())
+            }))
+        })
+        .workers(1)
+        .system_exit()
+        .disable_signals()
+        .bind_uds(&srv_path)
+        .unwrap()
+        .run();
+
+        let _ = tx.send((srv, actori_rt::System::current()));
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let client = actoriwc::Client::build()
+        .connector(client::Connector::new().unix_socket(&path).finish())
+        .finish();
+
+    let mut response = client.get("http://example.com/").send().await.unwrap();
+    assert!(response.status().is_success());
+    let body = response.body().await.unwrap();
+    assert_eq!(body, "example.com");
+
+    // stop
+    let _ = srv.stop(false);
+
+    thread::sleep(Duration::from_millis(100));
+    let _ = sys.stop();
+    let _ = std::fs::remove_file(&path);
+}
@@ -102,7 +102,7 @@ async fn test_body_gzip() {
     let mut response = srv
         .get("/")
         .no_decompress()
-        .header(ACCEPT_ENCODING, "gzip")
+        .insert_header((ACCEPT_ENCODING, "gzip"))
         .send()
         .await
         .unwrap();
@@ -131,7 +131,7 @@ async fn test_body_gzip2() {
     let mut response = srv
         .get("/")
         .no_decompress()
-        .header(ACCEPT_ENCODING, "gzip")
+        .insert_header((ACCEPT_ENCODING, "gzip"))
         .send()
         .await
         .unwrap();
@@ -172,7 +172,7 @@ async fn test_body_encoding_override() {
     let mut response = srv
         .get("/")
         .no_decompress()
-        .header(ACCEPT_ENCODING, "deflate")
+        .insert_header((ACCEPT_ENCODING, "deflate"))
         .send()
         .await
         .unwrap();
@@ -191,7 +191,7 @@ async fn test_body_encoding_override() {
     let mut response = srv
         .request(actori_web::http::Method::GET, srv.url("/raw"))
         .no_decompress()
-        .header(ACCEPT_ENCODING, "deflate")
+        .insert_header((ACCEPT_ENCODING, "deflate"))
         .send()
         .await
         .unwrap();
@@ -207,6 +207,81 @@ async fn test_body_encoding_override() {
     assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
 }
 
+#[actori_rt::test]
+async fn test_resource_encoding_override() {
+    let srv = test::start_with(test::config().h1(), || {
+        App::new().service(
+            web::scope("/api")
+                .wrap(Compress::new(ContentEncoding::Gzip))
+                .service(web::resource("/gzip").to(|| HttpResponse::Ok().body(STR)))
+                .service(
+                    web::resource("/identity")
+                        .encoding(ContentEncoding::Identity)
+                        .to(|| HttpResponse::Ok().body(STR)),
+                )
+                .service(
+                    web::resource("/route").route(
+                        web::get()
+                            .encoding(ContentEncoding::Identity)
+                            .to(|| HttpResponse::Ok().body(STR)),
+                    ),
+                )
+                .service(
+                    web::resource("/error")
+                        .encoding(ContentEncoding::Identity)
+                        .to(|| async {
+                            Err::<HttpResponse, _>(actori_web::error::ErrorBadRequest(
+                                "error",
+                            ))
+                        }),
+                ),
+        )
+    });
+
+    let response = srv
+        .get("/api/gzip")
+        .no_decompress()
+        .insert_header((ACCEPT_ENCODING, "gzip"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "gzip"
+    );
+
+    for path in &["/api/identity", "/api/route"] {
+        let mut response = srv
+            .get(*path)
+            .no_decompress()
+            .insert_header((ACCEPT_ENCODING, "gzip"))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert!(response.headers().get(CONTENT_ENCODING).is_none());
+        let bytes = response.body().await.unwrap();
+        assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+    }
+
+    let mut response = srv
+        .get("/api/error")
+        .no_decompress()
+        .insert_header((ACCEPT_ENCODING, "gzip"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), actori_web::http::StatusCode::BAD_REQUEST);
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"error"));
+}
+
 #[actori_rt::test]
 async fn test_body_gzip_large() {
     let data = STR.repeat(10);
@@ -225,7 +300,7 @@ async fn test_body_gzip_large() {
     let mut response = srv
         .get("/")
         .no_decompress()
-        .header(ACCEPT_ENCODING, "gzip")
+        .insert_header((ACCEPT_ENCODING, "gzip"))
         .send()
         .await
         .unwrap();
@@ -262,7 +337,7 @@ async fn test_body_gzip_large_random() {
     let mut response = srv
         .get("/")
         .no_decompress()
-        .header(ACCEPT_ENCODING, "gzip")
+        .insert_header((ACCEPT_ENCODING, "gzip"))
         .send()
         .await
         .unwrap();
@@ -293,7 +368,7 @@ async fn test_body_chunked_implicit() {
     let mut response = srv
         .get("/")
         .no_decompress()
-        .header(ACCEPT_ENCODING, "gzip")
+        .insert_header((ACCEPT_ENCODING, "gzip"))
         .send()
         .await
         .unwrap();
@@ -326,7 +401,7 @@ async fn test_body_br_streaming() {
 
     let mut response = srv
         .get("/")
-        .header(ACCEPT_ENCODING, "br")
+        .insert_header((ACCEPT_ENCODING, "br"))
         .no_decompress()
         .send()
         .await
@@ -399,7 +474,7 @@ async fn test_body_deflate() {
     // client request
     let mut response = srv
         .get("/")
-        .header(ACCEPT_ENCODING, "deflate")
+        .insert_header((ACCEPT_ENCODING, "deflate"))
         .no_decompress()
         .send()
         .await
@@ -426,7 +501,7 @@ async fn test_body_brotli() {
     // client request
     let mut response = srv
         .get("/")
-        .header(ACCEPT_ENCODING, "br")
+        .insert_header((ACCEPT_ENCODING, "br"))
         .no_decompress()
         .send()
         .await
@@ -459,7 +534,7 @@ async fn test_encoding() {
 
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "gzip")
+        .insert_header((CONTENT_ENCODING, "gzip"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -485,7 +560,7 @@ async fn test_gzip_encoding() {
 
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "gzip")
+        .insert_header((CONTENT_ENCODING, "gzip"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -512,7 +587,7 @@ async fn test_gzip_encoding_large() {
 
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "gzip")
+        .insert_header((CONTENT_ENCODING, "gzip"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -543,7 +618,7 @@ async fn test_reading_gzip_encoding_large_random() {
 
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "gzip")
+        .insert_header((CONTENT_ENCODING, "gzip"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -570,7 +645,7 @@ async fn test_reading_deflate_encoding() {
     // client request
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "deflate")
+        .insert_header((CONTENT_ENCODING, "deflate"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -597,7 +672,7 @@ async fn test_reading_deflate_encoding_large() {
     // client request
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "deflate")
+        .insert_header((CONTENT_ENCODING, "deflate"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -628,7 +703,7 @@ async fn test_reading_deflate_encoding_large_random() {
     // client request
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "deflate")
+        .insert_header((CONTENT_ENCODING, "deflate"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -655,7 +730,7 @@ async fn test_brotli_encoding() {
     // client request
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "br")
+        .insert_header((CONTENT_ENCODING, "br"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -689,7 +764,7 @@ async fn test_brotli_encoding_large() {
     // client request
     let request = srv
         .post("/")
-        .header(CONTENT_ENCODING, "br")
+        .insert_header((CONTENT_ENCODING, "br"))
         .send_body(enc.clone());
     let mut response = request.await.unwrap();
     assert!(response.status().is_success());
@@ -699,6 +774,59 @@ async fn test_brotli_encoding_large() {
     assert_eq!(bytes, Bytes::from(data));
 }
 
+#[cfg(feature = "compress-zstd")]
+#[actori_rt::test]
+async fn test_body_zstd() {
+    let srv = test::start_with(test::config().h1(), || {
+        App::new().wrap(Compress::default()).service(
+            web::resource("/").route(web::to(move || HttpResponse::Ok().body(STR))),
+        )
+    });
+
+    // client request
+    let mut response = srv
+        .get("/")
+        .insert_header((ACCEPT_ENCODING, "zstd, br, gzip"))
+        .no_decompress()
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(CONTENT_ENCODING).unwrap(), "zstd");
+
+    // read response
+    let bytes = response.body().await.unwrap();
+
+    // decode zstd
+    let dec = zstd::stream::decode_all(bytes.as_ref()).unwrap();
+    assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
+}
+
+#[cfg(feature = "compress-zstd")]
+#[actori_rt::test]
+async fn test_zstd_encoding() {
+    let srv = test::start_with(test::config().h1(), || {
+        App::new().service(
+            web::resource("/")
+                .route(web::to(move |body: Bytes| HttpResponse::Ok().body(body))),
+        )
+    });
+
+    let enc = zstd::stream::encode_all(STR.as_bytes(), 3).unwrap();
+
+    // client request
+    let request = srv
+        .post("/")
+        .insert_header((CONTENT_ENCODING, "zstd"))
+        .send_body(enc.clone());
+    let mut response = request.await.unwrap();
+    assert!(response.status().is_success());
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
 #[cfg(feature = "openssl")]
 #[actori_rt::test]
 async fn test_brotli_encoding_large_openssl() {
@@ -729,7 +857,7 @@ async fn test_brotli_encoding_large_openssl() {
     // client request
     let mut response = srv
         .post("/")
-        .header(actori_web::http::header::CONTENT_ENCODING, "br")
+        .insert_header((actori_web::http::header::CONTENT_ENCODING, "br"))
         .send_body(enc)
         .await
         .unwrap();
@@ -777,7 +905,7 @@ async fn test_reading_deflate_encoding_large_random_rustls() {
     // client request
     let req = srv
         .post("/")
-        .header(actori_web::http::header::CONTENT_ENCODING, "deflate")
+        .insert_header((actori_web::http::header::CONTENT_ENCODING, "deflate"))
         .send_stream(TestBody::new(Bytes::from(enc), 1024));
 
     let mut response = req.await.unwrap();